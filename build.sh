@@ -48,6 +48,15 @@ else
     exit 1
 fi
 
+# Generate service metadata
+log "INFO" "Generating service metadata..."
+if cargo run --features export-api -- --metadata > candid.metadata.json; then
+    log "SUCCESS" "Service metadata generated successfully"
+else
+    log "ERROR" "Failed to generate service metadata"
+    exit 1
+fi
+
 # Build WASM
 log "INFO" "Building WASM binary..."
 cd ..
@@ -0,0 +1,155 @@
+//! Admin Access Log
+//!
+//! Several principals may share controller rights, so every privileged call is recorded,
+//! whether it succeeded or was rejected, in a bounded stable log kept apart from the journal.
+//! The arguments are only stored as a digest, since some of them (e.g. webhook URLs) are secrets.
+//!
+//! ```plain
+//! privileged entrypoint ──► audit(method, args_digest, body) ──► outcome
+//!                                                              │
+//!                     ADMIN_ACTIONS ◄── AdminAction { method, caller, digest, outcome }
+//!                          │
+//!              get_admin_actions(limit), newest first
+//! ```
+//!
+//! Once `MAX_ADMIN_ACTIONS` entries are stored, the oldest ones are evicted.
+
+use std::{borrow::Cow, fmt::Debug};
+
+use alloy_primitives::keccak256;
+use candid::{CandidType, Decode, Encode, Principal};
+use ic_exports::ic_cdk::caller;
+use ic_stable_structures::{storable::Bound, Storable};
+use serde::Deserialize;
+
+use crate::{clock::time, state::ADMIN_ACTIONS};
+
+/// Maximum number of admin actions kept in stable memory
+const MAX_ADMIN_ACTIONS: u64 = 2_000;
+
+/// A privileged call and its outcome
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq)]
+pub struct AdminAction {
+    /// Name of the called method
+    pub method: String,
+    /// Principal that made the call
+    pub caller: Principal,
+    /// Keccak-256 digest of the call arguments
+    pub args_digest: String,
+    /// `Err` with the rejection reason if the call failed
+    pub outcome: Result<(), String>,
+    /// Timestamp in seconds of the call
+    pub timestamp: u64,
+}
+
+impl Storable for AdminAction {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        Cow::Owned(Encode!(self).expect("Failed to encode the admin action."))
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).expect("Failed to decode the admin action.")
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+/// Returns the digest of the arguments of a call.
+pub fn args_digest<A: Debug + ?Sized>(args: &A) -> String {
+    keccak256(format!("{:?}", args)).to_string()
+}
+
+/// Stores an admin action, evicting the oldest ones above `MAX_ADMIN_ACTIONS`.
+pub fn store_admin_action(action: AdminAction) {
+    ADMIN_ACTIONS.with(|actions| {
+        let mut actions = actions.borrow_mut();
+        let id = actions
+            .last_key_value()
+            .map_or(0, |(id, _)| id.saturating_add(1));
+        actions.insert(id, action);
+
+        let excess = actions.len().saturating_sub(MAX_ADMIN_ACTIONS);
+        let oldest: Vec<u64> = actions
+            .iter()
+            .take(excess as usize)
+            .map(|(id, _)| id)
+            .collect();
+        for id in oldest {
+            actions.remove(&id);
+        }
+    });
+}
+
+/// Records the outcome of a privileged call made by the current caller.
+pub fn record_admin_action<T, E: Debug>(method: &str, args_digest: String, result: &Result<T, E>) {
+    store_admin_action(AdminAction {
+        method: method.to_string(),
+        caller: caller(),
+        args_digest,
+        outcome: result
+            .as_ref()
+            .map(|_| ())
+            .map_err(|err| format!("{:?}", err)),
+        timestamp: time() / 1_000_000_000,
+    });
+}
+
+/// Runs the body of a privileged entrypoint and records its outcome.
+pub fn audit<T, E: Debug>(
+    method: &str,
+    args_digest: String,
+    body: impl FnOnce() -> Result<T, E>,
+) -> Result<T, E> {
+    let result = body();
+    record_admin_action(method, args_digest, &result);
+    result
+}
+
+/// Returns up to `limit` admin actions, newest first.
+pub fn admin_actions(limit: u64) -> Vec<AdminAction> {
+    ADMIN_ACTIONS.with(|actions| {
+        actions
+            .borrow()
+            .iter()
+            .rev()
+            .take(limit as usize)
+            .map(|(_, action)| action)
+            .collect()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn action(method: &str) -> AdminAction {
+        AdminAction {
+            method: method.to_string(),
+            caller: Principal::anonymous(),
+            args_digest: args_digest(&(1_u32, "a")),
+            outcome: Err("Unauthorized".to_string()),
+            timestamp: 0,
+        }
+    }
+
+    #[test]
+    fn test_actions_are_bounded_and_newest_first() {
+        for i in 0..MAX_ADMIN_ACTIONS + 5 {
+            store_admin_action(action(&i.to_string()));
+        }
+
+        assert_eq!(
+            ADMIN_ACTIONS.with(|actions| actions.borrow().len()),
+            MAX_ADMIN_ACTIONS
+        );
+        let newest = admin_actions(2);
+        assert_eq!(newest[0].method, (MAX_ADMIN_ACTIONS + 4).to_string());
+        assert_eq!(newest[1].method, (MAX_ADMIN_ACTIONS + 3).to_string());
+    }
+
+    #[test]
+    fn test_args_digest_is_deterministic() {
+        assert_eq!(args_digest(&(1_u32, "a")), args_digest(&(1_u32, "a")));
+        assert_ne!(args_digest(&(1_u32, "a")), args_digest(&(2_u32, "a")));
+    }
+}
@@ -0,0 +1,182 @@
+//! Confirmed Rate Adjustments by Block
+//!
+//! Indexes every confirmed rate adjustment by the number of the block that included it, so that
+//! the canister can be reconciled against on-chain indexers and subgraphs without scanning the
+//! journal or relying on canister timestamps.
+//!
+//! ```plain
+//! poll_receipts ──► Confirmed { block_number, .. } ──► run_id set? ──► ADJUSTMENTS_BY_BLOCK
+//!                                                                      key: (block_number, tx id)
+//!
+//! get_adjustments_between_blocks(from, to) ──► range [(from, 0), (to, u64::MAX)]
+//! ```
+//!
+//! Rate adjustments are the only transactions submitted within a strategy run; the ckETH
+//! deposits of the charger have no run ID and are not indexed. Reverted adjustments are
+//! indexed as well, with `success` set to `false`, as they were included on-chain.
+
+use std::borrow::Cow;
+
+use candid::{CandidType, Decode, Encode};
+use ic_stable_structures::{storable::Bound, Storable};
+use serde::Deserialize;
+
+use crate::{
+    constants::MAX_ADJUSTMENTS_PER_QUERY,
+    state::ADJUSTMENTS_BY_BLOCK,
+    tx_pool::{TxRecord, TxStatus},
+};
+
+/// A rate adjustment included in a block
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq)]
+pub struct ConfirmedAdjustment {
+    /// Number of the block that included the transaction
+    pub block_number: u64,
+    /// Key of the strategy that submitted the adjustment
+    pub strategy: u32,
+    /// ID of the strategy run that submitted the adjustment
+    pub run_id: u64,
+    /// Pool ID of the transaction
+    pub tx_id: u64,
+    /// Transaction hash
+    pub hash: String,
+    /// Nonce of the transaction
+    pub nonce: u64,
+    /// `true` if the transaction executed successfully, `false` if it reverted
+    pub success: bool,
+    /// Gas used by the transaction
+    pub gas_used: u64,
+    /// Price paid per unit of gas in wei
+    pub effective_gas_price: u128,
+    /// Timestamp in seconds at which the receipt was found
+    pub confirmed_at: u64,
+}
+
+impl Storable for ConfirmedAdjustment {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        Cow::Owned(Encode!(self).expect("Failed to encode a confirmed adjustment."))
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).expect("Failed to decode a confirmed adjustment.")
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+impl ConfirmedAdjustment {
+    /// Builds the index entry of a transaction record, if it is a confirmed rate adjustment.
+    pub fn from_record(record: &TxRecord) -> Option<Self> {
+        let run_id = record.run_id?;
+        match record.status {
+            TxStatus::Confirmed {
+                block_number,
+                gas_used,
+                effective_gas_price,
+                success,
+            } => Some(Self {
+                block_number,
+                strategy: record.strategy,
+                run_id,
+                tx_id: record.id,
+                hash: record.hash.clone(),
+                nonce: record.nonce,
+                success,
+                gas_used,
+                effective_gas_price,
+                confirmed_at: record.updated_at,
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// Indexes the transaction record if it is a confirmed rate adjustment.
+pub fn index_adjustment(record: &TxRecord) {
+    if let Some(adjustment) = ConfirmedAdjustment::from_record(record) {
+        ADJUSTMENTS_BY_BLOCK.with(|index| {
+            index
+                .borrow_mut()
+                .insert((adjustment.block_number, adjustment.tx_id), adjustment)
+        });
+    }
+}
+
+/// Returns the confirmed adjustments included between blocks `from` and `to` (both inclusive),
+/// ordered by block number and pool ID.
+///
+/// At most `MAX_ADJUSTMENTS_PER_QUERY` entries are returned; the next page starts at the block
+/// of the last returned entry.
+pub fn adjustments_between_blocks(from: u64, to: u64) -> Vec<ConfirmedAdjustment> {
+    if from > to {
+        return vec![];
+    }
+    ADJUSTMENTS_BY_BLOCK.with(|index| {
+        index
+            .borrow()
+            .range((from, 0)..=(to, u64::MAX))
+            .take(MAX_ADJUSTMENTS_PER_QUERY)
+            .map(|(_, adjustment)| adjustment)
+            .collect()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use candid::Principal;
+
+    use super::*;
+
+    fn record(id: u64, run_id: Option<u64>, status: TxStatus) -> TxRecord {
+        TxRecord {
+            id,
+            strategy: 1,
+            run_id,
+            nonce: id,
+            hash: format!("0x{:x}", id),
+            raw: "0x02f8".to_string(),
+            status,
+            submitted_at: 100,
+            updated_at: 120,
+            rpc_canister: Some(Principal::anonymous()),
+            submitted_block: None,
+            upfront_fee: None,
+        }
+    }
+
+    fn confirmed(block_number: u64) -> TxStatus {
+        TxStatus::Confirmed {
+            block_number,
+            gas_used: 21_000,
+            effective_gas_price: 1_000_000_000,
+            success: true,
+        }
+    }
+
+    #[test]
+    fn test_adjustments_between_blocks() {
+        index_adjustment(&record(1, Some(1), confirmed(100)));
+        index_adjustment(&record(2, Some(2), confirmed(105)));
+        index_adjustment(&record(3, Some(2), confirmed(105)));
+        index_adjustment(&record(4, Some(3), confirmed(110)));
+        // Pending transactions and charger deposits are not indexed
+        index_adjustment(&record(5, Some(4), TxStatus::Pending));
+        index_adjustment(&record(6, None, confirmed(107)));
+
+        let ids = |from, to| -> Vec<u64> {
+            adjustments_between_blocks(from, to)
+                .iter()
+                .map(|adjustment| adjustment.tx_id)
+                .collect()
+        };
+        assert_eq!(ids(0, u64::MAX), vec![1, 2, 3, 4]);
+        assert_eq!(ids(105, 105), vec![2, 3]);
+        assert_eq!(ids(101, 109), vec![2, 3]);
+        assert_eq!(ids(111, 200), Vec::<u64>::new());
+        assert_eq!(ids(110, 100), Vec::<u64>::new());
+
+        let adjustment = &adjustments_between_blocks(110, 110)[0];
+        assert_eq!(adjustment.run_id, 3);
+        assert_eq!(adjustment.confirmed_at, 120);
+    }
+}
@@ -0,0 +1,87 @@
+//! Build Information
+//!
+//! Identifies the deployed binary: crate version, git commit, build timestamp, and the
+//! enabled features. The commit and timestamp are embedded at compile time by `build.rs`.
+//!
+//! The build information is journaled after every upgrade, so that behavioral changes
+//! can be correlated with specific deployments.
+
+use candid::CandidType;
+
+/// Information about the deployed binary
+#[derive(CandidType, Clone, Debug, PartialEq)]
+pub struct BuildInfo {
+    /// Crate version
+    pub version: String,
+    /// Git commit the binary was built from
+    pub git_commit: String,
+    /// Build timestamp in seconds
+    pub build_timestamp: u64,
+    /// Enabled crate features
+    pub features: Vec<String>,
+}
+
+impl std::fmt::Display for BuildInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "ir_manager v{} (commit {}, built at {}, features: {})",
+            self.version,
+            self.git_commit,
+            self.build_timestamp,
+            self.features.join(", ")
+        )
+    }
+}
+
+/// Returns the build information of the running binary.
+pub fn build_info() -> BuildInfo {
+    let features = [
+        ("mainnet", cfg!(feature = "mainnet")),
+        ("sepolia", cfg!(feature = "sepolia")),
+        ("export-api", cfg!(feature = "export-api")),
+    ]
+    .into_iter()
+    .filter(|(_, enabled)| *enabled)
+    .map(|(feature, _)| feature.to_string())
+    .collect();
+
+    BuildInfo {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        git_commit: env!("IR_MANAGER_GIT_COMMIT").to_string(),
+        build_timestamp: env!("IR_MANAGER_BUILD_TIMESTAMP")
+            .parse()
+            .unwrap_or_default(),
+        features,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn build_info_is_embedded() {
+        let info = build_info();
+        assert_eq!(info.version, env!("CARGO_PKG_VERSION"));
+        assert!(!info.git_commit.is_empty());
+        assert_eq!(
+            info.features.contains(&"mainnet".to_string()),
+            cfg!(feature = "mainnet")
+        );
+    }
+
+    #[test]
+    fn build_info_display() {
+        let info = BuildInfo {
+            version: "0.1.0".to_string(),
+            git_commit: "abc".to_string(),
+            build_timestamp: 42,
+            features: vec!["mainnet".to_string()],
+        };
+        assert_eq!(
+            info.to_string(),
+            "ir_manager v0.1.0 (commit abc, built at 42, features: mainnet)"
+        );
+    }
+}
@@ -1,45 +1,94 @@
 //! The canister's public methods
 #![allow(missing_docs)]
 
-use std::{sync::Arc, time::Duration};
-
-use crate::cleanup::daily_cleanup;
-use crate::constants::MAX_RETRY_ATTEMPTS;
-use crate::constants::MINIMUM_ATTACHED_CYCLES;
-use crate::halt::{is_functional, update_halt_status, Halt};
-use crate::journal::JournalCollection;
+use crate::access_log::{self, args_digest, audit, record_admin_action, AdminAction};
+use crate::adjustments::{adjustments_between_blocks, ConfirmedAdjustment};
+use crate::build_info::{build_info, BuildInfo};
+use crate::charger_config::{self, ChargerConfig};
+use crate::clock::time;
+use crate::constants::{scale, CHAIN_ID};
+use crate::constants::{LOG_RATE_LIMIT_CALLS, LOG_RATE_LIMIT_WINDOW};
+use crate::constants::{MAX_LOGS_PER_PAGE, MINIMUM_ATTACHED_CYCLES};
+use crate::constants::{SNAPSHOT_RATE_LIMIT_CALLS, SNAPSHOT_RATE_LIMIT_WINDOW};
+use crate::constants::{SWAP_RATE_LIMIT_CALLS, SWAP_RATE_LIMIT_WINDOW};
+use crate::custom_providers::{self, CustomProvider, CustomProviderView};
+use crate::digest;
+use crate::flags::{self, FlagQuery, FlagValue};
+use crate::funding::{self, FundingEoa};
+use crate::guard::Guard;
+use crate::halt::{self, Halt, HaltCallback};
+use crate::health::{self, HealthReport};
+use crate::install::{self, InitArgs};
 use crate::journal::LogType;
-use crate::journal::StableJournalCollection;
-use crate::strategy::data::StrategyData;
-use crate::strategy::run::run_strategy;
-use crate::strategy::settings::StrategySettings;
-use crate::strategy::stable::StableStrategy;
-use crate::strategy::stable::StableStrategyQuery;
+use crate::journal::{journal_collection_at, journal_page, IndexedLog};
+use crate::journal::{migrate_journal_timestamps, to_query, JournalCollectionQuery};
+use crate::journal::{JournalCollection, LogFilter, LogPage};
+use crate::key_metadata::{self, KeyMetadataExport, SignerReport};
+use crate::managers;
+use crate::metadata::{MethodMetadata, Role, Stability};
+use crate::metrics::{self, Metrics};
+use crate::positioning_history::{positioning_history, PositioningSample};
+use crate::prometheus::{self, HttpGatewayResponse, HttpRequest};
+use crate::providers::{
+    self, fetch_provider_list, set_pool_members, DisabledProvider, ProviderPool,
+};
+use crate::rate_history::{rate_history, RateAdjustmentRecord};
+use crate::rate_source::{self, RateSource};
+use crate::retention::{self, RetentionPolicy};
+use crate::rpc_registry::{self, RpcCanisterRecord};
+use crate::runs::{
+    self, PositioningCheck, RedeemabilityDecomposition, RunReport, TargetDerivation,
+};
+use crate::scheduler::{self, scheduling_mode, ExecutionPermit, ExecutionTrigger, SchedulingMode};
+use crate::status::{self, Status};
+use crate::strategy::conflicts::{config_conflicts, ConfigConflict};
+use crate::strategy::contention::{self, LockStats};
+use crate::strategy::engine::{RateGranularity, RateStrategyKind};
+use crate::strategy::preview::StrategySimulation;
+use crate::strategy::preview::{preview_adjustment, simulate_strategy, AdjustmentPreview};
+use crate::strategy::report::PublicStrategyReport;
+use crate::strategy::retire::{retire_strategy, retired_strategies, RetiredStrategy};
+use crate::strategy::run::{cancel_pending_transaction, run_strategy};
+use crate::strategy::settings::{ExecutionBackend, RateGuard, RetryBackoff, RunRetry};
+use crate::strategy::setup;
+use crate::strategy::snapshot::{market_snapshot, MarketSnapshot};
+use crate::strategy::stable::{update_strategy, StableStrategy, StableStrategyQuery};
+use crate::strategy::warmup::{Warmup, WarmupReason};
+use crate::timers::start_system_timers;
+use crate::treasury::{self, CachedBalances, SwapWindow, Treasury};
+use crate::triggers::{self, HaltTrigger, TriggerAction, TriggerCondition};
+use crate::tx_pool::{latest_transactions, TxRecord};
 use crate::types::ProviderService;
+use crate::upgrade::{self, ensure_not_paused, StateSnapshot, UpgradeReadiness};
+use crate::utils::address::parse_address;
 use crate::utils::common::*;
 use crate::utils::error::*;
 use crate::utils::evm_rpc::Service;
-use crate::utils::signer::*;
+use crate::utils::gas::FeePolicy;
+use crate::utils::signer::{self, *};
+use crate::utils::user_operation;
 use crate::{
-    charger::{check_threshold, recharge_cketh, transfer_cketh, SwapLock},
+    charger::{check_threshold, transfer_cketh, SwapLock},
     state::*,
-    types::{StrategyInput, SwapResponse},
+    types::{StrategyInput, SwapResponse, SwapResponseV2},
 };
 
+use alloy_primitives::U256;
 use candid::Nat;
-use ic_canister::{generate_idl, query, update, Canister, Idl, PreUpdate};
+use evm_rpc_types::RpcApi;
+use ic_canister::{
+    generate_idl, init, post_upgrade, pre_upgrade, query, update, Canister, Idl, PreUpdate,
+};
 use ic_exports::ic_cdk::api::call::msg_cycles_available;
+use ic_exports::ic_cdk::api::canister_balance128;
+use ic_exports::ic_cdk::api::management_canister::http_request::{HttpResponse, TransformArgs};
 use ic_exports::ic_cdk::api::management_canister::main::canister_status;
 use ic_exports::ic_cdk::api::management_canister::main::CanisterIdRecord;
 use ic_exports::ic_cdk::api::management_canister::main::CanisterStatusResponse;
 use ic_exports::ic_cdk::id;
 use ic_exports::{
     candid::Principal,
-    ic_cdk::{
-        api::management_canister::ecdsa::{EcdsaCurve, EcdsaKeyId},
-        caller, spawn,
-    },
-    ic_cdk_timers::set_timer_interval,
+    ic_cdk::{caller, spawn, trap},
 };
 
 /// The IrManager canister struct (a `canister-sdk` requirement)
@@ -51,6 +100,661 @@ pub struct IrManager {
 
 impl PreUpdate for IrManager {}
 
+/// Metadata of all public methods, exported next to the Candid interface for tooling.
+///
+/// Every method exposed in the Candid interface must have an entry here.
+pub const API_METADATA: &[MethodMetadata] = &[
+    MethodMetadata {
+        name: "mint_strategy",
+        description: "Mints a new strategy and derives its EOA.",
+        role: Role::Controller,
+        stability: Stability::Stable,
+    },
+    MethodMetadata {
+        name: "set_batch_manager",
+        description: "Sets the batch manager of a strategy and starts its timers.",
+        role: Role::Controller,
+        stability: Stability::Stable,
+    },
+    MethodMetadata {
+        name: "set_gas_budget",
+        description: "Sets the gas spend budget per 30-day window of a strategy.",
+        role: Role::Controller,
+        stability: Stability::Experimental,
+    },
+    MethodMetadata {
+        name: "set_upfront_fee_budget",
+        description: "Sets the upfront fee budget per 30-day window of a strategy.",
+        role: Role::Controller,
+        stability: Stability::Experimental,
+    },
+    MethodMetadata {
+        name: "set_rate_guard",
+        description: "Sets the maximum rate deviation and the rate ceiling a strategy submits without an acknowledgment.",
+        role: Role::Controller,
+        stability: Stability::Experimental,
+    },
+    MethodMetadata {
+        name: "acknowledge_safety_trip",
+        description: "Lets the next run of a strategy submit the rate adjustment held back by its rate guard.",
+        role: Role::Controller,
+        stability: Stability::Experimental,
+    },
+    MethodMetadata {
+        name: "set_rate_granularity",
+        description: "Sets the increment and rounding of the rates proposed for a strategy.",
+        role: Role::Controller,
+        stability: Stability::Experimental,
+    },
+    MethodMetadata {
+        name: "set_rate_strategy",
+        description: "Sets the decision logic a strategy uses to pick new rates.",
+        role: Role::Controller,
+        stability: Stability::Experimental,
+    },
+    MethodMetadata {
+        name: "set_retry_backoff",
+        description: "Sets the backoff between rate adjustment resubmissions of a strategy.",
+        role: Role::Controller,
+        stability: Stability::Experimental,
+    },
+    MethodMetadata {
+        name: "set_run_retry",
+        description: "Sets the attempts of a failed run of a strategy and the backoff between them.",
+        role: Role::Controller,
+        stability: Stability::Experimental,
+    },
+    MethodMetadata {
+        name: "set_fee_policy",
+        description: "Sets the EIP-1559 fee policy of a strategy's transactions.",
+        role: Role::Controller,
+        stability: Stability::Experimental,
+    },
+    MethodMetadata {
+        name: "set_execution_backend",
+        description: "Sets whether a strategy submits its rate adjustments as transactions or as sponsored UserOperations.",
+        role: Role::Controller,
+        stability: Stability::Experimental,
+    },
+    MethodMetadata {
+        name: "set_registry",
+        description: "Sets the on-chain registry the confirmed rate adjustments of a strategy are recorded in.",
+        role: Role::Controller,
+        stability: Stability::Experimental,
+    },
+    MethodMetadata {
+        name: "pause_strategy",
+        description: "Pauses the executions of a single strategy.",
+        role: Role::Controller,
+        stability: Stability::Experimental,
+    },
+    MethodMetadata {
+        name: "resume_strategy",
+        description: "Resumes the executions of a paused strategy.",
+        role: Role::Controller,
+        stability: Stability::Experimental,
+    },
+    MethodMetadata {
+        name: "confirm_warmup",
+        description: "Ends the warm-up of a strategy, which goes live from its next run.",
+        role: Role::Controller,
+        stability: Stability::Experimental,
+    },
+    MethodMetadata {
+        name: "remove_strategy",
+        description: "Retires a strategy and archives its journal.",
+        role: Role::Controller,
+        stability: Stability::Experimental,
+    },
+    MethodMetadata {
+        name: "get_retired_strategies",
+        description: "Returns the archives of the strategies retired with a key.",
+        role: Role::Public,
+        stability: Stability::Experimental,
+    },
+    MethodMetadata {
+        name: "simulate_strategy",
+        description: "Simulates a strategy run up to the transaction submission, optionally with another minimum target.",
+        role: Role::Controller,
+        stability: Stability::Experimental,
+    },
+    MethodMetadata {
+        name: "preview_adjustment_calldata",
+        description: "Returns the setNewRate calldata a strategy would submit, without signing it.",
+        role: Role::Controller,
+        stability: Stability::Experimental,
+    },
+    MethodMetadata {
+        name: "get_market_snapshot",
+        description: "Returns the sorted troves of a strategy's market, the debt in front of its batch, and its target at the latest block.",
+        role: Role::Public,
+        stability: Stability::Experimental,
+    },
+    MethodMetadata {
+        name: "cancel_pending_tx",
+        description: "Cancels the oldest pending transaction of a strategy with a self-transfer.",
+        role: Role::Controller,
+        stability: Stability::Experimental,
+    },
+    MethodMetadata {
+        name: "start_timers",
+        description: "Starts the strategy, recharge, and cleanup timers.",
+        role: Role::Controller,
+        stability: Stability::Stable,
+    },
+    MethodMetadata {
+        name: "prune_unused_managers",
+        description: "Removes trove managers that no strategy uses from the manager registry.",
+        role: Role::Controller,
+        stability: Stability::Stable,
+    },
+    MethodMetadata {
+        name: "add_manager",
+        description: "Adds a trove manager to the registry aggregated by every strategy run.",
+        role: Role::Controller,
+        stability: Stability::Experimental,
+    },
+    MethodMetadata {
+        name: "remove_manager",
+        description: "Removes a trove manager that no strategy uses from the registry.",
+        role: Role::Controller,
+        stability: Stability::Experimental,
+    },
+    MethodMetadata {
+        name: "list_managers",
+        description: "Returns the trove managers aggregated by every strategy run.",
+        role: Role::Public,
+        stability: Stability::Experimental,
+    },
+    MethodMetadata {
+        name: "prepare_for_upgrade",
+        description: "Pauses new work and reports whether the canister can be upgraded safely.",
+        role: Role::Controller,
+        stability: Stability::Experimental,
+    },
+    MethodMetadata {
+        name: "resume_after_upgrade",
+        description: "Lifts the upgrade pause and restarts the timers if needed.",
+        role: Role::Controller,
+        stability: Stability::Experimental,
+    },
+    MethodMetadata {
+        name: "set_scheduling_mode",
+        description: "Switches between timer-based and keeper-based strategy scheduling.",
+        role: Role::Controller,
+        stability: Stability::Experimental,
+    },
+    MethodMetadata {
+        name: "get_scheduling_mode",
+        description: "Returns the current strategy scheduling mode.",
+        role: Role::Public,
+        stability: Stability::Experimental,
+    },
+    MethodMetadata {
+        name: "grant_execution_permit",
+        description: "Grants or replaces the execution permit of an external keeper.",
+        role: Role::Controller,
+        stability: Stability::Experimental,
+    },
+    MethodMetadata {
+        name: "revoke_execution_permit",
+        description: "Revokes the execution permit of an external keeper.",
+        role: Role::Controller,
+        stability: Stability::Experimental,
+    },
+    MethodMetadata {
+        name: "get_execution_permits",
+        description: "Returns all external keepers with their execution permits.",
+        role: Role::Public,
+        stability: Stability::Experimental,
+    },
+    MethodMetadata {
+        name: "execute_strategy",
+        description: "Executes a strategy on behalf of an external keeper holding a permit.",
+        role: Role::Public,
+        stability: Stability::Experimental,
+    },
+    MethodMetadata {
+        name: "execute_strategy_now",
+        description: "Starts a strategy execution immediately, unless the strategy is running.",
+        role: Role::Controller,
+        stability: Stability::Experimental,
+    },
+    MethodMetadata {
+        name: "get_strategies",
+        description: "Returns the settings, data, and lock state of all strategies.",
+        role: Role::Public,
+        stability: Stability::Stable,
+    },
+    MethodMetadata {
+        name: "get_strategies_full",
+        description: "Returns the settings, data, and lock state of all strategies, ordered by key.",
+        role: Role::Public,
+        stability: Stability::Experimental,
+    },
+    MethodMetadata {
+        name: "get_strategy",
+        description: "Returns the settings, data, and lock state of a strategy.",
+        role: Role::Public,
+        stability: Stability::Experimental,
+    },
+    MethodMetadata {
+        name: "get_strategy_address",
+        description: "Returns the EOA address of a strategy.",
+        role: Role::Public,
+        stability: Stability::Stable,
+    },
+    MethodMetadata {
+        name: "get_public_strategy_report",
+        description: "Returns the public track record of a strategy for trove owners.",
+        role: Role::Public,
+        stability: Stability::Experimental,
+    },
+    MethodMetadata {
+        name: "get_config_conflicts",
+        description: "Returns the strategies whose configurations overlap.",
+        role: Role::Public,
+        stability: Stability::Experimental,
+    },
+    MethodMetadata {
+        name: "export_key_metadata",
+        description: "Returns the key name, derivation paths, EOAs, and chain nonces of all strategies.",
+        role: Role::Controller,
+        stability: Stability::Experimental,
+    },
+    MethodMetadata {
+        name: "verify_signer",
+        description: "Signs a test payload with the derivation path of a strategy and checks that it recovers to the strategy's EOA.",
+        role: Role::Controller,
+        stability: Stability::Experimental,
+    },
+    MethodMetadata {
+        name: "get_transactions",
+        description: "Returns the most recent outbound transactions of a strategy.",
+        role: Role::Public,
+        stability: Stability::Experimental,
+    },
+    MethodMetadata {
+        name: "get_adjustments_between_blocks",
+        description: "Returns the confirmed rate adjustments included in a range of blocks.",
+        role: Role::Public,
+        stability: Stability::Experimental,
+    },
+    MethodMetadata {
+        name: "get_rate_history",
+        description: "Returns the latest confirmed rate changes of a strategy as structured records.",
+        role: Role::Public,
+        stability: Stability::Experimental,
+    },
+    MethodMetadata {
+        name: "get_positioning_history",
+        description: "Returns the daily samples of a strategy's debt in front, target debt in front, and batch rate.",
+        role: Role::Public,
+        stability: Stability::Experimental,
+    },
+    MethodMetadata {
+        name: "register_rpc_canister",
+        description: "Registers an EVM RPC canister that replaces the previous one for all strategies from an effective-from time.",
+        role: Role::Controller,
+        stability: Stability::Experimental,
+    },
+    MethodMetadata {
+        name: "get_rpc_canisters",
+        description: "Returns every registered EVM RPC canister with its effective-from time.",
+        role: Role::Public,
+        stability: Stability::Experimental,
+    },
+    MethodMetadata {
+        name: "get_run",
+        description: "Returns the summary, transactions, and logs of a strategy run.",
+        role: Role::Public,
+        stability: Stability::Experimental,
+    },
+    MethodMetadata {
+        name: "get_redeemability",
+        description: "Returns the most recent decompositions of the maximum debt redeemable against a strategy's collateral, including the 1% floor.",
+        role: Role::Public,
+        stability: Stability::Experimental,
+    },
+    MethodMetadata {
+        name: "get_target_derivations",
+        description: "Returns the most recent target percentage derivations of a strategy.",
+        role: Role::Public,
+        stability: Stability::Experimental,
+    },
+    MethodMetadata {
+        name: "get_positioning_checks",
+        description: "Returns the expected and realized debt in front of a strategy's recent rate adjustments.",
+        role: Role::Public,
+        stability: Stability::Experimental,
+    },
+    MethodMetadata {
+        name: "http_request",
+        description: "Serves the activity counters in the Prometheus text format at `/metrics`, and the journal as NDJSON or CSV at `/logs?strategy=K&depth=N&format=ndjson|csv`.",
+        role: Role::Public,
+        stability: Stability::Experimental,
+    },
+    MethodMetadata {
+        name: "get_metrics",
+        description: "Returns the activity counters of the canister.",
+        role: Role::Public,
+        stability: Stability::Experimental,
+    },
+    MethodMetadata {
+        name: "swap_cketh",
+        description: "Swaps attached cycles for discounted ckETH.",
+        role: Role::Public,
+        stability: Stability::Stable,
+    },
+    MethodMetadata {
+        name: "swap_cketh_v2",
+        description: "Swaps attached cycles for discounted ckETH and returns the rate breakdown.",
+        role: Role::Public,
+        stability: Stability::Experimental,
+    },
+    MethodMetadata {
+        name: "get_swaps",
+        description: "Returns the most recent ckETH<>Cycles swaps.",
+        role: Role::Public,
+        stability: Stability::Experimental,
+    },
+    MethodMetadata {
+        name: "next_swap_window",
+        description: "Projects when the cycles balance falls to the threshold at which swaps are accepted.",
+        role: Role::Public,
+        stability: Stability::Experimental,
+    },
+    MethodMetadata {
+        name: "set_fixed_rate",
+        description: "Prices swaps with a fixed ETH/CXDR rate, or with the XRC again.",
+        role: Role::Controller,
+        stability: Stability::Experimental,
+    },
+    MethodMetadata {
+        name: "get_rate_source",
+        description: "Returns the source of the ETH/CXDR rate used to price swaps.",
+        role: Role::Public,
+        stability: Stability::Experimental,
+    },
+    MethodMetadata {
+        name: "get_treasury",
+        description: "Returns the cycles and ckETH balances, EOA balances, pending mints, swap volume, and charger thresholds.",
+        role: Role::Public,
+        stability: Stability::Experimental,
+    },
+    MethodMetadata {
+        name: "get_cached_balances",
+        description: "Returns the cached ckETH and cycles balances with their age and staleness.",
+        role: Role::Public,
+        stability: Stability::Experimental,
+    },
+    MethodMetadata {
+        name: "init_funding_eoa",
+        description: "Derives and stores the funding EOA that sends every ckETH deposit.",
+        role: Role::Controller,
+        stability: Stability::Experimental,
+    },
+    MethodMetadata {
+        name: "get_funding_eoa",
+        description: "Returns the address, nonce, and last observed balance of the funding EOA.",
+        role: Role::Public,
+        stability: Stability::Experimental,
+    },
+    MethodMetadata {
+        name: "set_digest_webhook",
+        description: "Sets or clears the webhook the daily digest is posted to.",
+        role: Role::Controller,
+        stability: Stability::Experimental,
+    },
+    MethodMetadata {
+        name: "set_alert_webhook",
+        description: "Sets or clears the webhook the alerts and notices are posted to.",
+        role: Role::Controller,
+        stability: Stability::Experimental,
+    },
+    MethodMetadata {
+        name: "transform_webhook_response",
+        description: "Reduces webhook responses to their status code for replica consensus.",
+        role: Role::Public,
+        stability: Stability::Experimental,
+    },
+    MethodMetadata {
+        name: "transform_bundler_response",
+        description: "Reduces bundler responses to the UserOperation hash or the error for replica consensus.",
+        role: Role::Public,
+        stability: Stability::Experimental,
+    },
+    MethodMetadata {
+        name: "disable_provider",
+        description: "Removes an RPC provider from the rotation of both pools.",
+        role: Role::Controller,
+        stability: Stability::Stable,
+    },
+    MethodMetadata {
+        name: "enable_provider",
+        description: "Restores a disabled RPC provider to the rotation of both pools.",
+        role: Role::Controller,
+        stability: Stability::Stable,
+    },
+    MethodMetadata {
+        name: "get_disabled_providers",
+        description: "Returns the RPC providers disabled by a controller.",
+        role: Role::Public,
+        stability: Stability::Stable,
+    },
+    MethodMetadata {
+        name: "add_custom_provider",
+        description: "Registers a custom JSON-RPC endpoint in a provider pool.",
+        role: Role::Controller,
+        stability: Stability::Experimental,
+    },
+    MethodMetadata {
+        name: "remove_custom_provider",
+        description: "Removes a custom JSON-RPC endpoint from its provider pool.",
+        role: Role::Controller,
+        stability: Stability::Experimental,
+    },
+    MethodMetadata {
+        name: "get_custom_providers",
+        description: "Returns the custom JSON-RPC endpoints without their credentials.",
+        role: Role::Public,
+        stability: Stability::Experimental,
+    },
+    MethodMetadata {
+        name: "get_ranked_providers_list",
+        description: "Returns the reputation ranking of the RPC providers.",
+        role: Role::Public,
+        stability: Stability::Stable,
+    },
+    MethodMetadata {
+        name: "get_provider_pool",
+        description: "Returns the reputation ranking of a provider pool.",
+        role: Role::Public,
+        stability: Stability::Experimental,
+    },
+    MethodMetadata {
+        name: "set_provider_pool",
+        description: "Replaces the members of a provider pool and resets their reputations.",
+        role: Role::Controller,
+        stability: Stability::Experimental,
+    },
+    MethodMetadata {
+        name: "get_logs",
+        description: "Returns the most recent journal collections.",
+        role: Role::Public,
+        stability: Stability::Stable,
+    },
+    MethodMetadata {
+        name: "get_log",
+        description: "Returns a single journal collection by position, along with the number of collections.",
+        role: Role::Public,
+        stability: Stability::Experimental,
+    },
+    MethodMetadata {
+        name: "get_logs_paginated",
+        description: "Returns a page of journal collections after a cursor, for walking the whole journal.",
+        role: Role::Public,
+        stability: Stability::Experimental,
+    },
+    MethodMetadata {
+        name: "get_recharge_logs",
+        description: "Returns the most recent recharge journal collections.",
+        role: Role::Public,
+        stability: Stability::Stable,
+    },
+    MethodMetadata {
+        name: "get_strategy_logs",
+        description: "Returns the most recent journal collections of a strategy, optionally filtered by log type and time range.",
+        role: Role::Public,
+        stability: Stability::Stable,
+    },
+    MethodMetadata {
+        name: "halt_status",
+        description: "Returns the halt status of the canister.",
+        role: Role::Public,
+        stability: Stability::Stable,
+    },
+    MethodMetadata {
+        name: "cancel_halt",
+        description: "Cancels a halt that is in progress.",
+        role: Role::Controller,
+        stability: Stability::Experimental,
+    },
+    MethodMetadata {
+        name: "resume_from_halt",
+        description: "Lifts a completed halt once a strategy has an EOA and the balances are above their thresholds.",
+        role: Role::Controller,
+        stability: Stability::Experimental,
+    },
+    MethodMetadata {
+        name: "set_halt_callback",
+        description: "Sets or clears the canister method notified of the halt status transitions.",
+        role: Role::Controller,
+        stability: Stability::Experimental,
+    },
+    MethodMetadata {
+        name: "get_halt_callback",
+        description: "Returns the canister method notified of the halt status transitions.",
+        role: Role::Public,
+        stability: Stability::Experimental,
+    },
+    MethodMetadata {
+        name: "get_admin_actions",
+        description: "Returns the most recent privileged calls and their outcomes.",
+        role: Role::Public,
+        stability: Stability::Experimental,
+    },
+    MethodMetadata {
+        name: "get_status",
+        description: "Returns the halt and upgrade status, timer state, and journal integrity counters.",
+        role: Role::Public,
+        stability: Stability::Experimental,
+    },
+    MethodMetadata {
+        name: "health",
+        description: "Returns the balances, halt status, per-strategy lock and execution state, and provider reputations.",
+        role: Role::Public,
+        stability: Stability::Experimental,
+    },
+    MethodMetadata {
+        name: "get_lock_stats",
+        description: "Returns how often each strategy's lock was contended or released by the timeout.",
+        role: Role::Public,
+        stability: Stability::Experimental,
+    },
+    MethodMetadata {
+        name: "add_halt_trigger",
+        description: "Registers a custom halt or alert trigger evaluated daily.",
+        role: Role::Controller,
+        stability: Stability::Experimental,
+    },
+    MethodMetadata {
+        name: "remove_halt_trigger",
+        description: "Removes a custom halt or alert trigger.",
+        role: Role::Controller,
+        stability: Stability::Experimental,
+    },
+    MethodMetadata {
+        name: "get_halt_triggers",
+        description: "Returns the custom halt and alert triggers with their evaluation state.",
+        role: Role::Public,
+        stability: Stability::Experimental,
+    },
+    MethodMetadata {
+        name: "set_execution_trigger",
+        description: "Sets whether a strategy runs hourly or when new blocks are observed.",
+        role: Role::Controller,
+        stability: Stability::Experimental,
+    },
+    MethodMetadata {
+        name: "set_flag",
+        description: "Overrides the value of a runtime flag.",
+        role: Role::Controller,
+        stability: Stability::Experimental,
+    },
+    MethodMetadata {
+        name: "reset_flag",
+        description: "Restores the default value of a runtime flag.",
+        role: Role::Controller,
+        stability: Stability::Experimental,
+    },
+    MethodMetadata {
+        name: "get_flags",
+        description: "Returns all runtime flags with their values in effect.",
+        role: Role::Public,
+        stability: Stability::Experimental,
+    },
+    MethodMetadata {
+        name: "set_journal_retention",
+        description: "Replaces the journal retention policy enforced during the daily cleanup.",
+        role: Role::Controller,
+        stability: Stability::Experimental,
+    },
+    MethodMetadata {
+        name: "get_journal_retention",
+        description: "Returns the journal retention policy enforced during the daily cleanup.",
+        role: Role::Public,
+        stability: Stability::Experimental,
+    },
+    MethodMetadata {
+        name: "set_charger_config",
+        description: "Sets the ckETH and cycles thresholds and the discount of the ckETH<>Cycles swaps.",
+        role: Role::Controller,
+        stability: Stability::Experimental,
+    },
+    MethodMetadata {
+        name: "get_charger_config",
+        description: "Returns the ckETH and cycles thresholds and the discount of the ckETH<>Cycles swaps.",
+        role: Role::Public,
+        stability: Stability::Experimental,
+    },
+    MethodMetadata {
+        name: "set_ecdsa_key_name",
+        description: "Sets the name of the threshold ECDSA key the EOAs are derived from, before any EOA is derived.",
+        role: Role::Controller,
+        stability: Stability::Experimental,
+    },
+    MethodMetadata {
+        name: "get_ecdsa_key_name",
+        description: "Returns the name of the threshold ECDSA key the EOAs are derived from.",
+        role: Role::Public,
+        stability: Stability::Experimental,
+    },
+    MethodMetadata {
+        name: "get_build_info",
+        description:
+            "Returns the version, git commit, build timestamp, and features of the binary.",
+        role: Role::Public,
+        stability: Stability::Stable,
+    },
+    MethodMetadata {
+        name: "get_canister_status",
+        description: "Returns the management canister status of the canister.",
+        role: Role::Public,
+        stability: Stability::Stable,
+    },
+];
+
 impl IrManager {
     /// Mints a new strategy with the provided configuration parameters.
     ///
@@ -69,85 +773,34 @@ impl IrManager {
     ///   - upfront_fee_period: Cooldown period for rate adjustments in seconds
     ///   - collateral_registry: Address of the collateral registry contract
     ///   - hint_helper: Address of the hint helper contract
+    ///   - chain: Chain of the branch with its providers, the home chain if omitted
     ///
     /// # Returns
     ///
     /// * `Ok(String)` - The address of the newly generated EOA for this strategy
-    /// * `Err(ManagerError)` - If strategy creation fails due to:
+    /// * `Err(MintError)` - If strategy creation fails due to:
     ///   - Key already in use
-    ///   - Invalid addresses
-    ///   - tECDSA key generation failure
+    ///   - A conflicting configuration with another strategy, unless `force` is set
+    ///   - Invalid addresses or numbers, or contract addresses without deployed code
+    ///   - A minimum target of zero or above 100%
+    ///   - An invalid chain configuration, or other providers than the ones registered for the chain
+    ///   - tECDSA key derivation failure
+    ///   - An unreachable EVM RPC canister
+    ///   - The canister being halted
     ///
     /// # Access Control
     ///
     /// Only the canister controller can call this function.
     #[update]
-    pub async fn mint_strategy(&self, strategy: StrategyInput) -> ManagerResult<String> {
-        only_controller(caller())?;
-
-        let strategies = STRATEGY_STATE.with(|strategies| strategies.borrow().clone());
-
-        if strategies.contains_key(&strategy.key) {
-            return Err(ManagerError::Custom(
-                "This key is already being used.".to_string(),
-            ));
+    pub async fn mint_strategy(&self, strategy: StrategyInput) -> Result<String, MintError> {
+        let digest = args_digest(&strategy);
+        let result: Result<String, MintError> = async {
+            Guard::new("mint_strategy").check()?;
+            setup::mint_strategy(strategy).await
         }
-
-        let manager = string_to_address(strategy.manager)?;
-        MANAGERS.with(|managers| managers.borrow_mut().push(manager));
-
-        let derivation_path = vec![strategy.key.to_be_bytes().to_vec()];
-        let key_id = EcdsaKeyId {
-            curve: EcdsaCurve::Secp256k1,
-            name: String::from("key_1"),
-        };
-        let public_key_bytes =
-            get_canister_public_key(key_id, None, derivation_path.clone()).await?;
-        let eoa_pk = string_to_address(pubkey_bytes_to_address(&public_key_bytes)?)?;
-        let rpc_canister = Service(strategy.rpc_principal);
-        let eoa_nonce = get_nonce(&rpc_canister, eoa_pk).await?;
-
-        // Convert String addresses to Address ones
-        let collateral_registry_address = string_to_address(strategy.collateral_registry)?;
-        let multi_trove_getter_address = string_to_address(strategy.multi_trove_getter)?;
-        let sorted_troves = string_to_address(strategy.sorted_troves)?;
-        let hint_helper_address = string_to_address(strategy.hint_helper)?;
-
-        // Convert Nat values to U256 ones
-        let target_min_u256 = nat_to_u256(&strategy.target_min)?;
-        let upfront_fee_period_u256 = nat_to_u256(&strategy.upfront_fee_period)?;
-        let collateral_index_u256 = nat_to_u256(&strategy.collateral_index)?;
-
-        let strategy_settings = StrategySettings::default()
-            .key(strategy.key)
-            .manager(manager)
-            .collateral_registry(collateral_registry_address)
-            .multi_trove_getter(multi_trove_getter_address)
-            .sorted_troves(sorted_troves)
-            .hint_helper(hint_helper_address)
-            .upfront_fee_period(upfront_fee_period_u256)
-            .collateral_index(collateral_index_u256)
-            .eoa_pk(Some(eoa_pk))
-            .derivation_path(derivation_path)
-            .target_min(target_min_u256)
-            .rpc_canister(rpc_canister)
-            .clone();
-
-        // The following line sets the nonce, latest rate, and latest update timestamp to 0.
-        // We don't care about any of those at this point.
-        // The nonce will be recalculated.
-        // The latest rate will be adjusted when the `set_batch_manager` function is called.
-        // The timestamp will stay as 0 until the first strategy rate adjustment tx is sent.
-        let strategy_data = StrategyData::default()
-            .eoa_nonce(eoa_nonce.to::<u64>())
-            .clone();
-
-        StableStrategy::default()
-            .settings(strategy_settings)
-            .data(strategy_data)
-            .mint()?;
-
-        Ok(eoa_pk.to_string())
+        .await;
+        record_admin_action("mint_strategy", digest, &result);
+        result
     }
 
     /// Sets the batch manager contract address for a given strategy.
@@ -167,7 +820,8 @@ impl IrManager {
     /// * `Ok(())` - If batch manager was successfully set
     /// * `Err(ManagerError)` - If operation fails due to:
     ///   - Strategy not found
-    ///   - Invalid batch manager address
+    ///   - An invalid batch manager address, or one without a deployed contract
+    ///   - The batch manager being used by another strategy
     ///   - Rate conversion error
     ///
     /// # Access Control
@@ -180,233 +834,1882 @@ impl IrManager {
         batch_manager: String,
         current_rate: Nat,
     ) -> ManagerResult<()> {
-        only_controller(caller())?;
-        let batch_manager_address = string_to_address(batch_manager)?;
-        STRATEGY_STATE.with(|strategies| {
-            let mut binding = strategies.borrow_mut();
-            let strategy = binding
-                .get_mut(&key)
-                .ok_or(ManagerError::NonExistentValue)?;
-            strategy.settings.batch_manager = batch_manager_address;
-            strategy.data.latest_rate = nat_to_u256(&current_rate)?;
-            Ok(())
-        })
+        let digest = args_digest(&(&key, &batch_manager, &current_rate));
+        let result: ManagerResult<()> = async {
+            Guard::new("set_batch_manager").check()?;
+            setup::set_batch_manager(key, batch_manager, current_rate).await
+        }
+        .await;
+        record_admin_action("set_batch_manager", digest, &result);
+        result
     }
 
-    /// Starts all system timers for strategy execution and maintenance tasks.
+    /// Sets the backoff between rate adjustment resubmissions for a given strategy.
     ///
-    /// This function initializes recurring timers for:
-    /// - Hourly strategy execution cycles
-    /// - Daily ckETH balance monitoring and recharging
-    /// - Daily provider reputation management and cleanup
-    /// - Daily halt condition evaluation
+    /// When a rate adjustment transaction is rejected because of a wrong nonce, the next attempt
+    /// is scheduled after `base_delay * 2^(attempt - 1)` seconds, capped at `max_delay`.
     ///
-    /// Each strategy immediately executes once when timers start, then begins its hourly cycle.
-    /// The recharge cycle monitors both cycle and ckETH balances, triggering recharge
-    /// operations when thresholds are reached.
+    /// # Arguments
+    ///
+    /// * `key` - The unique identifier of the existing strategy
+    /// * `backoff` - Base and maximum delays in seconds
     ///
     /// # Returns
     ///
-    /// * `Ok(())` - If all timers were successfully started
-    /// * `Err(ManagerError)` - If timer initialization fails
+    /// * `Ok(())` - If the backoff was successfully set
+    /// * `Err(ManagerError)` - If the strategy is not found or the delays are invalid
     ///
     /// # Access Control
     ///
     /// Only the canister controller can call this function.
-    ///
-    /// # Note
-    ///
-    /// This should typically be called once after all strategies are configured
-    /// and before the canister is made immutable.
     #[update]
-    pub async fn start_timers(&self) -> ManagerResult<()> {
-        only_controller(caller())?;
-        // Retrieve all strategies for setting up timers
-        let strategies: Vec<u32> = STRATEGY_STATE
-            .with(|vector_data| vector_data.borrow().iter().map(|(key, _)| *key).collect());
-
-        let max_retry_attempts = Arc::new(MAX_RETRY_ATTEMPTS);
-
-        // Start all strategies immediately
-        strategies.clone().into_iter().for_each(|key| {
-            spawn(run_strategy(key));
-        });
-
-        // Set timers for each strategy (execute every 1 hour)
-        strategies.into_iter().for_each(|key| {
-            set_timer_interval(Duration::from_secs(3_600), move || {
-                spawn(run_strategy(key));
-            });
-        });
-
-        // Set a recurring timer for recharging ckETH balance (execute every 24 hours)
-        set_timer_interval(Duration::from_secs(86_400), move || {
-            let max_retry_attempts = Arc::clone(&max_retry_attempts);
-            spawn(async move {
-                assert!(is_functional());
-                let mut journal = JournalCollection::open(None);
-                for turn in 1..=*max_retry_attempts {
-                    let result = recharge_cketh(&mut journal).await;
-                    // log the result
-                    journal.append_note(
-                        result.clone(),
-                        crate::journal::LogType::Recharge,
-                        format!("Turn {}/{}", turn, max_retry_attempts),
-                    );
-
-                    if result.is_ok() {
-                        break;
-                    }
-                }
-            });
-        });
-
-        // Recurring timer (24h) that:
-        // - clears all reputation change logs and resets the reputations
-        // - checks if the logs have more than 300 items, if so, clear the surplus
-        set_timer_interval(Duration::from_secs(86_400), || {
-            spawn(daily_cleanup());
-        });
-
-        set_timer_interval(Duration::from_secs(86_400), || {
-            update_halt_status();
-        });
-
-        Ok(())
+    pub fn set_retry_backoff(&self, key: u32, backoff: RetryBackoff) -> ManagerResult<()> {
+        audit("set_retry_backoff", args_digest(&(&key, &backoff)), || {
+            Guard::new("set_retry_backoff").check()?;
+            if backoff.base_delay == 0 || backoff.max_delay < backoff.base_delay {
+                return Err(ManagerError::Custom(
+                    "The base delay must be positive and not exceed the maximum delay.".to_string(),
+                ));
+            }
+            update_strategy(key, |strategy| {
+                strategy.settings.retry_backoff(backoff);
+            })
+        })
     }
 
-    /// Retrieves current data for all strategies in the system.
+    /// Sets the attempts of a failed run of a strategy and the backoff between them.
     ///
-    /// Returns information about each strategy including:
-    /// - Contract addresses (trove manager, batch manager)
-    /// - Lock status and timing
-    /// - Current interest rate
-    /// - Target minimums
-    /// - EOA public key
-    /// - Last update timestamp
+    /// The attempt after a failed attempt `n` is scheduled after `base_delay * 4^(n - 1)`
+    /// seconds, capped at `max_delay`, plus a random jitter of up to `jitter_percent` of it.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The unique identifier of the existing strategy
+    /// * `run_retry` - Number of attempts, delays in seconds, and jitter in percent
     ///
     /// # Returns
     ///
-    /// A vector of StrategyQueryData structs containing current strategy states.
-    /// Returns an empty vector if no strategies exist.
-    #[query]
-    pub fn get_strategies(&self) -> ManagerResult<Vec<StableStrategyQuery>> {
-        STRATEGY_STATE.with(|vector_data| {
-            let binding = vector_data.borrow();
-            let values = binding.values();
-            if values.len() == 0 {
-                return Ok(vec![]);
+    /// * `Ok(())` - If the retry policy was successfully set
+    /// * `Err(ManagerError)` - If the strategy is not found or the policy is invalid
+    ///
+    /// # Access Control
+    ///
+    /// Only the canister controller can call this function.
+    #[update]
+    pub fn set_run_retry(&self, key: u32, run_retry: RunRetry) -> ManagerResult<()> {
+        audit("set_run_retry", args_digest(&(&key, &run_retry)), || {
+            Guard::new("set_run_retry").check()?;
+            if run_retry.max_attempts == 0
+                || run_retry.base_delay == 0
+                || run_retry.max_delay < run_retry.base_delay
+                || run_retry.jitter_percent > 100
+            {
+                return Err(ManagerError::Custom(
+                    "A run needs at least one attempt, the base delay must be positive and not exceed the maximum delay, and the jitter must not exceed 100%.".to_string(),
+                ));
             }
-            values
-                .map(|strategy| StableStrategyQuery::try_from(strategy.clone()))
-                .collect()
+            update_strategy(key, |strategy| {
+                strategy.settings.run_retry(run_retry);
+            })
         })
     }
 
-    /// Retrieves the EOA address associated with a specific strategy.
+    /// Sets the EIP-1559 fee policy of a strategy's transactions.
+    ///
+    /// The tips paid in the last blocks are sampled at `percentile`, raised to
+    /// `min_priority_fee`, and scaled by `multiplier_percent` along with the base fee. The
+    /// resulting max fee is bounded by `max_fee_cap`, which also bounds replacements.
     ///
     /// # Arguments
     ///
-    /// * `index` - The unique identifier of the strategy
+    /// * `key` - The unique identifier of the strategy
+    /// * `policy` - The fee policy to use from the next submission on
     ///
     /// # Returns
     ///
-    /// * `Some(String)` - The EOA address if the strategy exists and has an assigned EOA
-    /// * `None` - If strategy doesn't exist or has no EOA assigned
-    #[query]
-    pub fn get_strategy_address(&self, index: u32) -> Option<String> {
-        STRATEGY_STATE.with(|data| {
-            data.borrow()
-                .get(&index)
-                .and_then(|strategy| strategy.settings.eoa_pk.map(|pk| pk.to_string()))
+    /// * `Ok(())` - If the fee policy was successfully set
+    /// * `Err(ManagerError)` - If the strategy is not found or the policy is invalid
+    ///
+    /// # Access Control
+    ///
+    /// Only the canister controller can call this function.
+    #[update]
+    pub fn set_fee_policy(&self, key: u32, policy: FeePolicy) -> ManagerResult<()> {
+        audit("set_fee_policy", args_digest(&(&key, &policy)), || {
+            Guard::new("set_fee_policy").check()?;
+            policy.validate()?;
+            update_strategy(key, |strategy| {
+                strategy.settings.fee_policy(policy);
+            })
         })
     }
 
-    /// Facilitates ckETH<>Cycles arbitrage operations.
+    /// Sets how a strategy submits its rate adjustments and pays for them.
     ///
-    /// This function allows arbitrageurs to provide cycles to the canister in exchange
-    /// for discounted ckETH. The exchange includes:
-    /// - Verifying minimum cycle requirements
-    /// - Checking canister cycle balance thresholds
-    /// - Applying a discount on ckETH transfer
-    /// - Atomic swap execution with rollback on failure
+    /// With `UserOperation`, the rate adjustments are executed by a smart account owned by the
+    /// strategy's EOA and sponsored by a paymaster, so the EOA no longer pays for their gas.
+    /// The smart account must be allowed to set the rate of the batch manager.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The unique identifier of the strategy
+    /// * `backend` - The backend to use from the next submission on
     ///
     /// # Returns
     ///
-    /// * `Ok(SwapResponse)` - Details of the successful swap including:
-    ///   - accepted_cycles: Amount of cycles accepted
-    ///   - returning_ether: Amount of ckETH transferred
-    /// * `Err(ManagerError)` - If swap fails due to:
-    ///   - Insufficient cycles attached
-    ///   - Cycles balance above threshold
-    ///   - ckETH transfer failure
-    ///   - Lock acquisition failure
+    /// * `Ok(())` - If the backend was successfully set
+    /// * `Err(ManagerError)` - If the strategy is not found or the configuration is invalid
     ///
-    /// # Panics
+    /// # Access Control
     ///
-    /// Panics if the canister is not in a functional state.
+    /// Only the canister controller can call this function.
     #[update]
-    pub async fn swap_cketh(&self, receiver: Principal) -> ManagerResult<SwapResponse> {
-        assert!(is_functional());
-
-        // Ensure the caller has attached enough cycles
-        if msg_cycles_available() < MINIMUM_ATTACHED_CYCLES {
-            return Err(ManagerError::Custom(format!(
-                "The attached cycles amount ({}) is less than the minimum accepted amount ({})",
-                msg_cycles_available(),
-                MINIMUM_ATTACHED_CYCLES
-            )));
-        }
-
-        let mut swap_lock = SwapLock::default();
-        swap_lock.lock()?;
-        check_threshold().await?;
-        transfer_cketh(receiver).await
-    }
-
-    #[query]
-    pub async fn get_ranked_providers_list(&self) -> ManagerResult<Vec<(i64, ProviderService)>> {
-        let providers = RPC_REPUTATIONS.with(|rpcs| rpcs.borrow().clone());
-
-        Ok(providers)
+    pub fn set_execution_backend(&self, key: u32, backend: ExecutionBackend) -> ManagerResult<()> {
+        audit(
+            "set_execution_backend",
+            args_digest(&(&key, &backend)),
+            || {
+                Guard::new("set_execution_backend").check()?;
+                if let ExecutionBackend::UserOperation(config) = &backend {
+                    config.validate()?;
+                }
+                let description = match &backend {
+                    ExecutionBackend::Eoa => "transactions of the EOA".to_string(),
+                    ExecutionBackend::UserOperation(config) => {
+                        format!("UserOperations of {}", config.smart_account)
+                    }
+                };
+                update_strategy(key, |strategy| {
+                    strategy.settings.execution_backend(backend);
+                })?;
+                JournalCollection::open(Some(key)).append_note(
+                    Ok(()),
+                    LogType::Info,
+                    format!("The rate adjustments are now submitted as {}.", description),
+                );
+                Ok(())
+            },
+        )
     }
 
-    /// Retrieves recent system logs up to specified depth.
+    /// Sets the `IRMRegistry` contract the confirmed rate adjustments of a strategy are
+    /// recorded in.
     ///
-    /// Returns the most recent journal collections containing logs of:
-    /// - Strategy executions
-    /// - Rate adjustments
-    /// - Recharge operations
-    /// - Provider reputation changes
+    /// Each successful rate adjustment is then recorded with a `recordAdjustment` transaction
+    /// from the strategy's EOA, sent at the start of the run that observes its receipt.
     ///
     /// # Arguments
     ///
-    /// * `depth` - Number of most recent journal collections to return
+    /// * `key` - The unique identifier of the strategy
+    /// * `registry` - Address of the registry contract, `None` stops recording
+    ///
+    /// # Access Control
+    ///
+    /// Only the canister controller can call this function.
+    #[update]
+    pub fn set_registry(&self, key: u32, registry: Option<String>) -> ManagerResult<()> {
+        audit("set_registry", args_digest(&(&key, &registry)), || {
+            Guard::new("set_registry").check()?;
+            let registry = registry
+                .map(|registry| parse_address("registry", &registry, false))
+                .transpose()?;
+            update_strategy(key, |strategy| {
+                strategy.settings.registry(registry);
+            })?;
+            JournalCollection::open(None).append_note(
+                Ok(()),
+                LogType::Info,
+                match registry {
+                    Some(registry) => format!(
+                        "Strategy {} now records its rate adjustments in registry {}.",
+                        key, registry
+                    ),
+                    None => format!("Strategy {} no longer records its rate adjustments.", key),
+                },
+            );
+            Ok(())
+        })
+    }
+
+    /// Sets the decision logic a strategy uses to pick new rates.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The unique identifier of the strategy
+    /// * `rate_strategy` - The decision logic to use from the next run on
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the rate strategy was successfully set
+    /// * `Err(ManagerError)` - If the strategy is not found or the parameters are invalid
+    ///
+    /// # Access Control
+    ///
+    /// Only the canister controller can call this function.
+    #[update]
+    pub fn set_rate_strategy(
+        &self,
+        key: u32,
+        rate_strategy: RateStrategyKind,
+    ) -> ManagerResult<()> {
+        audit(
+            "set_rate_strategy",
+            args_digest(&(&key, &rate_strategy)),
+            || {
+                Guard::new("set_rate_strategy").check()?;
+                rate_strategy.validate()?;
+                update_strategy(key, |strategy| {
+                    strategy.settings.rate_strategy(rate_strategy);
+                })?;
+                JournalCollection::open(None).append_note(
+                    Ok(()),
+                    LogType::Info,
+                    format!(
+                        "Strategy {} now uses the {:?} rate strategy.",
+                        key, rate_strategy
+                    ),
+                );
+                Ok(())
+            },
+        )
+    }
+
+    /// Sets the increment and rounding of the rates proposed for a strategy.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The unique identifier of the strategy
+    /// * `rate_granularity` - Increment above the threshold trove and optional rounding step,
+    ///   both scaled by 1e18 (1 bps is `100_000_000_000_000`)
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the granularity was successfully set
+    /// * `Err(ManagerError)` - If the strategy is not found or the values are out of bounds
+    ///
+    /// # Access Control
+    ///
+    /// Only the canister controller can call this function.
+    #[update]
+    pub fn set_rate_granularity(
+        &self,
+        key: u32,
+        rate_granularity: RateGranularity,
+    ) -> ManagerResult<()> {
+        audit(
+            "set_rate_granularity",
+            args_digest(&(&key, &rate_granularity)),
+            || {
+                Guard::new("set_rate_granularity").check()?;
+                rate_granularity.validate()?;
+                update_strategy(key, |strategy| {
+                    strategy.settings.rate_granularity(rate_granularity);
+                })?;
+                JournalCollection::open(None).append_note(
+                    Ok(()),
+                    LogType::Info,
+                    format!(
+                        "Strategy {} now uses the rate granularity {:?}.",
+                        key, rate_granularity
+                    ),
+                );
+                Ok(())
+            },
+        )
+    }
+
+    /// Sets the gas spend budget of a strategy.
+    ///
+    /// A strategy whose EOA spent more than the budget on transaction fees over the last
+    /// 30 days is paused until the spend falls back within the budget.
+    ///
+    /// # Arguments
+    /// * `key` - Unique identifier of the strategy
+    /// * `gas_budget` - Maximum fees in wei per 30 days, `None` removes the budget
+    ///
+    /// # Access Control
+    ///
+    /// Only the canister controller can call this function.
+    #[update]
+    pub fn set_gas_budget(&self, key: u32, gas_budget: Option<Nat>) -> ManagerResult<()> {
+        audit("set_gas_budget", args_digest(&(&key, &gas_budget)), || {
+            Guard::new("set_gas_budget").check()?;
+            let gas_budget = gas_budget.map(nat_to_u128).transpose()?;
+            update_strategy(key, |strategy| {
+                strategy.settings.gas_budget(gas_budget);
+            })?;
+            JournalCollection::open(None).append_note(
+                Ok(()),
+                LogType::Info,
+                format!(
+                    "Strategy {} now has the gas budget {:?} wei.",
+                    key, gas_budget
+                ),
+            );
+            Ok(())
+        })
+    }
+
+    /// Sets the upfront fee budget of a strategy.
+    ///
+    /// A rate adjustment whose maximum upfront fee would bring the fees paid over the last
+    /// 30 days above the budget is skipped.
+    ///
+    /// # Arguments
+    /// * `key` - Unique identifier of the strategy
+    /// * `upfront_fee_budget` - Maximum upfront fees per 30 days, `None` removes the budget
+    ///
+    /// # Access Control
+    ///
+    /// Only the canister controller can call this function.
+    #[update]
+    pub fn set_upfront_fee_budget(
+        &self,
+        key: u32,
+        upfront_fee_budget: Option<Nat>,
+    ) -> ManagerResult<()> {
+        audit(
+            "set_upfront_fee_budget",
+            args_digest(&(&key, &upfront_fee_budget)),
+            || {
+                Guard::new("set_upfront_fee_budget").check()?;
+                let upfront_fee_budget =
+                    upfront_fee_budget.as_ref().map(nat_to_u256).transpose()?;
+                update_strategy(key, |strategy| {
+                    strategy.settings.upfront_fee_budget(upfront_fee_budget);
+                })?;
+                JournalCollection::open(None).append_note(
+                    Ok(()),
+                    LogType::Info,
+                    format!(
+                        "Strategy {} now has the upfront fee budget {:?}.",
+                        key, upfront_fee_budget
+                    ),
+                );
+                Ok(())
+            },
+        )
+    }
+
+    /// Sets the rate guard of a strategy.
+    ///
+    /// A new rate that deviates from the latest rate by more than `max_rate_delta_bps`, or
+    /// exceeds `max_rate_bps`, is held back and journaled as `SafetyTriggered` until a
+    /// controller acknowledges it with `acknowledge_safety_trip`.
+    ///
+    /// # Arguments
+    /// * `key` - Unique identifier of the strategy
+    /// * `guard` - Bounds in basis points, each unbounded if `None`
+    ///
+    /// # Access Control
+    ///
+    /// Only the canister controller can call this function.
+    #[update]
+    pub fn set_rate_guard(&self, key: u32, guard: RateGuard) -> ManagerResult<()> {
+        audit("set_rate_guard", args_digest(&(&key, &guard)), || {
+            Guard::new("set_rate_guard").check()?;
+            update_strategy(key, |strategy| {
+                strategy.settings.rate_guard(guard);
+            })?;
+            JournalCollection::open(None).append_note(
+                Ok(()),
+                LogType::Info,
+                format!("Strategy {} now has the rate guard {:?}.", key, guard),
+            );
+            Ok(())
+        })
+    }
+
+    /// Lets the next run of a strategy submit its rate even if it trips the rate guard.
+    ///
+    /// The acknowledgment is used once, by the next run whose rate is out of bounds, and is
+    /// dropped by a run whose rate is back within bounds.
+    ///
+    /// # Returns
+    /// * `Err(ManagerError::NonExistentValue)` - If the strategy doesn't exist or its rate
+    ///   guard has not tripped
+    ///
+    /// # Access Control
+    ///
+    /// Only the canister controller can call this function.
+    #[update]
+    pub fn acknowledge_safety_trip(&self, key: u32) -> ManagerResult<()> {
+        audit("acknowledge_safety_trip", args_digest(&key), || {
+            Guard::new("acknowledge_safety_trip").check()?;
+            let trip = update_strategy(key, |strategy| {
+                strategy.data.safety_trip.as_mut().map(|trip| {
+                    trip.acknowledged = true;
+                    trip.clone()
+                })
+            })?
+            .ok_or(ManagerError::NonExistentValue)?;
+            JournalCollection::open(Some(key)).append_note(
+                Ok(()),
+                LogType::SafetyTriggered,
+                format!(
+                    "A controller acknowledged the held back rate {} ({}). The next run may submit out of bounds.",
+                    trip.new_rate, trip.reason
+                ),
+            );
+            Ok(())
+        })
+    }
+
+    /// Pauses a strategy.
+    ///
+    /// A paused strategy skips its executions and rate adjustment resubmissions, while the
+    /// other strategies keep running. Its lock, data, and pending transactions are kept.
+    ///
+    /// # Arguments
+    /// * `key` - Unique identifier of the strategy
+    ///
+    /// # Access Control
+    ///
+    /// Only the canister controller can call this function.
+    #[update]
+    pub fn pause_strategy(&self, key: u32) -> ManagerResult<()> {
+        audit("pause_strategy", args_digest(&key), || {
+            Guard::new("pause_strategy").check()?;
+            update_strategy(key, |strategy| {
+                strategy.settings.enabled(false);
+            })?;
+            JournalCollection::open(Some(key)).append_note(
+                Ok(()),
+                LogType::Info,
+                format!("Strategy {} is paused.", key),
+            );
+            Ok(())
+        })
+    }
+
+    /// Resumes a strategy paused by `pause_strategy`.
+    ///
+    /// The strategy is executed again from its next scheduled execution, in simulation-only
+    /// mode for the first `warmup_runs` runs (see `strategy::warmup`).
+    ///
+    /// # Arguments
+    /// * `key` - Unique identifier of the strategy
+    ///
+    /// # Returns
+    /// * `Err(ManagerError::Locked)` - If the strategy is running, as the run would overwrite
+    ///   the warm-up when it finishes
+    ///
+    /// # Access Control
+    ///
+    /// Only the canister controller can call this function.
+    #[update]
+    pub fn resume_strategy(&self, key: u32) -> ManagerResult<()> {
+        audit("resume_strategy", args_digest(&key), || {
+            Guard::new("resume_strategy").check()?;
+            upgrade::ensure_not_in_flight(key, time() / 1_000_000_000)?;
+            let warmup = update_strategy(key, |strategy| {
+                strategy.settings.enabled(true);
+                strategy.data.warmup = Warmup::start(WarmupReason::Resumed);
+                strategy.data.warmup.clone()
+            })?;
+            JournalCollection::open(Some(key)).append_note(
+                Ok(()),
+                LogType::Info,
+                match warmup {
+                    Some(warmup) => format!(
+                        "Strategy {} is resumed and warms up for {} runs.",
+                        key, warmup.required_runs
+                    ),
+                    None => format!("Strategy {} is resumed.", key),
+                },
+            );
+            Ok(())
+        })
+    }
+
+    /// Ends the warm-up of a strategy, which goes live from its next run.
+    ///
+    /// # Arguments
+    /// * `key` - Unique identifier of the strategy
+    ///
+    /// # Returns
+    /// * `Err(ManagerError::Locked)` - If the strategy is running, as the run would restore
+    ///   the warm-up when it finishes
+    ///
+    /// # Access Control
+    ///
+    /// Only the canister controller can call this function.
+    #[update]
+    pub fn confirm_warmup(&self, key: u32) -> ManagerResult<()> {
+        audit("confirm_warmup", args_digest(&key), || {
+            Guard::new("confirm_warmup").check()?;
+            upgrade::ensure_not_in_flight(key, time() / 1_000_000_000)?;
+            let warmup =
+                update_strategy(key, |strategy| strategy.data.warmup.take())?.ok_or_else(|| {
+                    ManagerError::Custom("The strategy is not warming up.".to_string())
+                })?;
+            JournalCollection::open(Some(key)).append_note(
+                Ok(()),
+                LogType::Info,
+                format!(
+                    "The warm-up was confirmed with {} of {} runs remaining. The strategy is live from its next run.",
+                    warmup.remaining_runs, warmup.required_runs
+                ),
+            );
+            Ok(())
+        })
+    }
+
+    /// Retires a strategy.
+    ///
+    /// Removes the strategy from the state, unregisters its trove manager if no other
+    /// strategy uses it, cancels its execution timer, and moves its journal to the archive
+    /// returned by `get_retired_strategies`, ending with a final `ExecutionResult` log.
+    /// Running strategies and strategies with pending transactions are rejected.
+    ///
+    /// # Arguments
+    /// * `key` - Unique identifier of the strategy
+    ///
+    /// # Access Control
+    ///
+    /// Only the canister controller can call this function.
+    #[update]
+    pub fn remove_strategy(&self, key: u32) -> ManagerResult<()> {
+        audit("remove_strategy", args_digest(&key), || {
+            Guard::new("remove_strategy").check()?;
+            retire_strategy(key)?;
+            Ok(())
+        })
+    }
+
+    /// Returns the archives of the strategies retired with a key, oldest first.
+    ///
+    /// # Arguments
+    /// * `key` - Key the strategies were minted with
+    #[query]
+    pub fn get_retired_strategies(&self, key: u32) -> Vec<RetiredStrategy> {
+        retired_strategies(key)
+    }
+
+    /// Simulates a run of a strategy without submitting a transaction.
+    ///
+    /// Runs the execution context and rate decision of a strategy at the latest block and
+    /// returns the would-be new rate, its upfront fee, the debt in front, and the condition
+    /// checks the strategy evaluated. Nothing is locked, signed, submitted, or stored.
+    ///
+    /// # Arguments
+    /// * `key` - Unique identifier of the strategy
+    /// * `target_min` - Minimum target to simulate with, to validate a change before it goes
+    ///   live; `None` uses the stored one
+    ///
+    /// # Access Control
+    ///
+    /// Only the canister controller can call this function.
+    #[update]
+    pub async fn simulate_strategy(
+        &self,
+        key: u32,
+        target_min: Option<Nat>,
+    ) -> ManagerResult<StrategySimulation> {
+        let digest = args_digest(&(key, &target_min));
+        let result: ManagerResult<StrategySimulation> = async {
+            Guard::new("simulate_strategy").operational().check()?;
+            let target_min = target_min.as_ref().map(nat_to_u256).transpose()?;
+            if let Some(target_min) = target_min {
+                if target_min == U256::ZERO || target_min > scale() {
+                    return Err(ManagerError::Custom(
+                        "The minimum target must be above zero and at most 100%.".to_string(),
+                    ));
+                }
+            }
+            simulate_strategy(key, target_min).await
+        }
+        .await;
+        record_admin_action("simulate_strategy", digest, &result);
+        result
+    }
+
+    /// Previews the rate adjustment transaction of a strategy.
+    ///
+    /// Runs the read-only part of a strategy execution at the latest block and returns the
+    /// `setNewRate` calldata that would be signed, along with the target contract and a gas
+    /// estimation. Nothing is locked, signed, submitted, or stored.
+    ///
+    /// # Arguments
+    /// * `key` - Unique identifier of the strategy
+    ///
+    /// # Access Control
+    ///
+    /// Only the canister controller can call this function.
+    #[update]
+    pub async fn preview_adjustment_calldata(&self, key: u32) -> ManagerResult<AdjustmentPreview> {
+        let digest = args_digest(&key);
+        let result: ManagerResult<AdjustmentPreview> = async {
+            Guard::new("preview_adjustment_calldata")
+                .operational()
+                .check()?;
+            preview_adjustment(key).await
+        }
+        .await;
+        record_admin_action("preview_adjustment_calldata", digest, &result);
+        result
+    }
+
+    /// Takes a snapshot of the market a strategy positions its batch in.
+    ///
+    /// Reads the sorted troves at the latest block like a run does, and returns them with the
+    /// debt in front of the batch and the debt in front the strategy targets, so that delegates
+    /// can verify the positioning against independent sources. Nothing is locked, signed,
+    /// submitted, or stored.
+    ///
+    /// # Arguments
+    /// * `key` - Unique identifier of the strategy
+    ///
+    /// # Access Control
+    ///
+    /// Anyone can call this function, within `SNAPSHOT_RATE_LIMIT_CALLS` calls per
+    /// `SNAPSHOT_RATE_LIMIT_WINDOW` seconds.
+    #[update]
+    pub async fn get_market_snapshot(&self, key: u32) -> ManagerResult<MarketSnapshot> {
+        Guard::new("get_market_snapshot")
+            .operational()
+            .rate_limit(SNAPSHOT_RATE_LIMIT_CALLS, SNAPSHOT_RATE_LIMIT_WINDOW)
+            .check()?;
+        market_snapshot(key).await
+    }
+
+    /// Cancels the oldest pending transaction of a strategy.
+    ///
+    /// Sends a zero-value self-transfer with the same nonce and bumped fees, so that the
+    /// pending transaction can no longer be included. The strategy is locked during the
+    /// cancellation, and its latest rate is refreshed from the batch afterwards.
+    ///
+    /// # Arguments
+    /// * `key` - Unique identifier of the strategy
+    ///
+    /// # Returns
+    /// The transaction pool record of the cancellation
+    ///
+    /// # Access Control
+    ///
+    /// Only the canister controller can call this function.
+    #[update]
+    pub async fn cancel_pending_tx(&self, key: u32) -> ManagerResult<TxRecord> {
+        let digest = args_digest(&key);
+        let result: ManagerResult<TxRecord> = async {
+            Guard::new("cancel_pending_tx").operational().check()?;
+            cancel_pending_transaction(key).await
+        }
+        .await;
+        record_admin_action("cancel_pending_tx", digest, &result);
+        result
+    }
+
+    /// Starts all system timers for strategy execution and maintenance tasks.
+    ///
+    /// This function initializes recurring timers for:
+    /// - Hourly strategy execution cycles
+    /// - Chain head polling for strategies triggered by new blocks
+    /// - Daily ckETH balance monitoring and recharging
+    /// - Daily provider reputation management and cleanup
+    /// - Daily halt condition evaluation
+    /// - Daily digest of the past day's activity
+    ///
+    /// Each strategy immediately executes once when timers start, then begins its hourly cycle.
+    /// The recharge cycle monitors both cycle and ckETH balances, triggering recharge
+    /// operations when thresholds are reached.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If all timers were successfully started
+    /// * `Err(ManagerError)` - If timer initialization fails
+    ///
+    /// # Access Control
+    ///
+    /// Only the canister controller can call this function.
+    ///
+    /// # Note
+    ///
+    /// This should typically be called once after all strategies are configured
+    /// and before the canister is made immutable.
+    #[update]
+    pub async fn start_timers(&self) -> ManagerResult<()> {
+        let digest = args_digest(&());
+        let result: ManagerResult<()> = async {
+            Guard::new("start_timers").check()?;
+            start_system_timers();
+
+            Ok(())
+        }
+        .await;
+        record_admin_action("start_timers", digest, &result);
+        result
+    }
+
+    /// Removes the trove managers that no strategy uses from the manager registry.
+    ///
+    /// # Returns
+    ///
+    /// The addresses of the removed entries.
+    ///
+    /// # Access Control
+    ///
+    /// Only the canister controller can call this function.
+    #[update]
+    pub fn prune_unused_managers(&self) -> ManagerResult<Vec<String>> {
+        audit("prune_unused_managers", args_digest(&()), || {
+            Guard::new("prune_unused_managers").check()?;
+            let removed: Vec<String> = managers::prune_unused_managers()
+                .iter()
+                .map(|manager| manager.to_string())
+                .collect();
+            JournalCollection::open(None).append_note(
+                Ok(()),
+                LogType::Info,
+                format!("Pruned unused trove managers: {:?}", removed),
+            );
+            Ok(removed)
+        })
+    }
+
+    /// Adds a trove manager to the registry, e.g. of a branch that no strategy manages.
+    ///
+    /// The system debt and the unbacked debt are summed over all registered managers.
+    ///
+    /// # Access Control
+    ///
+    /// Only the canister controller can call this function.
+    #[update]
+    pub fn add_manager(&self, manager: String) -> ManagerResult<()> {
+        audit("add_manager", args_digest(&manager), || {
+            Guard::new("add_manager").check()?;
+            let manager = parse_address("manager", &manager, false)?;
+            managers::add_manager(manager)?;
+            JournalCollection::open(None).append_note(
+                Ok(()),
+                LogType::Info,
+                format!("Trove manager {} was added to the registry.", manager),
+            );
+            Ok(())
+        })
+    }
+
+    /// Removes a trove manager from the registry.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the manager was removed
+    /// * `Err(ManagerError)` - If a strategy still uses the manager, or it is not registered
+    ///
+    /// # Access Control
+    ///
+    /// Only the canister controller can call this function.
+    #[update]
+    pub fn remove_manager(&self, manager: String) -> ManagerResult<()> {
+        audit("remove_manager", args_digest(&manager), || {
+            Guard::new("remove_manager").check()?;
+            let manager = parse_address("manager", &manager, false)?;
+            managers::remove_manager(manager)?;
+            JournalCollection::open(None).append_note(
+                Ok(()),
+                LogType::Info,
+                format!("Trove manager {} was removed from the registry.", manager),
+            );
+            Ok(())
+        })
+    }
+
+    /// Returns the registered trove managers.
+    #[query]
+    pub fn list_managers(&self) -> Vec<String> {
+        managers::managers()
+            .iter()
+            .map(|manager| manager.to_string())
+            .collect()
+    }
+
+    /// Pauses the canister for an upgrade and reports whether in-flight work has drained.
+    ///
+    /// While paused, no new strategy runs, recharges, cleanups, digests, or swaps are
+    /// started, while in-flight runs and resubmissions are left to finish. The call can be
+    /// repeated until `ready` is `true`, at which point a snapshot of the persisted state is
+    /// taken and the canister can be upgraded. The pause survives the upgrade.
+    ///
+    /// # Access Control
+    ///
+    /// Only the canister controller can call this function.
+    #[update]
+    pub fn prepare_for_upgrade(&self) -> ManagerResult<UpgradeReadiness> {
+        audit("prepare_for_upgrade", args_digest(&()), || {
+            Guard::new("prepare_for_upgrade").check()?;
+            let readiness = upgrade::prepare_for_upgrade(time() / 1_000_000_000);
+            JournalCollection::open(None).append_note(
+                Ok(()),
+                LogType::Info,
+                format!(
+                    "Preparing for an upgrade. Ready: {}, executing strategies: {:?}, pending resubmissions: {:?}.",
+                    readiness.ready, readiness.locked_strategies, readiness.pending_retries
+                ),
+            );
+            Ok(readiness)
+        })
+    }
+
+    /// Lifts the upgrade pause and returns the snapshot taken before the upgrade.
+    ///
+    /// The timers are cleared by an upgrade, so they are started again if they are not
+    /// running in this instance. Fails, and the canister stays paused, if the restored state
+    /// does not match the snapshot.
+    ///
+    /// # Access Control
+    ///
+    /// Only the canister controller can call this function.
+    #[update]
+    pub async fn resume_after_upgrade(&self) -> ManagerResult<Option<StateSnapshot>> {
+        let digest = args_digest(&());
+        let result: ManagerResult<Option<StateSnapshot>> = async {
+            Guard::new("resume_after_upgrade").check()?;
+            let now = time() / 1_000_000_000;
+            let snapshot = upgrade::resume_after_upgrade(now)?;
+            JournalCollection::open(None).append_note(
+                Ok(()),
+                LogType::Info,
+                format!(
+                    "Resumed after the upgrade. State before: {:?}, state now: {:?}.",
+                    snapshot,
+                    upgrade::snapshot(now)
+                ),
+            );
+            if !TIMERS_STARTED.with(|started| started.get()) {
+                self.start_timers().await?;
+            }
+            Ok(snapshot)
+        }
+        .await;
+        record_admin_action("resume_after_upgrade", digest, &result);
+        result
+    }
+
+    /// Switches between timer-based and keeper-based strategy scheduling.
+    ///
+    /// In `External` mode, the hourly strategy timers stay idle and strategies are only
+    /// executed through `execute_strategy` by keepers holding an execution permit.
+    ///
+    /// # Access Control
+    ///
+    /// Only the canister controller can call this function.
+    #[update]
+    pub fn set_scheduling_mode(&self, mode: SchedulingMode) -> ManagerResult<()> {
+        audit("set_scheduling_mode", args_digest(&mode), || {
+            Guard::new("set_scheduling_mode").check()?;
+            scheduler::set_scheduling_mode(mode);
+            JournalCollection::open(None).append_note(
+                Ok(()),
+                LogType::Info,
+                format!("Scheduling mode was set to {:?}.", mode),
+            );
+            Ok(())
+        })
+    }
+
+    /// Sets what triggers the executions of a strategy while the canister schedules them with timers.
+    ///
+    /// # Arguments
+    /// * `key` - Unique identifier of the strategy
+    /// * `trigger` - The hourly timer, or a number of new blocks with a minimum interval in seconds
+    ///
+    /// # Access Control
+    ///
+    /// Only the canister controller can call this function.
+    #[update]
+    pub fn set_execution_trigger(&self, key: u32, trigger: ExecutionTrigger) -> ManagerResult<()> {
+        audit(
+            "set_execution_trigger",
+            args_digest(&(&key, &trigger)),
+            || {
+                Guard::new("set_execution_trigger").check()?;
+                trigger.validate()?;
+                update_strategy(key, |strategy| {
+                    strategy.settings.execution_trigger(trigger);
+                })?;
+                JournalCollection::open(None).append_note(
+                    Ok(()),
+                    LogType::Info,
+                    format!("Strategy {} is now triggered by {:?}.", key, trigger),
+                );
+                Ok(())
+            },
+        )
+    }
+
+    /// Returns the current strategy scheduling mode.
+    #[query]
+    pub fn get_scheduling_mode(&self) -> SchedulingMode {
+        scheduling_mode()
+    }
+
+    /// Grants or replaces the execution permit of an external keeper.
+    ///
+    /// # Arguments
+    ///
+    /// * `keeper` - Principal of the keeper canister or user
+    /// * `permit` - Strategies the keeper may execute and the minimum interval between executions
+    ///
+    /// # Access Control
+    ///
+    /// Only the canister controller can call this function.
+    #[update]
+    pub fn grant_execution_permit(
+        &self,
+        keeper: Principal,
+        permit: ExecutionPermit,
+    ) -> ManagerResult<()> {
+        audit(
+            "grant_execution_permit",
+            args_digest(&(&keeper, &permit)),
+            || {
+                Guard::new("grant_execution_permit").check()?;
+                scheduler::grant_permit(keeper, permit);
+                JournalCollection::open(None).append_note(
+                    Ok(()),
+                    LogType::Info,
+                    format!("Execution permit was granted to {}.", keeper),
+                );
+                Ok(())
+            },
+        )
+    }
+
+    /// Revokes the execution permit of an external keeper.
+    ///
+    /// # Access Control
+    ///
+    /// Only the canister controller can call this function.
+    #[update]
+    pub fn revoke_execution_permit(&self, keeper: Principal) -> ManagerResult<()> {
+        audit("revoke_execution_permit", args_digest(&keeper), || {
+            Guard::new("revoke_execution_permit").check()?;
+            scheduler::revoke_permit(keeper)?;
+            JournalCollection::open(None).append_note(
+                Ok(()),
+                LogType::Info,
+                format!("Execution permit of {} was revoked.", keeper),
+            );
+            Ok(())
+        })
+    }
+
+    /// Returns all external keepers with their execution permits.
+    #[query]
+    pub fn get_execution_permits(&self) -> Vec<(Principal, ExecutionPermit)> {
+        scheduler::list_permits()
+    }
+
+    /// Executes a strategy on behalf of an external keeper.
+    ///
+    /// The execution is started in the background; its outcome is recorded in the journal
+    /// and in the run summary.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the execution was started
+    /// * `Err(ManagerError)` - If:
+    ///   - The canister is halted or not in external scheduling mode
+    ///   - The caller holds no permit for the strategy
+    ///   - The strategy was executed too recently
+    ///   - The strategy does not exist
+    #[update]
+    pub fn execute_strategy(&self, key: u32) -> ManagerResult<()> {
+        Guard::new("execute_strategy").operational().check()?;
+        if !STRATEGY_STATE.with(|strategies| strategies.borrow().contains_key(&key)) {
+            return Err(ManagerError::NonExistentValue);
+        }
+        scheduler::consume_permit_now(caller(), key)?;
+        spawn(run_strategy(key));
+        Ok(())
+    }
+
+    /// Starts a strategy execution immediately, so that operators can force a rate review
+    /// after a market event instead of waiting for the next scheduled run.
+    ///
+    /// The execution is started in the background; its outcome is recorded in the journal
+    /// and in the run summary.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the execution was started
+    /// * `Err(ManagerError)` - If:
+    ///   - The canister is halted or paused for an upgrade
+    ///   - The strategy does not exist
+    ///   - The strategy is running (`ManagerError::Locked`)
+    ///
+    /// # Access Control
+    ///
+    /// Only the canister controller can call this function.
+    #[update]
+    pub fn execute_strategy_now(&self, key: u32) -> ManagerResult<()> {
+        audit("execute_strategy_now", args_digest(&key), || {
+            Guard::new("execute_strategy_now").operational().check()?;
+            let strategy = STRATEGY_STATE
+                .with(|strategies| strategies.borrow().get(&key).cloned())
+                .ok_or(ManagerError::NonExistentValue)?;
+            if upgrade::is_in_flight(&strategy, time() / 1_000_000_000) {
+                return Err(ManagerError::Locked);
+            }
+            spawn(run_strategy(key));
+            JournalCollection::open(Some(key)).append_note(
+                Ok(()),
+                LogType::Info,
+                "A controller triggered an immediate execution of the strategy.",
+            );
+            Ok(())
+        })
+    }
+
+    /// Retrieves current data for all strategies in the system.
+    ///
+    /// Returns information about each strategy including:
+    /// - Contract addresses (trove manager, batch manager)
+    /// - Lock status and timing
+    /// - Current interest rate
+    /// - Target minimums
+    /// - EOA public key
+    /// - Last update timestamp
+    ///
+    /// # Returns
+    ///
+    /// A vector of StableStrategyQuery structs containing current strategy states.
+    /// Returns an empty vector if no strategies exist.
+    #[query]
+    pub fn get_strategies(&self) -> ManagerResult<Vec<StableStrategyQuery>> {
+        STRATEGY_STATE.with(|vector_data| {
+            let binding = vector_data.borrow();
+            let values = binding.values();
+            if values.len() == 0 {
+                return Ok(vec![]);
+            }
+            values
+                .map(|strategy| StableStrategyQuery::try_from(strategy.clone()))
+                .collect()
+        })
+    }
+
+    /// Returns the settings, data, and lock state of a strategy.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Some(StableStrategyQuery))` - The full view of the strategy, including the
+    ///   sorted troves, collateral index, lock timestamps, and last successful exit
+    /// * `Ok(None)` - If the strategy doesn't exist
+    /// * `Err(ManagerError)` - If a value could not be converted
+    #[query]
+    pub fn get_strategy(&self, key: u32) -> ManagerResult<Option<StableStrategyQuery>> {
+        STRATEGY_STATE.with(|strategies| {
+            strategies
+                .borrow()
+                .get(&key)
+                .cloned()
+                .map(StableStrategyQuery::try_from)
+                .transpose()
+        })
+    }
+
+    /// Returns the settings, data, and lock state of all strategies, ordered by key.
+    ///
+    /// Same view as `get_strategies`, in a stable order for frontends listing the strategies.
+    #[query]
+    pub fn get_strategies_full(&self) -> ManagerResult<Vec<StableStrategyQuery>> {
+        let mut strategies: Vec<StableStrategy> =
+            STRATEGY_STATE.with(|state| state.borrow().values().cloned().collect());
+        strategies.sort_unstable_by_key(|strategy| strategy.settings.key);
+        strategies
+            .into_iter()
+            .map(StableStrategyQuery::try_from)
+            .collect()
+    }
+
+    /// Returns the public track record of a strategy for trove owners choosing a batch manager.
+    ///
+    /// The report contains the current rate, protection band, last adjustment, adjustment
+    /// count, and health of the strategy, without internal details such as nonces or
+    /// derivation paths.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Some(PublicStrategyReport))` - The report of the strategy
+    /// * `Ok(None)` - If the strategy doesn't exist
+    /// * `Err(ManagerError)` - If a value could not be converted
+    #[query]
+    pub fn get_public_strategy_report(
+        &self,
+        key: u32,
+    ) -> ManagerResult<Option<PublicStrategyReport>> {
+        let halt = HALT_STATE.with(|state| state.borrow().status.clone());
+        let now = time() / 1_000_000_000;
+        STRATEGY_STATE.with(|strategies| {
+            strategies
+                .borrow()
+                .get(&key)
+                .map(|strategy| PublicStrategyReport::new(strategy, &halt, now))
+                .transpose()
+        })
+    }
+
+    /// Exports the key metadata of all strategies for cold-storage documentation and disaster recovery.
+    ///
+    /// Returns the threshold ECDSA key name, and for each strategy its derivation path,
+    /// EOA address, and freshly fetched chain nonce. No private key material is exposed,
+    /// as threshold ECDSA keys cannot be exported.
+    ///
+    /// # Access Control
+    ///
+    /// Only the canister controller can call this function.
+    #[update]
+    pub async fn export_key_metadata(&self) -> ManagerResult<KeyMetadataExport> {
+        let digest = args_digest(&());
+        let result: ManagerResult<KeyMetadataExport> = async {
+            Guard::new("export_key_metadata").check()?;
+            Ok(key_metadata::export_key_metadata(id(), time() / 1_000_000_000).await)
+        }
+        .await;
+        record_admin_action("export_key_metadata", digest, &result);
+        result
+    }
+
+    /// Verifies that the derivation path of a strategy still yields its EOA.
+    ///
+    /// Signs a fixed test payload with the strategy's derivation path, recovers the signer, and
+    /// compares it and the derived public key with the stored EOA, so that a tECDSA
+    /// misconfiguration is caught before transactions fail on-chain.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The unique identifier of the strategy
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(SignerReport)` - The addresses, and the issues found if they differ
+    /// * `Err(ManagerError)` - If the strategy is not found
+    ///
+    /// # Access Control
+    ///
+    /// Only the canister controller can call this function.
+    #[update]
+    pub async fn verify_signer(&self, key: u32) -> ManagerResult<SignerReport> {
+        let digest = args_digest(&key);
+        let result: ManagerResult<SignerReport> = async {
+            Guard::new("verify_signer").check()?;
+            key_metadata::verify_signer(key).await
+        }
+        .await;
+        record_admin_action("verify_signer", digest, &result);
+        result
+    }
+
+    /// Returns the strategies whose configurations overlap.
+    ///
+    /// Two strategies sharing a batch manager, or a manager and collateral index pair,
+    /// would fight each other's rate adjustments.
+    #[query]
+    pub fn get_config_conflicts(&self) -> ManagerResult<Vec<ConfigConflict>> {
+        config_conflicts()
+    }
+
+    /// Retrieves the EOA address associated with a specific strategy.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - The unique identifier of the strategy
+    ///
+    /// # Returns
+    ///
+    /// * `Some(String)` - The EOA address if the strategy exists and has an assigned EOA
+    /// * `None` - If strategy doesn't exist or has no EOA assigned
+    #[query]
+    pub fn get_strategy_address(&self, index: u32) -> Option<String> {
+        STRATEGY_STATE.with(|data| {
+            data.borrow()
+                .get(&index)
+                .and_then(|strategy| strategy.settings.eoa_pk.map(|pk| pk.to_string()))
+        })
+    }
+
+    /// Retrieves the most recent outbound transactions of a strategy's EOA.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The unique identifier of the strategy
+    /// * `depth` - Number of most recent transactions to return
+    ///
+    /// # Returns
+    ///
+    /// Transaction records ordered from oldest to newest, including their current status.
+    #[query]
+    pub fn get_transactions(&self, key: u32, depth: u64) -> Vec<TxRecord> {
+        latest_transactions(key, depth)
+    }
+
+    /// Retrieves the confirmed rate adjustments of all strategies by inclusion block.
+    ///
+    /// # Arguments
+    ///
+    /// * `from` - First block number of the range (inclusive)
+    /// * `to` - Last block number of the range (inclusive)
+    ///
+    /// # Returns
+    ///
+    /// Up to `MAX_ADJUSTMENTS_PER_QUERY` adjustments ordered by block number. When the limit
+    /// is reached, the next page can be queried from the block of the last returned entry.
+    #[query]
+    pub fn get_adjustments_between_blocks(&self, from: u64, to: u64) -> Vec<ConfirmedAdjustment> {
+        adjustments_between_blocks(from, to)
+    }
+
+    /// Returns the latest confirmed rate changes of a strategy, newest first.
+    ///
+    /// # Arguments
+    /// * `key` - Key of the strategy
+    /// * `depth` - Number of records to return, capped at `MAX_RATE_HISTORY_PER_QUERY`
+    #[query]
+    pub fn get_rate_history(&self, key: u32, depth: u64) -> Vec<RateAdjustmentRecord> {
+        rate_history(key, depth)
+    }
+
+    /// Returns the daily positioning samples of a strategy over the last
+    /// `POSITIONING_HISTORY_DAYS` days, oldest first.
+    ///
+    /// Each sample holds the debt in front of the batch, the debt in front targeted by the
+    /// strategy, and the batch rate, as observed by the last execution of the day.
+    ///
+    /// # Arguments
+    /// * `key` - Key of the strategy
+    #[query]
+    pub fn get_positioning_history(&self, key: u32) -> Vec<PositioningSample> {
+        positioning_history(key)
+    }
+
+    /// Registers an EVM RPC canister that replaces the previous one for all strategies.
+    ///
+    /// # Arguments
+    /// * `principal` - Principal of the EVM RPC canister
+    /// * `effective_from` - Timestamp in seconds from which the canister is used, not in the past
+    ///   and after the one of every registered canister
+    /// * `module_hash` - Hex-encoded module hash of the canister, if known
+    /// * `version` - Release version of the canister, if known
+    ///
+    /// # Access Control
+    ///
+    /// Only the canister controller can call this function.
+    #[update]
+    pub fn register_rpc_canister(
+        &self,
+        principal: Principal,
+        effective_from: u64,
+        module_hash: Option<String>,
+        version: Option<String>,
+    ) -> ManagerResult<()> {
+        audit(
+            "register_rpc_canister",
+            args_digest(&(&principal, &effective_from, &module_hash, &version)),
+            || {
+                Guard::new("register_rpc_canister").check()?;
+                let now = time() / 1_000_000_000;
+                rpc_registry::register_rpc_canister(
+                    RpcCanisterRecord {
+                        principal,
+                        effective_from,
+                        module_hash,
+                        version,
+                        registered_at: now,
+                    },
+                    now,
+                )?;
+                JournalCollection::open(None).append_note(
+                    Ok(()),
+                    LogType::Info,
+                    format!(
+                        "The EVM RPC canister {} is used by all strategies from {}.",
+                        principal, effective_from
+                    ),
+                );
+                Ok(())
+            },
+        )
+    }
+
+    /// Returns every registered EVM RPC canister, oldest first.
+    ///
+    /// Strategies use the principal they were minted with until the first effective-from time.
+    #[query]
+    pub fn get_rpc_canisters(&self) -> Vec<RpcCanisterRecord> {
+        rpc_registry::rpc_canisters()
+    }
+
+    /// Retrieves everything recorded about a single strategy run.
+    ///
+    /// # Arguments
+    ///
+    /// * `run_id` - The unique identifier of the run
+    ///
+    /// # Returns
+    ///
+    /// The run summary, the transactions submitted during the run, and its journal collections,
+    /// or `None` if the run is unknown or was pruned.
+    #[query]
+    pub fn get_run(&self, run_id: u64) -> Option<RunReport> {
+        runs::get_run(run_id)
+    }
+
+    /// Returns the inputs of the most recent target percentage derivations of a strategy.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The unique identifier of the strategy
+    /// * `limit` - Maximum number of derivations to return
+    ///
+    /// # Returns
+    ///
+    /// Run IDs and their derivations, newest first.
+    #[query]
+    pub fn get_target_derivations(&self, key: u32, limit: u64) -> Vec<(u64, TargetDerivation)> {
+        runs::target_derivations(key, limit)
+    }
+
+    /// Returns the decompositions of the maximum debt redeemable against the collateral of a
+    /// strategy's branch, computed by its most recent runs.
+    ///
+    /// Each entry shows whether the 1% floor replaced the proportional share of the system debt.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The unique identifier of the strategy
+    /// * `limit` - Maximum number of decompositions to return
+    ///
+    /// # Returns
+    ///
+    /// Decompositions with their run IDs, newest first.
+    #[query]
+    pub fn get_redeemability(&self, key: u32, limit: u64) -> Vec<RedeemabilityDecomposition> {
+        runs::redeemability(key, limit)
+    }
+
+    /// Returns the expected and realized debt in front of the most recent verified rate adjustments of a strategy.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The unique identifier of the strategy
+    /// * `limit` - Maximum number of checks to return
+    ///
+    /// # Returns
+    ///
+    /// IDs of the runs that submitted the adjustments and their checks, newest first.
+    #[query]
+    pub fn get_positioning_checks(&self, key: u32, limit: u64) -> Vec<(u64, PositioningCheck)> {
+        runs::positioning_checks(key, limit)
+    }
+
+    /// Returns the activity counters of the canister.
+    #[query]
+    pub fn get_metrics(&self) -> Metrics {
+        metrics::get_metrics()
+    }
+
+    /// Serves the activity counters in the Prometheus text format at `/metrics`, and the
+    /// journal as NDJSON or CSV at `/logs?strategy=K&depth=N&format=ndjson|csv`.
+    #[query]
+    pub fn http_request(&self, request: HttpRequest) -> HttpGatewayResponse {
+        prometheus::http_request(&request, canister_balance128())
+    }
+
+    /// Facilitates ckETH<>Cycles arbitrage operations.
+    ///
+    /// This function allows arbitrageurs to provide cycles to the canister in exchange
+    /// for discounted ckETH. The exchange includes:
+    /// - Verifying minimum cycle requirements
+    /// - Checking canister cycle balance thresholds
+    /// - Applying a discount on ckETH transfer
+    /// - Atomic swap execution with rollback on failure
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(SwapResponse)` - Details of the successful swap including:
+    ///   - accepted_cycles: Amount of cycles accepted
+    ///   - returning_ether: Amount of ckETH transferred
+    /// * `Err(ManagerError)` - If swap fails due to:
+    ///   - Insufficient cycles attached
+    ///   - Cycles balance above threshold
+    ///   - ckETH transfer failure
+    ///   - Lock acquisition failure
+    ///   - The canister being halted
+    #[update]
+    pub async fn swap_cketh(&self, receiver: Principal) -> ManagerResult<SwapResponse> {
+        self.swap_cketh_v2(receiver).await.map(SwapResponse::from)
+    }
+
+    /// Same as `swap_cketh`, returning the breakdown of the rate, the ledger block index of
+    /// the transfer, and the ID under which the swap is kept in the swap history.
+    #[update]
+    pub async fn swap_cketh_v2(&self, receiver: Principal) -> ManagerResult<SwapResponseV2> {
+        Guard::new("swap_cketh_v2")
+            .operational()
+            .rate_limit(SWAP_RATE_LIMIT_CALLS, SWAP_RATE_LIMIT_WINDOW)
+            .check()?;
+
+        // Ensure the caller has attached enough cycles
+        if msg_cycles_available() < MINIMUM_ATTACHED_CYCLES {
+            return Err(ManagerError::Custom(format!(
+                "The attached cycles amount ({}) is less than the minimum accepted amount ({})",
+                msg_cycles_available(),
+                MINIMUM_ATTACHED_CYCLES
+            )));
+        }
+
+        let mut swap_lock = SwapLock::default();
+        swap_lock.lock()?;
+        check_threshold().await?;
+        transfer_cketh(receiver).await
+    }
+
+    /// Returns the reputation ranking of the read pool providers.
+    /// Returns the aggregate financial state of the charger subsystem.
+    ///
+    /// The ckETH and EOA balances are the last values observed while recharging, so that
+    /// this query does not call the ckETH ledger or the EVM RPC canister.
+    #[query]
+    pub fn get_treasury(&self) -> Treasury {
+        treasury::treasury(Nat::from(canister_balance128()), time() / 1_000_000_000)
+    }
+
+    /// Returns up to `limit` of the most recent ckETH<>Cycles swaps, newest first.
+    #[query]
+    pub fn get_swaps(&self, limit: u64) -> Vec<SwapResponseV2> {
+        treasury::swaps(limit)
+    }
+
+    /// Returns the cycles balance, the swap threshold, the estimated burn rate, and the
+    /// projected timestamp in seconds from which `swap_cketh` accepts swaps.
+    #[query]
+    pub fn next_swap_window(&self) -> SwapWindow {
+        treasury::swap_window(
+            canister_balance128(),
+            metrics::cycles_burn_rate(),
+            time() / 1_000_000_000,
+        )
+    }
+
+    /// Switches the ETH/CXDR rate used to price swaps to a fixed rate, or back to the XRC.
+    ///
+    /// # Arguments
+    /// * `rate` - Fixed ETH/CXDR rate, or `None` to use the exchange rate canister again
+    ///
+    /// # Access Control
+    ///
+    /// Only the canister controller can call this function.
+    #[update]
+    pub fn set_fixed_rate(&self, rate: Option<u64>) -> ManagerResult<()> {
+        audit("set_fixed_rate", args_digest(&rate), || {
+            Guard::new("set_fixed_rate").check()?;
+            let source = rate_source::set_fixed_rate(rate, time() / 1_000_000_000)?;
+            JournalCollection::open(None).append_note(
+                Ok(()),
+                LogType::Info,
+                format!("The ETH/CXDR rate source was set to {:?}.", source),
+            );
+            Ok(())
+        })
+    }
+
+    /// Returns the source of the ETH/CXDR rate used to price swaps.
+    #[query]
+    pub fn get_rate_source(&self) -> RateSource {
+        rate_source::rate_source()
+    }
+
+    /// Returns the cached ckETH and cycles balances with the time they were observed at.
+    ///
+    /// The balances are refreshed by a timer every `BALANCE_REFRESH_INTERVAL` seconds, and
+    /// whenever the recharge cycle or a swap reads them.
+    #[query]
+    pub fn get_cached_balances(&self) -> CachedBalances {
+        treasury::cached_balances(time() / 1_000_000_000)
+    }
+
+    /// Derives the funding EOA and stores it, so that it sends every ckETH deposit instead of
+    /// the strategy EOAs.
+    ///
+    /// # Arguments
+    ///
+    /// * `rpc_principal` - Principal ID of the EVM RPC canister of the home chain
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(String)` - The address of the funding EOA, to be funded with ETH
+    /// * `Err(ManagerError)` - If the funding EOA is already initialized, or the key derivation
+    ///   or the nonce query fails
+    ///
+    /// # Access Control
+    ///
+    /// Only the canister controller can call this function.
+    #[update]
+    pub async fn init_funding_eoa(&self, rpc_principal: Principal) -> ManagerResult<String> {
+        let digest = args_digest(&rpc_principal);
+        let result: ManagerResult<String> = async {
+            Guard::new("init_funding_eoa").check()?;
+            if let Some(funding) = funding::funding_eoa() {
+                return Err(ManagerError::Custom(format!(
+                    "The funding EOA is already initialized at {}.",
+                    funding.address
+                )));
+            }
+
+            let public_key_bytes =
+                get_canister_public_key(ecdsa_key_id(), None, funding::funding_derivation_path())
+                    .await?;
+            let address = string_to_address(pubkey_bytes_to_address(&public_key_bytes)?)?;
+            let nonce = get_nonce(&Service(rpc_principal, CHAIN_ID), address).await?;
+
+            funding::initialize_funding_eoa(
+                address,
+                rpc_principal,
+                nonce.to::<u64>(),
+                time() / 1_000_000_000,
+            )?;
+            JournalCollection::open(None).append_note(
+                Ok(()),
+                LogType::Info,
+                format!("The funding EOA was initialized at {}.", address),
+            );
+            Ok(address.to_string())
+        }
+        .await;
+        record_admin_action("init_funding_eoa", digest, &result);
+        result
+    }
+
+    /// Returns the funding EOA, with its last observed ETH balance, if initialized.
+    ///
+    /// The balance is refreshed with the cached balances.
+    #[query]
+    pub fn get_funding_eoa(&self) -> Option<FundingEoa> {
+        funding::funding_eoa()
+    }
+
+    /// Sets the HTTPS webhook the daily digest is posted to, or clears it with `None`.
+    ///
+    /// # Access Control
+    ///
+    /// Only the canister controller can call this function.
+    #[update]
+    pub fn set_digest_webhook(&self, url: Option<String>) -> ManagerResult<()> {
+        audit("set_digest_webhook", args_digest(&url), || {
+            Guard::new("set_digest_webhook").check()?;
+            let enabled = url.is_some();
+            digest::set_webhook_url(url)?;
+            JournalCollection::open(None).append_note(
+                Ok(()),
+                LogType::Info,
+                if enabled {
+                    "The daily digest webhook was set."
+                } else {
+                    "The daily digest webhook was cleared."
+                },
+            );
+            Ok(())
+        })
+    }
+
+    /// Sets the HTTPS webhook the alerts and notices are posted to, e.g. a paging endpoint,
+    /// or clears it with `None`.
+    ///
+    /// Without an alert webhook, the alerts and notices are posted to the digest webhook.
+    ///
+    /// # Access Control
+    ///
+    /// Only the canister controller can call this function.
+    #[update]
+    pub fn set_alert_webhook(&self, url: Option<String>) -> ManagerResult<()> {
+        audit("set_alert_webhook", args_digest(&url), || {
+            Guard::new("set_alert_webhook").check()?;
+            let enabled = url.is_some();
+            digest::set_alert_webhook_url(url)?;
+            JournalCollection::open(None).append_note(
+                Ok(()),
+                LogType::Info,
+                if enabled {
+                    "The alert webhook was set."
+                } else {
+                    "The alert webhook was cleared."
+                },
+            );
+            Ok(())
+        })
+    }
+
+    /// Transform function of the webhook HTTPS outcalls.
+    #[query]
+    pub fn transform_webhook_response(&self, args: TransformArgs) -> HttpResponse {
+        digest::transform_webhook_response(args)
+    }
+
+    /// Transform function of the bundler HTTPS outcalls.
+    #[query]
+    pub fn transform_bundler_response(&self, args: TransformArgs) -> HttpResponse {
+        user_operation::transform_bundler_response(args)
+    }
+
+    #[query]
+    pub async fn get_ranked_providers_list(&self) -> ManagerResult<Vec<(i64, ProviderService)>> {
+        let providers = fetch_provider_list(ProviderPool::Read);
+
+        Ok(providers)
+    }
+
+    /// Returns the reputation ranking of the given provider pool.
+    #[query]
+    pub fn get_provider_pool(&self, pool: ProviderPool) -> Vec<(i64, ProviderService)> {
+        fetch_provider_list(pool)
+    }
+
+    /// Replaces the members of a provider pool and resets their reputations.
+    ///
+    /// The read pool serves `eth_call` consensus and trove pagination, while the write pool
+    /// serves transaction submission and nonce queries.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the pool was updated
+    /// * `Err(ManagerError)` - If the list contains duplicates or fewer than `PROVIDER_COUNT` providers
+    ///
+    /// # Access Control
+    ///
+    /// Only the canister controller can call this function.
+    #[update]
+    pub fn set_provider_pool(
+        &self,
+        pool: ProviderPool,
+        providers: Vec<ProviderService>,
+    ) -> ManagerResult<()> {
+        audit(
+            "set_provider_pool",
+            args_digest(&(&pool, &providers)),
+            || {
+                Guard::new("set_provider_pool").check()?;
+                set_pool_members(pool, providers)?;
+                JournalCollection::open(None).append_note(
+                    Ok(()),
+                    LogType::Info,
+                    format!("Members of the {:?} provider pool were replaced.", pool),
+                );
+                Ok(())
+            },
+        )
+    }
+
+    /// Removes a provider from the rotation of both pools immediately, regardless of its reputation.
+    ///
+    /// Meant for providers known to be serving corrupted data. The provider stays disabled
+    /// across upgrades until `enable_provider` is called.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the provider was disabled
+    /// * `Err(ManagerError)` - If the provider is already disabled, or a pool would be left
+    ///   with fewer than `PROVIDER_COUNT` enabled providers
+    ///
+    /// # Access Control
+    ///
+    /// Only the canister controller can call this function.
+    #[update]
+    pub fn disable_provider(&self, provider: ProviderService) -> ManagerResult<()> {
+        audit("disable_provider", args_digest(&provider), || {
+            Guard::new("disable_provider").check()?;
+            providers::disable_provider(provider, time() / 1_000_000_000)?;
+            JournalCollection::open(None).append_note(
+                Ok(()),
+                LogType::ProviderReputationChange,
+                format!("Provider {:?} was disabled.", provider),
+            );
+            Ok(())
+        })
+    }
+
+    /// Restores a disabled provider to the rotation of both pools.
+    ///
+    /// # Access Control
+    ///
+    /// Only the canister controller can call this function.
+    #[update]
+    pub fn enable_provider(&self, provider: ProviderService) -> ManagerResult<()> {
+        audit("enable_provider", args_digest(&provider), || {
+            Guard::new("enable_provider").check()?;
+            providers::enable_provider(provider)?;
+            JournalCollection::open(None).append_note(
+                Ok(()),
+                LogType::ProviderReputationChange,
+                format!("Provider {:?} was enabled.", provider),
+            );
+            Ok(())
+        })
+    }
+
+    /// Returns the providers disabled by a controller.
+    #[query]
+    pub fn get_disabled_providers(&self) -> Vec<DisabledProvider> {
+        providers::disabled_providers()
+    }
+
+    /// Registers a custom JSON-RPC endpoint in a provider pool.
+    ///
+    /// API keys can be passed in the URL or the headers; they are stored in the canister
+    /// state and never returned. Once a pool has `PROVIDER_COUNT` custom providers, they serve
+    /// it instead of the built-in providers while their reputation is at least as high.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the provider was added
+    /// * `Err(ManagerError)` - If the name is taken, the URL is not HTTPS or already in the
+    ///   pool, or `MAX_CUSTOM_PROVIDERS` providers are registered
+    ///
+    /// # Access Control
+    ///
+    /// Only the canister controller can call this function.
+    #[update]
+    pub fn add_custom_provider(
+        &self,
+        name: String,
+        pool: ProviderPool,
+        api: RpcApi,
+    ) -> ManagerResult<()> {
+        audit(
+            "add_custom_provider",
+            args_digest(&(&name, &pool, &api)),
+            || {
+                Guard::new("add_custom_provider").check()?;
+                custom_providers::add_custom_provider(CustomProvider {
+                    name: name.clone(),
+                    pool,
+                    api,
+                    added_at: time() / 1_000_000_000,
+                })?;
+                JournalCollection::open(None).append_note(
+                    Ok(()),
+                    LogType::Info,
+                    format!("Custom provider {} was added to the {:?} pool.", name, pool),
+                );
+                Ok(())
+            },
+        )
+    }
+
+    /// Removes a custom JSON-RPC endpoint from its provider pool and from the rotation.
+    ///
+    /// # Access Control
+    ///
+    /// Only the canister controller can call this function.
+    #[update]
+    pub fn remove_custom_provider(&self, name: String) -> ManagerResult<()> {
+        audit("remove_custom_provider", args_digest(&name), || {
+            Guard::new("remove_custom_provider").check()?;
+            let provider = custom_providers::remove_custom_provider(&name)?;
+            JournalCollection::open(None).append_note(
+                Ok(()),
+                LogType::Info,
+                format!(
+                    "Custom provider {} was removed from the {:?} pool.",
+                    name, provider.pool
+                ),
+            );
+            Ok(())
+        })
+    }
+
+    /// Returns the custom providers with their reputations, without their credentials.
+    #[query]
+    pub fn get_custom_providers(&self) -> Vec<CustomProviderView> {
+        custom_providers::custom_provider_views()
+    }
+
+    /// Retrieves recent system logs up to specified depth.
+    ///
+    /// Returns the most recent journal collections containing logs of:
+    /// - Strategy executions
+    /// - Rate adjustments
+    /// - Recharge operations
+    /// - Provider reputation changes
+    ///
+    /// # Arguments
+    ///
+    /// * `depth` - Number of most recent journal collections to return
     ///
     /// # Returns
     ///
-    /// * `Ok(Vec<StableJournalCollection>)` - Vector of journal collections
+    /// * `Ok(Vec<JournalCollectionQuery>)` - Vector of journal collections
     /// * `Err(ManagerError)` - If log retrieval fails
     #[query]
-    pub async fn get_logs(&self, depth: u64) -> ManagerResult<Vec<StableJournalCollection>> {
-        let entries = JOURNAL.with(|m| m.borrow().iter().collect::<Vec<StableJournalCollection>>());
+    pub async fn get_logs(&self, depth: u64) -> ManagerResult<Vec<JournalCollectionQuery>> {
+        let mut entries = JOURNAL.with(|m| m.borrow().iter().collect::<Vec<_>>());
+
+        Ok(to_query(
+            entries.split_off(entries.len().saturating_sub(depth as usize)),
+        ))
+    }
+
+    /// Walks the journal from oldest to newest, one page at a time.
+    ///
+    /// Every stored collection has a sequence number that does not change when older
+    /// collections are pruned, so the journal can be followed while new entries arrive.
+    ///
+    /// # Arguments
+    ///
+    /// * `cursor` - Sequence number to start from, as returned in `next_cursor`;
+    ///   `None` starts at the oldest collection
+    /// * `limit` - Maximum number of collections to return, capped at `MAX_LOGS_PER_PAGE`
+    ///
+    /// # Returns
+    ///
+    /// The collections of the page and the cursor of the next one.
+    #[query]
+    pub fn get_logs_paginated(&self, cursor: Option<u64>, limit: u64) -> LogPage {
+        journal_page(cursor, limit.min(MAX_LOGS_PER_PAGE))
+    }
 
-        Ok(entries[entries.len().saturating_sub(depth as usize)..].to_vec())
+    /// Retrieves a single journal collection by its position in the journal.
+    ///
+    /// Unlike the depth-based queries, positions do not shift as new collections are
+    /// appended, so archivers can fetch collections incrementally and in parallel.
+    /// Positions only shift when old collections are pruned, which shows in the total.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - Position of the collection, starting at the oldest one
+    ///
+    /// # Returns
+    ///
+    /// The collection, if the position is in range, and the number of collections.
+    ///
+    /// # Access Control
+    ///
+    /// Anyone can call this function, up to `LOG_RATE_LIMIT_CALLS` times per
+    /// `LOG_RATE_LIMIT_WINDOW` seconds.
+    #[update]
+    pub fn get_log(&self, index: u64) -> ManagerResult<IndexedLog> {
+        Guard::new("get_log")
+            .allow_halted()
+            .rate_limit(LOG_RATE_LIMIT_CALLS, LOG_RATE_LIMIT_WINDOW)
+            .check()?;
+        Ok(journal_collection_at(index))
     }
 
     #[query]
     pub async fn get_recharge_logs(
         &self,
         depth: u64,
-    ) -> ManagerResult<Vec<StableJournalCollection>> {
-        let entries: Vec<StableJournalCollection> = JOURNAL.with(|n| {
+    ) -> ManagerResult<Vec<JournalCollectionQuery>> {
+        let mut entries: Vec<_> = JOURNAL.with(|n| {
             n.borrow()
                 .iter()
                 .filter(|collection| collection.entries[0].log_type == LogType::Recharge)
                 .collect()
         });
 
-        Ok(entries[entries.len().saturating_sub(depth as usize)..].to_vec())
+        Ok(to_query(
+            entries.split_off(entries.len().saturating_sub(depth as usize)),
+        ))
     }
 
     /// Retrieves logs for a specific strategy up to specified depth.
@@ -418,27 +2721,35 @@ impl IrManager {
     ///
     /// * `depth` - Number of most recent journal collections to return
     /// * `strategy_key` - Unique identifier of the strategy
+    /// * `filter` - Optional log type and time range the entries must match.
+    ///   Collections without any matching entry are skipped, and do not count towards `depth`.
     ///
     /// # Returns
     ///
-    /// * `Ok(Vec<StableJournalCollection>)` - Vector of filtered journal collections
+    /// * `Ok(Vec<JournalCollectionQuery>)` - Vector of filtered journal collections
     /// * `Err(ManagerError)` - If log retrieval fails
     #[query]
     pub async fn get_strategy_logs(
         &self,
         depth: u64,
         strategy_key: u32,
-    ) -> ManagerResult<Vec<StableJournalCollection>> {
+        filter: Option<LogFilter>,
+    ) -> ManagerResult<Vec<JournalCollectionQuery>> {
+        let filter = filter.unwrap_or_default();
+
         // Filter the journal entries by strategy_key
-        let entries: Vec<StableJournalCollection> = JOURNAL.with(|n| {
+        let mut entries: Vec<_> = JOURNAL.with(|n| {
             n.borrow()
                 .iter()
                 .filter(|entry| entry.strategy == Some(strategy_key))
+                .filter_map(|collection| filter.apply(collection))
                 .collect()
         });
 
         // Limit the results to the desired depth
-        Ok(entries[entries.len().saturating_sub(depth as usize)..].to_vec())
+        Ok(to_query(
+            entries.split_off(entries.len().saturating_sub(depth as usize)),
+        ))
     }
 
     /// Returns the current halt status of the canister.
@@ -446,18 +2757,435 @@ impl IrManager {
     /// The halt status indicates whether the canister is:
     /// - Functional: Operating normally
     /// - HaltingInProgress: In 7-day warning period before halt
-    /// - Halted: Permanently stopped due to system conditions
+    /// - Halted: Stopped due to system conditions until a controller calls `resume_from_halt`
     ///
     /// # Returns
     ///
-    /// Current Halt struct containing status and optional message
+    /// Current Halt struct containing status, optional message and condition, and the
+    /// seconds remaining until a halt in progress takes effect
     #[query]
     pub fn halt_status(&self) -> Halt {
-        HALT_STATE.with(|state| state.borrow().clone())
+        halt::halt_status(time() / 1_000_000_000)
+    }
+
+    /// Cancels a halt that is in progress, returning the canister to `Functional`.
+    ///
+    /// A completed halt cannot be cancelled.
+    ///
+    /// # Access Control
+    ///
+    /// Only the canister controller can call this function.
+    #[update]
+    pub fn cancel_halt(&self) -> ManagerResult<()> {
+        audit("cancel_halt", args_digest(&()), || {
+            Guard::new("cancel_halt").check()?;
+            let cancelled = halt::halt_status(time() / 1_000_000_000);
+            halt::cancel_halt()?;
+            JournalCollection::open(None).append_note(
+                Ok(()),
+                LogType::Info,
+                format!(
+                    "The halt scheduled for {:?} was cancelled (condition: {:?}).",
+                    cancelled.status, cancelled.condition
+                ),
+            );
+            Ok(())
+        })
+    }
+
+    /// Lifts a completed halt, returning the canister to `Functional`.
+    ///
+    /// The recovery is only accepted if at least one strategy has an EOA, the ckETH balance
+    /// is at or above the ckETH threshold, and the cycles balance is at or above the critical
+    /// floor. The strategies restart with a warm-up, and the timers are started if they are
+    /// not running.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the canister recovered
+    /// * `Err(ManagerError)` - If the canister is not halted, a precondition does not hold,
+    ///   or the ckETH ledger cannot be reached
+    ///
+    /// # Access Control
+    ///
+    /// Only the canister controller can call this function.
+    #[update]
+    pub async fn resume_from_halt(&self) -> ManagerResult<()> {
+        let digest = args_digest(&());
+        let result: ManagerResult<()> = async {
+            Guard::new("resume_from_halt").allow_halted().check()?;
+            let halted = halt::halt_status(time() / 1_000_000_000);
+            let cketh_balance = fetch_cketh_balance().await?;
+            treasury::record_cketh_balance(cketh_balance.clone(), time() / 1_000_000_000);
+            halt::resume_from_halt(
+                &cketh_balance,
+                canister_balance128(),
+                time() / 1_000_000_000,
+            )?;
+            JournalCollection::open(None).append_note(
+                Ok(()),
+                LogType::Info,
+                format!(
+                    "The canister recovered from the halt {:?} (condition: {:?}).",
+                    halted.status, halted.condition
+                ),
+            );
+            if !TIMERS_STARTED.with(|started| started.get()) {
+                self.start_timers().await?;
+            }
+            Ok(())
+        }
+        .await;
+        record_admin_action("resume_from_halt", digest, &result);
+        result
+    }
+
+    /// Sets the canister method notified of every halt status transition, or clears it with `None`.
+    ///
+    /// The method is called with a one-way call and a single `HaltTransition` argument, so
+    /// that e.g. a governance canister can react to a halt countdown. The transitions are
+    /// also posted to the digest webhook, independently of this setting.
+    ///
+    /// # Access Control
+    ///
+    /// Only the canister controller can call this function.
+    #[update]
+    pub fn set_halt_callback(&self, callback: Option<HaltCallback>) -> ManagerResult<()> {
+        audit("set_halt_callback", args_digest(&callback), || {
+            Guard::new("set_halt_callback").check()?;
+            halt::set_halt_callback(callback.clone())?;
+            JournalCollection::open(None).append_note(
+                Ok(()),
+                LogType::Info,
+                match callback {
+                    Some(callback) => format!(
+                        "Halt status transitions are now pushed to `{}` of {}.",
+                        callback.method, callback.canister
+                    ),
+                    None => "The halt callback was cleared.".to_string(),
+                },
+            );
+            Ok(())
+        })
+    }
+
+    /// Returns the canister method notified of the halt status transitions, if any.
+    #[query]
+    pub fn get_halt_callback(&self) -> Option<HaltCallback> {
+        halt::halt_callback()
+    }
+
+    /// Returns up to `limit` privileged calls and their outcomes, newest first.
+    ///
+    /// Both successful and rejected calls are recorded, with a digest of their arguments.
+    #[query]
+    pub fn get_admin_actions(&self, limit: u64) -> Vec<AdminAction> {
+        access_log::admin_actions(limit)
+    }
+
+    /// Returns the operational status of the canister, including the journal integrity counters.
+    #[query]
+    pub fn get_status(&self) -> Status {
+        status::status()
+    }
+
+    /// Returns the health of every subsystem: the cycles and cached ckETH balances, the halt
+    /// status, the lock state and last successful execution of each strategy, and the
+    /// provider reputations.
+    #[query]
+    pub fn health(&self) -> HealthReport {
+        health::health(Nat::from(canister_balance128()), time() / 1_000_000_000)
+    }
+
+    /// Returns the lock contention statistics of every strategy.
+    #[query]
+    pub fn get_lock_stats(&self) -> Vec<(u32, LockStats)> {
+        contention::lock_stats(time() / 1_000_000_000)
+    }
+
+    /// Registers a custom halt or alert trigger, evaluated with the daily halt status update.
+    ///
+    /// # Arguments
+    ///
+    /// * `condition` - Predicate template and its parameters
+    /// * `action` - Whether the trigger raises an alert or schedules a halt
+    ///
+    /// # Returns
+    ///
+    /// The ID of the trigger.
+    ///
+    /// # Access Control
+    ///
+    /// Only the canister controller can call this function.
+    #[update]
+    pub fn add_halt_trigger(
+        &self,
+        condition: TriggerCondition,
+        action: TriggerAction,
+    ) -> ManagerResult<u64> {
+        audit(
+            "add_halt_trigger",
+            args_digest(&(&condition, &action)),
+            || {
+                Guard::new("add_halt_trigger")
+                    .idempotent(args_digest(&(&condition, &action)))
+                    .check()?;
+                let id = triggers::add_trigger(condition.clone(), action)?;
+                JournalCollection::open(None).append_note(
+                    Ok(()),
+                    LogType::Info,
+                    format!(
+                        "Registered custom trigger {}: {:?} -> {:?}.",
+                        id, condition, action
+                    ),
+                );
+                Ok(id)
+            },
+        )
+    }
+
+    /// Removes a custom halt or alert trigger.
+    ///
+    /// # Access Control
+    ///
+    /// Only the canister controller can call this function.
+    #[update]
+    pub fn remove_halt_trigger(&self, id: u64) -> ManagerResult<()> {
+        audit("remove_halt_trigger", args_digest(&id), || {
+            Guard::new("remove_halt_trigger").check()?;
+            triggers::remove_trigger(id)?;
+            JournalCollection::open(None).append_note(
+                Ok(()),
+                LogType::Info,
+                format!("Removed custom trigger {}.", id),
+            );
+            Ok(())
+        })
+    }
+
+    /// Returns the custom halt and alert triggers with their evaluation state.
+    #[query]
+    pub fn get_halt_triggers(&self) -> Vec<(u64, HaltTrigger)> {
+        triggers::list_triggers()
+    }
+
+    /// Overrides the value of a runtime flag.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Name of a flag declared by the canister
+    /// * `value` - New value, which must have the same type as the flag's default
+    ///
+    /// # Access Control
+    ///
+    /// Only the canister controller can call this function.
+    #[update]
+    pub fn set_flag(&self, name: String, value: FlagValue) -> ManagerResult<()> {
+        audit("set_flag", args_digest(&(&name, &value)), || {
+            Guard::new("set_flag").check()?;
+            flags::set_flag(&name, value.clone())?;
+            JournalCollection::open(None).append_note(
+                Ok(()),
+                LogType::Info,
+                format!("Flag {} was set to {:?}.", name, value),
+            );
+            Ok(())
+        })
+    }
+
+    /// Removes the override of a runtime flag, restoring its default value.
+    ///
+    /// # Access Control
+    ///
+    /// Only the canister controller can call this function.
+    #[update]
+    pub fn reset_flag(&self, name: String) -> ManagerResult<()> {
+        audit("reset_flag", args_digest(&name), || {
+            Guard::new("reset_flag").check()?;
+            flags::reset_flag(&name)?;
+            JournalCollection::open(None).append_note(
+                Ok(()),
+                LogType::Info,
+                format!("Flag {} was reset to its default value.", name),
+            );
+            Ok(())
+        })
+    }
+
+    /// Returns all runtime flags declared by the canister with their values in effect.
+    #[query]
+    pub fn get_flags(&self) -> Vec<FlagQuery> {
+        flags::list_flags()
+    }
+
+    /// Replaces the journal retention policy enforced during the daily cleanup.
+    ///
+    /// # Arguments
+    /// * `policy` - Maximum number, age in days, and estimated size in bytes of the kept collections
+    ///
+    /// # Access Control
+    ///
+    /// Only the canister controller can call this function.
+    #[update]
+    pub fn set_journal_retention(&self, policy: RetentionPolicy) -> ManagerResult<()> {
+        audit("set_journal_retention", args_digest(&policy), || {
+            Guard::new("set_journal_retention").check()?;
+            retention::set_journal_retention(policy)?;
+            JournalCollection::open(None).append_note(
+                Ok(()),
+                LogType::Info,
+                format!("The journal retention policy was set to {:?}.", policy),
+            );
+            Ok(())
+        })
+    }
+
+    /// Returns the journal retention policy enforced during the daily cleanup.
+    #[query]
+    pub fn get_journal_retention(&self) -> RetentionPolicy {
+        retention::journal_retention()
+    }
+
+    /// Replaces the thresholds and the discount of the ckETH<>Cycles arbitrage.
+    ///
+    /// # Arguments
+    /// * `config` - Discount percentage, ckETH threshold in wei, and cycles threshold
+    ///
+    /// # Access Control
+    ///
+    /// Only the canister controller can call this function.
+    #[update]
+    pub fn set_charger_config(&self, config: ChargerConfig) -> ManagerResult<()> {
+        audit("set_charger_config", args_digest(&config), || {
+            Guard::new("set_charger_config").check()?;
+            let previous = charger_config::set_charger_config(config.clone())?;
+            JournalCollection::open(None).append_note(
+                Ok(()),
+                LogType::Info,
+                format!(
+                    "The charger config was changed from {:?} to {:?}.",
+                    previous, config
+                ),
+            );
+            Ok(())
+        })
+    }
+
+    /// Returns the thresholds and the discount of the ckETH<>Cycles arbitrage.
+    #[query]
+    pub fn get_charger_config(&self) -> ChargerConfig {
+        charger_config::charger_config()
+    }
+
+    /// Replaces the name of the threshold ECDSA key the EOAs are derived from.
+    ///
+    /// The name can only change before the first strategy is minted and the funding EOA is
+    /// initialized, as the EOAs of another key are different addresses.
+    ///
+    /// # Arguments
+    /// * `name` - `key_1`, `test_key_1`, or `dfx_test_key`
+    ///
+    /// # Access Control
+    ///
+    /// Only the canister controller can call this function.
+    #[update]
+    pub fn set_ecdsa_key_name(&self, name: String) -> ManagerResult<()> {
+        audit("set_ecdsa_key_name", args_digest(&name), || {
+            Guard::new("set_ecdsa_key_name").check()?;
+            let previous = signer::set_ecdsa_key_name(name.clone())?;
+            JournalCollection::open(None).append_note(
+                Ok(()),
+                LogType::Info,
+                format!(
+                    "The ECDSA key name was changed from {} to {}.",
+                    previous, name
+                ),
+            );
+            Ok(())
+        })
+    }
+
+    /// Returns the name of the threshold ECDSA key the EOAs are derived from.
+    #[query]
+    pub fn get_ecdsa_key_name(&self) -> String {
+        ecdsa_key_name()
+    }
+
+    /// Returns the version, git commit, build timestamp, and enabled features of the running binary.
+    #[query]
+    pub fn get_build_info(&self) -> BuildInfo {
+        build_info()
+    }
+
+    /// Configures the canister from the init arguments of its installation, if any.
+    ///
+    /// The strategies are minted right after the installation, and the timers are started
+    /// once every strategy is set up if requested (see `install`). Invalid init arguments
+    /// reject the installation.
+    #[init]
+    pub fn init(&self, args: Option<InitArgs>) {
+        let Some(args) = args else {
+            return;
+        };
+        if let Err(err) = install::install(args, time() / 1_000_000_000) {
+            trap(&format!("Invalid init arguments: {:?}", err));
+        }
+        JournalCollection::open(None).append_note(
+            Ok(()),
+            LogType::Info,
+            format!("Installed {} with init arguments.", build_info()),
+        );
+    }
+
+    /// Saves the heap state to stable memory for the new binary.
+    ///
+    /// A state that cannot be saved rejects the upgrade, rather than losing the strategies.
+    #[pre_upgrade]
+    pub fn pre_upgrade(&self) {
+        if let Err(err) = upgrade::save_heap_state(time() / 1_000_000_000) {
+            trap(&format!("Failed to save the heap state: {:?}", err));
+        }
+    }
+
+    /// Restores the heap state saved by the previous binary, and journals the build
+    /// information of the new binary, so that behavioral changes can be correlated with
+    /// specific deployments.
+    ///
+    /// A saved state that cannot be restored rejects the upgrade. The journal of a version
+    /// without a memory manager is read before the memory manager takes over the stable
+    /// memory, and stored again in its region.
+    #[post_upgrade]
+    pub fn post_upgrade(&self) {
+        let legacy_journal = take_legacy_journal();
+        let restored = match upgrade::restore_heap_state() {
+            Ok(restored) => restored,
+            Err(err) => trap(&format!("Failed to restore the heap state: {:?}", err)),
+        };
+        halt::resume_halt_countdown(time() / 1_000_000_000);
+        restore_legacy_journal(legacy_journal);
+        backfill_journal_sequences();
+        migrate_journal_timestamps();
+        JournalCollection::open(None).append_note(
+            Ok(()),
+            LogType::Info,
+            format!(
+                "Upgraded to {}. Restored strategies: {:?}.",
+                build_info(),
+                restored
+            ),
+        );
+        if let Err(err) = ensure_not_paused() {
+            JournalCollection::open(None).append_note(
+                Err(err),
+                LogType::Info,
+                "The canister is paused for an upgrade and stays idle until it is resumed.",
+            );
+        }
     }
 
     #[update]
     pub async fn get_canister_status(&self) -> ManagerResult<CanisterStatusResponse> {
+        Guard::new("get_canister_status").allow_halted().check()?;
         let response: CanisterStatusResponse =
             extract_call_result(canister_status(CanisterIdRecord { canister_id: id() }).await)?;
         Ok(response)
@@ -0,0 +1,350 @@
+//! Chain Configuration
+//!
+//! Each strategy manages a Liquity V2 branch on one EVM chain. The home chain (`CHAIN_ID`,
+//! selected by the `mainnet`/`sepolia` feature) is served by the ranked provider pools, while
+//! other chains, e.g. L2 deployments on Arbitrum or Base, bring their own providers:
+//!
+//! ```plain
+//! mint_strategy(chain?) ──► ChainConfig::validate ──► register_chain ──► CHAINS (stable)
+//!                                                                          │
+//! Service(rpc principal, chain id)                                         │
+//!          │                                                               │
+//!          ▼                                                               ▼
+//!    home chain? ──yes──► ranked provider pools        no ──► CHAINS[chain id].rpc_services
+//!                         (threshold consensus)                 (equality consensus)
+//! ```
+//!
+//! Transactions are signed with the chain ID of their strategy, and ckETH is only minted from
+//! EOAs on chains with a ckETH helper contract. A chain stays registered while strategies use
+//! it; its providers cannot be changed in the meantime. The registered chains are kept in
+//! stable memory, so that the strategies keep reaching their chains across upgrades.
+
+use std::borrow::Cow;
+
+use candid::{CandidType, Decode, Encode};
+use evm_rpc_types::{RpcService, RpcServices};
+use ic_stable_structures::{storable::Bound, Storable};
+use serde::Deserialize;
+
+#[cfg(feature = "mainnet")]
+use crate::constants::CKETH_HELPER;
+use crate::{
+    constants::CHAIN_ID,
+    state::{CHAINS, STRATEGY_STATE},
+    utils::{
+        address::parse_address,
+        error::{ManagerError, ManagerResult},
+    },
+};
+
+/// Chain a strategy operates on
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq)]
+pub struct ChainConfig {
+    /// EIP-155 chain ID, also used to sign the transactions
+    pub chain_id: u64,
+    /// Providers of the chain, `None` on the home chain, which uses the ranked provider pools
+    pub rpc_services: Option<RpcServices>,
+    /// ckETH helper contract on the chain, `None` if ckETH cannot be minted from it
+    pub cketh_helper: Option<String>,
+}
+
+impl Default for ChainConfig {
+    fn default() -> Self {
+        Self {
+            chain_id: CHAIN_ID,
+            rpc_services: None,
+            #[cfg(feature = "mainnet")]
+            cketh_helper: Some(CKETH_HELPER.to_string()),
+            #[cfg(not(feature = "mainnet"))]
+            cketh_helper: None,
+        }
+    }
+}
+
+impl Storable for ChainConfig {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        Cow::Owned(Encode!(self).expect("Failed to encode a chain config."))
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).expect("Failed to decode a chain config.")
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+impl ChainConfig {
+    /// Returns `true` if the chain is the one the canister was built for.
+    pub fn is_home(&self) -> bool {
+        is_home_chain(self.chain_id)
+    }
+
+    /// Checks that the providers belong to the chain and that the helper address is valid.
+    ///
+    /// The home chain must not list providers, and any other chain must list at least one.
+    pub fn validate(&self) -> ManagerResult<()> {
+        match (&self.rpc_services, self.is_home()) {
+            (Some(_), true) => {
+                return Err(ManagerError::Custom(format!(
+                    "Chain {} is served by the provider pools and cannot list providers.",
+                    self.chain_id
+                )))
+            }
+            (None, false) => {
+                return Err(ManagerError::Custom(format!(
+                    "Chain {} requires a list of providers.",
+                    self.chain_id
+                )))
+            }
+            (Some(services), false) => {
+                if services_chain_id(services) != self.chain_id {
+                    return Err(ManagerError::Custom(format!(
+                        "The providers serve chain {} instead of chain {}.",
+                        services_chain_id(services),
+                        self.chain_id
+                    )));
+                }
+                if service_count(services) == 0 {
+                    return Err(ManagerError::Custom(format!(
+                        "The provider list of chain {} is empty.",
+                        self.chain_id
+                    )));
+                }
+            }
+            (None, true) => {}
+        }
+
+        if let Some(helper) = &self.cketh_helper {
+            parse_address("cketh_helper", helper, false)?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns `true` if the chain is the one the canister was built for.
+pub fn is_home_chain(chain_id: u64) -> bool {
+    chain_id == CHAIN_ID
+}
+
+/// Returns the chain ID the providers serve.
+fn services_chain_id(services: &RpcServices) -> u64 {
+    match services {
+        RpcServices::Custom { chain_id, .. } => *chain_id,
+        RpcServices::EthMainnet(_) => 1,
+        RpcServices::EthSepolia(_) => 11155111,
+        RpcServices::ArbitrumOne(_) => 42161,
+        RpcServices::BaseMainnet(_) => 8453,
+        RpcServices::OptimismMainnet(_) => 10,
+    }
+}
+
+/// Returns the number of explicitly listed providers.
+fn service_count(services: &RpcServices) -> usize {
+    match services {
+        RpcServices::Custom { services, .. } => services.len(),
+        RpcServices::EthMainnet(services) => services.as_ref().map_or(0, Vec::len),
+        RpcServices::EthSepolia(services) => services.as_ref().map_or(0, Vec::len),
+        RpcServices::ArbitrumOne(services)
+        | RpcServices::BaseMainnet(services)
+        | RpcServices::OptimismMainnet(services) => services.as_ref().map_or(0, Vec::len),
+    }
+}
+
+/// Returns the `n`-th listed provider, wrapping around, or `None` if none is listed.
+fn nth_service(services: &RpcServices, n: usize) -> Option<RpcService> {
+    let count = service_count(services);
+    if count == 0 {
+        return None;
+    }
+    let n = n % count;
+    match services {
+        RpcServices::Custom { services, .. } => services.get(n).cloned().map(RpcService::Custom),
+        RpcServices::EthMainnet(services) => services
+            .as_ref()
+            .and_then(|services| services.get(n).copied())
+            .map(RpcService::EthMainnet),
+        RpcServices::EthSepolia(services) => services
+            .as_ref()
+            .and_then(|services| services.get(n).copied())
+            .map(RpcService::EthSepolia),
+        RpcServices::ArbitrumOne(services) => services
+            .as_ref()
+            .and_then(|services| services.get(n).copied())
+            .map(RpcService::ArbitrumOne),
+        RpcServices::BaseMainnet(services) => services
+            .as_ref()
+            .and_then(|services| services.get(n).copied())
+            .map(RpcService::BaseMainnet),
+        RpcServices::OptimismMainnet(services) => services
+            .as_ref()
+            .and_then(|services| services.get(n).copied())
+            .map(RpcService::OptimismMainnet),
+    }
+}
+
+/// Registers the providers of a chain, so that requests for it can be routed.
+///
+/// Registering the home chain is a no-op. A chain that is already registered with other
+/// providers is rejected.
+pub fn register_chain(config: &ChainConfig) -> ManagerResult<()> {
+    config.validate()?;
+    if config.is_home() {
+        return Ok(());
+    }
+    CHAINS.with(|chains| {
+        let mut chains = chains.borrow_mut();
+        match chains.get(&config.chain_id) {
+            Some(registered) if registered.rpc_services != config.rpc_services => {
+                Err(ManagerError::Custom(format!(
+                    "Chain {} is already registered with other providers.",
+                    config.chain_id
+                )))
+            }
+            _ => {
+                chains.insert(config.chain_id, config.clone());
+                Ok(())
+            }
+        }
+    })
+}
+
+/// Unregisters a chain if no strategy uses it anymore.
+pub fn release_chain(chain_id: u64) {
+    let in_use = STRATEGY_STATE.with(|state| {
+        state
+            .borrow()
+            .values()
+            .any(|strategy| strategy.settings.chain.chain_id == chain_id)
+    });
+    if !in_use {
+        CHAINS.with(|chains| chains.borrow_mut().remove(&chain_id));
+    }
+}
+
+/// Returns the providers of a chain, or `None` for the home chain.
+///
+/// Fails for unregistered chains, so that their requests never reach the providers of
+/// another chain.
+pub fn chain_services(chain_id: u64) -> ManagerResult<Option<RpcServices>> {
+    if is_home_chain(chain_id) {
+        return Ok(None);
+    }
+    CHAINS
+        .with(|chains| {
+            chains
+                .borrow()
+                .get(&chain_id)
+                .map(|config| config.rpc_services)
+        })
+        .ok_or_else(|| {
+            ManagerError::Custom(format!(
+                "No providers are registered for chain {}.",
+                chain_id
+            ))
+        })
+}
+
+/// Returns the `n`-th provider of a chain for single JSON-RPC requests, wrapping around, or
+/// `None` for the home chain.
+pub fn chain_service(chain_id: u64, n: usize) -> ManagerResult<Option<RpcService>> {
+    match chain_services(chain_id)? {
+        Some(services) => nth_service(&services, n).map(Some).ok_or_else(|| {
+            ManagerError::Custom(format!(
+                "No providers are registered for chain {}.",
+                chain_id
+            ))
+        }),
+        None => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use evm_rpc_types::{L2MainnetService, RpcApi};
+
+    use super::*;
+
+    fn arbitrum() -> ChainConfig {
+        ChainConfig {
+            chain_id: 42161,
+            rpc_services: Some(RpcServices::ArbitrumOne(Some(vec![
+                L2MainnetService::Alchemy,
+                L2MainnetService::PublicNode,
+            ]))),
+            cketh_helper: None,
+        }
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(ChainConfig::default().validate().is_ok());
+        assert!(arbitrum().validate().is_ok());
+
+        let mut home_with_services = ChainConfig::default();
+        home_with_services.rpc_services = arbitrum().rpc_services;
+        assert!(home_with_services.validate().is_err());
+
+        let mut without_services = arbitrum();
+        without_services.rpc_services = None;
+        assert!(without_services.validate().is_err());
+
+        let mut wrong_chain = arbitrum();
+        wrong_chain.chain_id = 8453;
+        assert!(wrong_chain.validate().is_err());
+
+        let mut empty = arbitrum();
+        empty.rpc_services = Some(RpcServices::ArbitrumOne(None));
+        assert!(empty.validate().is_err());
+
+        let mut invalid_helper = arbitrum();
+        invalid_helper.cketh_helper = Some("0x1234".to_string());
+        assert!(invalid_helper.validate().is_err());
+    }
+
+    #[test]
+    fn test_chain_routing() {
+        CHAINS.with(|chains| {
+            let mut chains = chains.borrow_mut();
+            let chain_ids: Vec<u64> = chains.iter().map(|(chain_id, _)| chain_id).collect();
+            for chain_id in chain_ids {
+                chains.remove(&chain_id);
+            }
+        });
+        STRATEGY_STATE.with(|state| state.borrow_mut().clear());
+
+        assert_eq!(chain_services(CHAIN_ID), Ok(None));
+        assert!(chain_services(42161).is_err());
+
+        register_chain(&arbitrum()).unwrap();
+        assert_eq!(
+            chain_service(42161, 3),
+            Ok(Some(RpcService::ArbitrumOne(L2MainnetService::PublicNode)))
+        );
+
+        // The providers of a registered chain cannot be swapped
+        let mut other = arbitrum();
+        other.rpc_services = Some(RpcServices::ArbitrumOne(Some(vec![L2MainnetService::Ankr])));
+        assert!(register_chain(&other).is_err());
+
+        // Unused chains are released
+        release_chain(42161);
+        assert!(chain_services(42161).is_err());
+
+        let custom = ChainConfig {
+            chain_id: 8453,
+            rpc_services: Some(RpcServices::Custom {
+                chain_id: 8453,
+                services: vec![RpcApi {
+                    url: "https://base.example.org".to_string(),
+                    headers: None,
+                }],
+            }),
+            cketh_helper: None,
+        };
+        register_chain(&custom).unwrap();
+        assert!(matches!(
+            chain_service(8453, 1),
+            Ok(Some(RpcService::Custom(_)))
+        ));
+    }
+}
@@ -7,31 +7,48 @@
 //! - Monitoring ckETH balance and minting ckETH when it is below a specified threshold.
 //! - Sending ETH to the ckETH helper contract to mint ckETH tokens.
 //! - Facilitating transfers of ckETH to arbitrageurs at a discounted rate.
+//! - Topping up cycles through the CMC when no arbitrageur keeps the balance above a critical floor.
 //! - Providing locking mechanisms to ensure atomicity for ckETH<>Cycles arbitrage operations.
 //!
 //! Dependencies:
 //! - EVM RPC for querying ETH balances and submitting transactions.
-//! - ICRC-1 ledger for transferring ckETH tokens.
+//! - ICRC-1 ledgers for transferring ckETH and ICP tokens.
+//! - The cycles minting canister (CMC) for converting ICP to cycles.
 //! - Stable strategies for managing multiple EOAs (Externally Owned Accounts).
 
 use crate::{
+    chain::ChainConfig,
+    charger_config::charger_config,
     constants::{
-        cketh_fee, cketh_ledger, cketh_threshold, ether_recharge_value, scale, CKETH_HELPER,
-        CYCLES_DISCOUNT_PERCENTAGE, CYCLES_THRESHOLD,
+        cketh_fee, cketh_ledger, cycles_minting_canister, ether_recharge_value, icp_ledger, scale,
+        DEFAULT_CMC_TOP_UP_AMOUNT, DEFAULT_CRITICAL_CYCLES_FLOOR, ICP_TRANSFER_FEE,
+        MEMO_TOP_UP_CANISTER,
+    },
+    flags::{flag_int, CMC_TOP_UP_AMOUNT, CRITICAL_CYCLES_FLOOR},
+    funding::{
+        funding_derivation_path, funding_eoa, record_funding_balance, store_funding_nonce,
+        FundingEoa,
     },
     journal::{JournalCollection, LogType},
-    strategy::stable::StableStrategy,
+    rate_source::ether_cycles_rate,
+    rpc_registry::resolve_rpc_canister,
+    strategy::stable::{update_strategy, StableStrategy},
+    treasury::{
+        record_cketh_balance, record_cycles_balance, record_eoa_balance, record_mint, record_swap,
+        store_swap, PendingMint,
+    },
     types::{depositEthCall, EthCallResponse},
     utils::{
-        common::{
-            fetch_cketh_balance, fetch_ether_cycles_rate, request_with_dynamic_retries, u256_to_nat,
-        },
+        address::parse_address,
+        cmc::{NotifyError, NotifyTopUpArg},
+        common::{fetch_cketh_balance, get_nonce, request_with_dynamic_retries, u256_to_nat},
         error::*,
         evm_rpc::{SendRawTransactionStatus, Service},
+        nonce::{reconcile_nonce, NonceLease},
         transaction_builder::TransactionBuilder,
     },
 };
-use crate::{state::*, types::SwapResponse};
+use crate::{clock::time, state::*, types::SwapResponseV2};
 use alloy_primitives::{FixedBytes, U256};
 use alloy_sol_types::SolCall;
 use candid::Principal;
@@ -39,12 +56,15 @@ use ic_exports::ic_cdk::{
     api::{
         self,
         call::{msg_cycles_accept, msg_cycles_available},
-        canister_balance,
+        canister_balance, canister_balance128,
     },
     call,
 };
 use ic_exports::{candid::Nat, ic_kit::CallResult};
-use icrc_ledger_types::icrc1::transfer::{TransferArg, TransferError};
+use icrc_ledger_types::icrc1::{
+    account::Account,
+    transfer::{Memo, TransferArg, TransferError},
+};
 use num_traits::ToPrimitive;
 use serde_json::json;
 
@@ -54,13 +74,29 @@ use serde_json::json;
 /// - `Ok(())` if the cycle balance is below the threshold.
 /// - `Err(ManagerError::CyclesBalanceAboveRechargingThreshold)` if the cycle balance exceeds the threshold.
 pub async fn check_threshold() -> ManagerResult<()> {
-    let threshold = CYCLES_THRESHOLD;
+    let threshold = charger_config().cycles_threshold;
     if canister_balance() <= threshold {
         return Ok(());
     }
     Err(ManagerError::CyclesBalanceAboveRechargingThreshold)
 }
 
+/// Refreshes the cached ckETH and cycles balances served by `get_cached_balances`, and the
+/// balance of the funding EOA.
+///
+/// The cycles balance is recorded even if the ckETH ledger cannot be reached.
+pub async fn refresh_balances() -> ManagerResult<()> {
+    record_cycles_balance(Nat::from(canister_balance128()), time() / 1_000_000_000);
+    let cketh_balance = fetch_cketh_balance().await?;
+    record_cketh_balance(cketh_balance, time() / 1_000_000_000);
+    if let Some(funding) = funding_eoa() {
+        let rpc_canister = funding.service(time() / 1_000_000_000);
+        let balance = fetch_balance(&rpc_canister, funding.address).await?;
+        record_funding_balance(&balance, time() / 1_000_000_000);
+    }
+    Ok(())
+}
+
 /// Monitors the canister's ckETH balance and triggers minting (recharging) if below the threshold.
 ///
 /// Returns:
@@ -68,12 +104,14 @@ pub async fn check_threshold() -> ManagerResult<()> {
 /// - Triggers `ether_deposit` if the ckETH balance is below the threshold.
 pub async fn recharge_cketh(journal: &mut JournalCollection) -> ManagerResult<()> {
     let current_balance = fetch_cketh_balance().await?;
+    record_cketh_balance(current_balance.clone(), time() / 1_000_000_000);
+    record_cycles_balance(Nat::from(canister_balance128()), time() / 1_000_000_000);
     journal.append_note(
         Ok(()),
         LogType::Recharge,
         format!("The current ckETH balance is at {}", current_balance),
     );
-    let cketh_threshold = cketh_threshold();
+    let cketh_threshold = charger_config().cketh_threshold;
 
     if current_balance < cketh_threshold {
         return ether_deposit(journal).await;
@@ -90,17 +128,175 @@ pub async fn recharge_cketh(journal: &mut JournalCollection) -> ManagerResult<()
     Ok(())
 }
 
+/// Tops up the canister's cycles through the cycles minting canister (CMC) when the cycles
+/// balance falls below the critical floor.
+///
+/// Arbitrageurs normally keep the canister charged by swapping cycles for ckETH. If none shows
+/// up, ICP held by the canister on the ICP ledger is sent to the CMC, which is then notified to
+/// mint cycles to the canister:
+///
+/// ```plain
+/// cycles < floor ──► ICP balance ──► icrc1_transfer(CMC, subaccount(canister), memo TPUP)
+///                                                  │ block index
+///                                                  ▼
+///                                   notify_top_up ──► cycles minted to the canister
+/// ```
+///
+/// A notification that failed with a retryable error is sent again by the next call, before
+/// any new transfer. Every top-up attempt is journaled.
+///
+/// Returns:
+/// - `Ok(())` if the cycles balance is above the floor, or the top-up succeeds.
+/// - `Err(ManagerError)` if the ICP balance is insufficient, or the transfer or the
+///   notification fails.
+pub async fn cmc_top_up() -> ManagerResult<()> {
+    let floor = flag_int!(CRITICAL_CYCLES_FLOOR, DEFAULT_CRITICAL_CYCLES_FLOOR);
+    let cycles_balance = canister_balance128();
+    if floor <= 0 || cycles_balance >= floor as u128 {
+        return Ok(());
+    }
+    if CMC_TOP_UP_LOCK.with(|lock| lock.replace(true)) {
+        return Ok(());
+    }
+
+    let mut journal = JournalCollection::open(None);
+    journal.append_note(
+        Ok(()),
+        LogType::Recharge,
+        format!(
+            "The cycles balance {} is below the critical floor {}. Topping up through the CMC.",
+            cycles_balance, floor
+        ),
+    );
+    let result = notify_cmc_top_up(&mut journal).await;
+    CMC_TOP_UP_LOCK.with(|lock| lock.set(false));
+    journal.append_note(result.clone(), LogType::Recharge, "CMC top-up");
+    result
+}
+
+/// Notifies the CMC of the pending ICP transfer, or of a new one.
+async fn notify_cmc_top_up(journal: &mut JournalCollection) -> ManagerResult<()> {
+    let block_index = match PENDING_TOP_UP.with(|pending| pending.get()) {
+        Some(block_index) => block_index,
+        None => {
+            let block_index = transfer_icp_to_cmc(journal).await?;
+            PENDING_TOP_UP.with(|pending| pending.set(Some(block_index)));
+            block_index
+        }
+    };
+
+    let args = NotifyTopUpArg {
+        block_index,
+        canister_id: api::id(),
+    };
+    let call_response: CallResult<(Result<Nat, NotifyError>,)> =
+        call(cycles_minting_canister(), "notify_top_up", (args,)).await;
+
+    match call_response {
+        Ok((Ok(cycles),)) => {
+            PENDING_TOP_UP.with(|pending| pending.set(None));
+            record_cycles_balance(Nat::from(canister_balance128()), time() / 1_000_000_000);
+            journal.append_note(
+                Ok(()),
+                LogType::Recharge,
+                format!(
+                    "The CMC minted {} cycles from the ICP transfer at block {}.",
+                    cycles, block_index
+                ),
+            );
+            Ok(())
+        }
+        Ok((Err(err),)) => {
+            if !err.is_retryable() {
+                PENDING_TOP_UP.with(|pending| pending.set(None));
+            }
+            Err(ManagerError::Custom(format!(
+                "The CMC rejected the top-up from the ICP transfer at block {}: {:?}",
+                block_index, err
+            )))
+        }
+        // The transfer stays pending and is notified again by the next top-up
+        Err(err) => Err(ManagerError::Custom(err.1)),
+    }
+}
+
+/// Transfers ICP from the canister's ledger account to its top-up subaccount of the CMC.
+///
+/// Returns the index of the ledger block of the transfer.
+async fn transfer_icp_to_cmc(journal: &mut JournalCollection) -> ManagerResult<u64> {
+    let amount = flag_int!(CMC_TOP_UP_AMOUNT, DEFAULT_CMC_TOP_UP_AMOUNT).max(0) as u64;
+    let ledger_principal = icp_ledger();
+    let account = Account {
+        owner: api::id(),
+        subaccount: None,
+    };
+    let balance_response: CallResult<(Nat,)> =
+        call(ledger_principal, "icrc1_balance_of", (account,)).await;
+    let icp_balance = balance_response
+        .map_err(|err| ManagerError::Custom(err.1))?
+        .0;
+    if icp_balance < Nat::from(amount.saturating_add(ICP_TRANSFER_FEE)) {
+        return Err(ManagerError::Custom(format!(
+            "The ICP balance {} of the canister cannot cover a top-up of {} e8s.",
+            icp_balance, amount
+        )));
+    }
+
+    let args = TransferArg {
+        from_subaccount: None,
+        to: Account {
+            owner: cycles_minting_canister(),
+            subaccount: Some(principal_subaccount(api::id())),
+        },
+        fee: Some(Nat::from(ICP_TRANSFER_FEE)),
+        created_at_time: None,
+        memo: Some(Memo::from(MEMO_TOP_UP_CANISTER.to_le_bytes().to_vec())),
+        amount: Nat::from(amount),
+    };
+    let call_response: CallResult<(Result<Nat, TransferError>,)> =
+        call(ledger_principal, "icrc1_transfer", (args,)).await;
+
+    match call_response {
+        Ok((Ok(block_index),)) => {
+            let block_index = block_index.0.to_u64().ok_or_else(|| {
+                ManagerError::DecodingError(
+                    "Error while decoding the ICP ledger block index to u64".to_string(),
+                )
+            })?;
+            journal.append_note(
+                Ok(()),
+                LogType::Recharge,
+                format!(
+                    "Transferred {} e8s of ICP to the CMC at block {}.",
+                    amount, block_index
+                ),
+            );
+            Ok(block_index)
+        }
+        Ok((Err(err),)) => Err(ManagerError::Custom(format!(
+            "The ICP transfer to the CMC failed: {:?}",
+            err
+        ))),
+        Err(err) => Err(ManagerError::Custom(err.1)),
+    }
+}
+
 /// Deposits ETH into the ckETH helper contract to mint ckETH tokens on the Internet Computer.
 ///
-/// This function rotates through available EOAs (Externally Owned Accounts) to select one
-/// with sufficient balance for the deposit operation.
+/// Once the funding EOA is initialized, it sends every deposit. Otherwise, this function
+/// rotates through available EOAs (Externally Owned Accounts) to select one
+/// with sufficient balance for the deposit operation. EOAs on chains without a ckETH helper
+/// contract are skipped.
 ///
 /// Returns:
 /// - `Ok(())` if the deposit succeeds.
 /// - `Err(ManagerError::Custom)` if no EOA has enough balance or an error occurs.
 async fn ether_deposit(journal: &mut JournalCollection) -> ManagerResult<()> {
+    if let Some(funding) = funding_eoa() {
+        return funding_deposit(journal, funding).await;
+    }
+
     let ether_value = ether_recharge_value();
-    let cketh_helper: String = CKETH_HELPER.to_string();
     let mut strategies: Vec<StableStrategy> = STRATEGY_STATE
         .with(|strategies_hashmap| strategies_hashmap.borrow().clone().into_values().collect());
 
@@ -112,9 +308,21 @@ async fn ether_deposit(journal: &mut JournalCollection) -> ManagerResult<()> {
             Some(pk) => pk,
             None => continue, // Skip if eoa_pk is None
         };
+        let cketh_helper = match &strategy.settings.chain.cketh_helper {
+            Some(helper) => helper.clone(),
+            None => continue, // Skip if ckETH cannot be minted from the strategy's chain
+        };
+        let rpc_canister =
+            resolve_rpc_canister(&strategy.settings.rpc_canister, time() / 1_000_000_000);
 
-        let balance = match fetch_balance(&strategy.settings.rpc_canister, eoa.to_string()).await {
+        let balance = match fetch_balance(&rpc_canister, eoa.to_string()).await {
             Ok(balance) => {
+                record_eoa_balance(
+                    strategy.settings.key,
+                    eoa.to_string(),
+                    &balance,
+                    time() / 1_000_000_000,
+                );
                 journal.append_note(
                     Ok(()),
                     LogType::Recharge,
@@ -145,39 +353,36 @@ async fn ether_deposit(journal: &mut JournalCollection) -> ManagerResult<()> {
                 "The balance is larger than the required ETH value. Proceeding with minting ckETH.",
             );
 
-            let principal = api::id();
-            let principal_bytes = principal.as_slice();
-            let n = principal_bytes.len();
-
-            let mut bytes = [0u8; 32];
-            bytes[0] = n as u8;
-            bytes[1..=n].copy_from_slice(principal_bytes);
-
-            let encoded_canister_id = FixedBytes::<32>::from(bytes);
-
-            let deposit_call = depositEthCall {
-                principal: encoded_canister_id,
-                subaccount: FixedBytes::<32>::ZERO,
-            };
-
-            let transaction_data = deposit_call.abi_encode();
+            let transaction_data = deposit_calldata();
 
             let new_counter = (index as u8 + turn + 1) % strategies.len() as u8;
             CKETH_EOA_TURN_COUNTER.with(|counter| counter.set(new_counter));
 
+            // The strategy may sign a rate adjustment from the same EOA in the meantime
+            let lease = NonceLease::acquire(eoa, strategy.data.eoa_nonce);
             let transaction_response = TransactionBuilder::default()
-                .to(cketh_helper.clone())
+                .to(cketh_helper)
                 .from(eoa.to_string())
                 .data(transaction_data)
                 .value(ether_value)
-                .nonce(strategy.data.eoa_nonce)
+                .nonce(lease.nonce())
                 .derivation_path(strategy.settings.derivation_path.clone())
                 .cycles(40_000_000_000)
-                .send(&strategy.settings.rpc_canister)
+                .strategy_key(strategy.settings.key)
+                .fee_policy(strategy.settings.fee_policy)
+                .journal(journal)
+                .send(&rpc_canister)
                 .await?;
 
             match transaction_response {
                 SendRawTransactionStatus::Ok(tx_hash) => {
+                    store_eoa_nonce(strategy.settings.key, lease.commit());
+                    record_mint(PendingMint {
+                        strategy: Some(strategy.settings.key),
+                        hash: tx_hash.clone(),
+                        value: u256_to_nat(&ether_value)?,
+                        submitted_at: time() / 1_000_000_000,
+                    });
                     journal.append_note(
                         Ok(()),
                         LogType::Recharge,
@@ -202,6 +407,13 @@ async fn ether_deposit(journal: &mut JournalCollection) -> ManagerResult<()> {
                         LogType::Recharge,
                         format!("The nonce needs adjusting: {:#?}", transaction_response),
                     );
+                    drop(lease);
+                    if let Ok(chain_nonce) = get_nonce(&rpc_canister, eoa).await {
+                        store_eoa_nonce(
+                            strategy.settings.key,
+                            reconcile_nonce(eoa, chain_nonce.to::<u64>()),
+                        );
+                    }
                     continue;
                 }
             }
@@ -213,6 +425,117 @@ async fn ether_deposit(journal: &mut JournalCollection) -> ManagerResult<()> {
     ))
 }
 
+/// Encodes a principal into 32 bytes, its length followed by its bytes, as the ckETH helper
+/// contract and the CMC expect it.
+fn principal_subaccount(principal: Principal) -> [u8; 32] {
+    let principal_bytes = principal.as_slice();
+    let n = principal_bytes.len();
+
+    let mut bytes = [0u8; 32];
+    bytes[0] = n as u8;
+    bytes[1..=n].copy_from_slice(principal_bytes);
+    bytes
+}
+
+/// Returns the calldata of a ckETH helper deposit minting ckETH to the canister.
+fn deposit_calldata() -> Vec<u8> {
+    let encoded_canister_id = FixedBytes::<32>::from(principal_subaccount(api::id()));
+
+    let deposit_call = depositEthCall {
+        principal: encoded_canister_id,
+        subaccount: FixedBytes::<32>::ZERO,
+    };
+
+    deposit_call.abi_encode()
+}
+
+/// Deposits ETH from the funding EOA into the ckETH helper contract of the home chain.
+///
+/// The strategy EOAs are left untouched, even if the funding EOA cannot cover the deposit.
+///
+/// Returns:
+/// - `Ok(())` if the deposit succeeds.
+/// - `Err(ManagerError::Custom)` if the funding EOA lacks funds or the deposit is rejected.
+async fn funding_deposit(
+    journal: &mut JournalCollection,
+    funding: FundingEoa,
+) -> ManagerResult<()> {
+    let cketh_helper = ChainConfig::default().cketh_helper.ok_or_else(|| {
+        ManagerError::Custom("ckETH cannot be minted on the home chain.".to_string())
+    })?;
+    let eoa = parse_address("funding_eoa", &funding.address, false)?;
+    let ether_value = ether_recharge_value();
+    let rpc_canister = funding.service(time() / 1_000_000_000);
+
+    let balance = fetch_balance(&rpc_canister, funding.address.clone()).await?;
+    record_funding_balance(&balance, time() / 1_000_000_000);
+    journal.append_note(
+        Ok(()),
+        LogType::Recharge,
+        format!(
+            "Queried the ETH balance of the funding EOA {}. The current balance is {}",
+            funding.address, balance
+        ),
+    );
+    if balance <= ether_value {
+        return Err(ManagerError::Custom(format!(
+            "The funding EOA {} does not hold the {} wei to deposit.",
+            funding.address, ether_value
+        )));
+    }
+
+    let lease = NonceLease::acquire(eoa, funding.nonce);
+    let transaction_response = TransactionBuilder::default()
+        .to(cketh_helper)
+        .from(funding.address.clone())
+        .data(deposit_calldata())
+        .value(ether_value)
+        .nonce(lease.nonce())
+        .derivation_path(funding_derivation_path())
+        .cycles(40_000_000_000)
+        .journal(journal)
+        .send(&rpc_canister)
+        .await?;
+
+    match transaction_response {
+        SendRawTransactionStatus::Ok(tx_hash) => {
+            store_funding_nonce(lease.commit());
+            record_mint(PendingMint {
+                strategy: None,
+                hash: tx_hash.clone(),
+                value: u256_to_nat(&ether_value)?,
+                submitted_at: time() / 1_000_000_000,
+            });
+            journal.append_note(
+                Ok(()),
+                LogType::Recharge,
+                format!(
+                    "The mint transaction of the funding EOA was sent with hash: {:#?}",
+                    tx_hash
+                ),
+            );
+            Ok(())
+        }
+        SendRawTransactionStatus::NonceTooHigh | SendRawTransactionStatus::NonceTooLow => {
+            drop(lease);
+            let chain_nonce = get_nonce(&rpc_canister, eoa).await?.to::<u64>();
+            store_funding_nonce(reconcile_nonce(eoa, chain_nonce));
+            Err(ManagerError::Custom(format!(
+                "The nonce of the funding EOA needed adjusting: {:#?}",
+                transaction_response
+            )))
+        }
+        SendRawTransactionStatus::InsufficientFunds => Err(ManagerError::Custom(
+            "The funding EOA cannot cover the mint value and the gas costs.".to_string(),
+        )),
+    }
+}
+
+/// Stores the next nonce of a strategy's EOA after a transaction sent outside of its runs.
+fn store_eoa_nonce(key: u32, next: u64) {
+    let _ = update_strategy(key, |strategy| strategy.data.eoa_nonce = next);
+}
+
 /// Queries the ETH balance for a given public key using the EVM RPC canister.
 ///
 /// Arguments:
@@ -222,7 +545,10 @@ async fn ether_deposit(journal: &mut JournalCollection) -> ManagerResult<()> {
 /// Returns:
 /// - `Ok(U256)` representing the balance.
 /// - `Err(ManagerError)` if the RPC call or balance parsing fails.
-async fn fetch_balance(rpc_canister: &Service, public_key: String) -> ManagerResult<U256> {
+pub(crate) async fn fetch_balance(
+    rpc_canister: &Service,
+    public_key: String,
+) -> ManagerResult<U256> {
     let json_args = json!({
         "id": 1,
         "jsonrpc": "2.0",
@@ -267,7 +593,7 @@ async fn fetch_balance(rpc_canister: &Service, public_key: String) -> ManagerRes
 ///
 /// This function performs the following steps:
 /// 1. **Rate Calculation**: Fetches the current Ether-to-Cycles conversion rate and applies a
-///    predefined discount percentage (`CYCLES_DISCOUNT_PERCENTAGE`).
+///    discount percentage of the charger configuration.
 /// 2. **Cycle Validation**: Verifies that the conversion rate is non-zero.
 /// 3. **Maximum ckETH Transfer Calculation**:
 ///    - Calculates the maximum amount of ckETH that can be transferred based on available cycles.
@@ -281,9 +607,11 @@ async fn fetch_balance(rpc_canister: &Service, public_key: String) -> ManagerRes
 /// * `receiver` - The principal identifier of the arbitrageur (the recipient).
 ///
 /// # Returns
-/// A `SwapResponse` struct containing:
+/// A `SwapResponseV2` struct, also stored in the swap history, containing:
 /// - `accepted_cycles`: The number of accepted cycles.
 /// - `returning_ether`: The amount of ckETH transferred.
+/// - The XRC rate, discount, and effective price the swap was priced at.
+/// - `ledger_block_index`: The ledger block of the transfer.
 ///
 /// # Errors
 /// Returns a `ManagerError` in cases where:
@@ -295,11 +623,11 @@ async fn fetch_balance(rpc_canister: &Service, public_key: String) -> ManagerRes
 /// ```rust
 /// let receiver = Principal::from_text("aaaaa-aa").unwrap();
 /// let response = transfer_cketh(receiver).await?;
-/// println!("Transferred: {} ckETH, Accepted Cycles: {}", response.returning_ether, response.accepted_cycles);
+/// println!("Swap {}: {} ckETH, Accepted Cycles: {}", response.swap_id, response.returning_ether, response.accepted_cycles);
 /// ```
-pub async fn transfer_cketh(receiver: Principal) -> ManagerResult<SwapResponse> {
-    let discount_percentage = CYCLES_DISCOUNT_PERCENTAGE;
-    let real_rate = fetch_ether_cycles_rate().await?;
+pub async fn transfer_cketh(receiver: Principal) -> ManagerResult<SwapResponseV2> {
+    let discount_percentage = charger_config().cycles_discount_percentage;
+    let real_rate = ether_cycles_rate().await?;
     let rate = real_rate * discount_percentage / 100;
 
     if rate == 0 {
@@ -324,7 +652,9 @@ pub async fn transfer_cketh(receiver: Principal) -> ManagerResult<SwapResponse>
     let maximum_returned_ether_amount = u256_to_nat(max_returned_ether_amount_u256)?;
 
     // Check the current balance of ckETH.
-    let cketh_balance = fetch_cketh_balance().await? - cketh_fee();
+    let cketh_balance = fetch_cketh_balance().await?;
+    record_cketh_balance(cketh_balance.clone(), time() / 1_000_000_000);
+    let cketh_balance = cketh_balance - cketh_fee();
 
     // Determine the amount to transfer and cycles to accept.
     let (transfer_amount, cycles_to_accept, returning_cycles) =
@@ -370,13 +700,32 @@ pub async fn transfer_cketh(receiver: Principal) -> ManagerResult<SwapResponse>
         call(ledger_principal, "icrc1_transfer", (args,)).await;
 
     match call_response {
-        Ok(_) => Ok(SwapResponse {
-            accepted_cycles: Nat::from(cycles_to_accept),
-            returning_ether: transfer_amount,
-            returning_cycles,
-            real_rate,
-            discounted_rate: rate,
-        }),
+        Ok((Ok(ledger_block_index),)) => {
+            let now = time() / 1_000_000_000;
+            record_swap(Nat::from(cycles_to_accept), transfer_amount.clone(), now);
+            let effective_price = if transfer_amount == Nat::from(0_u8) {
+                Nat::from(0_u8)
+            } else {
+                Nat::from(cycles_to_accept) * u256_to_nat(&scale())? / transfer_amount.clone()
+            };
+            Ok(store_swap(SwapResponseV2 {
+                swap_id: 0,
+                receiver,
+                timestamp: now,
+                accepted_cycles: Nat::from(cycles_to_accept),
+                returning_ether: transfer_amount,
+                returning_cycles,
+                xrc_rate: real_rate,
+                discount_percentage,
+                discounted_rate: rate,
+                effective_price,
+                ledger_block_index,
+            }))
+        }
+        Ok((Err(err),)) => Err(ManagerError::Custom(format!(
+            "The ckETH transfer failed: {:?}",
+            err
+        ))),
         Err(err) => Err(ManagerError::Custom(err.1)),
     }
 }
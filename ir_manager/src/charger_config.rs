@@ -0,0 +1,157 @@
+//! Charger Configuration
+//!
+//! The thresholds of the recharging cycle and the discount offered to arbitrageurs are
+//! tuned by a controller at runtime, and kept across upgrades:
+//!
+//! ```plain
+//! set_charger_config ──► validate ──► CHARGER_CONFIG (stable) ──► journal: old ──► new
+//!                                             │
+//!            ┌────────────────────────────────┼──────────────────────────────┐
+//!            ▼                                ▼                              ▼
+//!   cketh_threshold:                 cycles_threshold:            cycles_discount_percentage:
+//!   mint ckETH below it              accept swaps at or below it  share of the XRC rate paid
+//! ```
+//!
+//! The discount is bounded so that a misconfiguration cannot give the ckETH away, and the
+//! cycles threshold so that swaps cannot drain the canister towards freezing.
+
+use std::borrow::Cow;
+
+use candid::{CandidType, Decode, Encode, Nat};
+use ic_stable_structures::{storable::Bound, Storable};
+use serde::Deserialize;
+
+use crate::{
+    constants::{
+        DEFAULT_CKETH_THRESHOLD, DEFAULT_CYCLES_DISCOUNT_PERCENTAGE, DEFAULT_CYCLES_THRESHOLD,
+        MAX_CKETH_THRESHOLD, MAX_CYCLES_THRESHOLD, MIN_CYCLES_DISCOUNT_PERCENTAGE,
+        MIN_CYCLES_THRESHOLD,
+    },
+    state::CHARGER_CONFIG,
+    utils::error::{ManagerError, ManagerResult},
+};
+
+/// Thresholds and discount of the ckETH<>Cycles arbitrage
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq)]
+pub struct ChargerConfig {
+    /// Percentage of the XRC rate arbitrageurs receive ckETH at (97 is a 3% discount)
+    pub cycles_discount_percentage: u64,
+    /// ckETH balance in wei below which the recharging cycle mints ckETH
+    pub cketh_threshold: Nat,
+    /// Cycles balance at or below which swaps are accepted
+    pub cycles_threshold: u64,
+}
+
+impl Default for ChargerConfig {
+    fn default() -> Self {
+        Self {
+            cycles_discount_percentage: DEFAULT_CYCLES_DISCOUNT_PERCENTAGE,
+            cketh_threshold: Nat::from(DEFAULT_CKETH_THRESHOLD),
+            cycles_threshold: DEFAULT_CYCLES_THRESHOLD,
+        }
+    }
+}
+
+impl Storable for ChargerConfig {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        Cow::Owned(Encode!(self).expect("Failed to encode the charger config."))
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).expect("Failed to decode the charger config.")
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+impl ChargerConfig {
+    /// Checks that every value lies within its bounds.
+    pub fn validate(&self) -> ManagerResult<()> {
+        if !(MIN_CYCLES_DISCOUNT_PERCENTAGE..=100).contains(&self.cycles_discount_percentage) {
+            return Err(ManagerError::Custom(format!(
+                "The cycles discount percentage must be between {} and 100.",
+                MIN_CYCLES_DISCOUNT_PERCENTAGE
+            )));
+        }
+        if self.cketh_threshold == Nat::from(0_u8)
+            || self.cketh_threshold > Nat::from(MAX_CKETH_THRESHOLD)
+        {
+            return Err(ManagerError::Custom(format!(
+                "The ckETH threshold must be positive and at most {} wei.",
+                MAX_CKETH_THRESHOLD
+            )));
+        }
+        if !(MIN_CYCLES_THRESHOLD..=MAX_CYCLES_THRESHOLD).contains(&self.cycles_threshold) {
+            return Err(ManagerError::Custom(format!(
+                "The cycles threshold must be between {} and {} cycles.",
+                MIN_CYCLES_THRESHOLD, MAX_CYCLES_THRESHOLD
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Returns the charger configuration in effect.
+pub fn charger_config() -> ChargerConfig {
+    CHARGER_CONFIG.with(|config| config.borrow().get().clone())
+}
+
+/// Validates and replaces the charger configuration, returning the previous one.
+pub fn set_charger_config(config: ChargerConfig) -> ManagerResult<ChargerConfig> {
+    config.validate()?;
+    let previous = charger_config();
+    CHARGER_CONFIG.with(|current| {
+        current
+            .borrow_mut()
+            .set(config)
+            .expect("Failed to persist the charger config.")
+    });
+    Ok(previous)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_charger_config_validation() {
+        assert!(ChargerConfig::default().validate().is_ok());
+
+        let invalid = [
+            ChargerConfig {
+                cycles_discount_percentage: 101,
+                ..Default::default()
+            },
+            ChargerConfig {
+                cycles_discount_percentage: MIN_CYCLES_DISCOUNT_PERCENTAGE - 1,
+                ..Default::default()
+            },
+            ChargerConfig {
+                cketh_threshold: Nat::from(0_u8),
+                ..Default::default()
+            },
+            ChargerConfig {
+                cketh_threshold: Nat::from(MAX_CKETH_THRESHOLD + 1),
+                ..Default::default()
+            },
+            ChargerConfig {
+                cycles_threshold: MIN_CYCLES_THRESHOLD - 1,
+                ..Default::default()
+            },
+        ];
+        for config in invalid {
+            assert!(set_charger_config(config).is_err());
+        }
+        assert_eq!(charger_config(), ChargerConfig::default());
+
+        let tuned = ChargerConfig {
+            cycles_discount_percentage: 95,
+            ..Default::default()
+        };
+        assert_eq!(
+            set_charger_config(tuned.clone()),
+            Ok(ChargerConfig::default())
+        );
+        assert_eq!(charger_config(), tuned);
+    }
+}
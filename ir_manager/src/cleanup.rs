@@ -4,6 +4,7 @@
 //! - Journal log management and pruning
 //! - RPC provider reputation resets and randomization
 //! - System state maintenance
+//! - Batch manager parameter refreshes
 //!
 //! The cleanup operations help maintain system performance and ensure fair provider selection
 //! by periodically resetting reputations and removing excess logs.
@@ -25,8 +26,8 @@
 //!
 //! The cleanup system operates on three main components:
 //!
-//! 1. **Journal Management**: Removes excess logs and reputation change entries while maintaining
-//!    the most recent 300 entries.
+//! 1. **Journal Management**: Removes reputation change entries, then the oldest logs that fall
+//!    outside the controller-configurable retention policy (see `crate::retention`).
 //!
 //! 2. **Provider Reputations**: Periodically resets and randomizes provider rankings to ensure
 //!    fair selection and prevent gaming of the reputation system.
@@ -34,18 +35,25 @@
 //! 3. **State Cleanup**: Maintains system state by removing stale data and ensuring data structures
 //!    stay within size limits.
 
+use candid::Encode;
 use ic_exports::ic_cdk::api::management_canister::main::raw_rand;
 use rand::seq::SliceRandom;
 use rand_chacha::rand_core::SeedableRng;
 
-use crate::constants::PROVIDERS;
+use crate::clock::time;
 use crate::journal::JournalCollection;
 use crate::journal::LogType;
+use crate::metrics::{record_journal_retention, ManagementCall, RetentionCounters};
+use crate::providers::ProviderPool;
+use crate::retention::journal_retention;
+use crate::runs::runs_cleanup;
 use crate::state::JOURNAL;
-use crate::state::RPC_REPUTATIONS;
-use crate::utils::common::extract_call_result;
+use crate::strategy::batch::refresh_batch_manager_params;
+use crate::strategy::conflicts::config_conflicts;
+use crate::tx_pool::tx_pool_cleanup;
 use crate::utils::error::ManagerError;
 use crate::utils::error::ManagerResult;
+use crate::utils::management::management_call;
 
 /// Performs daily cleanup tasks including journal pruning and reputation resets.
 ///
@@ -65,9 +73,55 @@ pub async fn daily_cleanup() {
     journal.append_note(
         Ok(()),
         LogType::Info,
-        "Cleaned up the journal by removing excess logs and all reputation change entries.",
+        "Cleaned up the journal by removing all reputation change entries and the logs outside the retention policy.",
     );
 
+    runs_cleanup();
+
+    journal.append_note(
+        Ok(()),
+        LogType::Info,
+        "Removed the oldest strategy run summaries above the retention limit.",
+    );
+
+    let removed = tx_pool_cleanup(time() / 1_000_000_000);
+    journal.append_note(
+        Ok(()),
+        LogType::Info,
+        format!(
+            "Removed {} settled transactions outside the retention limits of the transaction pool.",
+            removed
+        ),
+    );
+
+    match config_conflicts() {
+        Ok(conflicts) if conflicts.is_empty() => {}
+        Ok(conflicts) => journal.append_note(
+            Ok(()),
+            LogType::Info,
+            format!(
+                "WARNING: Strategies with conflicting configurations: {:?}",
+                conflicts
+            ),
+        ),
+        Err(err) => journal.append_note(
+            Err(err),
+            LogType::Info,
+            "Failed to check the strategy configurations for conflicts.",
+        ),
+    };
+
+    for (key, err) in refresh_batch_manager_params(time() / 1_000_000_000).await {
+        journal.append_note(
+            Err(err),
+            LogType::Info,
+            format!(
+                "Failed to refresh the batch manager parameters of strategy {}.",
+                key
+            ),
+        );
+    }
+
     let reputations_cleanup_result = reputations_cleanup().await;
     match reputations_cleanup_result {
         Ok(()) => journal.append_note(
@@ -88,9 +142,9 @@ pub async fn daily_cleanup() {
 /// Resets and randomizes the RPC provider reputation rankings.
 ///
 /// This function:
-/// 1. Creates a new randomized ordering of providers using a secure RNG seed from the IC
+/// 1. Creates a new randomized ordering of each pool's members using a secure RNG seed from the IC
 /// 2. Resets all provider reputations to zero
-/// 3. Updates the global reputation state with the new rankings
+/// 3. Updates the reputation state of both pools with the new rankings
 ///
 /// # Returns
 /// - `Ok(())` if the cleanup succeeds
@@ -99,12 +153,8 @@ pub async fn daily_cleanup() {
 /// # Errors
 /// - Returns `ManagerError::DecodingError` if the random seed cannot be properly formatted
 pub async fn reputations_cleanup() -> ManagerResult<()> {
-    let mut providers = PROVIDERS.to_vec();
-
     // Create a seeded RNG using IC timestamp
-    let call_result = raw_rand().await;
-
-    let seed: Vec<u8> = extract_call_result(call_result)?;
+    let seed: Vec<u8> = management_call(ManagementCall::RawRand, raw_rand).await?;
 
     // Ensure the seed is exactly 32 bytes
     let seed_array: [u8; 32] = seed.try_into().map_err(|_| {
@@ -115,17 +165,19 @@ pub async fn reputations_cleanup() -> ManagerResult<()> {
 
     let mut rng = rand_chacha::ChaCha8Rng::from_seed(seed_array);
 
-    // Use standard shuffle with our seeded RNG
-    providers.shuffle(&mut rng);
+    for pool in [ProviderPool::Read, ProviderPool::Write] {
+        let mut providers = pool.members();
 
-    let new_reputations = providers
-        .into_iter()
-        .map(|provider| (0, provider))
-        .collect();
+        // Use standard shuffle with our seeded RNG
+        providers.shuffle(&mut rng);
 
-    RPC_REPUTATIONS.with(|reputations| {
-        *reputations.borrow_mut() = new_reputations;
-    });
+        let new_reputations = providers
+            .into_iter()
+            .map(|provider| (0, provider))
+            .collect();
+
+        pool.set_reputations(new_reputations);
+    }
 
     Ok(())
 }
@@ -134,51 +186,36 @@ pub async fn reputations_cleanup() -> ManagerResult<()> {
 ///
 /// This function performs two main cleanup operations:
 /// 1. Removes all provider reputation change log entries
-/// 2. Trims the journal to the most recent 300 entries if it exceeds that size
+/// 2. Trims the oldest collections until the journal satisfies the retention policy
 ///
-/// The cleanup process maintains only essential logs while preventing unbounded
-/// growth of the journal storage.
+/// The number of collections removed by each rule is added to the retention counters
+/// reported by `get_metrics`.
 pub fn journal_cleanup() {
-    crate::state::JOURNAL.with(|journal| {
-        let mut binding = journal.borrow_mut();
-
-        // Initialize a new StableVec safely and return if initialization fails
-        let temp = if let Ok(vec) =
-            ic_stable_structures::Vec::init(ic_stable_structures::DefaultMemoryImpl::default())
-        {
-            vec
-        } else {
-            return; // Exit if initialization fails
-        };
-
-        for collection in binding.iter() {
-            if !collection.is_reputation_change() {
-                let _ = temp.push(&collection.clone());
-            }
-        }
-
-        *binding = temp;
+    let removed_reputation_changes = JOURNAL
+        .with_borrow_mut(|journal| journal.retain(|collection| !collection.is_reputation_change()));
+
+    let policy = journal_retention();
+    let now = time() / 1_000_000_000;
+
+    let removed = JOURNAL.with_borrow_mut(|journal| {
+        let collections: Vec<(Option<u64>, u64)> = journal
+            .iter()
+            .map(|collection| {
+                let created_at = Some(collection.start_timestamp).filter(|start| *start != 0);
+                let size = Encode!(&collection).map_or(0, |bytes| bytes.len() as u64);
+                (created_at, size)
+            })
+            .collect();
+        let removed = policy.excess(&collections, now);
+
+        // The oldest collections are dropped by moving the head of the ring
+        journal.pop_front(removed.trimmed());
+
+        removed
     });
 
-    JOURNAL.with(|journal| {
-        let binding = journal.borrow_mut();
-
-        // Check if the journal has more than 300 items
-        let len = binding.len();
-        if len > 300 {
-            let excess = len - 300;
-
-            // Shift all items to remove the oldest ones
-            for i in excess..len {
-                if let Some(item) = binding.get(i) {
-                    binding.set(i - excess, &item);
-                }
-            }
-
-            // Pop the remaining items to resize the vector
-            for _ in 0..excess {
-                binding.pop();
-            }
-        }
+    record_journal_retention(&RetentionCounters {
+        reputation_changes: removed_reputation_changes,
+        ..removed
     });
 }
@@ -0,0 +1,186 @@
+//! Canister Clock
+//!
+//! Every read of the current time goes through `time()`, which has the same signature as
+//! `ic_cdk::api::time`. In the canister it forwards to the IC; with the `test-clock` feature
+//! it reads a thread-local clock instead, so that lock timeouts, halt scheduling, and cooldowns
+//! can be unit tested deterministically outside the IC environment.
+//!
+//! ```plain
+//! time() ──┬── default ──────► ic_cdk::api::time()
+//!          │
+//!          └── test-clock ───► TEST_CLOCK ◄── set_time() / advance()
+//!
+//! Seconds::now() ◄── time() / 1e9        Millis::now() ◄── time() / 1e6
+//! ```
+//!
+//! Timestamps and durations are kept in seconds across the canister. The `Seconds` and
+//! `Millis` wrappers make the unit of a value explicit where it is computed, and their
+//! arithmetic saturates instead of underflowing when a timestamp lies in the future, e.g.
+//! because it was recorded in another unit.
+
+#[cfg(feature = "test-clock")]
+use std::cell::Cell;
+
+/// Nanoseconds per second
+const NANOS_PER_SECOND: u64 = 1_000_000_000;
+
+/// Nanoseconds per millisecond
+const NANOS_PER_MILLI: u64 = 1_000_000;
+
+/// Seconds per day
+const SECONDS_PER_DAY: u64 = 86_400;
+
+/// A timestamp since the UNIX epoch or a duration, in seconds
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Seconds(pub u64);
+
+/// A timestamp since the UNIX epoch or a duration, in milliseconds
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Millis(pub u64);
+
+impl Seconds {
+    /// Returns the current time.
+    pub fn now() -> Self {
+        Self(time() / NANOS_PER_SECOND)
+    }
+
+    /// Returns a duration of `days` days.
+    pub const fn days(days: u64) -> Self {
+        Self(days.saturating_mul(SECONDS_PER_DAY))
+    }
+
+    /// Returns the raw number of seconds.
+    pub const fn get(self) -> u64 {
+        self.0
+    }
+
+    /// Returns the time elapsed from `earlier` to `self`, or zero if `earlier` is later.
+    pub const fn since(self, earlier: Seconds) -> Seconds {
+        Self(self.0.saturating_sub(earlier.0))
+    }
+
+    /// Returns `self - other`, or `None` if `other` is later.
+    pub const fn checked_sub(self, other: Seconds) -> Option<Seconds> {
+        match self.0.checked_sub(other.0) {
+            Some(seconds) => Some(Self(seconds)),
+            None => None,
+        }
+    }
+
+    /// Returns `self + other`, capped at `u64::MAX`.
+    pub const fn saturating_add(self, other: Seconds) -> Seconds {
+        Self(self.0.saturating_add(other.0))
+    }
+
+    /// Returns `true` if more than `age` elapsed from `self` to `now`.
+    ///
+    /// Timestamps in the future are never older than any age.
+    pub const fn is_older_than(self, age: Seconds, now: Seconds) -> bool {
+        now.since(self).0 > age.0
+    }
+
+    /// Converts to milliseconds, capped at `u64::MAX`.
+    pub const fn to_millis(self) -> Millis {
+        Millis(self.0.saturating_mul(1_000))
+    }
+}
+
+impl Millis {
+    /// Returns the current time.
+    pub fn now() -> Self {
+        Self(time() / NANOS_PER_MILLI)
+    }
+
+    /// Returns the raw number of milliseconds.
+    pub const fn get(self) -> u64 {
+        self.0
+    }
+
+    /// Returns the time elapsed from `earlier` to `self`, or zero if `earlier` is later.
+    pub const fn since(self, earlier: Millis) -> Millis {
+        Self(self.0.saturating_sub(earlier.0))
+    }
+
+    /// Converts to whole seconds, rounding down.
+    pub const fn to_seconds(self) -> Seconds {
+        Seconds(self.0 / 1_000)
+    }
+}
+
+#[cfg(feature = "test-clock")]
+thread_local! {
+    /// Current time in nanoseconds of the test clock
+    static TEST_CLOCK: Cell<u64> = const { Cell::new(0) };
+}
+
+/// Returns the current time in nanoseconds since the UNIX epoch.
+#[cfg(not(feature = "test-clock"))]
+pub fn time() -> u64 {
+    ic_exports::ic_cdk::api::time()
+}
+
+/// Returns the current time in nanoseconds of the test clock.
+#[cfg(feature = "test-clock")]
+pub fn time() -> u64 {
+    TEST_CLOCK.with(Cell::get)
+}
+
+/// Sets the test clock to `seconds` since the UNIX epoch.
+#[cfg(feature = "test-clock")]
+pub fn set_time(seconds: u64) {
+    TEST_CLOCK.with(|clock| clock.set(seconds.saturating_mul(1_000_000_000)));
+}
+
+/// Moves the test clock forward by `seconds`.
+#[cfg(feature = "test-clock")]
+pub fn advance(seconds: u64) {
+    TEST_CLOCK.with(|clock| {
+        clock.set(
+            clock
+                .get()
+                .saturating_add(seconds.saturating_mul(1_000_000_000)),
+        )
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "test-clock")]
+    #[test]
+    fn test_clock_is_deterministic() {
+        set_time(1_700_000_000);
+        assert_eq!(time(), 1_700_000_000_000_000_000);
+
+        advance(60);
+        assert_eq!(time() / 1_000_000_000, 1_700_000_060);
+        assert_eq!(Seconds::now(), Seconds(1_700_000_060));
+        assert_eq!(Millis::now(), Millis(1_700_000_060_000));
+    }
+
+    #[test]
+    fn test_time_math_saturates() {
+        let now = Seconds(1_000);
+        assert_eq!(now.since(Seconds(400)), Seconds(600));
+        // A timestamp in the future, e.g. recorded in milliseconds
+        assert_eq!(now.since(Seconds(5_000)), Seconds(0));
+        assert_eq!(now.checked_sub(Seconds(5_000)), None);
+        assert_eq!(Seconds(u64::MAX).saturating_add(now), Seconds(u64::MAX));
+        assert_eq!(Seconds(u64::MAX).to_millis(), Millis(u64::MAX));
+
+        assert!(Seconds(100).is_older_than(Seconds(800), now));
+        assert!(!Seconds(200).is_older_than(Seconds(800), now));
+        assert!(!Seconds(5_000).is_older_than(Seconds(0), now));
+        // Ages larger than the current time do not underflow
+        assert!(!Seconds(100).is_older_than(Seconds::days(90), now));
+    }
+
+    #[test]
+    fn test_unit_conversions() {
+        assert_eq!(Seconds::days(7), Seconds(604_800));
+        assert_eq!(Seconds(3).to_millis(), Millis(3_000));
+        assert_eq!(Millis(3_999).to_seconds(), Seconds(3));
+        assert_eq!(Millis(10).since(Millis(20)), Millis(0));
+    }
+}
@@ -11,6 +11,8 @@
 use alloy_primitives::U256;
 use candid::{Nat, Principal};
 
+use crate::clock::Seconds;
+
 /// Scale used for fixed point arithmetic
 pub const SCALE: u128 = 1_000_000_000_000_000_000; // e18
 
@@ -26,6 +28,16 @@ pub const CHAIN_ID: u64 = 11155111;
 #[cfg(feature = "mainnet")]
 pub const CHAIN_ID: u64 = 1;
 
+/// Default name of the threshold ECDSA key the EOAs are derived from
+pub const DEFAULT_ECDSA_KEY_NAME: &str = "key_1";
+
+/// Threshold ECDSA keys available to canisters: the production key, the test key, and the
+/// key of the local dfx replica
+pub const ECDSA_KEY_NAMES: &[&str] = &["key_1", "test_key_1", "dfx_test_key"];
+
+/// Payload whose hash is signed to check that a derivation path yields the strategy's EOA
+pub const SIGNER_TEST_PAYLOAD: &[u8] = b"bold-ir-management signer verification";
+
 /// Tolerance margin up formula constant
 const TOLERANCE_MARGIN_UP_RAW: u128 = 15 * SCALE / 100; // 15*10^16 => 15%
                                                         // const TOLERANCE_MARGIN_UP_RAW: u128 = SCALE / 100; // 1%
@@ -47,6 +59,63 @@ pub fn tolerance_margin_down() -> U256 {
 /// Max number of retry attempts
 pub const MAX_RETRY_ATTEMPTS: u8 = 2;
 
+/// Max number of attempts of a management canister call rejected as transient
+pub const MANAGEMENT_CALL_ATTEMPTS: u64 = 3;
+
+/// Window in seconds within which a repeated idempotent request is rejected
+pub const IDEMPOTENCY_WINDOW: u64 = 600;
+
+/// Max number of confirmed adjustments returned by one block range query
+pub const MAX_ADJUSTMENTS_PER_QUERY: usize = 500;
+
+/// Max number of rate adjustment records returned by one rate history query
+pub const MAX_RATE_HISTORY_PER_QUERY: usize = 500;
+
+/// Number of days of daily positioning samples kept per strategy
+pub const POSITIONING_HISTORY_DAYS: u64 = 90;
+
+/// Max number of journal entries served by one `/logs` export
+pub const MAX_EXPORTED_LOG_ENTRIES: usize = 1_000;
+
+/// Max number of journal collections returned by one page of the journal
+pub const MAX_LOGS_PER_PAGE: u64 = 100;
+
+/// Max number of single journal collection fetches per caller within `LOG_RATE_LIMIT_WINDOW`
+pub const LOG_RATE_LIMIT_CALLS: u64 = 120;
+
+/// Window in seconds of the single journal collection rate limit
+pub const LOG_RATE_LIMIT_WINDOW: u64 = 60;
+
+/// Max number of swaps per caller within `SWAP_RATE_LIMIT_WINDOW`
+pub const SWAP_RATE_LIMIT_CALLS: u64 = 10;
+
+/// Window in seconds of the swap rate limit
+pub const SWAP_RATE_LIMIT_WINDOW: u64 = 60;
+
+/// Max number of market snapshots per caller within `SNAPSHOT_RATE_LIMIT_WINDOW`
+pub const SNAPSHOT_RATE_LIMIT_CALLS: u64 = 5;
+
+/// Window in seconds of the market snapshot rate limit
+pub const SNAPSHOT_RATE_LIMIT_WINDOW: u64 = 300;
+
+/// Default base delay in seconds before resubmitting a rate adjustment transaction
+pub const RETRY_BASE_DELAY: u64 = 12; // one block
+
+/// Default upper bound in seconds for the delay between rate adjustment resubmissions
+pub const RETRY_MAX_DELAY: u64 = 120; // ten blocks
+
+/// Default base delay in seconds before retrying a failed strategy run
+pub const RUN_RETRY_BASE_DELAY: u64 = 30;
+
+/// Default upper bound in seconds for the delay between the attempts of a strategy run
+pub const RUN_RETRY_MAX_DELAY: u64 = 480; // 8 minutes
+
+/// Default jitter added to the delay between the attempts of a strategy run, in percent
+pub const RUN_RETRY_JITTER_PERCENT: u8 = 20;
+
+/// Seconds after its due time at which a run retry is considered lost, e.g. to an upgrade
+pub const RUN_RETRY_GRACE_PERIOD: u64 = 600;
+
 /// Max number of troves to fetch in one call
 pub const MAX_NUMBER_OF_TROVES: u128 = 75;
 
@@ -55,8 +124,35 @@ pub fn max_number_of_troves() -> U256 {
     U256::from(MAX_NUMBER_OF_TROVES)
 }
 
-/// Cycles balance threshold of the canister
-pub const CYCLES_THRESHOLD: u64 = 30_000_000_000_000;
+/// Default maximum age in seconds of the block an execution context is built on
+pub const DEFAULT_MAX_BLOCK_AGE: i64 = 120; // ten blocks
+
+/// Default maximum age in seconds of an execution context when signing begins
+pub const DEFAULT_MAX_CONTEXT_AGE: i64 = 300; // five minutes
+
+/// Default number of blocks a provider may lag behind the others before it loses reputation
+pub const DEFAULT_MAX_BLOCK_LAG: i64 = 3;
+
+/// Interval in seconds at which the read providers are ranked by block freshness
+pub const FRESHNESS_RANKING_INTERVAL: u64 = 600; // 10 minutes
+
+/// Default cap on the number of trove pages fetched per strategy run
+pub const DEFAULT_MAX_TROVE_PAGES: i64 = 40; // 3_000 troves
+
+/// Default number of trove list segments fetched at once (sequential fetching)
+pub const DEFAULT_TROVE_FETCH_CONCURRENCY: i64 = 1;
+
+/// Maximum number of trove list segments fetched at once
+pub const MAX_TROVE_FETCH_CONCURRENCY: i64 = 8;
+
+/// Default cycles balance threshold of the canister
+pub const DEFAULT_CYCLES_THRESHOLD: u64 = 30_000_000_000_000;
+
+/// Lowest configurable cycles balance threshold, which keeps the canister clear of freezing
+pub const MIN_CYCLES_THRESHOLD: u64 = 5_000_000_000_000;
+
+/// Highest configurable cycles balance threshold
+pub const MAX_CYCLES_THRESHOLD: u64 = 500_000_000_000_000;
 
 /// ckETH token transfer fee
 const CKETH_FEE_RAW: u64 = 2_000_000_000_000;
@@ -75,17 +171,84 @@ pub fn ether_recharge_value() -> U256 {
     U256::from(ETHER_RECHARGE_VALUE_RAW)
 }
 
-/// Cycles discount percentage
-pub const CYCLES_DISCOUNT_PERCENTAGE: u64 = 97; // 3% discount is provided
+/// Default maximum number of journal collections kept
+pub const DEFAULT_JOURNAL_MAX_COLLECTIONS: u64 = 300;
+
+/// Number of slots of the journal ring buffer, the upper bound of the retention policy
+pub const JOURNAL_CAPACITY: u64 = 1_000;
+
+/// Default age in seconds after which a fixed ETH/CXDR rate is reported as stale
+pub const DEFAULT_FIXED_RATE_MAX_AGE: i64 = 604_800; // 7 days
+
+/// Default cycles discount percentage
+pub const DEFAULT_CYCLES_DISCOUNT_PERCENTAGE: u64 = 97; // 3% discount is provided
+
+/// Lowest configurable cycles discount percentage
+pub const MIN_CYCLES_DISCOUNT_PERCENTAGE: u64 = 80; // 20% discount
 
-/// ckETH balance threshold of the canister.
+/// Default ckETH balance threshold of the canister.
 /// The recharging cycle will mint more ckETH if the balance falls below this number
-const CKETH_THRESHOLD_RAW: u64 = 30_000_000_000_000_000; // 0.03 ckETH
+pub const DEFAULT_CKETH_THRESHOLD: u64 = 30_000_000_000_000_000; // 0.03 ckETH
 
-/// Returns the ckETH balance threshold as a `Nat`.
-pub fn cketh_threshold() -> Nat {
-    Nat::from(CKETH_THRESHOLD_RAW)
-}
+/// Highest configurable ckETH balance threshold
+pub const MAX_CKETH_THRESHOLD: u64 = 1_000_000_000_000_000_000; // 1 ckETH
+
+/// Window in seconds over which the swap volume is aggregated
+pub const SWAP_VOLUME_WINDOW: u64 = 86_400; // 1 day
+
+/// Seconds after which an unsettled ckETH mint is no longer reported as pending
+pub const PENDING_MINT_EXPIRY: u64 = 21_600; // 6 hours
+
+/// Interval in seconds at which the chain head is polled for strategies triggered by new blocks
+pub const CHAIN_HEAD_POLL_INTERVAL: u64 = 60; // five blocks
+
+/// Interval in seconds at which the cached ckETH and cycles balances are refreshed
+pub const BALANCE_REFRESH_INTERVAL: u64 = 3_600; // 1 hour
+
+/// Seconds after which a cached balance is reported as stale
+pub const BALANCE_STALENESS_THRESHOLD: u64 = 7_200; // two missed refreshes
+
+/// Seconds without a successful run after which a strategy is reported as stale
+pub const STALE_STRATEGY_THRESHOLD: u64 = 7_200; // two hourly runs
+
+/// Window in seconds over which the gas spend of a strategy is checked against its budget
+pub const GAS_BUDGET_WINDOW: u64 = 2_592_000; // 30 days
+
+/// Window in seconds over which the upfront fees of a strategy are checked against its budget
+pub const UPFRONT_FEE_BUDGET_WINDOW: u64 = 2_592_000; // 30 days
+
+/// Seconds between the announcement of a halt and the halt taking effect
+pub const HALT_DELAY: u64 = 604_800; // 7 days
+
+/// Period in seconds covered by the daily digest
+pub const DIGEST_PERIOD: u64 = 86_400; // 1 day
+
+/// Cycles attached to a webhook HTTPS outcall, the unused part is refunded
+pub const WEBHOOK_CYCLES: u128 = 2_000_000_000;
+
+/// Maximum number of posts of a notification to the webhook
+pub const WEBHOOK_MAX_ATTEMPTS: u32 = 4;
+
+/// Cycles attached to a UserOperation submission to a bundler, the unused part is refunded
+pub const BUNDLER_CYCLES: u128 = 2_000_000_000;
+
+/// Delay in seconds before the first retry of a failed notification, doubled on every retry
+pub const WEBHOOK_RETRY_DELAY: u64 = 30;
+
+/// Default number of failed runs in a row after which a strategy raises an alert
+pub const DEFAULT_FAILURE_ALERT_THRESHOLD: i64 = 3;
+
+/// Default number of failed runs in a row after which a single strategy halts the canister
+pub const DEFAULT_FAILURE_HALT_THRESHOLD: i64 = 48;
+
+/// Default ETH balance in wei below which a strategy EOA is reported by the daily sweep
+pub const DEFAULT_EOA_BALANCE_WARNING_THRESHOLD: i64 = 20_000_000_000_000_000; // 0.02 ETH
+
+/// Default ETH balance in wei below which a strategy EOA is refilled by another EOA
+pub const DEFAULT_GAS_REFILL_FLOOR: i64 = 10_000_000_000_000_000; // 0.01 ETH
+
+/// Default ETH amount in wei transferred by a gas refill
+pub const DEFAULT_GAS_REFILL_AMOUNT: i64 = 50_000_000_000_000_000; // 0.05 ETH
 
 /// Default max response bytes
 pub const DEFAULT_MAX_RESPONSE_BYTES: u64 = 8_000;
@@ -103,6 +266,46 @@ pub fn exchange_rate_canister() -> Principal {
         .expect("Invalid principal ID for the exchange rate canister.")
 }
 
+/// ICP ledger canister's principal ID
+const ICP_LEDGER_RAW: &str = "ryjl3-tyaaa-aaaaa-aaaba-cai";
+
+/// Returns the Principal for the ICP ledger canister.
+///
+/// # Panics
+/// This function will panic if the hardcoded principal string is invalid.
+/// The panic should be caught by the unit tests.
+pub fn icp_ledger() -> Principal {
+    Principal::from_text(ICP_LEDGER_RAW).expect("Invalid principal ID for the ICP ledger.")
+}
+
+/// Cycles minting canister's principal ID
+const CYCLES_MINTING_CANISTER_RAW: &str = "rkp4c-7iaaa-aaaaa-aaaaq-cai";
+
+/// Returns the Principal for the cycles minting canister.
+///
+/// # Panics
+/// This function will panic if the hardcoded principal string is invalid.
+/// The panic should be caught by the unit tests.
+pub fn cycles_minting_canister() -> Principal {
+    Principal::from_text(CYCLES_MINTING_CANISTER_RAW)
+        .expect("Invalid principal ID for the cycles minting canister.")
+}
+
+/// ICP ledger transfer fee in e8s
+pub const ICP_TRANSFER_FEE: u64 = 10_000;
+
+/// Memo the cycles minting canister expects on canister top-up transfers ("TPUP")
+pub const MEMO_TOP_UP_CANISTER: u64 = 0x5055_5054;
+
+/// Default cycles balance below which the canister tops itself up through the CMC
+pub const DEFAULT_CRITICAL_CYCLES_FLOOR: i64 = 10_000_000_000_000;
+
+/// Default ICP amount in e8s converted to cycles by each CMC top-up
+pub const DEFAULT_CMC_TOP_UP_AMOUNT: i64 = 100_000_000; // 1 ICP
+
+/// Multicall3 contract, deployed at the same address on Ethereum mainnet and its testnets
+pub const MULTICALL3: &str = "0xcA11bde05977b3631167028862bE2a173976CA11";
+
 /// ckETH smart contract on Ethereum mainnet
 #[cfg(feature = "mainnet")]
 pub const CKETH_HELPER: &str = "0x18901044688D3756C35Ed2b36D93e6a5B8e00E68";
@@ -127,8 +330,50 @@ pub const PROVIDER_COUNT: u8 = 3;
 /// Number of providers needed to reach consensus
 pub const PROVIDER_THRESHOLD: u8 = 2;
 
-/// Timeout in milliseconds for strategy locks
-pub const STRATEGY_LOCK_TIMEOUT: u64 = 3_600_000; // one hour
+/// Maximum number of custom RPC providers across both pools
+pub const MAX_CUSTOM_PROVIDERS: usize = 12;
+
+/// Default number of consecutive `NoConsensus` outcomes after which an alert is raised
+pub const DEFAULT_NO_CONSENSUS_THRESHOLD: i64 = 3;
+
+/// Seconds during which reads use the top-ranked provider alone after persistent `NoConsensus` outcomes
+pub const CONSENSUS_FALLBACK_DURATION: u64 = 3_600; // one hour
+
+/// Timeout of strategy locks
+pub const STRATEGY_LOCK_TIMEOUT: Seconds = Seconds(3_600); // one hour
+
+/// Window in seconds over which strategy lock auto-unlocks are counted for warnings
+pub const AUTO_UNLOCK_WINDOW: u64 = 604_800; // 7 days
+
+/// Default number of auto-unlocks of a strategy per `AUTO_UNLOCK_WINDOW` above which a warning is journaled
+pub const DEFAULT_AUTO_UNLOCK_WARNING_THRESHOLD: i64 = 2;
+
+/// Default number of simulation-only runs of a freshly minted, resumed, or unhalted strategy (disabled)
+pub const DEFAULT_WARMUP_RUNS: i64 = 0;
+
+/// Default number of blocks after which a pending transaction is replaced with bumped fees
+pub const DEFAULT_STUCK_TX_BLOCKS: i64 = 25; // about five minutes
+
+/// Default percentage by which the fees of a replaced transaction are bumped
+pub const DEFAULT_TX_FEE_BUMP_PERCENT: i64 = 20;
+
+/// Minimum fee bump in percent accepted by the mempools for a replacement transaction
+pub const MIN_TX_FEE_BUMP_PERCENT: u64 = 10;
+
+/// Maximum number of replacements sent for the same nonce
+pub const MAX_TX_REPLACEMENTS: usize = 5;
+
+/// Gas limit of the self-transfer that cancels a pending transaction
+pub const CANCEL_TX_GAS_LIMIT: u128 = 21_000;
+
+/// Default percentile of the recent tips sampled to price a transaction
+pub const DEFAULT_FEE_PERCENTILE: u8 = 95;
+
+/// Default lowest priority fee per gas in wei
+pub const DEFAULT_MIN_PRIORITY_FEE_PER_GAS: u64 = 1_500_000_000;
+
+/// Highest multiplier in percent a fee policy may apply to the estimated fees
+pub const MAX_FEE_MULTIPLIER_PERCENT: u64 = 300;
 
 /// Sepolia providers
 #[cfg(feature = "sepolia")]
@@ -172,6 +417,19 @@ mod tests {
         );
     }
 
+    #[test]
+    fn cmc_canisters_are_correct() {
+        assert_eq!(
+            icp_ledger().to_text(),
+            "ryjl3-tyaaa-aaaaa-aaaba-cai".to_string()
+        );
+        assert_eq!(
+            cycles_minting_canister().to_text(),
+            "rkp4c-7iaaa-aaaaa-aaaaq-cai".to_string()
+        );
+        assert_eq!(MEMO_TOP_UP_CANISTER.to_le_bytes()[..4], *b"TPUP");
+    }
+
     #[test]
     fn scale_is_e18() {
         assert_eq!(SCALE, 10_u128.pow(18));
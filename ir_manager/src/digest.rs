@@ -0,0 +1,511 @@
+//! Daily Digest
+//!
+//! Once a day, the activity of the past `DIGEST_PERIOD` seconds is aggregated into a single
+//! journal entry, and optionally posted to an operator webhook, so that operators get a
+//! one-glance summary without querying several endpoints.
+//!
+//! ```plain
+//! RUNS ──────► runs, failures ─────────────┐
+//! TX_POOL ───► adjustments, gas spent ─────┼──► DailyDigest ──► journal
+//! TREASURY ──► ckETH minted and swapped ───┤                └─► webhook (optional)
+//! cycles balance delta ────────────────────┘
+//! ```
+//!
+//! High-severity alerts and notices are journaled and posted by `raise_alert` and
+//! `raise_notice`, to the alert webhook if one is set (e.g. a paging endpoint) and to the
+//! digest webhook otherwise. They cover the halt transitions, the custom triggers, and
+//! strategies failing `failure_alert_threshold` runs in a row. Failed deliveries are retried
+//! with an exponential backoff:
+//!
+//! ```plain
+//! raise_alert ──► journal ──► post ──fails──► journal, retry in WEBHOOK_RETRY_DELAY * 2^attempt
+//!                                                       (at most WEBHOOK_MAX_ATTEMPTS posts)
+//! ```
+//!
+//! The webhook receives a JSON body of the form `{"text": "<digest>"}`, which is accepted
+//! by most chat incoming webhooks. Responses are reduced to their status code by
+//! `transform_webhook_response`, so that all replicas agree on them.
+//!
+//! The post is a replicated HTTPS outcall: every replica of the subnet sends it, so the webhook
+//! receives one copy per replica. Each post carries an `Idempotency-Key` header, the end of the
+//! period for a digest and a hash of the text and time for a notification, kept across retries.
+//! Receivers must drop the posts whose key they have already seen.
+
+use std::{collections::BTreeMap, fmt, time::Duration};
+
+use alloy_primitives::keccak256;
+use candid::{CandidType, Nat};
+use ic_exports::ic_cdk::api::management_canister::http_request::{
+    http_request, CanisterHttpRequestArgument, HttpHeader, HttpMethod, HttpResponse, TransformArgs,
+    TransformContext,
+};
+use ic_exports::{ic_cdk::spawn, ic_cdk_timers::set_timer};
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::{
+    clock::time,
+    constants::{
+        DEFAULT_FAILURE_ALERT_THRESHOLD, DEFAULT_MAX_RESPONSE_BYTES, DIGEST_PERIOD, WEBHOOK_CYCLES,
+        WEBHOOK_MAX_ATTEMPTS, WEBHOOK_RETRY_DELAY,
+    },
+    flags::{flag_int, FAILURE_ALERT_THRESHOLD},
+    journal::{JournalCollection, LogType},
+    runs::RunSummary,
+    state::{DIGEST, RUNS, TX_POOL},
+    treasury::{minted_since, treasury},
+    tx_pool::{TxRecord, TxStatus},
+    utils::{
+        common::extract_call_result,
+        error::{ManagerError, ManagerResult},
+    },
+};
+
+/// Activity of a single strategy over the digest period
+#[derive(CandidType, Clone, Debug, Default, PartialEq)]
+pub struct StrategyDigest {
+    /// Key of the strategy
+    pub strategy: u32,
+    /// Number of runs started
+    pub runs: u64,
+    /// Number of runs that finished with an error
+    pub failed_runs: u64,
+    /// Number of rate adjustment transactions submitted
+    pub adjustments: u64,
+    /// Number of transactions that reverted or were dropped
+    pub failed_transactions: u64,
+    /// Gas fees in wei paid by the strategy EOA for the transactions confirmed in the period
+    pub gas_spent: Nat,
+}
+
+/// Activity of the canister over the digest period
+#[derive(CandidType, Clone, Debug, PartialEq)]
+pub struct DailyDigest {
+    /// Start timestamp in seconds of the period
+    pub from: u64,
+    /// End timestamp in seconds of the period
+    pub to: u64,
+    /// Activity of each strategy, sorted by key
+    pub strategies: Vec<StrategyDigest>,
+    /// Cycles spent since the previous digest, `None` for the first digest
+    pub cycles_spent: Option<Nat>,
+    /// ETH in wei deposited for ckETH mints
+    pub cketh_minted: Nat,
+    /// ckETH transferred to arbitrageurs in swaps
+    pub cketh_swapped: Nat,
+    /// Cycles accepted from arbitrageurs in swaps
+    pub cycles_received: Nat,
+}
+
+impl fmt::Display for DailyDigest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Daily digest ({} - {}):", self.from, self.to)?;
+        for strategy in &self.strategies {
+            write!(
+                f,
+                " strategy {}: {} runs ({} failed), {} adjustments, {} failed transactions, {} wei gas;",
+                strategy.strategy,
+                strategy.runs,
+                strategy.failed_runs,
+                strategy.adjustments,
+                strategy.failed_transactions,
+                strategy.gas_spent
+            )?;
+        }
+        match &self.cycles_spent {
+            Some(cycles_spent) => write!(f, " cycles spent: {},", cycles_spent)?,
+            None => write!(f, " cycles spent: unknown,")?,
+        }
+        write!(
+            f,
+            " ckETH minted: {} wei, ckETH swapped: {} for {} cycles.",
+            self.cketh_minted, self.cketh_swapped, self.cycles_received
+        )
+    }
+}
+
+/// Digest bookkeeping kept between digests
+#[derive(CandidType, Deserialize, Clone, Debug, Default)]
+pub struct DigestState {
+    /// URL the digests are posted to
+    pub webhook_url: Option<String>,
+    /// URL the alerts and notices are posted to, instead of `webhook_url`
+    pub alert_webhook_url: Option<String>,
+    /// Cycles balance observed at the previous digest
+    pub last_cycles_balance: Option<Nat>,
+}
+
+/// Returns the digest of a strategy, inserting an empty one if missing.
+fn entry(digests: &mut BTreeMap<u32, StrategyDigest>, strategy: u32) -> &mut StrategyDigest {
+    digests.entry(strategy).or_insert_with(|| StrategyDigest {
+        strategy,
+        gas_spent: Nat::from(0_u8),
+        ..Default::default()
+    })
+}
+
+/// Aggregates the runs and transactions of the period ending at `now` (in seconds).
+pub fn aggregate_strategies<'a>(
+    runs: impl IntoIterator<Item = RunSummary>,
+    transactions: impl IntoIterator<Item = &'a TxRecord>,
+    now: u64,
+) -> Vec<StrategyDigest> {
+    let from = now.saturating_sub(DIGEST_PERIOD);
+    let mut digests: BTreeMap<u32, StrategyDigest> = BTreeMap::new();
+
+    for run in runs.into_iter().filter(|run| run.started_at >= from) {
+        let digest = entry(&mut digests, run.strategy);
+        digest.runs += 1;
+        if matches!(run.result, Some(Err(_))) {
+            digest.failed_runs += 1;
+        }
+    }
+
+    for tx in transactions {
+        if tx.submitted_at >= from && tx.run_id.is_some() {
+            entry(&mut digests, tx.strategy).adjustments += 1;
+        }
+        if tx.updated_at < from {
+            continue;
+        }
+        match tx.status {
+            TxStatus::Confirmed {
+                gas_used,
+                effective_gas_price,
+                success,
+                ..
+            } => {
+                let digest = entry(&mut digests, tx.strategy);
+                let fee = u128::from(gas_used).saturating_mul(effective_gas_price);
+                digest.gas_spent = digest.gas_spent.clone() + Nat::from(fee);
+                if !success {
+                    digest.failed_transactions += 1;
+                }
+            }
+            TxStatus::Dropped => entry(&mut digests, tx.strategy).failed_transactions += 1,
+            TxStatus::Pending | TxStatus::Replaced { .. } => {}
+        }
+    }
+
+    digests.into_values().collect()
+}
+
+/// Builds the digest of the period ending at `now` (in seconds), given the current cycles balance.
+pub fn daily_digest(cycles_balance: Nat, now: u64) -> DailyDigest {
+    let strategies = RUNS.with(|runs| {
+        TX_POOL.with(|pool| {
+            let pool = pool.borrow();
+            let transactions: Vec<TxRecord> = pool.iter().map(|(_, tx)| tx).collect();
+            aggregate_strategies(
+                runs.borrow().iter().map(|(_, run)| run),
+                transactions.iter(),
+                now,
+            )
+        })
+    });
+
+    let swap_volume = treasury(cycles_balance.clone(), now).swap_volume;
+    let previous_balance = DIGEST.with(|digest| {
+        digest
+            .borrow_mut()
+            .last_cycles_balance
+            .replace(cycles_balance.clone())
+    });
+    let cycles_spent = previous_balance.map(|previous| {
+        let available = previous + swap_volume.accepted_cycles.clone();
+        if available > cycles_balance {
+            available - cycles_balance
+        } else {
+            Nat::from(0_u8)
+        }
+    });
+
+    DailyDigest {
+        from: now.saturating_sub(DIGEST_PERIOD),
+        to: now,
+        strategies,
+        cycles_spent,
+        cketh_minted: minted_since(now.saturating_sub(DIGEST_PERIOD)),
+        cketh_swapped: swap_volume.returned_cketh,
+        cycles_received: swap_volume.accepted_cycles,
+    }
+}
+
+/// Checks that a webhook URL uses HTTPS.
+fn validate_webhook_url(url: &Option<String>) -> ManagerResult<()> {
+    match url {
+        Some(url) if !url.starts_with("https://") => Err(ManagerError::Custom(
+            "The webhook URL must use HTTPS.".to_string(),
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Sets the URL the digests are posted to, or disables the webhook.
+pub fn set_webhook_url(url: Option<String>) -> ManagerResult<()> {
+    validate_webhook_url(&url)?;
+    DIGEST.with(|digest| digest.borrow_mut().webhook_url = url);
+    Ok(())
+}
+
+/// Sets the URL the alerts and notices are posted to, or falls back to the digest webhook.
+pub fn set_alert_webhook_url(url: Option<String>) -> ManagerResult<()> {
+    validate_webhook_url(&url)?;
+    DIGEST.with(|digest| digest.borrow_mut().alert_webhook_url = url);
+    Ok(())
+}
+
+/// Returns the URL the alerts and notices are posted to, if any.
+fn alert_webhook_url() -> Option<String> {
+    DIGEST.with(|state| {
+        let state = state.borrow();
+        state
+            .alert_webhook_url
+            .clone()
+            .or_else(|| state.webhook_url.clone())
+    })
+}
+
+/// Builds the daily digest, journals it, and posts it to the webhook if one is set.
+pub async fn publish_daily_digest(cycles_balance: Nat, now: u64) {
+    let mut journal = JournalCollection::open(None);
+    let digest = daily_digest(cycles_balance, now);
+    journal.append_note(Ok(()), LogType::Info, digest.to_string());
+
+    let Some(url) = DIGEST.with(|state| state.borrow().webhook_url.clone()) else {
+        return;
+    };
+    let idempotency_key = format!("digest-{}", digest.to);
+    if let Err(err) = post_webhook(url, digest.to_string(), idempotency_key).await {
+        journal.append_note(
+            Err(err),
+            LogType::Info,
+            "Failed to post the daily digest to the webhook.",
+        );
+    }
+}
+
+/// Journals a high-severity alert and posts it to the webhook if one is set.
+pub fn raise_alert(message: String) {
+    push_notification(format!("CRITICAL: {}", message));
+}
+
+/// Journals an informational notice and posts it to the webhook if one is set.
+pub fn raise_notice(message: String) {
+    push_notification(format!("NOTICE: {}", message));
+}
+
+/// Journals a notification and posts it to the alert webhook if one is set.
+fn push_notification(text: String) {
+    JournalCollection::open(None).append_note(Ok(()), LogType::Info, &text);
+
+    if let Some(url) = alert_webhook_url() {
+        // All replicas agree on the time, so they derive the same key
+        let idempotency_key = format!("notification-{}", keccak256(format!("{}:{}", time(), text)));
+        deliver_notification(url, text, idempotency_key, 0);
+    }
+}
+
+/// Posts a notification to the webhook, retrying a failed delivery with the same idempotency
+/// key after `retry_delay(attempt)` seconds.
+fn deliver_notification(url: String, text: String, idempotency_key: String, attempt: u32) {
+    spawn(async move {
+        let Err(err) = post_webhook(url.clone(), text.clone(), idempotency_key.clone()).await
+        else {
+            return;
+        };
+        let Some(delay) = retry_delay(attempt) else {
+            JournalCollection::open(None).append_note(
+                Err(err),
+                LogType::Info,
+                format!(
+                    "Failed to post a notification to the webhook after {} attempts.",
+                    WEBHOOK_MAX_ATTEMPTS
+                ),
+            );
+            return;
+        };
+        JournalCollection::open(None).append_note(
+            Err(err),
+            LogType::Info,
+            format!(
+                "Failed to post a notification to the webhook (attempt {}/{}). Retrying in {} seconds.",
+                attempt + 1,
+                WEBHOOK_MAX_ATTEMPTS,
+                delay
+            ),
+        );
+        set_timer(Duration::from_secs(delay), move || {
+            deliver_notification(url, text, idempotency_key, attempt + 1)
+        });
+    });
+}
+
+/// Returns the delay in seconds before retrying a delivery that failed at `attempt`
+/// (zero-based), or `None` once all attempts are used.
+fn retry_delay(attempt: u32) -> Option<u64> {
+    (attempt + 1 < WEBHOOK_MAX_ATTEMPTS).then(|| WEBHOOK_RETRY_DELAY << attempt)
+}
+
+/// Raises an alert when a strategy reaches `failure_alert_threshold` failed runs in a row.
+///
+/// The alert is raised once per failure streak.
+pub fn alert_on_failure_streak(strategy: u32, consecutive_failures: u64) {
+    let threshold = flag_int!(FAILURE_ALERT_THRESHOLD, DEFAULT_FAILURE_ALERT_THRESHOLD);
+    if threshold > 0 && consecutive_failures == threshold as u64 {
+        raise_alert(format!(
+            "Strategy {} failed {} runs in a row.",
+            strategy, consecutive_failures
+        ));
+    }
+}
+
+/// Posts a text message to the webhook, with the key receivers dedupe the replicas' copies by.
+async fn post_webhook(url: String, text: String, idempotency_key: String) -> ManagerResult<()> {
+    let request = CanisterHttpRequestArgument {
+        url,
+        max_response_bytes: Some(DEFAULT_MAX_RESPONSE_BYTES),
+        method: HttpMethod::POST,
+        headers: vec![
+            HttpHeader {
+                name: "Content-Type".to_string(),
+                value: "application/json".to_string(),
+            },
+            HttpHeader {
+                name: "Idempotency-Key".to_string(),
+                value: idempotency_key,
+            },
+        ],
+        body: Some(json!({ "text": text }).to_string().into_bytes()),
+        transform: Some(TransformContext::from_name(
+            "transform_webhook_response".to_string(),
+            vec![],
+        )),
+    };
+
+    let response = extract_call_result(http_request(request, WEBHOOK_CYCLES).await)?;
+    if response.status >= Nat::from(300_u16) {
+        return Err(ManagerError::Custom(format!(
+            "The webhook responded with status {}.",
+            response.status
+        )));
+    }
+    Ok(())
+}
+
+/// Reduces a webhook response to its status code, so that all replicas agree on it.
+pub fn transform_webhook_response(args: TransformArgs) -> HttpResponse {
+    HttpResponse {
+        status: args.response.status,
+        headers: vec![],
+        body: vec![],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(strategy: u32, started_at: u64, result: Option<ManagerResult<()>>) -> RunSummary {
+        RunSummary {
+            run_id: 0,
+            strategy,
+            started_at,
+            finished_at: None,
+            attempts: 1,
+            result,
+        }
+    }
+
+    fn tx(strategy: u32, run_id: Option<u64>, status: TxStatus, at: u64) -> TxRecord {
+        TxRecord {
+            id: 0,
+            strategy,
+            run_id,
+            nonce: 0,
+            hash: String::new(),
+            raw: String::new(),
+            status,
+            submitted_at: at,
+            updated_at: at,
+            rpc_canister: None,
+            submitted_block: None,
+            upfront_fee: None,
+        }
+    }
+
+    #[test]
+    fn test_aggregate_strategies() {
+        let now = 2 * DIGEST_PERIOD;
+        let runs = vec![
+            run(1, now - 10, Some(Ok(()))),
+            run(1, now - 20, Some(Err(ManagerError::Locked))),
+            run(1, 10, Some(Ok(()))),
+            run(2, now - 30, None),
+        ];
+        let confirmed = |success| TxStatus::Confirmed {
+            block_number: 1,
+            gas_used: 21_000,
+            effective_gas_price: 10,
+            success,
+        };
+        let transactions = vec![
+            tx(1, Some(1), confirmed(true), now - 10),
+            tx(1, None, confirmed(false), now - 10),
+            tx(2, Some(3), TxStatus::Dropped, now - 30),
+            tx(2, Some(4), confirmed(true), 10),
+        ];
+
+        let digests = aggregate_strategies(runs, transactions.iter(), now);
+        assert_eq!(
+            digests,
+            vec![
+                StrategyDigest {
+                    strategy: 1,
+                    runs: 2,
+                    failed_runs: 1,
+                    adjustments: 1,
+                    failed_transactions: 1,
+                    gas_spent: Nat::from(420_000_u32),
+                },
+                StrategyDigest {
+                    strategy: 2,
+                    runs: 1,
+                    failed_runs: 0,
+                    adjustments: 1,
+                    failed_transactions: 1,
+                    gas_spent: Nat::from(0_u8),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_webhook_url_must_use_https() {
+        assert!(set_webhook_url(Some("http://example.com".to_string())).is_err());
+        assert!(set_webhook_url(Some("https://example.com".to_string())).is_ok());
+        assert!(set_webhook_url(None).is_ok());
+        assert!(set_alert_webhook_url(Some("http://example.com".to_string())).is_err());
+    }
+
+    #[test]
+    fn test_alerts_fall_back_to_the_digest_webhook() {
+        set_webhook_url(Some("https://digest.example.com".to_string())).unwrap();
+        assert_eq!(
+            alert_webhook_url(),
+            Some("https://digest.example.com".to_string())
+        );
+        set_alert_webhook_url(Some("https://alerts.example.com".to_string())).unwrap();
+        assert_eq!(
+            alert_webhook_url(),
+            Some("https://alerts.example.com".to_string())
+        );
+    }
+
+    #[test]
+    fn test_retry_delay_backs_off() {
+        assert_eq!(retry_delay(0), Some(WEBHOOK_RETRY_DELAY));
+        assert_eq!(retry_delay(1), Some(WEBHOOK_RETRY_DELAY * 2));
+        assert_eq!(retry_delay(WEBHOOK_MAX_ATTEMPTS - 1), None);
+    }
+}
@@ -0,0 +1,138 @@
+//! EOA Balance Monitoring
+//!
+//! Strategies fail with `InsufficientFunds` once their EOA cannot pay for gas anymore. A daily
+//! sweep observes the ETH balance of every strategy EOA, so that a controller is warned before
+//! the rate adjustments stop:
+//!
+//! ```plain
+//! every day ──► per strategy EOA: fetch_balance ──► StrategyData.eoa_balance
+//!                                                 │   (and the treasury)
+//!                                                 ▼
+//!                                  below EOA_BALANCE_WARNING_THRESHOLD?
+//!                                                 │ yes
+//!                                                 ▼
+//!                                 journal warning + alert webhook
+//! ```
+//!
+//! The EOA of a strategy submitting UserOperations only signs, so its balance is observed
+//! without warnings.
+
+use alloy_primitives::U256;
+
+use crate::{
+    charger::fetch_balance,
+    clock::time,
+    constants::DEFAULT_EOA_BALANCE_WARNING_THRESHOLD,
+    digest::raise_alert,
+    flags::{flag_int, EOA_BALANCE_WARNING_THRESHOLD},
+    journal::{JournalCollection, LogType},
+    rpc_registry::resolve_rpc_canister,
+    state::STRATEGY_STATE,
+    strategy::{settings::ExecutionBackend, stable::update_strategy},
+    treasury::{record_eoa_balance, CachedBalance},
+    utils::{common::u256_to_nat, error::ManagerError},
+};
+
+/// Returns the balance in wei below which an EOA is reported, or `None` if the warnings are
+/// disabled.
+fn warning_threshold() -> Option<U256> {
+    let threshold = flag_int!(
+        EOA_BALANCE_WARNING_THRESHOLD,
+        DEFAULT_EOA_BALANCE_WARNING_THRESHOLD
+    );
+    (threshold > 0).then(|| U256::from(threshold as u64))
+}
+
+/// Returns `true` if `balance` is below the warning threshold.
+fn is_low_balance(balance: U256, threshold: Option<U256>) -> bool {
+    threshold.is_some_and(|threshold| balance < threshold)
+}
+
+/// Observes the ETH balance of every strategy EOA and warns about the ones running low.
+///
+/// A strategy whose balance cannot be fetched is journaled and skipped until the next sweep.
+pub async fn sweep_eoa_balances() {
+    let eoas: Vec<_> = STRATEGY_STATE.with(|strategies| {
+        strategies
+            .borrow()
+            .values()
+            .filter_map(|strategy| {
+                strategy.settings.eoa_pk.map(|eoa| {
+                    (
+                        strategy.settings.key,
+                        eoa,
+                        strategy.settings.rpc_canister.clone(),
+                        strategy.settings.execution_backend == ExecutionBackend::Eoa,
+                    )
+                })
+            })
+            .collect()
+    });
+
+    for (key, eoa, rpc_canister, pays_gas) in eoas {
+        let mut journal = JournalCollection::open(Some(key));
+        let now = time() / 1_000_000_000;
+        let rpc_canister = resolve_rpc_canister(&rpc_canister, now);
+
+        let balance = match fetch_balance(&rpc_canister, eoa.to_string()).await {
+            Ok(balance) => balance,
+            Err(err) => {
+                journal.append_note(
+                    Err(err),
+                    LogType::Info,
+                    format!("Failed to fetch the ETH balance of EOA {}.", eoa),
+                );
+                continue;
+            }
+        };
+
+        record_eoa_balance(key, eoa.to_string(), &balance, now);
+        if let Ok(amount) = u256_to_nat(&balance) {
+            let _ = update_strategy(key, |strategy| {
+                strategy.data.eoa_balance = Some(CachedBalance {
+                    amount,
+                    updated_at: now,
+                })
+            });
+        }
+
+        let threshold = warning_threshold();
+        if !pays_gas || !is_low_balance(balance, threshold) {
+            continue;
+        }
+        let message = format!(
+            "The EOA {} of strategy {} holds {} wei, below the warning threshold of {} wei. Rate adjustments fail once it cannot pay for gas.",
+            eoa,
+            key,
+            balance,
+            threshold.unwrap_or_default()
+        );
+        journal.append_note(
+            Err(ManagerError::Custom(message.clone())),
+            LogType::Info,
+            "The EOA balance is running low. Top it up with ETH.",
+        );
+        raise_alert(message);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::flags::{set_flag, FlagValue};
+
+    #[test]
+    fn test_low_balance_threshold() {
+        let threshold = warning_threshold();
+        assert_eq!(
+            threshold,
+            Some(U256::from(DEFAULT_EOA_BALANCE_WARNING_THRESHOLD as u64))
+        );
+        assert!(is_low_balance(U256::ZERO, threshold));
+        assert!(!is_low_balance(threshold.unwrap_or_default(), threshold));
+
+        set_flag(EOA_BALANCE_WARNING_THRESHOLD, FlagValue::Int(0)).unwrap();
+        assert_eq!(warning_threshold(), None);
+        assert!(!is_low_balance(U256::ZERO, warning_threshold()));
+    }
+}
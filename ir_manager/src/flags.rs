@@ -0,0 +1,383 @@
+//! Runtime Feature Flags
+//!
+//! A registry of named boolean and integer flags stored in stable memory.
+//! Flags allow behaviors to ship dark and be enabled per deployment by a controller,
+//! without redeploying the canister.
+//!
+//! ```plain
+//! Flag Resolution:
+//!
+//! flag_enabled!(NAME) ──► Stable override? ──yes──► override value
+//!                                │
+//!                                no
+//!                                ▼
+//!                         Default from FLAGS
+//! ```
+//!
+//! Every flag must be declared in `FLAGS` with a default value and a description.
+//! Code paths are gated with the `flag_enabled!` and `flag_int!` macros.
+
+use std::borrow::Cow;
+
+use candid::{CandidType, Decode, Encode};
+use ic_stable_structures::{storable::Bound, Storable};
+use serde::Deserialize;
+
+use crate::{
+    constants::{
+        DEFAULT_AUTO_UNLOCK_WARNING_THRESHOLD, DEFAULT_CMC_TOP_UP_AMOUNT,
+        DEFAULT_CRITICAL_CYCLES_FLOOR, DEFAULT_EOA_BALANCE_WARNING_THRESHOLD,
+        DEFAULT_FAILURE_ALERT_THRESHOLD, DEFAULT_FAILURE_HALT_THRESHOLD,
+        DEFAULT_FIXED_RATE_MAX_AGE, DEFAULT_GAS_REFILL_AMOUNT, DEFAULT_GAS_REFILL_FLOOR,
+        DEFAULT_MAX_BLOCK_AGE, DEFAULT_MAX_BLOCK_LAG, DEFAULT_MAX_CONTEXT_AGE,
+        DEFAULT_MAX_TROVE_PAGES, DEFAULT_NO_CONSENSUS_THRESHOLD, DEFAULT_STUCK_TX_BLOCKS,
+        DEFAULT_TROVE_FETCH_CONCURRENCY, DEFAULT_TX_FEE_BUMP_PERCENT, DEFAULT_WARMUP_RUNS,
+    },
+    state::FLAGS_STATE,
+    utils::error::{ManagerError, ManagerResult},
+};
+
+/// Value of a runtime flag
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq)]
+pub enum FlagValue {
+    /// Boolean flag (on/off)
+    Bool(bool),
+    /// Integer flag (thresholds, sizes, counts)
+    Int(i64),
+}
+
+impl Storable for FlagValue {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        Cow::Owned(Encode!(self).expect("Failed to encode a flag value."))
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).expect("Failed to decode a flag value.")
+    }
+
+    const BOUND: Bound = Bound::Bounded {
+        max_size: 64,
+        is_fixed_size: false,
+    };
+}
+
+/// Declaration of a flag known to the canister
+pub struct FlagDefinition {
+    /// Unique name of the flag
+    pub name: &'static str,
+    /// Value used when no override is stored
+    pub default: FlagValue,
+    /// Short description of the gated behavior
+    pub description: &'static str,
+}
+
+/// Polls the receipts of pending transactions at the start of every strategy execution.
+pub const TX_POOL_POLLING: &str = "tx_pool_polling";
+
+/// Maximum number of trove pages fetched per strategy run.
+pub const MAX_TROVE_PAGES: &str = "max_trove_pages";
+
+/// Number of trove list segments fetched at once, sequential fetching if one.
+pub const TROVE_FETCH_CONCURRENCY: &str = "trove_fetch_concurrency";
+
+/// Aggregates the reads of an execution context into a single Multicall3 call.
+pub const MULTICALL_READS: &str = "multicall_reads";
+
+/// Maximum age in seconds of the block an execution context is built on.
+pub const MAX_BLOCK_AGE: &str = "max_block_age";
+
+/// Maximum age in seconds of an execution context when signing begins.
+pub const MAX_CONTEXT_AGE: &str = "max_context_age";
+
+/// Number of consecutive `NoConsensus` outcomes after which an alert is raised.
+pub const NO_CONSENSUS_THRESHOLD: &str = "no_consensus_threshold";
+
+/// Number of blocks a read provider may lag behind the others before it loses reputation.
+pub const MAX_BLOCK_LAG: &str = "max_block_lag";
+
+/// Age in seconds after which a fixed ETH/CXDR rate is reported as stale.
+pub const FIXED_RATE_MAX_AGE: &str = "fixed_rate_max_age";
+
+/// Number of auto-unlocks of a strategy per week above which a warning is journaled.
+pub const AUTO_UNLOCK_WARNING_THRESHOLD: &str = "auto_unlock_warning_threshold";
+
+/// Starts the EVM RPC calls of each call class at the p90 of its observed response sizes.
+pub const PAYLOAD_AUTOTUNE: &str = "payload_autotune";
+
+/// Number of simulation-only runs of a freshly minted, resumed, or unhalted strategy.
+pub const WARMUP_RUNS: &str = "warmup_runs";
+
+/// Number of blocks after which a pending transaction is replaced with bumped fees.
+pub const STUCK_TX_BLOCKS: &str = "stuck_tx_blocks";
+
+/// Percentage by which the fees of a replaced transaction are bumped.
+pub const TX_FEE_BUMP_PERCENT: &str = "tx_fee_bump_percent";
+
+/// Cycles balance below which the canister tops itself up through the CMC.
+pub const CRITICAL_CYCLES_FLOOR: &str = "critical_cycles_floor";
+
+/// ICP amount in e8s converted to cycles by each CMC top-up.
+pub const CMC_TOP_UP_AMOUNT: &str = "cmc_top_up_amount";
+
+/// Number of failed runs in a row after which a strategy raises an alert.
+pub const FAILURE_ALERT_THRESHOLD: &str = "failure_alert_threshold";
+
+/// Number of failed runs in a row after which a single strategy schedules a halt.
+pub const FAILURE_HALT_THRESHOLD: &str = "failure_halt_threshold";
+
+/// ETH balance in wei below which a strategy EOA is reported by the daily balance sweep.
+pub const EOA_BALANCE_WARNING_THRESHOLD: &str = "eoa_balance_warning_threshold";
+
+/// ETH balance in wei below which a strategy EOA is refilled from the richest EOA on its chain.
+pub const GAS_REFILL_FLOOR: &str = "gas_refill_floor";
+
+/// ETH amount in wei transferred by a gas refill.
+pub const GAS_REFILL_AMOUNT: &str = "gas_refill_amount";
+
+/// All flags known to the canister
+pub const FLAGS: &[FlagDefinition] = &[
+    FlagDefinition {
+        name: TX_POOL_POLLING,
+        default: FlagValue::Bool(true),
+        description: "Poll the receipts of pending transactions before each strategy execution.",
+    },
+    FlagDefinition {
+        name: MAX_TROVE_PAGES,
+        default: FlagValue::Int(DEFAULT_MAX_TROVE_PAGES),
+        description: "Maximum number of trove pages fetched per strategy run. Rates are calculated from partial data when the cap is hit.",
+    },
+    FlagDefinition {
+        name: MULTICALL_READS,
+        default: FlagValue::Bool(true),
+        description: "Aggregate the branch debts, the unbacked portions, the redemption rate, and the first trove page of an execution context into a single Multicall3 call. Disable on chains without Multicall3.",
+    },
+    FlagDefinition {
+        name: TROVE_FETCH_CONCURRENCY,
+        default: FlagValue::Int(DEFAULT_TROVE_FETCH_CONCURRENCY),
+        description: "Number of trove list segments fetched at once, split at the rate quantiles of the previous run (at most 8). One fetches the pages sequentially.",
+    },
+    FlagDefinition {
+        name: MAX_BLOCK_AGE,
+        default: FlagValue::Int(DEFAULT_MAX_BLOCK_AGE),
+        description: "Maximum age in seconds of the block an execution context is built on. Older blocks are fetched again from the other provider pool.",
+    },
+    FlagDefinition {
+        name: MAX_CONTEXT_AGE,
+        default: FlagValue::Int(DEFAULT_MAX_CONTEXT_AGE),
+        description: "Maximum age in seconds of an execution context when signing begins. Older contexts abort the submission.",
+    },
+    FlagDefinition {
+        name: NO_CONSENSUS_THRESHOLD,
+        default: FlagValue::Int(DEFAULT_NO_CONSENSUS_THRESHOLD),
+        description: "Number of consecutive NoConsensus outcomes of a call class after which an alert is raised and reads fall back to the top-ranked provider for an hour.",
+    },
+    FlagDefinition {
+        name: MAX_BLOCK_LAG,
+        default: FlagValue::Int(DEFAULT_MAX_BLOCK_LAG),
+        description: "Number of blocks a read provider may lag behind the highest reported block height before it loses reputation. Zero or less disables the block height check.",
+    },
+    FlagDefinition {
+        name: FIXED_RATE_MAX_AGE,
+        default: FlagValue::Int(DEFAULT_FIXED_RATE_MAX_AGE),
+        description: "Age in seconds after which swaps priced with a fixed ETH/CXDR rate are journaled with a staleness warning.",
+    },
+    FlagDefinition {
+        name: AUTO_UNLOCK_WARNING_THRESHOLD,
+        default: FlagValue::Int(DEFAULT_AUTO_UNLOCK_WARNING_THRESHOLD),
+        description: "Number of expired locks of a strategy released by the timeout within a week above which a warning is journaled.",
+    },
+    FlagDefinition {
+        name: PAYLOAD_AUTOTUNE,
+        default: FlagValue::Bool(false),
+        description: "Start the EVM RPC calls of each call class at the p90 of the response sizes it needed, instead of doubling up from the default size.",
+    },
+    FlagDefinition {
+        name: WARMUP_RUNS,
+        default: FlagValue::Int(DEFAULT_WARMUP_RUNS),
+        description: "Number of successful runs a freshly minted, resumed, or unhalted strategy only journals the rate adjustments it would have submitted. A failed run restarts the warm-up. Zero or less disables it.",
+    },
+    FlagDefinition {
+        name: STUCK_TX_BLOCKS,
+        default: FlagValue::Int(DEFAULT_STUCK_TX_BLOCKS),
+        description: "Number of blocks after its submission a transaction may stay pending before the next run resends it with bumped fees. Zero or less disables replacements.",
+    },
+    FlagDefinition {
+        name: TX_FEE_BUMP_PERCENT,
+        default: FlagValue::Int(DEFAULT_TX_FEE_BUMP_PERCENT),
+        description: "Percentage by which the fees of a replaced or cancelled transaction are bumped. Values below 10 are raised to 10, the minimum accepted by the mempools.",
+    },
+    FlagDefinition {
+        name: CRITICAL_CYCLES_FLOOR,
+        default: FlagValue::Int(DEFAULT_CRITICAL_CYCLES_FLOOR),
+        description: "Cycles balance below which the canister converts ICP from its ledger account to cycles through the CMC, in case no arbitrageur swaps. Zero or less disables the top-ups.",
+    },
+    FlagDefinition {
+        name: CMC_TOP_UP_AMOUNT,
+        default: FlagValue::Int(DEFAULT_CMC_TOP_UP_AMOUNT),
+        description: "ICP amount in e8s converted to cycles by each CMC top-up.",
+    },
+    FlagDefinition {
+        name: FAILURE_ALERT_THRESHOLD,
+        default: FlagValue::Int(DEFAULT_FAILURE_ALERT_THRESHOLD),
+        description: "Number of failed runs in a row after which a strategy raises an alert, posted to the alert webhook. Zero or less disables the alerts.",
+    },
+    FlagDefinition {
+        name: FAILURE_HALT_THRESHOLD,
+        default: FlagValue::Int(DEFAULT_FAILURE_HALT_THRESHOLD),
+        description: "Number of failed runs in a row after which a single live strategy schedules a halt, even if the other strategies succeed. Zero or less disables the check.",
+    },
+    FlagDefinition {
+        name: EOA_BALANCE_WARNING_THRESHOLD,
+        default: FlagValue::Int(DEFAULT_EOA_BALANCE_WARNING_THRESHOLD),
+        description: "ETH balance in wei below which the daily balance sweep journals a warning for a strategy EOA and posts it to the alert webhook. Zero or less disables the warnings.",
+    },
+    FlagDefinition {
+        name: GAS_REFILL_FLOOR,
+        default: FlagValue::Int(DEFAULT_GAS_REFILL_FLOOR),
+        description: "ETH balance in wei below which the daily balance sweep refills a strategy EOA with a transfer from the richest EOA on its chain, the funding EOA included. Zero or less disables the refills.",
+    },
+    FlagDefinition {
+        name: GAS_REFILL_AMOUNT,
+        default: FlagValue::Int(DEFAULT_GAS_REFILL_AMOUNT),
+        description: "ETH amount in wei transferred by a gas refill. A donor keeps at least the refill floor plus this amount.",
+    },
+];
+
+/// Query representation of a flag
+#[derive(CandidType, Clone, Debug)]
+pub struct FlagQuery {
+    /// Unique name of the flag
+    pub name: String,
+    /// Value currently in effect
+    pub value: FlagValue,
+    /// Value used when no override is stored
+    pub default: FlagValue,
+    /// Short description of the gated behavior
+    pub description: String,
+}
+
+/// Returns the declaration of the flag with the given name.
+fn definition(name: &str) -> Option<&'static FlagDefinition> {
+    FLAGS.iter().find(|flag| flag.name == name)
+}
+
+/// Returns the value currently in effect for the given flag.
+pub fn get_flag(name: &str) -> Option<FlagValue> {
+    let definition = definition(name)?;
+    let stored = FLAGS_STATE.with(|flags| flags.borrow().get(&name.to_string()));
+    Some(stored.unwrap_or_else(|| definition.default.clone()))
+}
+
+/// Returns `true` if the given boolean flag is enabled.
+/// Unknown and non-boolean flags are treated as disabled.
+pub fn bool_flag(name: &str) -> bool {
+    matches!(get_flag(name), Some(FlagValue::Bool(true)))
+}
+
+/// Returns the value of the given integer flag, or `default` if the flag is unknown or not an integer.
+pub fn int_flag(name: &str, default: i64) -> i64 {
+    match get_flag(name) {
+        Some(FlagValue::Int(value)) => value,
+        _ => default,
+    }
+}
+
+/// Stores an override for a declared flag.
+///
+/// # Errors
+/// - `ManagerError::NonExistentValue` if the flag is not declared
+/// - `ManagerError::Custom` if the value type does not match the declared default
+pub fn set_flag(name: &str, value: FlagValue) -> ManagerResult<()> {
+    let definition = definition(name).ok_or(ManagerError::NonExistentValue)?;
+
+    if std::mem::discriminant(&definition.default) != std::mem::discriminant(&value) {
+        return Err(ManagerError::Custom(format!(
+            "Flag {} expects a value of the same type as {:?}.",
+            name, definition.default
+        )));
+    }
+
+    FLAGS_STATE.with(|flags| flags.borrow_mut().insert(name.to_string(), value));
+    Ok(())
+}
+
+/// Removes the override of a flag, restoring its default value.
+pub fn reset_flag(name: &str) -> ManagerResult<()> {
+    definition(name).ok_or(ManagerError::NonExistentValue)?;
+    FLAGS_STATE.with(|flags| flags.borrow_mut().remove(&name.to_string()));
+    Ok(())
+}
+
+/// Returns all declared flags with their values in effect.
+pub fn list_flags() -> Vec<FlagQuery> {
+    FLAGS
+        .iter()
+        .map(|flag| FlagQuery {
+            name: flag.name.to_string(),
+            value: get_flag(flag.name).unwrap_or_else(|| flag.default.clone()),
+            default: flag.default.clone(),
+            description: flag.description.to_string(),
+        })
+        .collect()
+}
+
+/// Evaluates to `true` if the given boolean flag is enabled.
+macro_rules! flag_enabled {
+    ($name:expr) => {
+        $crate::flags::bool_flag($name)
+    };
+}
+
+/// Evaluates to the value of the given integer flag, falling back to the provided default.
+macro_rules! flag_int {
+    ($name:expr, $default:expr) => {
+        $crate::flags::int_flag($name, $default)
+    };
+}
+
+pub(crate) use flag_enabled;
+pub(crate) use flag_int;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_value() {
+        reset_flag(TX_POOL_POLLING).unwrap();
+        assert_eq!(get_flag(TX_POOL_POLLING), Some(FlagValue::Bool(true)));
+        assert!(flag_enabled!(TX_POOL_POLLING));
+    }
+
+    #[test]
+    fn test_set_and_reset() {
+        set_flag(TX_POOL_POLLING, FlagValue::Bool(false)).unwrap();
+        assert!(!flag_enabled!(TX_POOL_POLLING));
+
+        reset_flag(TX_POOL_POLLING).unwrap();
+        assert!(flag_enabled!(TX_POOL_POLLING));
+    }
+
+    #[test]
+    fn test_unknown_flag() {
+        assert_eq!(get_flag("unknown"), None);
+        assert!(!flag_enabled!("unknown"));
+        assert_eq!(flag_int!("unknown", 5), 5);
+        assert_eq!(
+            set_flag("unknown", FlagValue::Bool(true)),
+            Err(ManagerError::NonExistentValue)
+        );
+    }
+
+    #[test]
+    fn test_type_mismatch() {
+        assert!(set_flag(TX_POOL_POLLING, FlagValue::Int(1)).is_err());
+    }
+
+    #[test]
+    fn test_list_flags() {
+        let flags = list_flags();
+        assert_eq!(flags.len(), FLAGS.len());
+        assert_eq!(flags[0].name, TX_POOL_POLLING);
+    }
+}
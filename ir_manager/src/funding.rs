@@ -0,0 +1,191 @@
+//! Funding EOA
+//!
+//! Minting ckETH from the strategy EOAs spends the ETH they need for their rate adjustments.
+//! A controller can initialize a dedicated funding EOA, derived from its own path, which then
+//! sends every ckETH deposit instead of the strategy EOAs:
+//!
+//! ```plain
+//! init_funding_eoa(rpc principal) ──► tECDSA key at path ["funding"] ──► FUNDING (stable)
+//!
+//! recharge_cketh ──► ether_deposit ──► funding EOA set? ──yes──► deposit from the funding EOA
+//!                                            │
+//!                                            no ──► rotate through the strategy EOAs
+//! ```
+//!
+//! The funding EOA operates on the home chain and never signs rate adjustments. Its balance is
+//! refreshed with the other balances and served by `get_funding_eoa`.
+
+use std::borrow::Cow;
+
+use alloy_primitives::{Address, U256};
+use candid::{CandidType, Decode, Encode, Principal};
+use ic_stable_structures::{storable::Bound, Storable};
+use serde::Deserialize;
+
+use crate::{
+    constants::CHAIN_ID,
+    rpc_registry::resolve_rpc_canister,
+    state::FUNDING,
+    treasury::CachedBalance,
+    types::DerivationPath,
+    utils::{
+        common::u256_to_nat,
+        error::{ManagerError, ManagerResult},
+        evm_rpc::Service,
+    },
+};
+
+/// Derivation path component of the funding EOA, which cannot collide with the 4-byte keys
+/// of the strategy EOAs
+const FUNDING_PATH: &[u8] = b"funding";
+
+/// The EOA dedicated to ckETH minting
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq)]
+pub struct FundingEoa {
+    /// Address of the EOA
+    pub address: String,
+    /// EVM RPC canister the deposits are sent through
+    pub rpc_principal: Principal,
+    /// Next nonce of the EOA
+    pub nonce: u64,
+    /// Last observed ETH balance in wei
+    pub balance: Option<CachedBalance>,
+    /// Timestamp in seconds of the initialization
+    pub initialized_at: u64,
+}
+
+impl FundingEoa {
+    /// Returns the EVM RPC canister to send the deposits through at `now` (in seconds).
+    pub fn service(&self, now: u64) -> Service {
+        resolve_rpc_canister(&Service(self.rpc_principal, CHAIN_ID), now)
+    }
+}
+
+/// Persisted funding EOA, `None` until initialized
+#[derive(CandidType, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct FundingState {
+    /// The funding EOA
+    pub eoa: Option<FundingEoa>,
+}
+
+impl Storable for FundingState {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        Cow::Owned(Encode!(self).expect("Failed to encode the funding state."))
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).expect("Failed to decode the funding state.")
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+/// Returns the derivation path of the funding EOA.
+pub fn funding_derivation_path() -> DerivationPath {
+    vec![FUNDING_PATH.to_vec()]
+}
+
+/// Returns the funding EOA, if initialized.
+pub fn funding_eoa() -> Option<FundingEoa> {
+    FUNDING.with(|funding| funding.borrow().get().eoa.clone())
+}
+
+/// Updates the funding EOA, if initialized.
+fn update_funding_eoa(update: impl FnOnce(&mut FundingEoa)) {
+    FUNDING.with(|funding| {
+        let mut cell = funding.borrow_mut();
+        let mut state = cell.get().clone();
+        if let Some(eoa) = state.eoa.as_mut() {
+            update(eoa);
+            cell.set(state)
+                .expect("Failed to persist the funding state.");
+        }
+    });
+}
+
+/// Stores the funding EOA derived at `funding_derivation_path`.
+///
+/// # Errors
+/// Returns `ManagerError::Custom` if the funding EOA is already initialized.
+pub fn initialize_funding_eoa(
+    address: Address,
+    rpc_principal: Principal,
+    nonce: u64,
+    now: u64,
+) -> ManagerResult<()> {
+    if let Some(eoa) = funding_eoa() {
+        return Err(ManagerError::Custom(format!(
+            "The funding EOA is already initialized at {}.",
+            eoa.address
+        )));
+    }
+    FUNDING.with(|funding| {
+        funding
+            .borrow_mut()
+            .set(FundingState {
+                eoa: Some(FundingEoa {
+                    address: address.to_string(),
+                    rpc_principal,
+                    nonce,
+                    balance: None,
+                    initialized_at: now,
+                }),
+            })
+            .expect("Failed to persist the funding state.");
+    });
+    Ok(())
+}
+
+/// Stores the next nonce of the funding EOA.
+pub fn store_funding_nonce(next: u64) {
+    update_funding_eoa(|eoa| eoa.nonce = next);
+}
+
+/// Records the ETH balance of the funding EOA observed at `now` (in seconds).
+pub fn record_funding_balance(balance: &U256, now: u64) {
+    let Ok(amount) = u256_to_nat(balance) else {
+        return;
+    };
+    update_funding_eoa(|eoa| {
+        eoa.balance = Some(CachedBalance {
+            amount,
+            updated_at: now,
+        })
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use candid::Nat;
+
+    use super::*;
+
+    #[test]
+    fn test_funding_eoa() {
+        assert_eq!(funding_eoa(), None);
+        // Nothing to update before the initialization
+        store_funding_nonce(3);
+        assert_eq!(funding_eoa(), None);
+
+        let address = Address::repeat_byte(9);
+        initialize_funding_eoa(address, Principal::anonymous(), 2, 100).unwrap();
+        assert!(initialize_funding_eoa(address, Principal::anonymous(), 0, 200).is_err());
+
+        store_funding_nonce(5);
+        record_funding_balance(&U256::from(1_000), 150);
+        let eoa = funding_eoa().unwrap();
+        assert_eq!(eoa.address, address.to_string());
+        assert_eq!(eoa.nonce, 5);
+        assert_eq!(
+            eoa.balance,
+            Some(CachedBalance {
+                amount: Nat::from(1_000_u64),
+                updated_at: 150
+            })
+        );
+        assert_ne!(
+            funding_derivation_path(),
+            vec![1_u32.to_be_bytes().to_vec()]
+        );
+    }
+}
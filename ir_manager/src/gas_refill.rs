@@ -0,0 +1,315 @@
+//! Gas Refill
+//!
+//! Keeps every strategy EOA able to pay for its rate adjustments. After the daily balance
+//! sweep, an EOA below the refill floor receives a plain ETH transfer from the richest EOA on
+//! the same chain, the funding EOA included:
+//!
+//! ```plain
+//! observed balances ──► plan_refills ──► per refill: donor ──(GAS_REFILL_AMOUNT wei)──► EOA
+//!                           │
+//!                           └─ donor keeps at least floor + amount, the balances are
+//!                              updated after each planned transfer
+//! ```
+//!
+//! The balances are the ones observed by the last sweep and the last refresh of the funding
+//! EOA. An EOA without an observed balance neither receives nor donates, and the EOA of a
+//! strategy submitting UserOperations only donates.
+
+use alloy_primitives::{Address, U256};
+
+use crate::{
+    clock::time,
+    constants::{CHAIN_ID, DEFAULT_GAS_REFILL_AMOUNT, DEFAULT_GAS_REFILL_FLOOR},
+    flags::{flag_int, GAS_REFILL_AMOUNT, GAS_REFILL_FLOOR},
+    funding::{funding_derivation_path, funding_eoa, store_funding_nonce},
+    journal::{JournalCollection, LogType},
+    rpc_registry::resolve_rpc_canister,
+    state::STRATEGY_STATE,
+    strategy::{settings::ExecutionBackend, stable::update_strategy},
+    utils::{
+        address::parse_address,
+        common::get_nonce,
+        error::{ManagerError, ManagerResult},
+        evm_rpc::{SendRawTransactionStatus, Service},
+        nonce::{reconcile_nonce, NonceLease},
+        transaction_builder::TransactionBuilder,
+        units::nat_to_u256,
+    },
+};
+
+/// Owner of an EOA taking part in the refills
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Holder {
+    /// EOA of the strategy with the given key
+    Strategy(u32),
+    /// Funding EOA, which only donates
+    Funding,
+}
+
+/// An EOA with its last observed balance
+#[derive(Clone, Debug)]
+struct Account {
+    holder: Holder,
+    address: Address,
+    chain_id: u64,
+    balance: U256,
+    /// `false` if the EOA does not pay for the gas of its strategy
+    needs_gas: bool,
+}
+
+/// A planned transfer between two accounts, by index
+#[derive(Debug, PartialEq)]
+struct Refill {
+    donor: usize,
+    recipient: usize,
+}
+
+/// Plans the transfers of `amount` wei refilling the strategy EOAs below `floor`.
+///
+/// Each recipient is refilled by the richest account on its chain that keeps at least
+/// `floor + amount` after the transfer.
+fn plan_refills(accounts: &mut [Account], floor: U256, amount: U256) -> Vec<Refill> {
+    let reserve = floor.saturating_add(amount).saturating_add(amount);
+    let mut refills = vec![];
+    for recipient in 0..accounts.len() {
+        if !accounts[recipient].needs_gas || accounts[recipient].balance >= floor {
+            continue;
+        }
+        let chain_id = accounts[recipient].chain_id;
+        let donor = accounts
+            .iter()
+            .enumerate()
+            .filter(|(index, account)| {
+                *index != recipient && account.chain_id == chain_id && account.balance >= reserve
+            })
+            .max_by_key(|(_, account)| account.balance)
+            .map(|(index, _)| index);
+        if let Some(donor) = donor {
+            accounts[donor].balance -= amount;
+            accounts[recipient].balance += amount;
+            refills.push(Refill { donor, recipient });
+        }
+    }
+    refills
+}
+
+/// Returns the strategy EOAs and the funding EOA with an observed balance.
+fn observed_accounts() -> Vec<Account> {
+    let mut accounts: Vec<Account> = STRATEGY_STATE.with(|strategies| {
+        strategies
+            .borrow()
+            .values()
+            .filter_map(|strategy| {
+                let balance = strategy.data.eoa_balance.as_ref()?;
+                Some(Account {
+                    holder: Holder::Strategy(strategy.settings.key),
+                    address: strategy.settings.eoa_pk?,
+                    chain_id: strategy.settings.chain.chain_id,
+                    balance: nat_to_u256(&balance.amount).ok()?,
+                    needs_gas: strategy.settings.execution_backend == ExecutionBackend::Eoa,
+                })
+            })
+            .collect()
+    });
+    accounts.sort_unstable_by_key(|account| match account.holder {
+        Holder::Strategy(key) => key,
+        Holder::Funding => u32::MAX,
+    });
+
+    if let Some(funding) = funding_eoa() {
+        let balance = funding
+            .balance
+            .as_ref()
+            .and_then(|balance| nat_to_u256(&balance.amount).ok());
+        let address = parse_address("funding_eoa", &funding.address, false).ok();
+        if let (Some(balance), Some(address)) = (balance, address) {
+            accounts.push(Account {
+                holder: Holder::Funding,
+                address,
+                chain_id: CHAIN_ID,
+                balance,
+                needs_gas: false,
+            });
+        }
+    }
+    accounts
+}
+
+/// Refills the strategy EOAs below `GAS_REFILL_FLOOR` from the richest EOA on their chain.
+///
+/// Every transfer is journaled. A failed transfer is skipped until the next sweep.
+pub async fn refill_eoas() {
+    let floor = flag_int!(GAS_REFILL_FLOOR, DEFAULT_GAS_REFILL_FLOOR);
+    let amount = flag_int!(GAS_REFILL_AMOUNT, DEFAULT_GAS_REFILL_AMOUNT);
+    if floor <= 0 || amount <= 0 {
+        return;
+    }
+    let amount = U256::from(amount as u64);
+
+    let mut accounts = observed_accounts();
+    let refills = plan_refills(&mut accounts, U256::from(floor as u64), amount);
+    for Refill { donor, recipient } in refills {
+        let (donor, recipient) = (&accounts[donor], &accounts[recipient]);
+        let key = match recipient.holder {
+            Holder::Strategy(key) => Some(key),
+            Holder::Funding => None,
+        };
+        let mut journal = JournalCollection::open(key);
+        let result = send_refill(&mut journal, donor, recipient.address, amount).await;
+        journal.append_note(
+            result,
+            LogType::Recharge,
+            format!(
+                "Refill of {} wei from {} to the EOA {}.",
+                amount, donor.address, recipient.address
+            ),
+        );
+    }
+}
+
+/// Signs and sends a plain ETH transfer of `amount` wei from `donor` to `recipient`.
+async fn send_refill(
+    journal: &mut JournalCollection,
+    donor: &Account,
+    recipient: Address,
+    amount: U256,
+) -> ManagerResult<()> {
+    let now = time() / 1_000_000_000;
+    let builder = TransactionBuilder::default()
+        .to(recipient.to_string())
+        .from(donor.address.to_string())
+        .data(vec![])
+        .value(amount)
+        .cycles(40_000_000_000);
+
+    let (builder, rpc_canister, nonce) = match donor.holder {
+        Holder::Strategy(key) => {
+            let strategy = STRATEGY_STATE
+                .with(|strategies| strategies.borrow().get(&key).cloned())
+                .ok_or(ManagerError::NonExistentValue)?;
+            let rpc_canister = resolve_rpc_canister(&strategy.settings.rpc_canister, now);
+            let builder = builder
+                .derivation_path(strategy.settings.derivation_path.clone())
+                .strategy_key(key)
+                .fee_policy(strategy.settings.fee_policy);
+            (builder, rpc_canister, strategy.data.eoa_nonce)
+        }
+        Holder::Funding => {
+            let funding = funding_eoa().ok_or(ManagerError::NonExistentValue)?;
+            let builder = builder.derivation_path(funding_derivation_path());
+            (builder, funding.service(now), funding.nonce)
+        }
+    };
+
+    // The donor may sign a rate adjustment or a deposit in the meantime
+    let lease = NonceLease::acquire(donor.address, nonce);
+    let status = builder
+        .nonce(lease.nonce())
+        .journal(journal)
+        .send(&rpc_canister)
+        .await?;
+
+    match status {
+        SendRawTransactionStatus::Ok(tx_hash) => {
+            store_nonce(donor.holder, lease.commit());
+            journal.append_note(
+                Ok(()),
+                LogType::Recharge,
+                format!("The refill transaction was sent with hash: {:#?}", tx_hash),
+            );
+            Ok(())
+        }
+        SendRawTransactionStatus::NonceTooHigh | SendRawTransactionStatus::NonceTooLow => {
+            drop(lease);
+            resync_nonce(&rpc_canister, donor).await?;
+            Err(ManagerError::Custom(format!(
+                "The nonce of the donor needed adjusting: {:#?}",
+                status
+            )))
+        }
+        SendRawTransactionStatus::InsufficientFunds => Err(ManagerError::Custom(
+            "The donor cannot cover the refill and the gas costs.".to_string(),
+        )),
+    }
+}
+
+/// Stores the next nonce of a donor.
+fn store_nonce(holder: Holder, next: u64) {
+    match holder {
+        Holder::Strategy(key) => {
+            let _ = update_strategy(key, |strategy| strategy.data.eoa_nonce = next);
+        }
+        Holder::Funding => store_funding_nonce(next),
+    }
+}
+
+/// Resyncs the nonce of a donor with the chain.
+async fn resync_nonce(rpc_canister: &Service, donor: &Account) -> ManagerResult<()> {
+    let chain_nonce = get_nonce(rpc_canister, donor.address).await?.to::<u64>();
+    store_nonce(donor.holder, reconcile_nonce(donor.address, chain_nonce));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn account(holder: Holder, chain_id: u64, balance: u64) -> Account {
+        Account {
+            holder,
+            address: Address::repeat_byte(1),
+            chain_id,
+            balance: U256::from(balance),
+            needs_gas: holder != Holder::Funding,
+        }
+    }
+
+    #[test]
+    fn test_plan_refills() {
+        let mut accounts = vec![
+            account(Holder::Strategy(1), 1, 5),
+            account(Holder::Strategy(2), 1, 100),
+            account(Holder::Strategy(3), 1, 2),
+            account(Holder::Strategy(4), 10, 0),
+            account(Holder::Funding, 1, 90),
+        ];
+        let refills = plan_refills(&mut accounts, U256::from(10), U256::from(20));
+
+        // The funding EOA refills the second recipient once strategy 2 got poorer than it
+        assert_eq!(
+            refills,
+            vec![
+                Refill {
+                    donor: 1,
+                    recipient: 0
+                },
+                Refill {
+                    donor: 4,
+                    recipient: 2
+                },
+            ]
+        );
+        assert_eq!(accounts[0].balance, U256::from(25));
+        assert_eq!(accounts[1].balance, U256::from(80));
+        assert_eq!(accounts[4].balance, U256::from(70));
+        // No account on chain 10 can donate
+        assert_eq!(accounts[3].balance, U256::ZERO);
+
+        // A donor must keep at least floor + amount
+        let mut accounts = vec![
+            account(Holder::Strategy(1), 1, 5),
+            account(Holder::Strategy(2), 1, 45),
+        ];
+        assert!(plan_refills(&mut accounts, U256::from(10), U256::from(20)).is_empty());
+
+        // The EOA of a strategy submitting UserOperations is not refilled
+        let mut accounts = vec![
+            Account {
+                needs_gas: false,
+                ..account(Holder::Strategy(1), 1, 5)
+            },
+            account(Holder::Strategy(2), 1, 100),
+        ];
+        assert!(plan_refills(&mut accounts, U256::from(10), U256::from(20)).is_empty());
+    }
+}
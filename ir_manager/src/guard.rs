@@ -0,0 +1,286 @@
+//! Guards shared by the canister's public entrypoints and timers
+//!
+//! Entrypoints must never trap because of the canister's operational state, as trapping
+//! burns the caller's message (and the cycles attached to it) without an explanation.
+//! Instead, every update entrypoint starts with a [`Guard`] and surfaces a typed error,
+//! while timers call the helpers below, journal the skipped run, and return early.
+//!
+//! ```plain
+//! Guard Order:
+//!
+//! ┌────────┐   ┌──────┐   ┌────────────┐   ┌──────┐   ┌────────────┐   ┌─────────────┐
+//! │ Caller ├──►│ Halt ├──►│ Operational├──►│ Role ├──►│ Rate limit ├──►│ Idempotency ├──► Entrypoint
+//! └────────┘   └──────┘   └────────────┘   └──────┘   └────────────┘   └─────────────┘
+//!              (unless     (opt-in: not     (from        (opt-in, per     (opt-in, per
+//!               allowed)    paused)          metadata)    caller)          caller and args)
+//! ```
+//!
+//! The role of a method is taken from its entry in `API_METADATA`, so the declared and the
+//! enforced access control cannot diverge. A test checks that every update method of
+//! `canister.rs` declares its guard.
+
+use std::collections::{HashMap, VecDeque};
+
+use candid::Principal;
+use ic_exports::ic_cdk::caller;
+
+use crate::{
+    canister::API_METADATA,
+    clock::time,
+    constants::IDEMPOTENCY_WINDOW,
+    halt::is_functional,
+    metadata::Role,
+    state::{GUARD_STATE, HALT_STATE},
+    upgrade::ensure_not_paused,
+    utils::{
+        common::only_controller,
+        error::{ManagerError, ManagerResult},
+    },
+};
+
+/// Returns `Err(ManagerError::Halted)` if the canister has been fully halted.
+///
+/// A canister with a halt in progress is still considered functional.
+pub fn ensure_functional() -> ManagerResult<()> {
+    if is_functional() {
+        return Ok(());
+    }
+
+    let status = HALT_STATE.with(|halt| halt.borrow().status.clone());
+    Err(ManagerError::Halted { status })
+}
+
+/// Calls and requests seen by the rate limits and the idempotency guards
+#[derive(Default)]
+pub struct GuardState {
+    /// Timestamps in seconds of the recent calls, by method and caller
+    calls: HashMap<(&'static str, Principal), VecDeque<u64>>,
+    /// Timestamp in seconds of the first call, by method, caller, and arguments digest
+    requests: HashMap<(&'static str, Principal, String), u64>,
+}
+
+/// The guards of an update entrypoint, checked in a fixed order
+#[derive(Clone, Debug)]
+pub struct Guard {
+    method: &'static str,
+    allow_halted: bool,
+    operational: bool,
+    rate_limit: Option<(u64, u64)>,
+    idempotency_key: Option<String>,
+}
+
+impl Guard {
+    /// Guards the method named `method` in `API_METADATA` with the halt and role checks.
+    pub fn new(method: &'static str) -> Self {
+        Self {
+            method,
+            allow_halted: false,
+            operational: false,
+            rate_limit: None,
+            idempotency_key: None,
+        }
+    }
+
+    /// Accepts calls while the canister is halted, for methods that do not change its state.
+    pub fn allow_halted(mut self) -> Self {
+        self.allow_halted = true;
+        self
+    }
+
+    /// Rejects calls while the canister is paused for an upgrade.
+    pub fn operational(mut self) -> Self {
+        self.operational = true;
+        self
+    }
+
+    /// Accepts at most `calls` calls per caller within `window` seconds.
+    pub fn rate_limit(mut self, calls: u64, window: u64) -> Self {
+        self.rate_limit = Some((calls, window));
+        self
+    }
+
+    /// Rejects a repeated call with the same arguments digest within `IDEMPOTENCY_WINDOW`.
+    pub fn idempotent(mut self, args_digest: String) -> Self {
+        self.idempotency_key = Some(args_digest);
+        self
+    }
+
+    /// Returns the role of the method declared in `API_METADATA`.
+    ///
+    /// Methods without metadata are restricted to the controllers.
+    fn role(&self) -> Role {
+        API_METADATA
+            .iter()
+            .find(|metadata| metadata.name == self.method)
+            .map_or(Role::Controller, |metadata| metadata.role)
+    }
+
+    /// Checks the guards for the current call.
+    pub fn check(&self) -> ManagerResult<()> {
+        self.check_at(caller(), time() / 1_000_000_000)
+    }
+
+    /// Checks the guards for a call of `caller` at `now` (in seconds).
+    ///
+    /// An accepted call counts towards the rate limit and claims its idempotency key.
+    pub fn check_at(&self, caller: Principal, now: u64) -> ManagerResult<()> {
+        if !self.allow_halted {
+            ensure_functional()?;
+        }
+        if self.operational {
+            ensure_not_paused()?;
+        }
+        if self.role() == Role::Controller {
+            only_controller(caller)?;
+        }
+
+        GUARD_STATE.with(|state| {
+            let mut state = state.borrow_mut();
+
+            if let Some((calls, window)) = self.rate_limit {
+                let recent = state.calls.entry((self.method, caller)).or_default();
+                while recent
+                    .front()
+                    .map_or(false, |called_at| now.saturating_sub(*called_at) >= window)
+                {
+                    recent.pop_front();
+                }
+                if recent.len() as u64 >= calls {
+                    let oldest = recent.front().copied().unwrap_or(now);
+                    return Err(ManagerError::RateLimited {
+                        retry_after: oldest.saturating_add(window),
+                    });
+                }
+            }
+
+            if let Some(key) = &self.idempotency_key {
+                state
+                    .requests
+                    .retain(|_, seen_at| now.saturating_sub(*seen_at) < IDEMPOTENCY_WINDOW);
+                if let Some(first_seen_at) = state.requests.get(&(self.method, caller, key.clone()))
+                {
+                    return Err(ManagerError::DuplicateRequest {
+                        first_seen_at: *first_seen_at,
+                    });
+                }
+                state
+                    .requests
+                    .insert((self.method, caller, key.clone()), now);
+            }
+
+            if self.rate_limit.is_some() {
+                state
+                    .calls
+                    .entry((self.method, caller))
+                    .or_default()
+                    .push_back(now);
+            }
+
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::halt::{Halt, HaltStatus};
+
+    fn set_halt_status(status: HaltStatus) {
+        HALT_STATE.with(|halt| {
+            *halt.borrow_mut() = Halt {
+                status,
+                ..Halt::default()
+            }
+        });
+    }
+
+    #[test]
+    fn functional_canister_passes() {
+        set_halt_status(HaltStatus::Functional);
+        assert_eq!(ensure_functional(), Ok(()));
+    }
+
+    #[test]
+    fn halting_in_progress_canister_passes() {
+        set_halt_status(HaltStatus::HaltingInProgress { halts_at: 42 });
+        assert_eq!(ensure_functional(), Ok(()));
+    }
+
+    #[test]
+    fn halted_canister_returns_typed_error() {
+        let status = HaltStatus::Halted { halted_at: 42 };
+        set_halt_status(status.clone());
+        assert_eq!(ensure_functional(), Err(ManagerError::Halted { status }));
+    }
+
+    #[test]
+    fn rate_limit_counts_accepted_calls_per_caller() {
+        set_halt_status(HaltStatus::Functional);
+        let guard = Guard::new("swap_cketh_v2").rate_limit(2, 60);
+        let caller = Principal::anonymous();
+        let other = Principal::management_canister();
+
+        assert_eq!(guard.check_at(caller, 100), Ok(()));
+        assert_eq!(guard.check_at(caller, 110), Ok(()));
+        assert_eq!(
+            guard.check_at(caller, 120),
+            Err(ManagerError::RateLimited { retry_after: 160 })
+        );
+        assert_eq!(guard.check_at(other, 120), Ok(()));
+        assert_eq!(guard.check_at(caller, 160), Ok(()));
+    }
+
+    #[test]
+    fn idempotent_calls_are_rejected_within_the_window() {
+        set_halt_status(HaltStatus::Functional);
+        let guard = Guard::new("swap_cketh").idempotent("digest".to_string());
+        let caller = Principal::anonymous();
+
+        assert_eq!(guard.check_at(caller, 100), Ok(()));
+        assert_eq!(
+            guard.check_at(caller, 101),
+            Err(ManagerError::DuplicateRequest { first_seen_at: 100 })
+        );
+        assert_eq!(guard.check_at(caller, 100 + IDEMPOTENCY_WINDOW), Ok(()));
+    }
+
+    #[test]
+    fn halt_is_checked_before_the_role() {
+        let status = HaltStatus::Halted { halted_at: 42 };
+        set_halt_status(status.clone());
+        assert_eq!(
+            Guard::new("set_flag").check_at(Principal::anonymous(), 100),
+            Err(ManagerError::Halted { status })
+        );
+        assert_eq!(
+            Guard::new("get_canister_status")
+                .allow_halted()
+                .check_at(Principal::anonymous(), 100),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn every_update_method_is_guarded() {
+        let source = include_str!("canister.rs");
+        for chunk in source.split("#[update]").skip(1) {
+            let Some((_, signature)) = chunk.split_once("pub ") else {
+                continue;
+            };
+            let name = signature
+                .trim_start_matches("async ")
+                .trim_start_matches("fn ")
+                .split('(')
+                .next()
+                .unwrap_or_default();
+            let body = chunk.split("\n    }\n").next().unwrap_or_default();
+            assert!(
+                body.contains(&format!("Guard::new(\"{}\")", name))
+                    || body.contains("self.swap_cketh_v2("),
+                "Update method {} does not declare its guard",
+                name
+            );
+        }
+    }
+}
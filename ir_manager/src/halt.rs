@@ -1,21 +1,69 @@
 //! Halting service to address canister failure
+//!
+//! Besides the built-in conditions below, the daily evaluation runs the custom triggers
+//! registered by a controller (see `triggers`).
+//!
+//! The built-in conditions look at all strategies, except for the failure streaks: a single
+//! live strategy whose runs keep failing schedules a halt once its streak reaches the
+//! `failure_halt_threshold` flag, even while the other strategies succeed. A streak ending
+//! with a configuration error raises an alert instead, as only a controller can fix it.
+//!
+//! A halt is announced `HALT_DELAY` seconds before it takes effect. During that window the
+//! countdown is exposed through `halt_status`, and a controller can cancel the halt. The
+//! scheduled timer cannot be stopped, so it only completes the halt it was scheduled for:
+//!
+//! ```plain
+//! Functional ──schedule──► HaltingInProgress { halts_at } ──timer──► Halted
+//!     ▲  ▲                           │                       (only if still in progress
+//!     │  └──────── cancel_halt ──────┘                        with the same halts_at)
+//!     │                                                              │
+//!     └──────────── resume_from_halt (preconditions hold) ───────────┘
+//! ```
+//!
+//! A completed halt is only lifted by a controller, once at least one strategy has an EOA and
+//! the ckETH and cycles balances are above their thresholds. The recovery time then counts as
+//! the latest exit and rate update of every strategy, so that the stale timestamps that caused
+//! the halt do not schedule another one.
+//!
+//! Every transition is pushed, so that the countdown does not go unnoticed by operators who
+//! do not poll `halt_status`: it is journaled and posted to the webhook (see `digest`), and
+//! the optional `HaltCallback` canister is notified with a one-way call carrying a
+//! `HaltTransition`.
 
-use candid::CandidType;
-use chrono::Duration;
-use ic_exports::{ic_cdk::api::time, ic_cdk_timers::set_timer};
+use candid::{CandidType, Nat, Principal};
+use ic_exports::{ic_cdk::api::call::notify, ic_cdk_timers::set_timer};
+use serde::Deserialize;
 
 use crate::{
-    state::{HALT_STATE, STRATEGY_STATE},
-    strategy::stable::StableStrategy,
+    charger_config::charger_config,
+    clock::Seconds,
+    constants::{DEFAULT_CRITICAL_CYCLES_FLOOR, DEFAULT_FAILURE_HALT_THRESHOLD, HALT_DELAY},
+    digest::{raise_alert, raise_notice},
+    flags::{flag_int, CRITICAL_CYCLES_FLOOR, FAILURE_HALT_THRESHOLD},
+    journal::{JournalCollection, LogType},
+    state::{HALT_CALLBACK, HALT_STATE, STRATEGY_STATE},
+    strategy::{
+        stable::StableStrategy,
+        warmup::{start_warmup_all, WarmupReason},
+    },
+    triggers::{evaluate_triggers, TriggerAction},
+    utils::error::{ManagerError, ManagerResult},
 };
 
 /// Halt struct containing reasoning and status
-#[derive(Clone, CandidType, PartialEq)]
+#[derive(Clone, CandidType, Debug, Deserialize, PartialEq)]
 pub struct Halt {
     /// The current halt status
     pub status: HaltStatus,
     /// The halt message (if the canister status is not `Functional`)
     pub message: Option<String>,
+    /// The condition that caused the halt (if the canister status is not `Functional`)
+    pub condition: Option<HaltCondition>,
+    /// Seconds until the halt takes effect, computed at query time while `HaltingInProgress`
+    pub seconds_remaining: Option<u64>,
+    /// Timestamp in seconds of the last recovery from a completed halt, the exit and update
+    /// checks ignore the activity before it
+    pub recovered_at: Option<u64>,
 }
 
 impl Default for Halt {
@@ -23,28 +71,135 @@ impl Default for Halt {
         Self {
             status: HaltStatus::Functional,
             message: None,
+            condition: None,
+            seconds_remaining: None,
+            recovered_at: None,
         }
     }
 }
 
+/// Condition that caused a halt
+#[derive(Clone, CandidType, Debug, Deserialize, PartialEq)]
+pub enum HaltCondition {
+    /// No strategy had a successful exit within the given number of days
+    NoSuccessfulExits {
+        /// Number of days without a successful exit
+        days: u64,
+    },
+    /// No strategy updated a rate within the given number of days
+    NoRateUpdates {
+        /// Number of days without a rate update
+        days: u64,
+    },
+    /// A live strategy failed the given number of runs in a row
+    ConsecutiveFailures {
+        /// Key of the strategy
+        strategy: u32,
+        /// Number of failed runs in a row
+        failures: u64,
+    },
+    /// A custom halt trigger fired
+    Trigger {
+        /// ID of the trigger
+        id: u64,
+    },
+}
+
 /// Halt Status enum determining the stage the canister is at
-#[derive(Clone, CandidType, PartialEq)]
+#[derive(Clone, CandidType, Debug, Deserialize, PartialEq)]
 pub enum HaltStatus {
     /// Functioning as expected
     Functional,
     /// Fully halted
     Halted {
-        /// Timestamp for when the timer to fully halt the canister was triggered in milliseconds.
+        /// Timestamp in seconds for when the timer to fully halt the canister was triggered.
         halted_at: u64,
     },
     /// Has a timer scheduled to fully halt the canister soon.
     /// In this stage the canister continues to function normally.
     HaltingInProgress {
-        /// Timestamp for when the timer to fully halt the canister gets triggered in milliseconds.
+        /// Timestamp in seconds for when the timer to fully halt the canister gets triggered.
         halts_at: u64,
     },
 }
 
+/// Canister method notified of every halt status transition
+#[derive(Clone, CandidType, Debug, Deserialize, PartialEq)]
+pub struct HaltCallback {
+    /// Canister to notify, e.g. a governance canister
+    pub canister: Principal,
+    /// Method called with a single `HaltTransition` argument
+    pub method: String,
+}
+
+/// A halt status transition, as pushed to the `HaltCallback`
+#[derive(Clone, CandidType, Debug, Deserialize, PartialEq)]
+pub struct HaltTransition {
+    /// Status before the transition
+    pub previous: HaltStatus,
+    /// Halt state after the transition
+    pub current: Halt,
+    /// Timestamp in seconds of the transition
+    pub at: u64,
+}
+
+/// Sets the canister method notified of the halt status transitions, or clears it.
+pub fn set_halt_callback(callback: Option<HaltCallback>) -> ManagerResult<()> {
+    if let Some(callback) = &callback {
+        if callback.method.trim().is_empty() {
+            return Err(ManagerError::Custom(
+                "The halt callback method cannot be empty.".to_string(),
+            ));
+        }
+    }
+    HALT_CALLBACK.with(|state| *state.borrow_mut() = callback);
+    Ok(())
+}
+
+/// Returns the canister method notified of the halt status transitions, if any.
+pub fn halt_callback() -> Option<HaltCallback> {
+    HALT_CALLBACK.with(|state| state.borrow().clone())
+}
+
+/// Journals a halt status transition, posts it to the webhook, and notifies the callback.
+fn announce_transition(transition: HaltTransition) {
+    let message = transition.current.message.clone().unwrap_or_default();
+    match &transition.current.status {
+        HaltStatus::HaltingInProgress { halts_at } => raise_alert(format!(
+            "The canister halts at {} unless the halt is cancelled. Condition: {:?}. {}",
+            halts_at, transition.current.condition, message
+        )),
+        HaltStatus::Halted { halted_at } => raise_alert(format!(
+            "The canister is halted since {}. Condition: {:?}. {}",
+            halted_at, transition.current.condition, message
+        )),
+        HaltStatus::Functional => match &transition.previous {
+            HaltStatus::Halted { .. } => raise_notice(format!(
+                "The canister recovered from the halt ({:?}). The canister is functional.",
+                transition.previous
+            )),
+            _ => raise_notice(format!(
+                "The halt ({:?}) was cancelled. The canister is functional.",
+                transition.previous
+            )),
+        },
+    }
+
+    let Some(callback) = halt_callback() else {
+        return;
+    };
+    if let Err(code) = notify(callback.canister, &callback.method, (transition,)) {
+        JournalCollection::open(None).append_note(
+            Err(ManagerError::CallResult(code, String::new())),
+            LogType::Info,
+            format!(
+                "WARNING: Could not notify {} of the halt status transition through `{}`.",
+                callback.canister, callback.method
+            ),
+        );
+    }
+}
+
 /// Returns `true` if the canister is not set to `Halted`, and `false` if not.
 pub fn is_functional() -> bool {
     HALT_STATE.with(|halt| {
@@ -68,10 +223,113 @@ fn is_explicitly_functional() -> bool {
 /// If yes, it will schedule a force-halt timer in 7 days.
 /// Runs every 24 hours via a recurring timer.
 pub fn update_halt_status() {
-    // There is no need to run the function if the canister is halted or has a halt in progress.
-    assert!(is_explicitly_functional());
+    // Custom triggers are evaluated every day, so that their streaks and alerts stay accurate.
+    let mut halt_trigger = None;
+    for fired in evaluate_triggers(Seconds::now().get()) {
+        match fired.action {
+            TriggerAction::Alert => raise_alert(fired.message),
+            TriggerAction::Halt => {
+                halt_trigger.get_or_insert((fired.id, fired.message));
+            }
+        }
+    }
+
+    // There is no need to schedule a halt if the canister is halted or has a halt in progress.
+    if !is_explicitly_functional() {
+        return;
+    }
+
+    if let Some((id, message)) = halt_trigger {
+        schedule_halt(message, HaltCondition::Trigger { id });
+        return;
+    }
+
+    let _ = check_strategy_exits() || check_strategy_failures() || check_strategy_updates();
+}
+
+/// Checks the failure streaks of the live strategies.
+/// If one of them reached the threshold, starts the process of halting the canister.
+/// Returns `true` if a halt is scheduled.
+fn check_strategy_failures() -> bool {
+    for (strategy, failures) in misconfiguration_streaks() {
+        raise_alert(format!(
+            "Strategy {} has failed {} runs in a row because of its configuration. A controller has to fix it.",
+            strategy, failures
+        ));
+    }
+
+    let Some((strategy, failures)) = halting_failure_streak() else {
+        return false;
+    };
+    schedule_halt(
+        format!(
+            "Strategy {} has failed {} runs in a row.",
+            strategy, failures
+        ),
+        HaltCondition::ConsecutiveFailures { strategy, failures },
+    );
+    true
+}
+
+/// Returns the longest failure streak, if it reached the threshold.
+fn halting_failure_streak() -> Option<(u32, u64)> {
+    let threshold = flag_int!(FAILURE_HALT_THRESHOLD, DEFAULT_FAILURE_HALT_THRESHOLD);
+    if threshold <= 0 {
+        return None;
+    }
+    longest_failure_streak().filter(|(_, failures)| *failures >= threshold as u64)
+}
+
+/// Returns the key and streak of the live strategy with the most failed runs in a row.
+///
+/// Paused strategies do not run, and warm-up runs only restart the warm-up when they fail.
+/// Streaks ending with an error that does not halt (see `ErrorCategory::halts`) are left out.
+fn longest_failure_streak() -> Option<(u32, u64)> {
+    live_failure_streaks(true)
+        .into_iter()
+        .max_by_key(|(key, failures)| (*failures, std::cmp::Reverse(*key)))
+}
+
+/// Returns the live strategies whose failure streak, ending with a configuration error,
+/// reached the halt threshold.
+fn misconfiguration_streaks() -> Vec<(u32, u64)> {
+    let threshold = flag_int!(FAILURE_HALT_THRESHOLD, DEFAULT_FAILURE_HALT_THRESHOLD);
+    if threshold <= 0 {
+        return vec![];
+    }
+    live_failure_streaks(false)
+        .into_iter()
+        .filter(|(_, failures)| *failures >= threshold as u64)
+        .collect()
+}
 
-    let _ = check_strategy_exits() || check_strategy_updates();
+/// Returns the keys and failure streaks of the live strategies whose last error halts, or
+/// does not halt, as given by `halts`.
+fn live_failure_streaks(halts: bool) -> Vec<(u32, u64)> {
+    STRATEGY_STATE.with(|strategies| {
+        strategies
+            .borrow()
+            .iter()
+            .filter(|(_, strategy)| strategy.settings.enabled && strategy.data.warmup.is_none())
+            .filter(|(_, strategy)| {
+                strategy
+                    .data
+                    .last_error
+                    .as_ref()
+                    .map_or(true, |err| err.category().halts())
+                    == halts
+            })
+            .map(|(key, strategy)| (*key, strategy.data.consecutive_failures))
+            .collect()
+    })
+}
+
+/// Returns the timestamp in seconds of the last recovery from a completed halt, zero if none.
+///
+/// The exit and update checks count the recovery as the latest activity, so that a recovered
+/// canister is not scheduled to halt again for the same stale timestamps.
+fn recovered_at() -> u64 {
+    HALT_STATE.with(|halt| halt.borrow().recovered_at.unwrap_or_default())
 }
 
 /// Checks if any strategy has updated a rate in the past 3 months.
@@ -87,15 +345,19 @@ fn check_strategy_updates() -> bool {
     });
 
     let mut no_update_strategies = 0;
+    let recovered_at = recovered_at();
 
     strategies.iter().for_each(|strategy| {
-        if is_older_than(strategy.data.last_update, 90) {
+        if is_older_than(strategy.data.last_update.max(recovered_at), 90) {
             no_update_strategies += 1;
         }
     });
 
     if no_update_strategies == strategies.len() {
-        schedule_halt("No strategy has updated a rate in the past 90 days.".to_string());
+        schedule_halt(
+            "No strategy has updated a rate in the past 90 days.".to_string(),
+            HaltCondition::NoRateUpdates { days: 90 },
+        );
         return true;
     }
 
@@ -116,58 +378,398 @@ fn check_strategy_exits() -> bool {
     });
 
     let mut unsuccessful_strategies = 0;
+    let recovered_at = recovered_at();
 
     strategies.iter().for_each(|strategy| {
-        if is_older_than(strategy.data.last_ok_exit, 7) {
+        if is_older_than(strategy.data.last_ok_exit.max(recovered_at), 7) {
             unsuccessful_strategies += 1;
         }
     });
 
     if unsuccessful_strategies == strategies.len() {
-        schedule_halt("No strategy has had a successful exit in the past 7 days.".to_string());
+        schedule_halt(
+            "No strategy has had a successful exit in the past 7 days.".to_string(),
+            HaltCondition::NoSuccessfulExits { days: 7 },
+        );
         return true;
     }
 
     false
 }
 
-/// Schedules a halt in 7 days
-fn schedule_halt(message: String) {
-    // Update the current status to `HaltingInProgress`
-    let current_time = time() / 1_000_000_000; // current time converted from nanoseconds to millis
-    let halts_at = current_time + 604_800_000; // current time + 7 days in milliseconds
-    HALT_STATE.with(|halt| {
-        *halt.borrow_mut() = Halt {
-            status: HaltStatus::HaltingInProgress { halts_at },
-            message: Some(message.clone()),
+/// Schedules a halt in `HALT_DELAY` seconds.
+fn schedule_halt(message: String, condition: HaltCondition) {
+    let halts_at = begin_halt(message, condition, Seconds::now().get());
+
+    set_timer(std::time::Duration::from_secs(HALT_DELAY), move || {
+        complete_halt(halts_at, Seconds::now().get());
+    });
+}
+
+/// Re-arms the completion of a halt in progress at `now` (in seconds), as upgrades clear the
+/// timer scheduled by `schedule_halt`.
+///
+/// A halt whose time passed during the upgrade is completed right away.
+pub fn resume_halt_countdown(now: u64) {
+    let HaltStatus::HaltingInProgress { halts_at } =
+        HALT_STATE.with(|halt| halt.borrow().status.clone())
+    else {
+        return;
+    };
+    if now >= halts_at {
+        complete_halt(halts_at, now);
+        return;
+    }
+    set_timer(std::time::Duration::from_secs(halts_at - now), move || {
+        complete_halt(halts_at, Seconds::now().get());
+    });
+}
+
+/// Sets the status to `HaltingInProgress` at `now` (in seconds) and returns the halt time.
+fn begin_halt(message: String, condition: HaltCondition, now: u64) -> u64 {
+    let halts_at = now.saturating_add(HALT_DELAY);
+    let current = Halt {
+        status: HaltStatus::HaltingInProgress { halts_at },
+        message: Some(message),
+        condition: Some(condition),
+        seconds_remaining: None,
+        recovered_at: HALT_STATE.with(|halt| halt.borrow().recovered_at),
+    };
+    let previous = HALT_STATE.with(|halt| halt.replace(current.clone()).status);
+    announce_transition(HaltTransition {
+        previous,
+        current,
+        at: now,
+    });
+    halts_at
+}
+
+/// Completes the halt that was scheduled for `halts_at`.
+///
+/// Does nothing if that halt was cancelled in the meantime, including when another halt
+/// was scheduled after the cancellation.
+fn complete_halt(halts_at: u64, now: u64) {
+    let transition = HALT_STATE.with(|halt| {
+        let mut halt = halt.borrow_mut();
+        if halt.status != (HaltStatus::HaltingInProgress { halts_at }) {
+            return None;
         }
+        let previous = std::mem::replace(&mut halt.status, HaltStatus::Halted { halted_at: now });
+        Some(HaltTransition {
+            previous,
+            current: halt.clone(),
+            at: now,
+        })
     });
+    if let Some(transition) = transition {
+        announce_transition(transition);
+    }
+}
 
-    // Schedule a timer for 7 days from now.
-    set_timer(std::time::Duration::from_secs(604_800), || {
-        HALT_STATE.with(|halt| {
-            *halt.borrow_mut() = Halt {
-                status: HaltStatus::Halted {
-                    halted_at: time() / 1_000_000_000,
-                },
-                message: Some(message),
-            }
-        });
+/// Cancels the halt in progress, returning the canister to `Functional`.
+///
+/// # Errors
+/// - `ManagerError::Custom` if no halt is in progress, a completed halt cannot be cancelled
+pub fn cancel_halt() -> ManagerResult<()> {
+    let (previous, current) = HALT_STATE.with(|halt| {
+        let mut halt = halt.borrow_mut();
+        if !matches!(halt.status, HaltStatus::HaltingInProgress { .. }) {
+            return Err(ManagerError::Custom(format!(
+                "There is no halt in progress to cancel, the status is {:?}.",
+                halt.status
+            )));
+        }
+        let current = Halt {
+            recovered_at: halt.recovered_at,
+            ..Halt::default()
+        };
+        Ok((
+            std::mem::replace(&mut *halt, current.clone()).status,
+            current,
+        ))
+    })?;
+    announce_transition(HaltTransition {
+        previous,
+        current,
+        at: Seconds::now().get(),
     });
+    // Whatever triggered the halt may still affect the strategies
+    start_warmup_all(WarmupReason::Unhalted);
+    Ok(())
 }
 
-/// Check if a given timestamp (milliseconds) is older than the given number of days
-fn is_older_than(timestamp_ms: u64, days: u64) -> bool {
-    if timestamp_ms == 0 {
-        return false;
+/// Checks the preconditions of a recovery from a completed halt.
+///
+/// At least one strategy must have an EOA, the ckETH balance must be at or above the ckETH
+/// threshold, and the cycles balance at or above the critical floor.
+fn check_recovery(cketh_balance: &Nat, cycles_balance: u128) -> ManagerResult<()> {
+    let has_eoa = STRATEGY_STATE.with(|strategies| {
+        strategies
+            .borrow()
+            .values()
+            .any(|strategy| strategy.settings.eoa_pk.is_some())
+    });
+    if !has_eoa {
+        return Err(ManagerError::Custom(
+            "No strategy has an EOA to sign rate adjustments.".to_string(),
+        ));
+    }
+
+    let cketh_threshold = charger_config().cketh_threshold;
+    if *cketh_balance < cketh_threshold {
+        return Err(ManagerError::Custom(format!(
+            "The ckETH balance {} is below the threshold {}.",
+            cketh_balance, cketh_threshold
+        )));
+    }
+
+    let cycles_floor = flag_int!(CRITICAL_CYCLES_FLOOR, DEFAULT_CRITICAL_CYCLES_FLOOR).max(0);
+    if cycles_balance < cycles_floor as u128 {
+        return Err(ManagerError::Custom(format!(
+            "The cycles balance {} is below the critical floor {}.",
+            cycles_balance, cycles_floor
+        )));
     }
+    Ok(())
+}
+
+/// Returns the canister from `Halted` to `Functional` at `now` (in seconds), once the
+/// preconditions of a recovery hold.
+///
+/// # Errors
+/// - `ManagerError::Custom` if the canister is not halted, no strategy has an EOA, or a
+///   balance is below its threshold
+pub fn resume_from_halt(cketh_balance: &Nat, cycles_balance: u128, now: u64) -> ManagerResult<()> {
+    let status = HALT_STATE.with(|halt| halt.borrow().status.clone());
+    if !matches!(status, HaltStatus::Halted { .. }) {
+        return Err(ManagerError::Custom(format!(
+            "The canister is not halted, the status is {:?}.",
+            status
+        )));
+    }
+    check_recovery(cketh_balance, cycles_balance)?;
+
+    // The exits and updates before the recovery no longer count towards a halt
+    let current = Halt {
+        recovered_at: Some(now),
+        ..Halt::default()
+    };
+    let previous = HALT_STATE.with(|halt| halt.replace(current.clone()).status);
+    announce_transition(HaltTransition {
+        previous,
+        current,
+        at: now,
+    });
+    // Whatever caused the halt may still affect the strategies
+    start_warmup_all(WarmupReason::Unhalted);
+    Ok(())
+}
 
-    // Get current time in milliseconds
-    let current_time_ms = time() / 1_000_000_000;
+/// Returns the halt state at `now` (in seconds), with the countdown of a halt in progress.
+pub fn halt_status(now: u64) -> Halt {
+    let mut halt = HALT_STATE.with(|halt| halt.borrow().clone());
+    if let HaltStatus::HaltingInProgress { halts_at } = halt.status {
+        halt.seconds_remaining = Some(halts_at.saturating_sub(now));
+    }
+    halt
+}
 
-    // Define the threshold
-    let threshold = current_time_ms - Duration::days(days as i64).num_milliseconds() as u64;
+/// Check if a given timestamp (seconds) is older than the given number of days
+///
+/// Unset (zero) timestamps are never considered old.
+fn is_older_than(timestamp: u64, days: u64) -> bool {
+    timestamp != 0 && Seconds(timestamp).is_older_than(Seconds::days(days), Seconds::now())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_halt_countdown() {
+        let halts_at = begin_halt("test".to_string(), HaltCondition::Trigger { id: 3 }, 1_000);
+
+        let halt = halt_status(1_000 + HALT_DELAY - 60);
+        assert_eq!(halt.status, HaltStatus::HaltingInProgress { halts_at });
+        assert_eq!(halt.seconds_remaining, Some(60));
+        assert_eq!(halt.condition, Some(HaltCondition::Trigger { id: 3 }));
+
+        complete_halt(halts_at, halts_at);
+        let halt = halt_status(halts_at + 10);
+        assert_eq!(
+            halt.status,
+            HaltStatus::Halted {
+                halted_at: halts_at
+            }
+        );
+        assert_eq!(halt.seconds_remaining, None);
+        assert!(cancel_halt().is_err());
+    }
 
-    // Compare timestamps
-    timestamp_ms < threshold
+    #[test]
+    fn test_cancelled_halt_is_not_completed() {
+        let first = begin_halt(
+            "first".to_string(),
+            HaltCondition::NoSuccessfulExits { days: 7 },
+            1_000,
+        );
+        assert_eq!(cancel_halt(), Ok(()));
+        assert_eq!(halt_status(2_000), Halt::default());
+
+        // A halt scheduled after the cancellation is not completed by the first timer
+        let second = begin_halt(
+            "second".to_string(),
+            HaltCondition::NoRateUpdates { days: 90 },
+            5_000,
+        );
+        complete_halt(first, first);
+        assert_eq!(
+            halt_status(5_000).status,
+            HaltStatus::HaltingInProgress { halts_at: second }
+        );
+        assert!(cancel_halt().is_ok());
+        assert!(cancel_halt().is_err());
+    }
+
+    #[test]
+    fn test_transitions_are_journaled() {
+        let last_note = || {
+            crate::state::JOURNAL.with(|journal| {
+                journal
+                    .borrow()
+                    .last()
+                    .and_then(|collection| collection.entries[0].note.clone())
+                    .unwrap_or_default()
+            })
+        };
+
+        let halts_at = begin_halt("test".to_string(), HaltCondition::Trigger { id: 1 }, 1_000);
+        assert!(last_note().starts_with("CRITICAL: The canister halts at"));
+        assert!(cancel_halt().is_ok());
+        assert!(last_note().starts_with("NOTICE: The halt"));
+
+        // A completion of a cancelled halt is not announced
+        complete_halt(halts_at, halts_at);
+        assert!(last_note().starts_with("NOTICE: The halt"));
+
+        assert!(set_halt_callback(Some(HaltCallback {
+            canister: Principal::anonymous(),
+            method: " ".to_string(),
+        }))
+        .is_err());
+        assert_eq!(halt_callback(), None);
+    }
+
+    #[cfg(feature = "test-clock")]
+    #[test]
+    fn test_is_older_than_uses_seconds() {
+        crate::clock::set_time(1_700_000_000);
+        assert!(!is_older_than(0, 7));
+        assert!(!is_older_than(1_700_000_000 - Seconds::days(7).get(), 7));
+        assert!(is_older_than(1_700_000_000 - Seconds::days(7).get() - 1, 7));
+        // Timestamps in the future do not underflow
+        assert!(!is_older_than(1_800_000_000, 7));
+    }
+
+    #[test]
+    fn test_longest_failure_streak() {
+        assert_eq!(longest_failure_streak(), None);
+
+        for (key, failures, enabled) in [(1, 2, true), (2, 50, true), (3, 80, false)] {
+            let mut strategy = StableStrategy::default();
+            strategy.settings.key = key;
+            strategy.settings.enabled = enabled;
+            strategy.data.consecutive_failures = failures;
+            STRATEGY_STATE.with(|strategies| strategies.borrow_mut().insert(key, strategy));
+        }
+        // The paused strategy is left out
+        assert_eq!(longest_failure_streak(), Some((2, 50)));
+
+        assert_eq!(halting_failure_streak(), Some((2, 50)));
+
+        STRATEGY_STATE.with(|strategies| {
+            strategies
+                .borrow_mut()
+                .get_mut(&2)
+                .unwrap()
+                .data
+                .consecutive_failures = 10
+        });
+        assert_eq!(halting_failure_streak(), None);
+
+        // A misconfigured strategy raises alerts instead of halting the canister
+        STRATEGY_STATE.with(|strategies| {
+            let mut strategies = strategies.borrow_mut();
+            let strategy = strategies.get_mut(&1).unwrap();
+            strategy.data.consecutive_failures = 60;
+            strategy.data.last_error = Some(ManagerError::NonExistentValue);
+        });
+        assert_eq!(longest_failure_streak(), Some((2, 10)));
+        assert_eq!(misconfiguration_streaks(), vec![(1, 60)]);
+    }
+
+    #[test]
+    fn test_resume_from_halt() {
+        let cketh_balance = charger_config().cketh_threshold;
+        let cycles_balance = DEFAULT_CRITICAL_CYCLES_FLOOR as u128;
+        assert!(resume_from_halt(&cketh_balance, cycles_balance, 100).is_err());
+
+        let halts_at = begin_halt("test".to_string(), HaltCondition::Trigger { id: 1 }, 1_000);
+        complete_halt(halts_at, halts_at);
+        // No strategy has an EOA
+        assert!(resume_from_halt(&cketh_balance, cycles_balance, halts_at).is_err());
+
+        let mut strategy = StableStrategy::default();
+        strategy.settings.eoa_pk = Some(alloy_primitives::Address::repeat_byte(1));
+        STRATEGY_STATE.with(|strategies| strategies.borrow_mut().insert(1, strategy));
+        assert!(resume_from_halt(&Nat::from(0_u8), cycles_balance, halts_at).is_err());
+        assert!(resume_from_halt(&cketh_balance, cycles_balance - 1, halts_at).is_err());
+        assert!(!is_functional());
+
+        assert_eq!(
+            resume_from_halt(&cketh_balance, cycles_balance, halts_at),
+            Ok(())
+        );
+        assert_eq!(
+            halt_status(halts_at),
+            Halt {
+                recovered_at: Some(halts_at),
+                ..Halt::default()
+            }
+        );
+    }
+
+    #[cfg(feature = "test-clock")]
+    #[test]
+    fn test_no_halt_right_after_recovery() {
+        let now = 1_700_000_000;
+        crate::clock::set_time(now);
+        let mut strategy = StableStrategy::default();
+        strategy.settings.eoa_pk = Some(alloy_primitives::Address::repeat_byte(1));
+        strategy.data.last_ok_exit = now - Seconds::days(30).get();
+        strategy.data.last_update = now - Seconds::days(100).get();
+        STRATEGY_STATE.with(|strategies| strategies.borrow_mut().insert(1, strategy));
+
+        let halts_at = begin_halt(
+            "test".to_string(),
+            HaltCondition::NoSuccessfulExits { days: 7 },
+            now - HALT_DELAY,
+        );
+        complete_halt(halts_at, halts_at);
+        let cketh_balance = charger_config().cketh_threshold;
+        let cycles_balance = DEFAULT_CRITICAL_CYCLES_FLOOR as u128;
+        assert_eq!(
+            resume_from_halt(&cketh_balance, cycles_balance, now),
+            Ok(())
+        );
+
+        // The stale exit and update predate the recovery, so no halt is scheduled
+        update_halt_status();
+        assert_eq!(halt_status(now).status, HaltStatus::Functional);
+
+        // A cancelled halt keeps the recovery time
+        begin_halt("test".to_string(), HaltCondition::Trigger { id: 1 }, now);
+        assert!(cancel_halt().is_ok());
+        assert_eq!(halt_status(now).recovered_at, Some(now));
+    }
 }
@@ -0,0 +1,134 @@
+//! Health Report
+//!
+//! The state of every subsystem in a single query, served by `health`, so that operators do
+//! not have to stitch it together from `get_strategies`, `get_logs`, and `halt_status`:
+//!
+//! ```plain
+//! health() ──► halt status ──────────────────────────────────────┐
+//!          ├─► cycles balance (live), ckETH balance (cached) ────┤
+//!          ├─► per strategy: lock, last successful execution ────┼──► HealthReport
+//!          └─► provider pools: reputations, disabled providers ──┘
+//! ```
+
+use candid::{CandidType, Nat};
+
+use crate::{
+    halt::{halt_status, Halt, HaltStatus},
+    providers::{disabled_providers, fetch_provider_list, DisabledProvider, ProviderPool},
+    state::STRATEGY_STATE,
+    strategy::{report::StrategyHealth, stable::StableStrategy},
+    treasury::{cached_balances, BalanceReading},
+    types::ProviderService,
+};
+
+/// Health of a single strategy
+#[derive(CandidType, Clone, Debug, PartialEq)]
+pub struct StrategyHealthReport {
+    /// Key of the strategy
+    pub key: u32,
+    /// Health of the strategy as seen by trove owners
+    pub health: StrategyHealth,
+    /// `false` if a controller paused the strategy
+    pub enabled: bool,
+    /// `true` while an execution holds the lock
+    pub is_locked: bool,
+    /// Seconds elapsed since the lock was acquired, if locked
+    pub locked_for: Option<u64>,
+    /// Timestamp in seconds of the last successful execution, `None` if it never succeeded
+    pub last_ok_exit: Option<u64>,
+    /// Seconds elapsed since the last successful execution
+    pub since_ok_exit: Option<u64>,
+}
+
+impl StrategyHealthReport {
+    /// Builds the health of a strategy at `now` (in seconds).
+    fn new(strategy: &StableStrategy, halt: &HaltStatus, now: u64) -> Self {
+        let last_ok_exit = Some(strategy.data.last_ok_exit).filter(|exit| *exit != 0);
+        Self {
+            key: strategy.settings.key,
+            health: StrategyHealth::evaluate(
+                halt,
+                strategy.settings.batch_manager,
+                strategy.data.dormant_since.is_some(),
+                strategy.data.last_ok_exit,
+                now,
+            ),
+            enabled: strategy.settings.enabled,
+            is_locked: strategy.lock.is_locked,
+            locked_for: strategy
+                .lock
+                .last_locked_at
+                .filter(|_| strategy.lock.is_locked)
+                .map(|locked_at| now.saturating_sub(locked_at)),
+            last_ok_exit,
+            since_ok_exit: last_ok_exit.map(|exit| now.saturating_sub(exit)),
+        }
+    }
+}
+
+/// Structured health report of the canister
+#[derive(CandidType, Clone, Debug, PartialEq)]
+pub struct HealthReport {
+    /// Halt status of the canister
+    pub halt: Halt,
+    /// Current cycles balance
+    pub cycles_balance: Nat,
+    /// Last observed ckETH balance in wei
+    pub cketh_balance: BalanceReading,
+    /// Health of every strategy, sorted by key
+    pub strategies: Vec<StrategyHealthReport>,
+    /// Reputations of the read pool providers
+    pub read_providers: Vec<(i64, ProviderService)>,
+    /// Reputations of the write pool providers
+    pub write_providers: Vec<(i64, ProviderService)>,
+    /// Providers excluded from the rankings
+    pub disabled_providers: Vec<DisabledProvider>,
+}
+
+/// Returns the health report of the canister at `now` (in seconds).
+pub fn health(cycles_balance: Nat, now: u64) -> HealthReport {
+    let halt = halt_status(now);
+    let mut strategies: Vec<StrategyHealthReport> = STRATEGY_STATE.with(|state| {
+        state
+            .borrow()
+            .values()
+            .map(|strategy| StrategyHealthReport::new(strategy, &halt.status, now))
+            .collect()
+    });
+    strategies.sort_by_key(|strategy| strategy.key);
+
+    HealthReport {
+        halt,
+        cycles_balance,
+        cketh_balance: cached_balances(now).cketh,
+        strategies,
+        read_providers: fetch_provider_list(ProviderPool::Read),
+        write_providers: fetch_provider_list(ProviderPool::Write),
+        disabled_providers: disabled_providers(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strategy_health() {
+        let mut strategy = StableStrategy::default();
+        strategy.settings.key = 3;
+        strategy.lock.last_locked_at = Some(100);
+        let health = StrategyHealthReport::new(&strategy, &HaltStatus::Functional, 1_000);
+        assert_eq!(health.health, StrategyHealth::Inactive);
+        assert!(!health.is_locked);
+        assert_eq!(health.locked_for, None);
+        assert_eq!(health.last_ok_exit, None);
+        assert_eq!(health.since_ok_exit, None);
+
+        strategy.lock.is_locked = true;
+        strategy.data.last_ok_exit = 400;
+        let health = StrategyHealthReport::new(&strategy, &HaltStatus::Functional, 1_000);
+        assert_eq!(health.locked_for, Some(900));
+        assert_eq!(health.last_ok_exit, Some(400));
+        assert_eq!(health.since_ok_exit, Some(600));
+    }
+}
@@ -0,0 +1,281 @@
+//! Installation With Init Arguments
+//!
+//! Configures the canister in one step at install time, so that a deployment is reproducible
+//! from its init arguments and the canister can be made immutable right after:
+//!
+//! ```plain
+//! install(args)
+//!    │
+//!    ├──► key name, charger config, trove managers, EVM RPC canister   (in `init`)
+//!    │
+//!    └──► one-shot timer ──► per strategy: mint ──► set batch manager   (after `init`)
+//!                                              │
+//!                                              ▼
+//!                            every strategy set up? ──► start_system_timers
+//! ```
+//!
+//! Minting derives the EOAs with tECDSA and checks the contracts through the EVM RPC canister,
+//! which are inter-canister calls that `init` cannot make. Invalid init arguments reject the
+//! installation. A strategy that fails to be set up is journaled, and the timers are then left
+//! for the controller to start once the installation is completed with the update calls.
+
+use std::time::Duration;
+
+use candid::{CandidType, Nat, Principal};
+use ic_exports::{ic_cdk::spawn, ic_cdk_timers::set_timer};
+use serde::Deserialize;
+
+use crate::{
+    charger_config::{self, ChargerConfig},
+    journal::{JournalCollection, LogType},
+    managers,
+    rpc_registry::{register_rpc_canister, RpcCanisterRecord},
+    strategy::setup::{mint_strategy, set_batch_manager},
+    timers::start_system_timers,
+    types::StrategyInput,
+    utils::{
+        address::parse_address,
+        error::{ManagerError, ManagerResult},
+        signer::set_ecdsa_key_name,
+    },
+};
+
+/// Configuration of the canister provided at install time
+#[derive(CandidType, Deserialize, Debug)]
+pub struct InitArgs {
+    /// Name of the threshold ECDSA key the EOAs are derived from, `key_1` if `None`
+    pub ecdsa_key_name: Option<String>,
+    /// EVM RPC canister used by all strategies, registered as effective from the installation
+    pub rpc_principal: Option<Principal>,
+    /// Trove managers to register in addition to the ones of the strategies
+    pub managers: Vec<String>,
+    /// Thresholds and discount of the ckETH<>Cycles arbitrage, the defaults if `None`
+    pub charger_config: Option<ChargerConfig>,
+    /// Strategies to mint after the installation
+    pub strategies: Vec<InitStrategy>,
+    /// Starts the timers once every strategy is set up
+    pub start_timers: bool,
+}
+
+/// A strategy to mint after the installation
+#[derive(CandidType, Deserialize, Debug)]
+pub struct InitStrategy {
+    /// Same input as `mint_strategy`
+    pub strategy: StrategyInput,
+    /// Batch manager to assign once the strategy is minted
+    pub batch_manager: Option<InitBatchManager>,
+}
+
+/// A batch manager to assign to a strategy minted after the installation
+#[derive(CandidType, Deserialize, Debug)]
+pub struct InitBatchManager {
+    /// Address of the batch manager contract
+    pub address: String,
+    /// Current interest rate of the batch manager
+    pub current_rate: Nat,
+}
+
+/// Applies the configuration of the init arguments at `now` (in seconds), and schedules the
+/// setup of the strategies.
+///
+/// # Errors
+/// Returns the first invalid argument.
+pub fn install(args: InitArgs, now: u64) -> ManagerResult<()> {
+    apply_init_args(&args, now)?;
+    if !args.strategies.is_empty() || args.start_timers {
+        let InitArgs {
+            strategies,
+            start_timers,
+            ..
+        } = args;
+        set_timer(Duration::ZERO, move || {
+            spawn(install_strategies(strategies, start_timers));
+        });
+    }
+    Ok(())
+}
+
+/// Applies the synchronous part of the init arguments at `now` (in seconds).
+fn apply_init_args(args: &InitArgs, now: u64) -> ManagerResult<()> {
+    let mut keys: Vec<u32> = args
+        .strategies
+        .iter()
+        .map(|init| init.strategy.key)
+        .collect();
+    keys.sort_unstable();
+    if let Some(key) = keys.windows(2).find(|pair| pair[0] == pair[1]) {
+        return Err(ManagerError::Custom(format!(
+            "Strategy {} is listed more than once.",
+            key[0]
+        )));
+    }
+
+    if let Some(name) = &args.ecdsa_key_name {
+        set_ecdsa_key_name(name.clone())?;
+    }
+    if let Some(config) = &args.charger_config {
+        charger_config::set_charger_config(config.clone())?;
+    }
+    for manager in &args.managers {
+        managers::add_manager(parse_address("managers", manager, false)?)?;
+    }
+    if let Some(principal) = args.rpc_principal {
+        register_rpc_canister(
+            RpcCanisterRecord {
+                principal,
+                effective_from: now,
+                module_hash: None,
+                version: None,
+                registered_at: now,
+            },
+            now,
+        )?;
+    }
+    Ok(())
+}
+
+/// Mints the strategies of the init arguments and assigns their batch managers, then starts
+/// the timers if requested and every strategy is set up.
+async fn install_strategies(strategies: Vec<InitStrategy>, start_timers: bool) {
+    let mut complete = true;
+    for InitStrategy {
+        strategy,
+        batch_manager,
+    } in strategies
+    {
+        let key = strategy.key;
+        let result = match mint_strategy(strategy).await {
+            Ok(eoa) => match batch_manager {
+                Some(batch_manager) => {
+                    set_batch_manager(key, batch_manager.address, batch_manager.current_rate)
+                        .await
+                        .map(|_| eoa)
+                }
+                None => Ok(eoa),
+            },
+            Err(err) => Err(ManagerError::Custom(format!(
+                "Failed to mint the strategy: {:?}",
+                err
+            ))),
+        };
+        let mut journal = JournalCollection::open(Some(key));
+        match result {
+            Ok(eoa) => journal.append_note(
+                Ok(()),
+                LogType::Info,
+                format!("Installed the strategy with EOA {}.", eoa),
+            ),
+            Err(err) => {
+                complete = false;
+                journal.append_note(
+                    Err(err),
+                    LogType::Info,
+                    "Failed to install the strategy. It has to be set up with the update calls.",
+                )
+            }
+        };
+    }
+
+    if !start_timers {
+        return;
+    }
+    let mut journal = JournalCollection::open(None);
+    if complete {
+        start_system_timers();
+        journal.append_note(
+            Ok(()),
+            LogType::Info,
+            "Installation completed. The timers are started.",
+        );
+    } else {
+        journal.append_note(
+            Err(ManagerError::Custom(
+                "Not every strategy was installed.".to_string(),
+            )),
+            LogType::Info,
+            "The timers are not started until the installation is completed with the update calls.",
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        constants::DEFAULT_ECDSA_KEY_NAME, rpc_registry::rpc_canisters,
+        utils::signer::ecdsa_key_name,
+    };
+
+    fn strategy(key: u32) -> InitStrategy {
+        InitStrategy {
+            strategy: StrategyInput {
+                key,
+                target_min: Nat::from(1u8),
+                manager: String::new(),
+                multi_trove_getter: String::new(),
+                sorted_troves: String::new(),
+                collateral_index: Nat::from(0u8),
+                rpc_principal: Principal::anonymous(),
+                upfront_fee_period: Nat::from(0u8),
+                collateral_registry: String::new(),
+                hint_helper: String::new(),
+                force: None,
+                chain: None,
+            },
+            batch_manager: None,
+        }
+    }
+
+    fn args() -> InitArgs {
+        InitArgs {
+            ecdsa_key_name: None,
+            rpc_principal: None,
+            managers: vec![],
+            charger_config: None,
+            strategies: vec![],
+            start_timers: false,
+        }
+    }
+
+    #[test]
+    fn test_apply_init_args() {
+        apply_init_args(&args(), 100).unwrap();
+        assert_eq!(ecdsa_key_name(), DEFAULT_ECDSA_KEY_NAME);
+
+        let manager = "0x1111111111111111111111111111111111111111";
+        apply_init_args(
+            &InitArgs {
+                ecdsa_key_name: Some("test_key_1".to_string()),
+                rpc_principal: Some(Principal::anonymous()),
+                managers: vec![manager.to_string()],
+                ..args()
+            },
+            100,
+        )
+        .unwrap();
+        assert_eq!(ecdsa_key_name(), "test_key_1");
+        assert_eq!(managers::managers().len(), 1);
+        assert_eq!(rpc_canisters()[0].effective_from, 100);
+    }
+
+    #[test]
+    fn test_invalid_init_args() {
+        let duplicates = InitArgs {
+            strategies: vec![strategy(1), strategy(2), strategy(1)],
+            ..args()
+        };
+        assert!(apply_init_args(&duplicates, 0).is_err());
+
+        let unknown_key = InitArgs {
+            ecdsa_key_name: Some("key_9".to_string()),
+            ..args()
+        };
+        assert!(apply_init_args(&unknown_key, 0).is_err());
+
+        let invalid_manager = InitArgs {
+            managers: vec!["0x12".to_string()],
+            ..args()
+        };
+        assert!(apply_init_args(&invalid_manager, 0).is_err());
+    }
+}
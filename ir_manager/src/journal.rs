@@ -15,32 +15,228 @@
 //! - `LogType`: Enum to categorize log entries.
 //!
 //! Journals are automatically closed and committed to the state upon dropping their instance.
+//!
+//! # Timestamps
+//!
+//! Timestamps are stored as seconds since the UNIX epoch, and formatted only in the query
+//! representations (`JournalCollectionQuery`, `JournalEntryQuery`). Collections stored before
+//! that held formatted strings; they are still decoded, and rewritten once after an upgrade:
+//!
+//! ```plain
+//! stored bytes ──► decode ──ok──────────────────────────────► StableJournalCollection (u64)
+//!                    │                                                  ▲
+//!                    └─fails──► decode legacy (strings) ──► parse ──────┘
+//!
+//! post_upgrade ──► migrate_journal_timestamps ──► re-encode every collection (once)
+//! ```
 
 use std::borrow::Cow;
 
 use candid::{CandidType, Decode, Encode};
-#[cfg(not(test))]
-use chrono::{DateTime, Utc};
-#[cfg(not(test))]
-use ic_exports::ic_cdk::api::time;
+use chrono::{DateTime, NaiveDateTime, Utc};
 use ic_stable_structures::{storable::Bound, Storable};
 use serde::Deserialize;
 
-use crate::{state::insert_journal_collection, utils::error::*};
+use crate::{
+    clock::Seconds,
+    state::{insert_journal_collection, JOURNAL, JOURNAL_SCHEMA_VERSION, JOURNAL_SEQUENCE},
+    utils::error::*,
+};
+
+/// Version of the journal schema with timestamps in seconds
+pub const JOURNAL_TIMESTAMPS_VERSION: u64 = 1;
 
 /// A stable representation of the journal collection.
 ///
 /// This structure is storable in stable memory and is used for persisting journal entries.
 #[derive(CandidType, Deserialize, Clone)]
 pub struct StableJournalCollection {
-    /// Start timestamp when the journal was created
-    pub start_date_and_time: String,
-    /// End timestamp when the journal was closed
-    pub end_date_and_time: String,
+    /// Timestamp in seconds when the journal was created
+    pub start_timestamp: u64,
+    /// Timestamp in seconds when the journal was closed
+    pub end_timestamp: u64,
     /// Optional strategy ID associated with the journal.
     pub strategy: Option<u32>,
+    /// Optional ID of the strategy run that produced the journal.
+    pub run_id: Option<u64>,
     /// A list of `JournalEntry` instances representing individual logs
     pub entries: Vec<JournalEntry>,
+    /// Increasing sequence number assigned when the collection is stored.
+    /// Unlike the position in the journal, it does not change when old collections are pruned.
+    pub sequence: Option<u64>,
+}
+
+/// Query representation of a journal collection, with formatted timestamps
+#[derive(CandidType, Deserialize, Clone)]
+pub struct JournalCollectionQuery {
+    /// Timestamp in seconds when the journal was created
+    pub start_timestamp: u64,
+    /// Timestamp in seconds when the journal was closed
+    pub end_timestamp: u64,
+    /// Creation time in the `dd-mm-yyyy hh:mm:ss` format
+    pub start_date_and_time: String,
+    /// Closing time in the `dd-mm-yyyy hh:mm:ss` format
+    pub end_date_and_time: String,
+    /// Optional strategy ID associated with the journal.
+    pub strategy: Option<u32>,
+    /// Optional ID of the strategy run that produced the journal.
+    pub run_id: Option<u64>,
+    /// Entries of the collection
+    pub entries: Vec<JournalEntryQuery>,
+    /// Sequence number of the collection
+    pub sequence: Option<u64>,
+}
+
+impl From<StableJournalCollection> for JournalCollectionQuery {
+    fn from(value: StableJournalCollection) -> Self {
+        Self {
+            start_timestamp: value.start_timestamp,
+            end_timestamp: value.end_timestamp,
+            start_date_and_time: format_timestamp(value.start_timestamp),
+            end_date_and_time: format_timestamp(value.end_timestamp),
+            strategy: value.strategy,
+            run_id: value.run_id,
+            entries: value
+                .entries
+                .into_iter()
+                .map(JournalEntryQuery::from)
+                .collect(),
+            sequence: value.sequence,
+        }
+    }
+}
+
+/// Query representation of a journal entry, with a formatted timestamp
+#[derive(CandidType, Deserialize, Clone)]
+pub struct JournalEntryQuery {
+    /// Timestamp in seconds when the entry was created
+    pub timestamp: u64,
+    /// Creation time in the `dd-mm-yyyy hh:mm:ss` format
+    pub date_and_time: String,
+    /// Optional ID of the strategy run that produced the entry.
+    pub run_id: Option<u64>,
+    /// The result or status associated with the log.
+    pub entry: ManagerResult<()>,
+    /// Optional note providing additional details.
+    pub note: Option<String>,
+    /// The type/category of the log.
+    pub log_type: LogType,
+}
+
+impl From<JournalEntry> for JournalEntryQuery {
+    fn from(value: JournalEntry) -> Self {
+        Self {
+            timestamp: value.timestamp,
+            date_and_time: format_timestamp(value.timestamp),
+            run_id: value.run_id,
+            entry: value.entry,
+            note: value.note,
+            log_type: value.log_type,
+        }
+    }
+}
+
+/// Converts stored collections to their query representation.
+pub fn to_query(collections: Vec<StableJournalCollection>) -> Vec<JournalCollectionQuery> {
+    collections
+        .into_iter()
+        .map(JournalCollectionQuery::from)
+        .collect()
+}
+
+/// A page of journal collections, returned by cursor-based journal queries
+#[derive(CandidType, Deserialize, Clone)]
+pub struct LogPage {
+    /// Collections ordered from oldest to newest
+    pub collections: Vec<JournalCollectionQuery>,
+    /// Cursor of the next page; pass it again later to receive the collections stored meanwhile
+    pub next_cursor: u64,
+    /// `true` if more collections are already available after this page
+    pub has_more: bool,
+}
+
+/// A single journal collection, returned by index-based journal queries
+#[derive(CandidType, Deserialize, Clone)]
+pub struct IndexedLog {
+    /// Collection at the requested position, `None` if the position is out of range
+    pub collection: Option<JournalCollectionQuery>,
+    /// Number of collections in the journal at the time of the call
+    pub total: u64,
+}
+
+/// Returns the journal collection at `index`, counted from the oldest collection still in
+/// the journal, along with the number of collections.
+///
+/// Positions only shift when old collections are pruned; the sequence number of the
+/// returned collection identifies it across prunings.
+pub fn journal_collection_at(index: u64) -> IndexedLog {
+    JOURNAL.with_borrow(|journal| IndexedLog {
+        collection: journal.get(index).map(JournalCollectionQuery::from),
+        total: journal.len(),
+    })
+}
+
+/// Returns up to `limit` journal collections with a sequence number of at least `cursor`.
+///
+/// Without a cursor, the page starts at the oldest collection still in the journal. If the
+/// collections at the cursor were pruned, the page starts at the oldest remaining one, and the
+/// gap shows in the sequence number of its first collection.
+pub fn journal_page(cursor: Option<u64>, limit: u64) -> LogPage {
+    JOURNAL.with_borrow(|journal| {
+        let sequence_at = |index: u64| {
+            journal
+                .get(index)
+                .and_then(|collection| collection.sequence)
+                .unwrap_or_default()
+        };
+
+        // Sequence numbers increase with the position, so the start is found by binary search
+        let (mut low, mut high) = (0, journal.len());
+        if let Some(cursor) = cursor {
+            while low < high {
+                let middle = low + (high - low) / 2;
+                if sequence_at(middle) < cursor {
+                    low = middle + 1;
+                } else {
+                    high = middle;
+                }
+            }
+        }
+
+        let end = low.saturating_add(limit).min(journal.len());
+        let collections: Vec<StableJournalCollection> =
+            (low..end).filter_map(|index| journal.get(index)).collect();
+        let next_cursor = match collections.last() {
+            Some(last) => last.sequence.unwrap_or_default().saturating_add(1),
+            None => {
+                cursor.unwrap_or_else(|| JOURNAL_SEQUENCE.with(|counter| *counter.borrow().get()))
+            }
+        };
+
+        LogPage {
+            collections: to_query(collections),
+            next_cursor,
+            has_more: end < journal.len(),
+        }
+    })
+}
+
+/// Removes the collections of a strategy from the journal and returns them, oldest first.
+///
+/// The remaining collections keep their order, so the sequence numbers stay increasing.
+pub fn take_strategy_collections(strategy: u32) -> Vec<StableJournalCollection> {
+    let mut taken = vec![];
+    JOURNAL.with_borrow_mut(|journal| {
+        journal.retain(|collection| {
+            if collection.strategy == Some(strategy) {
+                taken.push(collection.clone());
+                false
+            } else {
+                true
+            }
+        })
+    });
+    taken
 }
 
 impl StableJournalCollection {
@@ -63,9 +259,14 @@ impl Storable for StableJournalCollection {
         Cow::Owned(Encode!(self).unwrap())
     }
 
-    /// Deserializes a collection from bytes.
+    /// Deserializes a collection from bytes, including the legacy schema with formatted
+    /// timestamps.
     fn from_bytes(bytes: std::borrow::Cow<[u8]>) -> Self {
-        Decode!(bytes.as_ref(), Self).unwrap()
+        Decode!(bytes.as_ref(), Self).unwrap_or_else(|_| {
+            Decode!(bytes.as_ref(), LegacyJournalCollection)
+                .expect("Failed to decode a journal collection.")
+                .into()
+        })
     }
 
     /// Specifies the maximum size and dynamic nature of the stored collection.
@@ -75,18 +276,138 @@ impl Storable for StableJournalCollection {
     };
 }
 
+/// Journal collection as stored before the timestamps were kept in seconds
+#[derive(CandidType, Deserialize, Clone)]
+pub(crate) struct LegacyJournalCollection {
+    start_date_and_time: String,
+    end_date_and_time: String,
+    strategy: Option<u32>,
+    run_id: Option<u64>,
+    entries: Vec<LegacyJournalEntry>,
+    sequence: Option<u64>,
+}
+
+/// Journal entry as stored before the timestamps were kept in seconds
+#[derive(CandidType, Deserialize, Clone)]
+pub(crate) struct LegacyJournalEntry {
+    date_and_time: String,
+    run_id: Option<u64>,
+    entry: ManagerResult<()>,
+    note: Option<String>,
+    log_type: LogType,
+}
+
+/// Unparseable legacy timestamps become zero, which the retention policy treats as unknown.
+impl From<LegacyJournalCollection> for StableJournalCollection {
+    fn from(value: LegacyJournalCollection) -> Self {
+        let seconds = |date_and_time: &str| parse_date_and_time(date_and_time).unwrap_or(0);
+        Self {
+            start_timestamp: seconds(&value.start_date_and_time),
+            end_timestamp: seconds(&value.end_date_and_time),
+            strategy: value.strategy,
+            run_id: value.run_id,
+            entries: value
+                .entries
+                .into_iter()
+                .map(|entry| JournalEntry {
+                    timestamp: seconds(&entry.date_and_time),
+                    run_id: entry.run_id,
+                    entry: entry.entry,
+                    note: entry.note,
+                    log_type: entry.log_type,
+                })
+                .collect(),
+            sequence: value.sequence,
+        }
+    }
+}
+
+/// Rewrites the journal collections stored with formatted timestamps in the current schema,
+/// which also shrinks them.
+///
+/// Only has an effect on the first upgrade to a version with timestamps in seconds.
+pub fn migrate_journal_timestamps() {
+    let version = JOURNAL_SCHEMA_VERSION.with(|version| *version.borrow().get());
+    if version >= JOURNAL_TIMESTAMPS_VERSION {
+        return;
+    }
+    JOURNAL.with_borrow(|journal| {
+        for index in 0..journal.len() {
+            // Legacy collections are converted when they are read
+            if let Some(collection) = journal.get(index) {
+                journal.set(index, &collection);
+            }
+        }
+    });
+    JOURNAL_SCHEMA_VERSION.with(|version| {
+        version
+            .borrow_mut()
+            .set(JOURNAL_TIMESTAMPS_VERSION)
+            .expect("Failed to persist the journal schema version.");
+    });
+}
+
+/// Narrows down the entries returned by journal queries.
+///
+/// Bounds are timestamps in seconds and are inclusive. Unset fields match every entry.
+#[derive(CandidType, Deserialize, Clone, Debug, Default)]
+pub struct LogFilter {
+    /// Only return entries of this type
+    pub log_type: Option<LogType>,
+    /// Only return entries created at or after this time
+    pub from_timestamp: Option<u64>,
+    /// Only return entries created at or before this time
+    pub to_timestamp: Option<u64>,
+}
+
+impl LogFilter {
+    /// Returns `true` if the entry matches the filter.
+    ///
+    /// Entries without a known timestamp never match a time bound.
+    pub fn matches(&self, entry: &JournalEntry) -> bool {
+        if let Some(log_type) = &self.log_type {
+            if entry.log_type != *log_type {
+                return false;
+            }
+        }
+        if self.from_timestamp.is_none() && self.to_timestamp.is_none() {
+            return true;
+        }
+        let created_at = entry.timestamp;
+        created_at != 0
+            && self.from_timestamp.map_or(true, |from| created_at >= from)
+            && self.to_timestamp.map_or(true, |to| created_at <= to)
+    }
+
+    /// Keeps only the matching entries of a collection.
+    /// Returns `None` if no entry of the collection matches.
+    pub fn apply(
+        &self,
+        mut collection: StableJournalCollection,
+    ) -> Option<StableJournalCollection> {
+        collection.entries.retain(|entry| self.matches(entry));
+        if collection.entries.is_empty() {
+            None
+        } else {
+            Some(collection)
+        }
+    }
+}
+
 /// A runtime journal collection for recording log entries.
 ///
 /// This structure represents an open, time-bound log journal. Upon dropping the collection,
 /// it is closed and its data is committed to the state.
 #[derive(CandidType, Deserialize, Clone)]
 pub struct JournalCollection {
-    /// Timestamp when the journal was opened.
-    pub start_date_and_time: String,
-    /// Timestamp when the journal was closed.
-    pub end_date_and_time: String,
+    /// Timestamp in seconds when the journal was opened.
+    pub start_timestamp: u64,
+    /// Timestamp in seconds when the journal was closed, zero while it is open.
+    pub end_timestamp: u64,
     /// Optional strategy ID.
     pub strategy: Option<u32>,
+    /// Optional ID of the strategy run.
+    pub run_id: Option<u64>,
     /// A vector of `JournalEntry` instances.
     pub entries: Vec<JournalEntry>,
 }
@@ -94,8 +415,10 @@ pub struct JournalCollection {
 /// Represents a single log entry within a journal.
 #[derive(CandidType, Deserialize, Clone)]
 pub struct JournalEntry {
-    /// Timestamp when the entry was created.
-    pub date_and_time: String,
+    /// Timestamp in seconds when the entry was created.
+    pub timestamp: u64,
+    /// Optional ID of the strategy run that produced the entry.
+    pub run_id: Option<u64>,
     /// The result or status associated with the log.
     pub entry: ManagerResult<()>,
     /// Optional note providing additional details.
@@ -117,6 +440,8 @@ pub enum LogType {
     ProviderReputationChange,
     /// Logs related to recharges.
     Recharge,
+    /// Logs rate adjustments held back or let through by the rate guard.
+    SafetyTriggered,
 }
 
 impl JournalCollection {
@@ -129,24 +454,42 @@ impl JournalCollection {
     /// A new `JournalCollection` instance with the start time initialized.
     pub fn open(strategy: Option<u32>) -> Self {
         Self {
-            start_date_and_time: date_and_time(),
-            end_date_and_time: String::new(),
+            start_timestamp: Seconds::now().get(),
+            end_timestamp: 0,
             strategy,
+            run_id: None,
             entries: Vec::with_capacity(16), // Pre-allocated capacity for efficiency.
         }
     }
 
+    /// Tags the journal and all of its entries with a strategy run ID.
+    ///
+    /// # Arguments
+    /// - `run_id`: The ID of the run that the journal belongs to.
+    ///
+    /// # Returns
+    /// A mutable reference to the updated journal collection.
+    pub fn set_run_id(&mut self, run_id: u64) -> &mut Self {
+        self.run_id = Some(run_id);
+        self.entries
+            .iter_mut()
+            .for_each(|entry| entry.run_id = Some(run_id));
+        self
+    }
+
     /// Closes the journal and commits it to the state.
     ///
     /// This method sets the end time and stores the journal into stable storage.
     /// It is automatically called when the journal is dropped.
     fn close(&mut self) {
-        self.end_date_and_time = date_and_time();
+        self.end_timestamp = Seconds::now().get();
         let stable_jc = StableJournalCollection {
-            start_date_and_time: self.start_date_and_time.clone(),
-            end_date_and_time: self.end_date_and_time.clone(),
+            start_timestamp: self.start_timestamp,
+            end_timestamp: self.end_timestamp,
             strategy: self.strategy,
+            run_id: self.run_id,
             entries: self.entries.clone(),
+            sequence: None,
         };
         insert_journal_collection(stable_jc);
     }
@@ -166,7 +509,8 @@ impl JournalCollection {
         log_type: LogType,
         note: S,
     ) -> &mut Self {
-        let journal_entry = JournalEntry::new(entry, log_type, Some(note.as_ref().to_string()));
+        let mut journal_entry = JournalEntry::new(entry, log_type, Some(note.as_ref().to_string()));
+        journal_entry.run_id = self.run_id;
         self.entries.push(journal_entry);
         self
     }
@@ -191,7 +535,8 @@ impl JournalEntry {
     /// A new `JournalEntry` instance.
     fn new(entry: ManagerResult<()>, log_type: LogType, note: Option<String>) -> Self {
         Self {
-            date_and_time: date_and_time(),
+            timestamp: Seconds::now().get(),
+            run_id: None,
             entry,
             note,
             log_type,
@@ -199,27 +544,29 @@ impl JournalEntry {
     }
 }
 
-/// Generates the current date and time as a formatted string.
+/// Formats a timestamp in seconds in the `dd-mm-yyyy hh:mm:ss` format (UTC).
 ///
-/// # Returns
-/// A string representing the current UTC time in the format `dd-mm-yyyy hh:mm:ss`.
-#[cfg(not(test))]
-fn date_and_time() -> String {
-    let timestamp_s: i64 = time() as i64 / 1_000_000_000;
-    let datetime = DateTime::<Utc>::from_timestamp(timestamp_s, 0).expect("Invalid timestamp");
-
-    datetime.format("%d-%m-%Y %H:%M:%S").to_string()
+/// Unknown (zero) timestamps are formatted as an empty string.
+pub fn format_timestamp(seconds: u64) -> String {
+    if seconds == 0 {
+        return String::new();
+    }
+    DateTime::<Utc>::from_timestamp(seconds as i64, 0)
+        .map(|datetime| datetime.format("%d-%m-%Y %H:%M:%S").to_string())
+        .unwrap_or_default()
 }
 
-#[cfg(test)]
-fn date_and_time() -> String {
-    "03-01-2009 10:15:05".to_string()
+/// Parses a journal timestamp in the `dd-mm-yyyy hh:mm:ss` format into seconds since the UNIX epoch.
+pub fn parse_date_and_time(date_and_time: &str) -> Option<u64> {
+    NaiveDateTime::parse_from_str(date_and_time, "%d-%m-%Y %H:%M:%S")
+        .ok()
+        .and_then(|datetime| u64::try_from(datetime.and_utc().timestamp()).ok())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::utils::error::ManagerResult;
+    use crate::{state::JOURNAL, utils::error::ManagerResult};
 
     #[test]
     fn test_journal_collection_open() {
@@ -227,8 +574,8 @@ mod tests {
         let collection = JournalCollection::open(strategy_id);
 
         assert_eq!(collection.strategy, strategy_id);
-        assert!(!collection.start_date_and_time.is_empty());
-        assert!(collection.end_date_and_time.is_empty());
+        assert_eq!(collection.start_timestamp, Seconds::now().get());
+        assert_eq!(collection.end_timestamp, 0);
         assert!(collection.entries.is_empty());
     }
 
@@ -244,11 +591,12 @@ mod tests {
 
         assert_eq!(entry.log_type, log_type);
         assert_eq!(entry.note.as_deref(), Some("Test note"));
-        assert!(!entry.date_and_time.is_empty());
+        assert_eq!(entry.timestamp, Seconds::now().get());
     }
 
     #[test]
     fn test_close_sets_end_time_and_calls_insert() {
+        crate::clock::set_time(1_231_006_505);
         let mut collection = JournalCollection::open(Some(1));
         let log_type = LogType::ExecutionResult;
 
@@ -257,7 +605,69 @@ mod tests {
 
         collection.close();
 
-        assert!(!collection.end_date_and_time.is_empty());
+        assert_eq!(collection.end_timestamp, 1_231_006_505);
+    }
+
+    #[test]
+    fn test_oversized_collection_is_replaced() {
+        let mut collection = JournalCollection::open(Some(1));
+        collection.append_note(ManagerResult::Ok(()), LogType::Info, "x".repeat(40_000));
+
+        let stored_before = JOURNAL.with(|journal| journal.borrow().len());
+        collection.close();
+
+        let stored =
+            JOURNAL.with(|journal| journal.borrow().last().expect("A replacement is stored."));
+        assert_eq!(
+            JOURNAL.with(|journal| journal.borrow().len()),
+            stored_before + 1
+        );
+        assert_eq!(stored.entries.len(), 1);
+        assert!(stored.entries[0].entry.is_err());
+
+        let integrity = crate::metrics::get_metrics().journal;
+        assert_eq!(integrity.dropped_writes, 1);
+        assert_eq!(integrity.lost_writes, 0);
+        assert!(integrity.last_error.is_some());
+    }
+
+    #[test]
+    fn test_collection_limit_is_enforced_on_append() {
+        crate::retention::set_journal_retention(crate::retention::RetentionPolicy {
+            max_collections: Some(2),
+            max_age_days: None,
+            max_bytes: None,
+        })
+        .unwrap();
+        for note in ["First", "Second", "Third"] {
+            JournalCollection::open(None).append_note(Ok(()), LogType::Info, note);
+        }
+
+        let notes: Vec<Option<String>> = JOURNAL.with_borrow(|journal| {
+            journal
+                .iter()
+                .map(|collection| collection.entries[0].note.clone())
+                .collect()
+        });
+        assert_eq!(
+            notes,
+            vec![Some("Second".to_string()), Some("Third".to_string())]
+        );
+        assert_eq!(crate::metrics::get_metrics().journal_retention.by_count, 1);
+    }
+
+    #[test]
+    fn test_set_run_id_tags_entries() {
+        let mut collection = JournalCollection::open(Some(1));
+        collection.append_note(ManagerResult::Ok(()), LogType::Info, "Before");
+        collection.set_run_id(9);
+        collection.append_note(ManagerResult::Ok(()), LogType::Info, "After");
+
+        assert_eq!(collection.run_id, Some(9));
+        assert!(collection
+            .entries
+            .iter()
+            .all(|entry| entry.run_id == Some(9)));
     }
 
     #[test]
@@ -273,7 +683,171 @@ mod tests {
 
         assert_eq!(entry.log_type, log_type);
         assert_eq!(entry.note.as_deref(), Some(note));
-        assert!(!entry.date_and_time.is_empty());
+        assert_eq!(entry.timestamp, Seconds::now().get());
+    }
+
+    #[test]
+    fn test_journal_page() {
+        let legacy = StableJournalCollection {
+            start_timestamp: 1,
+            end_timestamp: 1,
+            strategy: None,
+            run_id: None,
+            entries: vec![],
+            sequence: None,
+        };
+        JOURNAL.with_borrow_mut(|journal| journal.push(&legacy).unwrap());
+        crate::state::backfill_journal_sequences();
+        for _ in 0..4 {
+            JournalCollection::open(None).append_note(Ok(()), LogType::Info, "Note");
+        }
+
+        let sequences = |page: &LogPage| -> Vec<Option<u64>> {
+            page.collections.iter().map(|c| c.sequence).collect()
+        };
+        let first = journal_page(None, 2);
+        assert_eq!(sequences(&first), vec![Some(0), Some(1)]);
+        assert_eq!(first.next_cursor, 2);
+        assert!(first.has_more);
+
+        let second = journal_page(Some(first.next_cursor), 10);
+        assert_eq!(sequences(&second), vec![Some(2), Some(3), Some(4)]);
+        assert_eq!(second.next_cursor, 5);
+        assert!(!second.has_more);
+
+        let caught_up = journal_page(Some(5), 10);
+        assert!(caught_up.collections.is_empty());
+        assert_eq!(caught_up.next_cursor, 5);
+    }
+
+    #[test]
+    fn test_journal_collection_at() {
+        JournalCollection::open(Some(1)).append_note(Ok(()), LogType::Info, "First");
+        JournalCollection::open(Some(2)).append_note(Ok(()), LogType::Info, "Second");
+
+        let log = journal_collection_at(1);
+        assert_eq!(log.total, 2);
+        assert_eq!(
+            log.collection.map(|collection| collection.strategy),
+            Some(Some(2))
+        );
+        let out_of_range = journal_collection_at(2);
+        assert!(out_of_range.collection.is_none());
+        assert_eq!(out_of_range.total, 2);
+    }
+
+    #[test]
+    fn test_format_timestamp() {
+        assert_eq!(format_timestamp(1_231_006_505), "03-01-2009 18:15:05");
+        assert_eq!(format_timestamp(0), "");
+        assert_eq!(
+            parse_date_and_time(&format_timestamp(1_700_000_000)),
+            Some(1_700_000_000)
+        );
+    }
+
+    #[test]
+    fn test_legacy_collections_are_migrated() {
+        let legacy = LegacyJournalCollection {
+            start_date_and_time: "03-01-2009 18:15:00".to_string(),
+            end_date_and_time: "garbled".to_string(),
+            strategy: Some(2),
+            run_id: Some(5),
+            entries: vec![LegacyJournalEntry {
+                date_and_time: "03-01-2009 18:15:05".to_string(),
+                run_id: Some(5),
+                entry: Err(ManagerError::Locked),
+                note: Some("Legacy".to_string()),
+                log_type: LogType::Info,
+            }],
+            sequence: Some(0),
+        };
+        let legacy_bytes = Encode!(&legacy).unwrap();
+
+        let decoded = StableJournalCollection::from_bytes(Cow::Borrowed(&legacy_bytes));
+        assert_eq!(decoded.start_timestamp, 1_231_006_500);
+        assert_eq!(decoded.end_timestamp, 0);
+        assert_eq!(decoded.entries[0].timestamp, 1_231_006_505);
+        assert_eq!(decoded.entries[0].note.as_deref(), Some("Legacy"));
+        assert!(decoded.to_bytes().len() < legacy_bytes.len());
+
+        JOURNAL.with_borrow_mut(|journal| journal.push(&decoded).unwrap());
+        migrate_journal_timestamps();
+        let migrated = JOURNAL.with_borrow(|journal| journal.get(0)).unwrap();
+        assert_eq!(migrated.start_timestamp, 1_231_006_500);
+        assert_eq!(
+            JOURNAL_SCHEMA_VERSION.with(|version| *version.borrow().get()),
+            JOURNAL_TIMESTAMPS_VERSION
+        );
+
+        let query = JournalCollectionQuery::from(migrated);
+        assert_eq!(query.start_date_and_time, "03-01-2009 18:15:00");
+        assert_eq!(query.end_date_and_time, "");
+        assert_eq!(query.entries[0].date_and_time, "03-01-2009 18:15:05");
+    }
+
+    #[test]
+    fn test_parse_date_and_time() {
+        assert_eq!(
+            parse_date_and_time("03-01-2009 18:15:05"),
+            Some(1_231_006_505)
+        );
+        assert_eq!(parse_date_and_time(""), None);
+    }
+
+    #[test]
+    fn test_log_filter() {
+        let entry = |log_type: LogType, timestamp: u64| JournalEntry {
+            timestamp,
+            ..JournalEntry::new(Ok(()), log_type, None)
+        };
+        let collection = StableJournalCollection {
+            start_timestamp: 1_231_006_500,
+            end_timestamp: 1_231_006_560,
+            strategy: Some(1),
+            run_id: None,
+            entries: vec![
+                entry(LogType::Info, 1_231_006_500),
+                entry(LogType::RateAdjustment, 1_231_006_505),
+                entry(LogType::RateAdjustment, 1_231_006_560),
+            ],
+            sequence: None,
+        };
+
+        assert_eq!(
+            LogFilter::default()
+                .apply(collection.clone())
+                .map(|collection| collection.entries.len()),
+            Some(3)
+        );
+
+        let adjustments = LogFilter {
+            log_type: Some(LogType::RateAdjustment),
+            ..Default::default()
+        };
+        assert_eq!(
+            adjustments
+                .apply(collection.clone())
+                .map(|collection| collection.entries.len()),
+            Some(2)
+        );
+
+        let window = LogFilter {
+            from_timestamp: Some(1_231_006_505),
+            to_timestamp: Some(1_231_006_505),
+            ..adjustments
+        };
+        let filtered = window
+            .apply(collection.clone())
+            .expect("One entry matches.");
+        assert_eq!(filtered.entries.len(), 1);
+        assert_eq!(filtered.entries[0].timestamp, 1_231_006_505);
+
+        let after_last_entry = LogFilter {
+            from_timestamp: Some(1_231_006_600),
+            ..Default::default()
+        };
+        assert!(after_last_entry.apply(collection).is_none());
     }
 
     #[test]
@@ -285,10 +859,12 @@ mod tests {
         );
 
         let collection = StableJournalCollection {
-            start_date_and_time: "01-01-2024 10:00:00".to_string(),
-            end_date_and_time: "01-01-2024 10:05:00".to_string(),
+            start_timestamp: 1_704_103_200,
+            end_timestamp: 1_704_103_500,
             strategy: None,
+            run_id: None,
             entries: vec![reputation_entry],
+            sequence: None,
         };
 
         assert!(collection.is_reputation_change());
@@ -303,10 +879,12 @@ mod tests {
         );
 
         let collection = StableJournalCollection {
-            start_date_and_time: "01-01-2024 10:00:00".to_string(),
-            end_date_and_time: "01-01-2024 10:05:00".to_string(),
+            start_timestamp: 1_704_103_200,
+            end_timestamp: 1_704_103_500,
             strategy: None,
+            run_id: None,
             entries: vec![other_entry],
+            sequence: None,
         };
 
         assert!(!collection.is_reputation_change());
@@ -321,23 +899,19 @@ mod tests {
         );
 
         let stable_collection = StableJournalCollection {
-            start_date_and_time: "01-01-2024 10:00:00".to_string(),
-            end_date_and_time: "01-01-2024 10:10:00".to_string(),
+            start_timestamp: 1_704_103_200,
+            end_timestamp: 1_704_103_800,
             strategy: Some(123),
+            run_id: None,
             entries: vec![entry],
+            sequence: None,
         };
 
         let bytes = stable_collection.to_bytes();
         let decoded = StableJournalCollection::from_bytes(bytes);
 
-        assert_eq!(
-            decoded.start_date_and_time,
-            stable_collection.start_date_and_time
-        );
-        assert_eq!(
-            decoded.end_date_and_time,
-            stable_collection.end_date_and_time
-        );
+        assert_eq!(decoded.start_timestamp, stable_collection.start_timestamp);
+        assert_eq!(decoded.end_timestamp, stable_collection.end_timestamp);
         assert_eq!(decoded.strategy, stable_collection.strategy);
         assert_eq!(decoded.entries.len(), 1);
         assert_eq!(decoded.entries[0].log_type, LogType::RateAdjustment);
@@ -346,10 +920,12 @@ mod tests {
     #[test]
     fn test_is_reputation_change_empty_entries() {
         let collection = StableJournalCollection {
-            start_date_and_time: "01-01-2024 10:00:00".to_string(),
-            end_date_and_time: "01-01-2024 10:10:00".to_string(),
+            start_timestamp: 1_704_103_200,
+            end_timestamp: 1_704_103_800,
             strategy: None,
+            run_id: None,
             entries: vec![],
+            sequence: None,
         };
 
         assert!(!collection.is_reputation_change());
@@ -366,10 +942,12 @@ mod tests {
         let entry2 = JournalEntry::new(ManagerResult::Ok(()), LogType::ExecutionResult, None);
 
         let collection = StableJournalCollection {
-            start_date_and_time: "01-01-2024 10:00:00".to_string(),
-            end_date_and_time: "01-01-2024 10:15:00".to_string(),
+            start_timestamp: 1_704_103_200,
+            end_timestamp: 1_704_104_100,
             strategy: None,
+            run_id: None,
             entries: vec![entry1, entry2],
+            sequence: None,
         };
 
         assert!(!collection.is_reputation_change());
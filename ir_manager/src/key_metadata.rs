@@ -0,0 +1,260 @@
+//! Key Metadata Export
+//!
+//! Everything needed to document the strategy EOAs for cold storage and disaster recovery:
+//! the threshold ECDSA key, the derivation path of each EOA, its address, and its nonce.
+//!
+//! ```plain
+//! EOA Derivation:
+//!
+//! (canister_id, ECDSA key name, derivation_path) ──tECDSA──► public key ──keccak──► EOA
+//! ```
+//!
+//! No private key material is involved: threshold ECDSA keys never leave the subnet and
+//! cannot be exported. Recovering an EOA means redeploying to the same canister ID with
+//! the same key name and derivation paths.
+//!
+//! The derivation of a single strategy can be verified before a transaction depends on it,
+//! by signing `SIGNER_TEST_PAYLOAD` and recovering the signer:
+//!
+//! ```plain
+//! derivation_path ──► public key ───────────────► derived address ────┐
+//!        │                                                            ├──► both eoa_pk?
+//!        └──► sign(keccak(payload)) ──► recover ──► recovered address ┘
+//! ```
+
+use std::str::FromStr;
+
+use alloy_primitives::{keccak256, Address};
+use candid::{CandidType, Principal};
+
+use crate::{
+    clock::time,
+    constants::{CHAIN_ID, SIGNER_TEST_PAYLOAD},
+    rpc_registry::resolve_rpc_canister,
+    state::STRATEGY_STATE,
+    strategy::stable::StableStrategy,
+    utils::{
+        common::get_nonce,
+        error::{ManagerError, ManagerResult},
+        signer::{
+            ecdsa_key_id, ecdsa_key_name, get_canister_public_key, pubkey_bytes_to_address,
+            recover_address, sign_prehash,
+        },
+    },
+};
+
+/// Key metadata of a single strategy EOA
+#[derive(CandidType, Clone, Debug, PartialEq)]
+pub struct StrategyKeyMetadata {
+    /// Key of the strategy
+    pub key: u32,
+    /// Hex-encoded components of the derivation path
+    pub derivation_path: Vec<String>,
+    /// Address of the EOA, if it was derived
+    pub eoa: Option<String>,
+    /// Chain the EOA transacts on
+    pub chain_id: u64,
+    /// Nonce tracked by the canister
+    pub stored_nonce: u64,
+    /// Nonce freshly fetched from the chain
+    pub chain_nonce: ManagerResult<u64>,
+}
+
+/// Key metadata of the canister and all strategy EOAs
+#[derive(CandidType, Clone, Debug, PartialEq)]
+pub struct KeyMetadataExport {
+    /// Canister the EOAs are derived for
+    pub canister_id: Principal,
+    /// Name of the threshold ECDSA key
+    pub key_name: String,
+    /// Curve of the threshold ECDSA key
+    pub curve: String,
+    /// Home chain of the deployment; strategies on other chains list their own
+    pub chain_id: u64,
+    /// Timestamp in seconds of the export
+    pub exported_at: u64,
+    /// Key metadata of each strategy, sorted by key
+    pub strategies: Vec<StrategyKeyMetadata>,
+}
+
+/// Diagnostic of the derivation path of a strategy
+#[derive(CandidType, Clone, Debug, PartialEq)]
+pub struct SignerReport {
+    /// Key of the strategy
+    pub key: u32,
+    /// Name of the threshold ECDSA key
+    pub key_name: String,
+    /// Hex-encoded components of the derivation path
+    pub derivation_path: Vec<String>,
+    /// EOA stored for the strategy, if it was derived
+    pub eoa: Option<String>,
+    /// Address of the public key at the derivation path
+    pub derived_address: Option<String>,
+    /// Address recovered from the signature of the test payload
+    pub recovered_address: Option<String>,
+    /// `true` if both addresses are the EOA
+    pub verified: bool,
+    /// What went wrong, empty if verified
+    pub issues: Vec<String>,
+}
+
+impl StrategyKeyMetadata {
+    /// Builds the key metadata of a strategy, given its freshly fetched nonce.
+    fn new(strategy: &StableStrategy, chain_nonce: ManagerResult<u64>) -> Self {
+        Self {
+            key: strategy.settings.key,
+            derivation_path: strategy
+                .settings
+                .derivation_path
+                .iter()
+                .map(hex::encode)
+                .collect(),
+            eoa: strategy.settings.eoa_pk.map(|eoa| eoa.to_string()),
+            chain_id: strategy.settings.chain.chain_id,
+            stored_nonce: strategy.data.eoa_nonce,
+            chain_nonce,
+        }
+    }
+}
+
+/// Exports the key metadata of all strategies, fetching the current nonce of each EOA.
+pub async fn export_key_metadata(canister_id: Principal, now: u64) -> KeyMetadataExport {
+    let mut strategies: Vec<StableStrategy> =
+        STRATEGY_STATE.with(|state| state.borrow().values().cloned().collect());
+    strategies.sort_by_key(|strategy| strategy.settings.key);
+
+    let mut metadata = Vec::with_capacity(strategies.len());
+    for strategy in strategies.iter() {
+        let chain_nonce = match strategy.settings.eoa_pk {
+            Some(eoa) => get_nonce(
+                &resolve_rpc_canister(&strategy.settings.rpc_canister, time() / 1_000_000_000),
+                eoa,
+            )
+            .await
+            .map(|nonce| nonce.to::<u64>()),
+            None => Err(ManagerError::NonExistentValue),
+        };
+        metadata.push(StrategyKeyMetadata::new(strategy, chain_nonce));
+    }
+
+    KeyMetadataExport {
+        canister_id,
+        key_name: ecdsa_key_name(),
+        curve: "secp256k1".to_string(),
+        chain_id: CHAIN_ID,
+        exported_at: now,
+        strategies: metadata,
+    }
+}
+
+/// Returns the mismatches between the EOA of a strategy and the addresses obtained from its
+/// derivation path.
+fn diagnose(
+    eoa: Option<Address>,
+    derived: &ManagerResult<Address>,
+    recovered: &ManagerResult<Address>,
+) -> Vec<String> {
+    let mut issues = vec![];
+    match derived {
+        Ok(derived) if eoa.is_some_and(|eoa| eoa != *derived) => issues.push(format!(
+            "The derivation path yields {} instead of the EOA. The key name or the canister ID differ from the ones the EOA was derived with.",
+            derived
+        )),
+        Ok(_) => {}
+        Err(err) => issues.push(format!("The public key could not be fetched: {:?}", err)),
+    }
+    match recovered {
+        Ok(recovered) if eoa.is_some_and(|eoa| eoa != *recovered) => issues.push(format!(
+            "The test signature recovers to {} instead of the EOA.",
+            recovered
+        )),
+        Ok(_) => {}
+        Err(err) => issues.push(format!("The test payload could not be signed: {:?}", err)),
+    }
+    if eoa.is_none() {
+        issues.push("The strategy has no EOA yet.".to_string());
+    }
+    issues
+}
+
+/// Signs `SIGNER_TEST_PAYLOAD` with the derivation path of a strategy and checks that both the
+/// derived and the recovered addresses are its EOA.
+///
+/// # Errors
+/// Returns `ManagerError::NonExistentValue` if the strategy does not exist. Failed management
+/// calls are reported as issues.
+pub async fn verify_signer(key: u32) -> ManagerResult<SignerReport> {
+    let strategy = STRATEGY_STATE
+        .with(|state| state.borrow().get(&key).cloned())
+        .ok_or(ManagerError::NonExistentValue)?;
+    let derivation_path = strategy.settings.derivation_path.clone();
+
+    let derived = get_canister_public_key(ecdsa_key_id(), None, derivation_path.clone())
+        .await
+        .and_then(|public_key| pubkey_bytes_to_address(&public_key))
+        .and_then(|address| {
+            Address::from_str(&address)
+                .map_err(|err| ManagerError::DecodingError(format!("invalid address: {:#?}", err)))
+        });
+
+    let prehash = keccak256(SIGNER_TEST_PAYLOAD);
+    let recovered = sign_prehash(prehash, ecdsa_key_id(), derivation_path)
+        .await
+        .and_then(|signature| recover_address(&prehash, &signature));
+
+    let eoa = strategy.settings.eoa_pk;
+    let issues = diagnose(eoa, &derived, &recovered);
+    Ok(SignerReport {
+        key,
+        key_name: ecdsa_key_name(),
+        derivation_path: strategy
+            .settings
+            .derivation_path
+            .iter()
+            .map(hex::encode)
+            .collect(),
+        eoa: eoa.map(|eoa| eoa.to_string()),
+        derived_address: derived.ok().map(|address| address.to_string()),
+        recovered_address: recovered.ok().map(|address| address.to_string()),
+        verified: issues.is_empty(),
+        issues,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strategy_key_metadata() {
+        let mut strategy = StableStrategy::default();
+        strategy.settings.key = 7;
+        strategy.settings.derivation_path = vec![7_u32.to_be_bytes().to_vec()];
+        strategy.data.eoa_nonce = 3;
+
+        let metadata = StrategyKeyMetadata::new(&strategy, Ok(4));
+        assert_eq!(metadata.derivation_path, vec!["00000007".to_string()]);
+        assert_eq!(metadata.eoa, None);
+        assert_eq!(metadata.chain_id, CHAIN_ID);
+        assert_eq!(metadata.stored_nonce, 3);
+        assert_eq!(metadata.chain_nonce, Ok(4));
+    }
+
+    #[test]
+    fn test_diagnose_signer() {
+        let eoa = Address::repeat_byte(1);
+        let other = Address::repeat_byte(2);
+        assert!(diagnose(Some(eoa), &Ok(eoa), &Ok(eoa)).is_empty());
+
+        // A wrong key name changes both addresses
+        assert_eq!(diagnose(Some(eoa), &Ok(other), &Ok(other)).len(), 2);
+        assert_eq!(
+            diagnose(Some(eoa), &Ok(eoa), &Err(ManagerError::NonExistentValue)).len(),
+            1
+        );
+        assert_eq!(
+            diagnose(None, &Ok(eoa), &Ok(eoa)),
+            vec!["The strategy has no EOA yet.".to_string()]
+        );
+    }
+}
@@ -4,16 +4,51 @@
 #![allow(clippy::missing_const_for_thread_local)]
 #![warn(missing_docs)]
 
+pub mod access_log;
+pub mod adjustments;
+pub mod build_info;
 pub mod canister;
+pub mod chain;
 pub mod charger;
+pub mod charger_config;
 pub mod cleanup;
+pub mod clock;
 pub mod constants;
+pub mod custom_providers;
+pub mod digest;
+pub mod eoa_balances;
+pub mod flags;
+pub mod funding;
+pub mod gas_refill;
+pub mod guard;
 pub mod halt;
+pub mod health;
+pub mod install;
 pub mod journal;
+pub mod key_metadata;
+pub mod log_export;
+pub mod managers;
+pub mod metadata;
+pub mod metrics;
+pub mod payload_sizes;
+pub mod positioning_history;
+pub mod prometheus;
 pub mod providers;
+pub mod rate_history;
+pub mod rate_source;
+pub mod retention;
+pub mod rpc_registry;
+pub mod runs;
+pub mod scheduler;
 pub mod state;
+pub mod status;
 pub mod strategy;
+pub mod timers;
+pub mod treasury;
+pub mod triggers;
+pub mod tx_pool;
 pub mod types;
+pub mod upgrade;
 pub mod utils;
 
 pub use canister::IrManager;
@@ -0,0 +1,315 @@
+//! Journal Export
+//!
+//! Serves the journal over the HTTP gateway as flat records, so that operators can pipe the
+//! logs into their observability tooling without a candid client:
+//!
+//! ```plain
+//! GET /logs?strategy=K&depth=N&format=ndjson|csv
+//!        │
+//!        ▼
+//! JOURNAL, newest collection first ──► strategy K only (if set) ──► N newest entries
+//!                                                                        │
+//!                              oldest entry first, one flat record per line ◄──┘
+//! ```
+//!
+//! Every entry becomes a `LogRecord` that repeats the fields of its collection, with an
+//! ISO-8601 timestamp. The depth defaults to and is capped at `MAX_EXPORTED_LOG_ENTRIES`.
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::Serialize;
+
+use crate::{
+    constants::MAX_EXPORTED_LOG_ENTRIES,
+    journal::{JournalEntry, StableJournalCollection},
+    state::JOURNAL,
+};
+
+/// Content type of newline-delimited JSON
+pub const NDJSON_CONTENT_TYPE: &str = "application/x-ndjson";
+
+/// Content type of CSV
+pub const CSV_CONTENT_TYPE: &str = "text/csv";
+
+/// Column names of the CSV export, in the order of the `LogRecord` fields
+const CSV_HEADER: &str = "sequence,strategy,run_id,timestamp,log_type,ok,error,note";
+
+/// Serialization format of the export
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ExportFormat {
+    /// One JSON object per line
+    Ndjson,
+    /// Comma-separated values with a header line
+    Csv,
+}
+
+impl ExportFormat {
+    /// Returns the content type of the format.
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            ExportFormat::Ndjson => NDJSON_CONTENT_TYPE,
+            ExportFormat::Csv => CSV_CONTENT_TYPE,
+        }
+    }
+}
+
+/// Parameters of an export, parsed from the query string
+#[derive(Clone, Debug, PartialEq)]
+pub struct ExportQuery {
+    /// Only export the entries of this strategy
+    pub strategy: Option<u32>,
+    /// Number of newest entries to export
+    pub depth: usize,
+    /// Serialization format
+    pub format: ExportFormat,
+}
+
+impl ExportQuery {
+    /// Parses the query string of a `/logs` URL.
+    ///
+    /// # Errors
+    /// Returns a message describing the first invalid parameter.
+    pub fn parse(query: &str) -> Result<Self, String> {
+        let mut parsed = Self {
+            strategy: None,
+            depth: MAX_EXPORTED_LOG_ENTRIES,
+            format: ExportFormat::Ndjson,
+        };
+        for pair in query.split('&').filter(|pair| !pair.is_empty()) {
+            let (name, value) = pair.split_once('=').unwrap_or((pair, ""));
+            match name {
+                "strategy" => {
+                    parsed.strategy = Some(
+                        value
+                            .parse()
+                            .map_err(|_| format!("Invalid strategy key: {}", value))?,
+                    )
+                }
+                "depth" => {
+                    let depth: usize = value
+                        .parse()
+                        .map_err(|_| format!("Invalid depth: {}", value))?;
+                    parsed.depth = depth.min(MAX_EXPORTED_LOG_ENTRIES);
+                }
+                "format" => {
+                    parsed.format = match value {
+                        "ndjson" => ExportFormat::Ndjson,
+                        "csv" => ExportFormat::Csv,
+                        _ => return Err(format!("Unsupported format: {}", value)),
+                    }
+                }
+                _ => return Err(format!("Unknown parameter: {}", name)),
+            }
+        }
+        Ok(parsed)
+    }
+}
+
+/// A journal entry flattened along with its collection
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct LogRecord {
+    /// Sequence number of the collection
+    pub sequence: Option<u64>,
+    /// Strategy of the collection
+    pub strategy: Option<u32>,
+    /// ID of the strategy run that produced the entry
+    pub run_id: Option<u64>,
+    /// ISO-8601 timestamp of the entry, empty if it is unknown
+    pub timestamp: String,
+    /// Type of the entry
+    pub log_type: String,
+    /// `false` if the entry records an error
+    pub ok: bool,
+    /// Error of the entry
+    pub error: Option<String>,
+    /// Note of the entry
+    pub note: Option<String>,
+}
+
+impl LogRecord {
+    /// Flattens an entry of a collection.
+    fn new(collection: &StableJournalCollection, entry: &JournalEntry) -> Self {
+        Self {
+            sequence: collection.sequence,
+            strategy: collection.strategy,
+            run_id: entry.run_id.or(collection.run_id),
+            timestamp: iso_timestamp(entry.timestamp),
+            log_type: format!("{:?}", entry.log_type),
+            ok: entry.entry.is_ok(),
+            error: entry.entry.as_ref().err().map(|err| format!("{:?}", err)),
+            note: entry.note.clone(),
+        }
+    }
+
+    /// Returns the record as a CSV line, without the line break.
+    fn to_csv(&self) -> String {
+        let optional = |value: Option<String>| value.unwrap_or_default();
+        [
+            optional(self.sequence.map(|sequence| sequence.to_string())),
+            optional(self.strategy.map(|strategy| strategy.to_string())),
+            optional(self.run_id.map(|run_id| run_id.to_string())),
+            self.timestamp.clone(),
+            self.log_type.clone(),
+            self.ok.to_string(),
+            optional(self.error.clone()),
+            optional(self.note.clone()),
+        ]
+        .iter()
+        .map(|field| csv_field(field))
+        .collect::<Vec<String>>()
+        .join(",")
+    }
+}
+
+/// Converts a timestamp in seconds to ISO-8601, or an empty string if it is unknown (zero).
+fn iso_timestamp(seconds: u64) -> String {
+    if seconds == 0 {
+        return String::new();
+    }
+    DateTime::<Utc>::from_timestamp(seconds as i64, 0)
+        .map(|datetime| datetime.to_rfc3339_opts(SecondsFormat::Secs, true))
+        .unwrap_or_default()
+}
+
+/// Quotes a CSV field if it contains a separator, a quote, or a line break.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Returns up to `depth` of the newest journal entries, optionally of a single strategy,
+/// oldest first.
+pub fn log_records(strategy: Option<u32>, depth: usize) -> Vec<LogRecord> {
+    let mut records = Vec::new();
+    JOURNAL.with_borrow(|journal| {
+        for index in (0..journal.len()).rev() {
+            if records.len() >= depth {
+                break;
+            }
+            let Some(collection) = journal.get(index) else {
+                continue;
+            };
+            if strategy.is_some() && collection.strategy != strategy {
+                continue;
+            }
+            for entry in collection.entries.iter().rev() {
+                if records.len() >= depth {
+                    break;
+                }
+                records.push(LogRecord::new(&collection, entry));
+            }
+        }
+    });
+    records.reverse();
+    records
+}
+
+/// Serializes the records in the requested format, one record per line.
+pub fn encode_records(records: &[LogRecord], format: ExportFormat) -> String {
+    let mut lines: Vec<String> = match format {
+        ExportFormat::Ndjson => records
+            .iter()
+            .map(|record| serde_json::to_string(record).expect("Failed to serialize a log record."))
+            .collect(),
+        ExportFormat::Csv => std::iter::once(CSV_HEADER.to_string())
+            .chain(records.iter().map(LogRecord::to_csv))
+            .collect(),
+    };
+    lines.push(String::new());
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{journal::LogType, state::insert_journal_collection, utils::error::ManagerError};
+
+    fn collection(strategy: Option<u32>, notes: &[&str]) -> StableJournalCollection {
+        StableJournalCollection {
+            start_timestamp: 1_230_977_705,
+            end_timestamp: 1_230_977_705,
+            strategy,
+            run_id: Some(4),
+            entries: notes
+                .iter()
+                .map(|note| JournalEntry {
+                    timestamp: 1_230_977_705,
+                    run_id: None,
+                    entry: Err(ManagerError::Locked),
+                    note: Some(note.to_string()),
+                    log_type: LogType::Info,
+                })
+                .collect(),
+            sequence: None,
+        }
+    }
+
+    #[test]
+    fn test_export_query() {
+        assert_eq!(
+            ExportQuery::parse("strategy=2&depth=5&format=csv"),
+            Ok(ExportQuery {
+                strategy: Some(2),
+                depth: 5,
+                format: ExportFormat::Csv
+            })
+        );
+        assert_eq!(
+            ExportQuery::parse("").unwrap().depth,
+            MAX_EXPORTED_LOG_ENTRIES
+        );
+        assert_eq!(
+            ExportQuery::parse("depth=1000000000").unwrap().depth,
+            MAX_EXPORTED_LOG_ENTRIES
+        );
+        assert!(ExportQuery::parse("strategy=x").is_err());
+        assert!(ExportQuery::parse("format=xml").is_err());
+        assert!(ExportQuery::parse("limit=3").is_err());
+    }
+
+    #[test]
+    fn test_log_records() {
+        insert_journal_collection(collection(Some(1), &["a", "b"]));
+        insert_journal_collection(collection(Some(2), &["c"]));
+        insert_journal_collection(collection(Some(1), &["d"]));
+
+        let notes = |records: Vec<LogRecord>| -> Vec<String> {
+            records
+                .into_iter()
+                .filter_map(|record| record.note)
+                .collect()
+        };
+        assert_eq!(notes(log_records(Some(1), 2)), vec!["b", "d"]);
+        assert_eq!(notes(log_records(None, 10)), vec!["a", "b", "c", "d"]);
+
+        let record = log_records(Some(2), 1).remove(0);
+        assert_eq!(record.timestamp, "2009-01-03T10:15:05Z");
+        assert_eq!(record.run_id, Some(4));
+        assert_eq!(record.error, Some("Locked".to_string()));
+    }
+
+    #[test]
+    fn test_encode_records() {
+        let mut record = LogRecord::new(
+            &collection(Some(1), &[]),
+            &collection(Some(1), &["rate, \"adjusted\""]).entries[0],
+        );
+        let ndjson = encode_records(&[record.clone()], ExportFormat::Ndjson);
+        assert!(ndjson.ends_with("}\n"));
+        let parsed: serde_json::Value = serde_json::from_str(ndjson.trim_end()).unwrap();
+        assert_eq!(parsed["strategy"], 1);
+        assert_eq!(parsed["ok"], false);
+
+        record.error = None;
+        let csv = encode_records(&[record], ExportFormat::Csv);
+        assert_eq!(
+            csv,
+            format!(
+                "{}\n,1,4,2009-01-03T10:15:05Z,Info,false,,\"rate, \"\"adjusted\"\"\"\n",
+                CSV_HEADER
+            )
+        );
+    }
+}
@@ -1,8 +1,15 @@
 //! Generates the candid file automatically
+//!
+//! Pass `--metadata` to generate the companion service metadata JSON instead.
 
-use ir_manager::IrManager;
+use ir_manager::{metadata::metadata_json, IrManager};
 
 fn main() {
+    if std::env::args().any(|arg| arg == "--metadata") {
+        println!("{}", metadata_json());
+        return;
+    }
+
     let canister_e_idl = IrManager::idl();
     let idl = candid::pretty::candid::compile(&canister_e_idl.env.env, &Some(canister_e_idl.actor));
 
@@ -0,0 +1,205 @@
+//! Trove Manager Registry
+//!
+//! `MANAGERS` lists the trove managers of all branches. Strategies iterate it to sum the
+//! system debt and the unbacked debt across branches, so an entry without a strategy skews
+//! every run.
+//!
+//! ```plain
+//! mint_strategy ──(validated, strategy stored)──► register_manager ──► MANAGERS
+//! add_manager ──(controller, not registered yet)──► register_manager ──┘  │
+//!                                                                         │
+//! prune_unused_managers ◄──── managers without a strategy ────────────────┤
+//!                                                                         │
+//! remove_strategy ──(strategy removed)──► unregister_manager ─────────────┤
+//! remove_manager ──(controller, no strategy uses it)──► unregister_manager┘
+//! ```
+//!
+//! Controllers can list and edit the registry directly, e.g. to add a branch that no strategy
+//! manages so that its debt is part of the aggregation. A manager that a strategy still uses
+//! cannot be removed.
+
+use std::collections::HashSet;
+
+use alloy_primitives::Address;
+
+use crate::{
+    state::{MANAGERS, STRATEGY_STATE},
+    utils::error::{ManagerError, ManagerResult},
+};
+
+/// Returns the registered trove managers, in registration order.
+pub fn managers() -> Vec<Address> {
+    MANAGERS.with(|managers| managers.borrow().clone())
+}
+
+/// Returns the keys of the strategies that use a trove manager.
+fn strategies_using(manager: Address) -> Vec<u32> {
+    let mut keys: Vec<u32> = STRATEGY_STATE.with(|state| {
+        state
+            .borrow()
+            .values()
+            .filter(|strategy| strategy.settings.manager == manager)
+            .map(|strategy| strategy.settings.key)
+            .collect()
+    });
+    keys.sort_unstable();
+    keys
+}
+
+/// Adds a trove manager to the registry on behalf of a controller.
+///
+/// # Errors
+/// Returns `ManagerError::Custom` if the manager is already registered.
+pub fn add_manager(manager: Address) -> ManagerResult<()> {
+    if managers().contains(&manager) {
+        return Err(ManagerError::Custom(format!(
+            "Trove manager {} is already registered.",
+            manager
+        )));
+    }
+    register_manager(manager);
+    Ok(())
+}
+
+/// Removes a trove manager from the registry on behalf of a controller.
+///
+/// # Errors
+/// - `ManagerError::Custom` if a strategy still uses the manager
+/// - `ManagerError::NonExistentValue` if the manager is not registered
+pub fn remove_manager(manager: Address) -> ManagerResult<()> {
+    let keys = strategies_using(manager);
+    if !keys.is_empty() {
+        return Err(ManagerError::Custom(format!(
+            "Trove manager {} is still used by strategies {:?}.",
+            manager, keys
+        )));
+    }
+    if unregister_manager(manager) {
+        Ok(())
+    } else {
+        Err(ManagerError::NonExistentValue)
+    }
+}
+
+/// Adds a trove manager to the registry, unless it is already registered.
+///
+/// Must only be called once the strategy using the manager is stored.
+pub fn register_manager(manager: Address) {
+    MANAGERS.with(|managers| {
+        let mut managers = managers.borrow_mut();
+        if !managers.contains(&manager) {
+            managers.push(manager);
+        }
+    });
+}
+
+/// Removes a trove manager from the registry if no strategy uses it anymore.
+///
+/// Returns `true` if the manager was removed.
+pub fn unregister_manager(manager: Address) -> bool {
+    if !strategies_using(manager).is_empty() {
+        return false;
+    }
+
+    MANAGERS.with(|managers| {
+        let mut managers = managers.borrow_mut();
+        let len = managers.len();
+        managers.retain(|registered| *registered != manager);
+        managers.len() != len
+    })
+}
+
+/// Removes the trove managers that no strategy uses, as well as duplicates,
+/// and returns the removed entries.
+pub fn prune_unused_managers() -> Vec<Address> {
+    let used: HashSet<Address> = STRATEGY_STATE.with(|state| {
+        state
+            .borrow()
+            .values()
+            .map(|strategy| strategy.settings.manager)
+            .collect()
+    });
+
+    MANAGERS.with(|managers| {
+        let mut managers = managers.borrow_mut();
+        let mut kept = HashSet::new();
+        let mut removed = vec![];
+        managers.retain(|manager| {
+            if used.contains(manager) && kept.insert(*manager) {
+                true
+            } else {
+                removed.push(*manager);
+                false
+            }
+        });
+        removed
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::strategy::stable::StableStrategy;
+
+    #[test]
+    fn test_prune_unused_managers() {
+        let used = Address::repeat_byte(1);
+        let phantom = Address::repeat_byte(2);
+
+        let mut strategy = StableStrategy::default();
+        strategy.settings.manager = used;
+        STRATEGY_STATE.with(|state| {
+            let mut state = state.borrow_mut();
+            state.clear();
+            state.insert(1, strategy);
+        });
+        MANAGERS.with(|managers| *managers.borrow_mut() = vec![used, phantom, used]);
+
+        assert_eq!(prune_unused_managers(), vec![phantom, used]);
+        assert_eq!(
+            MANAGERS.with(|managers| managers.borrow().clone()),
+            vec![used]
+        );
+    }
+
+    #[test]
+    fn test_add_and_remove_manager() {
+        let used = Address::repeat_byte(4);
+        let standalone = Address::repeat_byte(5);
+
+        let mut strategy = StableStrategy::default();
+        strategy.settings.key = 7;
+        strategy.settings.manager = used;
+        STRATEGY_STATE.with(|state| {
+            let mut state = state.borrow_mut();
+            state.clear();
+            state.insert(7, strategy);
+        });
+        MANAGERS.with(|managers| *managers.borrow_mut() = vec![used]);
+
+        add_manager(standalone).unwrap();
+        assert!(add_manager(used).is_err());
+        assert_eq!(managers(), vec![used, standalone]);
+
+        assert!(remove_manager(used).is_err());
+        remove_manager(standalone).unwrap();
+        assert_eq!(
+            remove_manager(standalone),
+            Err(ManagerError::NonExistentValue)
+        );
+        assert_eq!(managers(), vec![used]);
+    }
+
+    #[test]
+    fn test_register_manager_is_idempotent() {
+        let manager = Address::repeat_byte(3);
+        MANAGERS.with(|managers| managers.borrow_mut().clear());
+
+        register_manager(manager);
+        register_manager(manager);
+        assert_eq!(
+            MANAGERS.with(|managers| managers.borrow().clone()),
+            vec![manager]
+        );
+    }
+}
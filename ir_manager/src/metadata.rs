@@ -0,0 +1,101 @@
+//! Service Metadata
+//!
+//! Structured descriptions of the canister's public methods for deployment scripts and dashboards.
+//! The metadata is declared next to the methods in `canister.rs` (`API_METADATA`) and exported
+//! as a companion JSON artifact of the Candid interface:
+//!
+//! ```plain
+//! cargo run --features export-api > candid.did
+//! cargo run --features export-api -- --metadata > candid.metadata.json
+//! ```
+
+use serde::Serialize;
+
+use crate::canister::API_METADATA;
+
+/// Principals allowed to call a method
+#[derive(Serialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    /// Anyone
+    Public,
+    /// Controllers of the canister
+    Controller,
+}
+
+/// Stability guarantee of a method's interface
+#[derive(Serialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Stability {
+    /// The interface is not expected to change
+    Stable,
+    /// The interface may change without notice
+    Experimental,
+    /// The method is scheduled for removal
+    Deprecated,
+}
+
+/// Description of a single public method
+#[derive(Serialize, Clone, Debug)]
+pub struct MethodMetadata {
+    /// Name of the method in the Candid interface
+    pub name: &'static str,
+    /// Short description of the method
+    pub description: &'static str,
+    /// Principals allowed to call the method
+    pub role: Role,
+    /// Stability guarantee of the method's interface
+    pub stability: Stability,
+}
+
+/// Returns the metadata of all public methods as a JSON document.
+pub fn metadata_json() -> String {
+    serde_json::to_string_pretty(API_METADATA).expect("Failed to serialize the service metadata.")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::IrManager;
+
+    /// Returns the method names declared in the service block of the generated Candid interface.
+    fn service_methods() -> Vec<String> {
+        let idl = IrManager::idl();
+        let did = candid::pretty::candid::compile(&idl.env.env, &Some(idl.actor));
+        did.lines()
+            .skip_while(|line| !line.starts_with("service"))
+            .filter_map(|line| line.trim().split_once(" : ("))
+            .map(|(name, _)| name.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn every_method_is_annotated() {
+        for method in service_methods() {
+            assert!(
+                API_METADATA.iter().any(|metadata| metadata.name == method),
+                "Missing metadata for method {}",
+                method
+            );
+        }
+    }
+
+    #[test]
+    fn every_annotation_has_a_method() {
+        let methods = service_methods();
+        for metadata in API_METADATA {
+            assert!(
+                methods.iter().any(|method| method == metadata.name),
+                "Metadata for unknown method {}",
+                metadata.name
+            );
+        }
+    }
+
+    #[test]
+    fn metadata_serializes_to_json() {
+        let json = metadata_json();
+        assert!(json.contains("\"role\": \"controller\""));
+        assert!(json.contains("\"stability\": \"stable\""));
+    }
+}
@@ -0,0 +1,506 @@
+//! Canister Metrics
+//!
+//! Lightweight counters describing the activity of the canister, exposed through the
+//! `get_metrics` query for monitoring.
+//!
+//! Consecutive `NoConsensus` outcomes are tracked per call class. Persistent disagreement
+//! usually means that one of the top-ranked providers is broken, so once the streak reaches
+//! the threshold an alert is raised, and reads fall back to the top-ranked provider alone
+//! for `CONSENSUS_FALLBACK_DURATION` seconds:
+//!
+//! ```plain
+//! NoConsensus ──► streak + 1 ──(streak >= threshold)──► alert ──► read fallback
+//! Ok ───────────► streak = 0                                       (reads only)
+//! ```
+//!
+//! Journal writes that fail are counted as well, so that operators learn that logging is
+//! degraded even though the journal itself cannot tell them. The collections removed by the
+//! journal retention policy are counted by the rule that removed them, and the calls to the
+//! management canister by their outcome, latency, and cycle cost.
+//!
+//! The failed RPC calls are counted by provider, and the cycles burned by each strategy run
+//! are estimated from the canister balance before and after the run. The same counters are
+//! served in the Prometheus text format by `http_request`, see the `prometheus` module.
+
+use std::collections::BTreeMap;
+
+use candid::{CandidType, Nat};
+use serde::Deserialize;
+
+use crate::{
+    constants::CONSENSUS_FALLBACK_DURATION,
+    payload_sizes::{payload_sizes, PayloadHistogram},
+    state::METRICS,
+    utils::{
+        error::{ManagerError, ManagerResult},
+        management::is_transient,
+    },
+};
+
+/// Class of multi-provider RPC calls that require consensus
+#[derive(CandidType, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum CallClass {
+    /// `eth_call` reads
+    EthCall,
+    /// `eth_getTransactionCount` reads
+    TransactionCount,
+    /// `eth_sendRawTransaction` writes
+    SendRawTransaction,
+}
+
+impl CallClass {
+    /// Returns `true` if the call only reads from the chain, and may fall back to a single provider.
+    pub fn is_read(&self) -> bool {
+        !matches!(self, CallClass::SendRawTransaction)
+    }
+}
+
+/// Class of IC management canister calls
+#[derive(CandidType, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ManagementCall {
+    /// `ecdsa_public_key`
+    EcdsaPublicKey,
+    /// `sign_with_ecdsa`
+    SignWithEcdsa,
+    /// `raw_rand`
+    RawRand,
+}
+
+/// Outcomes and costs of a class of management canister calls
+#[derive(CandidType, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct ManagementCallStats {
+    /// Number of calls, retries excluded
+    pub calls: u64,
+    /// Number of retries after transient rejections
+    pub retries: u64,
+    /// Number of calls that were still rejected as transient after the last attempt
+    pub transient_failures: u64,
+    /// Number of calls that failed with a permanent rejection
+    pub permanent_failures: u64,
+    /// Total latency in milliseconds of the calls, retries included
+    pub total_latency_ms: u64,
+    /// Latency in milliseconds of the slowest call
+    pub max_latency_ms: u64,
+    /// Estimated cycles spent on the calls
+    pub cycles_spent: Nat,
+}
+
+/// Consensus outcomes of a call class
+#[derive(CandidType, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct ConsensusHealth {
+    /// Number of `NoConsensus` outcomes since the last successful call
+    pub consecutive_failures: u64,
+    /// Total number of `NoConsensus` outcomes
+    pub total_failures: u64,
+    /// Timestamp in seconds until which reads use the top-ranked provider alone
+    pub fallback_until: Option<u64>,
+}
+
+/// Journal collections that could not be stored
+#[derive(CandidType, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct JournalIntegrity {
+    /// Number of collections that could not be stored in full
+    pub dropped_writes: u64,
+    /// Number of those for which not even a minimal replacement entry could be stored
+    pub lost_writes: u64,
+    /// Last error returned by the journal
+    pub last_error: Option<String>,
+}
+
+/// Journal collections removed by the daily cleanup, by reason
+#[derive(CandidType, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct RetentionCounters {
+    /// Number of provider reputation change collections removed
+    pub reputation_changes: u64,
+    /// Number of collections removed for being older than the maximum age
+    pub by_age: u64,
+    /// Number of collections removed above the maximum number of collections
+    pub by_count: u64,
+    /// Number of collections removed above the maximum size
+    pub by_size: u64,
+}
+
+impl RetentionCounters {
+    /// Returns the number of collections removed by the retention policy.
+    pub fn trimmed(&self) -> u64 {
+        self.by_age + self.by_count + self.by_size
+    }
+}
+
+/// Estimated cycles burn rate of the canister
+#[derive(CandidType, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct CyclesBurn {
+    /// Last observed cycles balance
+    pub last_balance: Option<u128>,
+    /// Timestamp in seconds of the last observed cycles balance
+    pub last_observed_at: Option<u64>,
+    /// Exponential moving average of the burn rate in cycles per second
+    pub cycles_per_second: Option<u64>,
+}
+
+/// Cycles burned by the strategy runs
+///
+/// Runs overlap and cycles may be received during a run, so a single measurement is an
+/// approximation.
+#[derive(CandidType, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct RunCycles {
+    /// Number of runs measured
+    pub runs: u64,
+    /// Total cycles burned by the measured runs
+    pub total: Nat,
+    /// Cycles burned by the last measured run
+    pub last: Option<Nat>,
+}
+
+/// Counters describing the activity of the canister
+#[derive(CandidType, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct Metrics {
+    /// Number of strategy runs started
+    pub runs_started: u64,
+    /// Number of strategy runs that finished successfully
+    pub runs_succeeded: u64,
+    /// Number of strategy runs that finished with an error
+    pub runs_failed: u64,
+    /// ID of the most recently started strategy run
+    pub last_run_id: Option<u64>,
+    /// Consensus outcomes of each call class
+    pub consensus: BTreeMap<CallClass, ConsensusHealth>,
+    /// Failed journal writes
+    pub journal: JournalIntegrity,
+    /// Journal collections removed by the daily cleanup
+    pub journal_retention: RetentionCounters,
+    /// Outcomes and costs of the management canister calls
+    pub management: BTreeMap<ManagementCall, ManagementCallStats>,
+    /// Estimated cycles burn rate
+    pub cycles_burn: CyclesBurn,
+    /// Number of strategy writes that were merged with a concurrent write
+    pub state_conflicts: u64,
+    /// Response sizes needed by each EVM RPC call class, read from stable memory
+    pub payload_sizes: BTreeMap<String, PayloadHistogram>,
+    /// Number of failed RPC calls, by provider
+    pub provider_failures: BTreeMap<String, u64>,
+    /// Cycles burned by the strategy runs
+    pub run_cycles: RunCycles,
+}
+
+/// Returns a snapshot of the metrics.
+pub fn get_metrics() -> Metrics {
+    Metrics {
+        payload_sizes: payload_sizes(),
+        ..METRICS.with(|metrics| metrics.borrow().clone())
+    }
+}
+
+/// Records the start of a strategy run.
+pub fn record_run_started(run_id: u64) {
+    METRICS.with(|metrics| {
+        let mut metrics = metrics.borrow_mut();
+        metrics.runs_started = metrics.runs_started.saturating_add(1);
+        metrics.last_run_id = Some(run_id);
+    });
+}
+
+/// Records the end of a strategy run.
+pub fn record_run_finished(success: bool) {
+    METRICS.with(|metrics| {
+        let mut metrics = metrics.borrow_mut();
+        if success {
+            metrics.runs_succeeded = metrics.runs_succeeded.saturating_add(1);
+        } else {
+            metrics.runs_failed = metrics.runs_failed.saturating_add(1);
+        }
+    });
+}
+
+/// Records the cycles burned by a strategy run, given the balance before and after it.
+///
+/// Runs during which the balance increased are not measured.
+pub fn record_run_cycles(balance_before: u128, balance_after: u128) {
+    let Some(burned) = balance_before.checked_sub(balance_after) else {
+        return;
+    };
+    METRICS.with(|metrics| {
+        let run_cycles = &mut metrics.borrow_mut().run_cycles;
+        run_cycles.runs = run_cycles.runs.saturating_add(1);
+        run_cycles.total = run_cycles.total.clone() + Nat::from(burned);
+        run_cycles.last = Some(Nat::from(burned));
+    });
+}
+
+/// Records a failed RPC call of a provider, identified by `provider`.
+pub fn record_provider_failure(provider: String) {
+    METRICS.with(|metrics| {
+        let mut metrics = metrics.borrow_mut();
+        let failures = metrics.provider_failures.entry(provider).or_default();
+        *failures = failures.saturating_add(1);
+    });
+}
+
+/// Records a strategy write that was merged with a concurrent write.
+pub fn record_state_conflict() {
+    METRICS.with(|metrics| {
+        let mut metrics = metrics.borrow_mut();
+        metrics.state_conflicts = metrics.state_conflicts.saturating_add(1);
+    });
+}
+
+/// Records a journal collection that could not be stored in full.
+///
+/// `lost` is `true` if not even the minimal replacement entry could be stored.
+pub fn record_dropped_journal_write(error: String, lost: bool) {
+    METRICS.with(|metrics| {
+        let journal = &mut metrics.borrow_mut().journal;
+        journal.dropped_writes = journal.dropped_writes.saturating_add(1);
+        if lost {
+            journal.lost_writes = journal.lost_writes.saturating_add(1);
+        }
+        journal.last_error = Some(error);
+    });
+}
+
+/// Adds the collections removed by a journal cleanup to the retention counters.
+pub fn record_journal_retention(removed: &RetentionCounters) {
+    METRICS.with(|metrics| {
+        let counters = &mut metrics.borrow_mut().journal_retention;
+        counters.reputation_changes = counters
+            .reputation_changes
+            .saturating_add(removed.reputation_changes);
+        counters.by_age = counters.by_age.saturating_add(removed.by_age);
+        counters.by_count = counters.by_count.saturating_add(removed.by_count);
+        counters.by_size = counters.by_size.saturating_add(removed.by_size);
+    });
+}
+
+/// Records a management canister call that took `latency_ms` and `cycles` over all attempts.
+pub fn record_management_call<T>(
+    call: ManagementCall,
+    result: &ManagerResult<T>,
+    retries: u64,
+    latency_ms: u64,
+    cycles: u128,
+) {
+    METRICS.with(|metrics| {
+        let mut metrics = metrics.borrow_mut();
+        let stats = metrics.management.entry(call).or_default();
+        stats.calls = stats.calls.saturating_add(1);
+        stats.retries = stats.retries.saturating_add(retries);
+        stats.total_latency_ms = stats.total_latency_ms.saturating_add(latency_ms);
+        stats.max_latency_ms = stats.max_latency_ms.max(latency_ms);
+        stats.cycles_spent = stats.cycles_spent.clone() + Nat::from(cycles);
+        match result {
+            Ok(_) => {}
+            Err(err) if is_transient(err) => {
+                stats.transient_failures = stats.transient_failures.saturating_add(1)
+            }
+            Err(_) => stats.permanent_failures = stats.permanent_failures.saturating_add(1),
+        }
+    });
+}
+
+/// Records a cycles balance observed at `now` (in seconds) and updates the burn rate estimate.
+///
+/// Increases of the balance, such as cycles received by swaps, are not burns and only move
+/// the reference point.
+pub fn record_cycles_sample(balance: u128, now: u64) {
+    METRICS.with(|metrics| {
+        let burn = &mut metrics.borrow_mut().cycles_burn;
+        if let (Some(last_balance), Some(last_observed_at)) =
+            (burn.last_balance, burn.last_observed_at)
+        {
+            let elapsed = now.saturating_sub(last_observed_at);
+            if elapsed == 0 {
+                return;
+            }
+            if balance <= last_balance {
+                let observed =
+                    ((last_balance - balance) / elapsed as u128).min(u64::MAX as u128) as u64;
+                burn.cycles_per_second = Some(match burn.cycles_per_second {
+                    Some(average) => (average.saturating_mul(3) / 4).saturating_add(observed / 4),
+                    None => observed,
+                });
+            }
+        }
+        burn.last_balance = Some(balance);
+        burn.last_observed_at = Some(now);
+    });
+}
+
+/// Returns the estimated cycles burn rate in cycles per second.
+pub fn cycles_burn_rate() -> Option<u64> {
+    METRICS.with(|metrics| metrics.borrow().cycles_burn.cycles_per_second)
+}
+
+/// Records the consensus outcome of a call at `now` (in seconds).
+///
+/// Returns the length of the `NoConsensus` streak when it reaches `threshold` (and every
+/// time a fallback expires while the streak persists), so that the caller can raise an alert.
+pub fn record_consensus_outcome<T>(
+    class: CallClass,
+    result: &ManagerResult<T>,
+    threshold: u64,
+    now: u64,
+) -> Option<u64> {
+    METRICS.with(|metrics| {
+        let mut metrics = metrics.borrow_mut();
+        let health = metrics.consensus.entry(class).or_default();
+        match result {
+            Ok(_) => {
+                health.consecutive_failures = 0;
+                None
+            }
+            Err(ManagerError::NoConsensus(_)) => {
+                health.consecutive_failures = health.consecutive_failures.saturating_add(1);
+                health.total_failures = health.total_failures.saturating_add(1);
+                let fallback_active = health.fallback_until.map_or(false, |until| now < until);
+                if health.consecutive_failures < threshold.max(1) || fallback_active {
+                    return None;
+                }
+                if class.is_read() {
+                    health.fallback_until = Some(now.saturating_add(CONSENSUS_FALLBACK_DURATION));
+                } else if health.consecutive_failures > threshold.max(1) {
+                    // Writes have no fallback, so they are only alerted once per streak
+                    return None;
+                }
+                Some(health.consecutive_failures)
+            }
+            Err(_) => None,
+        }
+    })
+}
+
+/// Returns `true` if calls of the given class should use the top-ranked provider alone at `now`.
+pub fn consensus_fallback_active(class: CallClass, now: u64) -> bool {
+    METRICS.with(|metrics| {
+        metrics
+            .borrow()
+            .consensus
+            .get(&class)
+            .and_then(|health| health.fallback_until)
+            .map_or(false, |until| now < until)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_run_counters() {
+        METRICS.with(|metrics| *metrics.borrow_mut() = Metrics::default());
+
+        record_run_started(4);
+        record_run_finished(true);
+        record_run_started(5);
+        record_run_finished(false);
+
+        let metrics = get_metrics();
+        assert_eq!(metrics.runs_started, 2);
+        assert_eq!(metrics.runs_succeeded, 1);
+        assert_eq!(metrics.runs_failed, 1);
+        assert_eq!(metrics.last_run_id, Some(5));
+    }
+
+    #[test]
+    fn test_no_consensus_streak_triggers_read_fallback() {
+        METRICS.with(|metrics| *metrics.borrow_mut() = Metrics::default());
+        let no_consensus: ManagerResult<()> = Err(ManagerError::NoConsensus(String::new()));
+
+        assert_eq!(
+            record_consensus_outcome(CallClass::EthCall, &no_consensus, 2, 10),
+            None
+        );
+        assert_eq!(
+            record_consensus_outcome(CallClass::EthCall, &Ok(()), 2, 20),
+            None
+        );
+        assert_eq!(
+            record_consensus_outcome(CallClass::EthCall, &no_consensus, 2, 30),
+            None
+        );
+        assert_eq!(
+            record_consensus_outcome(CallClass::EthCall, &no_consensus, 2, 40),
+            Some(2)
+        );
+        assert!(consensus_fallback_active(CallClass::EthCall, 40));
+        assert!(!consensus_fallback_active(
+            CallClass::EthCall,
+            40 + CONSENSUS_FALLBACK_DURATION
+        ));
+
+        // The streak persists after the fallback expired
+        let after = 40 + CONSENSUS_FALLBACK_DURATION;
+        assert_eq!(
+            record_consensus_outcome(CallClass::EthCall, &no_consensus, 2, after),
+            Some(3)
+        );
+    }
+
+    #[test]
+    fn test_management_call_stats() {
+        METRICS.with(|metrics| *metrics.borrow_mut() = Metrics::default());
+        let transient: ManagerResult<()> = Err(ManagerError::CallResult(
+            ic_exports::ic_cdk::api::call::RejectionCode::SysTransient,
+            String::new(),
+        ));
+
+        record_management_call(ManagementCall::SignWithEcdsa, &Ok(()), 1, 40, 1_000);
+        record_management_call(ManagementCall::SignWithEcdsa, &transient, 2, 20, 500);
+
+        let stats = get_metrics().management[&ManagementCall::SignWithEcdsa].clone();
+        assert_eq!(stats.calls, 2);
+        assert_eq!(stats.retries, 3);
+        assert_eq!(stats.transient_failures, 1);
+        assert_eq!(stats.permanent_failures, 0);
+        assert_eq!(stats.total_latency_ms, 60);
+        assert_eq!(stats.max_latency_ms, 40);
+        assert_eq!(stats.cycles_spent, Nat::from(1_500_u64));
+    }
+
+    #[test]
+    fn test_run_cycles() {
+        METRICS.with(|metrics| *metrics.borrow_mut() = Metrics::default());
+
+        record_run_cycles(10_000, 9_000);
+        record_run_cycles(9_000, 8_500);
+        // Cycles received during the run hide the burn
+        record_run_cycles(8_500, 20_000);
+
+        let run_cycles = get_metrics().run_cycles;
+        assert_eq!(run_cycles.runs, 2);
+        assert_eq!(run_cycles.total, Nat::from(1_500_u64));
+        assert_eq!(run_cycles.last, Some(Nat::from(500_u64)));
+    }
+
+    #[test]
+    fn test_cycles_burn_rate() {
+        METRICS.with(|metrics| *metrics.borrow_mut() = Metrics::default());
+
+        record_cycles_sample(10_000, 100);
+        assert_eq!(get_metrics().cycles_burn.cycles_per_second, None);
+
+        record_cycles_sample(9_000, 110);
+        assert_eq!(get_metrics().cycles_burn.cycles_per_second, Some(100));
+
+        // Received cycles are not averaged in
+        record_cycles_sample(20_000, 120);
+        assert_eq!(get_metrics().cycles_burn.cycles_per_second, Some(100));
+
+        record_cycles_sample(18_000, 130);
+        assert_eq!(get_metrics().cycles_burn.cycles_per_second, Some(125));
+    }
+
+    #[test]
+    fn test_write_streaks_alert_once() {
+        METRICS.with(|metrics| *metrics.borrow_mut() = Metrics::default());
+        let no_consensus: ManagerResult<()> = Err(ManagerError::NoConsensus(String::new()));
+        let class = CallClass::SendRawTransaction;
+
+        assert_eq!(
+            record_consensus_outcome(class, &no_consensus, 1, 10),
+            Some(1)
+        );
+        assert_eq!(record_consensus_outcome(class, &no_consensus, 1, 20), None);
+        assert!(!consensus_fallback_active(class, 20));
+        assert_eq!(get_metrics().consensus[&class].total_failures, 2);
+    }
+}
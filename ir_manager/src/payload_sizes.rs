@@ -0,0 +1,211 @@
+//! Response Size Histograms
+//!
+//! The dynamic-retry helpers start every EVM RPC call with `DEFAULT_MAX_RESPONSE_BYTES` and
+//! double the limit until the response fits, paying for every attempt. This module records
+//! the size each call class ended up needing, and can start the calls at the observed p90:
+//!
+//! ```plain
+//!              bucket k = DEFAULT_MAX_RESPONSE_BYTES * 2^k
+//! eth_call:0x5d3a5e8e [ 12 |  3 | 41 |  0 | ... ]   p90 ──► 32 KB
+//! eth_call:0x70a08231 [ 55 |  0 |  0 |  0 | ... ]   p90 ──►  8 KB
+//! eth_feeHistory      [ 60 |  0 |  0 |  0 | ... ]   p90 ──►  8 KB
+//!
+//! payload_autotune off: start at 8 KB ──► 16 KB ──► 32 KB ──► ... (doubling retries)
+//! payload_autotune on:  start at p90 of the call class (after enough samples)
+//! ```
+//!
+//! Call classes are the JSON-RPC methods, and `eth_call` reads are further split by the
+//! selector of the called function, so that large trove-list reads don't raise the start size
+//! (and the cost) of small calls. The recorded size is the smallest bucket that fits
+//! the response body, capped at the limit that succeeded, so that an auto-tuned start size can
+//! shrink again when responses get smaller.
+
+use std::{borrow::Cow, collections::BTreeMap};
+
+use candid::{CandidType, Decode, Encode};
+use ic_stable_structures::{storable::Bound, Storable};
+use serde::Deserialize;
+
+use crate::{
+    constants::DEFAULT_MAX_RESPONSE_BYTES,
+    flags::{flag_enabled, PAYLOAD_AUTOTUNE},
+    state::PAYLOAD_SIZES,
+};
+
+/// Number of buckets, from `DEFAULT_MAX_RESPONSE_BYTES` up to the last size below the 2 MB limit
+pub const PAYLOAD_BUCKETS: usize = 8;
+
+/// Minimum number of samples of a call class before its p90 is used as the start size
+const MIN_AUTOTUNE_SAMPLES: u64 = 20;
+
+/// Histogram of the response sizes needed by one call class
+#[derive(CandidType, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct PayloadHistogram {
+    /// Number of calls per bucket, where bucket `k` is `DEFAULT_MAX_RESPONSE_BYTES * 2^k` bytes
+    pub counts: Vec<u64>,
+    /// Number of attempts repeated because the response exceeded the limit
+    pub doublings: u64,
+}
+
+impl Storable for PayloadHistogram {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        Cow::Owned(Encode!(self).expect("Failed to encode a payload histogram."))
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).expect("Failed to decode a payload histogram.")
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+/// Returns the size of bucket `k` in bytes.
+fn bucket_size(k: usize) -> u64 {
+    DEFAULT_MAX_RESPONSE_BYTES << k
+}
+
+/// Returns the smallest bucket that fits `bytes`, or the last bucket.
+fn bucket_for(bytes: u64) -> usize {
+    (0..PAYLOAD_BUCKETS)
+        .find(|k| bucket_size(*k) >= bytes)
+        .unwrap_or(PAYLOAD_BUCKETS - 1)
+}
+
+impl PayloadHistogram {
+    /// Returns the total number of recorded calls.
+    pub fn samples(&self) -> u64 {
+        self.counts.iter().sum()
+    }
+
+    /// Returns the smallest bucket size that fits at least 90% of the recorded calls.
+    pub fn p90(&self) -> Option<u64> {
+        let samples = self.samples();
+        if samples == 0 {
+            return None;
+        }
+        let threshold = samples.saturating_mul(9).saturating_add(9) / 10;
+        let mut cumulative = 0;
+        for (k, count) in self.counts.iter().enumerate() {
+            cumulative += count;
+            if cumulative >= threshold {
+                return Some(bucket_size(k));
+            }
+        }
+        None
+    }
+}
+
+/// Records a successful call of a call class.
+///
+/// # Arguments
+/// * `class` - JSON-RPC method of the call
+/// * `body_bytes` - Length of the response body
+/// * `limit` - Response size limit the call succeeded with
+/// * `doublings` - Number of attempts repeated because the response exceeded the limit
+pub fn record_payload_size(class: &str, body_bytes: u64, limit: u64, doublings: u64) {
+    let bucket = bucket_for(body_bytes).min(bucket_for(limit));
+    PAYLOAD_SIZES.with(|histograms| {
+        let mut histograms = histograms.borrow_mut();
+        let mut histogram = histograms.get(&class.to_string()).unwrap_or_default();
+        histogram.counts.resize(PAYLOAD_BUCKETS, 0);
+        histogram.counts[bucket] = histogram.counts[bucket].saturating_add(1);
+        histogram.doublings = histogram.doublings.saturating_add(doublings);
+        histograms.insert(class.to_string(), histogram);
+    });
+}
+
+/// Returns the response size limit to start the calls of a call class with.
+///
+/// This is the p90 of the class when `payload_autotune` is enabled and enough calls were
+/// recorded, and `DEFAULT_MAX_RESPONSE_BYTES` otherwise.
+pub fn starting_response_bytes(class: &str) -> u64 {
+    if !flag_enabled!(PAYLOAD_AUTOTUNE) {
+        return DEFAULT_MAX_RESPONSE_BYTES;
+    }
+    PAYLOAD_SIZES
+        .with(|histograms| histograms.borrow().get(&class.to_string()))
+        .filter(|histogram| histogram.samples() >= MIN_AUTOTUNE_SAMPLES)
+        .and_then(|histogram| histogram.p90())
+        .unwrap_or(DEFAULT_MAX_RESPONSE_BYTES)
+}
+
+/// Returns the JSON-RPC method of a request, used as its call class.
+pub fn request_class(json_data: &str) -> String {
+    serde_json::from_str::<serde_json::Value>(json_data)
+        .ok()
+        .and_then(|request| request["method"].as_str().map(str::to_string))
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+/// Returns the call class of an `eth_call` read, keyed by the selector of the called function.
+pub fn eth_call_class(data: &[u8]) -> String {
+    match data.get(..4) {
+        Some(selector) => format!("eth_call:0x{}", hex::encode(selector)),
+        None => "eth_call".to_string(),
+    }
+}
+
+/// Returns the histograms of all call classes.
+pub fn payload_sizes() -> BTreeMap<String, PayloadHistogram> {
+    PAYLOAD_SIZES.with(|histograms| histograms.borrow().iter().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::flags::{reset_flag, set_flag, FlagValue};
+
+    #[test]
+    fn test_autotune_starts_at_p90() {
+        for _ in 0..18 {
+            record_payload_size("eth_call", 20_000, 32_000, 2);
+        }
+        record_payload_size("eth_call", 100, 8_000, 0);
+        // A large limit is not recorded when the body is small
+        record_payload_size("eth_call", 100, 64_000, 0);
+
+        let histogram = &payload_sizes()["eth_call"];
+        assert_eq!(histogram.counts[0], 2);
+        assert_eq!(histogram.counts[2], 18);
+        assert_eq!(histogram.doublings, 36);
+        assert_eq!(histogram.p90(), Some(32_000));
+
+        reset_flag(PAYLOAD_AUTOTUNE).unwrap();
+        assert_eq!(
+            starting_response_bytes("eth_call"),
+            DEFAULT_MAX_RESPONSE_BYTES
+        );
+        set_flag(PAYLOAD_AUTOTUNE, FlagValue::Bool(true)).unwrap();
+        assert_eq!(starting_response_bytes("eth_call"), 32_000);
+        assert_eq!(
+            starting_response_bytes("eth_getCode"),
+            DEFAULT_MAX_RESPONSE_BYTES
+        );
+    }
+
+    #[test]
+    fn test_request_class() {
+        assert_eq!(
+            request_class(r#"{"id":1,"jsonrpc":"2.0","method":"eth_feeHistory","params":[]}"#),
+            "eth_feeHistory"
+        );
+        assert_eq!(request_class("not json"), "unknown");
+    }
+
+    #[test]
+    fn test_eth_calls_are_classed_by_selector() {
+        let small = eth_call_class(&[0x70, 0xa0, 0x82, 0x31, 0x00, 0x01]);
+        let large = eth_call_class(&[0x5d, 0x3a, 0x5e, 0x8e]);
+        assert_eq!(small, "eth_call:0x70a08231");
+        assert_eq!(large, "eth_call:0x5d3a5e8e");
+        assert_eq!(eth_call_class(&[]), "eth_call");
+
+        for _ in 0..MIN_AUTOTUNE_SAMPLES {
+            record_payload_size(&large, 200_000, 256_000, 5);
+            record_payload_size(&small, 100, 8_000, 0);
+        }
+        set_flag(PAYLOAD_AUTOTUNE, FlagValue::Bool(true)).unwrap();
+        assert_eq!(starting_response_bytes(&large), 256_000);
+        assert_eq!(starting_response_bytes(&small), DEFAULT_MAX_RESPONSE_BYTES);
+    }
+}
@@ -0,0 +1,125 @@
+//! Positioning History
+//!
+//! Keeps a daily sample of where each batch stood against its target, so that `target_min`
+//! can be tuned on the redemption pressure observed over the last months rather than on a
+//! single run:
+//!
+//! ```plain
+//! execution ──► debt in front, target debt in front, batch rate
+//!                            │
+//!                            ▼
+//!       POSITIONING_HISTORY[(strategy, day)]   (the last run of a day wins)
+//!                            │
+//!                            └─ days older than POSITIONING_HISTORY_DAYS are pruned
+//!
+//! get_positioning_history(key) ──► samples of the strategy, oldest first
+//! ```
+//!
+//! A sample is taken by every execution that locates the batch, including warm-up runs, so a
+//! day without a sample is a day the strategy did not run or had no delegated trove.
+
+use std::borrow::Cow;
+
+use candid::{CandidType, Decode, Encode, Nat};
+use ic_stable_structures::{storable::Bound, Storable};
+use serde::Deserialize;
+
+use crate::{constants::POSITIONING_HISTORY_DAYS, state::POSITIONING_HISTORY};
+
+/// Seconds in a sampling period
+const DAY: u64 = 86_400;
+
+/// Position of a batch against its target, as observed by an execution
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq)]
+pub struct PositioningSample {
+    /// Timestamp in seconds of the execution
+    pub timestamp: u64,
+    /// Debt in front of the batch
+    pub debt_in_front: Nat,
+    /// Debt in front targeted by the strategy
+    pub target_debt_in_front: Nat,
+    /// Rate of the batch
+    pub rate: Nat,
+}
+
+impl Storable for PositioningSample {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        Cow::Owned(Encode!(self).expect("Failed to encode a positioning sample."))
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).expect("Failed to decode a positioning sample.")
+    }
+
+    const BOUND: Bound = Bound::Bounded {
+        max_size: 256,
+        is_fixed_size: false,
+    };
+}
+
+/// Stores the sample of a strategy for the day of its timestamp, replacing an earlier sample
+/// of the same day, and prunes the days out of the retention window.
+pub fn record_positioning_sample(strategy: u32, sample: PositioningSample) {
+    let day = sample.timestamp / DAY;
+    let first_kept = day.saturating_sub(POSITIONING_HISTORY_DAYS - 1);
+    POSITIONING_HISTORY.with(|history| {
+        let mut history = history.borrow_mut();
+        history.insert((strategy, day), sample);
+        let expired: Vec<(u32, u64)> = history
+            .range((strategy, 0)..(strategy, first_kept))
+            .map(|(key, _)| key)
+            .collect();
+        for key in expired {
+            history.remove(&key);
+        }
+    });
+}
+
+/// Returns the daily samples of a strategy, oldest first.
+pub fn positioning_history(strategy: u32) -> Vec<PositioningSample> {
+    POSITIONING_HISTORY.with(|history| {
+        history
+            .borrow()
+            .range((strategy, 0)..=(strategy, u64::MAX))
+            .map(|(_, sample)| sample)
+            .collect()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(timestamp: u64, rate: u64) -> PositioningSample {
+        PositioningSample {
+            timestamp,
+            debt_in_front: Nat::from(1_000_u64),
+            target_debt_in_front: Nat::from(1_200_u64),
+            rate: Nat::from(rate),
+        }
+    }
+
+    #[test]
+    fn test_positioning_history() {
+        record_positioning_sample(1, sample(DAY, 1));
+        record_positioning_sample(2, sample(DAY, 2));
+        // The last run of a day replaces the earlier ones
+        record_positioning_sample(1, sample(DAY + 3_600, 3));
+        record_positioning_sample(1, sample(2 * DAY, 4));
+
+        let rates: Vec<Nat> = positioning_history(1)
+            .into_iter()
+            .map(|sample| sample.rate)
+            .collect();
+        assert_eq!(rates, vec![Nat::from(3_u8), Nat::from(4_u8)]);
+        assert_eq!(positioning_history(2).len(), 1);
+        assert!(positioning_history(3).is_empty());
+
+        // Only the last POSITIONING_HISTORY_DAYS days are kept
+        record_positioning_sample(1, sample((POSITIONING_HISTORY_DAYS + 1) * DAY, 5));
+        let history = positioning_history(1);
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].rate, Nat::from(4_u8));
+        assert_eq!(positioning_history(2).len(), 1);
+    }
+}
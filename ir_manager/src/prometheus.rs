@@ -0,0 +1,420 @@
+//! Prometheus Metrics
+//!
+//! Serves the canister metrics in the Prometheus text exposition format over the HTTP gateway,
+//! so that existing monitoring stacks can scrape the canister without a candid client:
+//!
+//! ```plain
+//! GET /metrics ──► http_request ──► Metrics, cycles balance, JOURNAL, ADJUSTMENTS_BY_BLOCK,
+//!                                   strategy costs
+//!                                              │
+//!                                              ▼
+//!                          text/plain; version=0.0.4 ◄── encode_metrics
+//!
+//! GET /logs ──► journal export (see `log_export`)
+//!
+//! any other path ──► 404
+//! ```
+//!
+//! Counters end with `_total`. Most of them are kept on the heap and restart from zero after an
+//! upgrade, which Prometheus handles as a counter reset. The costs of the strategies are
+//! labeled with their key and collateral index, so that they can be attributed to a branch.
+
+use std::fmt::{Display, Write};
+
+use alloy_primitives::U256;
+use candid::{CandidType, Nat};
+use serde::Deserialize;
+use serde_bytes::ByteBuf;
+
+use crate::{
+    log_export::{encode_records, log_records, ExportQuery},
+    metrics::{get_metrics, Metrics},
+    state::{ADJUSTMENTS_BY_BLOCK, JOURNAL, STRATEGY_STATE},
+};
+
+/// Path the metrics are served at
+const METRICS_PATH: &str = "/metrics";
+
+/// Path the journal export is served at
+const LOGS_PATH: &str = "/logs";
+
+/// Content type of the Prometheus text exposition format
+const CONTENT_TYPE: &str = "text/plain; version=0.0.4";
+
+/// HTTP request received through the HTTP gateway
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct HttpRequest {
+    /// HTTP method
+    pub method: String,
+    /// Requested URL, including the query string
+    pub url: String,
+    /// Request headers
+    pub headers: Vec<(String, String)>,
+    /// Request body
+    pub body: ByteBuf,
+}
+
+/// HTTP response served through the HTTP gateway
+#[derive(CandidType, Clone, Debug, PartialEq)]
+pub struct HttpGatewayResponse {
+    /// HTTP status code
+    pub status_code: u16,
+    /// Response headers
+    pub headers: Vec<(String, String)>,
+    /// Response body
+    pub body: ByteBuf,
+}
+
+impl HttpGatewayResponse {
+    /// Returns a plain text response.
+    fn text(status_code: u16, content_type: &str, body: String) -> Self {
+        Self {
+            status_code,
+            headers: vec![
+                ("Content-Type".to_string(), content_type.to_string()),
+                ("Content-Length".to_string(), body.len().to_string()),
+            ],
+            body: ByteBuf::from(body.into_bytes()),
+        }
+    }
+}
+
+/// Gauges read from the state when the metrics are scraped
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Gauges {
+    /// Current cycles balance
+    pub cycles_balance: u128,
+    /// Number of strategies
+    pub strategies: u64,
+    /// Number of journal collections in stable memory
+    pub journal_collections: u64,
+    /// Number of confirmed rate adjustments
+    pub rate_adjustments: u64,
+    /// Cumulative costs of each strategy, by key
+    pub strategy_costs: Vec<StrategyCosts>,
+}
+
+/// Cumulative costs of a strategy
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct StrategyCosts {
+    /// Key of the strategy
+    pub key: u32,
+    /// Collateral index of the strategy's branch
+    pub collateral_index: U256,
+    /// Fees in wei paid for gas
+    pub gas_spent: u128,
+    /// Upfront fees charged to the batch
+    pub upfront_fees_paid: U256,
+}
+
+impl Gauges {
+    /// Reads the gauges from the state.
+    pub fn read(cycles_balance: u128) -> Self {
+        Self {
+            cycles_balance,
+            strategies: STRATEGY_STATE.with(|state| state.borrow().len() as u64),
+            journal_collections: JOURNAL.with_borrow(|journal| journal.len()),
+            rate_adjustments: ADJUSTMENTS_BY_BLOCK.with(|adjustments| adjustments.borrow().len()),
+            strategy_costs: strategy_costs(),
+        }
+    }
+}
+
+/// Reads the cumulative costs of the strategies, ordered by key.
+fn strategy_costs() -> Vec<StrategyCosts> {
+    let mut costs: Vec<StrategyCosts> = STRATEGY_STATE.with(|state| {
+        state
+            .borrow()
+            .values()
+            .map(|strategy| StrategyCosts {
+                key: strategy.settings.key,
+                collateral_index: strategy.settings.collateral_index,
+                gas_spent: strategy.data.gas_spent,
+                upfront_fees_paid: strategy.data.upfront_fees_paid,
+            })
+            .collect()
+    });
+    costs.sort_unstable_by_key(|costs| costs.key);
+    costs
+}
+
+/// Text exposition being written
+struct Exposition(String);
+
+impl Exposition {
+    /// Writes the `HELP` and `TYPE` lines of a metric.
+    fn describe(&mut self, name: &str, kind: &str, help: &str) {
+        let _ = writeln!(self.0, "# HELP {} {}", name, help);
+        let _ = writeln!(self.0, "# TYPE {} {}", name, kind);
+    }
+
+    /// Writes a sample of a metric.
+    fn sample(&mut self, name: &str, labels: &[(&str, &str)], value: impl Display) {
+        self.0.push_str(name);
+        if !labels.is_empty() {
+            let labels: Vec<String> = labels
+                .iter()
+                .map(|(label, value)| format!("{}=\"{}\"", label, escape_label(value)))
+                .collect();
+            let _ = write!(self.0, "{{{}}}", labels.join(","));
+        }
+        let _ = writeln!(self.0, " {}", value);
+    }
+
+    /// Writes a metric with a single unlabeled sample.
+    fn single(&mut self, name: &str, kind: &str, help: &str, value: impl Display) {
+        self.describe(name, kind, help);
+        self.sample(name, &[], value);
+    }
+}
+
+/// Escapes a label value as required by the exposition format.
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// Returns the digits of a `Nat`, without the separators of its `Display` implementation.
+fn digits(value: &Nat) -> String {
+    value.0.to_string()
+}
+
+/// Encodes the metrics in the Prometheus text exposition format.
+pub fn encode_metrics(metrics: &Metrics, gauges: &Gauges) -> String {
+    let mut out = Exposition(String::new());
+
+    out.single(
+        "ir_manager_runs_started_total",
+        "counter",
+        "Number of strategy runs started.",
+        metrics.runs_started,
+    );
+    out.single(
+        "ir_manager_runs_succeeded_total",
+        "counter",
+        "Number of strategy runs that finished successfully.",
+        metrics.runs_succeeded,
+    );
+    out.single(
+        "ir_manager_runs_failed_total",
+        "counter",
+        "Number of strategy runs that finished with an error.",
+        metrics.runs_failed,
+    );
+    out.single(
+        "ir_manager_rate_adjustments",
+        "gauge",
+        "Number of confirmed rate adjustments in the index.",
+        gauges.rate_adjustments,
+    );
+
+    out.describe(
+        "ir_manager_rpc_failures_total",
+        "counter",
+        "Number of failed RPC calls, by provider.",
+    );
+    for (provider, failures) in &metrics.provider_failures {
+        out.sample(
+            "ir_manager_rpc_failures_total",
+            &[("provider", provider)],
+            failures,
+        );
+    }
+    out.describe(
+        "ir_manager_no_consensus_total",
+        "counter",
+        "Number of multi-provider calls without consensus, by call class.",
+    );
+    for (class, health) in &metrics.consensus {
+        out.sample(
+            "ir_manager_no_consensus_total",
+            &[("class", &format!("{:?}", class))],
+            health.total_failures,
+        );
+    }
+
+    out.describe(
+        "ir_manager_gas_spent_wei_total",
+        "counter",
+        "Fees in wei paid for the gas of confirmed transactions, by strategy.",
+    );
+    for costs in &gauges.strategy_costs {
+        let (key, collateral_index) = (costs.key.to_string(), costs.collateral_index.to_string());
+        out.sample(
+            "ir_manager_gas_spent_wei_total",
+            &[("strategy", &key), ("collateral_index", &collateral_index)],
+            costs.gas_spent,
+        );
+    }
+    out.describe(
+        "ir_manager_upfront_fees_paid_total",
+        "counter",
+        "Upfront fees charged to the batch by confirmed transactions, by strategy.",
+    );
+    for costs in &gauges.strategy_costs {
+        let (key, collateral_index) = (costs.key.to_string(), costs.collateral_index.to_string());
+        out.sample(
+            "ir_manager_upfront_fees_paid_total",
+            &[("strategy", &key), ("collateral_index", &collateral_index)],
+            costs.upfront_fees_paid,
+        );
+    }
+
+    out.single(
+        "ir_manager_cycles_balance",
+        "gauge",
+        "Cycles balance of the canister.",
+        gauges.cycles_balance,
+    );
+    out.single(
+        "ir_manager_run_cycles_total",
+        "counter",
+        "Estimated cycles burned by the measured strategy runs.",
+        digits(&metrics.run_cycles.total),
+    );
+    out.single(
+        "ir_manager_measured_runs_total",
+        "counter",
+        "Number of strategy runs whose cycles were measured.",
+        metrics.run_cycles.runs,
+    );
+    if let Some(last) = &metrics.run_cycles.last {
+        out.single(
+            "ir_manager_last_run_cycles",
+            "gauge",
+            "Estimated cycles burned by the last measured strategy run.",
+            digits(last),
+        );
+    }
+
+    out.single(
+        "ir_manager_strategies",
+        "gauge",
+        "Number of strategies.",
+        gauges.strategies,
+    );
+    out.single(
+        "ir_manager_journal_collections",
+        "gauge",
+        "Number of journal collections in stable memory.",
+        gauges.journal_collections,
+    );
+    out.single(
+        "ir_manager_journal_dropped_writes_total",
+        "counter",
+        "Number of journal collections that could not be stored in full.",
+        metrics.journal.dropped_writes,
+    );
+
+    out.0
+}
+
+/// Serves the metrics at `/metrics`.
+pub fn http_request(request: &HttpRequest, cycles_balance: u128) -> HttpGatewayResponse {
+    let (path, query) = request.url.split_once('?').unwrap_or((&request.url, ""));
+    if path != METRICS_PATH && path != LOGS_PATH {
+        return HttpGatewayResponse::text(404, "text/plain", "Not found".to_string());
+    }
+    if request.method != "GET" {
+        return HttpGatewayResponse::text(405, "text/plain", "Method not allowed".to_string());
+    }
+    if path == LOGS_PATH {
+        return export_logs(query);
+    }
+    HttpGatewayResponse::text(
+        200,
+        CONTENT_TYPE,
+        encode_metrics(&get_metrics(), &Gauges::read(cycles_balance)),
+    )
+}
+
+/// Serves the journal export requested by a `/logs` query string.
+fn export_logs(query: &str) -> HttpGatewayResponse {
+    match ExportQuery::parse(query) {
+        Ok(query) => HttpGatewayResponse::text(
+            200,
+            query.format.content_type(),
+            encode_records(&log_records(query.strategy, query.depth), query.format),
+        ),
+        Err(message) => HttpGatewayResponse::text(400, "text/plain", message),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_metrics() {
+        let mut metrics = Metrics {
+            runs_started: 3,
+            ..Default::default()
+        };
+        metrics
+            .provider_failures
+            .insert("Custom(\"a\\b\")".to_string(), 2);
+        metrics.run_cycles.total = Nat::from(1_500_000_u64);
+        let gauges = Gauges {
+            cycles_balance: 7,
+            ..Default::default()
+        };
+
+        let text = encode_metrics(&metrics, &gauges);
+        assert!(text.contains("# TYPE ir_manager_runs_started_total counter\n"));
+        assert!(text.contains("ir_manager_runs_started_total 3\n"));
+        assert!(
+            text.contains("ir_manager_rpc_failures_total{provider=\"Custom(\\\"a\\\\b\\\")\"} 2\n")
+        );
+        assert!(text.contains("ir_manager_run_cycles_total 1500000\n"));
+        assert!(text.contains("ir_manager_cycles_balance 7\n"));
+        assert!(!text.contains("ir_manager_last_run_cycles"));
+        assert!(text.contains("# TYPE ir_manager_gas_spent_wei_total counter\n"));
+        assert!(!text.contains("ir_manager_gas_spent_wei_total{"));
+
+        let gauges = Gauges {
+            strategy_costs: vec![StrategyCosts {
+                key: 2,
+                collateral_index: U256::from(1),
+                gas_spent: 156_000_000_000_000,
+                upfront_fees_paid: U256::from(42),
+            }],
+            ..Default::default()
+        };
+        let text = encode_metrics(&metrics, &gauges);
+        assert!(text.contains(
+            "ir_manager_gas_spent_wei_total{strategy=\"2\",collateral_index=\"1\"} 156000000000000\n"
+        ));
+        assert!(text.contains(
+            "ir_manager_upfront_fees_paid_total{strategy=\"2\",collateral_index=\"1\"} 42\n"
+        ));
+    }
+
+    #[test]
+    fn test_http_request_routes() {
+        let request = |method: &str, url: &str| HttpRequest {
+            method: method.to_string(),
+            url: url.to_string(),
+            headers: vec![],
+            body: ByteBuf::new(),
+        };
+
+        assert_eq!(http_request(&request("GET", "/"), 0).status_code, 404);
+        assert_eq!(
+            http_request(&request("POST", "/metrics"), 0).status_code,
+            405
+        );
+        let response = http_request(&request("GET", "/metrics?format=text"), 0);
+        assert_eq!(response.status_code, 200);
+        assert_eq!(response.headers[0].1, CONTENT_TYPE);
+
+        assert_eq!(
+            http_request(&request("GET", "/logs?format=xml"), 0).status_code,
+            400
+        );
+        let response = http_request(&request("GET", "/logs?depth=5"), 0);
+        assert_eq!(response.status_code, 200);
+        assert_eq!(response.headers[0].1, "application/x-ndjson");
+    }
+}
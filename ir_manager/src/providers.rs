@@ -28,15 +28,54 @@
 //! - Periodic reputation logging (every 10 score changes)
 //! - Provider ranking with tie-breaking mechanisms
 //! - Consensus-based reputation updates
+//! - Separate pools for reads and transaction submission
+//!
+//! ```plain
+//! Provider Pools:
+//!
+//! Read pool  ──► eth_call, trove pagination, blocks, fee history
+//! Write pool ──► eth_sendRawTransaction, eth_getTransactionCount
+//! ```
+//!
+//! A provider that is great at archival reads may be poor at mempool propagation and vice versa,
+//! so each pool has its own members and reputations.
+//!
+//! Providers that serve stale chain data are caught before they cause consensus failures:
+//! whenever a block tag is fetched, every read pool member reports its block height, and
+//! the members lagging more than `max_block_lag` blocks behind the highest height lose
+//! reputation.
+//!
+//! ```plain
+//! Block Freshness:
+//!
+//! P1: 21_000_010 ─┐
+//! P2: 21_000_009 ─┼─► max = 21_000_010 ──► P3 lags 7 blocks ──► P3: -1
+//! P3: 21_000_003 ─┘
+//! ```
+//!
+//! A controller can disable a provider that is known to be serving corrupted data. Disabled
+//! providers are removed from the rotation of both pools immediately, regardless of their
+//! reputation, and stay disabled across upgrades until they are enabled again.
+//!
+//! A pool with enough custom providers (see `custom_providers`) is served by them instead of
+//! the built-in providers while their reputation is at least as high.
 
-use std::fmt::Debug;
+use std::{borrow::Cow, cell::RefCell, fmt::Debug, thread::LocalKey};
 
-use evm_rpc_types::{MultiRpcResult, RpcServices};
+use candid::{CandidType, Decode, Encode};
+use evm_rpc_types::{MultiRpcResult, RpcService, RpcServices};
+use ic_stable_structures::{storable::Bound, Storable};
+use serde::Deserialize;
 
 use crate::{
-    constants::PROVIDER_COUNT,
+    constants::{CHAIN_ID, PROVIDER_COUNT},
+    custom_providers::{preferred_custom_services, score_custom_provider},
     journal::JournalCollection,
-    state::RPC_REPUTATIONS,
+    metrics::record_provider_failure,
+    state::{
+        DISABLED_PROVIDERS, READ_POOL_PROVIDERS, RPC_REPUTATIONS, RPC_WRITE_REPUTATIONS,
+        WRITE_POOL_PROVIDERS,
+    },
     types::ProviderService,
     utils::{
         error::{ManagerError, ManagerResult},
@@ -44,18 +83,185 @@ use crate::{
     },
 };
 
-/// Retrieves the current provider rankings from thread-local storage.
+/// Thread-local reputation leaderboard of a provider pool
+type Leaderboard = LocalKey<RefCell<Vec<(i64, ProviderService)>>>;
+
+/// Provider pool used for a request
+#[derive(CandidType, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProviderPool {
+    /// Heavy read consensus (`eth_call`, trove pagination, blocks, fee history)
+    Read,
+    /// Transaction submission and nonce queries
+    Write,
+}
+
+impl ProviderPool {
+    /// Returns the reputation leaderboard of the pool.
+    fn leaderboard(self) -> &'static Leaderboard {
+        match self {
+            ProviderPool::Read => &RPC_REPUTATIONS,
+            ProviderPool::Write => &RPC_WRITE_REPUTATIONS,
+        }
+    }
+
+    /// Returns the configured members of the pool.
+    pub fn members(self) -> Vec<ProviderService> {
+        match self {
+            ProviderPool::Read => READ_POOL_PROVIDERS.with(|members| members.borrow().clone()),
+            ProviderPool::Write => WRITE_POOL_PROVIDERS.with(|members| members.borrow().clone()),
+        }
+    }
+
+    /// Replaces the reputation leaderboard of the pool.
+    pub fn set_reputations(self, reputations: Vec<(i64, ProviderService)>) {
+        self.leaderboard()
+            .with(|leaderboard| *leaderboard.borrow_mut() = reputations);
+    }
+}
+
+/// A provider removed from the rotation by a controller
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq)]
+pub struct DisabledProvider {
+    /// The disabled provider
+    pub provider: ProviderService,
+    /// Timestamp in seconds of when the provider was disabled
+    pub disabled_at: u64,
+}
+
+impl Storable for DisabledProvider {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        Cow::Owned(Encode!(self).expect("Failed to encode a disabled provider."))
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).expect("Failed to decode a disabled provider.")
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+/// Returns the key of a provider in `DISABLED_PROVIDERS`.
+fn provider_key(provider: &ProviderService) -> String {
+    format!("{:?}", provider)
+}
+
+/// Returns `true` if the provider was disabled by a controller.
+pub fn is_provider_disabled(provider: &ProviderService) -> bool {
+    DISABLED_PROVIDERS.with(|disabled| disabled.borrow().contains_key(&provider_key(provider)))
+}
+
+/// Returns all disabled providers.
+pub fn disabled_providers() -> Vec<DisabledProvider> {
+    DISABLED_PROVIDERS.with(|disabled| {
+        disabled
+            .borrow()
+            .iter()
+            .map(|(_, provider)| provider)
+            .collect()
+    })
+}
+
+/// Removes a provider from the rotation of both pools at `now` (in seconds).
+///
+/// # Errors
+/// - `ManagerError::Custom` if the provider is already disabled
+/// - `ManagerError::Custom` if a pool would be left with fewer than `PROVIDER_COUNT` enabled
+///   providers, as quorum reads are sent to `PROVIDER_COUNT` providers
+pub fn disable_provider(provider: ProviderService, now: u64) -> ManagerResult<()> {
+    if is_provider_disabled(&provider) {
+        return Err(ManagerError::Custom(format!(
+            "Provider {:?} is already disabled.",
+            provider
+        )));
+    }
+
+    for pool in [ProviderPool::Read, ProviderPool::Write] {
+        let remaining = fetch_provider_list(pool)
+            .iter()
+            .filter(|(_, member)| *member != provider && !is_provider_disabled(member))
+            .count();
+        if remaining < PROVIDER_COUNT as usize {
+            return Err(ManagerError::Custom(format!(
+                "Disabling provider {:?} would leave the {:?} pool with {} enabled providers.",
+                provider, pool, remaining
+            )));
+        }
+    }
+
+    DISABLED_PROVIDERS.with(|disabled| {
+        disabled.borrow_mut().insert(
+            provider_key(&provider),
+            DisabledProvider {
+                provider,
+                disabled_at: now,
+            },
+        )
+    });
+    Ok(())
+}
+
+/// Restores a disabled provider to the rotation of both pools.
+///
+/// # Errors
+/// Returns `ManagerError::NonExistentValue` if the provider is not disabled.
+pub fn enable_provider(provider: ProviderService) -> ManagerResult<()> {
+    DISABLED_PROVIDERS
+        .with(|disabled| disabled.borrow_mut().remove(&provider_key(&provider)))
+        .map(|_| ())
+        .ok_or(ManagerError::NonExistentValue)
+}
+
+/// Replaces the members of a pool and resets their reputations to zero.
+///
+/// # Errors
+/// Returns `ManagerError::Custom` if the list contains duplicates or fewer than `PROVIDER_COUNT` providers.
+pub fn set_pool_members(pool: ProviderPool, providers: Vec<ProviderService>) -> ManagerResult<()> {
+    if providers
+        .iter()
+        .enumerate()
+        .any(|(i, provider)| providers[..i].contains(provider))
+    {
+        return Err(ManagerError::Custom(
+            "The provider list contains duplicates.".to_string(),
+        ));
+    }
+    if providers.len() < PROVIDER_COUNT as usize {
+        return Err(ManagerError::Custom(format!(
+            "A provider pool needs at least {} providers.",
+            PROVIDER_COUNT
+        )));
+    }
+
+    match pool {
+        ProviderPool::Read => {
+            READ_POOL_PROVIDERS.with(|members| *members.borrow_mut() = providers.clone())
+        }
+        ProviderPool::Write => {
+            WRITE_POOL_PROVIDERS.with(|members| *members.borrow_mut() = providers.clone())
+        }
+    }
+    pool.set_reputations(
+        providers
+            .into_iter()
+            .map(|provider| (0, provider))
+            .collect(),
+    );
+    Ok(())
+}
+
+/// Retrieves the current provider rankings of a pool from thread-local storage.
 ///
 /// Returns a vector of tuples containing each provider's score and identifier,
 /// maintaining the original order from storage.
-fn fetch_provider_list() -> Vec<(i64, ProviderService)> {
-    RPC_REPUTATIONS.with(|leaderboard| leaderboard.borrow().clone())
+pub fn fetch_provider_list(pool: ProviderPool) -> Vec<(i64, ProviderService)> {
+    pool.leaderboard()
+        .with(|leaderboard| leaderboard.borrow().clone())
 }
 
 /// Computes and returns the top-ranked providers based on reputation scores.
 ///
 /// The ranking algorithm:
-/// 1. Sorts providers by score in descending order
+/// 1. Skips disabled providers, then sorts the rest by score in descending order
 /// 2. Selects providers up to PROVIDER_COUNT
 /// 3. Includes tied providers if they are exactly 1 point behind
 ///
@@ -64,8 +270,9 @@ fn fetch_provider_list() -> Vec<(i64, ProviderService)> {
 /// Scores:  10   10   9    8    7
 /// Result:  [P1, P2, P3]  // P3 included despite being 1 point lower
 /// ```
-fn ranked_provider_list() -> Vec<ProviderService> {
-    let mut provider_list = fetch_provider_list();
+fn ranked_provider_list(pool: ProviderPool) -> Vec<ProviderService> {
+    let mut provider_list = fetch_provider_list(pool);
+    provider_list.retain(|(_, provider)| !is_provider_disabled(provider));
 
     // Sort the providers by the first element in descending order
     provider_list.sort_by(|a, b| b.0.cmp(&a.0));
@@ -103,9 +310,10 @@ fn ranked_provider_list() -> Vec<ProviderService> {
 /// - Thread-safe through RefCell borrow_mut
 ///
 /// # Arguments
+/// * `pool` - The pool whose leaderboard is updated
 /// * `provider` - Reference to the provider whose score should be incremented
-pub fn increment_provider_score(provider: &ProviderService) {
-    RPC_REPUTATIONS.with(|leaderboard| {
+pub fn increment_provider_score(pool: ProviderPool, provider: &ProviderService) {
+    pool.leaderboard().with(|leaderboard| {
         let mut leaderboard = leaderboard.borrow_mut();
 
         // Find the provider in the leaderboard
@@ -117,8 +325,8 @@ pub fn increment_provider_score(provider: &ProviderService) {
                     Ok(()),
                     crate::journal::LogType::ProviderReputationChange,
                     format!(
-                        "Provider {:#?} reputation change: +1 | new reputation: {}",
-                        provider, entry.0
+                        "Provider {:#?} reputation change ({:?} pool): +1 | new reputation: {}",
+                        provider, pool, entry.0
                     ),
                 );
             }
@@ -133,21 +341,23 @@ pub fn increment_provider_score(provider: &ProviderService) {
 /// - Thread-safe through RefCell borrow_mut
 ///
 /// # Arguments
+/// * `pool` - The pool whose leaderboard is updated
 /// * `provider` - Reference to the provider whose score should be decremented
-pub fn decrement_provider_score(provider: &ProviderService) {
-    RPC_REPUTATIONS.with(|leaderboard| {
+pub fn decrement_provider_score(pool: ProviderPool, provider: &ProviderService) {
+    pool.leaderboard().with(|leaderboard| {
         let mut leaderboard = leaderboard.borrow_mut();
 
         // Find the provider in the leaderboard
         if let Some(entry) = leaderboard.iter_mut().find(|(_, p)| p == provider) {
+            record_provider_failure(format!("{:?}", provider));
             entry.0 = entry.0.saturating_sub(1); // Decrement the score, saturating at i64::MIN
             if entry.0 % 10 == 0 {
                 JournalCollection::open(None).append_note(
                     Ok(()),
                     crate::journal::LogType::ProviderReputationChange,
                     format!(
-                        "Provider {:#?} reputation change: -1 | new reputation: {}",
-                        provider, entry.0
+                        "Provider {:#?} reputation change ({:?} pool): -1 | new reputation: {}",
+                        provider, pool, entry.0
                     ),
                 );
             }
@@ -155,30 +365,87 @@ pub fn decrement_provider_score(provider: &ProviderService) {
     });
 }
 
-/// Returns the current top-ranked providers as an RPC service collection.
+/// Returns the sum of the reputations of the given providers of a pool.
+fn reputation_sum(pool: ProviderPool, providers: &[ProviderService]) -> i64 {
+    fetch_provider_list(pool)
+        .iter()
+        .filter(|(_, provider)| providers.contains(provider))
+        .fold(0_i64, |sum, (score, _)| sum.saturating_add(*score))
+}
+
+/// Returns the top-ranked custom providers of a pool, if they outrank the given built-in ones.
+fn custom_services(
+    pool: ProviderPool,
+    ranked_provider_list: &[ProviderService],
+) -> Option<RpcServices> {
+    preferred_custom_services(pool, reputation_sum(pool, ranked_provider_list)).map(|services| {
+        RpcServices::Custom {
+            chain_id: CHAIN_ID,
+            services,
+        }
+    })
+}
+
+/// Updates the reputations of the custom providers used for a request.
+fn score_custom_services(pool: ProviderPool, providers: &RpcServices, success: bool) {
+    if let RpcServices::Custom { services, .. } = providers {
+        services
+            .iter()
+            .for_each(|api| score_custom_provider(pool, &api.url, success));
+    }
+}
+
+/// Returns the current top-ranked providers of a pool as an RPC service collection.
 ///
 /// The ranking considers reputation scores and includes providers up to PROVIDER_COUNT.
-/// Returns appropriate enum variant based on compile-time network selection (mainnet/sepolia).
-pub fn get_ranked_rpc_providers() -> RpcServices {
-    let ranked_provider_list = ranked_provider_list();
+/// Returns appropriate enum variant based on compile-time network selection (mainnet/sepolia),
+/// or the custom providers of the pool if they outrank the built-in ones.
+pub fn get_ranked_rpc_providers(pool: ProviderPool) -> RpcServices {
+    let ranked_provider_list = ranked_provider_list(pool);
+    if let Some(services) = custom_services(pool, &ranked_provider_list) {
+        return services;
+    }
     #[cfg(feature = "sepolia")]
     return RpcServices::EthSepolia(Some(ranked_provider_list));
     #[cfg(feature = "mainnet")]
     return RpcServices::EthMainnet(Some(ranked_provider_list));
 }
 
-/// Returns the single highest-ranked provider as an RPC service.
+/// Returns the single highest-ranked provider of a pool as an RPC service.
 ///
 /// Selects the provider with the highest reputation score.
-/// Returns appropriate enum variant based on compile-time network selection (mainnet/sepolia).
-pub fn get_ranked_rpc_provider() -> RpcServices {
-    let ranked_provider_list = ranked_provider_list();
+/// Returns appropriate enum variant based on compile-time network selection (mainnet/sepolia),
+/// or the top custom provider of the pool if the custom providers outrank the built-in ones.
+pub fn get_ranked_rpc_provider(pool: ProviderPool) -> RpcServices {
+    let ranked_provider_list = ranked_provider_list(pool);
+    if let Some(RpcServices::Custom { chain_id, services }) =
+        custom_services(pool, &ranked_provider_list)
+    {
+        return RpcServices::Custom {
+            chain_id,
+            services: services[..1].to_vec(),
+        };
+    }
     #[cfg(feature = "sepolia")]
     return RpcServices::EthSepolia(Some(ranked_provider_list[..1].to_vec()));
     #[cfg(feature = "mainnet")]
     return RpcServices::EthMainnet(Some(ranked_provider_list[..1].to_vec()));
 }
 
+/// Returns the providers whose block height lags more than `max_lag` blocks behind the
+/// highest height in `heights`.
+pub fn lagging_providers(heights: &[(ProviderService, u64)], max_lag: u64) -> Vec<ProviderService> {
+    let Some(max_height) = heights.iter().map(|(_, height)| *height).max() else {
+        return vec![];
+    };
+
+    heights
+        .iter()
+        .filter(|(_, height)| max_height.saturating_sub(*height) > max_lag)
+        .map(|(provider, _)| *provider)
+        .collect()
+}
+
 /// Processes multi-RPC results and updates provider reputations accordingly.
 ///
 /// # Reputation Updates
@@ -187,6 +454,7 @@ pub fn get_ranked_rpc_provider() -> RpcServices {
 /// - Inconsistent responses: Individual providers gain/lose based on their responses
 ///
 /// # Arguments
+/// * `pool` - The pool the providers were selected from
 /// * `providers` - The RPC services used for the request
 /// * `result` - The multi-RPC result to process
 ///
@@ -194,18 +462,20 @@ pub fn get_ranked_rpc_provider() -> RpcServices {
 /// * `Ok(T)` - The successful result value
 /// * `Err(ManagerError)` - Error indicating consensus failure or RPC issues
 pub fn extract_multi_rpc_result<T: Debug>(
+    pool: ProviderPool,
     providers: RpcServices,
     result: MultiRpcResult<T>,
 ) -> ManagerResult<T> {
     match result {
         MultiRpcResult::Consistent(response) => {
+            score_custom_services(pool, &providers, response.is_ok());
             if response.is_ok() {
                 #[cfg(feature = "sepolia")]
                 if let RpcServices::EthSepolia(services) = providers {
                     let providers_unwrapped = services.ok_or(ManagerError::NonExistentValue)?;
                     providers_unwrapped
                         .iter()
-                        .for_each(increment_provider_score);
+                        .for_each(|provider| increment_provider_score(pool, provider));
                 }
 
                 #[cfg(feature = "mainnet")]
@@ -213,7 +483,7 @@ pub fn extract_multi_rpc_result<T: Debug>(
                     let providers_unwrapped = services.ok_or(ManagerError::NonExistentValue)?;
                     providers_unwrapped
                         .iter()
-                        .for_each(increment_provider_score);
+                        .for_each(|provider| increment_provider_score(pool, provider));
                 }
             } else {
                 #[cfg(feature = "sepolia")]
@@ -221,7 +491,7 @@ pub fn extract_multi_rpc_result<T: Debug>(
                     let providers_unwrapped = services.ok_or(ManagerError::NonExistentValue)?;
                     providers_unwrapped
                         .iter()
-                        .for_each(decrement_provider_score);
+                        .for_each(|provider| decrement_provider_score(pool, provider));
                 }
 
                 #[cfg(feature = "mainnet")]
@@ -229,7 +499,7 @@ pub fn extract_multi_rpc_result<T: Debug>(
                     let providers_unwrapped = services.ok_or(ManagerError::NonExistentValue)?;
                     providers_unwrapped
                         .iter()
-                        .for_each(decrement_provider_score);
+                        .for_each(|provider| decrement_provider_score(pool, provider));
                 }
             }
 
@@ -237,21 +507,25 @@ pub fn extract_multi_rpc_result<T: Debug>(
         }
         MultiRpcResult::Inconsistent(responses) => {
             responses.iter().for_each(|(provider, result)| {
+                if let RpcService::Custom(api) = provider {
+                    score_custom_provider(pool, &api.url, result.is_ok());
+                }
+
                 #[cfg(feature = "sepolia")]
                 if let evm_rpc_types::RpcService::EthSepolia(eth_sepolia_service) = provider {
                     if result.is_ok() {
-                        increment_provider_score(eth_sepolia_service);
+                        increment_provider_score(pool, eth_sepolia_service);
                     } else {
-                        decrement_provider_score(eth_sepolia_service);
+                        decrement_provider_score(pool, eth_sepolia_service);
                     }
                 }
 
                 #[cfg(feature = "mainnet")]
                 if let evm_rpc_types::RpcService::EthMainnet(eth_mainnet_service) = provider {
                     if result.is_ok() {
-                        increment_provider_score(eth_mainnet_service);
+                        increment_provider_score(pool, eth_mainnet_service);
                     } else {
-                        decrement_provider_score(eth_mainnet_service);
+                        decrement_provider_score(pool, eth_mainnet_service);
                     }
                 }
             });
@@ -261,6 +535,7 @@ pub fn extract_multi_rpc_result<T: Debug>(
 }
 
 /// Specialized handler for raw transaction submission results across multiple providers.
+/// The reputations of the write pool are updated.
 ///
 /// Extends the base multi-RPC result handling with transaction-specific logic:
 /// - Prioritizes nonce-related responses
@@ -278,15 +553,17 @@ pub fn extract_multi_rpc_send_raw_transaction_status(
     providers: RpcServices,
     result: MultiRpcResult<SendRawTransactionStatus>,
 ) -> ManagerResult<SendRawTransactionStatus> {
+    let pool = ProviderPool::Write;
     match result {
         MultiRpcResult::Consistent(response) => {
+            score_custom_services(pool, &providers, response.is_ok());
             if response.is_ok() {
                 #[cfg(feature = "sepolia")]
                 if let RpcServices::EthSepolia(services) = providers {
                     let providers_unwrapped = services.ok_or(ManagerError::NonExistentValue)?;
                     providers_unwrapped
                         .iter()
-                        .for_each(increment_provider_score);
+                        .for_each(|provider| increment_provider_score(pool, provider));
                 }
 
                 #[cfg(feature = "mainnet")]
@@ -294,7 +571,7 @@ pub fn extract_multi_rpc_send_raw_transaction_status(
                     let providers_unwrapped = services.ok_or(ManagerError::NonExistentValue)?;
                     providers_unwrapped
                         .iter()
-                        .for_each(increment_provider_score);
+                        .for_each(|provider| increment_provider_score(pool, provider));
                 }
             } else {
                 #[cfg(feature = "sepolia")]
@@ -302,7 +579,7 @@ pub fn extract_multi_rpc_send_raw_transaction_status(
                     let providers_unwrapped = services.ok_or(ManagerError::NonExistentValue)?;
                     providers_unwrapped
                         .iter()
-                        .for_each(decrement_provider_score);
+                        .for_each(|provider| decrement_provider_score(pool, provider));
                 }
 
                 #[cfg(feature = "mainnet")]
@@ -310,7 +587,7 @@ pub fn extract_multi_rpc_send_raw_transaction_status(
                     let providers_unwrapped = services.ok_or(ManagerError::NonExistentValue)?;
                     providers_unwrapped
                         .iter()
-                        .for_each(decrement_provider_score);
+                        .for_each(|provider| decrement_provider_score(pool, provider));
                 }
             }
 
@@ -330,21 +607,25 @@ pub fn extract_multi_rpc_send_raw_transaction_status(
             }
 
             responses.iter().for_each(|(provider, result)| {
+                if let RpcService::Custom(api) = provider {
+                    score_custom_provider(pool, &api.url, result.is_ok());
+                }
+
                 #[cfg(feature = "sepolia")]
                 if let evm_rpc_types::RpcService::EthSepolia(eth_sepolia_service) = provider {
                     if result.is_ok() {
-                        increment_provider_score(eth_sepolia_service);
+                        increment_provider_score(pool, eth_sepolia_service);
                     } else {
-                        decrement_provider_score(eth_sepolia_service);
+                        decrement_provider_score(pool, eth_sepolia_service);
                     }
                 }
 
                 #[cfg(feature = "mainnet")]
                 if let evm_rpc_types::RpcService::EthMainnet(eth_mainnet_service) = provider {
                     if result.is_ok() {
-                        increment_provider_score(eth_mainnet_service);
+                        increment_provider_score(pool, eth_mainnet_service);
                     } else {
-                        decrement_provider_score(eth_mainnet_service);
+                        decrement_provider_score(pool, eth_mainnet_service);
                     }
                 }
             });
@@ -352,3 +633,111 @@ pub fn extract_multi_rpc_send_raw_transaction_status(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pools_keep_separate_reputations() {
+        let provider = ProviderPool::Read.members()[0];
+        let read_before = fetch_provider_list(ProviderPool::Read);
+
+        increment_provider_score(ProviderPool::Write, &provider);
+
+        assert_eq!(fetch_provider_list(ProviderPool::Read), read_before);
+        let write_score = fetch_provider_list(ProviderPool::Write)
+            .into_iter()
+            .find(|(_, p)| *p == provider)
+            .map(|(score, _)| score);
+        assert_eq!(write_score, Some(1));
+    }
+
+    #[test]
+    fn lagging_providers_are_detected() {
+        let members = ProviderPool::Read.members();
+        let heights = vec![(members[0], 110), (members[1], 107), (members[2], 106)];
+
+        assert_eq!(lagging_providers(&heights, 3), vec![members[2]]);
+        assert!(lagging_providers(&heights, 4).is_empty());
+        assert!(lagging_providers(&[], 3).is_empty());
+    }
+
+    #[test]
+    fn set_pool_members_resets_reputations() {
+        let members = ProviderPool::Write.members();
+        increment_provider_score(ProviderPool::Write, &members[0]);
+
+        set_pool_members(ProviderPool::Write, members.clone()).unwrap();
+
+        assert!(fetch_provider_list(ProviderPool::Write)
+            .iter()
+            .all(|(score, _)| *score == 0));
+        assert_eq!(ProviderPool::Write.members(), members);
+    }
+
+    #[test]
+    fn set_pool_members_rejects_invalid_lists() {
+        let members = ProviderPool::Read.members();
+
+        let duplicated = vec![members[0], members[1], members[0]];
+        assert!(set_pool_members(ProviderPool::Read, duplicated).is_err());
+
+        let too_short = members[..(PROVIDER_COUNT as usize - 1)].to_vec();
+        assert!(set_pool_members(ProviderPool::Read, too_short).is_err());
+    }
+
+    #[test]
+    fn disabled_provider_leaves_the_rotation() {
+        let provider = ProviderPool::Read.members()[0];
+        ProviderPool::Read.set_reputations(
+            fetch_provider_list(ProviderPool::Read)
+                .into_iter()
+                .map(|(_, member)| (if member == provider { 100 } else { 0 }, member))
+                .collect(),
+        );
+        assert!(ranked_provider_list(ProviderPool::Read).contains(&provider));
+
+        disable_provider(provider, 10).unwrap();
+        assert!(disable_provider(provider, 20).is_err());
+        assert!(!ranked_provider_list(ProviderPool::Read).contains(&provider));
+        assert!(!ranked_provider_list(ProviderPool::Write).contains(&provider));
+        assert_eq!(
+            disabled_providers(),
+            vec![DisabledProvider {
+                provider,
+                disabled_at: 10
+            }]
+        );
+
+        enable_provider(provider).unwrap();
+        assert!(ranked_provider_list(ProviderPool::Read).contains(&provider));
+        assert_eq!(
+            enable_provider(provider),
+            Err(ManagerError::NonExistentValue)
+        );
+    }
+
+    #[test]
+    fn pools_keep_enough_enabled_providers() {
+        let members = fetch_provider_list(ProviderPool::Write);
+        let allowed = members.len() - PROVIDER_COUNT as usize;
+
+        let disabled = members
+            .iter()
+            .filter(|(_, provider)| disable_provider(*provider, 0).is_ok())
+            .count();
+        assert_eq!(disabled, allowed);
+
+        // Quorum reads need `PROVIDER_COUNT` enabled providers
+        let (_, enabled) = members
+            .iter()
+            .find(|(_, provider)| !is_provider_disabled(provider))
+            .unwrap();
+        assert!(disable_provider(*enabled, 0).is_err());
+
+        for (_, provider) in members {
+            let _ = enable_provider(provider);
+        }
+    }
+}
@@ -0,0 +1,206 @@
+//! Rate Adjustment History
+//!
+//! Keeps every confirmed rate change as a structured record, so that analytics do not have to
+//! parse the free-form notes of the journal:
+//!
+//! ```plain
+//! poll_receipts ──► Confirmed { success: true } rate adjustment (run ID set)
+//!                            │
+//!                            ▼
+//!        raw transaction ──► setNewRate(new rate, .., max upfront fee)
+//!                            │
+//!  previous record of the strategy ──► old rate
+//!                            ▼
+//!                      RATE_HISTORY (append-only)
+//!
+//! get_rate_history(key, depth) ──► newest records of the strategy first
+//! ```
+//!
+//! Reverted adjustments did not change the rate and are left out. The old rate of the first
+//! recorded adjustment of a strategy is unknown.
+
+use std::borrow::Cow;
+
+use alloy_primitives::U256;
+use alloy_sol_types::SolCall;
+use candid::{CandidType, Decode, Encode, Nat};
+use ic_stable_structures::{storable::Bound, Storable};
+use serde::Deserialize;
+
+use crate::{
+    constants::MAX_RATE_HISTORY_PER_QUERY,
+    journal::{JournalCollection, LogType},
+    state::RATE_HISTORY,
+    tx_pool::{TxRecord, TxStatus},
+    types::setNewRateCall,
+    utils::{transaction_builder::decode_eip1559_transaction, units::u256_to_nat},
+};
+
+/// A confirmed change of a strategy's interest rate
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq)]
+pub struct RateAdjustmentRecord {
+    /// Key of the strategy that submitted the adjustment
+    pub strategy: u32,
+    /// Rate before the adjustment, `None` for the first recorded adjustment of the strategy
+    pub old_rate: Option<Nat>,
+    /// Rate set by the adjustment
+    pub new_rate: Nat,
+    /// Transaction hash
+    pub tx_hash: String,
+    /// Number of the block that included the transaction
+    pub block_number: u64,
+    /// Maximum upfront fee the adjustment was submitted with
+    pub max_upfront_fee: Nat,
+    /// Timestamp in seconds at which the receipt was found
+    pub timestamp: u64,
+}
+
+impl Storable for RateAdjustmentRecord {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        Cow::Owned(Encode!(self).expect("Failed to encode a rate adjustment record."))
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).expect("Failed to decode a rate adjustment record.")
+    }
+
+    const BOUND: Bound = Bound::Bounded {
+        max_size: 512,
+        is_fixed_size: false,
+    };
+}
+
+impl RateAdjustmentRecord {
+    /// Builds the history record of a transaction, if it is a successful rate adjustment.
+    fn from_transaction(record: &TxRecord, old_rate: Option<Nat>) -> Option<Self> {
+        record.run_id?;
+        let TxStatus::Confirmed {
+            block_number,
+            success: true,
+            ..
+        } = record.status
+        else {
+            return None;
+        };
+        let transaction = decode_eip1559_transaction(&record.raw).ok()?;
+        let call = setNewRateCall::abi_decode(&transaction.input, true).ok()?;
+        Some(Self {
+            strategy: record.strategy,
+            old_rate,
+            new_rate: u256_to_nat(&U256::from(call._newAnnualInterestRate)).ok()?,
+            tx_hash: record.hash.clone(),
+            block_number,
+            max_upfront_fee: u256_to_nat(&call._maxUpfrontFee).ok()?,
+            timestamp: record.updated_at,
+        })
+    }
+}
+
+/// Returns the rate set by the last recorded adjustment of a strategy.
+fn last_rate(strategy: u32) -> Option<Nat> {
+    RATE_HISTORY.with(|history| {
+        let history = history.borrow();
+        (0..history.len())
+            .rev()
+            .filter_map(|index| history.get(index))
+            .find(|record| record.strategy == strategy)
+            .map(|record| record.new_rate)
+    })
+}
+
+/// Appends the transaction to the rate history if it is a successful rate adjustment.
+pub fn record_rate_adjustment(record: &TxRecord) {
+    let Some(adjustment) =
+        RateAdjustmentRecord::from_transaction(record, last_rate(record.strategy))
+    else {
+        return;
+    };
+    if let Err(err) = RATE_HISTORY.with(|history| history.borrow_mut().push(&adjustment)) {
+        JournalCollection::open(Some(record.strategy)).append_note(
+            Ok(()),
+            LogType::Info,
+            format!(
+                "Failed to record the rate adjustment {} in the rate history: {:?}",
+                record.hash, err
+            ),
+        );
+    }
+}
+
+/// Returns up to `depth` of the latest rate adjustments of a strategy, newest first.
+///
+/// The depth is capped at `MAX_RATE_HISTORY_PER_QUERY`.
+pub fn rate_history(strategy: u32, depth: u64) -> Vec<RateAdjustmentRecord> {
+    let depth = depth.min(MAX_RATE_HISTORY_PER_QUERY as u64) as usize;
+    RATE_HISTORY.with(|history| {
+        let history = history.borrow();
+        (0..history.len())
+            .rev()
+            .filter_map(|index| history.get(index))
+            .filter(|record| record.strategy == strategy)
+            .take(depth)
+            .collect()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(strategy: u32, rate: u64) -> RateAdjustmentRecord {
+        RateAdjustmentRecord {
+            strategy,
+            old_rate: None,
+            new_rate: Nat::from(rate),
+            tx_hash: format!("0x{:x}", rate),
+            block_number: rate,
+            max_upfront_fee: Nat::from(0_u8),
+            timestamp: rate,
+        }
+    }
+
+    #[test]
+    fn test_rate_history() {
+        RATE_HISTORY.with(|history| {
+            let history = history.borrow_mut();
+            for adjustment in [record(1, 10), record(2, 20), record(1, 30), record(1, 40)] {
+                history.push(&adjustment).unwrap();
+            }
+        });
+
+        let rates: Vec<Nat> = rate_history(1, 2)
+            .into_iter()
+            .map(|adjustment| adjustment.new_rate)
+            .collect();
+        assert_eq!(rates, vec![Nat::from(40_u8), Nat::from(30_u8)]);
+        assert_eq!(rate_history(2, 10).len(), 1);
+        assert!(rate_history(3, 10).is_empty());
+
+        assert_eq!(last_rate(1), Some(Nat::from(40_u8)));
+        assert_eq!(last_rate(3), None);
+    }
+
+    #[test]
+    fn test_reverted_adjustments_are_skipped() {
+        let record = TxRecord {
+            id: 1,
+            strategy: 1,
+            run_id: Some(2),
+            nonce: 3,
+            hash: "0xabc".to_string(),
+            raw: "0x02f8".to_string(),
+            status: TxStatus::Confirmed {
+                block_number: 10,
+                gas_used: 21_000,
+                effective_gas_price: 1,
+                success: false,
+            },
+            submitted_at: 100,
+            updated_at: 120,
+            rpc_canister: None,
+            submitted_block: None,
+            upfront_fee: None,
+        };
+        assert_eq!(RateAdjustmentRecord::from_transaction(&record, None), None);
+    }
+}
@@ -0,0 +1,131 @@
+//! ETH/CXDR Rate Source
+//!
+//! The ckETH<>Cycles swaps are priced with the ETH/CXDR rate. By default the rate is read
+//! from the exchange rate canister (XRC). Deployments without the XRC, such as testnets,
+//! can run on a fixed rate set by a controller instead.
+//!
+//! ```plain
+//! ether_cycles_rate() ──┬── Xrc ─────────────► exchange rate canister
+//!                       │
+//!                       └── FixedRate ───────► rate ──(older than fixed_rate_max_age)──► WARNING
+//! ```
+//!
+//! A fixed rate does not follow the market, so swaps keep using it when it gets old, but
+//! every swap priced with a stale fixed rate is journaled with a warning.
+
+use candid::CandidType;
+use serde::Deserialize;
+
+use crate::{
+    clock::time,
+    constants::DEFAULT_FIXED_RATE_MAX_AGE,
+    flags::{flag_int, FIXED_RATE_MAX_AGE},
+    journal::{JournalCollection, LogType},
+    state::RATE_SOURCE,
+    utils::{
+        common::fetch_ether_cycles_rate,
+        error::{ManagerError, ManagerResult},
+    },
+};
+
+/// Source of the ETH/CXDR rate used to price swaps
+#[derive(CandidType, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+pub enum RateSource {
+    /// The exchange rate canister
+    #[default]
+    Xrc,
+    /// A rate set by a controller
+    FixedRate {
+        /// ETH/CXDR rate, in the same unit as the rate derived from the XRC
+        rate: u64,
+        /// Timestamp in seconds at which the rate was set
+        set_at: u64,
+    },
+}
+
+impl RateSource {
+    /// Returns the age in seconds of a fixed rate at `now` if it is older than `max_age`.
+    pub fn stale_for(&self, max_age: u64, now: u64) -> Option<u64> {
+        match self {
+            RateSource::Xrc => None,
+            RateSource::FixedRate { set_at, .. } => {
+                let age = now.saturating_sub(*set_at);
+                (age > max_age).then_some(age)
+            }
+        }
+    }
+}
+
+/// Returns the current rate source.
+pub fn rate_source() -> RateSource {
+    RATE_SOURCE.with(|source| *source.borrow())
+}
+
+/// Switches to a fixed rate set at `now` (in seconds), or back to the XRC with `None`.
+pub fn set_fixed_rate(rate: Option<u64>, now: u64) -> ManagerResult<RateSource> {
+    let source = match rate {
+        Some(0) => {
+            return Err(ManagerError::Custom(
+                "The fixed ETH/CXDR rate must be positive.".to_string(),
+            ))
+        }
+        Some(rate) => RateSource::FixedRate { rate, set_at: now },
+        None => RateSource::Xrc,
+    };
+    RATE_SOURCE.with(|current| *current.borrow_mut() = source);
+    Ok(source)
+}
+
+/// Returns the ETH/CXDR rate from the configured source.
+pub async fn ether_cycles_rate() -> ManagerResult<u64> {
+    match rate_source() {
+        RateSource::Xrc => fetch_ether_cycles_rate().await,
+        source @ RateSource::FixedRate { rate, .. } => {
+            let max_age = flag_int!(FIXED_RATE_MAX_AGE, DEFAULT_FIXED_RATE_MAX_AGE).max(0) as u64;
+            if let Some(age) = source.stale_for(max_age, time() / 1_000_000_000) {
+                JournalCollection::open(None).append_note(
+                    Ok(()),
+                    LogType::Info,
+                    format!(
+                        "WARNING: The fixed ETH/CXDR rate {} was set {} seconds ago, more than {} seconds.",
+                        rate, age, max_age
+                    ),
+                );
+            }
+            Ok(rate)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fixed_rate_staleness() {
+        let source = RateSource::FixedRate {
+            rate: 4_000,
+            set_at: 1_000,
+        };
+
+        assert_eq!(source.stale_for(600, 1_600), None);
+        assert_eq!(source.stale_for(600, 1_601), Some(601));
+        assert_eq!(RateSource::Xrc.stale_for(0, u64::MAX), None);
+    }
+
+    #[test]
+    fn test_set_fixed_rate() {
+        assert!(set_fixed_rate(Some(0), 10).is_err());
+        assert_eq!(rate_source(), RateSource::Xrc);
+
+        assert_eq!(
+            set_fixed_rate(Some(4_000), 10),
+            Ok(RateSource::FixedRate {
+                rate: 4_000,
+                set_at: 10
+            })
+        );
+        assert_eq!(set_fixed_rate(None, 20), Ok(RateSource::Xrc));
+        assert_eq!(rate_source(), RateSource::Xrc);
+    }
+}
@@ -0,0 +1,176 @@
+//! Journal Retention Policy
+//!
+//! The daily cleanup trims the oldest journal collections until the journal satisfies the
+//! retention policy. Each rule is optional, and they are applied in order, so that every
+//! removed collection is attributed to the first rule that required its removal:
+//!
+//! ```plain
+//! oldest ─────────────────────────────────────────────────────────────► newest
+//! [ older than max_age_days ][ above max_collections ][ above max_bytes ][ kept ... ]
+//!          by_age                     by_count                by_size
+//! ```
+//!
+//! Deployments can favour deep history by raising the limits, or storage frugality by
+//! lowering them. The policy must bound the journal by count or by size.
+//!
+//! The journal is a ring buffer of `JOURNAL_CAPACITY` collections, which bounds
+//! `max_collections`. The count limit is also enforced on every append, by dropping the
+//! oldest collections; the age and size limits are only enforced by the daily cleanup.
+
+use candid::CandidType;
+use serde::Deserialize;
+
+use crate::{
+    constants::{DEFAULT_JOURNAL_MAX_COLLECTIONS, JOURNAL_CAPACITY},
+    metrics::RetentionCounters,
+    state::JOURNAL_RETENTION,
+    utils::error::{ManagerError, ManagerResult},
+};
+
+/// Limits of the journal enforced during the daily cleanup
+#[derive(CandidType, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct RetentionPolicy {
+    /// Maximum number of collections kept
+    pub max_collections: Option<u64>,
+    /// Maximum age in days of the collections kept
+    pub max_age_days: Option<u64>,
+    /// Maximum estimated size in bytes of the collections kept
+    pub max_bytes: Option<u64>,
+}
+
+impl Default for RetentionPolicy {
+    fn default() -> Self {
+        Self {
+            max_collections: Some(DEFAULT_JOURNAL_MAX_COLLECTIONS),
+            max_age_days: None,
+            max_bytes: None,
+        }
+    }
+}
+
+impl RetentionPolicy {
+    /// Checks that every limit is positive and that the journal is bounded by count or size.
+    pub fn validate(&self) -> ManagerResult<()> {
+        if [self.max_collections, self.max_age_days, self.max_bytes].contains(&Some(0)) {
+            return Err(ManagerError::Custom(
+                "Retention limits must be positive.".to_string(),
+            ));
+        }
+        if self.max_collections > Some(JOURNAL_CAPACITY) {
+            return Err(ManagerError::Custom(format!(
+                "The journal keeps at most {} collections.",
+                JOURNAL_CAPACITY
+            )));
+        }
+        if self.max_collections.is_none() && self.max_bytes.is_none() {
+            return Err(ManagerError::Custom(
+                "The retention policy must limit the number of collections or their size."
+                    .to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Returns the number of collections to remove from the start of the journal, by rule.
+    ///
+    /// `collections` holds the creation timestamp in seconds (if known) and the estimated size
+    /// in bytes of each collection, oldest first.
+    pub fn excess(&self, collections: &[(Option<u64>, u64)], now: u64) -> RetentionCounters {
+        let len = collections.len() as u64;
+        let mut removed = RetentionCounters::default();
+
+        if let Some(days) = self.max_age_days {
+            let max_age = days.saturating_mul(86_400);
+            removed.by_age = collections
+                .iter()
+                .take_while(|(created_at, _)| {
+                    created_at.map_or(false, |created_at| now.saturating_sub(created_at) > max_age)
+                })
+                .count() as u64;
+        }
+
+        if let Some(max_collections) = self.max_collections {
+            removed.by_count = (len - removed.by_age).saturating_sub(max_collections);
+        }
+
+        if let Some(max_bytes) = self.max_bytes {
+            let start = (removed.by_age + removed.by_count) as usize;
+            let mut total: u64 = collections[start..].iter().map(|(_, size)| size).sum();
+            for (_, size) in &collections[start..] {
+                if total <= max_bytes {
+                    break;
+                }
+                total -= size;
+                removed.by_size += 1;
+            }
+        }
+
+        removed
+    }
+}
+
+/// Returns the current journal retention policy.
+pub fn journal_retention() -> RetentionPolicy {
+    JOURNAL_RETENTION.with(|policy| *policy.borrow())
+}
+
+/// Validates and replaces the journal retention policy.
+pub fn set_journal_retention(policy: RetentionPolicy) -> ManagerResult<()> {
+    policy.validate()?;
+    JOURNAL_RETENTION.with(|current| *current.borrow_mut() = policy);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retention_validation() {
+        assert!(RetentionPolicy::default().validate().is_ok());
+
+        let zero = RetentionPolicy {
+            max_bytes: Some(0),
+            ..RetentionPolicy::default()
+        };
+        assert!(zero.validate().is_err());
+
+        let above_capacity = RetentionPolicy {
+            max_collections: Some(JOURNAL_CAPACITY + 1),
+            ..RetentionPolicy::default()
+        };
+        assert!(above_capacity.validate().is_err());
+
+        let unbounded = RetentionPolicy {
+            max_collections: None,
+            max_age_days: Some(30),
+            max_bytes: None,
+        };
+        assert!(unbounded.validate().is_err());
+    }
+
+    #[test]
+    fn test_excess_attributes_each_removal_to_one_rule() {
+        let day = 86_400;
+        let now = 10 * day;
+        let collections = [
+            (Some(0), 100),
+            (Some(day), 100),
+            (None, 100),
+            (Some(8 * day), 100),
+            (Some(9 * day), 400),
+            (Some(9 * day), 300),
+        ];
+        let policy = RetentionPolicy {
+            max_collections: Some(3),
+            max_age_days: Some(5),
+            max_bytes: Some(700),
+        };
+
+        let removed = policy.excess(&collections, now);
+        assert_eq!(removed.by_age, 2);
+        assert_eq!(removed.by_count, 1);
+        assert_eq!(removed.by_size, 1);
+        assert_eq!(removed.trimmed(), 4);
+    }
+}
@@ -0,0 +1,164 @@
+//! EVM RPC Canister Provenance
+//!
+//! Keeps a permanent, append-only record of the EVM RPC canisters used by the strategies.
+//! Every strategy is minted with the principal of an EVM RPC canister, and a controller can
+//! register a successor that takes over for all strategies from an effective-from time:
+//!
+//! ```plain
+//! time ──────────────┬─────────────────────────┬──────────────────────────►
+//!   minted principal │ successor A             │ successor B
+//!                    effective_from(A)         effective_from(B)
+//! ```
+//!
+//! Every submitted transaction records the principal it was sent through, so that historical
+//! misbehavior can be attributed to a specific EVM RPC canister. The module hash and version
+//! are provided by the controller, as the canister is not a controller of the EVM RPC canister
+//! and cannot read them itself.
+
+use std::borrow::Cow;
+
+use candid::{CandidType, Decode, Encode, Principal};
+use ic_stable_structures::{storable::Bound, Storable};
+use serde::Deserialize;
+
+use crate::{
+    state::RPC_CANISTERS,
+    utils::{
+        error::{ManagerError, ManagerResult},
+        evm_rpc::Service,
+    },
+};
+
+/// A registered EVM RPC canister
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq)]
+pub struct RpcCanisterRecord {
+    /// Principal of the EVM RPC canister
+    pub principal: Principal,
+    /// Timestamp in seconds from which the canister is used by all strategies
+    pub effective_from: u64,
+    /// Hex-encoded module hash of the canister, as provided by the controller
+    pub module_hash: Option<String>,
+    /// Release version of the canister, as provided by the controller
+    pub version: Option<String>,
+    /// Timestamp in seconds at which the canister was registered
+    pub registered_at: u64,
+}
+
+impl Storable for RpcCanisterRecord {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        Cow::Owned(Encode!(self).expect("Failed to encode an EVM RPC canister record."))
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).expect("Failed to decode an EVM RPC canister record.")
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+impl RpcCanisterRecord {
+    /// Checks that the record can be appended after `latest` at `now` (in seconds).
+    fn validate(&self, latest: Option<&RpcCanisterRecord>, now: u64) -> ManagerResult<()> {
+        if self.effective_from < now {
+            return Err(ManagerError::Custom(
+                "The effective-from time of an EVM RPC canister cannot be in the past.".to_string(),
+            ));
+        }
+        if let Some(latest) = latest {
+            if self.effective_from <= latest.effective_from {
+                return Err(ManagerError::Custom(format!(
+                    "The effective-from time must be after the one of the latest registered EVM RPC canister ({}).",
+                    latest.effective_from
+                )));
+            }
+        }
+        if let Some(hash) = &self.module_hash {
+            let digits = hash.strip_prefix("0x").unwrap_or(hash);
+            if digits.len() != 64 || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+                return Err(ManagerError::Custom(
+                    "The module hash must have exactly 64 hexadecimal digits.".to_string(),
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Registers an EVM RPC canister that replaces the previous one from `record.effective_from`.
+///
+/// The registry is append-only: the effective-from time must be in the future of `now`
+/// (in seconds) and after the one of every registered canister.
+pub fn register_rpc_canister(record: RpcCanisterRecord, now: u64) -> ManagerResult<()> {
+    RPC_CANISTERS.with(|registry| {
+        let mut registry = registry.borrow_mut();
+        let latest = registry.last_key_value().map(|(_, record)| record);
+        record.validate(latest.as_ref(), now)?;
+        registry.insert(record.effective_from, record);
+        Ok(())
+    })
+}
+
+/// Returns the registered EVM RPC canister in effect at `now` (in seconds), if any.
+pub fn effective_rpc_canister(now: u64) -> Option<RpcCanisterRecord> {
+    RPC_CANISTERS.with(|registry| {
+        registry
+            .borrow()
+            .range(..=now)
+            .last()
+            .map(|(_, record)| record)
+    })
+}
+
+/// Returns the EVM RPC canister to call at `now` (in seconds) in place of the `minted` one.
+///
+/// The chain of the `minted` service is kept.
+pub fn resolve_rpc_canister(minted: &Service, now: u64) -> Service {
+    effective_rpc_canister(now).map_or(*minted, |record| Service(record.principal, minted.1))
+}
+
+/// Returns every registered EVM RPC canister, oldest first.
+pub fn rpc_canisters() -> Vec<RpcCanisterRecord> {
+    RPC_CANISTERS.with(|registry| registry.borrow().iter().map(|(_, record)| record).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(effective_from: u64) -> RpcCanisterRecord {
+        RpcCanisterRecord {
+            principal: Principal::from_slice(&effective_from.to_be_bytes()),
+            effective_from,
+            module_hash: None,
+            version: Some("2.0.0".to_string()),
+            registered_at: 100,
+        }
+    }
+
+    #[test]
+    fn test_successors_take_over_from_their_effective_time() {
+        let minted = Service(Principal::anonymous(), 42161);
+        assert_eq!(resolve_rpc_canister(&minted, 100).0, minted.0);
+
+        assert!(register_rpc_canister(record(200), 100).is_ok());
+        assert!(register_rpc_canister(record(300), 100).is_ok());
+        assert!(register_rpc_canister(record(250), 100).is_err());
+        assert!(register_rpc_canister(record(50), 100).is_err());
+
+        let invalid_hash = RpcCanisterRecord {
+            module_hash: Some("0x1234".to_string()),
+            ..record(400)
+        };
+        assert!(register_rpc_canister(invalid_hash, 100).is_err());
+
+        assert_eq!(resolve_rpc_canister(&minted, 199).0, minted.0);
+        assert_eq!(resolve_rpc_canister(&minted, 200).0, record(200).principal);
+        assert_eq!(
+            resolve_rpc_canister(&minted, 1_000).0,
+            record(300).principal
+        );
+        // Successors serve the chain of the minted canister
+        assert_eq!(resolve_rpc_canister(&minted, 1_000).1, 42161);
+        assert_eq!(rpc_canisters().len(), 2);
+    }
+}
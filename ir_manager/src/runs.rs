@@ -0,0 +1,454 @@
+//! Strategy Run Tracking
+//!
+//! Every strategy execution is assigned a unique, monotonically increasing run ID from a
+//! stable counter. The run ID is attached to the journal collection of the run, to the
+//! transactions submitted during the run, and to a compact run summary stored in stable memory.
+//!
+//! ```plain
+//! Run Lifecycle:
+//!
+//! start_run() ──► RunSummary { finished_at: None } ──► finish_run() ──► RunSummary { result }
+//!      │
+//!      └──► run_id ──► JournalCollection, TxRecord, Metrics
+//! ```
+//!
+//! Unlike start/end timestamps, run IDs allow reliable correlation when runs overlap.
+//!
+//! The summary also keeps the inputs of the run's target percentage derivation, so that
+//! integrators can verify the fixed-point arithmetic against their own models:
+//!
+//! ```plain
+//! numerator   = target_min * 2 * redemption_fee
+//! denominator = redemption_fee + 5e15
+//! target      = numerator / denominator
+//! ```
+//!
+//! The maximum debt redeemable against the branch's collateral is decomposed as well, as the
+//! 1% floor materially changes the targeting of small branches:
+//!
+//! ```plain
+//! share        = unbacked_portion * 100 / total_unbacked               (whole percent)
+//! proportional = unbacked_portion * entire_system_debt / total_unbacked
+//! floor        = entire_system_debt / 100
+//! maximum      = share < 1 ? floor : proportional
+//! ```
+//!
+//! Once a rate adjustment is observed on-chain, the debt in front the engine expected is
+//! compared with the realized one and the delta is stored on the run that submitted it.
+//! Persistent large deltas point at formula or market data quality issues.
+
+use std::borrow::Cow;
+
+use candid::{CandidType, Decode, Encode, Int, Nat};
+use ic_stable_structures::{storable::Bound, Storable};
+use serde::Deserialize;
+
+use crate::{
+    clock::time,
+    digest::alert_on_failure_streak,
+    journal::JournalCollectionQuery,
+    metrics::{record_run_finished, record_run_started},
+    state::{JOURNAL, RUNS, RUN_COUNTER},
+    tx_pool::{transactions_for_run, TxRecord},
+    utils::error::ManagerResult,
+};
+
+/// Maximum number of run summaries kept in stable memory
+const MAX_RUN_SUMMARIES: u64 = 5_000;
+
+/// Compact summary of a single strategy execution
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct RunSummary {
+    /// Unique run ID
+    pub run_id: u64,
+    /// Key of the executed strategy
+    pub strategy: u32,
+    /// Start timestamp in seconds
+    pub started_at: u64,
+    /// End timestamp in seconds, `None` while the run is in progress
+    pub finished_at: Option<u64>,
+    /// Number of execution attempts made during the run
+    pub attempts: u8,
+    /// Result of the last attempt, `None` while the run is in progress
+    pub result: Option<ManagerResult<()>>,
+    /// Target percentage derivation of the last attempt that got that far
+    pub target_derivation: Option<TargetDerivation>,
+    /// Comparison of the debt in front expected by the run's rate adjustment with the realized one
+    pub positioning_check: Option<PositioningCheck>,
+}
+
+/// Expected and realized debt in front of a rate adjustment (scaled by 1e18)
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq)]
+pub struct PositioningCheck {
+    /// Rate the batch was adjusted to
+    pub rate: Nat,
+    /// Debt in front the strategy was targeting
+    pub target_debt_in_front: Nat,
+    /// Debt in front at the new rate, had the market stayed unchanged
+    pub expected_debt_in_front: Nat,
+    /// Debt in front observed once the new rate was applied on-chain
+    pub realized_debt_in_front: Nat,
+    /// `realized_debt_in_front - expected_debt_in_front`
+    pub delta: Int,
+    /// ID of the run that observed the realized debt in front
+    pub verified_in_run: Option<u64>,
+    /// Timestamp in seconds of the verification
+    pub verified_at: u64,
+}
+
+/// Inputs and intermediate values of the target percentage derivation (scaled by 1e18)
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq)]
+pub struct TargetDerivation {
+    /// Number of the block the inputs were read at
+    pub block_number: Option<Nat>,
+    /// Redemption fee rate of the collateral registry
+    pub redemption_fee: Nat,
+    /// `target_min * 2 * redemption_fee`
+    pub target_percentage_numerator: Nat,
+    /// `redemption_fee + 5e15`
+    pub target_percentage_denominator: Nat,
+    /// `numerator / denominator`
+    pub target_percentage: Nat,
+    /// Unbacked portion of the strategy's branch
+    pub unbacked_portion: Nat,
+    /// Unbacked portion of all branches
+    pub total_unbacked: Nat,
+    /// Debt of the entire system
+    pub entire_system_debt: Nat,
+    /// `true` if the branch's share of the unbacked portion was below 1% and was floored at 1%
+    pub one_percent_floor: bool,
+    /// Maximum debt redeemable against the branch's collateral
+    pub maximum_redeemable_against_collateral: Nat,
+}
+
+/// Components of the maximum debt redeemable against a branch's collateral (scaled by 1e18)
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq)]
+pub struct RedeemabilityDecomposition {
+    /// ID of the run that computed the value
+    pub run_id: u64,
+    /// Number of the block the inputs were read at
+    pub block_number: Option<Nat>,
+    /// Unbacked portion of the strategy's branch
+    pub unbacked_portion: Nat,
+    /// Unbacked portion of all branches
+    pub total_unbacked: Nat,
+    /// Debt of the entire system
+    pub entire_system_debt: Nat,
+    /// `unbacked_portion * 100 / total_unbacked`, the branch's share in whole percent
+    pub unbacked_share_percent: Nat,
+    /// `unbacked_portion * entire_system_debt / total_unbacked`
+    pub proportional_redeemable: Nat,
+    /// `entire_system_debt / 100`
+    pub floor_redeemable: Nat,
+    /// `true` if the share was below 1% and the floor was applied
+    pub one_percent_floor: bool,
+    /// Maximum debt redeemable against the branch's collateral used by the run
+    pub maximum_redeemable_against_collateral: Nat,
+}
+
+impl TargetDerivation {
+    /// Decomposes the maximum redeemable debt computed by the run `run_id`.
+    pub fn redeemability(&self, run_id: u64) -> RedeemabilityDecomposition {
+        let total_unbacked = &self.total_unbacked.0;
+        let share_of = |amount: &Nat| {
+            if *total_unbacked == 0_u8.into() {
+                Nat::from(0_u8)
+            } else {
+                Nat(&self.unbacked_portion.0 * &amount.0 / total_unbacked)
+            }
+        };
+
+        RedeemabilityDecomposition {
+            run_id,
+            block_number: self.block_number.clone(),
+            unbacked_portion: self.unbacked_portion.clone(),
+            total_unbacked: self.total_unbacked.clone(),
+            entire_system_debt: self.entire_system_debt.clone(),
+            unbacked_share_percent: share_of(&Nat::from(100_u8)),
+            proportional_redeemable: share_of(&self.entire_system_debt),
+            floor_redeemable: Nat(&self.entire_system_debt.0 / 100_u8),
+            one_percent_floor: self.one_percent_floor,
+            maximum_redeemable_against_collateral: self
+                .maximum_redeemable_against_collateral
+                .clone(),
+        }
+    }
+}
+
+impl Storable for RunSummary {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        Cow::Owned(Encode!(self).expect("Failed to encode a run summary."))
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).expect("Failed to decode a run summary.")
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+/// Everything recorded about a single run
+#[derive(CandidType, Clone)]
+pub struct RunReport {
+    /// Summary of the run
+    pub summary: RunSummary,
+    /// Transactions submitted during the run
+    pub transactions: Vec<TxRecord>,
+    /// Journal collections of the run
+    pub logs: Vec<JournalCollectionQuery>,
+}
+
+/// Allocates a new run ID for the given strategy and records the start of the run.
+pub fn start_run(strategy: u32) -> u64 {
+    let run_id = RUN_COUNTER.with(|counter| {
+        let mut counter = counter.borrow_mut();
+        let run_id = counter.get().saturating_add(1);
+        counter
+            .set(run_id)
+            .expect("Failed to persist the run counter.");
+        run_id
+    });
+
+    let summary = RunSummary {
+        run_id,
+        strategy,
+        started_at: time() / 1_000_000_000,
+        finished_at: None,
+        attempts: 0,
+        result: None,
+        target_derivation: None,
+        positioning_check: None,
+    };
+    RUNS.with(|runs| runs.borrow_mut().insert(run_id, summary));
+    record_run_started(run_id);
+
+    run_id
+}
+
+/// Records the end of a run with the number of attempts and the result of the last attempt.
+///
+/// A failed run that extends the failure streak of its strategy to `failure_alert_threshold`
+/// raises an alert.
+pub fn finish_run(run_id: u64, attempts: u8, result: ManagerResult<()>) {
+    record_run_finished(result.is_ok());
+    let failed = result.is_err();
+    let strategy = RUNS.with(|runs| {
+        let mut runs = runs.borrow_mut();
+        let mut summary = runs.get(&run_id)?;
+        summary.finished_at = Some(time() / 1_000_000_000);
+        summary.attempts = attempts;
+        summary.result = Some(result);
+        let strategy = summary.strategy;
+        runs.insert(run_id, summary);
+        Some(strategy)
+    });
+    if let Some(strategy) = strategy.filter(|_| failed) {
+        alert_on_failure_streak(strategy, consecutive_failures(strategy));
+    }
+}
+
+/// Returns the number of finished runs of a strategy that failed since its last successful run.
+pub fn consecutive_failures(strategy: u32) -> u64 {
+    RUNS.with(|runs| {
+        runs.borrow()
+            .iter()
+            .rev()
+            .filter(|(_, summary)| summary.strategy == strategy)
+            .filter_map(|(_, summary)| summary.result)
+            .take_while(|result| result.is_err())
+            .count() as u64
+    })
+}
+
+/// Records the target percentage derivation of a run, replacing the one of an earlier attempt.
+pub fn record_target_derivation(run_id: u64, derivation: TargetDerivation) {
+    RUNS.with(|runs| {
+        let mut runs = runs.borrow_mut();
+        if let Some(mut summary) = runs.get(&run_id) {
+            summary.target_derivation = Some(derivation);
+            runs.insert(run_id, summary);
+        }
+    });
+}
+
+/// Returns up to `limit` of the most recent target percentage derivations of a strategy, newest first.
+pub fn target_derivations(strategy: u32, limit: u64) -> Vec<(u64, TargetDerivation)> {
+    RUNS.with(|runs| {
+        runs.borrow()
+            .iter()
+            .rev()
+            .filter(|(_, summary)| summary.strategy == strategy)
+            .filter_map(|(run_id, summary)| {
+                summary
+                    .target_derivation
+                    .map(|derivation| (run_id, derivation))
+            })
+            .take(limit as usize)
+            .collect()
+    })
+}
+
+/// Returns up to `limit` of the most recent redeemability decompositions of a strategy, newest first.
+pub fn redeemability(strategy: u32, limit: u64) -> Vec<RedeemabilityDecomposition> {
+    target_derivations(strategy, limit)
+        .iter()
+        .map(|(run_id, derivation)| derivation.redeemability(*run_id))
+        .collect()
+}
+
+/// Records the positioning check of the rate adjustment submitted by a run.
+pub fn record_positioning_check(run_id: u64, check: PositioningCheck) {
+    RUNS.with(|runs| {
+        let mut runs = runs.borrow_mut();
+        if let Some(mut summary) = runs.get(&run_id) {
+            summary.positioning_check = Some(check);
+            runs.insert(run_id, summary);
+        }
+    });
+}
+
+/// Returns up to `limit` of the most recent positioning checks of a strategy, newest first.
+pub fn positioning_checks(strategy: u32, limit: u64) -> Vec<(u64, PositioningCheck)> {
+    RUNS.with(|runs| {
+        runs.borrow()
+            .iter()
+            .rev()
+            .filter(|(_, summary)| summary.strategy == strategy)
+            .filter_map(|(run_id, summary)| summary.positioning_check.map(|check| (run_id, check)))
+            .take(limit as usize)
+            .collect()
+    })
+}
+
+/// Returns the summary, transactions, and journal collections of a run.
+pub fn get_run(run_id: u64) -> Option<RunReport> {
+    let summary = RUNS.with(|runs| runs.borrow().get(&run_id))?;
+    let logs = JOURNAL.with(|journal| {
+        journal
+            .borrow()
+            .iter()
+            .filter(|collection| collection.run_id == Some(run_id))
+            .map(JournalCollectionQuery::from)
+            .collect()
+    });
+
+    Some(RunReport {
+        summary,
+        transactions: transactions_for_run(run_id),
+        logs,
+    })
+}
+
+/// Removes the oldest run summaries above `MAX_RUN_SUMMARIES`.
+pub fn runs_cleanup() {
+    RUNS.with(|runs| {
+        let mut runs = runs.borrow_mut();
+        let excess = runs.len().saturating_sub(MAX_RUN_SUMMARIES);
+        let oldest: Vec<u64> = runs
+            .iter()
+            .take(excess as usize)
+            .map(|(id, _)| id)
+            .collect();
+        for run_id in oldest {
+            runs.remove(&run_id);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::error::ManagerError;
+
+    #[test]
+    fn test_storable_roundtrip() {
+        let summary = RunSummary {
+            run_id: 12,
+            strategy: 3,
+            started_at: 1_700_000_000,
+            finished_at: Some(1_700_000_060),
+            attempts: 2,
+            result: Some(Err(ManagerError::Locked)),
+            target_derivation: Some(TargetDerivation {
+                block_number: Some(Nat::from(21_000_000_u64)),
+                redemption_fee: Nat::from(5_000_000_000_000_000_u64),
+                target_percentage_numerator: Nat::from(10_u64),
+                target_percentage_denominator: Nat::from(10_000_000_000_000_000_u64),
+                target_percentage: Nat::from(0_u64),
+                unbacked_portion: Nat::from(1_u64),
+                total_unbacked: Nat::from(1_000_u64),
+                entire_system_debt: Nat::from(1_000_000_u64),
+                one_percent_floor: true,
+                maximum_redeemable_against_collateral: Nat::from(10_000_u64),
+            }),
+            positioning_check: Some(PositioningCheck {
+                rate: Nat::from(50_000_000_000_000_000_u64),
+                target_debt_in_front: Nat::from(1_000_u64),
+                expected_debt_in_front: Nat::from(1_020_u64),
+                realized_debt_in_front: Nat::from(990_u64),
+                delta: Int::from(-30_i64),
+                verified_in_run: Some(13),
+                verified_at: 1_700_003_600,
+            }),
+        };
+
+        let decoded = RunSummary::from_bytes(summary.to_bytes());
+
+        assert_eq!(decoded.run_id, summary.run_id);
+        assert_eq!(decoded.strategy, summary.strategy);
+        assert_eq!(decoded.finished_at, summary.finished_at);
+        assert_eq!(decoded.attempts, summary.attempts);
+        assert_eq!(decoded.result, summary.result);
+        assert_eq!(decoded.target_derivation, summary.target_derivation);
+        assert_eq!(decoded.positioning_check, summary.positioning_check);
+    }
+
+    #[test]
+    fn test_consecutive_failures() {
+        crate::clock::set_time(1_700_000_000);
+        for result in [Err(ManagerError::Locked), Ok(()), Err(ManagerError::Locked)] {
+            finish_run(start_run(1), 1, result);
+        }
+        assert_eq!(consecutive_failures(1), 1);
+        assert_eq!(consecutive_failures(2), 0);
+
+        // Runs in progress and runs of other strategies do not break the streak
+        let run_id = start_run(1);
+        start_run(2);
+        finish_run(run_id, 1, Err(ManagerError::Locked));
+        assert_eq!(consecutive_failures(1), 2);
+    }
+
+    #[test]
+    fn test_redeemability_decomposition() {
+        let mut derivation = TargetDerivation {
+            block_number: None,
+            redemption_fee: Nat::from(0_u64),
+            target_percentage_numerator: Nat::from(0_u64),
+            target_percentage_denominator: Nat::from(1_u64),
+            target_percentage: Nat::from(0_u64),
+            unbacked_portion: Nat::from(5_u64),
+            total_unbacked: Nat::from(1_000_u64),
+            entire_system_debt: Nat::from(1_000_000_u64),
+            one_percent_floor: true,
+            maximum_redeemable_against_collateral: Nat::from(10_000_u64),
+        };
+
+        let floored = derivation.redeemability(7);
+        assert_eq!(floored.run_id, 7);
+        assert_eq!(floored.unbacked_share_percent, Nat::from(0_u64));
+        assert_eq!(floored.proportional_redeemable, Nat::from(5_000_u64));
+        assert_eq!(floored.floor_redeemable, Nat::from(10_000_u64));
+        assert!(floored.one_percent_floor);
+
+        derivation.unbacked_portion = Nat::from(250_u64);
+        derivation.one_percent_floor = false;
+        derivation.maximum_redeemable_against_collateral = Nat::from(250_000_u64);
+        let proportional = derivation.redeemability(8);
+        assert_eq!(proportional.unbacked_share_percent, Nat::from(25_u64));
+        assert_eq!(
+            proportional.proportional_redeemable,
+            proportional.maximum_redeemable_against_collateral
+        );
+    }
+}
@@ -0,0 +1,474 @@
+//! Strategy Scheduling
+//!
+//! Strategies are either executed by the canister's own hourly timers, or by external keepers
+//! holding an execution permit. Signing and rate decisioning always stay inside the canister;
+//! a keeper can only decide *when* a strategy runs.
+//!
+//! ```plain
+//! Scheduling Modes:
+//!
+//! Timers:    set_timer_interval ──────────────────────────────► run_strategy(key)
+//!
+//! External:  keeper ──► execute_strategy(key) ──► Permit? ──► Rate limit? ──► run_strategy(key)
+//!                                                   │             │
+//!                                                   ▼             ▼
+//!                                              Unauthorized    Too early
+//! ```
+//!
+//! A permit is bound to the keeper's principal, which is authenticated by the signature
+//! of its ingress message or by the calling canister's identity.
+//!
+//! In `Timers` mode, latency-sensitive strategies can be triggered by new blocks instead of
+//! the hourly timer. A poller reads the chain head from a single provider every
+//! `CHAIN_HEAD_POLL_INTERVAL` seconds (per chain), and executes a strategy once enough blocks elapsed
+//! since its last triggered execution, at most once per `min_interval` seconds:
+//!
+//! ```plain
+//! poll_chain_head ──► head - last_block >= blocks? ──► now - last_run >= min_interval? ──► run_strategy(key)
+//! ```
+
+use std::collections::BTreeMap;
+
+use candid::{CandidType, Principal};
+use ic_exports::ic_cdk::spawn;
+use serde::Deserialize;
+
+use crate::{
+    clock::time,
+    constants::CHAIN_HEAD_POLL_INTERVAL,
+    guard::ensure_functional,
+    journal::{JournalCollection, LogType},
+    providers::ProviderPool,
+    state::{
+        BLOCK_TRIGGERS, EXECUTION_PERMITS, FAILED_HEAD_POLLS, LAST_PERMITTED_RUNS, SCHEDULING_MODE,
+        STRATEGY_STATE,
+    },
+    strategy::run::run_strategy,
+    upgrade::ensure_not_paused,
+    utils::{
+        common::{get_block, nat_to_u128},
+        error::{ManagerError, ManagerResult},
+        evm_rpc::Service,
+    },
+};
+
+/// Who triggers strategy executions
+#[derive(CandidType, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+pub enum SchedulingMode {
+    /// The canister's own hourly timers
+    #[default]
+    Timers,
+    /// External keepers holding an execution permit
+    External,
+}
+
+/// Permission for a keeper to trigger strategy executions
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq)]
+pub struct ExecutionPermit {
+    /// Strategy keys the keeper may execute, `None` for all strategies
+    pub keys: Option<Vec<u32>>,
+    /// Minimum number of seconds between two permitted executions of the same strategy
+    pub min_interval: u64,
+}
+
+impl ExecutionPermit {
+    /// Returns `true` if the permit covers the given strategy.
+    pub fn covers(&self, key: u32) -> bool {
+        self.keys.as_ref().map_or(true, |keys| keys.contains(&key))
+    }
+}
+
+/// What triggers the executions of a strategy in `Timers` scheduling mode
+#[derive(CandidType, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+pub enum ExecutionTrigger {
+    /// The hourly timer
+    #[default]
+    Hourly,
+    /// New blocks observed by the chain head poller
+    NewBlocks {
+        /// Number of new blocks since the last triggered execution that trigger the next one
+        blocks: u64,
+        /// Minimum number of seconds between two triggered executions, bounding the cycles cost
+        min_interval: u64,
+    },
+}
+
+impl ExecutionTrigger {
+    /// Checks that a new-block trigger fires on at least one block and is rate-limited to
+    /// at most one execution per poll.
+    pub fn validate(&self) -> ManagerResult<()> {
+        if let ExecutionTrigger::NewBlocks {
+            blocks,
+            min_interval,
+        } = self
+        {
+            if *blocks == 0 {
+                return Err(ManagerError::Custom(
+                    "A new-block trigger must wait for at least one block.".to_string(),
+                ));
+            }
+            if *min_interval < CHAIN_HEAD_POLL_INTERVAL {
+                return Err(ManagerError::Custom(format!(
+                    "The minimum interval of a new-block trigger must be at least {} seconds.",
+                    CHAIN_HEAD_POLL_INTERVAL
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Chain head and time of the last execution triggered by new blocks
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BlockTriggerState {
+    /// Chain head when the strategy was last triggered
+    pub last_block: u64,
+    /// Timestamp in seconds of the last triggered execution
+    pub last_triggered_at: u64,
+}
+
+/// Returns the current scheduling mode.
+pub fn scheduling_mode() -> SchedulingMode {
+    SCHEDULING_MODE.with(|mode| *mode.borrow())
+}
+
+/// Sets the scheduling mode.
+pub fn set_scheduling_mode(mode: SchedulingMode) {
+    SCHEDULING_MODE.with(|current| *current.borrow_mut() = mode);
+}
+
+/// Grants or replaces the execution permit of a keeper.
+pub fn grant_permit(keeper: Principal, permit: ExecutionPermit) {
+    EXECUTION_PERMITS.with(|permits| permits.borrow_mut().insert(keeper, permit));
+}
+
+/// Revokes the execution permit of a keeper.
+pub fn revoke_permit(keeper: Principal) -> ManagerResult<()> {
+    EXECUTION_PERMITS
+        .with(|permits| permits.borrow_mut().remove(&keeper))
+        .map(|_| ())
+        .ok_or(ManagerError::NonExistentValue)
+}
+
+/// Returns all keepers with their execution permits.
+pub fn list_permits() -> Vec<(Principal, ExecutionPermit)> {
+    EXECUTION_PERMITS.with(|permits| {
+        permits
+            .borrow()
+            .iter()
+            .map(|(keeper, permit)| (*keeper, permit.clone()))
+            .collect()
+    })
+}
+
+/// Checks that `keeper` may execute the strategy `key` at `now` (in seconds), and records the execution.
+///
+/// # Errors
+/// - `ManagerError::Custom` if the canister is not in external scheduling mode
+/// - `ManagerError::Unauthorized` if the keeper has no permit covering the strategy
+/// - `ManagerError::Custom` if the strategy was executed less than `min_interval` seconds ago
+pub fn consume_permit(keeper: Principal, key: u32, now: u64) -> ManagerResult<()> {
+    if scheduling_mode() != SchedulingMode::External {
+        return Err(ManagerError::Custom(
+            "Strategies are scheduled by the canister's timers.".to_string(),
+        ));
+    }
+
+    let permit = EXECUTION_PERMITS
+        .with(|permits| permits.borrow().get(&keeper).cloned())
+        .filter(|permit| permit.covers(key))
+        .ok_or(ManagerError::Unauthorized)?;
+
+    LAST_PERMITTED_RUNS.with(|runs| {
+        let mut runs = runs.borrow_mut();
+        if let Some(last_run) = runs.get(&key) {
+            let next_allowed = last_run.saturating_add(permit.min_interval);
+            if now < next_allowed {
+                return Err(ManagerError::Custom(format!(
+                    "Strategy {} cannot be executed again before {}.",
+                    key, next_allowed
+                )));
+            }
+        }
+        runs.insert(key, now);
+        Ok(())
+    })
+}
+
+/// Same as `consume_permit`, using the current time.
+pub fn consume_permit_now(keeper: Principal, key: u32) -> ManagerResult<()> {
+    consume_permit(keeper, key, time() / 1_000_000_000)
+}
+
+/// Returns the execution trigger of a strategy, `Hourly` for unknown strategies.
+pub fn execution_trigger(key: u32) -> ExecutionTrigger {
+    STRATEGY_STATE.with(|state| {
+        state
+            .borrow()
+            .get(&key)
+            .map(|strategy| strategy.settings.execution_trigger)
+            .unwrap_or_default()
+    })
+}
+
+/// Returns `true` if the strategy `key` is due for an execution at chain head `head` and
+/// `now` (in seconds), and records the execution.
+///
+/// The first observation of a strategy only records the chain head.
+pub fn consume_block_trigger(
+    key: u32,
+    blocks: u64,
+    min_interval: u64,
+    head: u64,
+    now: u64,
+) -> bool {
+    BLOCK_TRIGGERS.with(|triggers| {
+        let mut triggers = triggers.borrow_mut();
+        let Some(state) = triggers.get(&key).copied() else {
+            triggers.insert(
+                key,
+                BlockTriggerState {
+                    last_block: head,
+                    last_triggered_at: now,
+                },
+            );
+            return false;
+        };
+
+        let due = head.saturating_sub(state.last_block) >= blocks
+            && now.saturating_sub(state.last_triggered_at) >= min_interval;
+        if due {
+            triggers.insert(
+                key,
+                BlockTriggerState {
+                    last_block: head,
+                    last_triggered_at: now,
+                },
+            );
+        }
+        due
+    })
+}
+
+/// Records the outcome of a head poll of a chain, and returns the number of failed polls in a
+/// row before it, so that only the first failure and the recovery are journaled.
+fn record_head_poll(chain_id: u64, succeeded: bool) -> u64 {
+    FAILED_HEAD_POLLS.with(|failures| {
+        let mut failures = failures.borrow_mut();
+        if succeeded {
+            failures.remove(&chain_id).unwrap_or_default()
+        } else {
+            let streak = failures.entry(chain_id).or_default();
+            *streak += 1;
+            *streak - 1
+        }
+    })
+}
+
+/// Reads the chain heads and executes the strategies whose new-block trigger is due.
+///
+/// The head of a chain is only read if at least one strategy on it is triggered by new blocks.
+/// A provider outage polls every `CHAIN_HEAD_POLL_INTERVAL`, so only its first failed poll and
+/// the recovery are journaled.
+pub async fn poll_chain_head() {
+    if scheduling_mode() != SchedulingMode::Timers
+        || ensure_functional().is_err()
+        || ensure_not_paused().is_err()
+    {
+        return;
+    }
+
+    // Block heights are only comparable within a chain
+    let mut triggered: BTreeMap<u64, (Service, Vec<(u32, u64, u64)>)> = BTreeMap::new();
+    STRATEGY_STATE.with(|state| {
+        for (key, strategy) in state.borrow().iter() {
+            if let ExecutionTrigger::NewBlocks {
+                blocks,
+                min_interval,
+            } = strategy.settings.execution_trigger
+            {
+                let rpc_canister = strategy.settings.rpc_canister;
+                triggered
+                    .entry(rpc_canister.1)
+                    .or_insert_with(|| (rpc_canister, vec![]))
+                    .1
+                    .push((*key, blocks, min_interval));
+            }
+        }
+    });
+
+    for (chain_id, (rpc_canister, strategies)) in triggered {
+        let head = match get_block(&rpc_canister, true, ProviderPool::Read)
+            .await
+            .and_then(|block| nat_to_u128(block.number))
+        {
+            Ok(head) => head as u64,
+            Err(err) => {
+                if record_head_poll(chain_id, false) == 0 {
+                    JournalCollection::open(None).append_note(
+                        Err(err),
+                        LogType::Info,
+                        format!(
+                            "WARNING: Failed to poll the head of chain {}. Further failures are not journaled until a poll succeeds.",
+                            chain_id
+                        ),
+                    );
+                }
+                continue;
+            }
+        };
+        let failures = record_head_poll(chain_id, true);
+        if failures > 0 {
+            JournalCollection::open(None).append_note(
+                Ok(()),
+                LogType::Info,
+                format!(
+                    "Polled the head of chain {} again after {} failed polls.",
+                    chain_id, failures
+                ),
+            );
+        }
+
+        let now = time() / 1_000_000_000;
+        for (key, blocks, min_interval) in strategies {
+            if consume_block_trigger(key, blocks, min_interval, head, now) {
+                spawn(run_strategy(key));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keeper() -> Principal {
+        Principal::from_slice(&[1; 29])
+    }
+
+    fn reset(mode: SchedulingMode) {
+        set_scheduling_mode(mode);
+        EXECUTION_PERMITS.with(|permits| permits.borrow_mut().clear());
+        LAST_PERMITTED_RUNS.with(|runs| runs.borrow_mut().clear());
+    }
+
+    #[test]
+    fn timers_mode_rejects_keepers() {
+        reset(SchedulingMode::Timers);
+        grant_permit(
+            keeper(),
+            ExecutionPermit {
+                keys: None,
+                min_interval: 0,
+            },
+        );
+        assert!(consume_permit(keeper(), 1, 100).is_err());
+    }
+
+    #[test]
+    fn unknown_keeper_is_unauthorized() {
+        reset(SchedulingMode::External);
+        assert_eq!(
+            consume_permit(keeper(), 1, 100),
+            Err(ManagerError::Unauthorized)
+        );
+    }
+
+    #[test]
+    fn permit_is_limited_to_its_keys() {
+        reset(SchedulingMode::External);
+        grant_permit(
+            keeper(),
+            ExecutionPermit {
+                keys: Some(vec![2]),
+                min_interval: 0,
+            },
+        );
+        assert_eq!(
+            consume_permit(keeper(), 1, 100),
+            Err(ManagerError::Unauthorized)
+        );
+        assert_eq!(consume_permit(keeper(), 2, 100), Ok(()));
+    }
+
+    #[test]
+    fn executions_are_rate_limited_per_key() {
+        reset(SchedulingMode::External);
+        grant_permit(
+            keeper(),
+            ExecutionPermit {
+                keys: None,
+                min_interval: 60,
+            },
+        );
+        assert_eq!(consume_permit(keeper(), 1, 100), Ok(()));
+        assert!(consume_permit(keeper(), 1, 159).is_err());
+        assert_eq!(consume_permit(keeper(), 2, 159), Ok(()));
+        assert_eq!(consume_permit(keeper(), 1, 160), Ok(()));
+    }
+
+    #[test]
+    fn new_block_triggers_are_validated() {
+        assert!(ExecutionTrigger::Hourly.validate().is_ok());
+        assert!(ExecutionTrigger::NewBlocks {
+            blocks: 0,
+            min_interval: CHAIN_HEAD_POLL_INTERVAL
+        }
+        .validate()
+        .is_err());
+        assert!(ExecutionTrigger::NewBlocks {
+            blocks: 5,
+            min_interval: CHAIN_HEAD_POLL_INTERVAL - 1
+        }
+        .validate()
+        .is_err());
+        assert!(ExecutionTrigger::NewBlocks {
+            blocks: 5,
+            min_interval: CHAIN_HEAD_POLL_INTERVAL
+        }
+        .validate()
+        .is_ok());
+    }
+
+    #[test]
+    fn block_triggers_wait_for_blocks_and_interval() {
+        BLOCK_TRIGGERS.with(|triggers| triggers.borrow_mut().clear());
+
+        // The first observation only records the chain head
+        assert!(!consume_block_trigger(1, 5, 120, 1_000, 0));
+        assert!(!consume_block_trigger(1, 5, 120, 1_004, 120));
+        // Enough blocks, but too early
+        assert!(!consume_block_trigger(1, 5, 120, 1_010, 60));
+        assert!(consume_block_trigger(1, 5, 120, 1_010, 120));
+        assert!(!consume_block_trigger(1, 5, 120, 1_014, 300));
+        assert!(consume_block_trigger(1, 5, 120, 1_015, 300));
+    }
+
+    #[test]
+    fn revoked_keeper_is_unauthorized() {
+        reset(SchedulingMode::External);
+        grant_permit(
+            keeper(),
+            ExecutionPermit {
+                keys: None,
+                min_interval: 0,
+            },
+        );
+        assert_eq!(revoke_permit(keeper()), Ok(()));
+        assert_eq!(
+            consume_permit(keeper(), 1, 100),
+            Err(ManagerError::Unauthorized)
+        );
+        assert_eq!(revoke_permit(keeper()), Err(ManagerError::NonExistentValue));
+    }
+
+    #[test]
+    fn head_poll_failures_are_counted_per_chain() {
+        assert_eq!(record_head_poll(1, false), 0);
+        assert_eq!(record_head_poll(1, false), 1);
+        assert_eq!(record_head_poll(10, false), 0);
+        assert_eq!(record_head_poll(1, true), 2);
+        assert_eq!(record_head_poll(1, true), 0);
+        assert_eq!(record_head_poll(10, true), 1);
+    }
+}
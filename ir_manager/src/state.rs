@@ -6,34 +6,286 @@ use std::{
 };
 
 use alloy_primitives::Address;
+use candid::Principal;
 #[cfg(feature = "mainnet")]
 use evm_rpc_types::EthMainnetService;
 #[cfg(feature = "sepolia")]
 use evm_rpc_types::EthSepoliaService;
 use evm_rpc_types::RpcService;
-use ic_stable_structures::{DefaultMemoryImpl, Vec as StableVec};
+use ic_exports::ic_cdk_timers::TimerId;
+use ic_stable_structures::{
+    memory_manager::{MemoryId, MemoryManager, VirtualMemory},
+    storable::Bound,
+    Cell as StableCell, DefaultMemoryImpl, Memory as _, StableBTreeMap, Storable, Vec as StableVec,
+};
+
+use crate::{
+    access_log::AdminAction,
+    adjustments::ConfirmedAdjustment,
+    chain::ChainConfig,
+    charger_config::ChargerConfig,
+    constants::{DEFAULT_ECDSA_KEY_NAME, JOURNAL_CAPACITY, PROVIDERS},
+    custom_providers::CustomProvider,
+    digest::DigestState,
+    flags::FlagValue,
+    funding::FundingState,
+    guard::GuardState,
+    halt::{Halt, HaltCallback},
+    journal::{JournalEntry, LogType, StableJournalCollection},
+    metrics::{record_dropped_journal_write, record_journal_retention, Metrics, RetentionCounters},
+    payload_sizes::PayloadHistogram,
+    positioning_history::PositioningSample,
+    providers::DisabledProvider,
+    rate_history::RateAdjustmentRecord,
+    rate_source::RateSource,
+    retention::{journal_retention, RetentionPolicy},
+    rpc_registry::RpcCanisterRecord,
+    runs::RunSummary,
+    scheduler::{BlockTriggerState, ExecutionPermit, SchedulingMode},
+    strategy::{
+        contention::LockStats, retire::RetiredStrategy, stable::StableStrategy, troves::TroveLayout,
+    },
+    treasury::TreasuryCache,
+    triggers::HaltTrigger,
+    tx_pool::TxRecord,
+    types::{ProviderService, SwapResponseV2},
+    upgrade::{HeapState, UpgradeState},
+    utils::{
+        error::ManagerError,
+        nonce::NonceState,
+        ring::{RingState, StableRing},
+    },
+};
+
+/// Virtual memory region handed out by the memory manager
+pub type Memory = VirtualMemory<DefaultMemoryImpl>;
+
+/// Memory region of the journal
+const JOURNAL_MEMORY_ID: MemoryId = MemoryId::new(0);
+/// Memory region of the outbound transaction pool
+const TX_POOL_MEMORY_ID: MemoryId = MemoryId::new(1);
+/// Memory region of the runtime flag overrides
+const FLAGS_MEMORY_ID: MemoryId = MemoryId::new(2);
+/// Memory region of the strategy run counter
+const RUN_COUNTER_MEMORY_ID: MemoryId = MemoryId::new(3);
+/// Memory region of the strategy run summaries
+const RUNS_MEMORY_ID: MemoryId = MemoryId::new(4);
+/// Memory region of the disabled RPC providers
+const DISABLED_PROVIDERS_MEMORY_ID: MemoryId = MemoryId::new(5);
+/// Memory region of the upgrade gating state
+const UPGRADE_MEMORY_ID: MemoryId = MemoryId::new(6);
+/// Memory region of the custom halt triggers
+const HALT_TRIGGERS_MEMORY_ID: MemoryId = MemoryId::new(7);
+/// Memory region of the admin access log
+const ADMIN_ACTIONS_MEMORY_ID: MemoryId = MemoryId::new(8);
+
+/// Memory region of the swap history
+const SWAPS_MEMORY_ID: MemoryId = MemoryId::new(9);
+/// Memory region of the strategy lock contention statistics
+const LOCK_STATS_MEMORY_ID: MemoryId = MemoryId::new(10);
+/// Memory region of the EVM RPC canister registry
+const RPC_CANISTERS_MEMORY_ID: MemoryId = MemoryId::new(11);
+/// Memory region of the confirmed adjustments indexed by block
+const ADJUSTMENTS_BY_BLOCK_MEMORY_ID: MemoryId = MemoryId::new(12);
+/// Memory region of the journal sequence counter
+const JOURNAL_SEQUENCE_MEMORY_ID: MemoryId = MemoryId::new(13);
+/// Memory region of the response size histograms
+const PAYLOAD_SIZES_MEMORY_ID: MemoryId = MemoryId::new(14);
+/// Memory region of the retired strategies
+const RETIRED_STRATEGIES_MEMORY_ID: MemoryId = MemoryId::new(15);
+/// Memory region of the custom RPC providers
+const CUSTOM_PROVIDERS_MEMORY_ID: MemoryId = MemoryId::new(16);
+/// Memory region of the rate adjustment history
+const RATE_HISTORY_MEMORY_ID: MemoryId = MemoryId::new(17);
+/// Memory region of the funding EOA
+const FUNDING_MEMORY_ID: MemoryId = MemoryId::new(18);
+/// Memory region of the charger configuration
+const CHARGER_CONFIG_MEMORY_ID: MemoryId = MemoryId::new(19);
+/// Memory region of the journal schema version
+const JOURNAL_SCHEMA_MEMORY_ID: MemoryId = MemoryId::new(20);
+/// Memory region of the position and length of the journal ring buffer
+const JOURNAL_RING_MEMORY_ID: MemoryId = MemoryId::new(21);
+/// Memory region of the threshold ECDSA key name
+const ECDSA_KEY_NAME_MEMORY_ID: MemoryId = MemoryId::new(22);
+/// Memory region of the daily positioning samples
+const POSITIONING_HISTORY_MEMORY_ID: MemoryId = MemoryId::new(23);
+/// Memory region of the heap state saved before an upgrade
+const HEAP_STATE_MEMORY_ID: MemoryId = MemoryId::new(24);
+/// Memory region of the chains other than the home chain
+const CHAINS_MEMORY_ID: MemoryId = MemoryId::new(25);
 
-use crate::{halt::Halt, journal::StableJournalCollection, strategy::stable::StableStrategy};
+/// Magic bytes of the `StableVec` the journal was kept in before the memory manager was
+/// introduced, at the start of the raw stable memory
+const LEGACY_JOURNAL_MAGIC: &[u8; 3] = b"SVC";
+
+/// Returns the virtual memory region associated with the given ID
+fn get_memory(id: MemoryId) -> Memory {
+    MEMORY_MANAGER.with(|manager| manager.borrow().get(id))
+}
 
 thread_local! {
+    /// Memory manager splitting the stable memory between the stable structures
+    pub static MEMORY_MANAGER: RefCell<MemoryManager<DefaultMemoryImpl>> = RefCell::new(
+        MemoryManager::init(DefaultMemoryImpl::default())
+    );
     /// Halt state tracking the functionality status of the canister
     pub static HALT_STATE: RefCell<Halt> = RefCell::new(Halt::default());
+    /// Canister method notified of the halt status transitions
+    pub static HALT_CALLBACK: RefCell<Option<HaltCallback>> = RefCell::new(None);
     /// Latest safe block
     pub static LAST_SAFE_BLOCK: Cell<u128> = Cell::new(0);
+    /// Timestamp in seconds of the last ranking of the read providers by block freshness
+    pub static LAST_FRESHNESS_RANKING: Cell<u64> = Cell::new(0);
     /// Swap ckETH Lock
     pub static SWAP_LOCK: Cell<bool> = Cell::new(false);
+    /// Whether the recurring timers were started in this instance, as upgrades clear them
+    pub static TIMERS_STARTED: Cell<bool> = Cell::new(false);
+    /// Execution timers of the strategies, keyed by strategy key
+    pub static STRATEGY_TIMERS: RefCell<HashMap<u32, TimerId>> = RefCell::new(HashMap::new());
     /// HashMap containing all strategies' information
     pub static STRATEGY_STATE: RefCell<HashMap<u32, StableStrategy>> = RefCell::new(HashMap::new());
+    /// Trove list segmentation learned from the previous run, keyed by strategy key
+    pub static TROVE_LAYOUTS: RefCell<HashMap<u32, TroveLayout>> = RefCell::new(HashMap::new());
     /// Tracks if STRATEGY_STATE is mutably borrowed
     pub static STRATEGY_STATE_BORROW: Cell<bool> = Cell::new(false);
+    /// Nonce bookkeeping of the strategy EOAs, keyed by EOA address
+    pub static NONCES: RefCell<HashMap<Address, NonceState>> = RefCell::new(HashMap::new());
     /// Vector of all manager addresses
     pub static MANAGERS: RefCell<Vec<Address>> = RefCell::new(Vec::new());
     /// A counter that tracks EOA turns for minting ckETH
     pub static CKETH_EOA_TURN_COUNTER: Cell<u8> = Cell::new(0);
-    /// Journal
-    pub static JOURNAL: RefCell<StableVec<StableJournalCollection, DefaultMemoryImpl>> = RefCell::new(
-        StableVec::init(DefaultMemoryImpl::default()).expect("Failed to create default memory.")
+    /// `true` while a CMC top-up is in progress
+    pub static CMC_TOP_UP_LOCK: Cell<bool> = Cell::new(false);
+    /// ICP ledger block of a transfer to the CMC that still has to be notified
+    pub static PENDING_TOP_UP: Cell<Option<u64>> = Cell::new(None);
+    /// Journal, a ring buffer of at most `JOURNAL_CAPACITY` collections
+    pub static JOURNAL: RefCell<StableRing<StableJournalCollection, Memory>> = RefCell::new(
+        StableRing::init(
+            StableVec::init(get_memory(JOURNAL_MEMORY_ID)).expect("Failed to create default memory."),
+            StableCell::init(get_memory(JOURNAL_RING_MEMORY_ID), RingState::default()).expect("Failed to initialize the journal ring."),
+            JOURNAL_CAPACITY,
+        )
+    );
+    /// Sequence number of the next journal collection
+    pub static JOURNAL_SEQUENCE: RefCell<StableCell<u64, Memory>> = RefCell::new(
+        StableCell::init(get_memory(JOURNAL_SEQUENCE_MEMORY_ID), 0).expect("Failed to initialize the journal sequence.")
+    );
+    /// Name of the threshold ECDSA key the EOAs are derived from
+    pub static ECDSA_KEY_NAME: RefCell<StableCell<String, Memory>> = RefCell::new(
+        StableCell::init(get_memory(ECDSA_KEY_NAME_MEMORY_ID), DEFAULT_ECDSA_KEY_NAME.to_string()).expect("Failed to initialize the ECDSA key name.")
+    );
+    /// Version of the schema the journal collections were last migrated to
+    pub static JOURNAL_SCHEMA_VERSION: RefCell<StableCell<u64, Memory>> = RefCell::new(
+        StableCell::init(get_memory(JOURNAL_SCHEMA_MEMORY_ID), 0).expect("Failed to initialize the journal schema version.")
+    );
+    /// Outbound transactions of all strategy EOAs, keyed by their pool ID
+    pub static TX_POOL: RefCell<StableBTreeMap<u64, TxRecord, Memory>> = RefCell::new(
+        StableBTreeMap::init(get_memory(TX_POOL_MEMORY_ID))
+    );
+    /// Runtime flag overrides, keyed by flag name
+    pub static FLAGS_STATE: RefCell<StableBTreeMap<String, FlagValue, Memory>> = RefCell::new(
+        StableBTreeMap::init(get_memory(FLAGS_MEMORY_ID))
+    );
+    /// Last assigned strategy run ID
+    pub static RUN_COUNTER: RefCell<StableCell<u64, Memory>> = RefCell::new(
+        StableCell::init(get_memory(RUN_COUNTER_MEMORY_ID), 0).expect("Failed to initialize the run counter.")
+    );
+    /// Summaries of strategy runs, keyed by their run ID
+    pub static RUNS: RefCell<StableBTreeMap<u64, RunSummary, Memory>> = RefCell::new(
+        StableBTreeMap::init(get_memory(RUNS_MEMORY_ID))
+    );
+    /// RPC providers removed from the rotation by a controller, keyed by provider name
+    pub static DISABLED_PROVIDERS: RefCell<StableBTreeMap<String, DisabledProvider, Memory>> = RefCell::new(
+        StableBTreeMap::init(get_memory(DISABLED_PROVIDERS_MEMORY_ID))
+    );
+    /// Upgrade gating state, kept across upgrades
+    pub static UPGRADE_STATE: RefCell<StableCell<UpgradeState, Memory>> = RefCell::new(
+        StableCell::init(get_memory(UPGRADE_MEMORY_ID), UpgradeState::default()).expect("Failed to initialize the upgrade state.")
+    );
+    /// Heap state saved by `pre_upgrade`, until `post_upgrade` restores it
+    pub static HEAP_STATE: RefCell<StableCell<HeapState, Memory>> = RefCell::new(
+        StableCell::init(get_memory(HEAP_STATE_MEMORY_ID), HeapState::default()).expect("Failed to initialize the heap state.")
+    );
+    /// Custom halt and alert triggers registered by a controller, keyed by ID
+    pub static HALT_TRIGGERS: RefCell<StableBTreeMap<u64, HaltTrigger, Memory>> = RefCell::new(
+        StableBTreeMap::init(get_memory(HALT_TRIGGERS_MEMORY_ID))
+    );
+    /// Privileged calls and their outcomes, keyed by a sequential ID
+    pub static ADMIN_ACTIONS: RefCell<StableBTreeMap<u64, AdminAction, Memory>> = RefCell::new(
+        StableBTreeMap::init(get_memory(ADMIN_ACTIONS_MEMORY_ID))
+    );
+    /// Completed ckETH<>Cycles swaps, keyed by swap ID
+    pub static SWAPS: RefCell<StableBTreeMap<u64, SwapResponseV2, Memory>> = RefCell::new(
+        StableBTreeMap::init(get_memory(SWAPS_MEMORY_ID))
+    );
+    /// Lock contention statistics, keyed by strategy key
+    pub static LOCK_STATS: RefCell<StableBTreeMap<u32, LockStats, Memory>> = RefCell::new(
+        StableBTreeMap::init(get_memory(LOCK_STATS_MEMORY_ID))
+    );
+    /// Registered EVM RPC canisters, keyed by effective-from timestamp
+    pub static RPC_CANISTERS: RefCell<StableBTreeMap<u64, RpcCanisterRecord, Memory>> = RefCell::new(
+        StableBTreeMap::init(get_memory(RPC_CANISTERS_MEMORY_ID))
     );
+    /// Confirmed rate adjustments, keyed by inclusion block number and pool ID
+    pub static ADJUSTMENTS_BY_BLOCK: RefCell<StableBTreeMap<(u64, u64), ConfirmedAdjustment, Memory>> = RefCell::new(
+        StableBTreeMap::init(get_memory(ADJUSTMENTS_BY_BLOCK_MEMORY_ID))
+    );
+    /// Response size histograms, keyed by call class
+    pub static PAYLOAD_SIZES: RefCell<StableBTreeMap<String, PayloadHistogram, Memory>> = RefCell::new(
+        StableBTreeMap::init(get_memory(PAYLOAD_SIZES_MEMORY_ID))
+    );
+    /// Retired strategies with their archived journals, keyed by strategy key and retirement time
+    pub static RETIRED_STRATEGIES: RefCell<StableBTreeMap<(u32, u64), RetiredStrategy, Memory>> = RefCell::new(
+        StableBTreeMap::init(get_memory(RETIRED_STRATEGIES_MEMORY_ID))
+    );
+    /// Chains other than the home chain that strategies operate on, keyed by chain ID
+    pub static CHAINS: RefCell<StableBTreeMap<u64, ChainConfig, Memory>> = RefCell::new(
+        StableBTreeMap::init(get_memory(CHAINS_MEMORY_ID))
+    );
+    /// Custom RPC providers registered by a controller, keyed by name
+    pub static CUSTOM_PROVIDERS: RefCell<StableBTreeMap<String, CustomProvider, Memory>> = RefCell::new(
+        StableBTreeMap::init(get_memory(CUSTOM_PROVIDERS_MEMORY_ID))
+    );
+    /// Confirmed rate adjustments of all strategies, in confirmation order
+    pub static RATE_HISTORY: RefCell<StableVec<RateAdjustmentRecord, Memory>> = RefCell::new(
+        StableVec::init(get_memory(RATE_HISTORY_MEMORY_ID)).expect("Failed to initialize the rate history.")
+    );
+    /// Daily positioning samples, keyed by strategy key and day
+    pub static POSITIONING_HISTORY: RefCell<StableBTreeMap<(u32, u64), PositioningSample, Memory>> = RefCell::new(
+        StableBTreeMap::init(get_memory(POSITIONING_HISTORY_MEMORY_ID))
+    );
+    /// EOA dedicated to ckETH minting, kept across upgrades
+    pub static FUNDING: RefCell<StableCell<FundingState, Memory>> = RefCell::new(
+        StableCell::init(get_memory(FUNDING_MEMORY_ID), FundingState::default()).expect("Failed to initialize the funding state.")
+    );
+    /// Thresholds and discount of the ckETH<>Cycles arbitrage, kept across upgrades
+    pub static CHARGER_CONFIG: RefCell<StableCell<ChargerConfig, Memory>> = RefCell::new(
+        StableCell::init(get_memory(CHARGER_CONFIG_MEMORY_ID), ChargerConfig::default()).expect("Failed to initialize the charger config.")
+    );
+    /// Reputations of the custom RPC providers, keyed by name
+    pub static CUSTOM_REPUTATIONS: RefCell<HashMap<String, i64>> = RefCell::new(HashMap::new());
+    /// Activity counters of the canister
+    pub static METRICS: RefCell<Metrics> = RefCell::new(Metrics::default());
+    /// Source of the ETH/CXDR rate used to price swaps
+    pub static RATE_SOURCE: RefCell<RateSource> = RefCell::new(RateSource::default());
+    /// Limits of the journal enforced during the daily cleanup
+    pub static JOURNAL_RETENTION: RefCell<RetentionPolicy> = RefCell::new(RetentionPolicy::default());
+    /// Rate limiting and idempotency state of the update methods
+    pub static GUARD_STATE: RefCell<GuardState> = RefCell::new(GuardState::default());
+    /// Who triggers strategy executions
+    pub static SCHEDULING_MODE: RefCell<SchedulingMode> = RefCell::new(SchedulingMode::default());
+    /// Execution permits of external keepers
+    pub static EXECUTION_PERMITS: RefCell<HashMap<Principal, ExecutionPermit>> = RefCell::new(HashMap::new());
+    /// Timestamp in seconds of the last keeper-triggered execution of each strategy
+    pub static LAST_PERMITTED_RUNS: RefCell<HashMap<u32, u64>> = RefCell::new(HashMap::new());
+    /// Chain head and time of the last execution of each strategy triggered by new blocks
+    pub static BLOCK_TRIGGERS: RefCell<HashMap<u32, BlockTriggerState>> = RefCell::new(HashMap::new());
+    /// Failed chain head polls in a row, keyed by chain ID
+    pub static FAILED_HEAD_POLLS: RefCell<HashMap<u64, u64>> = RefCell::new(HashMap::new());
+    /// Cached observations of the charger subsystem
+    pub static TREASURY: RefCell<TreasuryCache> = RefCell::new(TreasuryCache::default());
+    /// Daily digest bookkeeping and webhook configuration
+    pub static DIGEST: RefCell<DigestState> = RefCell::new(DigestState::default());
     /// RPC Service Vec Deque
     #[cfg(feature = "sepolia")]
     pub static RPC_SERVICE: RefCell<VecDeque<RpcService>> = RefCell::new(VecDeque::from([
@@ -53,17 +305,154 @@ thread_local! {
         RpcService::EthMainnet(evm_rpc_types::EthMainnetService::Llama),
         RpcService::EthMainnet(evm_rpc_types::EthMainnetService::PublicNode),
     ]));
-    /// Reputation-based ranking list of all providers
+    /// Reputation-based ranking list of the read pool providers
     #[cfg(feature = "sepolia")]
     pub static RPC_REPUTATIONS: RefCell<Vec<(i64, EthSepoliaService)>> = RefCell::new(vec![(0, EthSepoliaService::Ankr), (0, EthSepoliaService::BlockPi), (0, EthSepoliaService::PublicNode), (0, EthSepoliaService::Sepolia), (0, EthSepoliaService::Alchemy)]);
-    /// Reputation-based ranking list of all providers
+    /// Reputation-based ranking list of the read pool providers
     #[cfg(feature = "mainnet")]
     pub static RPC_REPUTATIONS: RefCell<Vec<(i64, EthMainnetService)>> = RefCell::new(vec![
         (0, EthMainnetService::Ankr),
         (0, EthMainnetService::BlockPi), (0, EthMainnetService::PublicNode), (0, EthMainnetService::Cloudflare), (0, EthMainnetService::Alchemy)]);
+    /// Reputation-based ranking list of the write pool providers
+    #[cfg(feature = "sepolia")]
+    pub static RPC_WRITE_REPUTATIONS: RefCell<Vec<(i64, EthSepoliaService)>> = RefCell::new(vec![(0, EthSepoliaService::Ankr), (0, EthSepoliaService::BlockPi), (0, EthSepoliaService::PublicNode), (0, EthSepoliaService::Sepolia), (0, EthSepoliaService::Alchemy)]);
+    /// Reputation-based ranking list of the write pool providers
+    #[cfg(feature = "mainnet")]
+    pub static RPC_WRITE_REPUTATIONS: RefCell<Vec<(i64, EthMainnetService)>> = RefCell::new(vec![
+        (0, EthMainnetService::Ankr),
+        (0, EthMainnetService::BlockPi), (0, EthMainnetService::PublicNode), (0, EthMainnetService::Cloudflare), (0, EthMainnetService::Alchemy)]);
+    /// Members of the read provider pool, restored by the daily reputation reset
+    pub static READ_POOL_PROVIDERS: RefCell<Vec<ProviderService>> = RefCell::new(PROVIDERS.to_vec());
+    /// Members of the write provider pool, restored by the daily reputation reset
+    pub static WRITE_POOL_PROVIDERS: RefCell<Vec<ProviderService>> = RefCell::new(PROVIDERS.to_vec());
+}
+
+/// Pushes a journal collection, checking its size against the bound first,
+/// as an oversized collection would otherwise trap.
+///
+/// The oldest collections above the `max_collections` limit of the retention policy are
+/// pruned first, so that the limit holds between the daily cleanups.
+fn push_journal_collection(entry: &StableJournalCollection) -> Result<(), String> {
+    let size = entry.to_bytes().len();
+    if let Bound::Bounded { max_size, .. } = StableJournalCollection::BOUND {
+        if size > max_size as usize {
+            return Err(format!(
+                "The collection takes {} bytes, above the bound of {} bytes.",
+                size, max_size
+            ));
+        }
+    }
+    let max_collections = journal_retention()
+        .max_collections
+        .unwrap_or(JOURNAL_CAPACITY)
+        .min(JOURNAL_CAPACITY);
+    let (result, pruned) = JOURNAL.with_borrow_mut(|journal| {
+        let pruned = journal.pop_front((journal.len() + 1).saturating_sub(max_collections));
+        (journal.push(entry), pruned)
+    });
+    if pruned > 0 {
+        record_journal_retention(&RetentionCounters {
+            by_count: pruned,
+            ..Default::default()
+        });
+    }
+    result.map_err(|err| format!("{:?}", err))
+}
+
+/// Returns the next journal sequence number and advances the counter.
+fn next_journal_sequence() -> u64 {
+    JOURNAL_SEQUENCE.with(|counter| {
+        let mut counter = counter.borrow_mut();
+        let sequence = *counter.get();
+        counter
+            .set(sequence.saturating_add(1))
+            .expect("Failed to persist the journal sequence.");
+        sequence
+    })
+}
+
+/// Inserts a new journal collection, tagged with the next sequence number
+///
+/// If the collection cannot be stored, the failure is counted in the metrics and a minimal
+/// collection recording the loss is stored in its place, on a best-effort basis.
+pub fn insert_journal_collection(mut entry: StableJournalCollection) {
+    entry.sequence = Some(next_journal_sequence());
+    let Err(error) = push_journal_collection(&entry) else {
+        return;
+    };
+
+    let replacement = StableJournalCollection {
+        entries: vec![JournalEntry {
+            timestamp: entry.end_timestamp,
+            run_id: entry.run_id,
+            entry: Err(ManagerError::Custom(error.clone())),
+            note: Some(format!(
+                "WARNING: A journal collection with {} entries could not be stored. Logging is degraded.",
+                entry.entries.len()
+            )),
+            log_type: LogType::Info,
+        }],
+        ..entry
+    };
+    let lost = push_journal_collection(&replacement).is_err();
+    record_dropped_journal_write(error, lost);
+}
+
+/// Assigns sequence numbers to the journal collections stored before they were introduced.
+///
+/// Such collections all precede the sequenced ones, so the whole journal is renumbered by
+/// position. Only has an effect on the first upgrade to a version with journal sequences.
+pub fn backfill_journal_sequences() {
+    JOURNAL.with_borrow(|journal| {
+        let legacy = journal
+            .iter()
+            .take_while(|collection| collection.sequence.is_none())
+            .count() as u64;
+        if legacy == 0 {
+            return;
+        }
+        for index in 0..journal.len() {
+            if let Some(mut collection) = journal.get(index) {
+                collection.sequence = Some(index);
+                journal.set(index, &collection);
+            }
+        }
+        JOURNAL_SEQUENCE.with(|counter| {
+            counter
+                .borrow_mut()
+                .set(journal.len())
+                .expect("Failed to persist the journal sequence.");
+        });
+    });
+}
+
+/// Reads the journal kept in the raw stable memory by the versions without a memory manager.
+///
+/// Must run before `MEMORY_MANAGER` is first used, as it overwrites the legacy layout. Returns
+/// no collections once the memory manager owns the stable memory.
+pub fn take_legacy_journal() -> Vec<StableJournalCollection> {
+    let memory = DefaultMemoryImpl::default();
+    if memory.size() == 0 {
+        return vec![];
+    }
+    let mut magic = [0; 3];
+    memory.read(0, &mut magic);
+    if &magic != LEGACY_JOURNAL_MAGIC {
+        return vec![];
+    }
+    match StableVec::<StableJournalCollection, _>::init(memory) {
+        Ok(legacy) => legacy.iter().collect(),
+        Err(_) => vec![],
+    }
 }
 
-/// Inserts a new journal collection
-pub fn insert_journal_collection(entry: StableJournalCollection) {
-    let _ = JOURNAL.with_borrow_mut(|vec| vec.push(&entry));
+/// Stores the collections read by `take_legacy_journal` in the journal, oldest first.
+///
+/// They are left without a sequence number, for `backfill_journal_sequences` to assign.
+pub fn restore_legacy_journal(collections: Vec<StableJournalCollection>) {
+    for collection in collections {
+        if let Err(error) = push_journal_collection(&collection) {
+            record_dropped_journal_write(error, true);
+        }
+    }
 }
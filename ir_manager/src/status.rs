@@ -0,0 +1,44 @@
+//! Canister Status
+//!
+//! Compact operational status for operators, served by `get_status`. Unlike
+//! `get_canister_status`, it does not call the management canister and can be queried.
+
+use candid::CandidType;
+
+use crate::{
+    clock::time,
+    halt::{halt_status, Halt},
+    metrics::{get_metrics, JournalIntegrity},
+    state::{STRATEGY_STATE, TIMERS_STARTED, UPGRADE_STATE},
+    strategy::contention::{total_lock_stats, LockStats},
+};
+
+/// Operational status of the canister
+#[derive(CandidType, Clone, Debug, PartialEq)]
+pub struct Status {
+    /// Halt status of the canister
+    pub halt: Halt,
+    /// Timestamp in seconds at which the canister was paused for an upgrade, if paused
+    pub paused_since: Option<u64>,
+    /// `true` once the timers are running
+    pub timers_started: bool,
+    /// Number of strategies
+    pub strategies: u64,
+    /// Failed journal writes, a non-zero count means that logging is degraded
+    pub journal: JournalIntegrity,
+    /// Lock contention statistics of all strategies combined
+    pub lock_contention: LockStats,
+}
+
+/// Returns the operational status of the canister.
+pub fn status() -> Status {
+    let now = time() / 1_000_000_000;
+    Status {
+        halt: halt_status(now),
+        paused_since: UPGRADE_STATE.with(|state| state.borrow().get().paused_since),
+        timers_started: TIMERS_STARTED.with(|started| started.get()),
+        strategies: STRATEGY_STATE.with(|state| state.borrow().len() as u64),
+        journal: get_metrics().journal,
+        lock_contention: total_lock_stats(now),
+    }
+}
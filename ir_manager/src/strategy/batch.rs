@@ -0,0 +1,199 @@
+//! Batch Manager Parameters
+//!
+//! Delegators evaluating a batch need its on-chain parameters alongside the strategy's
+//! targeting configuration. They are fetched once a day during maintenance and cached in
+//! the strategy data, so that `get_public_strategy_report` can serve them from a query.
+//!
+//! ```plain
+//! TroveManager ──borrowerOperations()──► BorrowerOperations
+//!      │                                         │
+//! getLatestBatchData(batch)           getInterestBatchManager(batch)
+//!      │                                         │
+//!      └──────────► BatchManagerParams ◄─────────┘
+//!                          │
+//!                  StrategyData (cache)
+//! ```
+
+use alloy_primitives::Address;
+use alloy_sol_types::SolCall;
+use candid::{CandidType, Nat};
+use serde::Deserialize;
+
+use crate::{
+    rpc_registry::resolve_rpc_canister,
+    state::STRATEGY_STATE,
+    types::{
+        borrowerOperationsCall, borrowerOperationsReturn, getInterestBatchManagerCall,
+        getInterestBatchManagerReturn, getLatestBatchDataCall, getLatestBatchDataReturn,
+        InterestBatchManager, LatestBatchData,
+    },
+    utils::{
+        common::{call_with_dynamic_retries, decode_abi_response, u256_to_nat},
+        error::{ManagerError, ManagerResult},
+        evm_rpc::{BlockTag, Service},
+    },
+};
+
+use super::stable::update_strategy;
+
+/// On-chain parameters of a batch manager
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq)]
+pub struct BatchManagerParams {
+    /// Annual management fee charged to the batch (scaled by 1e18)
+    pub annual_management_fee: Nat,
+    /// Current interest rate of the batch (scaled by 1e18)
+    pub annual_interest_rate: Nat,
+    /// Minimum interest rate the batch manager can set (scaled by 1e18)
+    pub min_interest_rate: Nat,
+    /// Maximum interest rate the batch manager can set (scaled by 1e18)
+    pub max_interest_rate: Nat,
+    /// Minimum period in seconds between rate changes without an upfront fee
+    pub min_interest_rate_change_period: u64,
+    /// Timestamp in seconds of the last rate change of the batch
+    pub last_interest_rate_adj_time: u64,
+    /// Timestamp in seconds at which the current change period ends
+    pub current_period_ends_at: u64,
+    /// Timestamp in seconds at which the parameters were fetched
+    pub fetched_at: u64,
+}
+
+impl BatchManagerParams {
+    /// Combines the batch data and the batch manager registration fetched at `now` (in seconds).
+    pub fn new(
+        batch: &LatestBatchData,
+        registration: &InterestBatchManager,
+        now: u64,
+    ) -> ManagerResult<Self> {
+        let min_interest_rate_change_period = registration
+            .minInterestRateChangePeriod
+            .saturating_to::<u64>();
+        let last_interest_rate_adj_time = batch.lastInterestRateAdjTime.saturating_to::<u64>();
+
+        Ok(Self {
+            annual_management_fee: u256_to_nat(&batch.annualManagementFee)?,
+            annual_interest_rate: u256_to_nat(&batch.annualInterestRate)?,
+            min_interest_rate: Nat::from(registration.minInterestRate),
+            max_interest_rate: Nat::from(registration.maxInterestRate),
+            min_interest_rate_change_period,
+            last_interest_rate_adj_time,
+            current_period_ends_at: last_interest_rate_adj_time
+                .saturating_add(min_interest_rate_change_period),
+            fetched_at: now,
+        })
+    }
+}
+
+/// Fetches the on-chain parameters of `batch_manager` from the branch of `manager` at `now` (in seconds).
+pub async fn fetch_batch_manager_params(
+    rpc_canister: &Service,
+    manager: Address,
+    batch_manager: Address,
+    now: u64,
+) -> ManagerResult<BatchManagerParams> {
+    let response = call_with_dynamic_retries(
+        rpc_canister,
+        BlockTag::Latest,
+        manager,
+        borrowerOperationsCall::SELECTOR.to_vec(),
+    )
+    .await?;
+    let borrower_operations =
+        decode_abi_response::<borrowerOperationsReturn, borrowerOperationsCall>(response)?._0;
+
+    let data = getLatestBatchDataCall {
+        _batchAddress: batch_manager,
+    }
+    .abi_encode();
+    let response = call_with_dynamic_retries(rpc_canister, BlockTag::Latest, manager, data).await?;
+    let batch =
+        decode_abi_response::<getLatestBatchDataReturn, getLatestBatchDataCall>(response)?._0;
+
+    let data = getInterestBatchManagerCall {
+        _account: batch_manager,
+    }
+    .abi_encode();
+    let response =
+        call_with_dynamic_retries(rpc_canister, BlockTag::Latest, borrower_operations, data)
+            .await?;
+    let registration = decode_abi_response::<
+        getInterestBatchManagerReturn,
+        getInterestBatchManagerCall,
+    >(response)?
+    ._0;
+
+    BatchManagerParams::new(&batch, &registration, now)
+}
+
+/// Refreshes the cached batch manager parameters of all strategies with a batch manager at
+/// `now` (in seconds), and returns the strategies that could not be refreshed with their errors.
+pub async fn refresh_batch_manager_params(now: u64) -> Vec<(u32, ManagerError)> {
+    let strategies: Vec<(u32, Service, Address, Address)> = STRATEGY_STATE.with(|state| {
+        state
+            .borrow()
+            .iter()
+            .filter(|(_, strategy)| strategy.settings.batch_manager != Address::ZERO)
+            .map(|(key, strategy)| {
+                (
+                    *key,
+                    resolve_rpc_canister(&strategy.settings.rpc_canister, now),
+                    strategy.settings.manager,
+                    strategy.settings.batch_manager,
+                )
+            })
+            .collect()
+    });
+
+    let mut failures = vec![];
+    for (key, rpc_canister, manager, batch_manager) in strategies {
+        match fetch_batch_manager_params(&rpc_canister, manager, batch_manager, now).await {
+            Ok(params) => {
+                // The strategy may have been removed during the call
+                let _ = update_strategy(key, |strategy| {
+                    strategy.data.batch_manager_params = Some(params);
+                });
+            }
+            Err(err) => failures.push((key, err)),
+        }
+    }
+    failures
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_primitives::U256;
+
+    #[test]
+    fn test_current_period_end() {
+        let batch = LatestBatchData {
+            entireDebtWithoutRedistribution: U256::ZERO,
+            entireCollWithoutRedistribution: U256::ZERO,
+            accruedInterest: U256::ZERO,
+            recordedDebt: U256::ZERO,
+            annualInterestRate: U256::from(50_000_000_000_000_000_u64),
+            weightedRecordedDebt: U256::ZERO,
+            annualManagementFee: U256::from(1_000_000_000_000_000_u64),
+            accruedManagementFee: U256::ZERO,
+            weightedRecordedBatchManagementFee: U256::ZERO,
+            lastDebtUpdateTime: U256::ZERO,
+            lastInterestRateAdjTime: U256::from(1_000),
+        };
+        let registration = InterestBatchManager {
+            minInterestRate: 5_000_000_000_000_000,
+            maxInterestRate: 250_000_000_000_000_000,
+            minInterestRateChangePeriod: U256::from(3_600),
+        };
+
+        let params = BatchManagerParams::new(&batch, &registration, 2_000).unwrap();
+        assert_eq!(params.current_period_ends_at, 4_600);
+        assert_eq!(
+            params.annual_management_fee,
+            Nat::from(1_000_000_000_000_000_u64)
+        );
+        assert_eq!(
+            params.max_interest_rate,
+            Nat::from(250_000_000_000_000_000_u64)
+        );
+        assert_eq!(params.fetched_at, 2_000);
+    }
+}
@@ -0,0 +1,215 @@
+//! Cross-Strategy Configuration Validation
+//!
+//! Detects strategies whose configurations overlap, which would make them fight each
+//! other's rate adjustments:
+//!
+//! ```plain
+//! Strategy 1 ──┐
+//!              ├──► same batch manager                ──► SharedBatchManager
+//! Strategy 2 ──┘
+//!
+//! Strategy 3 ──┐
+//!              ├──► same manager and collateral index ──► SharedBranch
+//! Strategy 4 ──┘
+//! ```
+//!
+//! Only strategies on the same chain can conflict, as the same addresses designate different
+//! contracts on other chains. Conflicts are checked when a strategy is minted or its batch
+//! manager is set, and once a day.
+
+use std::collections::BTreeMap;
+
+use alloy_primitives::{Address, U256};
+use candid::{CandidType, Nat};
+
+use crate::{
+    state::STRATEGY_STATE,
+    utils::{common::u256_to_nat, error::ManagerResult},
+};
+
+use super::settings::StrategySettings;
+
+/// Kind of overlap between strategy configurations
+#[derive(CandidType, Clone, Debug, PartialEq)]
+pub enum ConflictKind {
+    /// The strategies adjust the rate of the same batch manager
+    SharedBatchManager {
+        /// Chain ID of the strategies
+        chain_id: u64,
+        /// Batch manager contract address
+        batch_manager: String,
+    },
+    /// The strategies manage the same collateral branch
+    SharedBranch {
+        /// Chain ID of the strategies
+        chain_id: u64,
+        /// Trove manager contract address
+        manager: String,
+        /// Collateral index
+        collateral_index: Nat,
+    },
+}
+
+/// Strategies whose configurations overlap
+#[derive(CandidType, Clone, Debug, PartialEq)]
+pub struct ConfigConflict {
+    /// Kind of overlap
+    pub kind: ConflictKind,
+    /// Keys of the conflicting strategies, in ascending order
+    pub strategies: Vec<u32>,
+}
+
+/// Returns the conflicts between the given strategy configurations.
+///
+/// Strategies without a batch manager are only checked for shared branches.
+pub fn find_conflicts<'a>(
+    settings: impl IntoIterator<Item = &'a StrategySettings>,
+) -> ManagerResult<Vec<ConfigConflict>> {
+    let mut batch_managers: BTreeMap<(u64, Address), Vec<u32>> = BTreeMap::new();
+    let mut branches: BTreeMap<(u64, Address, U256), Vec<u32>> = BTreeMap::new();
+
+    for settings in settings {
+        let chain_id = settings.chain.chain_id;
+        if settings.batch_manager != Address::ZERO {
+            batch_managers
+                .entry((chain_id, settings.batch_manager))
+                .or_default()
+                .push(settings.key);
+        }
+        branches
+            .entry((chain_id, settings.manager, settings.collateral_index))
+            .or_default()
+            .push(settings.key);
+    }
+
+    let mut conflicts = vec![];
+    for ((chain_id, batch_manager), mut keys) in batch_managers {
+        if keys.len() > 1 {
+            keys.sort_unstable();
+            conflicts.push(ConfigConflict {
+                kind: ConflictKind::SharedBatchManager {
+                    chain_id,
+                    batch_manager: batch_manager.to_string(),
+                },
+                strategies: keys,
+            });
+        }
+    }
+    for ((chain_id, manager, collateral_index), mut keys) in branches {
+        if keys.len() > 1 {
+            keys.sort_unstable();
+            conflicts.push(ConfigConflict {
+                kind: ConflictKind::SharedBranch {
+                    chain_id,
+                    manager: manager.to_string(),
+                    collateral_index: u256_to_nat(&collateral_index)?,
+                },
+                strategies: keys,
+            });
+        }
+    }
+    Ok(conflicts)
+}
+
+/// Returns the conflicts between the minted strategies.
+pub fn config_conflicts() -> ManagerResult<Vec<ConfigConflict>> {
+    STRATEGY_STATE.with(|state| {
+        let state = state.borrow();
+        find_conflicts(state.values().map(|strategy| &strategy.settings))
+    })
+}
+
+/// Returns the conflicts the candidate configuration would create with the minted strategies.
+///
+/// A minted strategy with the same key as the candidate is replaced by it.
+pub fn conflicts_with(candidate: &StrategySettings) -> ManagerResult<Vec<ConfigConflict>> {
+    STRATEGY_STATE.with(|state| {
+        let state = state.borrow();
+        let others = state
+            .values()
+            .map(|strategy| &strategy.settings)
+            .filter(|settings| settings.key != candidate.key);
+        let conflicts = find_conflicts(others.chain(std::iter::once(candidate)))?;
+        Ok(conflicts
+            .into_iter()
+            .filter(|conflict| conflict.strategies.contains(&candidate.key))
+            .collect())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::CHAIN_ID;
+
+    fn settings(
+        key: u32,
+        batch_manager: u8,
+        manager: u8,
+        collateral_index: u64,
+    ) -> StrategySettings {
+        StrategySettings::default()
+            .key(key)
+            .batch_manager(if batch_manager == 0 {
+                Address::ZERO
+            } else {
+                Address::repeat_byte(batch_manager)
+            })
+            .manager(Address::repeat_byte(manager))
+            .collateral_index(U256::from(collateral_index))
+            .clone()
+    }
+
+    #[test]
+    fn test_no_conflicts() {
+        let strategies = [
+            settings(1, 1, 1, 0),
+            settings(2, 2, 1, 1),
+            settings(3, 0, 2, 0),
+        ];
+        assert!(find_conflicts(strategies.iter()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_shared_batch_manager() {
+        let strategies = [
+            settings(2, 1, 1, 0),
+            settings(1, 1, 2, 0),
+            settings(3, 0, 3, 0),
+        ];
+        assert_eq!(
+            find_conflicts(strategies.iter()).unwrap(),
+            vec![ConfigConflict {
+                kind: ConflictKind::SharedBatchManager {
+                    chain_id: CHAIN_ID,
+                    batch_manager: Address::repeat_byte(1).to_string()
+                },
+                strategies: vec![1, 2],
+            }]
+        );
+    }
+
+    #[test]
+    fn test_shared_branch() {
+        let strategies = [settings(1, 0, 1, 4), settings(2, 0, 1, 4)];
+        assert_eq!(
+            find_conflicts(strategies.iter()).unwrap(),
+            vec![ConfigConflict {
+                kind: ConflictKind::SharedBranch {
+                    chain_id: CHAIN_ID,
+                    manager: Address::repeat_byte(1).to_string(),
+                    collateral_index: Nat::from(4_u8),
+                },
+                strategies: vec![1, 2],
+            }]
+        );
+    }
+
+    #[test]
+    fn test_other_chain() {
+        let mut other_chain = settings(2, 1, 1, 0);
+        other_chain.chain.chain_id = 42161;
+        let strategies = [settings(1, 1, 1, 0), other_chain];
+        assert!(find_conflicts(strategies.iter()).unwrap().is_empty());
+    }
+}
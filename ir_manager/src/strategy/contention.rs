@@ -0,0 +1,141 @@
+//! Lock Contention Statistics
+//!
+//! Counts how often a strategy could not be executed because its lock was held, and how often
+//! an expired lock had to be released by the timeout. Contention is otherwise invisible until
+//! it causes missed cycles, and every auto-unlock means that a previous run got stuck.
+//!
+//! ```plain
+//! try_lock ──┬── Locked ─────────────► contentions + 1
+//!            │
+//!            ├── expired lock ───────► auto_unlocks + 1 ──(> threshold this week)──► WARNING
+//!            │
+//!            └── free ───────────────► (not counted)
+//! ```
+//!
+//! The counters are kept in stable memory, so they survive upgrades.
+
+use std::borrow::Cow;
+
+use candid::{CandidType, Decode, Encode};
+use ic_stable_structures::{storable::Bound, Storable};
+use serde::Deserialize;
+
+use crate::{constants::AUTO_UNLOCK_WINDOW, state::LOCK_STATS};
+
+/// Lock contention counters of a strategy
+#[derive(CandidType, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct LockStats {
+    /// Number of executions refused because the lock was held
+    pub contentions: u64,
+    /// Number of expired locks released by the timeout
+    pub auto_unlocks: u64,
+    /// Timestamp in seconds of the last refused execution
+    pub last_contention_at: Option<u64>,
+    /// Timestamps in seconds of the auto-unlocks within the last `AUTO_UNLOCK_WINDOW`
+    pub recent_auto_unlocks: Vec<u64>,
+}
+
+impl Storable for LockStats {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        Cow::Owned(Encode!(self).expect("Failed to encode lock statistics."))
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).expect("Failed to decode lock statistics.")
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+impl LockStats {
+    /// Drops the auto-unlocks older than `AUTO_UNLOCK_WINDOW` at `now` (in seconds).
+    fn prune(&mut self, now: u64) {
+        self.recent_auto_unlocks
+            .retain(|unlocked_at| now.saturating_sub(*unlocked_at) < AUTO_UNLOCK_WINDOW);
+    }
+
+    /// Adds the counters of `other`.
+    fn merge(&mut self, other: &LockStats) {
+        self.contentions = self.contentions.saturating_add(other.contentions);
+        self.auto_unlocks = self.auto_unlocks.saturating_add(other.auto_unlocks);
+        self.last_contention_at = self.last_contention_at.max(other.last_contention_at);
+        self.recent_auto_unlocks
+            .extend_from_slice(&other.recent_auto_unlocks);
+        self.recent_auto_unlocks.sort_unstable();
+    }
+}
+
+/// Updates the statistics of a strategy in stable memory.
+fn update_lock_stats<R>(key: u32, update: impl FnOnce(&mut LockStats) -> R) -> R {
+    LOCK_STATS.with(|stats| {
+        let mut stats = stats.borrow_mut();
+        let mut strategy_stats = stats.get(&key).unwrap_or_default();
+        let result = update(&mut strategy_stats);
+        stats.insert(key, strategy_stats);
+        result
+    })
+}
+
+/// Records an execution of a strategy refused at `now` (in seconds) because its lock was held.
+pub fn record_lock_contention(key: u32, now: u64) {
+    update_lock_stats(key, |stats| {
+        stats.contentions = stats.contentions.saturating_add(1);
+        stats.last_contention_at = Some(now);
+    });
+}
+
+/// Records an expired lock of a strategy released at `now` (in seconds).
+///
+/// Returns the number of auto-unlocks of the strategy within the last `AUTO_UNLOCK_WINDOW`.
+pub fn record_auto_unlock(key: u32, now: u64) -> u64 {
+    update_lock_stats(key, |stats| {
+        stats.auto_unlocks = stats.auto_unlocks.saturating_add(1);
+        stats.prune(now);
+        stats.recent_auto_unlocks.push(now);
+        stats.recent_auto_unlocks.len() as u64
+    })
+}
+
+/// Returns the lock contention statistics of every strategy at `now` (in seconds).
+pub fn lock_stats(now: u64) -> Vec<(u32, LockStats)> {
+    LOCK_STATS.with(|stats| {
+        stats
+            .borrow()
+            .iter()
+            .map(|(key, mut strategy_stats)| {
+                strategy_stats.prune(now);
+                (key, strategy_stats)
+            })
+            .collect()
+    })
+}
+
+/// Returns the lock contention statistics of all strategies combined at `now` (in seconds).
+pub fn total_lock_stats(now: u64) -> LockStats {
+    lock_stats(now)
+        .iter()
+        .fold(LockStats::default(), |mut total, (_, strategy_stats)| {
+            total.merge(strategy_stats);
+            total
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_auto_unlocks_are_counted_per_window() {
+        assert_eq!(record_auto_unlock(1, 1_000), 1);
+        assert_eq!(record_auto_unlock(1, 2_000), 2);
+        assert_eq!(record_auto_unlock(1, 1_000 + AUTO_UNLOCK_WINDOW), 2);
+        record_lock_contention(1, 3_000);
+        record_lock_contention(2, 4_000);
+
+        let total = total_lock_stats(2_000 + AUTO_UNLOCK_WINDOW);
+        assert_eq!(total.contentions, 2);
+        assert_eq!(total.auto_unlocks, 3);
+        assert_eq!(total.last_contention_at, Some(4_000));
+        assert_eq!(total.recent_auto_unlocks, vec![1_000 + AUTO_UNLOCK_WINDOW]);
+    }
+}
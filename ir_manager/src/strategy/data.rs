@@ -18,25 +18,76 @@
 //! └─────────────┘    └─────────┘     │  last_ok_exit  │
 //!                          │         └────────────────┘
 //!                          │
+//!                          │         ┌──────────────────────┐
+//!                          ├────────►│    Failure State     │
+//!                          │         │ consecutive_failures │
+//!                          │         │ last_error           │
+//!                          │         └──────────────────────┘
+//!                          │
 //!                          │         ┌────────────────┐
-//!                          └────────►│    EOA State   │
-//!                                    │   eoa_nonce    │
-//!                                    └────────────────┘
+//!                          ├────────►│    EOA State   │
+//!                          │         │   eoa_nonce    │
+//!                          │         │   eoa_balance  │
+//!                          │         └────────────────┘
+//!                          │
+//!                          │         ┌───────────────────┐
+//!                          ├────────►│    Retry State    │
+//!                          │         │ pending_retry     │
+//!                          │         │ pending_run_retry │
+//!                          │         └───────────────────┘
+//!                          │
+//!                          │         ┌─────────────────────┐
+//!                          ├────────►│  History State      │
+//!                          │         │ adjustment_count    │
+//!                          │         │ last_adjustment_tx  │
+//!                          │         │ upfront_fees        │
+//!                          │         └─────────────────────┘
+//!                          │
+//!                          │         ┌─────────────────────┐
+//!                          ├────────►│     Cost State      │
+//!                          │         │ gas_spent           │
+//!                          │         │ upfront_fees_paid   │
+//!                          │         └─────────────────────┘
+//!                          │
+//!                          │         ┌─────────────────────┐
+//!                          ├────────►│ Verification State  │
+//!                          │         │ pending_positioning │
+//!                          │         └─────────────────────┘
+//!                          │
+//!                          │         ┌─────────────────────┐
+//!                          ├────────►│   Warm-up State     │
+//!                          │         │ warmup              │
+//!                          │         └─────────────────────┘
+//!                          │
+//!                          │         ┌─────────────────────┐
+//!                          └────────►│    Safety State     │
+//!                                    │ safety_trip         │
+//!                                    └─────────────────────┘
 //! ```
 
 use alloy_primitives::U256;
-use candid::CandidType;
+use candid::{CandidType, Nat};
 use chrono::{DateTime, Utc};
-use ic_exports::ic_cdk::api::time;
+use serde::Deserialize;
+
+use crate::{
+    clock::Seconds,
+    constants::UPFRONT_FEE_BUDGET_WINDOW,
+    treasury::CachedBalance,
+    utils::{
+        error::{ErrorCategory, ManagerError},
+        units::{Rate, Wei},
+    },
+};
 
-use crate::utils::{common::u256_to_nat, error::ManagerError};
+use super::{batch::BatchManagerParams, warmup::Warmup};
 
 /// Core strategy runtime state containing mutable execution data.
 ///
 /// Tracks three key state components:
 /// - Interest rate state (latest applied rate)
 /// - Timing state (update/exit timestamps)
-/// - Transaction state (nonce management)
+/// - Transaction state (nonce management, scheduled resubmissions)
 #[derive(Clone, Default)]
 pub struct StrategyData {
     /// Current interest rate from last execution
@@ -45,8 +96,111 @@ pub struct StrategyData {
     pub last_update: u64,
     /// Current EOA transaction nonce
     pub eoa_nonce: u64,
+    /// ETH balance of the EOA observed by the last daily balance sweep
+    pub eoa_balance: Option<CachedBalance>,
     /// Last successful strategy completion
     pub last_ok_exit: u64,
+    /// Number of runs in a row that failed, reset by a successful run
+    pub consecutive_failures: u64,
+    /// Error of the last failed run, cleared by a successful run
+    pub last_error: Option<ManagerError>,
+    /// Rate adjustment resubmission scheduled after a nonce mismatch
+    pub pending_retry: Option<PendingRetry>,
+    /// Next attempt of a failed run, scheduled with backoff
+    pub pending_run_retry: Option<PendingRunRetry>,
+    /// Number of successful rate adjustments
+    pub adjustment_count: u64,
+    /// Hash of the last successful rate adjustment transaction
+    pub last_adjustment_tx: Option<String>,
+    /// Timestamps in seconds and maximum upfront fees of the rate adjustments submitted
+    /// within the last `UPFRONT_FEE_BUDGET_WINDOW`
+    pub upfront_fees: Vec<(u64, U256)>,
+    /// Cumulative fees in wei paid for the gas of the confirmed transactions of the EOA
+    pub gas_spent: u128,
+    /// Cumulative upfront fees charged to the batch by the confirmed transactions of the EOA
+    pub upfront_fees_paid: U256,
+    /// On-chain parameters of the batch manager, refreshed during daily maintenance
+    pub batch_manager_params: Option<BatchManagerParams>,
+    /// Timestamp in seconds since which the market has had no troves
+    pub dormant_since: Option<u64>,
+    /// Timestamp in seconds at which the strategy was paused for exceeding its gas budget
+    pub budget_paused_since: Option<u64>,
+    /// Positioning expected from the last rate adjustment, until it is observed on-chain
+    pub pending_positioning: Option<ExpectedPositioning>,
+    /// Simulation-only state, until the strategy goes live
+    pub warmup: Option<Warmup>,
+    /// Rate adjustment held back by the rate guard, until a run is back within bounds
+    pub safety_trip: Option<SafetyTrip>,
+}
+
+/// A rate adjustment held back by the rate guard.
+///
+/// Once acknowledged by a controller, the next run submits its rate even if it is out of
+/// bounds.
+#[derive(Clone, Debug, PartialEq, CandidType, Deserialize)]
+pub struct SafetyTrip {
+    /// ID of the run that calculated the rate
+    pub run_id: Option<u64>,
+    /// Rate of the batch when the guard tripped
+    pub latest_rate: Nat,
+    /// Rate that was held back
+    pub new_rate: Nat,
+    /// Bound that was exceeded
+    pub reason: String,
+    /// Timestamp in seconds at which the guard tripped
+    pub tripped_at: u64,
+    /// `true` once a controller allowed the next run to submit out of bounds
+    pub acknowledged: bool,
+}
+
+/// Debt in front the engine expected when it proposed a rate adjustment.
+///
+/// Verified against the realized debt in front once the new rate is observed on-chain.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ExpectedPositioning {
+    /// ID of the run that calculated the rate
+    pub run_id: Option<u64>,
+    /// Rate the batch was adjusted to
+    pub rate: U256,
+    /// Debt in front the strategy was targeting
+    pub target_debt_in_front: U256,
+    /// Debt in front at the new rate, had the market stayed unchanged
+    pub expected_debt_in_front: U256,
+}
+
+/// The next attempt of a failed run, waiting for its timer.
+#[derive(Clone, Debug, PartialEq, CandidType, Deserialize)]
+pub struct PendingRunRetry {
+    /// ID of the run
+    pub run_id: u64,
+    /// Number of the upcoming attempt
+    pub attempt: u8,
+    /// Timestamp in seconds at which the attempt is due
+    pub due_at: u64,
+    /// Cycles burned by the previous attempts
+    pub cycles_burned: u128,
+}
+
+/// A rate adjustment waiting to be resubmitted by a timer.
+///
+/// Persisted in the strategy data so that the continuation can resume
+/// with the same rate after the delay.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PendingRetry {
+    /// ID of the run that calculated the rate
+    pub run_id: Option<u64>,
+    /// Rate to submit
+    pub new_rate: U256,
+    /// Upfront fee predicted for the rate
+    pub max_upfront_fee: U256,
+    /// Number of troves used for the hint calculation
+    pub troves_count: U256,
+    /// Timestamp in seconds of the block the rate was calculated from
+    pub context_timestamp: u64,
+    /// Number of the upcoming attempt (the first submission is attempt 1)
+    pub attempt: u8,
+    /// Timestamp in seconds at which the resubmission was scheduled
+    pub scheduled_at: u64,
 }
 
 impl StrategyData {
@@ -72,9 +226,53 @@ impl StrategyData {
         self
     }
 
-    /// Records successful strategy completion time.
+    /// Sets the simulation-only state of the strategy.
+    pub fn warmup(&mut self, warmup: Option<Warmup>) -> &mut Self {
+        self.warmup = warmup;
+        self
+    }
+
+    /// Records successful strategy completion time and resets the failure streak.
     pub fn record_last_ok_exit(&mut self) -> &mut Self {
-        self.last_ok_exit = time() / 1_000_000_000;
+        self.last_ok_exit = Seconds::now().get();
+        self.consecutive_failures = 0;
+        self.last_error = None;
+        self
+    }
+
+    /// Records the maximum upfront fee of a rate adjustment submitted at `now` (in seconds),
+    /// and forgets the fees that fell out of the budget window.
+    pub fn record_upfront_fee(&mut self, now: u64, fee: U256) -> &mut Self {
+        let since = now.saturating_sub(UPFRONT_FEE_BUDGET_WINDOW);
+        self.upfront_fees
+            .retain(|(timestamp, _)| *timestamp > since);
+        if fee > U256::ZERO {
+            self.upfront_fees.push((now, fee));
+        }
+        self
+    }
+
+    /// Returns the upfront fees paid within the budget window ending at `now` (in seconds).
+    pub fn upfront_fees_in_window(&self, now: u64) -> U256 {
+        let since = now.saturating_sub(UPFRONT_FEE_BUDGET_WINDOW);
+        self.upfront_fees
+            .iter()
+            .filter(|(timestamp, _)| *timestamp > since)
+            .fold(U256::ZERO, |total, (_, fee)| total.saturating_add(*fee))
+    }
+
+    /// Adds the gas fee and the upfront fee read from the receipt of a confirmed transaction
+    /// to the cumulative costs of the strategy.
+    pub fn record_costs(&mut self, gas_fee: u128, upfront_fee: U256) -> &mut Self {
+        self.gas_spent = self.gas_spent.saturating_add(gas_fee);
+        self.upfront_fees_paid = self.upfront_fees_paid.saturating_add(upfront_fee);
+        self
+    }
+
+    /// Records a failed run, extending the failure streak.
+    pub fn record_failure(&mut self, error: ManagerError) -> &mut Self {
+        self.consecutive_failures = self.consecutive_failures.saturating_add(1);
+        self.last_error = Some(error);
         self
     }
 }
@@ -91,8 +289,28 @@ pub struct StrategyDataQuery {
     pub last_update: String,
     /// Current transaction nonce
     pub eoa_nonce: u64,
+    /// ETH balance of the EOA observed by the last daily balance sweep
+    pub eoa_balance: Option<CachedBalance>,
     /// Last successful completion time
     pub last_ok_exit: String,
+    /// Number of runs in a row that failed
+    pub consecutive_failures: u64,
+    /// Error of the last failed run, if the streak is not over
+    pub last_error: Option<ManagerError>,
+    /// Category of the last error, which decides whether runs are retried
+    pub last_error_category: Option<ErrorCategory>,
+    /// Timestamp in seconds at which the strategy was paused for exceeding its gas budget
+    pub budget_paused_since: Option<u64>,
+    /// Simulation-only state, `None` once the strategy is live
+    pub warmup: Option<Warmup>,
+    /// Rate adjustment held back by the rate guard
+    pub safety_trip: Option<SafetyTrip>,
+    /// Upfront fees paid within the last `UPFRONT_FEE_BUDGET_WINDOW`
+    pub upfront_fees_in_window: Nat,
+    /// Cumulative fees in wei paid for gas
+    pub gas_spent: Nat,
+    /// Cumulative upfront fees paid
+    pub upfront_fees_paid: Nat,
 }
 
 /// Validated conversion from runtime to query state
@@ -110,10 +328,21 @@ impl TryFrom<StrategyData> for StrategyDataQuery {
             .to_string();
 
         Ok(Self {
-            latest_rate: u256_to_nat(&value.latest_rate)?,
+            latest_rate: Rate(value.latest_rate).to_nat(),
             last_update,
             eoa_nonce: value.eoa_nonce,
+            eoa_balance: value.eoa_balance,
             last_ok_exit,
+            consecutive_failures: value.consecutive_failures,
+            last_error_category: value.last_error.as_ref().map(ManagerError::category),
+            last_error: value.last_error,
+            budget_paused_since: value.budget_paused_since,
+            warmup: value.warmup,
+            upfront_fees_in_window: Wei(value.upfront_fees_in_window(Seconds::now().get()))
+                .to_nat(),
+            gas_spent: Nat::from(value.gas_spent),
+            upfront_fees_paid: Wei(value.upfront_fees_paid).to_nat(),
+            safety_trip: value.safety_trip,
         })
     }
 }
@@ -143,6 +372,52 @@ mod tests {
         assert_eq!(data.eoa_nonce, eoa_nonce);
     }
 
+    #[test]
+    fn test_failure_streak() {
+        let mut data = StrategyData::default();
+
+        data.record_failure(ManagerError::NonExistentValue)
+            .record_failure(ManagerError::Locked);
+        assert_eq!(data.consecutive_failures, 2);
+        assert_eq!(data.last_error, Some(ManagerError::Locked));
+
+        data.record_last_ok_exit();
+        assert_eq!(data.consecutive_failures, 0);
+        assert_eq!(data.last_error, None);
+    }
+
+    #[test]
+    fn test_upfront_fees_in_window() {
+        let mut data = StrategyData::default();
+        let start = 1_700_000_000;
+
+        data.record_upfront_fee(start, U256::from(5))
+            .record_upfront_fee(start + 10, U256::ZERO)
+            .record_upfront_fee(start + 20, U256::from(7));
+        assert_eq!(data.upfront_fees.len(), 2);
+        assert_eq!(data.upfront_fees_in_window(start + 20), U256::from(12));
+
+        let expiry = start + UPFRONT_FEE_BUDGET_WINDOW;
+        assert_eq!(data.upfront_fees_in_window(expiry), U256::from(7));
+        data.record_upfront_fee(expiry, U256::from(1));
+        assert_eq!(data.upfront_fees.len(), 2);
+        assert_eq!(data.upfront_fees_in_window(expiry), U256::from(8));
+    }
+
+    #[test]
+    fn test_record_costs() {
+        let mut data = StrategyData::default();
+
+        data.record_costs(21_000, U256::ZERO)
+            .record_costs(52_000, U256::from(9));
+        assert_eq!(data.gas_spent, 73_000);
+        assert_eq!(data.upfront_fees_paid, U256::from(9));
+
+        data.record_costs(u128::MAX, U256::MAX);
+        assert_eq!(data.gas_spent, u128::MAX);
+        assert_eq!(data.upfront_fees_paid, U256::MAX);
+    }
+
     // Property-based testing for StrategyData
     proptest! {
         #[test]
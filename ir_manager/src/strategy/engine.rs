@@ -0,0 +1,795 @@
+//! Rate Strategy Engine
+//!
+//! The decision logic of a strategy is pluggable: a `RateStrategy` proposes a new rate from
+//! the execution context and decides whether adjusting to it is worth the upfront fee.
+//! The execution and transaction plumbing in `executable` is shared by all strategies.
+//!
+//! ```plain
+//! Execution Context ──► RateStrategy::calculate_new_rate ──► new rate
+//!                                                               │
+//!                                   predict upfront fee ◄───────┘
+//!                                            │
+//!                                            ▼
+//!                        RateStrategy::should_adjust ──► submit / skip
+//!                                            │
+//!                                            └──► ConditionCheck per evaluated check
+//! ```
+//!
+//! Available strategies:
+//! - `DebtInFrontTargeting`: keeps a target amount of debt in front of the batch
+//! - `PercentileTracking`: tracks a debt-weighted percentile of the market rates plus a spread
+//!
+//! The batch is located in the market by `batch_position`. During rate transitions the trove
+//! getter can return the batch at several rate buckets; the entries are aggregated and the
+//! lowest bucket determines the debt in front:
+//!
+//! ```plain
+//! [ market ][ batch @ r1 ][ market ][ batch @ r2 ][ market ]
+//! └─ in front ─┘└──────────── batch debt = r1 + r2 ───────┘
+//! ```
+//!
+//! The granularity of the proposed rates is configured per strategy by a `RateGranularity`:
+//! the increment above the trove the batch is positioned after, and an optional step the
+//! final rate is rounded up to. Rounding up keeps the batch behind that trove.
+
+use alloy_primitives::{Address, U256};
+use candid::CandidType;
+use serde::Deserialize;
+
+use crate::{
+    constants::{scale, tolerance_margin_down, tolerance_margin_up},
+    journal::{JournalCollection, LogType},
+    types::DebtPerInterestRate,
+    utils::{
+        error::{arithmetic_err, ManagerError, ManagerResult},
+        units::{Bps, Rate, Wei},
+    },
+};
+
+/// One basis point in the rate scale (0.01%)
+const BASIS_POINT: u128 = Bps(1).to_wad();
+
+/// Largest increment or rounding step accepted (1%)
+const MAX_RATE_STEP: u64 = Bps(100).to_wad() as u64;
+
+/// Location of a batch in the market
+#[derive(Clone, Debug, PartialEq)]
+pub struct BatchPosition {
+    /// Debt of the market in front of the lowest bucket of the batch
+    pub debt_in_front: U256,
+    /// Debt of the batch, summed over all of its buckets
+    pub batch_debt: U256,
+    /// Rates of the buckets the batch was found at, lowest first
+    pub rates: Vec<U256>,
+}
+
+/// Locates the batch of `batch_manager` in `troves`, sorted by ascending rate.
+///
+/// Returns `None` if no trove has delegated to the batch manager.
+pub fn batch_position(
+    troves: &[DebtPerInterestRate],
+    batch_manager: Address,
+) -> Option<BatchPosition> {
+    let lowest = troves
+        .iter()
+        .position(|trove| trove.interestBatchManager == batch_manager)?;
+    let debt_in_front = troves[..lowest]
+        .iter()
+        .fold(U256::ZERO, |debt, trove| debt.saturating_add(trove.debt));
+
+    let mut position = BatchPosition {
+        debt_in_front,
+        batch_debt: U256::ZERO,
+        rates: vec![],
+    };
+    for trove in troves[lowest..]
+        .iter()
+        .filter(|trove| trove.interestBatchManager == batch_manager)
+    {
+        position.batch_debt = position.batch_debt.saturating_add(trove.debt);
+        if !position.rates.contains(&trove.interestRate) {
+            position.rates.push(trove.interestRate);
+        }
+    }
+    position.rates.sort_unstable();
+    Some(position)
+}
+
+/// Granularity of the rates proposed for a strategy
+#[derive(CandidType, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct RateGranularity {
+    /// Added to the rate of the trove the batch is positioned after (scaled by 1e18)
+    pub increment: u64,
+    /// Step the final rate is rounded up to (scaled by 1e18), if any
+    pub rounding_step: Option<u64>,
+}
+
+impl Default for RateGranularity {
+    fn default() -> Self {
+        Self {
+            increment: BASIS_POINT as u64,
+            rounding_step: None,
+        }
+    }
+}
+
+impl RateGranularity {
+    /// Validates that the increment and the rounding step are between zero and 1%.
+    pub fn validate(&self) -> ManagerResult<()> {
+        if self.increment == 0 || self.increment > MAX_RATE_STEP {
+            return Err(ManagerError::Custom(format!(
+                "The rate increment must be between 1 and {}.",
+                MAX_RATE_STEP
+            )));
+        }
+        if let Some(step) = self.rounding_step {
+            if step == 0 || step > MAX_RATE_STEP {
+                return Err(ManagerError::Custom(format!(
+                    "The rounding step must be between 1 and {}.",
+                    MAX_RATE_STEP
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Rounds `rate` up to the next multiple of the rounding step, if one is set.
+    pub fn round(&self, rate: U256) -> U256 {
+        match self.rounding_step {
+            Some(step) if step > 0 => {
+                let step = U256::from(step);
+                let remainder = rate % step;
+                if remainder == U256::ZERO {
+                    rate
+                } else {
+                    rate.saturating_add(step - remainder)
+                }
+            }
+            _ => rate,
+        }
+    }
+}
+
+/// Decision logic of a strategy, selected per strategy in its settings
+#[derive(CandidType, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+pub enum RateStrategyKind {
+    /// Keeps a target amount of debt in front of the batch
+    #[default]
+    DebtInFront,
+    /// Tracks a debt-weighted percentile of the market rates plus a spread
+    Percentile {
+        /// Percentile of the market debt, from 1 to 100
+        percentile: u8,
+        /// Spread added to the percentile rate in basis points
+        spread_bps: u64,
+    },
+}
+
+impl RateStrategyKind {
+    /// Validates the parameters of the strategy.
+    pub fn validate(&self) -> ManagerResult<()> {
+        match self {
+            RateStrategyKind::DebtInFront => Ok(()),
+            RateStrategyKind::Percentile { percentile, .. } => {
+                if *percentile == 0 || *percentile > 100 {
+                    return Err(ManagerError::Custom(
+                        "The percentile must be between 1 and 100.".to_string(),
+                    ));
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Returns the implementation of the strategy.
+    pub fn engine(&self) -> Box<dyn RateStrategy> {
+        match *self {
+            RateStrategyKind::DebtInFront => Box::new(DebtInFrontTargeting),
+            RateStrategyKind::Percentile {
+                percentile,
+                spread_bps,
+            } => Box::new(PercentileTracking {
+                percentile,
+                spread: Bps(spread_bps).to_rate().0,
+            }),
+        }
+    }
+}
+
+/// Market state a rate strategy decides on
+pub struct RateInputs<'a> {
+    /// Troves of the branch in ascending rate order, including the batch
+    pub troves: &'a [DebtPerInterestRate],
+    /// Batch manager of the strategy
+    pub batch_manager: Address,
+    /// Current rate of the batch
+    pub latest_rate: U256,
+    /// Debt in front of the batch
+    pub current_debt_in_front: U256,
+    /// Target percentage of the redeemable debt to keep in front
+    pub target_percentage: U256,
+    /// Maximum debt redeemable against the branch's collateral
+    pub maximum_redeemable_against_collateral: U256,
+    /// Seconds since the last rate adjustment
+    pub time_since_last_update: U256,
+    /// Upfront fee period in seconds
+    pub upfront_fee_period: U256,
+    /// Added to the rate of the trove the batch is positioned after
+    pub increment: U256,
+}
+
+impl RateInputs<'_> {
+    /// Returns the troves that are not part of the batch.
+    fn market(&self) -> impl Iterator<Item = &DebtPerInterestRate> {
+        self.troves
+            .iter()
+            .filter(move |trove| trove.interestBatchManager != self.batch_manager)
+    }
+
+    /// Returns the target debt in front of the batch.
+    pub fn target_debt(&self) -> U256 {
+        self.target_percentage * self.maximum_redeemable_against_collateral / scale()
+    }
+
+    /// Returns the debt that would be in front of the batch at `rate`, if the market stays unchanged.
+    pub fn expected_debt_in_front(&self, rate: U256) -> U256 {
+        self.market()
+            .filter(|trove| trove.interestRate < rate)
+            .fold(U256::ZERO, |debt, trove| debt.saturating_add(trove.debt))
+    }
+}
+
+/// Outcome of one condition evaluated by `RateStrategy::should_adjust`
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq)]
+pub struct ConditionCheck {
+    /// Name of the check, e.g. `increase`
+    pub name: String,
+    /// `true` if the condition held
+    pub passed: bool,
+    /// Compared values, as journaled
+    pub detail: String,
+}
+
+impl ConditionCheck {
+    fn new(name: &str, passed: bool, detail: String) -> Self {
+        Self {
+            name: name.to_string(),
+            passed,
+            detail,
+        }
+    }
+}
+
+/// Decision logic of a strategy
+pub trait RateStrategy {
+    /// Proposes a new rate for the batch. Zero means no rate could be determined.
+    fn calculate_new_rate(
+        &self,
+        journal: &mut JournalCollection,
+        inputs: &RateInputs,
+    ) -> ManagerResult<U256>;
+
+    /// Decides whether adjusting to `new_rate` is worth its upfront fee.
+    ///
+    /// Every condition evaluated on the way is pushed to `checks`, in evaluation order.
+    fn should_adjust(
+        &self,
+        journal: &mut JournalCollection,
+        inputs: &RateInputs,
+        new_rate: U256,
+        upfront_fee: U256,
+        checks: &mut Vec<ConditionCheck>,
+    ) -> ManagerResult<bool>;
+}
+
+/// Keeps a target amount of debt in front of the batch, positioning it one increment
+/// (1 bps by default) above the trove at which the target is reached.
+pub struct DebtInFrontTargeting;
+
+impl RateStrategy for DebtInFrontTargeting {
+    fn calculate_new_rate(
+        &self,
+        journal: &mut JournalCollection,
+        inputs: &RateInputs,
+    ) -> ManagerResult<U256> {
+        let mut counted_debt = U256::ZERO;
+        let mut new_rate = U256::ZERO;
+        let target_debt = inputs.target_debt();
+
+        journal.append_note(
+            Ok(()),
+            LogType::Info,
+            format!("Calculated target debt in front: {}, number of troves in this collateral market (including the batch): {}",
+            target_debt,
+            inputs.troves.len()),
+        );
+
+        if target_debt == U256::ZERO {
+            return Err(ManagerError::Permanent(
+                "The target amount is zero. Not proceeding.".to_string(),
+            ));
+        }
+
+        for trove in inputs.market() {
+            counted_debt = counted_debt
+                .checked_add(trove.debt)
+                .ok_or_else(|| arithmetic_err("Counted debt overflowed."))?;
+
+            if counted_debt > target_debt {
+                new_rate = trove.interestRate.saturating_add(inputs.increment);
+
+                journal.append_note(
+                    Ok(()),
+                    LogType::Info,
+                    format!("Positioning the batch after trove with debt {}", trove.debt),
+                );
+                break;
+            }
+        }
+
+        if let Some(last_trove) = inputs.troves.last() {
+            if new_rate == U256::ZERO && last_trove.interestBatchManager != inputs.batch_manager {
+                // There was not enough debt in the market
+                // the trove should be positioned at the end of the market.
+                new_rate = last_trove.interestRate.saturating_add(inputs.increment);
+
+                journal.append_note(
+                    Ok(()),
+                    LogType::Info,
+                    "Not enough debt in the market, moving the batch to the end.",
+                );
+            }
+        }
+
+        Ok(new_rate)
+    }
+
+    fn should_adjust(
+        &self,
+        journal: &mut JournalCollection,
+        inputs: &RateInputs,
+        new_rate: U256,
+        upfront_fee: U256,
+        checks: &mut Vec<ConditionCheck>,
+    ) -> ManagerResult<bool> {
+        Ok(increase_check(journal, inputs, checks)
+            || (first_decrease_check(journal, inputs, checks)
+                && second_decrease_check(journal, inputs, new_rate, upfront_fee, checks)?))
+    }
+}
+
+/// Tracks a debt-weighted percentile of the market rates plus a spread.
+///
+/// With `percentile` 50 and a spread of 10 bps, the batch is positioned 10 bps above the
+/// rate below which half of the market's debt sits.
+pub struct PercentileTracking {
+    /// Percentile of the market debt, from 1 to 100
+    pub percentile: u8,
+    /// Spread added to the percentile rate
+    pub spread: U256,
+}
+
+impl RateStrategy for PercentileTracking {
+    fn calculate_new_rate(
+        &self,
+        journal: &mut JournalCollection,
+        inputs: &RateInputs,
+    ) -> ManagerResult<U256> {
+        let market_debt = inputs
+            .market()
+            .try_fold(U256::ZERO, |total, trove| total.checked_add(trove.debt))
+            .ok_or_else(|| arithmetic_err("Market debt overflowed."))?;
+
+        if market_debt == U256::ZERO {
+            journal.append_note(
+                Ok(()),
+                LogType::Info,
+                "There is no debt in the market outside the batch.",
+            );
+            return Ok(U256::ZERO);
+        }
+
+        let percentile_debt =
+            market_debt.saturating_mul(U256::from(self.percentile)) / U256::from(100);
+        let mut counted_debt = U256::ZERO;
+        let mut percentile_rate = U256::ZERO;
+        for trove in inputs.market() {
+            counted_debt = counted_debt.saturating_add(trove.debt);
+            percentile_rate = trove.interestRate;
+            if counted_debt >= percentile_debt {
+                break;
+            }
+        }
+
+        let new_rate = percentile_rate.saturating_add(self.spread);
+        journal.append_note(
+            Ok(()),
+            LogType::Info,
+            format!(
+                "The {}th percentile of the market rates is {} (market debt: {}). Positioning the batch at {}.",
+                self.percentile,
+                Rate(percentile_rate),
+                Wei(market_debt),
+                Rate(new_rate)
+            ),
+        );
+        Ok(new_rate)
+    }
+
+    fn should_adjust(
+        &self,
+        journal: &mut JournalCollection,
+        inputs: &RateInputs,
+        new_rate: U256,
+        upfront_fee: U256,
+        checks: &mut Vec<ConditionCheck>,
+    ) -> ManagerResult<bool> {
+        let detail = format!("{} > {}", new_rate, inputs.latest_rate);
+        let increase = new_rate > inputs.latest_rate;
+        checks.push(ConditionCheck::new("increase", increase, detail.clone()));
+        if increase {
+            journal.append_note(Ok(()), LogType::Info, format!("increase check: {}", detail));
+            return Ok(true);
+        }
+        second_decrease_check(journal, inputs, new_rate, upfront_fee, checks)
+    }
+}
+
+/// Validates rate increase conditions
+fn increase_check(
+    journal: &mut JournalCollection,
+    inputs: &RateInputs,
+    checks: &mut Vec<ConditionCheck>,
+) -> bool {
+    let target_debt_with_margin =
+        inputs.target_debt() * (scale() - tolerance_margin_down()) / scale();
+
+    let detail = format!(
+        "{} < {}",
+        inputs.current_debt_in_front, target_debt_with_margin
+    );
+    journal.append_note(Ok(()), LogType::Info, format!("increase check: {}", detail));
+
+    let passed = inputs.current_debt_in_front < target_debt_with_margin;
+    checks.push(ConditionCheck::new("increase", passed, detail));
+    passed
+}
+
+/// First phase decrease validation
+fn first_decrease_check(
+    journal: &mut JournalCollection,
+    inputs: &RateInputs,
+    checks: &mut Vec<ConditionCheck>,
+) -> bool {
+    let target_debt_with_margin =
+        inputs.target_debt() * (scale() + tolerance_margin_up()) / scale();
+
+    let detail = format!(
+        "{} > {}",
+        inputs.current_debt_in_front, target_debt_with_margin
+    );
+    journal.append_note(
+        Ok(()),
+        LogType::Info,
+        format!("first decrease check: {}", detail),
+    );
+
+    let passed = inputs.current_debt_in_front > target_debt_with_margin;
+    checks.push(ConditionCheck::new("first_decrease", passed, detail));
+    passed
+}
+
+/// Second phase decrease validation: the rate decrease must outweigh the upfront fee,
+/// which is amortized over the upfront fee period.
+fn second_decrease_check(
+    journal: &mut JournalCollection,
+    inputs: &RateInputs,
+    new_rate: U256,
+    average_rate: U256,
+    checks: &mut Vec<ConditionCheck>,
+) -> ManagerResult<bool> {
+    let time_since_last_update = inputs.time_since_last_update;
+    let upfront_fee_period = inputs.upfront_fee_period;
+
+    // Check if time exceeds period first
+    if time_since_last_update > upfront_fee_period {
+        journal.append_note(
+            Ok(()),
+            LogType::Info,
+            "second decrease check passed: time exceeded period",
+        );
+        checks.push(ConditionCheck::new(
+            "second_decrease",
+            true,
+            format!(
+                "time since last update {} > upfront fee period {}",
+                time_since_last_update, upfront_fee_period
+            ),
+        ));
+        return Ok(true);
+    }
+
+    // Scale the division by 100 to get 2 decimal places
+    let scale_hundred: U256 = U256::from(100);
+
+    // Calculate r with 2 decimal precision
+    let scaled_time = time_since_last_update
+        .checked_mul(scale_hundred)
+        .ok_or(arithmetic_err("Overflow in time scaling"))?;
+
+    let r = scaled_time
+        .checked_div(upfront_fee_period)
+        .ok_or(arithmetic_err("Upfront fee period was 0."))?;
+
+    journal.append_note(
+        Ok(()),
+        LogType::Info,
+        format!(
+            "second decrease check: time since last update {} upfront fee period {} latest rate {} new rate {} average rate {} scaled r {}",
+            time_since_last_update,
+            upfront_fee_period,
+            inputs.latest_rate,
+            new_rate,
+            average_rate,
+            r
+        )
+    );
+
+    // For the main condition, we need to scale the computation
+    // (1 - r/100) * (latest_rate - new_rate) > average_rate
+    let scaled_diff = scale_hundred
+        .checked_sub(r)
+        .ok_or(arithmetic_err("Error in r subtraction"))?;
+
+    let rate_diff = inputs
+        .latest_rate
+        .checked_sub(new_rate)
+        .ok_or(arithmetic_err("Error in rate difference calculation"))?;
+
+    let scaled_product = scaled_diff
+        .checked_mul(rate_diff)
+        .ok_or(arithmetic_err("Overflow in scaled product"))?;
+
+    let scaled_average = average_rate
+        .checked_mul(scale_hundred)
+        .ok_or(arithmetic_err("Error in scaling average rate"))?;
+
+    let passed = scaled_product > scaled_average;
+    checks.push(ConditionCheck::new(
+        "second_decrease",
+        passed,
+        format!(
+            "(100 - {}) * ({} - {}) > {} * 100",
+            r, inputs.latest_rate, new_rate, average_rate
+        ),
+    ));
+    if passed {
+        journal.append_note(
+            Ok(()),
+            LogType::Info,
+            "second decrease check passed: rate condition",
+        );
+    }
+
+    Ok(passed)
+}
+
+/// Returns the position of the first trove whose interest rate is lower than the rate of the
+/// trove before it, or `None` if the rates are non-decreasing.
+///
+/// The sorted troves list is ordered by interest rate, so a decrease means that troves moved
+/// between the pages of a paginated fetch.
+pub fn first_unsorted_trove(troves: &[DebtPerInterestRate]) -> Option<usize> {
+    troves
+        .windows(2)
+        .position(|pair| pair[1].interestRate < pair[0].interestRate)
+        .map(|index| index + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bps(value: u64) -> U256 {
+        U256::from(value) * U256::from(BASIS_POINT)
+    }
+
+    fn trove(batch_manager: Address, rate_bps: u64, debt: u64) -> DebtPerInterestRate {
+        DebtPerInterestRate {
+            interestBatchManager: batch_manager,
+            interestRate: bps(rate_bps),
+            debt: U256::from(debt),
+        }
+    }
+
+    #[test]
+    fn test_first_unsorted_trove() {
+        let manager = Address::repeat_byte(1);
+        let sorted = [
+            trove(manager, 100, 1),
+            trove(manager, 100, 2),
+            trove(manager, 200, 3),
+        ];
+        assert_eq!(first_unsorted_trove(&sorted), None);
+        assert_eq!(first_unsorted_trove(&[]), None);
+
+        let moved = [
+            trove(manager, 100, 1),
+            trove(manager, 300, 2),
+            trove(manager, 200, 3),
+        ];
+        assert_eq!(first_unsorted_trove(&moved), Some(2));
+    }
+
+    #[test]
+    fn batch_position_aggregates_multiple_buckets() {
+        let batch = Address::repeat_byte(1);
+        let market = Address::ZERO;
+        let troves = [
+            trove(market, 100, 10),
+            trove(batch, 200, 5),
+            trove(market, 300, 20),
+            trove(batch, 400, 7),
+            trove(batch, 400, 1),
+        ];
+
+        let position = batch_position(&troves, batch).unwrap();
+        assert_eq!(position.debt_in_front, U256::from(10));
+        assert_eq!(position.batch_debt, U256::from(13));
+        assert_eq!(position.rates, vec![bps(200), bps(400)]);
+
+        assert_eq!(batch_position(&troves[..1], batch), None);
+    }
+
+    fn inputs(troves: &[DebtPerInterestRate], batch_manager: Address) -> RateInputs<'_> {
+        RateInputs {
+            troves,
+            batch_manager,
+            latest_rate: bps(500),
+            current_debt_in_front: U256::ZERO,
+            target_percentage: scale() / U256::from(2),
+            maximum_redeemable_against_collateral: U256::from(100),
+            time_since_last_update: U256::ZERO,
+            upfront_fee_period: U256::from(7 * 24 * 3600),
+            increment: bps(1),
+        }
+    }
+
+    #[test]
+    fn test_debt_in_front_positions_after_target() {
+        let batch = Address::repeat_byte(1);
+        let troves = [
+            trove(Address::ZERO, 100, 30),
+            trove(Address::ZERO, 200, 30),
+            trove(batch, 300, 10),
+            trove(Address::ZERO, 400, 40),
+        ];
+        let mut journal = JournalCollection::open(None);
+
+        // target debt is 50% of 100
+        let rate = DebtInFrontTargeting
+            .calculate_new_rate(&mut journal, &inputs(&troves, batch))
+            .unwrap();
+        assert_eq!(rate, bps(201));
+    }
+
+    #[test]
+    fn test_should_adjust_records_checks() {
+        let batch = Address::repeat_byte(1);
+        let troves = [trove(Address::ZERO, 100, 60), trove(batch, 300, 10)];
+        let mut journal = JournalCollection::open(None);
+        let mut checks = vec![];
+
+        // 100 in front of a target of 50: no increase, and a decrease within the period
+        // that does not outweigh the upfront fee
+        let mut inputs = inputs(&troves, batch);
+        inputs.current_debt_in_front = U256::from(100);
+        let adjust = DebtInFrontTargeting
+            .should_adjust(&mut journal, &inputs, bps(499), bps(10), &mut checks)
+            .unwrap();
+        assert!(!adjust);
+        let outcomes: Vec<(&str, bool)> = checks
+            .iter()
+            .map(|check| (check.name.as_str(), check.passed))
+            .collect();
+        assert_eq!(
+            outcomes,
+            vec![
+                ("increase", false),
+                ("first_decrease", true),
+                ("second_decrease", false)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_expected_debt_in_front() {
+        let batch = Address::repeat_byte(2);
+        let troves = [
+            trove(Address::ZERO, 100, 40),
+            trove(batch, 150, 1_000),
+            trove(Address::ZERO, 200, 60),
+            trove(Address::ZERO, 300, 10),
+        ];
+        let inputs = inputs(&troves, batch);
+
+        assert_eq!(inputs.target_debt(), U256::from(50));
+        assert_eq!(inputs.expected_debt_in_front(bps(100)), U256::ZERO);
+        assert_eq!(inputs.expected_debt_in_front(bps(201)), U256::from(100));
+    }
+
+    #[test]
+    fn test_custom_increment_and_rounding() {
+        let batch = Address::repeat_byte(1);
+        let troves = [trove(Address::ZERO, 100, 60), trove(batch, 300, 10)];
+        let mut journal = JournalCollection::open(None);
+        let mut inputs = inputs(&troves, batch);
+        inputs.increment = bps(2);
+
+        let rate = DebtInFrontTargeting
+            .calculate_new_rate(&mut journal, &inputs)
+            .unwrap();
+        assert_eq!(rate, bps(102));
+
+        // rounded up to the next 0.05%
+        let granularity = RateGranularity {
+            increment: bps(2).to::<u64>(),
+            rounding_step: Some(bps(5).to::<u64>()),
+        };
+        assert_eq!(granularity.round(rate), bps(105));
+        assert_eq!(granularity.round(bps(105)), bps(105));
+        assert_eq!(RateGranularity::default().round(rate), rate);
+    }
+
+    #[test]
+    fn test_granularity_validation() {
+        assert!(RateGranularity::default().validate().is_ok());
+        assert!(RateGranularity {
+            increment: 0,
+            rounding_step: None
+        }
+        .validate()
+        .is_err());
+        assert!(RateGranularity {
+            increment: BASIS_POINT as u64,
+            rounding_step: Some(MAX_RATE_STEP + 1)
+        }
+        .validate()
+        .is_err());
+    }
+
+    #[test]
+    fn test_percentile_tracking() {
+        let batch = Address::repeat_byte(1);
+        let troves = [
+            trove(Address::ZERO, 100, 25),
+            trove(batch, 150, 1_000),
+            trove(Address::ZERO, 200, 25),
+            trove(Address::ZERO, 300, 50),
+        ];
+        let mut journal = JournalCollection::open(None);
+        let engine = RateStrategyKind::Percentile {
+            percentile: 50,
+            spread_bps: 10,
+        }
+        .engine();
+
+        let rate = engine
+            .calculate_new_rate(&mut journal, &inputs(&troves, batch))
+            .unwrap();
+        assert_eq!(rate, bps(210));
+    }
+
+    #[test]
+    fn test_percentile_validation() {
+        assert!(RateStrategyKind::DebtInFront.validate().is_ok());
+        for percentile in [0, 101] {
+            assert!(RateStrategyKind::Percentile {
+                percentile,
+                spread_bps: 0
+            }
+            .validate()
+            .is_err());
+        }
+    }
+}
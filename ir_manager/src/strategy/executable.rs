@@ -30,24 +30,68 @@ use std::ops::Div;
 
 use alloy_primitives::{Address, U256};
 use alloy_sol_types::SolCall;
-use ic_exports::ic_cdk::{api::time, print};
+use candid::Int;
+use futures::future::join_all;
+use ic_exports::ic_cdk::print;
+use num_bigint::BigInt;
 
 use crate::{
+    clock::Seconds,
     constants::{
-        max_number_of_troves, scale, tolerance_margin_down, tolerance_margin_up, MAX_RETRY_ATTEMPTS,
+        max_number_of_troves, DEFAULT_AUTO_UNLOCK_WARNING_THRESHOLD, DEFAULT_MAX_BLOCK_AGE,
+        DEFAULT_MAX_CONTEXT_AGE, DEFAULT_MAX_TROVE_PAGES, GAS_BUDGET_WINDOW, MAX_RETRY_ATTEMPTS,
+        UPFRONT_FEE_BUDGET_WINDOW,
+    },
+    digest::raise_alert,
+    flags::{
+        flag_enabled, flag_int, AUTO_UNLOCK_WARNING_THRESHOLD, MAX_BLOCK_AGE, MAX_CONTEXT_AGE,
+        MAX_TROVE_PAGES, MULTICALL_READS, TX_POOL_POLLING,
     },
     journal::{JournalCollection, LogType},
+    metrics::record_state_conflict,
+    positioning_history::{record_positioning_sample, PositioningSample},
+    providers::ProviderPool,
+    runs::{
+        record_positioning_check, record_target_derivation, PositioningCheck, TargetDerivation,
+    },
     state::{MANAGERS, STRATEGY_STATE},
+    tx_pool::{
+        gas_spent_since, pending_transactions, poll_receipts, transactions_for_nonce, TxRecord,
+    },
     types::*,
     utils::{
         common::*,
         error::*,
         evm_rpc::{BlockTag, SendRawTransactionStatus},
-        transaction_builder::TransactionBuilder,
+        gas::{estimate_transaction_fees, get_estimate_gas},
+        multicall::MulticallBatch,
+        nonce::{reconcile_nonce, NonceLease},
+        transaction_builder::{
+            replace_stuck_transactions, replace_transaction, Replacement, ReplacementParams,
+            TransactionBuilder,
+        },
+        units::{Rate, Wei},
+        user_operation::{send_user_operation, UserOperationConfig},
     },
 };
 
-use super::{data::StrategyData, lock::Lock, settings::StrategySettings};
+use super::{
+    batch::fetch_batch_manager_params,
+    contention::{record_auto_unlock, record_lock_contention},
+    data::{ExpectedPositioning, PendingRetry, SafetyTrip, StrategyData},
+    engine::{batch_position, first_unsorted_trove, ConditionCheck, RateInputs},
+    lock::Lock,
+    preview::{AdjustmentPreview, StrategySimulation},
+    registry::registry_call,
+    run::schedule_rate_adjustment_retry,
+    settings::{ExecutionBackend, StrategySettings},
+    snapshot::{MarketSnapshot, TroveBucket},
+    stable::StableStrategy,
+    troves::{
+        is_end_marker, reconcile_segments, record_trove_layout, trove_fetch_concurrency,
+        trove_layout, TroveLayout,
+    },
+};
 
 /// An atomic execution context that manages rate adjustments while maintaining
 /// strict state consistency. Implements sophisticated concurrency control through
@@ -85,6 +129,10 @@ pub struct ExecutableStrategy {
     pub lock: Lock,
     /// Lock acquisition status for clean Drop behavior
     acquired_lock: bool,
+    /// Revision of the stored strategy this instance was loaded from or last wrote
+    pub revision: u64,
+    /// `true` if the instance never writes back to the state, e.g. for previews
+    detached: bool,
 }
 
 // State management functions
@@ -96,24 +144,100 @@ impl ExecutableStrategy {
             data,
             lock,
             acquired_lock: false,
+            revision: 0,
+            detached: false,
+        }
+    }
+
+    /// Creates an instance that never writes back to the state.
+    ///
+    /// Used to run the read-only part of the pipeline without taking the lock, as in
+    /// `simulate`, `market_snapshot`, and `preview_adjustment`: nothing is locked, signed, or
+    /// stored.
+    pub fn detached(strategy: &StableStrategy) -> ExecutableStrategy {
+        ExecutableStrategy {
+            detached: true,
+            ..strategy.into()
         }
     }
 
     /// Updates strategy state in persistent storage.
-    fn apply_change(&self) {
-        STRATEGY_STATE.with(|strategies| {
-            strategies
-                .borrow_mut()
-                .insert(self.settings.key, self.into());
+    ///
+    /// If the stored strategy was written since this instance loaded it, both writes are
+    /// merged instead of clobbering the newer one, and the conflict is journaled.
+    fn apply_change(&mut self) {
+        if self.detached {
+            return;
+        }
+        let key = self.settings.key;
+        let mut written = StableStrategy::from(&*self);
+
+        let conflict = STRATEGY_STATE.with(|strategies| {
+            let mut strategies = strategies.borrow_mut();
+            let conflict = match strategies.get(&key) {
+                Some(stored) if stored.revision != self.revision => {
+                    written = stored.merge_executor_state(&written);
+                    Some(stored.revision)
+                }
+                _ => None,
+            };
+            written.revision = written.revision.wrapping_add(1);
+            strategies.insert(key, written.clone());
+            conflict
         });
+
+        self.revision = written.revision;
+        if let Some(stored_revision) = conflict {
+            self.settings = written.settings;
+            self.data = written.data;
+            record_state_conflict();
+            JournalCollection::open(Some(key)).append_note(
+                Ok(()),
+                LogType::Info,
+                format!(
+                    "WARNING: The strategy was written by another task (revision {}) since it was loaded. Merged the changes into revision {}.",
+                    stored_revision, self.revision
+                ),
+            );
+        }
     }
 
     /// Acquires execution lock with state consistency guarantees.
-    fn lock(&mut self) -> ManagerResult<()> {
-        self.lock.try_lock().map(|_| {
-            self.acquired_lock = true;
-            self.apply_change();
-        })
+    ///
+    /// Refused acquisitions and expired locks released by the timeout are counted in the
+    /// lock contention statistics.
+    fn lock(&mut self, journal: &mut JournalCollection) -> ManagerResult<()> {
+        let key = self.settings.key;
+        let now = Seconds::now().get();
+
+        let auto_unlocked = match self.lock.try_lock() {
+            Ok(auto_unlocked) => auto_unlocked,
+            Err(err) => {
+                record_lock_contention(key, now);
+                return Err(err);
+            }
+        };
+        self.acquired_lock = true;
+        self.apply_change();
+
+        if auto_unlocked {
+            let recent = record_auto_unlock(key, now);
+            let threshold = flag_int!(
+                AUTO_UNLOCK_WARNING_THRESHOLD,
+                DEFAULT_AUTO_UNLOCK_WARNING_THRESHOLD
+            );
+            let note = if recent as i64 > threshold {
+                format!(
+                    "WARNING: Released an expired lock left by a previous run. The strategy had {} auto-unlocks within the last week.",
+                    recent
+                )
+            } else {
+                "Released an expired lock left by a previous run.".to_string()
+            };
+            journal.append_note(Ok(()), LogType::Info, note);
+        }
+
+        Ok(())
     }
 
     /// Releases execution lock and persists final state.
@@ -126,6 +250,8 @@ impl ExecutableStrategy {
 #[derive(Clone)]
 struct ExecutionContext {
     pub block_tag: BlockTag,
+    /// Timestamp in seconds of the block the context is built on
+    pub block_timestamp: u64,
     pub troves: Vec<DebtPerInterestRate>,
     pub maximum_redeemable_against_collateral: U256,
     pub target_percentage: U256,
@@ -133,60 +259,114 @@ struct ExecutionContext {
     pub troves_count: U256,
 }
 
+/// A page of sorted troves and the ID to continue from
+type TrovePage = (Vec<DebtPerInterestRate>, U256);
+
+/// Market-wide reads of an execution context, taken at the same block
+struct MarketReads {
+    /// Debt of every registered branch
+    entire_system_debt: U256,
+    /// Unbacked portion of the strategy's branch
+    unbacked_portion: U256,
+    /// Unbacked portion of every registered branch
+    total_unbacked: U256,
+    /// Redemption fee rate with decay
+    redemption_fee: U256,
+    /// First page of the sorted troves, if it was read along
+    first_trove_page: Option<TrovePage>,
+}
+
 // Query functions that gather the execution context required for running the strategy
 impl ExecutableStrategy {
+    /// Gathers the execution context of a run.
+    ///
+    /// Returns `None` if the market has no troves, in which case there is nothing to position
+    /// the batch against.
     async fn prepare_execution_context(
         &self,
         journal: &mut JournalCollection,
-    ) -> ManagerResult<ExecutionContext> {
-        // Fetch the current block tag
-        let block_tag = get_block_tag(&self.settings.rpc_canister, true).await?;
-        journal.append_note(
-            Ok(()),
-            LogType::Info,
-            format!("Fixed block tag: {:?}.", block_tag),
-        );
-
-        // Calculate time since last update
-        let time_since_last_update = U256::from(time().div(1_000_000_000) - self.data.last_update);
-
-        // Fetch the entire system debt from the blockchain
-        let entire_system_debt: U256 = self.fetch_entire_system_debt(block_tag.clone()).await?;
+    ) -> ManagerResult<Option<ExecutionContext>> {
+        // Fetch the block, the market reads, and the troves.
+        // Every page is read at the same block, but the pages are fetched by separate consensus
+        // calls, and a provider may serve the list in an inconsistent state. Troves out of
+        // order show up as decreasing interest rates, in which case everything is fetched once
+        // more at a newer block, so that the market reads and the troves stay consistent.
+        let mut inconsistent_block: Option<BlockTag> = None;
+        let (block_tag, block_timestamp, market_reads, troves) = loop {
+            let (block_tag, block_timestamp) = self.fetch_fresh_block(journal).await?;
+            if inconsistent_block.as_ref() == Some(&block_tag) {
+                let err = ManagerError::Custom(format!(
+                    "The trove list is inconsistent across pages at {:?}, and no newer block is available yet.",
+                    block_tag
+                ));
+                journal.append_note(
+                    Err(err.clone()),
+                    LogType::Info,
+                    "The troves could not be fetched again at a newer block.",
+                );
+                return Err(err);
+            }
+            journal.append_note(
+                Ok(()),
+                LogType::Info,
+                format!(
+                    "Fixed block tag: {:?} (timestamp: {}).",
+                    block_tag, block_timestamp
+                ),
+            );
 
-        // Fetch the unbacked portion price and redeemability status
-        let unbacked_portion = self
-            .fetch_unbacked_portion_price_and_redeemablity(None, block_tag.clone())
-            .await?
-            ._0;
+            // Fetch the branch debts, the unbacked portions, and the redemption fee rate
+            let mut market_reads = if flag_enabled!(MULTICALL_READS) {
+                self.fetch_market_reads_batched(&block_tag).await?
+            } else {
+                self.fetch_market_reads(&block_tag).await?
+            };
 
-        // Fetch and collect troves
-        let mut troves: Vec<DebtPerInterestRate> = vec![];
-        let mut troves_index = U256::from(0);
-        let max_count = max_number_of_troves();
-        loop {
-            let (fetched_troves, curr_id) = self
-                .fetch_multiple_sorted_troves(troves_index, max_count, block_tag.clone())
+            // Fetch and collect troves
+            let troves = self
+                .fetch_troves(journal, &block_tag, market_reads.first_trove_page.take())
                 .await?;
-
-            let last_trove = fetched_troves
-                .last()
-                .ok_or(ManagerError::NonExistentValue)?
-                .clone();
-            troves.extend(fetched_troves);
-            if last_trove.debt == U256::ZERO && last_trove.interestRate == U256::ZERO {
-                break;
+            let Some(position) = first_unsorted_trove(&troves) else {
+                break (block_tag, block_timestamp, market_reads, troves);
+            };
+            if inconsistent_block.is_some() {
+                let err = ManagerError::Custom(format!(
+                    "The interest rates of the fetched troves decrease at position {}.",
+                    position
+                ));
+                journal.append_note(
+                    Err(err.clone()),
+                    LogType::Info,
+                    "The trove list is still inconsistent across pages after fetching it again at a newer block.",
+                );
+                return Err(err);
             }
-            troves_index = curr_id;
-        }
-
-        troves.retain(|trove| trove.debt != U256::ZERO && trove.interestRate != U256::ZERO);
-        let troves_count = U256::from(troves.len());
+            journal.append_note(
+                Ok(()),
+                LogType::Info,
+                format!(
+                    "WARNING: The interest rates of the fetched troves decrease at position {}. Fetching the market again at a newer block.",
+                    position
+                ),
+            );
+            inconsistent_block = Some(block_tag);
+        };
+        let MarketReads {
+            entire_system_debt,
+            unbacked_portion,
+            total_unbacked,
+            redemption_fee,
+            ..
+        } = market_reads;
 
-        // Fetch the redemption fee rate
-        let redemption_fee = self.fetch_redemption_rate(block_tag.clone()).await?;
+        // Calculate time since last update
+        let time_since_last_update =
+            U256::from(Seconds::now().since(Seconds(self.data.last_update)).get());
 
-        // Calculate the total unbacked collateral
-        let total_unbacked = self.fetch_total_unbacked(block_tag.clone()).await?;
+        if troves.is_empty() {
+            return Ok(None);
+        }
+        let troves_count = U256::from(troves.len());
 
         if total_unbacked == U256::ZERO {
             return Err(arithmetic_err("total unbacked was 0."));
@@ -205,7 +385,8 @@ impl ExecutableStrategy {
             .saturating_mul(U256::from(100))
             .div(total_unbacked);
 
-        let maximum_redeemable_against_collateral = if two_digit_accuracy_split < U256::from(1) {
+        let one_percent_floor = two_digit_accuracy_split < U256::from(1);
+        let maximum_redeemable_against_collateral = if one_percent_floor {
             // less than 1% split
             // we saturate the split at 1%
             entire_system_debt.div(U256::from(100))
@@ -239,16 +420,356 @@ impl ExecutableStrategy {
             ),
         );
 
-        Ok(ExecutionContext {
+        if let Some(run_id) = journal.run_id {
+            let block_number = match &block_tag {
+                BlockTag::Number(number) => Some(number.clone()),
+                _ => None,
+            };
+            record_target_derivation(
+                run_id,
+                TargetDerivation {
+                    block_number,
+                    redemption_fee: u256_to_nat(&redemption_fee)?,
+                    target_percentage_numerator: u256_to_nat(&target_percentage_numerator)?,
+                    target_percentage_denominator: u256_to_nat(&target_percentage_denominator)?,
+                    target_percentage: u256_to_nat(&target_percentage)?,
+                    unbacked_portion: u256_to_nat(&unbacked_portion)?,
+                    total_unbacked: u256_to_nat(&total_unbacked)?,
+                    entire_system_debt: u256_to_nat(&entire_system_debt)?,
+                    one_percent_floor,
+                    maximum_redeemable_against_collateral: u256_to_nat(
+                        &maximum_redeemable_against_collateral,
+                    )?,
+                },
+            );
+        }
+
+        Ok(Some(ExecutionContext {
             block_tag,
+            block_timestamp,
             troves,
             maximum_redeemable_against_collateral,
             target_percentage,
             time_since_last_update,
             troves_count,
+        }))
+    }
+
+    /// Reads the branch debts, the unbacked portions, and the redemption fee rate one call
+    /// at a time.
+    async fn fetch_market_reads(&self, block_tag: &BlockTag) -> ManagerResult<MarketReads> {
+        Ok(MarketReads {
+            entire_system_debt: self.fetch_entire_system_debt(block_tag.clone()).await?,
+            unbacked_portion: self
+                .fetch_unbacked_portion_price_and_redeemablity(None, block_tag.clone())
+                .await?
+                ._0,
+            total_unbacked: self.fetch_total_unbacked(block_tag.clone()).await?,
+            redemption_fee: self.fetch_redemption_rate(block_tag.clone()).await?,
+            first_trove_page: None,
+        })
+    }
+
+    /// Reads the branch debts, the unbacked portions, the redemption fee rate, and the first
+    /// trove page in a single Multicall3 call.
+    async fn fetch_market_reads_batched(&self, block_tag: &BlockTag) -> ManagerResult<MarketReads> {
+        let managers: Vec<Address> =
+            MANAGERS.with(|managers_vector| managers_vector.borrow().clone());
+
+        let mut batch = MulticallBatch::default();
+        let debts: Vec<usize> = managers
+            .iter()
+            .map(|manager| batch.add(*manager, &getEntireBranchDebtCall {}))
+            .collect();
+        let unbacked: Vec<usize> = managers
+            .iter()
+            .map(|manager| batch.add(*manager, &getUnbackedPortionPriceAndRedeemabilityCall {}))
+            .collect();
+        let own_unbacked = batch.add(
+            self.settings.manager,
+            &getUnbackedPortionPriceAndRedeemabilityCall {},
+        );
+        let redemption_rate = batch.add(
+            self.settings.collateral_registry,
+            &getRedemptionRateWithDecayCall {},
+        );
+        let first_page = batch.add(
+            self.settings.multi_trove_getter,
+            &getDebtPerInterestRateAscendingCall {
+                _collIndex: self.settings.collateral_index,
+                _startId: U256::ZERO,
+                _maxIterations: max_number_of_troves(),
+            },
+        );
+
+        let results = batch
+            .execute(&self.settings.rpc_canister, block_tag.clone())
+            .await?;
+
+        let mut entire_system_debt = U256::ZERO;
+        for index in debts {
+            entire_system_debt += results
+                .decode::<getEntireBranchDebtCall>(index)?
+                .entireSystemDebt;
+        }
+        let mut total_unbacked = U256::ZERO;
+        for index in unbacked {
+            total_unbacked += results
+                .decode::<getUnbackedPortionPriceAndRedeemabilityCall>(index)?
+                ._0;
+        }
+        let page = results.decode::<getDebtPerInterestRateAscendingCall>(first_page)?;
+
+        Ok(MarketReads {
+            entire_system_debt,
+            unbacked_portion: results
+                .decode::<getUnbackedPortionPriceAndRedeemabilityCall>(own_unbacked)?
+                ._0,
+            total_unbacked,
+            redemption_fee: results
+                .decode::<getRedemptionRateWithDecayCall>(redemption_rate)?
+                ._0,
+            first_trove_page: Some((page._0, page.currId)),
         })
     }
 
+    /// Fetches the sorted troves of the market at `block_tag`.
+    ///
+    /// In parallel mode, the segments learned from the previous run are fetched concurrently,
+    /// falling back to sequential fetching if a segment hits its share of the page cap.
+    /// Sequential fetching starts from `first_page` if it was already read.
+    async fn fetch_troves(
+        &self,
+        journal: &mut JournalCollection,
+        block_tag: &BlockTag,
+        first_page: Option<TrovePage>,
+    ) -> ManagerResult<Vec<DebtPerInterestRate>> {
+        let concurrency = trove_fetch_concurrency();
+        let layout = trove_layout(self.settings.key).filter(|_| concurrency > 1);
+
+        let troves = match layout {
+            Some(layout) => match self
+                .fetch_troves_in_parallel(&layout, concurrency, block_tag)
+                .await?
+            {
+                Some(troves) => {
+                    journal.append_note(
+                        Ok(()),
+                        LogType::Info,
+                        format!(
+                            "Fetched {} troves in {} segments.",
+                            troves.len(),
+                            layout.boundaries.len() + 1
+                        ),
+                    );
+                    troves
+                }
+                None => {
+                    journal.append_note(
+                        Ok(()),
+                        LogType::Info,
+                        "A trove segment hit its share of the page cap. Fetching the troves sequentially.",
+                    );
+                    self.fetch_troves_sequentially(journal, block_tag, first_page)
+                        .await?
+                }
+            },
+            None => {
+                self.fetch_troves_sequentially(journal, block_tag, first_page)
+                    .await?
+            }
+        };
+
+        if concurrency > 1 {
+            record_trove_layout(self.settings.key, &troves, concurrency);
+        }
+        Ok(troves)
+    }
+
+    /// Fetches the trove list segments of `layout` concurrently and reconciles them.
+    ///
+    /// Returns `None` if a segment hit its share of the page cap, as the list would then
+    /// miss troves in the middle.
+    async fn fetch_troves_in_parallel(
+        &self,
+        layout: &TroveLayout,
+        concurrency: usize,
+        block_tag: &BlockTag,
+    ) -> ManagerResult<Option<Vec<DebtPerInterestRate>>> {
+        let max_pages = flag_int!(MAX_TROVE_PAGES, DEFAULT_MAX_TROVE_PAGES).max(1) as u64;
+        let segment_count = layout.boundaries.len() + 1;
+        let pages_per_segment = max_pages.div_ceil(segment_count as u64);
+        let troves_count = U256::from(layout.troves_count);
+
+        let bounds: Vec<(Option<U256>, Option<U256>)> = (0..segment_count)
+            .map(|index| {
+                (
+                    index.checked_sub(1).map(|lower| layout.boundaries[lower]),
+                    layout.boundaries.get(index).copied(),
+                )
+            })
+            .collect();
+
+        let mut segments = Vec::with_capacity(segment_count);
+        for chunk in bounds.chunks(concurrency) {
+            let results = join_all(chunk.iter().map(|(lower, upper)| {
+                self.fetch_trove_segment(*lower, *upper, troves_count, pages_per_segment, block_tag)
+            }))
+            .await;
+            for result in results {
+                match result? {
+                    Some(segment) => segments.push(segment),
+                    None => return Ok(None),
+                }
+            }
+        }
+
+        Ok(Some(reconcile_segments(&layout.boundaries, segments)))
+    }
+
+    /// Fetches the troves from the first one with a rate of at least `lower` (the tail of the
+    /// list if `None`) until a page reaches `upper` or the end of the list.
+    ///
+    /// Returns `None` if `max_pages` pages did not suffice.
+    async fn fetch_trove_segment(
+        &self,
+        lower: Option<U256>,
+        upper: Option<U256>,
+        troves_count: U256,
+        max_pages: u64,
+        block_tag: &BlockTag,
+    ) -> ManagerResult<Option<Vec<DebtPerInterestRate>>> {
+        let mut troves_index = match lower {
+            Some(rate) => {
+                let hint = self
+                    .fetch_approximate_hint(rate, troves_count, block_tag.clone())
+                    .await?;
+                // The previous node holds the lowest rate that is at least `rate`
+                let (prev_id, _) = self
+                    .fetch_insert_position(rate, hint, block_tag.clone())
+                    .await?;
+                if prev_id == U256::ZERO {
+                    return Ok(Some(vec![]));
+                }
+                prev_id
+            }
+            None => U256::ZERO,
+        };
+
+        let max_count = max_number_of_troves();
+        let mut troves: Vec<DebtPerInterestRate> = vec![];
+        let mut pages = 0;
+        loop {
+            let (fetched_troves, curr_id) = self
+                .fetch_multiple_sorted_troves(troves_index, max_count, block_tag.clone())
+                .await?;
+            pages += 1;
+
+            let reached_end = fetched_troves.last().map_or(true, is_end_marker);
+            let reached_upper = upper.map_or(false, |upper| {
+                fetched_troves
+                    .iter()
+                    .any(|trove| !is_end_marker(trove) && trove.interestRate >= upper)
+            });
+            troves.extend(fetched_troves);
+            if reached_end || reached_upper {
+                return Ok(Some(troves));
+            }
+            if pages >= max_pages {
+                return Ok(None);
+            }
+            troves_index = curr_id;
+        }
+    }
+
+    /// Fetches the sorted troves of the market at `block_tag`, page by page.
+    ///
+    /// The number of pages is capped to keep the run within the instruction and cycles limits.
+    /// When the cap is hit, the troves with the lowest rates are returned.
+    /// Zero-debt entries marking the end of the list are left out.
+    async fn fetch_troves_sequentially(
+        &self,
+        journal: &mut JournalCollection,
+        block_tag: &BlockTag,
+        first_page: Option<TrovePage>,
+    ) -> ManagerResult<Vec<DebtPerInterestRate>> {
+        let mut prefetched = first_page;
+        let mut troves: Vec<DebtPerInterestRate> = vec![];
+        let mut troves_index = U256::from(0);
+        let max_count = max_number_of_troves();
+        let max_pages = flag_int!(MAX_TROVE_PAGES, DEFAULT_MAX_TROVE_PAGES).max(1) as u64;
+        let mut pages = 0;
+        loop {
+            let (fetched_troves, curr_id) = match prefetched.take() {
+                Some(page) => page,
+                None => {
+                    self.fetch_multiple_sorted_troves(troves_index, max_count, block_tag.clone())
+                        .await?
+                }
+            };
+            pages += 1;
+
+            // An empty page means that the end of the market was reached.
+            let Some(last_trove) = fetched_troves.last().cloned() else {
+                break;
+            };
+            troves.extend(fetched_troves);
+            if is_end_marker(&last_trove) {
+                break;
+            }
+            if pages >= max_pages {
+                journal.append_note(
+                    Ok(()),
+                    LogType::Info,
+                    format!(
+                        "WARNING: Reached the cap of {} trove pages. Calculating the rate from partial data ({} troves).",
+                        max_pages,
+                        troves.len()
+                    ),
+                );
+                break;
+            }
+            troves_index = curr_id;
+        }
+
+        troves.retain(|trove| trove.debt != U256::ZERO && trove.interestRate != U256::ZERO);
+        Ok(troves)
+    }
+
+    /// Fetches the latest block, returning its tag and timestamp.
+    ///
+    /// A block older than `MAX_BLOCK_AGE` seconds means the provider is serving stale data,
+    /// so the block is fetched again from the next provider pool.
+    async fn fetch_fresh_block(
+        &self,
+        journal: &mut JournalCollection,
+    ) -> ManagerResult<(BlockTag, u64)> {
+        let max_age = flag_int!(MAX_BLOCK_AGE, DEFAULT_MAX_BLOCK_AGE).max(0) as u64;
+
+        for pool in [ProviderPool::Read, ProviderPool::Write] {
+            let block = get_block(&self.settings.rpc_canister, true, pool).await?;
+            let timestamp = nat_to_u128(block.timestamp)? as u64;
+            let age = Seconds::now().since(Seconds(timestamp)).get();
+
+            if age <= max_age {
+                return Ok((BlockTag::Number(block.number), timestamp));
+            }
+
+            journal.append_note(
+                Ok(()),
+                LogType::Info,
+                format!(
+                    "WARNING: The {:?} pool served block {} that is {} seconds old (maximum: {}).",
+                    pool, block.number, age, max_age
+                ),
+            );
+        }
+
+        Err(ManagerError::NoConsensus(
+            "All provider pools served stale blocks.".to_string(),
+        ))
+    }
+
     /// Fetches total system debt across all markets
     async fn fetch_entire_system_debt(&self, block_tag: BlockTag) -> ManagerResult<U256> {
         let managers = MANAGERS.with(|managers_vector| managers_vector.borrow().clone());
@@ -374,75 +895,228 @@ impl ExecutableStrategy {
         new_rate: U256,
         max_upfront_fee: U256,
         execution_context: &ExecutionContext,
+    ) -> ManagerResult<()> {
+        let retry = PendingRetry {
+            run_id: journal.run_id,
+            new_rate,
+            max_upfront_fee,
+            troves_count: execution_context.troves_count,
+            context_timestamp: execution_context.block_timestamp,
+            attempt: 1,
+            scheduled_at: Seconds::now().get(),
+        };
+
+        self.attempt_rate_adjustment(journal, retry, execution_context.block_tag.clone())
+            .await
+    }
+
+    /// Submits a single rate adjustment attempt.
+    ///
+    /// If the nonce was wrong, the nonce is resynced and the next attempt is scheduled
+    /// on a timer with exponential backoff, giving the previous transaction time to propagate.
+    async fn attempt_rate_adjustment(
+        &mut self,
+        journal: &mut JournalCollection,
+        retry: PendingRetry,
+        block_tag: BlockTag,
     ) -> ManagerResult<()> {
         let hints = self
-            .calculate_hints(
-                new_rate,
-                execution_context.troves_count,
-                execution_context.block_tag.clone(),
-            )
+            .calculate_hints(retry.new_rate, retry.troves_count, block_tag)
             .await?;
 
         // Prepare the payload for updating the interest rate
-        let payload = setNewRateCall {
-            _newAnnualInterestRate: new_rate.to::<u128>(),
-            _upperHint: hints.0,
-            _lowerHint: hints.1,
-            _maxUpfrontFee: max_upfront_fee.saturating_add(U256::from(1_000_000_000_000_000_u128)), // + %0.001 ,
-        };
+        let payload = set_new_rate_call(retry.new_rate, retry.max_upfront_fee, hints);
+
+        let eoa = self.settings.eoa_pk.ok_or(ManagerError::NonExistentValue)?;
+
+        // The rate must not be signed if the market may have moved since it was calculated
+        let max_context_age = flag_int!(MAX_CONTEXT_AGE, DEFAULT_MAX_CONTEXT_AGE).max(0) as u64;
+        let context_age = Seconds::now().since(Seconds(retry.context_timestamp)).get();
+        if context_age > max_context_age {
+            return Err(ManagerError::Custom(format!(
+                "The execution context is {} seconds old (maximum: {}). Refusing to sign the rate adjustment.",
+                context_age, max_context_age
+            )));
+        }
+
+        if let ExecutionBackend::UserOperation(config) = &self.settings.execution_backend {
+            let config = config.clone();
+            return self
+                .send_rate_adjustment_user_operation(
+                    journal,
+                    &config,
+                    &retry,
+                    block_tag,
+                    payload.abi_encode(),
+                )
+                .await;
+        }
+
+        journal.append_note(
+            Ok(()),
+            LogType::Info,
+            format!(
+                "Sending a rate adjustment transaction with rate: {} (attempt {})",
+                retry.new_rate, retry.attempt
+            ),
+        );
+
+        // Deposits from the same EOA may be signed while this transaction is in flight
+        let lease = NonceLease::acquire(eoa, self.data.eoa_nonce);
+        let result = TransactionBuilder::default()
+            .to(self.settings.batch_manager.to_string())
+            .from(eoa.to_string())
+            .data(payload.abi_encode())
+            .value(U256::ZERO)
+            .nonce(lease.nonce())
+            .derivation_path(self.settings.derivation_path.clone())
+            .cycles(40_000_000_000_u128)
+            .strategy_key(self.settings.key)
+            .run_id(retry.run_id)
+            .fee_policy(self.settings.fee_policy)
+            .journal(journal)
+            .send(&self.settings.rpc_canister)
+            .await?;
+
+        journal.append_note(
+            Ok(()),
+            LogType::Info,
+            "The rate adjustment transaction is sent.",
+        );
 
-        // we want at least 2 runs in case the nonce needs adjustment
-        let max_attempts = MAX_RETRY_ATTEMPTS.max(2);
+        // Handle different transaction statuses
+        if self.handle_transaction_response(
+            journal,
+            result,
+            retry.new_rate,
+            retry.max_upfront_fee,
+            lease,
+        )? {
+            return Ok(());
+        }
 
-        for _ in 1..=max_attempts {
-            let eoa = self
-                .settings
-                .eoa_pk
-                .ok_or(ManagerError::NonExistentValue)?
-                .to_string();
+        self.update_nonce().await?;
 
+        // we want at least 2 attempts in case the nonce needs adjustment
+        if retry.attempt >= MAX_RETRY_ATTEMPTS.max(2) {
             journal.append_note(
                 Ok(()),
                 LogType::Info,
                 format!(
-                    "Sending a rate adjustment transaction with rate: {}",
-                    new_rate
+                    "The rate adjustment transaction was not accepted after {} attempts. Giving up until the next run.",
+                    retry.attempt
                 ),
             );
+            return Ok(());
+        }
 
-            let result = TransactionBuilder::default()
-                .to(self.settings.batch_manager.to_string())
-                .from(eoa)
-                .data(payload.abi_encode())
-                .value(U256::ZERO)
-                .nonce(self.data.eoa_nonce)
-                .derivation_path(self.settings.derivation_path.clone())
-                .cycles(40_000_000_000_u128)
-                .send(&self.settings.rpc_canister)
-                .await?;
+        let delay = self.settings.retry_backoff.delay(retry.attempt);
+        self.data.pending_retry = Some(PendingRetry {
+            attempt: retry.attempt + 1,
+            scheduled_at: Seconds::now().get(),
+            ..retry
+        });
+        self.apply_change();
+        schedule_rate_adjustment_retry(self.settings.key, delay);
 
-            journal.append_note(
-                Ok(()),
-                LogType::Info,
-                "The rate adjustment transaction is sent.",
-            );
+        journal.append_note(
+            Ok(()),
+            LogType::Info,
+            format!(
+                "Resubmitting the rate adjustment transaction in {} seconds.",
+                delay
+            ),
+        );
+        Ok(())
+    }
 
-            // Handle different transaction statuses
-            if self.handle_transaction_response(journal, result, new_rate)? {
-                break;
-            } else {
-                self.update_nonce().await?;
-            }
-        }
+    /// Submits a rate adjustment as a UserOperation of the strategy's smart account.
+    ///
+    /// The nonce is kept by the EntryPoint and the bundler resubmits the operation itself, so
+    /// a rejected operation is left to the next run.
+    async fn send_rate_adjustment_user_operation(
+        &mut self,
+        journal: &mut JournalCollection,
+        config: &UserOperationConfig,
+        retry: &PendingRetry,
+        block_tag: BlockTag,
+        calldata: Vec<u8>,
+    ) -> ManagerResult<()> {
+        journal.append_note(
+            Ok(()),
+            LogType::Info,
+            format!(
+                "Sending a rate adjustment UserOperation with rate: {} to the bundler {}",
+                retry.new_rate, config.bundler_url
+            ),
+        );
+
+        let fees = estimate_transaction_fees(
+            9,
+            &self.settings.rpc_canister,
+            block_tag.clone(),
+            &self.settings.fee_policy,
+        )
+        .await?;
+        let user_op_hash = send_user_operation(
+            config,
+            &self.settings.rpc_canister,
+            block_tag,
+            self.settings.batch_manager,
+            calldata,
+            fees,
+            self.settings.derivation_path.clone(),
+        )
+        .await?;
+
+        journal.append_note(
+            Ok(()),
+            LogType::RateAdjustment,
+            format!(
+                "The rate adjustment UserOperation was accepted by the bundler. UserOperation hash: {}",
+                user_op_hash
+            ),
+        );
+        self.record_rate_adjustment(Some(user_op_hash), retry.new_rate, retry.max_upfront_fee);
         Ok(())
     }
 
-    /// True means break the loop, the tx was successful. False means nonce needs adjustment, continue the loop and adjust. Err means error occured, abort.
+    /// Resumes a rate adjustment that was scheduled by a previous attempt.
+    ///
+    /// The hints are recalculated against a fresh block, as the trove list may have
+    /// changed during the delay.
+    pub async fn resume_rate_adjustment(
+        &mut self,
+        journal: &mut JournalCollection,
+    ) -> ManagerResult<()> {
+        self.lock(journal)?;
+
+        let retry = match self.data.pending_retry.take() {
+            Some(retry) => retry,
+            None => {
+                journal.append_note(
+                    Ok(()),
+                    LogType::Info,
+                    "No rate adjustment resubmission is pending. A newer run has superseded it.",
+                );
+                return Ok(());
+            }
+        };
+        self.apply_change();
+
+        let block_tag = get_block_tag(&self.settings.rpc_canister, true).await?;
+        self.attempt_rate_adjustment(journal, retry, block_tag)
+            .await
+    }
+
+    /// True means the tx was successful. False means nonce needs adjustment, adjust and retry. Err means error occured, abort.
     fn handle_transaction_response(
         &mut self,
         journal: &mut JournalCollection,
         result: SendRawTransactionStatus,
         new_rate: U256,
+        max_upfront_fee: U256,
+        lease: NonceLease,
     ) -> ManagerResult<bool> {
         match result {
             SendRawTransactionStatus::Ok(tx_hash) => {
@@ -455,13 +1129,11 @@ impl ExecutableStrategy {
                     ),
                 );
 
-                self.data.eoa_nonce += 1;
-                self.data.last_update = time() / 1_000_000_000;
-                self.data.latest_rate = new_rate;
-                self.apply_change();
+                self.data.eoa_nonce = lease.commit();
+                self.record_rate_adjustment(tx_hash, new_rate, max_upfront_fee);
                 Ok(true)
             }
-            SendRawTransactionStatus::InsufficientFunds => Err(ManagerError::Custom(
+            SendRawTransactionStatus::InsufficientFunds => Err(ManagerError::Permanent(
                 "Not enough balance to cover the gas fee.".to_string(),
             )),
             SendRawTransactionStatus::NonceTooLow | SendRawTransactionStatus::NonceTooHigh => {
@@ -471,13 +1143,30 @@ impl ExecutableStrategy {
         }
     }
 
+    /// Records a submitted rate adjustment, identified by its transaction or UserOperation hash.
+    fn record_rate_adjustment(
+        &mut self,
+        hash: Option<String>,
+        new_rate: U256,
+        max_upfront_fee: U256,
+    ) {
+        let now = Seconds::now().get();
+        self.data.last_update = now;
+        self.data.latest_rate = new_rate;
+        self.data.adjustment_count = self.data.adjustment_count.saturating_add(1);
+        self.data.last_adjustment_tx = hash;
+        self.data.record_upfront_fee(now, max_upfront_fee);
+        self.apply_change();
+    }
+
     /// Syncs EOA nonce with current chain state
     async fn update_nonce(&mut self) -> ManagerResult<()> {
         // Fetch the nonce for the given account
         let account = self.settings.eoa_pk.ok_or(ManagerError::NonExistentValue)?;
-        self.data.eoa_nonce = get_nonce(&self.settings.rpc_canister, account)
+        let chain_nonce = get_nonce(&self.settings.rpc_canister, account)
             .await?
             .to::<u64>();
+        self.data.eoa_nonce = reconcile_nonce(account, chain_nonce);
         self.apply_change();
         Ok(())
     }
@@ -569,12 +1258,49 @@ impl ExecutableStrategy {
     /// 6. State persistence
     pub async fn execute(&mut self, journal: &mut JournalCollection) -> ManagerResult<()> {
         // Lock the strategy to prevent concurrent execution
-        self.lock()?;
+        self.lock(journal)?;
+
+        // A fresh run recalculates the rate, so any scheduled resubmission is obsolete
+        if self.data.pending_retry.take().is_some() {
+            self.apply_change();
+            journal.append_note(
+                Ok(()),
+                LogType::Info,
+                "Discarded the pending rate adjustment resubmission in favor of this run.",
+            );
+        }
+
+        if flag_enabled!(TX_POOL_POLLING) {
+            let updated = self.reconcile_transactions(journal).await;
+            self.record_in_registry(journal, &updated).await;
+            self.speed_up_stuck_transactions(journal).await;
+        }
 
-        let execution_context = self.prepare_execution_context(journal).await?;
+        if self.enforce_gas_budget(journal) {
+            return Ok(());
+        }
+
+        let Some(execution_context) = self.prepare_execution_context(journal).await? else {
+            // Nothing to position the batch against, the strategy is dormant for this cycle.
+            let now = Seconds::now().get();
+            let dormant_since = *self.data.dormant_since.get_or_insert(now);
+            self.apply_change();
+            journal.append_note(
+                Ok(()),
+                LogType::Info,
+                format!(
+                    "The market has no troves (empty since {}). The strategy is dormant for this cycle.",
+                    dormant_since
+                ),
+            );
+            return Ok(());
+        };
+        if self.data.dormant_since.take().is_some() {
+            self.apply_change();
+        }
 
         let current_debt_in_front =
-            match self.get_current_debt_in_front(execution_context.troves.clone()) {
+            match self.get_current_debt_in_front(journal, execution_context.troves.clone()) {
                 Some(debt) => debt,
                 None => {
                     journal.append_note(
@@ -586,6 +1312,42 @@ impl ExecutableStrategy {
                 }
             };
 
+        self.verify_positioning(journal, current_debt_in_front)?;
+        self.sample_positioning(current_debt_in_front, &execution_context);
+
+        if let Some(remaining_runs) = self.data.warmup.as_ref().map(|w| w.remaining_runs) {
+            // Warming up: record what the run would have done without touching the chain
+            let decision = self
+                .decide_rate(journal, current_debt_in_front, &execution_context)
+                .await?;
+            if decision.adjust {
+                if let Some(warmup) = self.data.warmup.as_mut() {
+                    warmup.would_have_adjusted = warmup.would_have_adjusted.saturating_add(1);
+                }
+                self.apply_change();
+            }
+            let outcome = match decision.upfront_fee {
+                Some(upfront_fee) if decision.adjust => format!(
+                    "would have adjusted the rate from {} to {} with an upfront fee of {}",
+                    self.data.latest_rate, decision.new_rate, upfront_fee
+                ),
+                _ => format!(
+                    "would have kept the rate at {} (calculated rate: {})",
+                    self.data.latest_rate, decision.new_rate
+                ),
+            };
+            journal.append_note(
+                Ok(()),
+                LogType::RateAdjustment,
+                format!(
+                    "Warming up ({} runs remaining): the run {}. Nothing is submitted.",
+                    remaining_runs, outcome
+                ),
+            );
+            self.unlock();
+            return Ok(());
+        }
+
         // Execute the strategy logic based on calculated values and collected troves
         let strategy_result = self
             .run_strategy(journal, current_debt_in_front, &execution_context)
@@ -613,6 +1375,325 @@ impl ExecutableStrategy {
         Ok(())
     }
 
+    /// Polls the receipts of the strategy's pending transactions, journals their new status,
+    /// and adds the fees of the confirmed ones to the strategy's costs.
+    ///
+    /// Returns the transactions whose status changed. Failures are journaled but do not abort
+    /// the execution.
+    async fn reconcile_transactions(&mut self, journal: &mut JournalCollection) -> Vec<TxRecord> {
+        let eoa = match self.settings.eoa_pk {
+            Some(eoa) => eoa,
+            None => return vec![],
+        };
+
+        let polled = poll_receipts(
+            self.settings.key,
+            &self.settings.rpc_canister,
+            eoa,
+            self.settings.batch_manager,
+        )
+        .await;
+        match polled {
+            Ok(updated) => {
+                for record in &updated {
+                    self.data
+                        .record_costs(record.fee_paid(), record.upfront_fee_paid());
+                    journal.append_note(
+                        Ok(()),
+                        LogType::Info,
+                        format!(
+                            "Transaction {} with nonce {} is now {:?}.",
+                            record.hash, record.nonce, record.status
+                        ),
+                    );
+                }
+                if !updated.is_empty() {
+                    self.apply_change();
+                }
+                updated
+            }
+            Err(err) => {
+                journal.append_note(
+                    Err(err),
+                    LogType::Info,
+                    "Failed to poll the receipts of pending transactions.",
+                );
+                vec![]
+            }
+        }
+    }
+
+    /// Records the newly confirmed rate adjustments in the strategy's `IRMRegistry` contract.
+    ///
+    /// Failures are journaled but do not abort the execution.
+    async fn record_in_registry(&mut self, journal: &mut JournalCollection, updated: &[TxRecord]) {
+        let (Some(registry), Some(eoa)) = (self.settings.registry, self.settings.eoa_pk) else {
+            return;
+        };
+
+        for record in updated {
+            let Some(call) = registry_call(record, self.settings.batch_manager) else {
+                continue;
+            };
+
+            let lease = NonceLease::acquire(eoa, self.data.eoa_nonce);
+            let result = TransactionBuilder::default()
+                .to(registry.to_string())
+                .from(eoa.to_string())
+                .data(call.abi_encode())
+                .value(U256::ZERO)
+                .nonce(lease.nonce())
+                .derivation_path(self.settings.derivation_path.clone())
+                .cycles(40_000_000_000_u128)
+                .strategy_key(self.settings.key)
+                .fee_policy(self.settings.fee_policy)
+                .journal(journal)
+                .send(&self.settings.rpc_canister)
+                .await;
+
+            match result {
+                Ok(SendRawTransactionStatus::Ok(hash)) => {
+                    self.data.eoa_nonce = lease.commit();
+                    self.apply_change();
+                    journal.append_note(
+                        Ok(()),
+                        LogType::Info,
+                        format!(
+                            "Recorded rate adjustment {} in registry {}: {:?}.",
+                            record.hash, registry, hash
+                        ),
+                    );
+                }
+                Ok(status) => {
+                    journal.append_note(
+                        Ok(()),
+                        LogType::Info,
+                        format!(
+                            "WARNING: The registry rejected the record of rate adjustment {} ({:?}).",
+                            record.hash, status
+                        ),
+                    );
+                    if matches!(
+                        status,
+                        SendRawTransactionStatus::NonceTooLow
+                            | SendRawTransactionStatus::NonceTooHigh
+                    ) {
+                        drop(lease);
+                        if let Err(err) = self.update_nonce().await {
+                            journal.append_note(
+                                Err(err),
+                                LogType::Info,
+                                "Failed to resync the nonce.",
+                            );
+                        }
+                    }
+                }
+                Err(err) => journal.append_note(
+                    Err(err),
+                    LogType::Info,
+                    format!(
+                        "WARNING: Failed to record rate adjustment {} in registry {}.",
+                        record.hash, registry
+                    ),
+                ),
+            }
+        }
+    }
+
+    /// Resends the strategy's transactions that stayed pending for `stuck_tx_blocks` blocks
+    /// with bumped fees.
+    ///
+    /// Failures are journaled but do not abort the execution.
+    async fn speed_up_stuck_transactions(&self, journal: &mut JournalCollection) {
+        let Some(eoa) = self.settings.eoa_pk else {
+            return;
+        };
+
+        let outcomes = match replace_stuck_transactions(
+            self.settings.key,
+            &self.settings.rpc_canister,
+            eoa,
+            self.settings.derivation_path.clone(),
+            40_000_000_000_u128,
+            &self.settings.fee_policy,
+            journal,
+        )
+        .await
+        {
+            Ok(outcomes) => outcomes,
+            Err(err) => {
+                journal.append_note(
+                    Err(err),
+                    LogType::Info,
+                    "Failed to look for stuck transactions.",
+                );
+                return;
+            }
+        };
+
+        for (record, outcome) in outcomes {
+            match outcome {
+                Ok(SendRawTransactionStatus::Ok(hash)) => journal.append_note(
+                    Ok(()),
+                    LogType::Info,
+                    format!(
+                        "WARNING: Transaction {} with nonce {} was stuck. Resent it with bumped fees: {:?}.",
+                        record.hash, record.nonce, hash
+                    ),
+                ),
+                Ok(status) => journal.append_note(
+                    Ok(()),
+                    LogType::Info,
+                    format!(
+                        "The replacement of transaction {} with nonce {} was rejected ({:?}). It may have been included already.",
+                        record.hash, record.nonce, status
+                    ),
+                ),
+                Err(err) => journal.append_note(
+                    Err(err),
+                    LogType::Info,
+                    format!(
+                        "WARNING: Failed to replace stuck transaction {} with nonce {}.",
+                        record.hash, record.nonce
+                    ),
+                ),
+            }
+        }
+    }
+
+    /// Cancels the oldest pending transaction of the strategy with a self-transfer that
+    /// consumes its nonce.
+    ///
+    /// The latest rate is refreshed from the batch afterwards, as the cancelled rate
+    /// adjustment is never applied.
+    ///
+    /// # Returns
+    /// The pool record of the cancellation
+    pub async fn cancel_pending_transaction(
+        &mut self,
+        journal: &mut JournalCollection,
+    ) -> ManagerResult<TxRecord> {
+        self.lock(journal)?;
+
+        let eoa = self.settings.eoa_pk.ok_or(ManagerError::NonExistentValue)?;
+        let pending = pending_transactions(self.settings.key)
+            .into_iter()
+            .next()
+            .ok_or_else(|| {
+                ManagerError::Custom("The strategy has no pending transaction.".to_string())
+            })?;
+
+        let status = replace_transaction(
+            &self.settings.rpc_canister,
+            &pending,
+            ReplacementParams {
+                replacement: Replacement::Cancel,
+                eoa,
+                derivation_path: self.settings.derivation_path.clone(),
+                cycles: 40_000_000_000_u128,
+                fee_policy: &self.settings.fee_policy,
+            },
+            journal,
+        )
+        .await?;
+        let SendRawTransactionStatus::Ok(hash) = status else {
+            return Err(ManagerError::Custom(format!(
+                "The cancellation of transaction {} was rejected ({:?}). It may have been included already.",
+                pending.hash, status
+            )));
+        };
+        journal.append_note(
+            Ok(()),
+            LogType::Info,
+            format!(
+                "WARNING: Cancelled transaction {} with nonce {} by a self-transfer: {:?}.",
+                pending.hash, pending.nonce, hash
+            ),
+        );
+
+        match fetch_batch_manager_params(
+            &self.settings.rpc_canister,
+            self.settings.manager,
+            self.settings.batch_manager,
+            Seconds::now().get(),
+        )
+        .await
+        {
+            Ok(params) => {
+                self.data.latest_rate = nat_to_u256(&params.annual_interest_rate)?;
+                self.data.batch_manager_params = Some(params);
+                // The expected positioning belonged to the cancelled rate
+                self.data.pending_positioning = None;
+                self.apply_change();
+            }
+            Err(err) => journal.append_note(
+                Err(err),
+                LogType::Info,
+                format!(
+                    "WARNING: Failed to refresh the rate of the batch. The latest rate {} may not be applied on-chain.",
+                    self.data.latest_rate
+                ),
+            ),
+        }
+
+        self.unlock();
+        transactions_for_nonce(self.settings.key, pending.nonce)
+            .into_iter()
+            .find(TxRecord::is_pending)
+            .ok_or(ManagerError::NonExistentValue)
+    }
+
+    /// Checks the gas spend of the strategy's EOA over the last `GAS_BUDGET_WINDOW` against its budget.
+    ///
+    /// The strategy is paused while the budget is exceeded, and resumes on its own once older
+    /// transactions leave the window or the budget is raised.
+    /// Returns `true` if the run should be skipped.
+    fn enforce_gas_budget(&mut self, journal: &mut JournalCollection) -> bool {
+        let Some(budget) = self.settings.gas_budget else {
+            return false;
+        };
+
+        let now = Seconds::now().get();
+        let spent = gas_spent_since(self.settings.key, now.saturating_sub(GAS_BUDGET_WINDOW));
+
+        if spent <= budget {
+            if self.data.budget_paused_since.take().is_some() {
+                self.apply_change();
+                journal.append_note(
+                    Ok(()),
+                    LogType::Info,
+                    format!(
+                        "The gas spend of {} wei is back within the budget of {} wei. The strategy is resumed.",
+                        spent, budget
+                    ),
+                );
+            }
+            return false;
+        }
+
+        if self.data.budget_paused_since.is_none() {
+            self.data.budget_paused_since = Some(now);
+            self.apply_change();
+            raise_alert(format!(
+                "Strategy {} spent {} wei on gas in the last {} days, above its budget of {} wei. The strategy is paused.",
+                self.settings.key,
+                spent,
+                GAS_BUDGET_WINDOW / 86_400,
+                budget
+            ));
+        }
+
+        journal.append_note(
+            Err(ManagerError::Custom(format!(
+                "Gas spend of {} wei exceeds the budget of {} wei.",
+                spent, budget
+            ))),
+            LogType::Info,
+            "The strategy is paused for exceeding its gas budget. Skipping the execution.",
+        );
+        true
+    }
+
     /// Estimates upfront fee cost for rate change
     async fn predict_upfront_fee(
         &self,
@@ -642,281 +1723,492 @@ impl ExecutableStrategy {
     }
 
     /// Calculates debt in front of current batch
-    fn get_current_debt_in_front(&mut self, troves: Vec<DebtPerInterestRate>) -> Option<U256> {
-        let mut counted_debt = U256::from(0);
-
-        for trove in troves.iter() {
-            if trove.interestBatchManager == self.settings.batch_manager {
-                // update the current interest rate
-                self.data.latest_rate(trove.interestRate);
-                return Some(counted_debt);
-            }
-            counted_debt = counted_debt.saturating_add(trove.debt);
-        }
-        None
-    }
-
-    /// Core strategy execution logic
-    async fn run_strategy(
+    ///
+    /// If the batch is found at several rate buckets, the lowest one is used and the
+    /// buckets are journaled.
+    fn get_current_debt_in_front(
         &mut self,
         journal: &mut JournalCollection,
-        current_debt_in_front: U256,
-        execution_context: &ExecutionContext,
-    ) -> ManagerResult<Option<(U256, U256)>> {
-        // Calculate new rate
-        let new_rate = self
-            .calculate_new_rate(
-                journal,
-                execution_context.troves.clone(),
-                execution_context.target_percentage,
-                execution_context.maximum_redeemable_against_collateral,
-            )
-            .await?;
-
-        if new_rate == self.data.latest_rate {
-            // we don't want to adjust the rate with the same value.
-            journal.append_note(
-                Ok(()),
-                LogType::Info,
-                "The calculated rate is the same as the current rate. No need to progress further.",
-            );
-
-            return Ok(None);
-        } else if new_rate == U256::ZERO {
+        troves: Vec<DebtPerInterestRate>,
+    ) -> Option<U256> {
+        let position = batch_position(&troves, self.settings.batch_manager)?;
+        if position.rates.len() > 1 {
             journal.append_note(
                 Ok(()),
                 LogType::Info,
-                "The calculated rate is zero. No need to progress further.",
+                format!(
+                    "The batch was found at {} rate buckets ({:?}) with a total debt of {}. Positioning from the lowest bucket.",
+                    position.rates.len(),
+                    position.rates,
+                    position.batch_debt
+                ),
             );
-
-            return Ok(None);
         }
-
-        // Predict upfront fee
-        let upfront_fee = self
-            .predict_upfront_fee(new_rate, execution_context.block_tag.clone())
-            .await?;
-
-        // Check conditions to execute the strategy
-        if self.increase_check(
-            journal,
-            current_debt_in_front,
-            execution_context.maximum_redeemable_against_collateral,
-            execution_context.target_percentage,
-        ) || (self.first_decrease_check(
-            journal,
-            current_debt_in_front,
-            execution_context.maximum_redeemable_against_collateral,
-            execution_context.target_percentage,
-        ) && self.second_decrease_check(
-            journal,
-            execution_context.time_since_last_update,
-            self.settings.upfront_fee_period,
-            new_rate,
-            upfront_fee,
-        )?) {
-            return Ok(Some((new_rate, upfront_fee)));
+        // update the current interest rate
+        if let Some(lowest_rate) = position.rates.first() {
+            self.data.latest_rate(*lowest_rate);
         }
+        Some(position.debt_in_front)
+    }
 
-        Ok(None)
+    /// Records the daily positioning sample of the strategy.
+    fn sample_positioning(&self, debt_in_front: U256, execution_context: &ExecutionContext) {
+        let target_debt_in_front = self
+            .rate_inputs(debt_in_front, execution_context)
+            .target_debt();
+        record_positioning_sample(
+            self.settings.key,
+            PositioningSample {
+                timestamp: execution_context.block_timestamp,
+                debt_in_front: Wei(debt_in_front).to_nat(),
+                target_debt_in_front: Wei(target_debt_in_front).to_nat(),
+                rate: Rate(self.data.latest_rate).to_nat(),
+            },
+        );
     }
 
-    /// Calculates optimal new interest rate
-    async fn calculate_new_rate(
-        &self,
+    /// Compares the debt in front expected by the last rate adjustment with the realized one,
+    /// once the adjusted rate is observed on-chain.
+    ///
+    /// The check is stored on the run that submitted the adjustment.
+    fn verify_positioning(
+        &mut self,
         journal: &mut JournalCollection,
-        troves: Vec<DebtPerInterestRate>,
-        target_percentage: U256,
-        maximum_redeemable_against_collateral: U256,
-    ) -> ManagerResult<U256> {
-        let mut counted_debt = U256::ZERO;
-        let mut new_rate = U256::ZERO;
-        let target_debt = target_percentage * maximum_redeemable_against_collateral / scale();
+        realized_debt_in_front: U256,
+    ) -> ManagerResult<()> {
+        let Some(expected) = self.data.pending_positioning.clone() else {
+            return Ok(());
+        };
+        if self.data.latest_rate != expected.rate {
+            // The adjustment is not applied yet, or was superseded before it could be observed
+            return Ok(());
+        }
 
+        let expected_debt_in_front = u256_to_nat(&expected.expected_debt_in_front)?;
+        let realized = u256_to_nat(&realized_debt_in_front)?;
+        let delta =
+            Int(BigInt::from(realized.0.clone()) - BigInt::from(expected_debt_in_front.0.clone()));
         journal.append_note(
             Ok(()),
             LogType::Info,
-            format!("Calculated target debt in front: {}, number of troves in this collateral market (including the batch): {}",
-            target_debt,
-            troves.len()),
+            format!(
+                "Verified the positioning of the rate adjustment: expected debt in front {}, realized {}, delta {}.",
+                expected_debt_in_front, realized, delta
+            ),
         );
 
-        if target_debt == U256::ZERO {
-            return Err(ManagerError::Custom(
-                "The target amount is zero. Not proceeding.".to_string(),
-            ));
+        if let Some(run_id) = expected.run_id {
+            record_positioning_check(
+                run_id,
+                PositioningCheck {
+                    rate: u256_to_nat(&expected.rate)?,
+                    target_debt_in_front: u256_to_nat(&expected.target_debt_in_front)?,
+                    expected_debt_in_front,
+                    realized_debt_in_front: realized,
+                    delta,
+                    verified_in_run: journal.run_id,
+                    verified_at: Seconds::now().get(),
+                },
+            );
         }
+        self.data.pending_positioning = None;
+        self.apply_change();
+        Ok(())
+    }
 
-        for trove in troves
-            .iter()
-            .filter(|t| t.interestBatchManager != self.settings.batch_manager)
-        {
-            counted_debt = counted_debt
-                .checked_add(trove.debt)
-                .ok_or_else(|| arithmetic_err("Counted debt overflowed."))?;
-
-            if counted_debt > target_debt {
-                new_rate = trove
-                    .interestRate
-                    .saturating_add(U256::from(100_000_000_000_000_u128)); // Increment rate by 1 bps (0.01%)
+    /// Core strategy execution logic
+    ///
+    /// The decision is delegated to the strategy's `RateStrategy`. An adjustment whose upfront
+    /// fee would exceed the upfront fee budget is skipped. A new rate out of the bounds of the
+    /// rate guard is held back, unless a controller acknowledged the trip.
+    async fn run_strategy(
+        &mut self,
+        journal: &mut JournalCollection,
+        current_debt_in_front: U256,
+        execution_context: &ExecutionContext,
+    ) -> ManagerResult<Option<(U256, U256)>> {
+        let decision = self
+            .decide_rate(journal, current_debt_in_front, execution_context)
+            .await?;
 
-                journal.append_note(
-                    Ok(()),
-                    LogType::Info,
-                    format!("Positioning the batch after trove with debt {}", trove.debt),
-                );
-                break;
+        match decision.upfront_fee {
+            Some(upfront_fee) if decision.adjust => {
+                if !self.within_upfront_fee_budget(journal, upfront_fee) {
+                    return Ok(None);
+                }
+                if !self.check_rate_guard(journal, decision.new_rate)? {
+                    return Ok(None);
+                }
+                // Verified against the realized debt in front once the rate is observed on-chain
+                self.data.pending_positioning = Some(ExpectedPositioning {
+                    run_id: journal.run_id,
+                    rate: decision.new_rate,
+                    target_debt_in_front: decision.target_debt_in_front,
+                    expected_debt_in_front: decision.expected_debt_in_front,
+                });
+                self.apply_change();
+                Ok(Some((decision.new_rate, upfront_fee)))
             }
+            _ => Ok(None),
         }
-
-        if new_rate == U256::ZERO
-            && troves.last().unwrap().interestBatchManager != self.settings.batch_manager
-        {
-            // There was not enough debt in the market
-            // the trove should be positioned at the end of the market.
-            new_rate = troves
-                .last()
-                .unwrap()
-                .interestRate
-                .saturating_add(U256::from(100_000_000_000_000_u128)); // Increment rate by 1 bps (0.01%)
-
-            journal.append_note(
-                Ok(()),
-                LogType::Info,
-                format!("Not enough debt in the market, moving the batch to the end."),
-            );
-        }
-
-        Ok(new_rate)
     }
 
-    /// Validates rate increase conditions
-    fn increase_check(
+    /// Returns `true` if paying `upfront_fee` keeps the strategy within its upfront fee budget.
+    fn within_upfront_fee_budget(
         &self,
         journal: &mut JournalCollection,
-        debt_in_front: U256,
-        maximum_redeemable_against_collateral: U256,
-        target_percentage: U256,
+        upfront_fee: U256,
     ) -> bool {
-        let target_debt = target_percentage * maximum_redeemable_against_collateral / scale();
-        let target_debt_with_margin = target_debt * (scale() - tolerance_margin_down()) / scale();
+        let Some(budget) = self.settings.upfront_fee_budget else {
+            return true;
+        };
+
+        let spent = self.data.upfront_fees_in_window(Seconds::now().get());
+        if spent.saturating_add(upfront_fee) <= budget {
+            return true;
+        }
 
         journal.append_note(
             Ok(()),
             LogType::Info,
             format!(
-                "increase check: {} < {}",
-                debt_in_front, target_debt_with_margin
+                "Skipping the rate adjustment: its upfront fee of {} would exceed the upfront fee budget of {} ({} already paid in the last {} days).",
+                upfront_fee,
+                budget,
+                spent,
+                UPFRONT_FEE_BUDGET_WINDOW / 86_400
             ),
         );
-
-        if debt_in_front < target_debt_with_margin {
-            return true;
-        }
         false
     }
 
-    /// First phase decrease validation
-    fn first_decrease_check(
-        &self,
+    /// Checks a new rate against the rate guard and returns `true` if it may be submitted.
+    ///
+    /// A rate out of bounds trips the guard and raises an alert. An acknowledged trip lets
+    /// the rate through once. A rate within bounds clears the trip.
+    fn check_rate_guard(
+        &mut self,
         journal: &mut JournalCollection,
-        debt_in_front: U256,
-        maximum_redeemable_against_collateral: U256,
-        target_percentage: U256,
-    ) -> bool {
-        let target_debt = target_percentage * maximum_redeemable_against_collateral / scale();
-        let target_debt_with_margin = target_debt * (scale() + tolerance_margin_up()) / scale();
+        new_rate: U256,
+    ) -> ManagerResult<bool> {
+        let latest_rate = self.data.latest_rate;
+        let Some(reason) = self.settings.rate_guard.violation(latest_rate, new_rate) else {
+            if self.data.safety_trip.take().is_some() {
+                self.apply_change();
+            }
+            return Ok(true);
+        };
+
+        let acknowledged = self
+            .data
+            .safety_trip
+            .as_ref()
+            .is_some_and(|trip| trip.acknowledged);
+        if acknowledged {
+            self.data.safety_trip = None;
+            self.apply_change();
+            journal.append_note(
+                Ok(()),
+                LogType::SafetyTriggered,
+                format!(
+                    "{} Submitting the rate as the trip was acknowledged by a controller.",
+                    reason
+                ),
+            );
+            return Ok(true);
+        }
 
+        self.data.safety_trip = Some(SafetyTrip {
+            run_id: journal.run_id,
+            latest_rate: u256_to_nat(&latest_rate)?,
+            new_rate: u256_to_nat(&new_rate)?,
+            reason: reason.clone(),
+            tripped_at: Seconds::now().get(),
+            acknowledged: false,
+        });
+        self.apply_change();
         journal.append_note(
-            Ok(()),
-            LogType::Info,
-            format!(
-                "first decrease check: {} > {}",
-                debt_in_front, target_debt_with_margin
-            ),
+            Err(ManagerError::Custom(reason.clone())),
+            LogType::SafetyTriggered,
+            "The rate guard held back the rate adjustment until a controller acknowledges it.",
         );
+        raise_alert(format!(
+            "The rate guard of strategy {} held back a rate adjustment: {}",
+            self.settings.key, reason
+        ));
+        Ok(false)
+    }
 
-        if debt_in_front > target_debt_with_margin {
-            return true;
+    /// Returns the inputs of the rate decision in an execution context.
+    fn rate_inputs<'a>(
+        &self,
+        current_debt_in_front: U256,
+        execution_context: &'a ExecutionContext,
+    ) -> RateInputs<'a> {
+        RateInputs {
+            troves: &execution_context.troves,
+            batch_manager: self.settings.batch_manager,
+            latest_rate: self.data.latest_rate,
+            current_debt_in_front,
+            target_percentage: execution_context.target_percentage,
+            maximum_redeemable_against_collateral: execution_context
+                .maximum_redeemable_against_collateral,
+            time_since_last_update: execution_context.time_since_last_update,
+            upfront_fee_period: self.settings.upfront_fee_period,
+            increment: U256::from(self.settings.rate_granularity.increment),
         }
-        false
     }
 
-    /// Second phase decrease validation
-    fn second_decrease_check(
+    /// Calculates the new rate and checks whether it should be submitted, without side effects.
+    async fn decide_rate(
         &self,
         journal: &mut JournalCollection,
-        time_since_last_update: U256,
-        upfront_fee_period: U256,
-        new_rate: U256,
-        average_rate: U256,
-    ) -> ManagerResult<bool> {
-        // Check if time exceeds period first
-        if time_since_last_update > upfront_fee_period {
+        current_debt_in_front: U256,
+        execution_context: &ExecutionContext,
+    ) -> ManagerResult<RateDecision> {
+        let engine = self.settings.rate_strategy.engine();
+        let inputs = self.rate_inputs(current_debt_in_front, execution_context);
+
+        // Calculate new rate
+        let new_rate = self
+            .settings
+            .rate_granularity
+            .round(engine.calculate_new_rate(journal, &inputs)?);
+
+        let mut decision = RateDecision {
+            new_rate,
+            upfront_fee: None,
+            adjust: false,
+            target_debt_in_front: inputs.target_debt(),
+            expected_debt_in_front: inputs.expected_debt_in_front(new_rate),
+            checks: vec![],
+        };
+
+        if new_rate == self.data.latest_rate {
+            // we don't want to adjust the rate with the same value.
             journal.append_note(
                 Ok(()),
                 LogType::Info,
-                "second decrease check passed: time exceeded period",
+                "The calculated rate is the same as the current rate. No need to progress further.",
             );
-            return Ok(true);
+
+            return Ok(decision);
+        } else if new_rate == U256::ZERO {
+            journal.append_note(
+                Ok(()),
+                LogType::Info,
+                "The calculated rate is zero. No need to progress further.",
+            );
+
+            return Ok(decision);
         }
 
-        // Scale the division by 100 to get 2 decimal places
-        let scale_hundred: U256 = U256::from(100);
+        // Predict upfront fee
+        let upfront_fee = self
+            .predict_upfront_fee(new_rate, execution_context.block_tag.clone())
+            .await?;
+        decision.upfront_fee = Some(upfront_fee);
+
+        // Check conditions to execute the strategy
+        decision.adjust = engine.should_adjust(
+            journal,
+            &inputs,
+            new_rate,
+            upfront_fee,
+            &mut decision.checks,
+        )?;
+        Ok(decision)
+    }
 
-        // Calculate r with 2 decimal precision
-        let scaled_time = time_since_last_update
-            .checked_mul(scale_hundred)
-            .ok_or(arithmetic_err("Overflow in time scaling"))?;
+    /// Runs the read-only part of the pipeline, up to the rate decision.
+    ///
+    /// Unlike a run, a market without troves or without the batch is reported as an error.
+    async fn read_only_decision(
+        &mut self,
+        journal: &mut JournalCollection,
+    ) -> ManagerResult<(ExecutionContext, U256, RateDecision)> {
+        let execution_context =
+            self.prepare_execution_context(journal)
+                .await?
+                .ok_or_else(|| {
+                    ManagerError::Permanent(
+                        "The market has no troves. There is nothing to position the batch against."
+                            .to_string(),
+                    )
+                })?;
+        let current_debt_in_front = self
+            .get_current_debt_in_front(journal, execution_context.troves.clone())
+            .ok_or_else(|| {
+                ManagerError::Permanent("No trove has delegated to this batch manager.".to_string())
+            })?;
+
+        let decision = self
+            .decide_rate(journal, current_debt_in_front, &execution_context)
+            .await?;
+        Ok((execution_context, current_debt_in_front, decision))
+    }
 
-        let r = scaled_time
-            .checked_div(upfront_fee_period)
-            .ok_or(arithmetic_err("Upfront fee period was 0."))?;
+    /// Runs the read-only part of the pipeline and reports the rate decision of a run.
+    ///
+    /// Expects a `detached` instance, the decision is reported instead of submitted.
+    pub async fn simulate(
+        &mut self,
+        journal: &mut JournalCollection,
+    ) -> ManagerResult<StrategySimulation> {
+        let (execution_context, current_debt_in_front, decision) =
+            self.read_only_decision(journal).await?;
+
+        Ok(StrategySimulation {
+            key: self.settings.key,
+            block_tag: format!("{:?}", execution_context.block_tag),
+            target_min: u256_to_nat(&self.settings.target_min)?,
+            current_rate: u256_to_nat(&self.data.latest_rate)?,
+            new_rate: u256_to_nat(&decision.new_rate)?,
+            upfront_fee: decision.upfront_fee.as_ref().map(u256_to_nat).transpose()?,
+            current_debt_in_front: u256_to_nat(&current_debt_in_front)?,
+            target_debt_in_front: u256_to_nat(&decision.target_debt_in_front)?,
+            expected_debt_in_front: u256_to_nat(&decision.expected_debt_in_front)?,
+            checks: decision.checks,
+            would_submit: decision.adjust,
+        })
+    }
 
-        journal.append_note(
-            Ok(()),
-            LogType::Info,
-            format!(
-                "second decrease check: time since last update {} upfront fee period {} latest rate {} new rate {} average rate {} scaled r {}", 
-                time_since_last_update,
-                upfront_fee_period,
-                self.data.latest_rate,
-                new_rate,
-                average_rate,
-                r
-            )
+    /// Reads the market the strategy positions its batch in, along with its target.
+    ///
+    /// Expects a `detached` instance.
+    pub async fn market_snapshot(
+        &mut self,
+        journal: &mut JournalCollection,
+    ) -> ManagerResult<MarketSnapshot> {
+        let execution_context =
+            self.prepare_execution_context(journal)
+                .await?
+                .ok_or_else(|| {
+                    ManagerError::Permanent(
+                        "The market has no troves. There is nothing to take a snapshot of."
+                            .to_string(),
+                    )
+                })?;
+        let position = batch_position(&execution_context.troves, self.settings.batch_manager);
+        let inputs = self.rate_inputs(
+            position
+                .as_ref()
+                .map(|position| position.debt_in_front)
+                .unwrap_or_default(),
+            &execution_context,
         );
 
-        // For the main condition, we need to scale the computation
-        // (1 - r/100) * (latest_rate - new_rate) > average_rate
-        let scaled_diff = scale_hundred
-            .checked_sub(r)
-            .ok_or(arithmetic_err("Error in r subtraction"))?;
-
-        let rate_diff = self
-            .data
-            .latest_rate
-            .checked_sub(new_rate)
-            .ok_or(arithmetic_err("Error in rate difference calculation"))?;
+        Ok(MarketSnapshot {
+            key: self.settings.key,
+            block_tag: format!("{:?}", execution_context.block_tag),
+            block_timestamp: execution_context.block_timestamp,
+            batch_manager: self.settings.batch_manager.to_string(),
+            troves: execution_context
+                .troves
+                .iter()
+                .map(|bucket| TroveBucket::new(bucket, self.settings.batch_manager))
+                .collect::<ManagerResult<_>>()?,
+            debt_in_front: position
+                .as_ref()
+                .map(|position| u256_to_nat(&position.debt_in_front))
+                .transpose()?,
+            batch_debt: u256_to_nat(
+                &position
+                    .map(|position| position.batch_debt)
+                    .unwrap_or_default(),
+            )?,
+            target_percentage: u256_to_nat(&execution_context.target_percentage)?,
+            maximum_redeemable_against_collateral: u256_to_nat(
+                &execution_context.maximum_redeemable_against_collateral,
+            )?,
+            target_debt_in_front: u256_to_nat(&inputs.target_debt())?,
+        })
+    }
 
-        let scaled_product = scaled_diff
-            .checked_mul(rate_diff)
-            .ok_or(arithmetic_err("Overflow in scaled product"))?;
+    /// Runs the read-only part of the pipeline and builds the `setNewRate` calldata that
+    /// would be signed for the calculated rate.
+    ///
+    /// Expects a `detached` instance, the calldata is returned unsigned.
+    pub async fn preview_adjustment(
+        &mut self,
+        journal: &mut JournalCollection,
+    ) -> ManagerResult<AdjustmentPreview> {
+        let (execution_context, _, decision) = self.read_only_decision(journal).await?;
+
+        let mut preview = AdjustmentPreview {
+            key: self.settings.key,
+            block_tag: format!("{:?}", execution_context.block_tag),
+            target: self.settings.batch_manager.to_string(),
+            current_rate: u256_to_nat(&self.data.latest_rate)?,
+            new_rate: u256_to_nat(&decision.new_rate)?,
+            would_submit: decision.adjust,
+            upper_hint: None,
+            lower_hint: None,
+            max_upfront_fee: None,
+            calldata: None,
+            estimated_gas: None,
+        };
+        let Some(upfront_fee) = decision.upfront_fee else {
+            return Ok(preview);
+        };
 
-        let scaled_average = average_rate
-            .checked_mul(scale_hundred)
-            .ok_or(arithmetic_err("Error in scaling average rate"))?;
+        let hints = self
+            .calculate_hints(
+                decision.new_rate,
+                execution_context.troves_count,
+                execution_context.block_tag,
+            )
+            .await?;
+        let payload = set_new_rate_call(decision.new_rate, upfront_fee, hints);
+        let calldata = payload.abi_encode();
 
-        if scaled_product > scaled_average {
-            journal.append_note(
-                Ok(()),
-                LogType::Info,
-                "second decrease check passed: rate condition",
-            );
-            return Ok(true);
+        if let Some(eoa) = self.settings.eoa_pk {
+            // A reverting estimation is reported as a missing estimate rather than an error
+            preview.estimated_gas = get_estimate_gas(
+                &self.settings.rpc_canister,
+                calldata.clone(),
+                preview.target.clone(),
+                eoa.to_string(),
+            )
+            .await
+            .ok()
+            .map(|gas| u256_to_nat(&gas))
+            .transpose()?;
         }
 
-        Ok(false)
+        preview.upper_hint = Some(u256_to_nat(&payload._upperHint)?);
+        preview.lower_hint = Some(u256_to_nat(&payload._lowerHint)?);
+        preview.max_upfront_fee = Some(u256_to_nat(&payload._maxUpfrontFee)?);
+        preview.calldata = Some(format!("0x{}", hex::encode(calldata)));
+        Ok(preview)
+    }
+}
+
+/// Outcome of the rate decision of a run
+struct RateDecision {
+    /// Calculated rate, after rounding
+    new_rate: U256,
+    /// Predicted upfront fee, `None` if the rate is unchanged or zero
+    upfront_fee: Option<U256>,
+    /// `true` if the engine decided to submit the rate
+    adjust: bool,
+    /// Debt in front targeted by the strategy
+    target_debt_in_front: U256,
+    /// Debt in front expected at the new rate
+    expected_debt_in_front: U256,
+    /// Conditions evaluated by the engine, empty if the rate is unchanged or zero
+    checks: Vec<ConditionCheck>,
+}
+
+/// Builds the `setNewRate` call for a rate, its predicted upfront fee, and its hints.
+///
+/// The maximum upfront fee is padded by 0.001 to absorb rounding in the contract.
+fn set_new_rate_call(new_rate: U256, upfront_fee: U256, hints: (U256, U256)) -> setNewRateCall {
+    setNewRateCall {
+        _newAnnualInterestRate: new_rate.to::<u128>(),
+        _upperHint: hints.0,
+        _lowerHint: hints.1,
+        _maxUpfrontFee: upfront_fee.saturating_add(U256::from(1_000_000_000_000_000_u128)),
     }
 }
 
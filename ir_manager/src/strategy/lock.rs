@@ -23,9 +23,10 @@
 
 use candid::CandidType;
 use chrono::{DateTime, Utc};
-use ic_exports::ic_cdk::api::time;
+use serde::Deserialize;
 
 use crate::{
+    clock::Seconds,
     constants::STRATEGY_LOCK_TIMEOUT,
     utils::error::{ManagerError, ManagerResult},
 };
@@ -53,21 +54,23 @@ impl Lock {
     /// 2. Existing lock has exceeded timeout period
     ///
     /// # Returns
-    /// * `Ok(())` - Lock successfully acquired
+    /// * `Ok(auto_unlocked)` - Lock successfully acquired, `true` if an expired lock was released
     /// * `Err(ManagerError::Locked)` - Lock unavailable
-    pub fn try_lock(&mut self) -> ManagerResult<()> {
-        let current_time = time() / 1_000_000_000; // current time in millis
+    pub fn try_lock(&mut self) -> ManagerResult<bool> {
+        let now = Seconds::now();
+        let mut auto_unlocked = false;
 
         if let Some(last_locked_at) = self.last_locked_at {
-            if self.is_locked && current_time - last_locked_at > STRATEGY_LOCK_TIMEOUT {
+            if self.is_locked && Seconds(last_locked_at).is_older_than(STRATEGY_LOCK_TIMEOUT, now) {
                 self.is_locked = false;
+                auto_unlocked = true;
             }
         }
 
         if !self.is_locked {
             self.is_locked = true;
-            self.last_locked_at = Some(current_time);
-            Ok(())
+            self.last_locked_at = Some(now.get());
+            Ok(auto_unlocked)
         } else {
             Err(ManagerError::Locked)
         }
@@ -87,9 +90,9 @@ impl Lock {
             self.is_locked = false;
             self.last_locked_at = None;
         } else if let Some(last_locked_at) = self.last_locked_at {
-            let current_time = time() / 1_000_000_000; // current time in millis
+            let now = Seconds::now();
 
-            if self.is_locked && current_time - last_locked_at > STRATEGY_LOCK_TIMEOUT {
+            if self.is_locked && Seconds(last_locked_at).is_older_than(STRATEGY_LOCK_TIMEOUT, now) {
                 self.is_locked = false;
                 self.last_locked_at = None;
             }
@@ -107,11 +110,11 @@ impl Lock {
 /// - Direct state access
 ///
 /// Note: Does not implement locking logic.
-#[derive(Clone, Default, CandidType)]
+#[derive(Clone, Default, CandidType, Deserialize)]
 pub struct StableLock {
     /// Status of the lock. `true` represents locked and `false` unlocked
     pub is_locked: bool,
-    /// Last locked timstamp in milliseconds
+    /// Last locked timestamp in seconds
     pub last_locked_at: Option<u64>,
 }
 
@@ -147,7 +150,7 @@ impl From<Lock> for StableLock {
 pub struct LockQuery {
     /// Status of the lock. `true` represents locked and `false` unlocked
     pub is_locked: bool,
-    /// Last locked timstamp in milliseconds
+    /// Last locked timestamp in seconds
     pub last_locked_at: Option<String>,
 }
 
@@ -168,3 +171,43 @@ impl TryFrom<StableLock> for LockQuery {
         })
     }
 }
+
+#[cfg(all(test, feature = "test-clock"))]
+mod tests {
+    use super::*;
+    use crate::clock::{advance, set_time};
+
+    #[test]
+    fn test_abandoned_lock_expires() {
+        set_time(1_700_000_000);
+        let mut lock = Lock::default();
+
+        assert_eq!(lock.try_lock(), Ok(false));
+        assert_eq!(lock.try_lock(), Err(ManagerError::Locked));
+
+        advance(STRATEGY_LOCK_TIMEOUT.get());
+        assert_eq!(lock.try_lock(), Err(ManagerError::Locked));
+
+        advance(1);
+        assert_eq!(lock.try_lock(), Ok(true));
+        assert_eq!(
+            lock.last_locked_at,
+            Some(1_700_000_000 + STRATEGY_LOCK_TIMEOUT.get() + 1)
+        );
+    }
+
+    #[test]
+    fn test_foreign_unlock_only_clears_expired_locks() {
+        set_time(1_700_000_000);
+        let mut lock = Lock::default();
+        lock.try_lock().unwrap();
+
+        lock.try_unlock(false);
+        assert!(lock.is_locked);
+
+        advance(STRATEGY_LOCK_TIMEOUT.get() + 1);
+        lock.try_unlock(false);
+        assert!(!lock.is_locked);
+        assert_eq!(lock.last_locked_at, None);
+    }
+}
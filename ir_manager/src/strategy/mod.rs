@@ -28,12 +28,25 @@
 //!
 //! Module Components:
 //!
+//! - `batch`: Cached on-chain batch manager parameters
+//! - `conflicts`: Cross-strategy configuration validation
+//! - `contention`: Lock contention statistics
 //! - `data`: Strategy runtime state management
+//! - `engine`: Pluggable rate decision logic
+//! - `persisted`: Strategies saved across upgrades
+//! - `preview`: Read-only previews of rate adjustments
+//! - `registry`: On-chain records of confirmed rate adjustments
+//! - `retire`: Strategy removal and archives
 //! - `run`: Strategy execution orchestration
 //! - `settings`: Strategy configuration parameters
+//! - `setup`: Minting and batch manager assignment
+//! - `snapshot`: Read-only snapshots of a strategy's market
 //! - `stable`: Persistent strategy storage
+//! - `troves`: Parallel trove list fetching
+//! - `warmup`: Simulation-only runs before going live
 //! - `executable`: Runtime strategy operations
 //! - `lock`: Concurrent execution control
+//! - `report`: Public strategy reports
 //!
 //! Key Design Features:
 //!
@@ -46,10 +59,23 @@
 //! proper lifecycle management and state consistency.
 
 // Core component modules
+pub(crate) mod batch; // Batch manager parameters
+pub(crate) mod conflicts; // Configuration validation
+pub(crate) mod contention; // Lock contention statistics
 pub(crate) mod data; // Strategy state
+pub(crate) mod engine; // Rate decision logic
+pub(crate) mod persisted; // Upgrade persistence
+pub(crate) mod preview; // Adjustment previews
+pub(crate) mod registry; // On-chain adjustment records
+pub(crate) mod report; // Public reports
+pub(crate) mod retire; // Removal
 pub(crate) mod run; // Execution flow
 pub(crate) mod settings; // Configuration
+pub(crate) mod setup; // Minting
+pub(crate) mod snapshot; // Market snapshots
 pub(crate) mod stable; // Persistent storage
+pub(crate) mod troves; // Trove list segments
+pub(crate) mod warmup; // Simulation-only runs
 
 // Restricted access modules
 pub(in crate::strategy) mod executable; // Runtime execution
@@ -0,0 +1,396 @@
+//! Persisted Strategies
+//!
+//! `STRATEGY_STATE` lives on the heap, which an upgrade wipes. The strategies are therefore
+//! written to stable memory by `pre_upgrade` in a candid representation, with the addresses
+//! as strings and the amounts as `Nat`, and read back by `post_upgrade`:
+//!
+//! ```plain
+//! StableStrategy ──TryFrom──► PersistedStrategy ──► HEAP_STATE (pre_upgrade)
+//!                                                         │
+//! StableStrategy ◄──TryFrom── PersistedStrategy ◄─────────┘ (post_upgrade)
+//! ```
+//!
+//! The lock is persisted as it is, since the canister is only upgraded once no strategy is
+//! executing anymore (see `upgrade`).
+
+// The fields mirror the documented fields of `StrategySettings` and `StrategyData`.
+#![allow(missing_docs)]
+
+use alloy_primitives::{Address, U256};
+use candid::{CandidType, Nat, Principal};
+use serde::Deserialize;
+
+use crate::{
+    chain::ChainConfig,
+    scheduler::ExecutionTrigger,
+    treasury::CachedBalance,
+    types::DerivationPath,
+    utils::{
+        address::parse_address,
+        common::{nat_to_u256, u256_to_nat},
+        error::{ManagerError, ManagerResult},
+        evm_rpc::Service,
+        gas::FeePolicy,
+    },
+};
+
+use super::{
+    batch::BatchManagerParams,
+    data::{ExpectedPositioning, PendingRetry, PendingRunRetry, SafetyTrip, StrategyData},
+    engine::{RateGranularity, RateStrategyKind},
+    lock::StableLock,
+    settings::{ExecutionBackend, RateGuard, RetryBackoff, RunRetry, StrategySettings},
+    stable::StableStrategy,
+    warmup::Warmup,
+};
+
+/// Candid representation of a `StableStrategy`
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct PersistedStrategy {
+    /// Configuration of the strategy
+    pub settings: PersistedSettings,
+    /// Runtime state of the strategy
+    pub data: PersistedData,
+    /// Execution lock of the strategy
+    pub lock: StableLock,
+    /// Number of writes of the strategy
+    pub revision: u64,
+}
+
+/// Candid representation of `StrategySettings`
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct PersistedSettings {
+    pub key: u32,
+    pub batch_manager: String,
+    pub hint_helper: String,
+    pub manager: String,
+    pub collateral_registry: String,
+    pub multi_trove_getter: String,
+    pub sorted_troves: String,
+    pub registry: Option<String>,
+    pub collateral_index: Nat,
+    pub derivation_path: DerivationPath,
+    pub target_min: Nat,
+    pub upfront_fee_period: Nat,
+    pub eoa_pk: Option<String>,
+    /// EVM RPC canister and the chain ID its requests are routed to
+    pub rpc_canister: (Principal, u64),
+    pub chain: ChainConfig,
+    pub retry_backoff: RetryBackoff,
+    pub run_retry: RunRetry,
+    pub rate_strategy: RateStrategyKind,
+    pub rate_granularity: RateGranularity,
+    pub rate_guard: RateGuard,
+    pub gas_budget: Option<u128>,
+    pub upfront_fee_budget: Option<Nat>,
+    pub fee_policy: FeePolicy,
+    pub execution_backend: ExecutionBackend,
+    pub execution_trigger: ExecutionTrigger,
+    pub enabled: bool,
+}
+
+/// Candid representation of `StrategyData`
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct PersistedData {
+    pub latest_rate: Nat,
+    pub last_update: u64,
+    pub eoa_nonce: u64,
+    pub eoa_balance: Option<CachedBalance>,
+    pub last_ok_exit: u64,
+    pub consecutive_failures: u64,
+    pub last_error: Option<ManagerError>,
+    pub pending_retry: Option<PersistedPendingRetry>,
+    pub pending_run_retry: Option<PendingRunRetry>,
+    pub adjustment_count: u64,
+    pub last_adjustment_tx: Option<String>,
+    pub upfront_fees: Vec<(u64, Nat)>,
+    pub batch_manager_params: Option<BatchManagerParams>,
+    pub dormant_since: Option<u64>,
+    pub budget_paused_since: Option<u64>,
+    pub pending_positioning: Option<PersistedPositioning>,
+    pub warmup: Option<Warmup>,
+    pub safety_trip: Option<SafetyTrip>,
+    pub gas_spent: u128,
+    pub upfront_fees_paid: Nat,
+}
+
+/// Candid representation of a `PendingRetry`
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct PersistedPendingRetry {
+    pub run_id: Option<u64>,
+    pub new_rate: Nat,
+    pub max_upfront_fee: Nat,
+    pub troves_count: Nat,
+    pub context_timestamp: u64,
+    pub attempt: u8,
+    pub scheduled_at: u64,
+}
+
+/// Candid representation of an `ExpectedPositioning`
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct PersistedPositioning {
+    pub run_id: Option<u64>,
+    pub rate: Nat,
+    pub target_debt_in_front: Nat,
+    pub expected_debt_in_front: Nat,
+}
+
+/// Parses an address written by `Address::to_string`.
+fn address(field: &str, value: &str) -> ManagerResult<Address> {
+    parse_address(field, value, true)
+}
+
+impl TryFrom<&StableStrategy> for PersistedStrategy {
+    type Error = ManagerError;
+
+    fn try_from(value: &StableStrategy) -> Result<Self, Self::Error> {
+        let settings = &value.settings;
+        let data = &value.data;
+        Ok(Self {
+            settings: PersistedSettings {
+                key: settings.key,
+                batch_manager: settings.batch_manager.to_string(),
+                hint_helper: settings.hint_helper.to_string(),
+                manager: settings.manager.to_string(),
+                collateral_registry: settings.collateral_registry.to_string(),
+                multi_trove_getter: settings.multi_trove_getter.to_string(),
+                sorted_troves: settings.sorted_troves.to_string(),
+                registry: settings.registry.map(|registry| registry.to_string()),
+                collateral_index: u256_to_nat(&settings.collateral_index)?,
+                derivation_path: settings.derivation_path.clone(),
+                target_min: u256_to_nat(&settings.target_min)?,
+                upfront_fee_period: u256_to_nat(&settings.upfront_fee_period)?,
+                eoa_pk: settings.eoa_pk.map(|eoa| eoa.to_string()),
+                rpc_canister: (settings.rpc_canister.0, settings.rpc_canister.1),
+                chain: settings.chain.clone(),
+                retry_backoff: settings.retry_backoff,
+                run_retry: settings.run_retry,
+                rate_strategy: settings.rate_strategy,
+                rate_granularity: settings.rate_granularity,
+                rate_guard: settings.rate_guard,
+                gas_budget: settings.gas_budget,
+                upfront_fee_budget: settings
+                    .upfront_fee_budget
+                    .as_ref()
+                    .map(u256_to_nat)
+                    .transpose()?,
+                fee_policy: settings.fee_policy,
+                execution_backend: settings.execution_backend.clone(),
+                execution_trigger: settings.execution_trigger,
+                enabled: settings.enabled,
+            },
+            data: PersistedData {
+                latest_rate: u256_to_nat(&data.latest_rate)?,
+                last_update: data.last_update,
+                eoa_nonce: data.eoa_nonce,
+                eoa_balance: data.eoa_balance.clone(),
+                last_ok_exit: data.last_ok_exit,
+                consecutive_failures: data.consecutive_failures,
+                last_error: data.last_error.clone(),
+                pending_retry: data
+                    .pending_retry
+                    .as_ref()
+                    .map(|retry| {
+                        Ok::<_, ManagerError>(PersistedPendingRetry {
+                            run_id: retry.run_id,
+                            new_rate: u256_to_nat(&retry.new_rate)?,
+                            max_upfront_fee: u256_to_nat(&retry.max_upfront_fee)?,
+                            troves_count: u256_to_nat(&retry.troves_count)?,
+                            context_timestamp: retry.context_timestamp,
+                            attempt: retry.attempt,
+                            scheduled_at: retry.scheduled_at,
+                        })
+                    })
+                    .transpose()?,
+                pending_run_retry: data.pending_run_retry.clone(),
+                adjustment_count: data.adjustment_count,
+                last_adjustment_tx: data.last_adjustment_tx.clone(),
+                upfront_fees: data
+                    .upfront_fees
+                    .iter()
+                    .map(|(timestamp, fee)| Ok((*timestamp, u256_to_nat(fee)?)))
+                    .collect::<ManagerResult<_>>()?,
+                batch_manager_params: data.batch_manager_params.clone(),
+                dormant_since: data.dormant_since,
+                budget_paused_since: data.budget_paused_since,
+                pending_positioning: data
+                    .pending_positioning
+                    .as_ref()
+                    .map(|positioning| {
+                        Ok::<_, ManagerError>(PersistedPositioning {
+                            run_id: positioning.run_id,
+                            rate: u256_to_nat(&positioning.rate)?,
+                            target_debt_in_front: u256_to_nat(&positioning.target_debt_in_front)?,
+                            expected_debt_in_front: u256_to_nat(
+                                &positioning.expected_debt_in_front,
+                            )?,
+                        })
+                    })
+                    .transpose()?,
+                warmup: data.warmup.clone(),
+                safety_trip: data.safety_trip.clone(),
+                gas_spent: data.gas_spent,
+                upfront_fees_paid: u256_to_nat(&data.upfront_fees_paid)?,
+            },
+            lock: value.lock.clone(),
+            revision: value.revision,
+        })
+    }
+}
+
+impl TryFrom<PersistedStrategy> for StableStrategy {
+    type Error = ManagerError;
+
+    fn try_from(value: PersistedStrategy) -> Result<Self, Self::Error> {
+        let PersistedStrategy {
+            settings,
+            data,
+            lock,
+            revision,
+        } = value;
+        Ok(Self {
+            settings: StrategySettings {
+                key: settings.key,
+                batch_manager: address("batch_manager", &settings.batch_manager)?,
+                hint_helper: address("hint_helper", &settings.hint_helper)?,
+                manager: address("manager", &settings.manager)?,
+                collateral_registry: address("collateral_registry", &settings.collateral_registry)?,
+                multi_trove_getter: address("multi_trove_getter", &settings.multi_trove_getter)?,
+                sorted_troves: address("sorted_troves", &settings.sorted_troves)?,
+                registry: settings
+                    .registry
+                    .map(|registry| address("registry", &registry))
+                    .transpose()?,
+                collateral_index: nat_to_u256(&settings.collateral_index)?,
+                derivation_path: settings.derivation_path,
+                target_min: nat_to_u256(&settings.target_min)?,
+                upfront_fee_period: nat_to_u256(&settings.upfront_fee_period)?,
+                eoa_pk: settings
+                    .eoa_pk
+                    .map(|eoa| address("eoa_pk", &eoa))
+                    .transpose()?,
+                rpc_canister: Service(settings.rpc_canister.0, settings.rpc_canister.1),
+                chain: settings.chain,
+                retry_backoff: settings.retry_backoff,
+                run_retry: settings.run_retry,
+                rate_strategy: settings.rate_strategy,
+                rate_granularity: settings.rate_granularity,
+                rate_guard: settings.rate_guard,
+                gas_budget: settings.gas_budget,
+                upfront_fee_budget: settings
+                    .upfront_fee_budget
+                    .as_ref()
+                    .map(nat_to_u256)
+                    .transpose()?,
+                fee_policy: settings.fee_policy,
+                execution_backend: settings.execution_backend,
+                execution_trigger: settings.execution_trigger,
+                enabled: settings.enabled,
+            },
+            data: StrategyData {
+                latest_rate: nat_to_u256(&data.latest_rate)?,
+                last_update: data.last_update,
+                eoa_nonce: data.eoa_nonce,
+                eoa_balance: data.eoa_balance,
+                last_ok_exit: data.last_ok_exit,
+                consecutive_failures: data.consecutive_failures,
+                last_error: data.last_error,
+                pending_retry: data
+                    .pending_retry
+                    .map(|retry| {
+                        Ok::<_, ManagerError>(PendingRetry {
+                            run_id: retry.run_id,
+                            new_rate: nat_to_u256(&retry.new_rate)?,
+                            max_upfront_fee: nat_to_u256(&retry.max_upfront_fee)?,
+                            troves_count: nat_to_u256(&retry.troves_count)?,
+                            context_timestamp: retry.context_timestamp,
+                            attempt: retry.attempt,
+                            scheduled_at: retry.scheduled_at,
+                        })
+                    })
+                    .transpose()?,
+                pending_run_retry: data.pending_run_retry,
+                adjustment_count: data.adjustment_count,
+                last_adjustment_tx: data.last_adjustment_tx,
+                upfront_fees: data
+                    .upfront_fees
+                    .iter()
+                    .map(|(timestamp, fee)| Ok((*timestamp, nat_to_u256(fee)?)))
+                    .collect::<ManagerResult<Vec<(u64, U256)>>>()?,
+                batch_manager_params: data.batch_manager_params,
+                dormant_since: data.dormant_since,
+                budget_paused_since: data.budget_paused_since,
+                pending_positioning: data
+                    .pending_positioning
+                    .map(|positioning| {
+                        Ok::<_, ManagerError>(ExpectedPositioning {
+                            run_id: positioning.run_id,
+                            rate: nat_to_u256(&positioning.rate)?,
+                            target_debt_in_front: nat_to_u256(&positioning.target_debt_in_front)?,
+                            expected_debt_in_front: nat_to_u256(
+                                &positioning.expected_debt_in_front,
+                            )?,
+                        })
+                    })
+                    .transpose()?,
+                warmup: data.warmup,
+                safety_trip: data.safety_trip,
+                gas_spent: data.gas_spent,
+                upfront_fees_paid: nat_to_u256(&data.upfront_fees_paid)?,
+            },
+            lock,
+            revision,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_persisted_strategy_roundtrip() {
+        let mut strategy = StableStrategy::default();
+        strategy.settings.key = 3;
+        strategy.settings.batch_manager = Address::repeat_byte(1);
+        strategy.settings.registry = Some(Address::repeat_byte(2));
+        strategy.settings.eoa_pk = Some(Address::repeat_byte(3));
+        strategy.settings.target_min = U256::from(5);
+        strategy.settings.upfront_fee_budget = Some(U256::from(7));
+        strategy.settings.enabled = true;
+        strategy.data.latest_rate = U256::from(45_000_000_000_000_000_u64);
+        strategy.data.upfront_fees = vec![(100, U256::from(9))];
+        strategy.data.pending_retry = Some(PendingRetry {
+            run_id: Some(4),
+            new_rate: U256::from(1),
+            max_upfront_fee: U256::from(2),
+            troves_count: U256::from(3),
+            context_timestamp: 50,
+            attempt: 2,
+            scheduled_at: 60,
+        });
+        strategy.data.gas_spent = 21_000;
+        strategy.data.upfront_fees_paid = U256::MAX;
+        strategy.lock.is_locked = true;
+        strategy.revision = 12;
+
+        let persisted = PersistedStrategy::try_from(&strategy).unwrap();
+        let restored = StableStrategy::try_from(persisted).unwrap();
+
+        assert_eq!(restored.settings.key, 3);
+        assert_eq!(restored.settings.batch_manager, Address::repeat_byte(1));
+        assert_eq!(restored.settings.registry, Some(Address::repeat_byte(2)));
+        assert_eq!(restored.settings.eoa_pk, Some(Address::repeat_byte(3)));
+        assert_eq!(restored.settings.hint_helper, Address::ZERO);
+        assert_eq!(restored.settings.target_min, U256::from(5));
+        assert_eq!(restored.settings.upfront_fee_budget, Some(U256::from(7)));
+        assert!(restored.settings.enabled);
+        assert_eq!(restored.data.latest_rate, strategy.data.latest_rate);
+        assert_eq!(restored.data.upfront_fees, strategy.data.upfront_fees);
+        assert_eq!(restored.data.pending_retry, strategy.data.pending_retry);
+        assert_eq!(restored.data.gas_spent, 21_000);
+        assert_eq!(restored.data.upfront_fees_paid, U256::MAX);
+        assert!(restored.lock.is_locked);
+        assert_eq!(restored.revision, 12);
+    }
+}
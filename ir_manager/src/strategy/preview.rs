@@ -0,0 +1,155 @@
+//! Rate Adjustment Previews
+//!
+//! Lets operators see what a strategy would do at the latest block before it goes live:
+//! the rate decision and the condition checks behind it (simulations), or the exact
+//! transaction it would submit, which can be checked against the contracts, e.g. with `cast`,
+//! before the canister signs anything (calldata previews).
+//!
+//! ```plain
+//! simulate_strategy(key, target_min?)      preview_adjustment_calldata(key)
+//!          │                                          │
+//!          ▼                                          ▼
+//! detached ExecutableStrategy ──► execution context ──► rate decision
+//!   (no lock, no writes)                                      │
+//!          ┌──────────────────────────────────────────────────┤
+//!          ▼                                                  ▼
+//! StrategySimulation                    rate unchanged? ──yes──► no calldata
+//! (rate, upfront fee, debt in front,          │
+//!  condition checks)                          no
+//!                                             ▼
+//!                             hints ──► setNewRate calldata ──► eth_estimateGas
+//! ```
+//!
+//! Both run the same reads as a real run at the latest block, so consecutive results can
+//! differ as the market moves. `verify_positioning` and the confirmation bookkeeping of real
+//! runs are skipped.
+
+use alloy_primitives::U256;
+use candid::{CandidType, Nat};
+
+use crate::{
+    journal::{JournalCollection, LogType},
+    state::STRATEGY_STATE,
+    utils::error::{ManagerError, ManagerResult},
+};
+
+use super::{engine::ConditionCheck, executable::ExecutableStrategy};
+
+/// Transaction a strategy would submit at the latest block
+#[derive(CandidType, Clone, Debug)]
+pub struct AdjustmentPreview {
+    /// Key of the strategy
+    pub key: u32,
+    /// Block the preview was computed against
+    pub block_tag: String,
+    /// Contract the transaction would be sent to (the batch manager)
+    pub target: String,
+    /// Rate currently set by the strategy
+    pub current_rate: Nat,
+    /// Rate calculated by the strategy
+    pub new_rate: Nat,
+    /// `true` if a run would submit the new rate
+    pub would_submit: bool,
+    /// Upper insertion hint, `None` if the rate is unchanged or zero
+    pub upper_hint: Option<Nat>,
+    /// Lower insertion hint, `None` if the rate is unchanged or zero
+    pub lower_hint: Option<Nat>,
+    /// Maximum upfront fee passed to `setNewRate`, including the safety margin
+    pub max_upfront_fee: Option<Nat>,
+    /// Hex-encoded `setNewRate` calldata, `None` if the rate is unchanged or zero
+    pub calldata: Option<String>,
+    /// Gas estimation of the call, `None` if it would revert or the strategy has no EOA
+    pub estimated_gas: Option<Nat>,
+}
+
+/// Rate decision a strategy run would make at the latest block
+#[derive(CandidType, Clone, Debug)]
+pub struct StrategySimulation {
+    /// Key of the strategy
+    pub key: u32,
+    /// Block the simulation was computed against
+    pub block_tag: String,
+    /// Minimum target the simulation used, which may differ from the stored one
+    pub target_min: Nat,
+    /// Rate currently set by the strategy
+    pub current_rate: Nat,
+    /// Rate calculated by the strategy
+    pub new_rate: Nat,
+    /// Predicted upfront fee of the new rate, `None` if the rate is unchanged or zero
+    pub upfront_fee: Option<Nat>,
+    /// Debt currently in front of the batch
+    pub current_debt_in_front: Nat,
+    /// Debt in front targeted by the strategy
+    pub target_debt_in_front: Nat,
+    /// Debt in front expected at the new rate, if the market stays unchanged
+    pub expected_debt_in_front: Nat,
+    /// Conditions evaluated by the strategy, in evaluation order
+    pub checks: Vec<ConditionCheck>,
+    /// `true` if a run would submit the new rate
+    pub would_submit: bool,
+}
+
+/// Returns a detached instance of a stored strategy.
+fn detached_strategy(key: u32) -> ManagerResult<ExecutableStrategy> {
+    STRATEGY_STATE
+        .with(|state| {
+            state
+                .borrow()
+                .get(&key)
+                .map(|stable| ExecutableStrategy::detached(&stable))
+        })
+        .ok_or(ManagerError::NonExistentValue)
+}
+
+/// Simulates a run of a strategy up to the transaction submission.
+///
+/// # Arguments
+/// * `key` - Unique identifier of the strategy
+/// * `target_min` - Minimum target to simulate with instead of the stored one
+pub async fn simulate_strategy(
+    key: u32,
+    target_min: Option<U256>,
+) -> ManagerResult<StrategySimulation> {
+    let mut strategy = detached_strategy(key)?;
+    if let Some(target_min) = target_min {
+        strategy.settings.target_min(target_min);
+    }
+
+    let mut journal = JournalCollection::open(Some(key));
+    journal.append_note(
+        Ok(()),
+        LogType::Info,
+        format!(
+            "Simulating a run with a minimum target of {}. Nothing is signed or submitted.",
+            strategy.settings.target_min
+        ),
+    );
+
+    let result = strategy.simulate(&mut journal).await;
+    journal.append_note(
+        result.clone().map(|_| ()),
+        LogType::Info,
+        "The simulation is finished.",
+    );
+    result
+}
+
+/// Previews the `setNewRate` transaction of a strategy without signing or submitting it.
+pub async fn preview_adjustment(key: u32) -> ManagerResult<AdjustmentPreview> {
+    let mut strategy = detached_strategy(key)?;
+
+    let mut journal = JournalCollection::open(Some(key));
+    journal.append_note(
+        Ok(()),
+        LogType::Info,
+        "Previewing the rate adjustment. Nothing is signed or submitted.",
+    );
+
+    let result = strategy.preview_adjustment(&mut journal).await;
+    journal.append_note(
+        result.clone().map(|_| ()),
+        LogType::Info,
+        "The rate adjustment preview is finished.",
+    );
+    result
+}
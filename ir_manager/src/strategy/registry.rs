@@ -0,0 +1,102 @@
+//! On-chain Adjustment Registry
+//!
+//! Third parties that consume the rate adjustment history from Ethereum should not have to
+//! trust the canister's journal. A strategy configured with an `IRMRegistry` contract records
+//! each of its confirmed rate adjustments there with a second transaction:
+//!
+//! ```plain
+//! poll_receipts ──► Confirmed { success: true } rate adjustment (run ID set)
+//!                            │
+//!                            ▼
+//!           registry set? ──no──► nothing to do
+//!                            │
+//!                           yes
+//!                            ▼
+//! recordAdjustment(batch manager, new rate, block number, tx hash) ──► registry contract
+//! ```
+//!
+//! The registry transaction is sent at the start of the next run, from the strategy's EOA and
+//! without a run ID, so that it is never indexed or recorded as an adjustment itself. Reverted
+//! adjustments are not recorded. A registry transaction that is rejected is journaled and not
+//! retried, as the adjustment stays available through `get_adjustments_between_blocks`.
+
+use alloy_primitives::{Address, FixedBytes, U256};
+use alloy_sol_types::SolCall;
+
+use crate::{
+    tx_pool::{TxRecord, TxStatus},
+    types::{recordAdjustmentCall, setNewRateCall},
+    utils::transaction_builder::decode_eip1559_transaction,
+};
+
+/// Returns the `recordAdjustment` call of a transaction, if it is a successful rate adjustment.
+///
+/// # Arguments
+/// * `record` - Transaction of the strategy whose status just changed
+/// * `batch_manager` - Batch manager the strategy adjusts the rate of
+pub fn registry_call(record: &TxRecord, batch_manager: Address) -> Option<recordAdjustmentCall> {
+    record.run_id?;
+    let TxStatus::Confirmed {
+        block_number,
+        success: true,
+        ..
+    } = record.status
+    else {
+        return None;
+    };
+    let transaction = decode_eip1559_transaction(&record.raw).ok()?;
+    record_adjustment_call(
+        batch_manager,
+        &transaction.input,
+        block_number,
+        &record.hash,
+    )
+}
+
+/// Builds the `recordAdjustment` call of the `setNewRate` calldata `input`.
+fn record_adjustment_call(
+    batch_manager: Address,
+    input: &[u8],
+    block_number: u64,
+    hash: &str,
+) -> Option<recordAdjustmentCall> {
+    let set_new_rate = setNewRateCall::abi_decode(input, true).ok()?;
+    let hash = hex::decode(hash.strip_prefix("0x").unwrap_or(hash)).ok()?;
+    Some(recordAdjustmentCall {
+        _batchManager: batch_manager,
+        _newAnnualInterestRate: U256::from(set_new_rate._newAnnualInterestRate),
+        _blockNumber: U256::from(block_number),
+        _txHash: FixedBytes::<32>::try_from(hash.as_slice()).ok()?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_adjustment_call() {
+        let batch_manager = Address::repeat_byte(7);
+        let input = setNewRateCall {
+            _newAnnualInterestRate: 45_000_000_000_000_000,
+            _upperHint: U256::from(1),
+            _lowerHint: U256::from(2),
+            _maxUpfrontFee: U256::from(3),
+        }
+        .abi_encode();
+        let hash = format!("0x{}", "ab".repeat(32));
+
+        let call = record_adjustment_call(batch_manager, &input, 21_000_000, &hash).unwrap();
+        assert_eq!(call._batchManager, batch_manager);
+        assert_eq!(
+            call._newAnnualInterestRate,
+            U256::from(45_000_000_000_000_000_u128)
+        );
+        assert_eq!(call._blockNumber, U256::from(21_000_000));
+        assert_eq!(call._txHash, FixedBytes::<32>::repeat_byte(0xab));
+
+        // Other calls, such as ckETH deposits, are not recorded
+        assert!(record_adjustment_call(batch_manager, &input[4..], 1, &hash).is_none());
+        assert!(record_adjustment_call(batch_manager, &input, 1, "0x1234").is_none());
+    }
+}
@@ -0,0 +1,205 @@
+//! Public Strategy Reports
+//!
+//! End-user facing view of a strategy for front-ends that help trove owners choose a batch
+//! manager. Only information that is already public on-chain or describes the strategy's
+//! track record is exposed; internal details such as nonces, derivation paths, and locks
+//! are left out.
+//!
+//! ```plain
+//! Health Evaluation:
+//!
+//! Halted? ──yes──► Halted
+//!    │
+//!    no
+//!    ▼
+//! Batch manager set? ──no──► Inactive
+//!    │
+//!    yes
+//!    ▼
+//! Market empty? ──yes──► Dormant
+//!    │
+//!    no
+//!    ▼
+//! Successful run within STALE_STRATEGY_THRESHOLD? ──no──► Stale
+//!    │
+//!    yes
+//!    ▼
+//! Healthy
+//! ```
+
+use alloy_primitives::Address;
+use candid::{CandidType, Nat};
+
+use crate::{
+    constants::{tolerance_margin_down, tolerance_margin_up, STALE_STRATEGY_THRESHOLD},
+    halt::HaltStatus,
+    utils::{common::u256_to_nat, error::ManagerResult},
+};
+
+use super::{batch::BatchManagerParams, stable::StableStrategy};
+
+/// Health of a strategy as seen by trove owners
+#[derive(CandidType, Clone, Copy, Debug, PartialEq)]
+pub enum StrategyHealth {
+    /// The strategy is running as expected
+    Healthy,
+    /// The strategy has not completed a run recently
+    Stale,
+    /// The strategy has no batch manager and is not running
+    Inactive,
+    /// The market has no troves to position the batch against
+    Dormant,
+    /// The canister is halted and no longer adjusts rates
+    Halted,
+}
+
+impl StrategyHealth {
+    /// Evaluates the health of a strategy at `now` (in seconds).
+    pub fn evaluate(
+        halt: &HaltStatus,
+        batch_manager: Address,
+        dormant: bool,
+        last_ok_exit: u64,
+        now: u64,
+    ) -> Self {
+        if matches!(halt, HaltStatus::Halted { .. }) {
+            StrategyHealth::Halted
+        } else if batch_manager == Address::ZERO {
+            StrategyHealth::Inactive
+        } else if dormant {
+            StrategyHealth::Dormant
+        } else if now.saturating_sub(last_ok_exit) > STALE_STRATEGY_THRESHOLD {
+            StrategyHealth::Stale
+        } else {
+            StrategyHealth::Healthy
+        }
+    }
+}
+
+/// Band around the target debt in front within which the rate is left unchanged
+#[derive(CandidType, Clone, Debug, PartialEq)]
+pub struct ProtectionBand {
+    /// Minimum target for debt positioning (scaled by 1e18)
+    pub target_min: Nat,
+    /// Tolerated shortfall below the target before the rate is increased (scaled by 1e18)
+    pub tolerance_margin_down: Nat,
+    /// Tolerated excess above the target before the rate is decreased (scaled by 1e18)
+    pub tolerance_margin_up: Nat,
+}
+
+/// Public track record of a strategy
+#[derive(CandidType, Clone, Debug, PartialEq)]
+pub struct PublicStrategyReport {
+    /// Key of the strategy
+    pub key: u32,
+    /// Batch manager contract address of the strategy
+    pub batch_manager: String,
+    /// Collateral index of the branch the strategy manages
+    pub collateral_index: Nat,
+    /// Current interest rate of the batch (scaled by 1e18)
+    pub current_rate: Nat,
+    /// Band the strategy keeps the batch in
+    pub protection_band: ProtectionBand,
+    /// Timestamp in seconds of the last rate adjustment
+    pub last_adjustment_at: Option<u64>,
+    /// Hash of the last rate adjustment transaction
+    pub last_adjustment_tx: Option<String>,
+    /// Number of rate adjustments made by the strategy
+    pub adjustment_count: u64,
+    /// Health of the strategy
+    pub health: StrategyHealth,
+    /// On-chain parameters of the batch manager, as of the last daily maintenance
+    pub batch_manager_params: Option<BatchManagerParams>,
+}
+
+impl PublicStrategyReport {
+    /// Builds the public report of a strategy at `now` (in seconds).
+    pub fn new(strategy: &StableStrategy, halt: &HaltStatus, now: u64) -> ManagerResult<Self> {
+        let settings = &strategy.settings;
+        let data = &strategy.data;
+
+        Ok(Self {
+            key: settings.key,
+            batch_manager: settings.batch_manager.to_string(),
+            collateral_index: u256_to_nat(&settings.collateral_index)?,
+            current_rate: u256_to_nat(&data.latest_rate)?,
+            protection_band: ProtectionBand {
+                target_min: u256_to_nat(&settings.target_min)?,
+                tolerance_margin_down: u256_to_nat(&tolerance_margin_down())?,
+                tolerance_margin_up: u256_to_nat(&tolerance_margin_up())?,
+            },
+            last_adjustment_at: (data.last_update != 0).then_some(data.last_update),
+            last_adjustment_tx: data.last_adjustment_tx.clone(),
+            adjustment_count: data.adjustment_count,
+            health: StrategyHealth::evaluate(
+                halt,
+                settings.batch_manager,
+                data.dormant_since.is_some(),
+                data.last_ok_exit,
+                now,
+            ),
+            batch_manager_params: data.batch_manager_params.clone(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strategy_health() {
+        let batch_manager = Address::repeat_byte(1);
+        let now = 100_000;
+
+        assert_eq!(
+            StrategyHealth::evaluate(
+                &HaltStatus::Halted { halted_at: 0 },
+                batch_manager,
+                false,
+                now,
+                now
+            ),
+            StrategyHealth::Halted
+        );
+        assert_eq!(
+            StrategyHealth::evaluate(&HaltStatus::Functional, Address::ZERO, false, now, now),
+            StrategyHealth::Inactive
+        );
+        assert_eq!(
+            StrategyHealth::evaluate(&HaltStatus::Functional, batch_manager, true, now, now),
+            StrategyHealth::Dormant
+        );
+        assert_eq!(
+            StrategyHealth::evaluate(
+                &HaltStatus::Functional,
+                batch_manager,
+                false,
+                now - STALE_STRATEGY_THRESHOLD - 1,
+                now
+            ),
+            StrategyHealth::Stale
+        );
+        assert_eq!(
+            StrategyHealth::evaluate(
+                &HaltStatus::HaltingInProgress { halts_at: now },
+                batch_manager,
+                false,
+                now - STALE_STRATEGY_THRESHOLD,
+                now
+            ),
+            StrategyHealth::Healthy
+        );
+    }
+
+    #[test]
+    fn test_report_hides_unset_adjustments() {
+        let strategy = StableStrategy::default();
+        let report = PublicStrategyReport::new(&strategy, &HaltStatus::Functional, 0).unwrap();
+
+        assert_eq!(report.last_adjustment_at, None);
+        assert_eq!(report.adjustment_count, 0);
+        assert_eq!(report.health, StrategyHealth::Inactive);
+        assert_eq!(report.batch_manager_params, None);
+    }
+}
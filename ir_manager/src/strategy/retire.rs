@@ -0,0 +1,239 @@
+//! Strategy Retirement
+//!
+//! Unwinds the state of a strategy that should no longer run, while keeping its history
+//! available for audits.
+//!
+//! ```plain
+//! remove_strategy(key)
+//!        │
+//!        ▼
+//! locked or pending transactions? ──yes──► rejected
+//!        │
+//!        no
+//!        ▼
+//! STRATEGY_STATE ──remove──► unregister_manager, release_chain ──► clear execution timer
+//!                                                                          │
+//!                                                                          ▼
+//!                            RETIRED_STRATEGIES ◄── journal collections ◄── final ExecutionResult log
+//! ```
+//!
+//! Strategies with pending transactions are rejected, as their receipts, nonce gaps, and
+//! resubmissions are still tracked through the strategy. The key can be minted again once
+//! retired; earlier retirements of the same key stay in the archive.
+//!
+//! Archives written before the journal timestamps were kept in seconds are converted when
+//! they are read.
+
+use std::borrow::Cow;
+
+use candid::{CandidType, Decode, Encode, Nat};
+use ic_exports::ic_cdk_timers::clear_timer;
+use ic_stable_structures::{storable::Bound, Storable};
+use serde::Deserialize;
+
+use crate::{
+    chain::release_chain,
+    clock::Seconds,
+    journal::{
+        take_strategy_collections, JournalCollection, LegacyJournalCollection, LogType,
+        StableJournalCollection,
+    },
+    managers::unregister_manager,
+    state::{RETIRED_STRATEGIES, STRATEGY_STATE, STRATEGY_TIMERS},
+    tx_pool::pending_transactions,
+    utils::{
+        common::u256_to_nat,
+        error::{ManagerError, ManagerResult},
+    },
+};
+
+/// Archive of a removed strategy
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct RetiredStrategy {
+    /// Key the strategy was minted with
+    pub key: u32,
+    /// Trove manager of the strategy's branch
+    pub manager: String,
+    /// Batch manager the strategy adjusted the rate of
+    pub batch_manager: String,
+    /// EOA of the strategy, if one was derived
+    pub eoa: Option<String>,
+    /// Last rate set by the strategy
+    pub latest_rate: Nat,
+    /// Timestamp in seconds at which the strategy was removed
+    pub retired_at: u64,
+    /// Journal collections of the strategy, oldest first, ending with the retirement log
+    pub journal: Vec<StableJournalCollection>,
+}
+
+impl Storable for RetiredStrategy {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        Cow::Owned(Encode!(self).expect("Failed to encode a retired strategy."))
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).unwrap_or_else(|_| {
+            Decode!(bytes.as_ref(), LegacyRetiredStrategy)
+                .expect("Failed to decode a retired strategy.")
+                .into()
+        })
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+/// Archive as stored before the journal timestamps were kept in seconds
+#[derive(CandidType, Deserialize)]
+struct LegacyRetiredStrategy {
+    key: u32,
+    manager: String,
+    batch_manager: String,
+    eoa: Option<String>,
+    latest_rate: Nat,
+    retired_at: u64,
+    journal: Vec<LegacyJournalCollection>,
+}
+
+impl From<LegacyRetiredStrategy> for RetiredStrategy {
+    fn from(value: LegacyRetiredStrategy) -> Self {
+        Self {
+            key: value.key,
+            manager: value.manager,
+            batch_manager: value.batch_manager,
+            eoa: value.eoa,
+            latest_rate: value.latest_rate,
+            retired_at: value.retired_at,
+            journal: value.journal.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+/// Removes a strategy from the state and archives its journal.
+///
+/// # Arguments
+/// * `key` - Unique identifier of the strategy
+///
+/// # Returns
+/// The archive of the strategy, also stored in `RETIRED_STRATEGIES`
+pub fn retire_strategy(key: u32) -> ManagerResult<RetiredStrategy> {
+    let strategy = STRATEGY_STATE
+        .with(|state| state.borrow().get(&key).cloned())
+        .ok_or(ManagerError::NonExistentValue)?;
+
+    if strategy.lock.is_locked {
+        return Err(ManagerError::Locked);
+    }
+    let pending = pending_transactions(key);
+    if !pending.is_empty() {
+        return Err(ManagerError::Custom(format!(
+            "The strategy has {} pending transactions. Retry once they are confirmed or dropped.",
+            pending.len()
+        )));
+    }
+
+    STRATEGY_STATE.with(|state| state.borrow_mut().remove(&key));
+    let manager_unregistered = unregister_manager(strategy.settings.manager);
+    release_chain(strategy.settings.chain.chain_id);
+    if let Some(timer) = STRATEGY_TIMERS.with(|timers| timers.borrow_mut().remove(&key)) {
+        clear_timer(timer);
+    }
+
+    // Closed before the journal is archived, so that the archive ends with it
+    JournalCollection::open(Some(key)).append_note(
+        Ok(()),
+        LogType::ExecutionResult,
+        format!(
+            "Strategy {} is retired. Its manager was {}.",
+            key,
+            if manager_unregistered {
+                "unregistered"
+            } else {
+                "kept, as other strategies use it"
+            }
+        ),
+    );
+
+    let retired = RetiredStrategy {
+        key,
+        manager: strategy.settings.manager.to_string(),
+        batch_manager: strategy.settings.batch_manager.to_string(),
+        eoa: strategy.settings.eoa_pk.map(|eoa| eoa.to_string()),
+        latest_rate: u256_to_nat(&strategy.data.latest_rate)?,
+        retired_at: Seconds::now().get(),
+        journal: take_strategy_collections(key),
+    };
+    RETIRED_STRATEGIES.with(|archive| {
+        archive
+            .borrow_mut()
+            .insert((key, retired.retired_at), retired.clone())
+    });
+
+    Ok(retired)
+}
+
+/// Returns the archives of the strategies retired with a key, oldest first.
+pub fn retired_strategies(key: u32) -> Vec<RetiredStrategy> {
+    RETIRED_STRATEGIES.with(|archive| {
+        archive
+            .borrow()
+            .range((key, 0)..=(key, u64::MAX))
+            .map(|(_, retired)| retired)
+            .collect()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::Address;
+
+    use super::*;
+    use crate::{state::MANAGERS, strategy::stable::StableStrategy};
+
+    #[test]
+    fn test_retire_strategy() {
+        let shared = Address::repeat_byte(1);
+        let own = Address::repeat_byte(2);
+        let mut first = StableStrategy::default();
+        first.settings.key = 1;
+        first.settings.manager = own;
+        let mut second = StableStrategy::default();
+        second.settings.key = 2;
+        second.settings.manager = shared;
+        let mut third = StableStrategy::default();
+        third.settings.key = 3;
+        third.settings.manager = shared;
+        STRATEGY_STATE.with(|state| {
+            let mut state = state.borrow_mut();
+            state.clear();
+            state.insert(1, first);
+            state.insert(2, second);
+            state.insert(3, third);
+        });
+        MANAGERS.with(|managers| *managers.borrow_mut() = vec![own, shared]);
+        JournalCollection::open(Some(1)).append_note(Ok(()), LogType::Info, "Run 1");
+        JournalCollection::open(Some(2)).append_note(Ok(()), LogType::Info, "Run 1");
+
+        let retired = retire_strategy(1).unwrap();
+        assert_eq!(retired.journal.len(), 2);
+        let last = &retired.journal[1].entries[0];
+        assert_eq!(last.log_type, LogType::ExecutionResult);
+        assert!(take_strategy_collections(1).is_empty());
+        assert_eq!(retired_strategies(1).len(), 1);
+        assert_eq!(
+            MANAGERS.with(|managers| managers.borrow().clone()),
+            vec![shared]
+        );
+
+        // The manager is kept while another strategy uses it
+        retire_strategy(2).unwrap();
+        assert_eq!(
+            MANAGERS.with(|managers| managers.borrow().clone()),
+            vec![shared]
+        );
+        assert!(matches!(
+            retire_strategy(2),
+            Err(ManagerError::NonExistentValue)
+        ));
+        assert!(STRATEGY_STATE.with(|state| state.borrow().contains_key(&3)));
+    }
+}
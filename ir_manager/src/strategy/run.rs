@@ -16,37 +16,193 @@
 //!                      ┌───────────┘  ╲   
 //!                      ▼              ╲
 //!               ┌────────────┐    ┌────┐
-//! Retry Loop:   │  Success   │    │Fail│
-//! MAX_RETRY     │   Exit     │    └──┬─┘
-//! ATTEMPTS      └────────────┘       │
-//!                                    └─► Retry
+//! Retry:        │  Success   │    │Fail│
+//! RunRetry      │   Exit     │    └──┬─┘
+//! attempts      └────────────┘       │
+//!                                    └─► timer (backoff + jitter) ──► resume_run
 //! ```
+//!
+//! The attempts of a failed run are spaced by the exponential backoff with jitter of the
+//! strategy's `RunRetry` (30s, 2m, 8m by default), so that they do not hit the same transient
+//! RPC failure. Only transient and external errors are retried (see `ErrorCategory`). Until
+//! the next attempt is due, the scheduled executions of the strategy are skipped.
+//!
+//! Rate adjustment resubmissions after a nonce mismatch are not retried in place.
+//! They are continued by a one-off timer with exponential backoff (`resume_rate_adjustment`).
+//! Pending transactions can be cancelled by a controller (`cancel_pending_transaction`), which
+//! takes the strategy lock like a run.
+
+use std::time::Duration;
+
+use ic_exports::{
+    ic_cdk::{api::canister_balance128, spawn},
+    ic_cdk_timers::set_timer,
+};
 
 use crate::{
-    constants::MAX_RETRY_ATTEMPTS,
-    halt::is_functional,
+    clock::time,
+    constants::RUN_RETRY_GRACE_PERIOD,
+    guard::ensure_functional,
     journal::{JournalCollection, LogType},
+    metrics::record_run_cycles,
+    runs::{finish_run, start_run},
     state::STRATEGY_STATE,
-    utils::error::ManagerError,
+    tx_pool::TxRecord,
+    upgrade::ensure_not_paused,
+    utils::error::{ManagerError, ManagerResult},
 };
 
-use super::executable::ExecutableStrategy;
+use super::{data::PendingRunRetry, executable::ExecutableStrategy, stable::update_strategy};
 
 /// Executes a strategy with retry logic and state management.
 ///
 /// Creates and manages a strategy execution lifecycle:
 /// 1. Validates system functionality
 /// 2. Opens execution journal
-/// 3. Loads strategy from state
-/// 4. Executes with automatic retries
-/// 5. Handles cleanup via Drop trait
+/// 3. Skips strategies that are paused or not bound to a batch manager yet
+/// 4. Assigns a unique run ID
+/// 5. Loads strategy from state
+/// 6. Executes the first attempt, the next ones are scheduled with backoff
+/// 7. Records the run summary after the last attempt
+/// 8. Handles cleanup via Drop trait
 ///
 /// # Arguments
 /// * `key` - Unique identifier of the strategy to execute
 pub async fn run_strategy(key: u32) {
-    assert!(is_functional());
     let mut journal = JournalCollection::open(Some(key));
 
+    if let Err(err) = ensure_functional() {
+        journal.append_note(
+            Err(err),
+            LogType::Info,
+            "The canister is halted. Skipping the strategy execution.",
+        );
+        return;
+    }
+
+    if let Err(err) = ensure_not_paused() {
+        journal.append_note(
+            Err(err),
+            LogType::Info,
+            "The canister is paused for an upgrade. Skipping the strategy execution.",
+        );
+        return;
+    }
+
+    let enabled = STRATEGY_STATE.with(|state| {
+        state
+            .borrow()
+            .get(&key)
+            .map_or(true, |strategy| strategy.settings.enabled)
+    });
+    if !enabled {
+        journal.append_note(
+            Ok(()),
+            LogType::Info,
+            "The strategy is paused. Skipping the strategy execution until `resume_strategy` is called.",
+        );
+        return;
+    }
+
+    let awaiting_batch_manager = STRATEGY_STATE.with(|state| {
+        state
+            .borrow()
+            .get(&key)
+            .map_or(false, |strategy| strategy.settings.awaiting_batch_manager())
+    });
+    if awaiting_batch_manager {
+        journal.append_note(
+            Ok(()),
+            LogType::Info,
+            "The strategy is awaiting a batch manager. Skipping the strategy execution until `set_batch_manager` binds one.",
+        );
+        return;
+    }
+
+    if let Some(retry) = pending_run_retry(key) {
+        let now = time() / 1_000_000_000;
+        if now <= retry.due_at.saturating_add(RUN_RETRY_GRACE_PERIOD) {
+            journal.append_note(
+                Ok(()),
+                LogType::Info,
+                format!(
+                    "Attempt {} of run {} is scheduled. Skipping the strategy execution.",
+                    retry.attempt, retry.run_id
+                ),
+            );
+            return;
+        }
+        // The timer of the retry did not fire, e.g. because of an upgrade
+        clear_pending_run_retry(key);
+        finish_run(
+            retry.run_id,
+            retry.attempt.saturating_sub(1),
+            Err(ManagerError::Custom(
+                "The next attempt of the run was lost.".to_string(),
+            )),
+        );
+    }
+
+    let run_id = start_run(key);
+    journal.set_run_id(run_id);
+    journal.append_note(Ok(()), LogType::Info, format!("Run {} is started.", run_id));
+
+    run_attempt(key, journal, run_id, 1, 0).await;
+}
+
+/// Continues a failed run with its next attempt, once its backoff delay is over.
+///
+/// The attempt is dropped if the run was superseded in the meantime.
+///
+/// # Arguments
+/// * `key` - Unique identifier of the strategy
+/// * `run_id` - ID of the run to continue
+/// * `attempt` - Number of the attempt to execute
+pub async fn resume_run(key: u32, run_id: u64, attempt: u8) {
+    let mut journal = JournalCollection::open(Some(key));
+    journal.set_run_id(run_id);
+
+    let Some(retry) =
+        pending_run_retry(key).filter(|retry| retry.run_id == run_id && retry.attempt == attempt)
+    else {
+        journal.append_note(
+            Ok(()),
+            LogType::Info,
+            format!(
+                "Attempt {} of run {} is no longer scheduled. Skipping it.",
+                attempt, run_id
+            ),
+        );
+        return;
+    };
+    clear_pending_run_retry(key);
+
+    if let Err(err) = ensure_functional().and_then(|_| ensure_not_paused()) {
+        journal.append_note(
+            Err(err.clone()),
+            LogType::Info,
+            "The canister is halted or paused. The run is given up.",
+        );
+        finish_run(run_id, attempt.saturating_sub(1), Err(err));
+        return;
+    }
+
+    run_attempt(key, journal, run_id, attempt, retry.cycles_burned).await;
+}
+
+/// Executes one attempt of a run.
+///
+/// A failed attempt schedules the next one after the backoff delay of the strategy's
+/// `RunRetry`, until the attempts are exhausted. The last attempt records the run summary.
+async fn run_attempt(
+    key: u32,
+    mut journal: JournalCollection,
+    run_id: u64,
+    attempt: u8,
+    cycles_burned: u128,
+) {
+    let balance_before = canister_balance128();
+
     // Create an executable instance of the strategy
     let strategy: Option<ExecutableStrategy> = STRATEGY_STATE.with(|state| {
         state.borrow().get(&key).map_or_else(
@@ -60,27 +216,214 @@ pub async fn run_strategy(key: u32) {
         )
     });
 
-    if let Some(mut executable_strategy) = strategy {
-        journal.append_note(Ok(()), LogType::Info, "Executable strategy is created.");
+    let Some(mut executable_strategy) = strategy else {
+        finish_run(run_id, attempt, Err(ManagerError::NonExistentValue));
+        return;
+    };
+    journal.append_note(Ok(()), LogType::Info, "Executable strategy is created.");
 
-        for turn in 1..=MAX_RETRY_ATTEMPTS {
-            let result = executable_strategy.execute(&mut journal).await;
-            executable_strategy.unlock();
+    let run_retry = executable_strategy.settings.run_retry;
+    let max_attempts = run_retry.max_attempts.max(1);
+    let result = executable_strategy.execute(&mut journal).await;
 
-            // log the result
+    // log the result
+    journal.append_note(
+        result.clone(),
+        LogType::ExecutionResult,
+        format!(
+            "Strategy execution attempt {}/{} is finished.",
+            attempt, max_attempts
+        ),
+    );
+
+    let cycles_burned =
+        cycles_burned.saturating_add(balance_before.saturating_sub(canister_balance128()));
+
+    let category = result.as_ref().err().map(ManagerError::category);
+    let retryable = category.is_some_and(|category| category.is_retryable());
+    if let Some(category) = category.filter(|_| !retryable && attempt < max_attempts) {
+        journal.append_note(
+            Ok(()),
+            LogType::Info,
+            format!(
+                "The run is not retried, as retrying cannot fix a {:?} error.",
+                category
+            ),
+        );
+    }
+
+    if retryable && attempt < max_attempts {
+        let now = time() / 1_000_000_000;
+        let delay = run_retry.delay(attempt, time());
+        executable_strategy.data.pending_run_retry = Some(PendingRunRetry {
+            run_id,
+            attempt: attempt + 1,
+            due_at: now.saturating_add(delay),
+            cycles_burned,
+        });
+        executable_strategy.unlock();
+        set_timer(Duration::from_secs(delay), move || {
+            spawn(resume_run(key, run_id, attempt + 1));
+        });
+        journal.append_note(
+            Ok(()),
+            LogType::Info,
+            format!(
+                "Retrying the run in {} seconds (attempt {}/{}).",
+                delay,
+                attempt + 1,
+                max_attempts
+            ),
+        );
+        return;
+    }
+
+    if result.is_ok() {
+        executable_strategy.data.record_last_ok_exit();
+    }
+    if let Err(err) = &result {
+        executable_strategy.data.record_failure(err.clone());
+    }
+
+    if let Some(mut warmup) = executable_strategy.data.warmup.take() {
+        if warmup.record_run(result.is_ok()) {
             journal.append_note(
-                result.clone(),
-                LogType::ExecutionResult,
+                Ok(()),
+                LogType::Info,
                 format!(
-                    "Strategy execution attempt is finished. Attempts remaining: {}",
-                    MAX_RETRY_ATTEMPTS - turn
+                    "The warm-up is over after {} runs ({} restarts). The strategy is live from its next run.",
+                    warmup.required_runs, warmup.restarts
                 ),
             );
-
-            if result.is_ok() {
-                executable_strategy.data.record_last_ok_exit();
-                break;
+        } else {
+            if result.is_err() {
+                journal.append_note(
+                    Ok(()),
+                    LogType::Info,
+                    format!(
+                        "WARNING: The run failed during the warm-up. The warm-up restarts with {} runs.",
+                        warmup.remaining_runs
+                    ),
+                );
             }
+            executable_strategy.data.warmup(Some(warmup));
         }
     }
+    executable_strategy.unlock();
+
+    finish_run(run_id, attempt, result);
+    // Summed over the attempts of the run
+    record_run_cycles(cycles_burned, 0);
+}
+
+/// Returns the next attempt of a failed run of a strategy, if one is scheduled.
+fn pending_run_retry(key: u32) -> Option<PendingRunRetry> {
+    STRATEGY_STATE.with(|state| {
+        state
+            .borrow()
+            .get(&key)
+            .and_then(|strategy| strategy.data.pending_run_retry.clone())
+    })
+}
+
+/// Clears the scheduled attempt of a strategy's run.
+fn clear_pending_run_retry(key: u32) {
+    let _ = update_strategy(key, |strategy| strategy.data.pending_run_retry = None);
+}
+
+/// Schedules the continuation of a strategy's pending rate adjustment after `delay` seconds.
+pub(crate) fn schedule_rate_adjustment_retry(key: u32, delay: u64) {
+    set_timer(Duration::from_secs(delay), move || {
+        spawn(resume_rate_adjustment(key));
+    });
+}
+
+/// Resubmits the pending rate adjustment of a strategy.
+///
+/// The continuation journals under the run ID of the run that calculated the rate.
+///
+/// # Arguments
+/// * `key` - Unique identifier of the strategy
+pub async fn resume_rate_adjustment(key: u32) {
+    let mut journal = JournalCollection::open(Some(key));
+
+    if let Err(err) = ensure_functional() {
+        journal.append_note(
+            Err(err),
+            LogType::Info,
+            "The canister is halted. Skipping the rate adjustment resubmission.",
+        );
+        return;
+    }
+
+    let strategy: Option<ExecutableStrategy> =
+        STRATEGY_STATE.with(|state| state.borrow().get(&key).map(ExecutableStrategy::from));
+
+    let mut executable_strategy = match strategy {
+        Some(strategy) => strategy,
+        None => {
+            journal.append_note(
+                Err(ManagerError::NonExistentValue),
+                LogType::Info,
+                "This strategy key was not found in the state. The resubmission could not be started.",
+            );
+            return;
+        }
+    };
+
+    if !executable_strategy.settings.enabled {
+        journal.append_note(
+            Ok(()),
+            LogType::Info,
+            "The strategy is paused. Skipping the rate adjustment resubmission, which is discarded by the first run after the strategy is resumed.",
+        );
+        return;
+    }
+
+    if let Some(run_id) = executable_strategy
+        .data
+        .pending_retry
+        .as_ref()
+        .and_then(|retry| retry.run_id)
+    {
+        journal.set_run_id(run_id);
+    }
+
+    let result = executable_strategy
+        .resume_rate_adjustment(&mut journal)
+        .await;
+    executable_strategy.unlock();
+
+    journal.append_note(
+        result,
+        LogType::ExecutionResult,
+        "Rate adjustment resubmission is finished.",
+    );
+}
+
+/// Cancels the oldest pending transaction of a strategy.
+///
+/// # Arguments
+/// * `key` - Unique identifier of the strategy
+///
+/// # Returns
+/// The pool record of the cancellation
+pub async fn cancel_pending_transaction(key: u32) -> ManagerResult<TxRecord> {
+    let mut journal = JournalCollection::open(Some(key));
+
+    let mut executable_strategy = STRATEGY_STATE
+        .with(|state| state.borrow().get(&key).map(ExecutableStrategy::from))
+        .ok_or(ManagerError::NonExistentValue)?;
+
+    let result = executable_strategy
+        .cancel_pending_transaction(&mut journal)
+        .await;
+    executable_strategy.unlock();
+
+    journal.append_note(
+        result.clone().map(|_| ()),
+        LogType::ExecutionResult,
+        "Pending transaction cancellation is finished.",
+    );
+    result
 }
@@ -19,12 +19,28 @@
 
 use alloy_primitives::{Address, U256};
 use candid::{CandidType, Nat};
+use serde::Deserialize;
 
 use crate::{
+    chain::ChainConfig,
+    constants::{
+        MAX_RETRY_ATTEMPTS, RETRY_BASE_DELAY, RETRY_MAX_DELAY, RUN_RETRY_BASE_DELAY,
+        RUN_RETRY_JITTER_PERCENT, RUN_RETRY_MAX_DELAY,
+    },
+    scheduler::ExecutionTrigger,
     types::DerivationPath,
-    utils::{common::u256_to_nat, error::ManagerError, evm_rpc::Service},
+    utils::{
+        common::u256_to_nat,
+        error::ManagerError,
+        evm_rpc::Service,
+        gas::FeePolicy,
+        units::{Bps, Wei},
+        user_operation::UserOperationConfig,
+    },
 };
 
+use super::engine::{RateGranularity, RateStrategyKind};
+
 /// Strategy configuration parameters with lazy initialization.
 ///
 /// Configuration categories:
@@ -33,6 +49,7 @@ use crate::{
 ///    - Hint helper
 ///    - Trove manager
 ///    - Registry contracts
+///    - Adjustment registry, if any
 ///
 /// 2. Execution Parameters
 ///    - Collateral index
@@ -43,9 +60,27 @@ use crate::{
 ///    - Strategy key
 ///    - EOA settings
 ///    - RPC configuration
+///    - Chain
+///
+/// 4. Retry Behavior
+///    - Resubmission backoff
+///    - Run retries
+///
+/// 5. Decision Logic
+///    - Rate strategy
+///    - Rate guard
+///
+/// 6. Spending
+///    - Gas budget
+///    - Upfront fee budget
+///    - Fee policy
+///    - Execution backend
+///
+/// 7. Availability
+///    - Enabled flag, set when the strategy is minted
 #[derive(Clone, Default)]
 pub struct StrategySettings {
-    /// Key in the Hashmap<u32, StrategyData> that is `STRATEGY_DATA`
+    /// Key in the HashMap<u32, StableStrategy> that is `STRATEGY_STATE`
     pub key: u32,
     /// Batch manager contract address for this strategy
     pub batch_manager: Address,
@@ -59,6 +94,8 @@ pub struct StrategySettings {
     pub multi_trove_getter: Address,
     /// Sorted troves contract address for this strategy
     pub sorted_troves: Address,
+    /// `IRMRegistry` contract the confirmed rate adjustments are recorded in, if any
+    pub registry: Option<Address>,
     /// Collateral index
     pub collateral_index: U256,
     /// Derivation path of the ECDSA signature
@@ -71,6 +108,148 @@ pub struct StrategySettings {
     pub eoa_pk: Option<Address>,
     /// RPC canister service
     pub rpc_canister: Service,
+    /// Chain the strategy operates on
+    pub chain: ChainConfig,
+    /// Backoff between rate adjustment resubmissions
+    pub retry_backoff: RetryBackoff,
+    /// Attempts of a failed run and the backoff between them
+    pub run_retry: RunRetry,
+    /// Decision logic used to pick new rates
+    pub rate_strategy: RateStrategyKind,
+    /// Increment and rounding of the proposed rates
+    pub rate_granularity: RateGranularity,
+    /// Bounds on the rate jumps submitted without a controller acknowledgment
+    pub rate_guard: RateGuard,
+    /// Maximum fees in wei the EOA may spend per `GAS_BUDGET_WINDOW`, unlimited if `None`
+    pub gas_budget: Option<u128>,
+    /// Maximum upfront fees the batch may pay per `UPFRONT_FEE_BUDGET_WINDOW`, unlimited if
+    /// `None`
+    pub upfront_fee_budget: Option<U256>,
+    /// EIP-1559 fee policy of the strategy's transactions
+    pub fee_policy: FeePolicy,
+    /// How the rate adjustments are submitted and paid for
+    pub execution_backend: ExecutionBackend,
+    /// What triggers the executions of the strategy in `Timers` scheduling mode
+    pub execution_trigger: ExecutionTrigger,
+    /// `false` if a controller paused the strategy, which then skips all executions
+    pub enabled: bool,
+}
+
+/// Exponential backoff between rate adjustment resubmissions.
+///
+/// The delay before attempt `n + 1` is `base_delay * 2^(n - 1)`, capped at `max_delay`.
+#[derive(Clone, Copy, Debug, PartialEq, CandidType, Deserialize)]
+pub struct RetryBackoff {
+    /// Delay in seconds before the first resubmission
+    pub base_delay: u64,
+    /// Upper bound for the delay in seconds
+    pub max_delay: u64,
+}
+
+impl Default for RetryBackoff {
+    fn default() -> Self {
+        Self {
+            base_delay: RETRY_BASE_DELAY,
+            max_delay: RETRY_MAX_DELAY,
+        }
+    }
+}
+
+/// Attempts of a failed strategy run, spaced by an exponential backoff with jitter.
+///
+/// The delay before attempt `n + 1` is `base_delay * 4^(n - 1)`, capped at `max_delay`, plus
+/// a random jitter of up to `jitter_percent` of it, so that the attempts do not hit the same
+/// transient RPC failure.
+#[derive(Clone, Copy, Debug, PartialEq, CandidType, Deserialize)]
+pub struct RunRetry {
+    /// Number of attempts of a run, including the first one
+    pub max_attempts: u8,
+    /// Delay in seconds before the second attempt
+    pub base_delay: u64,
+    /// Upper bound for the delay in seconds, before the jitter
+    pub max_delay: u64,
+    /// Maximum jitter in percent of the delay
+    pub jitter_percent: u8,
+}
+
+impl Default for RunRetry {
+    fn default() -> Self {
+        Self {
+            max_attempts: MAX_RETRY_ATTEMPTS,
+            base_delay: RUN_RETRY_BASE_DELAY,
+            max_delay: RUN_RETRY_MAX_DELAY,
+            jitter_percent: RUN_RETRY_JITTER_PERCENT,
+        }
+    }
+}
+
+impl RunRetry {
+    /// Returns the delay in seconds to wait after the given failed attempt (starting at 1).
+    ///
+    /// The jitter is drawn from `entropy`.
+    pub fn delay(&self, attempt: u8, entropy: u64) -> u64 {
+        let exponent = u32::from(attempt.saturating_sub(1));
+        let delay = self
+            .base_delay
+            .saturating_mul(4_u64.saturating_pow(exponent))
+            .min(self.max_delay);
+        let max_jitter = delay.saturating_mul(u64::from(self.jitter_percent)) / 100;
+        delay.saturating_add(entropy % max_jitter.saturating_add(1))
+    }
+}
+
+/// Bounds on the rate jumps a strategy submits without a controller acknowledgment.
+///
+/// A new rate out of bounds is held back as an anomaly, e.g. caused by corrupted reads.
+#[derive(Clone, Copy, Debug, Default, PartialEq, CandidType, Deserialize)]
+pub struct RateGuard {
+    /// Maximum deviation from the latest rate in basis points, unbounded if `None`
+    pub max_rate_delta_bps: Option<u64>,
+    /// Maximum rate in basis points, unbounded if `None`
+    pub max_rate_bps: Option<u64>,
+}
+
+impl RateGuard {
+    /// Returns why a jump from `latest_rate` to `new_rate` is out of bounds, if it is.
+    pub fn violation(&self, latest_rate: U256, new_rate: U256) -> Option<String> {
+        if let Some(max_rate) = self.max_rate_bps.map(Bps) {
+            if new_rate > max_rate.to_rate().0 {
+                return Some(format!(
+                    "The rate {} exceeds the ceiling of {}.",
+                    new_rate, max_rate
+                ));
+            }
+        }
+        if let Some(max_delta) = self.max_rate_delta_bps.map(Bps) {
+            if new_rate.abs_diff(latest_rate) > max_delta.to_rate().0 {
+                return Some(format!(
+                    "The jump from {} to {} exceeds the maximum deviation of {}.",
+                    latest_rate, new_rate, max_delta
+                ));
+            }
+        }
+        None
+    }
+}
+
+/// How the rate adjustments of a strategy are submitted and paid for
+#[derive(Clone, Debug, Default, PartialEq, CandidType, Deserialize)]
+pub enum ExecutionBackend {
+    /// Transactions signed and paid for by the strategy EOA
+    #[default]
+    Eoa,
+    /// UserOperations of a smart account owned by the EOA, sponsored by a paymaster
+    UserOperation(UserOperationConfig),
+}
+
+impl RetryBackoff {
+    /// Returns the delay in seconds to wait after the given failed attempt (starting at 1).
+    pub fn delay(&self, attempt: u8) -> u64 {
+        let exponent = u32::from(attempt.saturating_sub(1));
+        self.base_delay
+            .saturating_mul(2_u64.saturating_pow(exponent))
+            .min(self.max_delay)
+    }
 }
 
 impl StrategySettings {
@@ -151,12 +330,95 @@ impl StrategySettings {
         self.rpc_canister = rpc_canister;
         self
     }
+
+    /// Sets the chain the strategy operates on.
+    pub fn chain(&mut self, chain: ChainConfig) -> &mut Self {
+        self.chain = chain;
+        self
+    }
+
+    /// Sets the backoff between rate adjustment resubmissions for the strategy.
+    pub fn retry_backoff(&mut self, retry_backoff: RetryBackoff) -> &mut Self {
+        self.retry_backoff = retry_backoff;
+        self
+    }
+
+    /// Sets the attempts of a failed run and the backoff between them.
+    pub fn run_retry(&mut self, run_retry: RunRetry) -> &mut Self {
+        self.run_retry = run_retry;
+        self
+    }
+
+    /// Sets the decision logic used to pick new rates for the strategy.
+    pub fn rate_strategy(&mut self, rate_strategy: RateStrategyKind) -> &mut Self {
+        self.rate_strategy = rate_strategy;
+        self
+    }
+
+    /// Sets the increment and rounding of the rates proposed for the strategy.
+    pub fn rate_granularity(&mut self, rate_granularity: RateGranularity) -> &mut Self {
+        self.rate_granularity = rate_granularity;
+        self
+    }
+
+    /// Sets the bounds on the rate jumps submitted without a controller acknowledgment.
+    pub fn rate_guard(&mut self, rate_guard: RateGuard) -> &mut Self {
+        self.rate_guard = rate_guard;
+        self
+    }
+
+    /// Sets the maximum fees in wei the strategy's EOA may spend per `GAS_BUDGET_WINDOW`.
+    pub fn gas_budget(&mut self, gas_budget: Option<u128>) -> &mut Self {
+        self.gas_budget = gas_budget;
+        self
+    }
+
+    /// Sets the maximum upfront fees the batch may pay per `UPFRONT_FEE_BUDGET_WINDOW`.
+    pub fn upfront_fee_budget(&mut self, upfront_fee_budget: Option<U256>) -> &mut Self {
+        self.upfront_fee_budget = upfront_fee_budget;
+        self
+    }
+
+    /// Sets the `IRMRegistry` contract the confirmed rate adjustments are recorded in.
+    pub fn registry(&mut self, registry: Option<Address>) -> &mut Self {
+        self.registry = registry;
+        self
+    }
+
+    /// Sets the EIP-1559 fee policy of the strategy's transactions.
+    pub fn fee_policy(&mut self, fee_policy: FeePolicy) -> &mut Self {
+        self.fee_policy = fee_policy;
+        self
+    }
+
+    /// Sets how the rate adjustments of the strategy are submitted and paid for.
+    pub fn execution_backend(&mut self, execution_backend: ExecutionBackend) -> &mut Self {
+        self.execution_backend = execution_backend;
+        self
+    }
+
+    /// Sets what triggers the executions of the strategy in `Timers` scheduling mode.
+    pub fn execution_trigger(&mut self, execution_trigger: ExecutionTrigger) -> &mut Self {
+        self.execution_trigger = execution_trigger;
+        self
+    }
+
+    /// Sets whether the strategy is executed.
+    pub fn enabled(&mut self, enabled: bool) -> &mut Self {
+        self.enabled = enabled;
+        self
+    }
+
+    /// Returns `true` if the strategy was minted but not yet bound to a batch manager.
+    pub fn awaiting_batch_manager(&self) -> bool {
+        self.batch_manager == Address::ZERO
+    }
 }
 
 /// Candid-compatible settings representation for queries.
 #[derive(Clone, Default, CandidType)]
 pub struct StrategySettingsQuery {
-    /// Key in the Hashmap<u32, StrategyData> that is `STRATEGY_DATA`
+    /// Key in the HashMap<u32, StableStrategy> that is `STRATEGY_STATE`
     pub key: u32,
     /// Batch manager contract address for this strategy
     pub batch_manager: String,
@@ -170,6 +432,8 @@ pub struct StrategySettingsQuery {
     pub multi_trove_getter: String,
     /// Sorted troves contract address for this strategy
     pub sorted_troves: String,
+    /// `IRMRegistry` contract the confirmed rate adjustments are recorded in, if any
+    pub registry: Option<String>,
     /// Collateral index
     pub collateral_index: Nat,
     /// Minimum target for this strategy
@@ -178,6 +442,32 @@ pub struct StrategySettingsQuery {
     pub upfront_fee_period: Nat,
     /// The EOA's public key
     pub eoa_pk: Option<String>,
+    /// EIP-155 chain ID of the chain the strategy operates on
+    pub chain_id: u64,
+    /// ckETH helper contract of the strategy's chain, if ckETH can be minted from it
+    pub cketh_helper: Option<String>,
+    /// Backoff between rate adjustment resubmissions
+    pub retry_backoff: RetryBackoff,
+    /// Attempts of a failed run and the backoff between them
+    pub run_retry: RunRetry,
+    /// Decision logic used to pick new rates
+    pub rate_strategy: RateStrategyKind,
+    /// Increment and rounding of the proposed rates
+    pub rate_granularity: RateGranularity,
+    /// Bounds on the rate jumps submitted without a controller acknowledgment
+    pub rate_guard: RateGuard,
+    /// Maximum fees in wei the EOA may spend per `GAS_BUDGET_WINDOW`
+    pub gas_budget: Option<Nat>,
+    /// Maximum upfront fees the batch may pay per `UPFRONT_FEE_BUDGET_WINDOW`
+    pub upfront_fee_budget: Option<Nat>,
+    /// EIP-1559 fee policy of the strategy's transactions
+    pub fee_policy: FeePolicy,
+    /// How the rate adjustments are submitted and paid for
+    pub execution_backend: ExecutionBackend,
+    /// What triggers the executions of the strategy in `Timers` scheduling mode
+    pub execution_trigger: ExecutionTrigger,
+    /// `false` if a controller paused the strategy
+    pub enabled: bool,
 }
 
 impl TryFrom<StrategySettings> for StrategySettingsQuery {
@@ -192,10 +482,24 @@ impl TryFrom<StrategySettings> for StrategySettingsQuery {
             collateral_registry: value.collateral_registry.to_string(),
             multi_trove_getter: value.multi_trove_getter.to_string(),
             sorted_troves: value.sorted_troves.to_string(),
+            registry: value.registry.map(|registry| registry.to_string()),
             collateral_index: u256_to_nat(&value.collateral_index)?,
-            target_min: u256_to_nat(&value.target_min)?,
+            target_min: Wei(value.target_min).to_nat(),
             upfront_fee_period: u256_to_nat(&value.upfront_fee_period)?,
             eoa_pk: value.eoa_pk.map(|address| address.to_string()),
+            chain_id: value.chain.chain_id,
+            cketh_helper: value.chain.cketh_helper,
+            retry_backoff: value.retry_backoff,
+            run_retry: value.run_retry,
+            rate_strategy: value.rate_strategy,
+            rate_granularity: value.rate_granularity,
+            rate_guard: value.rate_guard,
+            gas_budget: value.gas_budget.map(Nat::from),
+            upfront_fee_budget: value.upfront_fee_budget.map(|budget| Wei(budget).to_nat()),
+            fee_policy: value.fee_policy,
+            execution_backend: value.execution_backend,
+            execution_trigger: value.execution_trigger,
+            enabled: value.enabled,
         })
     }
 }
@@ -224,7 +528,13 @@ mod tests {
         let target_min = U256::from(500u64);
         let upfront_fee_period = U256::from(3600u64);
         let eoa_pk = Some(Address::repeat_byte(0x66));
+        let registry = Some(Address::repeat_byte(0x77));
         let rpc_service = Service::default();
+        let chain = ChainConfig {
+            chain_id: 8453,
+            rpc_services: None,
+            cketh_helper: None,
+        };
 
         settings
             .key(key)
@@ -238,7 +548,10 @@ mod tests {
             .target_min(target_min)
             .upfront_fee_period(upfront_fee_period)
             .eoa_pk(eoa_pk)
-            .rpc_canister(rpc_service.clone());
+            .registry(registry)
+            .rpc_canister(rpc_service.clone())
+            .chain(chain.clone())
+            .enabled(true);
 
         assert_eq!(settings.key, key);
         assert_eq!(settings.batch_manager, batch_manager);
@@ -251,6 +564,64 @@ mod tests {
         assert_eq!(settings.target_min, target_min);
         assert_eq!(settings.upfront_fee_period, upfront_fee_period);
         assert_eq!(settings.eoa_pk, eoa_pk);
+        assert_eq!(settings.registry, registry);
+        assert_eq!(settings.chain, chain);
+        assert!(settings.enabled);
+        assert!(!settings.awaiting_batch_manager());
+        assert!(StrategySettings::default().awaiting_batch_manager());
+    }
+
+    #[test]
+    fn test_retry_backoff_delay() {
+        let backoff = RetryBackoff {
+            base_delay: 12,
+            max_delay: 60,
+        };
+
+        assert_eq!(backoff.delay(1), 12);
+        assert_eq!(backoff.delay(2), 24);
+        assert_eq!(backoff.delay(3), 48);
+        assert_eq!(backoff.delay(4), 60);
+        assert_eq!(backoff.delay(u8::MAX), 60);
+    }
+
+    #[test]
+    fn test_run_retry_delay() {
+        let retry = RunRetry {
+            max_attempts: 4,
+            base_delay: 30,
+            max_delay: 480,
+            jitter_percent: 0,
+        };
+        assert_eq!(retry.delay(1, 7), 30);
+        assert_eq!(retry.delay(2, 7), 120);
+        assert_eq!(retry.delay(3, 7), 480);
+        assert_eq!(retry.delay(u8::MAX, 7), 480);
+
+        let retry = RunRetry {
+            jitter_percent: 20,
+            ..retry
+        };
+        assert_eq!(retry.delay(1, 0), 30);
+        assert_eq!(retry.delay(1, 6), 36);
+        assert_eq!(retry.delay(1, 7), 30);
+        assert!((480..=576).contains(&retry.delay(3, u64::MAX)));
+    }
+
+    #[test]
+    fn test_rate_guard_violation() {
+        let bps = |value: u64| Bps(value).to_rate().0;
+        assert_eq!(RateGuard::default().violation(bps(100), bps(10_000)), None);
+
+        let guard = RateGuard {
+            max_rate_delta_bps: Some(200),
+            max_rate_bps: Some(2_000),
+        };
+        assert_eq!(guard.violation(bps(500), bps(700)), None);
+        assert_eq!(guard.violation(bps(500), bps(300)), None);
+        assert!(guard.violation(bps(500), bps(701)).is_some());
+        assert!(guard.violation(bps(500), bps(299)).is_some());
+        assert!(guard.violation(bps(1_900), bps(2_001)).is_some());
     }
 
     // Property-based test for StrategySettings setters
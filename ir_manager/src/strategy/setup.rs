@@ -0,0 +1,198 @@
+//! Strategy Setup
+//!
+//! Mints strategies and assigns their batch managers. The controller entrypoints and the
+//! installation with init arguments share these steps, so that a strategy installed with the
+//! canister is configured exactly like one minted afterwards:
+//!
+//! ```plain
+//! mint_strategy ──► validate inputs ──► register chain ──► confirm contracts ──► derive EOA
+//!                                                                                   │
+//!        ┌──────────────────────────────────────────────────────────────────────────┘
+//!        ▼
+//!   store strategy ──► register manager
+//!
+//! set_batch_manager ──► validate address ──► no shared batch manager ──► confirm contract
+//! ```
+//!
+//! Guards and audit records are left to the callers.
+
+use alloy_primitives::U256;
+use candid::Nat;
+
+use crate::{
+    chain::{register_chain, release_chain, ChainConfig},
+    constants::scale,
+    managers::register_manager,
+    state::STRATEGY_STATE,
+    strategy::{
+        conflicts::{conflicts_with, ConfigConflict, ConflictKind},
+        data::StrategyData,
+        settings::StrategySettings,
+        stable::{update_strategy, StableStrategy},
+        warmup::{Warmup, WarmupReason},
+    },
+    types::StrategyInput,
+    utils::{
+        address::{confirm_address_role, parse_address, AddressRole},
+        common::{get_nonce, nat_to_u256, string_to_address},
+        error::{ManagerError, ManagerResult, MintError},
+        evm_rpc::Service,
+        signer::{ecdsa_key_id, get_canister_public_key, pubkey_bytes_to_address},
+    },
+};
+
+/// Mints a strategy and returns the address of its EOA.
+pub async fn mint_strategy(strategy: StrategyInput) -> Result<String, MintError> {
+    let chain = strategy.chain.clone().unwrap_or_default();
+    let result = mint(strategy, chain.clone()).await;
+    // A failed mint must not keep its chain registered
+    release_chain(chain.chain_id);
+    result
+}
+
+async fn mint(strategy: StrategyInput, chain: ChainConfig) -> Result<String, MintError> {
+    if STRATEGY_STATE.with(|strategies| strategies.borrow().contains_key(&strategy.key)) {
+        return Err(MintError::KeyInUse);
+    }
+
+    // Validate all inputs before any call is made
+    let address = |field: &str, value: String| {
+        parse_address(field, &value, false).map_err(MintError::from_address_error)
+    };
+    let number = |field: &str, value: &Nat| {
+        nat_to_u256(value).map_err(|_| MintError::InvalidNumber {
+            field: field.to_string(),
+        })
+    };
+    let manager = address("manager", strategy.manager)?;
+    let collateral_registry_address = address("collateral_registry", strategy.collateral_registry)?;
+    let multi_trove_getter_address = address("multi_trove_getter", strategy.multi_trove_getter)?;
+    let sorted_troves = address("sorted_troves", strategy.sorted_troves)?;
+    let hint_helper_address = address("hint_helper", strategy.hint_helper)?;
+    let collateral_index_u256 = number("collateral_index", &strategy.collateral_index)?;
+    let upfront_fee_period_u256 = number("upfront_fee_period", &strategy.upfront_fee_period)?;
+    let target_min_u256 = number("target_min", &strategy.target_min)?;
+    if target_min_u256 == U256::ZERO || target_min_u256 > scale() {
+        return Err(MintError::TargetOutOfBounds);
+    }
+
+    // Two strategies on the same branch would fight each other's rate adjustments
+    let candidate = StrategySettings::default()
+        .key(strategy.key)
+        .manager(manager)
+        .collateral_index(collateral_index_u256)
+        .chain(chain.clone())
+        .clone();
+    let conflicts = conflicts_with(&candidate)?;
+    if !conflicts.is_empty() && !strategy.force.unwrap_or(false) {
+        return Err(MintError::ConflictingConfiguration { conflicts });
+    }
+
+    // The chain is registered before the calls below, which are routed through it
+    register_chain(&chain)?;
+    let rpc_canister = Service(strategy.rpc_principal, chain.chain_id);
+    for (field, contract) in [
+        ("manager", manager),
+        ("collateral_registry", collateral_registry_address),
+        ("multi_trove_getter", multi_trove_getter_address),
+        ("sorted_troves", sorted_troves),
+        ("hint_helper", hint_helper_address),
+    ] {
+        confirm_address_role(&rpc_canister, field, contract, AddressRole::Contract)
+            .await
+            .map_err(MintError::from_address_error)?;
+    }
+
+    let derivation_path = vec![strategy.key.to_be_bytes().to_vec()];
+    let public_key_bytes = get_canister_public_key(ecdsa_key_id(), None, derivation_path.clone())
+        .await
+        .map_err(|err| match err {
+            ManagerError::CallResult(code, message) => {
+                MintError::KeyDerivationFailed { code, message }
+            }
+            err => MintError::Rejected(err),
+        })?;
+    let eoa_pk = string_to_address(pubkey_bytes_to_address(&public_key_bytes)?)?;
+    let eoa_nonce = get_nonce(&rpc_canister, eoa_pk)
+        .await
+        .map_err(|_| MintError::RpcPrincipalUnreachable)?;
+
+    let strategy_settings = StrategySettings::default()
+        .key(strategy.key)
+        .manager(manager)
+        .collateral_registry(collateral_registry_address)
+        .multi_trove_getter(multi_trove_getter_address)
+        .sorted_troves(sorted_troves)
+        .hint_helper(hint_helper_address)
+        .upfront_fee_period(upfront_fee_period_u256)
+        .collateral_index(collateral_index_u256)
+        .eoa_pk(Some(eoa_pk))
+        .derivation_path(derivation_path)
+        .target_min(target_min_u256)
+        .rpc_canister(rpc_canister)
+        .chain(chain.clone())
+        .enabled(true)
+        .clone();
+
+    // The following line sets the nonce, latest rate, and latest update timestamp to 0.
+    // We don't care about any of those at this point.
+    // The nonce will be recalculated.
+    // The latest rate will be adjusted when the `set_batch_manager` function is called.
+    // The timestamp will stay as 0 until the first strategy rate adjustment tx is sent.
+    let strategy_data = StrategyData::default()
+        .eoa_nonce(eoa_nonce.to::<u64>())
+        .warmup(Warmup::start(WarmupReason::Minted))
+        .clone();
+
+    // The key may have been taken by a concurrent mint while awaiting the calls above
+    StableStrategy::default()
+        .settings(strategy_settings)
+        .data(strategy_data)
+        .mint()
+        .map_err(|_| MintError::KeyInUse)?;
+
+    // Only register the manager once the strategy exists, so that failed mints don't leave it behind
+    register_manager(manager);
+
+    Ok(eoa_pk.to_string())
+}
+
+/// Assigns the batch manager of a strategy and initializes its latest rate.
+pub async fn set_batch_manager(
+    key: u32,
+    batch_manager: String,
+    current_rate: Nat,
+) -> ManagerResult<()> {
+    let batch_manager_address = parse_address("batch_manager", &batch_manager, false)?;
+    let latest_rate = nat_to_u256(&current_rate)?;
+
+    let candidate = STRATEGY_STATE
+        .with(|strategies| strategies.borrow().get(&key).cloned())
+        .ok_or(ManagerError::NonExistentValue)?
+        .settings
+        .batch_manager(batch_manager_address)
+        .clone();
+    let shared_batch_managers: Vec<ConfigConflict> = conflicts_with(&candidate)?
+        .into_iter()
+        .filter(|conflict| matches!(conflict.kind, ConflictKind::SharedBatchManager { .. }))
+        .collect();
+    if !shared_batch_managers.is_empty() {
+        return Err(ManagerError::Custom(format!(
+            "The batch manager is already used by another strategy: {:?}",
+            shared_batch_managers
+        )));
+    }
+
+    confirm_address_role(
+        &candidate.rpc_canister,
+        "batch_manager",
+        batch_manager_address,
+        AddressRole::Contract,
+    )
+    .await?;
+
+    update_strategy(key, |strategy| {
+        strategy.settings.batch_manager = batch_manager_address;
+        strategy.data.latest_rate = latest_rate;
+    })
+}
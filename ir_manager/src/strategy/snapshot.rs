@@ -0,0 +1,135 @@
+//! Market Snapshots
+//!
+//! Exposes the market a strategy positions its batch in, as read by a run at the latest
+//! block, so that delegates can check the positioning against independent sources such as
+//! Dune dashboards:
+//!
+//! ```plain
+//! get_market_snapshot(key)
+//!          │
+//!          ▼
+//! detached ExecutableStrategy ──► execution context ──┬──► sorted troves (one per bucket)
+//!   (no lock, no writes)                              ├──► debt in front of the batch
+//!                                                     └──► target debt in front
+//! ```
+//!
+//! Each bucket aggregates the debt of a batch, or of the individual troves, at one rate. The
+//! target is the one the rate decision of a run compares the debt in front with.
+
+use alloy_primitives::Address;
+use candid::{CandidType, Nat};
+
+use crate::{
+    journal::{JournalCollection, LogType},
+    state::STRATEGY_STATE,
+    types::DebtPerInterestRate,
+    utils::{
+        common::u256_to_nat,
+        error::{ManagerError, ManagerResult},
+    },
+};
+
+use super::executable::ExecutableStrategy;
+
+/// Debt of the troves of the branch at one rate, in ascending rate order
+#[derive(CandidType, Clone, Debug, PartialEq)]
+pub struct TroveBucket {
+    /// Batch manager the troves delegated to, `None` for individual troves
+    pub batch_manager: Option<String>,
+    /// Annual interest rate (scaled by 1e18)
+    pub interest_rate: Nat,
+    /// Debt of the troves
+    pub debt: Nat,
+    /// `true` if the bucket belongs to the strategy's batch
+    pub own_batch: bool,
+}
+
+impl TroveBucket {
+    /// Converts a bucket of the `MultiTroveGetter` for the batch of `batch_manager`.
+    pub fn new(bucket: &DebtPerInterestRate, batch_manager: Address) -> ManagerResult<Self> {
+        Ok(Self {
+            batch_manager: (bucket.interestBatchManager != Address::ZERO)
+                .then(|| bucket.interestBatchManager.to_string()),
+            interest_rate: u256_to_nat(&bucket.interestRate)?,
+            debt: u256_to_nat(&bucket.debt)?,
+            own_batch: bucket.interestBatchManager == batch_manager,
+        })
+    }
+}
+
+/// Market of a strategy at the latest block
+#[derive(CandidType, Clone, Debug)]
+pub struct MarketSnapshot {
+    /// Key of the strategy
+    pub key: u32,
+    /// Block the snapshot was taken at
+    pub block_tag: String,
+    /// Timestamp in seconds of the block
+    pub block_timestamp: u64,
+    /// Batch manager of the strategy
+    pub batch_manager: String,
+    /// Sorted troves of the branch, one entry per bucket
+    pub troves: Vec<TroveBucket>,
+    /// Debt in front of the lowest bucket of the batch, `None` if no trove delegated to it
+    pub debt_in_front: Option<Nat>,
+    /// Debt of the batch over all of its buckets
+    pub batch_debt: Nat,
+    /// Target percentage of the redeemable debt to keep in front (scaled by 1e18)
+    pub target_percentage: Nat,
+    /// Maximum debt redeemable against the branch's collateral
+    pub maximum_redeemable_against_collateral: Nat,
+    /// Debt in front targeted by the strategy
+    pub target_debt_in_front: Nat,
+}
+
+/// Takes a snapshot of the market of a strategy at the latest block.
+pub async fn market_snapshot(key: u32) -> ManagerResult<MarketSnapshot> {
+    let mut strategy = STRATEGY_STATE
+        .with(|state| {
+            state
+                .borrow()
+                .get(&key)
+                .map(|stable| ExecutableStrategy::detached(&stable))
+        })
+        .ok_or(ManagerError::NonExistentValue)?;
+
+    let mut journal = JournalCollection::open(Some(key));
+    let result = strategy.market_snapshot(&mut journal).await;
+    journal.append_note(
+        result.clone().map(|_| ()),
+        LogType::Info,
+        "Took a snapshot of the market. Nothing is signed or submitted.",
+    );
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::U256;
+
+    use super::*;
+
+    #[test]
+    fn test_trove_bucket() {
+        let batch_manager = Address::repeat_byte(1);
+        let bucket = |manager: Address| DebtPerInterestRate {
+            interestBatchManager: manager,
+            interestRate: U256::from(5),
+            debt: U256::from(100),
+        };
+
+        let own = TroveBucket::new(&bucket(batch_manager), batch_manager).unwrap();
+        assert_eq!(own.batch_manager, Some(batch_manager.to_string()));
+        assert_eq!(own.interest_rate, Nat::from(5_u8));
+        assert_eq!(own.debt, Nat::from(100_u8));
+        assert!(own.own_batch);
+
+        let individual = TroveBucket::new(&bucket(Address::ZERO), batch_manager).unwrap();
+        assert_eq!(individual.batch_manager, None);
+        assert!(!individual.own_batch);
+
+        let other = TroveBucket::new(&bucket(Address::repeat_byte(2)), batch_manager).unwrap();
+        assert!(other.batch_manager.is_some());
+        assert!(!other.own_batch);
+    }
+}
@@ -20,6 +20,8 @@
 use candid::CandidType;
 
 use crate::{
+    clock::time,
+    rpc_registry::resolve_rpc_canister,
     state::STRATEGY_STATE,
     utils::error::{ManagerError, ManagerResult},
 };
@@ -40,6 +42,16 @@ use super::{
 ///
 /// The stable strategy serves as the canonical source of truth, while executable strategies
 /// handle runtime operations.
+///
+/// Every write increments the `revision`. An executable strategy only overwrites the stored
+/// strategy if the revision it loaded is still current; otherwise the writes of the admin
+/// endpoints and the batch refresh made during its awaits are merged in:
+///
+/// ```plain
+/// executor loads rev 4 ──await──► admin writes rev 5 ──► executor writes
+///                                                          │
+///        stored rev 5 != loaded rev 4 ──► merge ──► rev 6 ─┘ (conflict journaled)
+/// ```
 #[derive(Clone, Default)]
 pub struct StableStrategy {
     /// Core configuration parameters that remain constant after initialization
@@ -48,6 +60,8 @@ pub struct StableStrategy {
     pub data: StrategyData,
     /// Atomic execution lock to prevent concurrent operations
     pub lock: StableLock,
+    /// Number of writes of the strategy, used to detect lost updates
+    pub revision: u64,
 }
 
 impl StableStrategy {
@@ -98,16 +112,66 @@ impl StableStrategy {
             Ok(())
         })
     }
+
+    /// Merges the state written by an executor into this stored strategy.
+    ///
+    /// The settings belong to the admin endpoints, the batch manager parameters to the batch
+    /// refresh, and the EOA balance to the balance sweep, so they are kept, as is the latest
+    /// rate set along with a new batch manager. A controller's acknowledgment of a safety trip
+    /// is kept as long as the executor still holds a trip. The EVM RPC canister the executor
+    /// switched to, the rest of the data, and the lock belong to the executor.
+    pub fn merge_executor_state(&self, executor: &StableStrategy) -> StableStrategy {
+        let mut settings = self.settings.clone();
+        settings.rpc_canister = executor.settings.rpc_canister;
+
+        let mut data = executor.data.clone();
+        data.batch_manager_params = self.data.batch_manager_params.clone();
+        data.eoa_balance = self.data.eoa_balance.clone();
+        if self.settings.batch_manager != executor.settings.batch_manager {
+            data.latest_rate = self.data.latest_rate;
+        }
+        if let (Some(trip), Some(stored_trip)) =
+            (data.safety_trip.as_mut(), self.data.safety_trip.as_ref())
+        {
+            trip.acknowledged |= stored_trip.acknowledged;
+        }
+
+        StableStrategy {
+            settings,
+            data,
+            lock: executor.lock.clone(),
+            revision: self.revision,
+        }
+    }
+}
+
+/// Applies `update` to a stored strategy and increments its revision.
+pub fn update_strategy<R>(
+    key: u32,
+    update: impl FnOnce(&mut StableStrategy) -> R,
+) -> ManagerResult<R> {
+    STRATEGY_STATE.with(|strategies| {
+        let mut strategies = strategies.borrow_mut();
+        let strategy = strategies
+            .get_mut(&key)
+            .ok_or(ManagerError::NonExistentValue)?;
+        let result = update(strategy);
+        strategy.revision = strategy.revision.wrapping_add(1);
+        Ok(result)
+    })
 }
 
 /// Bidirectional conversion between stable and executable strategies
 impl From<&StableStrategy> for ExecutableStrategy {
+    /// Switches the strategy to the registered EVM RPC canister in effect, if any.
     fn from(value: &StableStrategy) -> Self {
-        ExecutableStrategy::new(
-            value.settings.clone(),
-            value.data.clone(),
-            value.lock.clone().into(),
-        )
+        let mut settings = value.settings.clone();
+        settings.rpc_canister =
+            resolve_rpc_canister(&settings.rpc_canister, time() / 1_000_000_000);
+        let mut strategy =
+            ExecutableStrategy::new(settings, value.data.clone(), value.lock.clone().into());
+        strategy.revision = value.revision;
+        strategy
     }
 }
 
@@ -117,6 +181,7 @@ impl From<&ExecutableStrategy> for StableStrategy {
             settings: value.settings.clone(),
             data: value.data.clone(),
             lock: value.lock.clone().into(),
+            revision: value.revision,
         }
     }
 }
@@ -133,6 +198,8 @@ pub struct StableStrategyQuery {
     pub data: StrategyDataQuery,
     /// Current execution lock status
     pub lock: LockQuery,
+    /// Whether the strategy is skipped until a batch manager is set
+    pub awaiting_batch_manager: bool,
 }
 
 /// Validated conversion from full strategy to query representation
@@ -140,6 +207,7 @@ impl TryFrom<StableStrategy> for StableStrategyQuery {
     type Error = ManagerError;
 
     fn try_from(value: StableStrategy) -> Result<Self, Self::Error> {
+        let awaiting_batch_manager = value.settings.awaiting_batch_manager();
         let settings = StrategySettingsQuery::try_from(value.settings)?;
         let data = StrategyDataQuery::try_from(value.data)?;
         let lock = LockQuery::try_from(value.lock)?;
@@ -148,6 +216,75 @@ impl TryFrom<StableStrategy> for StableStrategyQuery {
             settings,
             data,
             lock,
+            awaiting_batch_manager,
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::strategy::data::SafetyTrip;
+    use alloy_primitives::Address;
+
+    #[test]
+    fn test_concurrent_writes_are_merged() {
+        let mut stored = StableStrategy::default();
+        stored.settings.key = 7;
+        STRATEGY_STATE.with(|state| state.borrow_mut().insert(7, stored.clone()));
+
+        let mut executor = stored.clone();
+        executor.data.eoa_nonce = 3;
+
+        assert_eq!(
+            update_strategy(7, |strategy| strategy.settings.batch_manager =
+                Address::repeat_byte(1)),
+            Ok(())
+        );
+        assert!(update_strategy(8, |_| ()).is_err());
+
+        let stored = STRATEGY_STATE
+            .with(|state| state.borrow().get(&7).cloned())
+            .unwrap();
+        assert_eq!(stored.revision, 1);
+
+        let merged = stored.merge_executor_state(&executor);
+        assert_eq!(merged.settings.batch_manager, Address::repeat_byte(1));
+        assert_eq!(merged.data.eoa_nonce, 3);
+        assert_eq!(merged.data.latest_rate, stored.data.latest_rate);
+        assert_eq!(merged.revision, 1);
+    }
+
+    #[test]
+    fn test_acknowledgment_survives_merge() {
+        let trip = SafetyTrip {
+            run_id: Some(1),
+            latest_rate: 0_u8.into(),
+            new_rate: 1_u8.into(),
+            reason: "test".to_string(),
+            tripped_at: 100,
+            acknowledged: false,
+        };
+        let mut stored = StableStrategy::default();
+        stored.data.safety_trip = Some(SafetyTrip {
+            acknowledged: true,
+            ..trip.clone()
+        });
+
+        // The run in flight tripped the guard again before seeing the acknowledgment
+        let mut executor = StableStrategy::default();
+        executor.data.safety_trip = Some(SafetyTrip {
+            tripped_at: 200,
+            ..trip
+        });
+        let merged = stored.merge_executor_state(&executor);
+        assert!(merged.data.safety_trip.unwrap().acknowledged);
+
+        // A run whose rate is back within bounds drops the acknowledged trip
+        executor.data.safety_trip = None;
+        assert_eq!(
+            stored.merge_executor_state(&executor).data.safety_trip,
+            None
+        );
+    }
+}
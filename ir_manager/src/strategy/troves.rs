@@ -0,0 +1,157 @@
+//! Parallel Trove Fetching
+//!
+//! The trove list is paginated by trove ID: each page returns the ID to continue from, so
+//! pages can only be fetched one after another. Branches with thousands of troves then spend
+//! most of a run waiting for sequential calls. In parallel mode, the list is split into
+//! segments at interest rate boundaries, whose first troves are located with the insertion
+//! hints, and the segments are fetched concurrently:
+//!
+//! ```plain
+//! previous run's troves ──► TroveLayout { boundaries: [b1, b2, ..] }
+//!                                          │
+//!            ┌─────────────────────────────┼─────────────────────────────┐
+//!            ▼                             ▼                             ▼
+//!   tail ──► pages until rate >= b1   hint(b1) ──► pages until >= b2   hint(bn) ──► pages until end
+//!            │                             │                             │
+//!            └──────────────► reconcile_segments: keep [b(i), b(i+1)) ◄──┘
+//! ```
+//!
+//! The boundaries are the rate quantiles of the previous run, so that the segments are of
+//! similar size. The first run of a strategy, and any run in which a segment hits its share
+//! of the page cap, fetch the list sequentially. The number of segments fetched at once is the
+//! `trove_fetch_concurrency` flag, which is one (sequential fetching) by default.
+
+use alloy_primitives::U256;
+
+use crate::{
+    constants::{DEFAULT_TROVE_FETCH_CONCURRENCY, MAX_TROVE_FETCH_CONCURRENCY},
+    flags::{flag_int, TROVE_FETCH_CONCURRENCY},
+    state::TROVE_LAYOUTS,
+    types::DebtPerInterestRate,
+};
+
+/// Segmentation of a strategy's trove list, learned from its previous run
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TroveLayout {
+    /// Strictly increasing interest rates at which the segments start, the first excluded
+    pub boundaries: Vec<U256>,
+    /// Number of troves fetched by the previous run
+    pub troves_count: usize,
+}
+
+/// Returns the number of trove segments fetched at once.
+pub fn trove_fetch_concurrency() -> usize {
+    flag_int!(TROVE_FETCH_CONCURRENCY, DEFAULT_TROVE_FETCH_CONCURRENCY)
+        .clamp(1, MAX_TROVE_FETCH_CONCURRENCY) as usize
+}
+
+/// Returns `true` if the entry marks the end of the trove list.
+pub fn is_end_marker(trove: &DebtPerInterestRate) -> bool {
+    trove.debt == U256::ZERO && trove.interestRate == U256::ZERO
+}
+
+/// Splits sorted troves into `segments` segments of similar size.
+///
+/// Returns fewer boundaries when many troves share the same rate.
+pub fn segment_boundaries(troves: &[DebtPerInterestRate], segments: usize) -> Vec<U256> {
+    let mut boundaries: Vec<U256> = vec![];
+    let Some(first) = troves.first() else {
+        return boundaries;
+    };
+    for segment in 1..segments {
+        let rate = troves[troves.len() * segment / segments].interestRate;
+        let previous = boundaries.last().copied().unwrap_or(first.interestRate);
+        if rate > previous {
+            boundaries.push(rate);
+        }
+    }
+    boundaries
+}
+
+/// Merges concurrently fetched segments into the sorted trove list.
+///
+/// Segment `i` starts at the first trove with a rate of at least `boundaries[i - 1]` and may
+/// run past the next boundary, so only its troves below `boundaries[i]` are kept. End
+/// markers are left out.
+pub fn reconcile_segments(
+    boundaries: &[U256],
+    segments: Vec<Vec<DebtPerInterestRate>>,
+) -> Vec<DebtPerInterestRate> {
+    segments
+        .into_iter()
+        .enumerate()
+        .flat_map(|(index, segment)| {
+            let lower = index.checked_sub(1).map(|lower| boundaries[lower]);
+            let upper = boundaries.get(index).copied();
+            segment.into_iter().filter(move |trove| {
+                !is_end_marker(trove)
+                    && lower.map_or(true, |lower| trove.interestRate >= lower)
+                    && upper.map_or(true, |upper| trove.interestRate < upper)
+            })
+        })
+        .collect()
+}
+
+/// Returns the layout learned from the previous run of a strategy.
+pub fn trove_layout(key: u32) -> Option<TroveLayout> {
+    TROVE_LAYOUTS.with(|layouts| layouts.borrow().get(&key).cloned())
+}
+
+/// Learns the layout of a strategy's trove list for its next run.
+pub fn record_trove_layout(key: u32, troves: &[DebtPerInterestRate], segments: usize) {
+    let layout = TroveLayout {
+        boundaries: segment_boundaries(troves, segments),
+        troves_count: troves.len(),
+    };
+    TROVE_LAYOUTS.with(|layouts| layouts.borrow_mut().insert(key, layout));
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::Address;
+
+    use super::*;
+
+    fn trove(rate: u64) -> DebtPerInterestRate {
+        DebtPerInterestRate {
+            interestBatchManager: Address::ZERO,
+            interestRate: U256::from(rate),
+            debt: U256::from(100),
+        }
+    }
+
+    #[test]
+    fn test_segment_boundaries() {
+        let troves: Vec<_> = [1, 2, 3, 4, 5, 6, 7, 8].into_iter().map(trove).collect();
+        assert_eq!(
+            segment_boundaries(&troves, 4),
+            vec![U256::from(3), U256::from(5), U256::from(7)]
+        );
+        assert!(segment_boundaries(&troves, 1).is_empty());
+
+        // Shared rates collapse the segments
+        let troves: Vec<_> = [1, 1, 1, 1, 1, 1, 2, 2].into_iter().map(trove).collect();
+        assert_eq!(segment_boundaries(&troves, 4), vec![U256::from(2)]);
+    }
+
+    #[test]
+    fn test_reconcile_segments() {
+        let boundaries = vec![U256::from(3), U256::from(5)];
+        let end = DebtPerInterestRate {
+            interestBatchManager: Address::ZERO,
+            interestRate: U256::ZERO,
+            debt: U256::ZERO,
+        };
+        let segments = vec![
+            vec![trove(1), trove(2), trove(3), trove(3)],
+            vec![trove(3), trove(3), trove(4), trove(5)],
+            vec![trove(5), trove(6), end],
+        ];
+
+        let rates: Vec<U256> = reconcile_segments(&boundaries, segments)
+            .into_iter()
+            .map(|trove| trove.interestRate)
+            .collect();
+        assert_eq!(rates, [1, 2, 3, 3, 4, 5, 6].map(U256::from).to_vec());
+    }
+}
@@ -0,0 +1,121 @@
+//! Strategy Warm-up
+//!
+//! A misconfigured strategy can submit a bad rate adjustment on its very first run. While
+//! warming up, a strategy goes through its whole pipeline but only journals the adjustment
+//! it would have submitted:
+//!
+//! ```plain
+//! mint_strategy / resume_strategy / cancel_halt
+//!          │
+//!          ▼
+//!   Warmup { remaining_runs = warmup_runs } ──► run: simulate, journal the decision
+//!          ▲                                            │
+//!          │ failed run: restart                        ▼
+//!          └────────────────────────────── remaining_runs - 1 == 0? ──yes──► live
+//!                                                                             ▲
+//! confirm_warmup(key) ────────────────────────────────────────────────────────┘
+//! ```
+//!
+//! The number of runs is the `warmup_runs` flag, which is zero (no warm-up) by default.
+
+use candid::CandidType;
+use serde::Deserialize;
+
+use crate::{
+    clock::Seconds,
+    constants::DEFAULT_WARMUP_RUNS,
+    flags::{flag_int, WARMUP_RUNS},
+    state::STRATEGY_STATE,
+};
+
+use super::stable::update_strategy;
+
+/// Why a strategy is warming up
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq)]
+pub enum WarmupReason {
+    /// The strategy was just minted
+    Minted,
+    /// The strategy was resumed after a pause
+    Resumed,
+    /// A halt of the canister was lifted
+    Unhalted,
+}
+
+/// Simulation-only state of a strategy, until enough runs passed without anomalies
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq)]
+pub struct Warmup {
+    /// Why the warm-up started
+    pub reason: WarmupReason,
+    /// Number of successful runs required
+    pub required_runs: u64,
+    /// Number of successful runs left before the strategy goes live
+    pub remaining_runs: u64,
+    /// Number of runs that would have submitted a rate adjustment
+    pub would_have_adjusted: u64,
+    /// Number of times a failed run restarted the warm-up
+    pub restarts: u64,
+    /// Timestamp in seconds at which the warm-up started
+    pub started_at: u64,
+}
+
+impl Warmup {
+    /// Returns a warm-up of `warmup_runs` runs, or `None` if warm-ups are disabled.
+    pub fn start(reason: WarmupReason) -> Option<Warmup> {
+        let runs = flag_int!(WARMUP_RUNS, DEFAULT_WARMUP_RUNS);
+        if runs <= 0 {
+            return None;
+        }
+        Some(Warmup {
+            reason,
+            required_runs: runs as u64,
+            remaining_runs: runs as u64,
+            would_have_adjusted: 0,
+            restarts: 0,
+            started_at: Seconds::now().get(),
+        })
+    }
+
+    /// Counts a finished run; a failed run restarts the warm-up.
+    ///
+    /// Returns `true` once the warm-up is over.
+    pub fn record_run(&mut self, succeeded: bool) -> bool {
+        if succeeded {
+            self.remaining_runs = self.remaining_runs.saturating_sub(1);
+        } else {
+            self.remaining_runs = self.required_runs;
+            self.restarts = self.restarts.saturating_add(1);
+        }
+        self.remaining_runs == 0
+    }
+}
+
+/// Starts a warm-up for every strategy, e.g. when a halt is lifted.
+pub fn start_warmup_all(reason: WarmupReason) {
+    let keys: Vec<u32> = STRATEGY_STATE.with(|state| state.borrow().keys().copied().collect());
+    for key in keys {
+        let warmup = Warmup::start(reason.clone());
+        // The strategy cannot be missing, as the keys were just listed
+        let _ = update_strategy(key, |strategy| strategy.data.warmup = warmup);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::flags::{set_flag, FlagValue};
+
+    #[test]
+    fn test_warmup_runs() {
+        assert_eq!(Warmup::start(WarmupReason::Minted), None);
+
+        set_flag(WARMUP_RUNS, FlagValue::Int(2)).unwrap();
+        let mut warmup = Warmup::start(WarmupReason::Resumed).unwrap();
+        assert!(!warmup.record_run(true));
+        // A failed run restarts the count
+        assert!(!warmup.record_run(false));
+        assert_eq!(warmup.remaining_runs, 2);
+        assert_eq!(warmup.restarts, 1);
+        assert!(!warmup.record_run(true));
+        assert!(warmup.record_run(true));
+    }
+}
@@ -0,0 +1,167 @@
+//! System Timers
+//!
+//! Starts the recurring timers of the canister, either from the `start_timers` entrypoint or
+//! at the end of an installation with init arguments:
+//!
+//! ```plain
+//! every hour       ──► run_strategy(key), per strategy with the hourly trigger
+//! every poll       ──► poll_chain_head
+//! every refresh    ──► refresh_balances ──► cmc_top_up
+//! every day        ──► recharge_cketh, daily_cleanup, update_halt_status, publish_daily_digest,
+//!                      sweep_eoa_balances ──► refill_eoas
+//! ```
+
+use std::{sync::Arc, time::Duration};
+
+use candid::Nat;
+use ic_exports::{
+    ic_cdk::{api::canister_balance128, spawn},
+    ic_cdk_timers::set_timer_interval,
+};
+
+use crate::{
+    charger::{cmc_top_up, recharge_cketh, refresh_balances},
+    cleanup::daily_cleanup,
+    clock::time,
+    constants::{BALANCE_REFRESH_INTERVAL, CHAIN_HEAD_POLL_INTERVAL, MAX_RETRY_ATTEMPTS},
+    digest::publish_daily_digest,
+    eoa_balances::sweep_eoa_balances,
+    gas_refill::refill_eoas,
+    guard::ensure_functional,
+    halt::update_halt_status,
+    journal::{JournalCollection, LogType},
+    scheduler::{
+        execution_trigger, poll_chain_head, scheduling_mode, ExecutionTrigger, SchedulingMode,
+    },
+    state::{STRATEGY_STATE, STRATEGY_TIMERS, TIMERS_STARTED},
+    strategy::run::run_strategy,
+    upgrade::ensure_not_paused,
+};
+
+/// Starts the strategy and maintenance timers, and runs every strategy once.
+pub fn start_system_timers() {
+    // Retrieve all strategies for setting up timers
+    let strategies: Vec<u32> = STRATEGY_STATE
+        .with(|vector_data| vector_data.borrow().iter().map(|(key, _)| *key).collect());
+
+    let max_retry_attempts = Arc::new(MAX_RETRY_ATTEMPTS);
+
+    // Start all strategies immediately, unless they are scheduled by external keepers
+    if scheduling_mode() == SchedulingMode::Timers {
+        strategies.clone().into_iter().for_each(|key| {
+            spawn(run_strategy(key));
+        });
+    }
+
+    // Set timers for each strategy (execute every 1 hour)
+    // The timers are idle while the canister is in external scheduling mode,
+    // and for strategies that are triggered by new blocks.
+    strategies.into_iter().for_each(|key| {
+        let timer = set_timer_interval(Duration::from_secs(3_600), move || {
+            if scheduling_mode() == SchedulingMode::Timers
+                && execution_trigger(key) == ExecutionTrigger::Hourly
+            {
+                spawn(run_strategy(key));
+            }
+        });
+        // Kept so that `remove_strategy` can cancel it
+        STRATEGY_TIMERS.with(|timers| timers.borrow_mut().insert(key, timer));
+    });
+
+    // Poll the chain head for strategies that are triggered by new blocks
+    set_timer_interval(Duration::from_secs(CHAIN_HEAD_POLL_INTERVAL), || {
+        spawn(poll_chain_head());
+    });
+
+    // Set a recurring timer for recharging ckETH balance (execute every 24 hours)
+    set_timer_interval(Duration::from_secs(86_400), move || {
+        let max_retry_attempts = Arc::clone(&max_retry_attempts);
+        spawn(async move {
+            let mut journal = JournalCollection::open(None);
+            if let Err(err) = ensure_functional() {
+                journal.append_note(
+                    Err(err),
+                    LogType::Recharge,
+                    "The canister is halted. Skipping the recharge cycle.",
+                );
+                return;
+            }
+            if let Err(err) = ensure_not_paused() {
+                journal.append_note(
+                    Err(err),
+                    LogType::Recharge,
+                    "The canister is paused for an upgrade. Skipping the recharge cycle.",
+                );
+                return;
+            }
+            for turn in 1..=*max_retry_attempts {
+                let result = recharge_cketh(&mut journal).await;
+                // log the result
+                journal.append_note(
+                    result.clone(),
+                    crate::journal::LogType::Recharge,
+                    format!("Turn {}/{}", turn, max_retry_attempts),
+                );
+
+                if result.is_ok() {
+                    break;
+                }
+            }
+        });
+    });
+
+    // Recurring timer (24h) that:
+    // - clears all reputation change logs and resets the reputations
+    // - trims the oldest logs that fall outside the journal retention policy
+    set_timer_interval(Duration::from_secs(86_400), || {
+        if ensure_not_paused().is_ok() {
+            spawn(daily_cleanup());
+        }
+    });
+
+    set_timer_interval(Duration::from_secs(86_400), || {
+        update_halt_status();
+    });
+
+    // Warn about strategy EOAs that are running out of ETH for gas, and refill them
+    set_timer_interval(Duration::from_secs(86_400), || {
+        spawn(async {
+            sweep_eoa_balances().await;
+            if ensure_not_paused().is_ok() && ensure_functional().is_ok() {
+                refill_eoas().await;
+            }
+        });
+    });
+
+    // Refresh the cached balances served by `get_cached_balances`
+    spawn(async {
+        let _ = refresh_balances().await;
+    });
+    set_timer_interval(Duration::from_secs(BALANCE_REFRESH_INTERVAL), || {
+        spawn(async {
+            if let Err(err) = refresh_balances().await {
+                JournalCollection::open(None).append_note(
+                    Err(err),
+                    LogType::Info,
+                    "Failed to refresh the cached ckETH balance.",
+                );
+            }
+            // Fall back to the CMC if no arbitrageur kept the cycles balance up
+            if ensure_not_paused().is_ok() {
+                let _ = cmc_top_up().await;
+            }
+        });
+    });
+
+    set_timer_interval(Duration::from_secs(86_400), || {
+        if ensure_not_paused().is_err() {
+            return;
+        }
+        spawn(publish_daily_digest(
+            Nat::from(canister_balance128()),
+            time() / 1_000_000_000,
+        ));
+    });
+
+    TIMERS_STARTED.with(|started| started.set(true));
+}
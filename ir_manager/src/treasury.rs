@@ -0,0 +1,553 @@
+//! Treasury
+//!
+//! Consolidated view of the charger subsystem's financial state. The values observed while
+//! recharging and swapping are cached here, so that `get_treasury` can answer without
+//! calling the ckETH ledger or the EVM RPC canister.
+//!
+//! ```plain
+//! recharge_cketh ──► ckETH balance ──────────┐
+//! ether_deposit  ──► EOA balances, mints ────┼──► TREASURY ──► get_treasury()
+//! transfer_cketh ──► swaps ──────────────────┘        ▲
+//!                                                     │
+//!                                   cycles balance, thresholds
+//! ```
+//!
+//! The ckETH and cycles balances are also refreshed by a timer every `BALANCE_REFRESH_INTERVAL`
+//! seconds and served by `get_cached_balances`, together with their age, so that UIs can show
+//! the time they were observed at.
+//!
+//! Every completed swap is also kept in a bounded stable history with the breakdown of its
+//! rate, served by `get_swaps`.
+//!
+//! Arbitrageurs can schedule swaps with `next_swap_window`, which projects when the cycles
+//! balance falls to the swap threshold from the burn rate estimated by the metrics:
+//!
+//! ```plain
+//! opens_at = now + ceil((cycles_balance - cycles_threshold) / burn_rate)
+//! ```
+//!
+//! A pending mint is settled as soon as a higher ckETH balance is observed, and expires after
+//! `PENDING_MINT_EXPIRY` seconds otherwise.
+
+use std::collections::{HashMap, VecDeque};
+
+use alloy_primitives::U256;
+use candid::{CandidType, Nat};
+use num_traits::ToPrimitive;
+use serde::Deserialize;
+
+use crate::{
+    charger_config::charger_config,
+    constants::{
+        ether_recharge_value, BALANCE_STALENESS_THRESHOLD, MINIMUM_ATTACHED_CYCLES,
+        PENDING_MINT_EXPIRY, SWAP_VOLUME_WINDOW,
+    },
+    metrics::record_cycles_sample,
+    state::{SWAPS, TREASURY},
+    types::SwapResponseV2,
+    utils::common::u256_to_nat,
+};
+
+/// Maximum number of swaps kept in the stable swap history
+const MAX_SWAP_HISTORY: u64 = 5_000;
+
+/// A balance together with the time it was observed
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq)]
+pub struct CachedBalance {
+    /// Observed balance
+    pub amount: Nat,
+    /// Timestamp in seconds of the observation
+    pub updated_at: u64,
+}
+
+/// A cached balance together with its staleness at the time of the query
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq)]
+pub struct BalanceReading {
+    /// Last observation, `None` if the balance was never observed
+    pub balance: Option<CachedBalance>,
+    /// Seconds elapsed since the observation
+    pub age: Option<u64>,
+    /// `true` if the balance was never observed or is older than `stale_after` seconds
+    pub stale: bool,
+}
+
+/// Cached ckETH and cycles balances of the canister
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq)]
+pub struct CachedBalances {
+    /// ckETH balance in wei
+    pub cketh: BalanceReading,
+    /// Cycles balance
+    pub cycles: BalanceReading,
+    /// Age in seconds after which a balance is reported as stale
+    pub stale_after: u64,
+}
+
+/// Last observed ETH balance of a strategy EOA
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq)]
+pub struct EoaBalance {
+    /// Key of the strategy owning the EOA
+    pub strategy: u32,
+    /// Address of the EOA
+    pub address: String,
+    /// Observed ETH balance in wei
+    pub balance: CachedBalance,
+}
+
+/// A ckETH mint transaction that has not been reflected in the ckETH balance yet
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq)]
+pub struct PendingMint {
+    /// Key of the strategy whose EOA sent the deposit, `None` for the funding EOA
+    pub strategy: Option<u32>,
+    /// Hash of the deposit transaction, if returned by the RPC providers
+    pub hash: Option<String>,
+    /// Deposited ETH in wei
+    pub value: Nat,
+    /// Timestamp in seconds of the submission
+    pub submitted_at: u64,
+}
+
+/// Aggregate ckETH<>Cycles swaps over the last `window` seconds
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq)]
+pub struct SwapVolume {
+    /// Length of the window in seconds
+    pub window: u64,
+    /// Number of swaps
+    pub swaps: u64,
+    /// Cycles accepted from arbitrageurs
+    pub accepted_cycles: Nat,
+    /// ckETH transferred to arbitrageurs
+    pub returned_cketh: Nat,
+}
+
+/// Thresholds of the charger subsystem in effect
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq)]
+pub struct TreasuryThresholds {
+    /// Swaps are only accepted while the cycles balance is below this threshold
+    pub cycles_threshold: u64,
+    /// ckETH is minted when the balance falls below this threshold
+    pub cketh_threshold: Nat,
+    /// ETH deposited per ckETH mint
+    pub ether_recharge_value: Nat,
+    /// Percentage of the ETH/Cycles rate arbitrageurs pay
+    pub cycles_discount_percentage: u64,
+    /// Minimum cycles attached to a swap
+    pub minimum_attached_cycles: u64,
+}
+
+/// Aggregate financial state of the canister
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq)]
+pub struct Treasury {
+    /// Current cycles balance of the canister
+    pub cycles_balance: Nat,
+    /// Last observed ckETH balance of the canister
+    pub cketh_balance: Option<CachedBalance>,
+    /// Last observed ETH balances of the strategy EOAs
+    pub eoa_balances: Vec<EoaBalance>,
+    /// ckETH mints not reflected in the ckETH balance yet
+    pub pending_mints: Vec<PendingMint>,
+    /// Recent swap volume
+    pub swap_volume: SwapVolume,
+    /// Thresholds in effect
+    pub thresholds: TreasuryThresholds,
+}
+
+/// A completed ckETH<>Cycles swap
+#[derive(Clone, Debug)]
+struct SwapRecord {
+    at: u64,
+    accepted_cycles: Nat,
+    returned_cketh: Nat,
+}
+
+/// Cached observations of the charger subsystem
+#[derive(Clone, Debug, Default)]
+pub struct TreasuryCache {
+    cketh_balance: Option<CachedBalance>,
+    cycles_balance: Option<CachedBalance>,
+    eoa_balances: HashMap<u32, EoaBalance>,
+    pending_mints: Vec<PendingMint>,
+    mints: VecDeque<PendingMint>,
+    swaps: VecDeque<SwapRecord>,
+}
+
+/// Records an observed ckETH balance, settling the pending mints if it increased.
+pub fn record_cketh_balance(amount: Nat, now: u64) {
+    TREASURY.with(|treasury| {
+        let mut treasury = treasury.borrow_mut();
+        let increased = treasury
+            .cketh_balance
+            .as_ref()
+            .map_or(false, |previous| amount > previous.amount);
+        if increased {
+            treasury.pending_mints.clear();
+        }
+        treasury.cketh_balance = Some(CachedBalance {
+            amount,
+            updated_at: now,
+        });
+    });
+}
+
+/// Records an observed cycles balance.
+pub fn record_cycles_balance(amount: Nat, now: u64) {
+    if let Some(balance) = amount.0.to_u128() {
+        record_cycles_sample(balance, now);
+    }
+    TREASURY.with(|treasury| {
+        treasury.borrow_mut().cycles_balance = Some(CachedBalance {
+            amount,
+            updated_at: now,
+        })
+    });
+}
+
+/// Projection of when swaps are accepted again
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq)]
+pub struct SwapWindow {
+    /// Current cycles balance of the canister
+    pub cycles_balance: Nat,
+    /// Cycles balance at or below which swaps are accepted
+    pub cycles_threshold: u64,
+    /// Estimated burn rate in cycles per second, `None` until two balances were observed
+    pub burn_rate: Option<u64>,
+    /// `true` if swaps are accepted now
+    pub accepting_swaps: bool,
+    /// Projected timestamp in seconds from which swaps are accepted, `None` without a burn rate
+    pub opens_at: Option<u64>,
+}
+
+/// Projects when the cycles balance falls to the swap threshold at `now` (in seconds).
+pub fn swap_window(cycles_balance: u128, burn_rate: Option<u64>, now: u64) -> SwapWindow {
+    let cycles_threshold = charger_config().cycles_threshold;
+    let excess = cycles_balance.saturating_sub(cycles_threshold as u128);
+    let opens_at = if excess == 0 {
+        Some(now)
+    } else {
+        burn_rate.filter(|rate| *rate > 0).map(|rate| {
+            let seconds = (excess + rate as u128 - 1) / rate as u128;
+            now.saturating_add(seconds.min(u64::MAX as u128) as u64)
+        })
+    };
+
+    SwapWindow {
+        cycles_balance: Nat::from(cycles_balance),
+        cycles_threshold,
+        burn_rate,
+        accepting_swaps: excess == 0,
+        opens_at,
+    }
+}
+
+/// Returns the reading of a cached balance at `now` (in seconds).
+fn reading(balance: Option<CachedBalance>, now: u64) -> BalanceReading {
+    let age = balance
+        .as_ref()
+        .map(|balance| now.saturating_sub(balance.updated_at));
+    BalanceReading {
+        balance,
+        age,
+        stale: age.map_or(true, |age| age > BALANCE_STALENESS_THRESHOLD),
+    }
+}
+
+/// Returns the cached ckETH and cycles balances at `now` (in seconds).
+pub fn cached_balances(now: u64) -> CachedBalances {
+    TREASURY.with(|treasury| {
+        let treasury = treasury.borrow();
+        CachedBalances {
+            cketh: reading(treasury.cketh_balance.clone(), now),
+            cycles: reading(treasury.cycles_balance.clone(), now),
+            stale_after: BALANCE_STALENESS_THRESHOLD,
+        }
+    })
+}
+
+/// Records the observed ETH balance of a strategy EOA.
+pub fn record_eoa_balance(strategy: u32, address: String, balance: &U256, now: u64) {
+    let Ok(amount) = u256_to_nat(balance) else {
+        return;
+    };
+    TREASURY.with(|treasury| {
+        treasury.borrow_mut().eoa_balances.insert(
+            strategy,
+            EoaBalance {
+                strategy,
+                address,
+                balance: CachedBalance {
+                    amount,
+                    updated_at: now,
+                },
+            },
+        )
+    });
+}
+
+/// Records a submitted ckETH mint, discarding the mints that left the volume window.
+pub fn record_mint(mint: PendingMint) {
+    TREASURY.with(|treasury| {
+        let mut treasury = treasury.borrow_mut();
+        let window_start = mint.submitted_at.saturating_sub(SWAP_VOLUME_WINDOW);
+        while treasury
+            .mints
+            .front()
+            .map_or(false, |previous| previous.submitted_at < window_start)
+        {
+            treasury.mints.pop_front();
+        }
+        treasury.mints.push_back(mint.clone());
+        treasury.pending_mints.push(mint);
+    });
+}
+
+/// Returns the ETH in wei deposited for ckETH mints since `since` (in seconds).
+pub fn minted_since(since: u64) -> Nat {
+    TREASURY.with(|treasury| {
+        treasury
+            .borrow()
+            .mints
+            .iter()
+            .filter(|mint| mint.submitted_at >= since)
+            .fold(Nat::from(0_u8), |total, mint| total + mint.value.clone())
+    })
+}
+
+/// Records a completed swap, discarding the swaps that left the volume window.
+pub fn record_swap(accepted_cycles: Nat, returned_cketh: Nat, now: u64) {
+    TREASURY.with(|treasury| {
+        let mut treasury = treasury.borrow_mut();
+        let window_start = now.saturating_sub(SWAP_VOLUME_WINDOW);
+        while treasury
+            .swaps
+            .front()
+            .map_or(false, |swap| swap.at < window_start)
+        {
+            treasury.swaps.pop_front();
+        }
+        treasury.swaps.push_back(SwapRecord {
+            at: now,
+            accepted_cycles,
+            returned_cketh,
+        });
+    });
+}
+
+/// Stores a swap in the stable history under the next swap ID, evicting the oldest swaps
+/// above `MAX_SWAP_HISTORY`, and returns it with its ID.
+pub fn store_swap(mut swap: SwapResponseV2) -> SwapResponseV2 {
+    SWAPS.with(|swaps| {
+        let mut swaps = swaps.borrow_mut();
+        swap.swap_id = swaps
+            .last_key_value()
+            .map_or(0, |(id, _)| id.saturating_add(1));
+        swaps.insert(swap.swap_id, swap.clone());
+
+        let excess = swaps.len().saturating_sub(MAX_SWAP_HISTORY);
+        let oldest: Vec<u64> = swaps
+            .iter()
+            .take(excess as usize)
+            .map(|(id, _)| id)
+            .collect();
+        for id in oldest {
+            swaps.remove(&id);
+        }
+    });
+    swap
+}
+
+/// Returns up to `limit` of the most recent swaps, newest first.
+pub fn swaps(limit: u64) -> Vec<SwapResponseV2> {
+    SWAPS.with(|swaps| {
+        swaps
+            .borrow()
+            .iter()
+            .rev()
+            .take(limit as usize)
+            .map(|(_, swap)| swap)
+            .collect()
+    })
+}
+
+/// Returns the treasury status at `now` (in seconds), given the current cycles balance.
+pub fn treasury(cycles_balance: Nat, now: u64) -> Treasury {
+    TREASURY.with(|treasury| {
+        let treasury = treasury.borrow();
+
+        let mut eoa_balances: Vec<EoaBalance> = treasury.eoa_balances.values().cloned().collect();
+        eoa_balances.sort_by_key(|balance| balance.strategy);
+
+        let pending_mints = treasury
+            .pending_mints
+            .iter()
+            .filter(|mint| now < mint.submitted_at.saturating_add(PENDING_MINT_EXPIRY))
+            .cloned()
+            .collect();
+
+        let config = charger_config();
+        let window_start = now.saturating_sub(SWAP_VOLUME_WINDOW);
+        let swap_volume = treasury
+            .swaps
+            .iter()
+            .filter(|swap| swap.at >= window_start)
+            .fold(
+                SwapVolume {
+                    window: SWAP_VOLUME_WINDOW,
+                    swaps: 0,
+                    accepted_cycles: Nat::from(0_u8),
+                    returned_cketh: Nat::from(0_u8),
+                },
+                |mut volume, swap| {
+                    volume.swaps += 1;
+                    volume.accepted_cycles = volume.accepted_cycles + swap.accepted_cycles.clone();
+                    volume.returned_cketh = volume.returned_cketh + swap.returned_cketh.clone();
+                    volume
+                },
+            );
+
+        Treasury {
+            cycles_balance,
+            cketh_balance: treasury.cketh_balance.clone(),
+            eoa_balances,
+            pending_mints,
+            swap_volume,
+            thresholds: TreasuryThresholds {
+                cycles_threshold: config.cycles_threshold,
+                cketh_threshold: config.cketh_threshold,
+                ether_recharge_value: u256_to_nat(&ether_recharge_value())
+                    .unwrap_or_else(|_| Nat::from(0_u8)),
+                cycles_discount_percentage: config.cycles_discount_percentage,
+                minimum_attached_cycles: MINIMUM_ATTACHED_CYCLES,
+            },
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reset() {
+        TREASURY.with(|treasury| *treasury.borrow_mut() = TreasuryCache::default());
+    }
+
+    fn mint(submitted_at: u64) -> PendingMint {
+        PendingMint {
+            strategy: Some(1),
+            hash: None,
+            value: Nat::from(10_u8),
+            submitted_at,
+        }
+    }
+
+    #[test]
+    fn swap_window_projects_threshold_crossing() {
+        let threshold = charger_config().cycles_threshold as u128;
+
+        let open = swap_window(threshold, None, 100);
+        assert!(open.accepting_swaps);
+        assert_eq!(open.opens_at, Some(100));
+
+        let projected = swap_window(threshold + 1_001, Some(10), 100);
+        assert!(!projected.accepting_swaps);
+        assert_eq!(projected.opens_at, Some(201));
+
+        assert_eq!(swap_window(threshold + 1, None, 100).opens_at, None);
+        assert_eq!(swap_window(threshold + 1, Some(0), 100).opens_at, None);
+    }
+
+    #[test]
+    fn higher_cketh_balance_settles_pending_mints() {
+        reset();
+        record_cketh_balance(Nat::from(100_u8), 10);
+        record_mint(mint(20));
+        record_cketh_balance(Nat::from(90_u8), 30);
+        assert_eq!(treasury(Nat::from(0_u8), 40).pending_mints.len(), 1);
+
+        record_cketh_balance(Nat::from(110_u8), 50);
+        let status = treasury(Nat::from(0_u8), 60);
+        assert!(status.pending_mints.is_empty());
+        assert_eq!(
+            status.cketh_balance,
+            Some(CachedBalance {
+                amount: Nat::from(110_u8),
+                updated_at: 50
+            })
+        );
+    }
+
+    #[test]
+    fn swap_history_assigns_ids_and_lists_newest_first() {
+        let swap = SwapResponseV2 {
+            swap_id: 0,
+            receiver: candid::Principal::anonymous(),
+            timestamp: 100,
+            accepted_cycles: Nat::from(1_000_u64),
+            returning_ether: Nat::from(10_u64),
+            returning_cycles: Nat::from(0_u8),
+            xrc_rate: 100,
+            discount_percentage: charger_config().cycles_discount_percentage,
+            discounted_rate: 97,
+            effective_price: Nat::from(100_u64),
+            ledger_block_index: Nat::from(7_u8),
+        };
+
+        assert_eq!(store_swap(swap.clone()).swap_id, 0);
+        assert_eq!(store_swap(swap.clone()).swap_id, 1);
+
+        let listed = swaps(5);
+        assert_eq!(listed.len(), 2);
+        assert_eq!(listed[0].swap_id, 1);
+        assert_eq!(swaps(1).len(), 1);
+    }
+
+    #[test]
+    fn pending_mints_expire() {
+        reset();
+        record_mint(mint(100));
+        assert_eq!(treasury(Nat::from(0_u8), 100).pending_mints.len(), 1);
+        assert!(treasury(Nat::from(0_u8), 100 + PENDING_MINT_EXPIRY)
+            .pending_mints
+            .is_empty());
+    }
+
+    #[test]
+    fn swap_volume_covers_the_window() {
+        reset();
+        record_swap(Nat::from(5_u8), Nat::from(1_u8), 100);
+        record_swap(Nat::from(7_u8), Nat::from(2_u8), 100 + SWAP_VOLUME_WINDOW);
+
+        let volume = treasury(Nat::from(0_u8), 101 + SWAP_VOLUME_WINDOW).swap_volume;
+        assert_eq!(volume.swaps, 1);
+        assert_eq!(volume.accepted_cycles, Nat::from(7_u8));
+        assert_eq!(volume.returned_cketh, Nat::from(2_u8));
+    }
+
+    #[test]
+    fn cached_balances_report_staleness() {
+        reset();
+        let balances = cached_balances(100);
+        assert!(balances.cketh.stale);
+        assert_eq!(balances.cketh.age, None);
+
+        record_cketh_balance(Nat::from(5_u8), 100);
+        record_cycles_balance(Nat::from(7_u8), 50);
+        let balances = cached_balances(100 + BALANCE_STALENESS_THRESHOLD);
+        assert!(!balances.cketh.stale);
+        assert_eq!(balances.cketh.age, Some(BALANCE_STALENESS_THRESHOLD));
+        assert!(balances.cycles.stale);
+        assert_eq!(
+            balances.cycles.balance.map(|balance| balance.amount),
+            Some(Nat::from(7_u8))
+        );
+    }
+
+    #[test]
+    fn minted_volume_survives_settlement() {
+        reset();
+        record_mint(mint(100));
+        record_mint(mint(200));
+        record_cketh_balance(Nat::from(110_u8), 300);
+
+        assert_eq!(minted_since(0), Nat::from(20_u8));
+        assert_eq!(minted_since(150), Nat::from(10_u8));
+    }
+}
@@ -0,0 +1,323 @@
+//! Custom Halt Triggers
+//!
+//! Besides the built-in halt conditions in `halt`, controllers can register their own halt
+//! and alert conditions. They are expressed as predefined predicate templates with parameters,
+//! kept in stable memory, and evaluated by the daily halt status update.
+//!
+//! ```plain
+//! update_halt_status (daily)
+//!        │
+//!        ▼
+//! for each trigger: condition holds? ──no──► reset streak
+//!        │
+//!       yes, streak += 1
+//!        ▼
+//! streak long enough? ──no──► wait for the next day
+//!        │
+//!       yes
+//!        ├── Alert ──► raise_alert (journal + webhook)
+//!        └── Halt  ──► schedule_halt
+//! ```
+
+use std::borrow::Cow;
+
+use candid::{CandidType, Decode, Encode, Nat};
+use ic_stable_structures::{storable::Bound, Storable};
+use serde::Deserialize;
+
+use crate::{
+    state::{HALT_TRIGGERS, RUNS},
+    treasury::cached_balances,
+    utils::error::{ManagerError, ManagerResult},
+};
+
+/// Seconds in a day
+const DAY: u64 = 86_400;
+
+/// Predicate template of a trigger
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq)]
+pub enum TriggerCondition {
+    /// The cached ckETH balance was below `threshold` on `days` consecutive evaluations
+    LowCkethBalance {
+        /// Balance threshold in wei
+        threshold: Nat,
+        /// Number of consecutive daily evaluations
+        days: u32,
+    },
+    /// More than `max_failures` runs failed within the last `window_days` days
+    RunFailures {
+        /// Strategy to count the failures of, all strategies if `None`
+        strategy: Option<u32>,
+        /// Number of failures tolerated within the window
+        max_failures: u64,
+        /// Length of the window in days
+        window_days: u32,
+    },
+}
+
+impl TriggerCondition {
+    /// Validates the parameters of the condition.
+    pub fn validate(&self) -> ManagerResult<()> {
+        match self {
+            TriggerCondition::LowCkethBalance { days, .. } if *days == 0 => Err(
+                ManagerError::Custom("The number of days must be positive.".to_string()),
+            ),
+            TriggerCondition::RunFailures { window_days, .. } if *window_days == 0 => Err(
+                ManagerError::Custom("The window must be at least one day.".to_string()),
+            ),
+            _ => Ok(()),
+        }
+    }
+
+    /// Returns the number of consecutive evaluations the condition must hold for.
+    fn required_streak(&self) -> u32 {
+        match self {
+            TriggerCondition::LowCkethBalance { days, .. } => *days,
+            TriggerCondition::RunFailures { .. } => 1,
+        }
+    }
+
+    /// Evaluates the condition at `now` (in seconds).
+    fn holds(&self, now: u64) -> bool {
+        match self {
+            TriggerCondition::LowCkethBalance { threshold, .. } => cached_balances(now)
+                .cketh
+                .balance
+                .map_or(false, |balance| balance.amount < *threshold),
+            TriggerCondition::RunFailures {
+                strategy,
+                max_failures,
+                window_days,
+            } => {
+                let since = now.saturating_sub(u64::from(*window_days) * DAY);
+                let failures = RUNS.with(|runs| {
+                    runs.borrow()
+                        .iter()
+                        .filter(|(_, run)| {
+                            run.started_at >= since
+                                && strategy.map_or(true, |key| run.strategy == key)
+                                && matches!(run.result, Some(Err(_)))
+                        })
+                        .count() as u64
+                });
+                failures > *max_failures
+            }
+        }
+    }
+}
+
+/// What happens when a trigger fires
+#[derive(CandidType, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum TriggerAction {
+    /// Journals a high-severity alert and posts it to the webhook
+    Alert,
+    /// Schedules a halt of the canister
+    Halt,
+}
+
+/// A registered trigger and its evaluation state
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq)]
+pub struct HaltTrigger {
+    /// Predicate template and its parameters
+    pub condition: TriggerCondition,
+    /// Action taken when the trigger fires
+    pub action: TriggerAction,
+    /// Number of consecutive evaluations the condition held for
+    pub streak: u32,
+    /// Timestamp in seconds of the last time the trigger fired
+    pub last_fired_at: Option<u64>,
+}
+
+impl Storable for HaltTrigger {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        Cow::Owned(Encode!(self).expect("Failed to encode the halt trigger."))
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).expect("Failed to decode the halt trigger.")
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+/// A trigger that fired during an evaluation
+#[derive(Clone, Debug, PartialEq)]
+pub struct FiredTrigger {
+    /// ID of the trigger
+    pub id: u64,
+    /// Action to take
+    pub action: TriggerAction,
+    /// Description of the condition that held
+    pub message: String,
+}
+
+/// Registers a trigger and returns its ID.
+pub fn add_trigger(condition: TriggerCondition, action: TriggerAction) -> ManagerResult<u64> {
+    condition.validate()?;
+    HALT_TRIGGERS.with(|triggers| {
+        let mut triggers = triggers.borrow_mut();
+        let id = triggers
+            .last_key_value()
+            .map_or(0, |(id, _)| id.saturating_add(1));
+        triggers.insert(
+            id,
+            HaltTrigger {
+                condition,
+                action,
+                streak: 0,
+                last_fired_at: None,
+            },
+        );
+        Ok(id)
+    })
+}
+
+/// Removes a trigger.
+pub fn remove_trigger(id: u64) -> ManagerResult<()> {
+    HALT_TRIGGERS
+        .with(|triggers| triggers.borrow_mut().remove(&id))
+        .map(|_| ())
+        .ok_or(ManagerError::NonExistentValue)
+}
+
+/// Returns the registered triggers by ID.
+pub fn list_triggers() -> Vec<(u64, HaltTrigger)> {
+    HALT_TRIGGERS.with(|triggers| triggers.borrow().iter().collect())
+}
+
+/// Evaluates all triggers at `now` (in seconds) and returns the ones that fired.
+pub fn evaluate_triggers(now: u64) -> Vec<FiredTrigger> {
+    let mut fired = vec![];
+    for (id, mut trigger) in list_triggers() {
+        if trigger.condition.holds(now) {
+            trigger.streak = trigger.streak.saturating_add(1);
+        } else {
+            trigger.streak = 0;
+        }
+
+        if trigger.streak >= trigger.condition.required_streak() {
+            trigger.last_fired_at = Some(now);
+            fired.push(FiredTrigger {
+                id,
+                action: trigger.action,
+                message: format!(
+                    "Custom trigger {} fired: {:?} (held for {} evaluations).",
+                    id, trigger.condition, trigger.streak
+                ),
+            });
+        }
+
+        HALT_TRIGGERS.with(|triggers| triggers.borrow_mut().insert(id, trigger));
+    }
+    fired
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        runs::RunSummary,
+        state::TREASURY,
+        treasury::{record_cketh_balance, TreasuryCache},
+    };
+
+    fn reset() {
+        HALT_TRIGGERS.with(|triggers| {
+            let mut triggers = triggers.borrow_mut();
+            let ids: Vec<u64> = triggers.iter().map(|(id, _)| id).collect();
+            for id in ids {
+                triggers.remove(&id);
+            }
+        });
+        TREASURY.with(|treasury| *treasury.borrow_mut() = TreasuryCache::default());
+    }
+
+    #[test]
+    fn test_low_balance_requires_consecutive_days() {
+        reset();
+        let id = add_trigger(
+            TriggerCondition::LowCkethBalance {
+                threshold: Nat::from(100_u8),
+                days: 2,
+            },
+            TriggerAction::Halt,
+        )
+        .unwrap();
+
+        record_cketh_balance(Nat::from(50_u8), 0);
+        assert!(evaluate_triggers(DAY).is_empty());
+        record_cketh_balance(Nat::from(150_u8), 2 * DAY);
+        assert!(evaluate_triggers(2 * DAY).is_empty());
+        record_cketh_balance(Nat::from(50_u8), 3 * DAY);
+        assert!(evaluate_triggers(3 * DAY).is_empty());
+
+        let fired = evaluate_triggers(4 * DAY);
+        assert_eq!(fired.len(), 1);
+        assert_eq!(fired[0].id, id);
+        assert_eq!(fired[0].action, TriggerAction::Halt);
+    }
+
+    #[test]
+    fn test_run_failures_within_window() {
+        reset();
+        add_trigger(
+            TriggerCondition::RunFailures {
+                strategy: Some(1),
+                max_failures: 1,
+                window_days: 7,
+            },
+            TriggerAction::Alert,
+        )
+        .unwrap();
+
+        let now = 30 * DAY;
+        RUNS.with(|runs| {
+            let mut runs = runs.borrow_mut();
+            for (run_id, strategy, started_at) in
+                [(1, 1, now - 8 * DAY), (2, 1, now - DAY), (3, 2, now - DAY)]
+            {
+                runs.insert(
+                    run_id,
+                    RunSummary {
+                        run_id,
+                        strategy,
+                        started_at,
+                        finished_at: Some(started_at),
+                        attempts: 1,
+                        result: Some(Err(ManagerError::Locked)),
+                    },
+                );
+            }
+        });
+        assert!(evaluate_triggers(now).is_empty());
+
+        RUNS.with(|runs| {
+            runs.borrow_mut().insert(
+                4,
+                RunSummary {
+                    run_id: 4,
+                    strategy: 1,
+                    started_at: now,
+                    finished_at: Some(now),
+                    attempts: 1,
+                    result: Some(Err(ManagerError::Locked)),
+                },
+            )
+        });
+        assert_eq!(evaluate_triggers(now).len(), 1);
+    }
+
+    #[test]
+    fn test_validation() {
+        assert!(TriggerCondition::LowCkethBalance {
+            threshold: Nat::from(1_u8),
+            days: 0
+        }
+        .validate()
+        .is_err());
+        assert_eq!(
+            remove_trigger(u64::MAX),
+            Err(ManagerError::NonExistentValue)
+        );
+    }
+}
@@ -0,0 +1,692 @@
+//! Outbound Transaction Pool
+//!
+//! Tracks every transaction signed and submitted by the strategy EOAs in stable memory.
+//! The pool is the single source of truth for the state of EOA transactions across upgrades,
+//! and is used by nonce reconciliation, rebroadcasting, and fee bumping.
+//!
+//! ```plain
+//! Transaction Status Flow:
+//!
+//!                      receipt found
+//!              ┌─────────────────────────► Confirmed
+//!              │
+//! Submit ──► Pending ─── same nonce resent ──► Replaced
+//!              │  ▲                   ▲
+//!              │  └── stuck for N blocks: resent with bumped fees (speed up)
+//!              │                      │
+//!              │         cancel_pending_tx: self-transfer with bumped fees (cancel)
+//!              │
+//!              └─── nonce consumed, no receipt ──► Dropped
+//! ```
+//!
+//! Pending transactions are polled for receipts at the start of every strategy execution.
+//! Those still pending `stuck_tx_blocks` blocks after their submission are replaced by
+//! `utils::transaction_builder`.
+//! The fees of confirmed transactions are summed up to enforce the gas budgets of the strategies.
+//! The upfront fee a transaction charged to the batch is read from the `BatchUpdated` events of
+//! its receipt, so that the strategies can account for what they paid on top of the gas.
+//!
+//! Settled transactions (confirmed, dropped, or replaced) are removed by the daily cleanup
+//! once they are older than `SETTLED_TX_RETENTION`, or above `MAX_SETTLED_TRANSACTIONS`.
+
+use std::borrow::Cow;
+
+use alloy_primitives::{Address, B256, U256};
+use alloy_sol_types::SolEvent;
+use candid::{CandidType, Decode, Encode, Nat, Principal};
+use ic_stable_structures::{storable::Bound, Storable};
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::{
+    adjustments::index_adjustment,
+    clock::time,
+    rate_history::record_rate_adjustment,
+    state::TX_POOL,
+    types::BatchUpdated,
+    utils::{
+        common::{get_nonce, parse_quantity, request_with_dynamic_retries, u256_to_nat},
+        error::{ManagerError, ManagerResult},
+        evm_rpc::Service,
+        units::nat_to_u256,
+    },
+};
+
+/// Seconds a settled transaction is kept, longer than the gas budget window it counts towards
+const SETTLED_TX_RETENTION: u64 = 7_776_000; // 90 days
+
+/// Maximum number of settled transactions kept in stable memory
+const MAX_SETTLED_TRANSACTIONS: u64 = 5_000;
+
+/// Status of an outbound transaction
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq)]
+pub enum TxStatus {
+    /// Submitted, but no receipt was found yet
+    Pending,
+    /// Included in a block
+    Confirmed {
+        /// Number of the block that included the transaction
+        block_number: u64,
+        /// Gas used by the transaction
+        gas_used: u64,
+        /// Price paid per unit of gas in wei
+        effective_gas_price: u128,
+        /// `true` if the transaction executed successfully, `false` if it reverted
+        success: bool,
+    },
+    /// The nonce was consumed by another transaction, and this transaction never landed
+    Dropped,
+    /// Replaced by a newer transaction with the same nonce
+    Replaced {
+        /// Pool ID of the replacing transaction
+        by: u64,
+    },
+}
+
+/// A single outbound transaction record
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct TxRecord {
+    /// Unique, monotonically increasing ID in the pool
+    pub id: u64,
+    /// Key of the strategy whose EOA signed the transaction
+    pub strategy: u32,
+    /// ID of the strategy run that submitted the transaction
+    pub run_id: Option<u64>,
+    /// Nonce of the transaction
+    pub nonce: u64,
+    /// Transaction hash
+    pub hash: String,
+    /// Hex-encoded signed transaction bytes, kept for rebroadcasting
+    pub raw: String,
+    /// Current status of the transaction
+    pub status: TxStatus,
+    /// Submission timestamp in seconds
+    pub submitted_at: u64,
+    /// Timestamp of the last status update in seconds
+    pub updated_at: u64,
+    /// Principal of the EVM RPC canister the transaction was submitted through
+    pub rpc_canister: Option<Principal>,
+    /// Number of the latest block at submission, used to detect stuck transactions
+    pub submitted_block: Option<u64>,
+    /// Upfront fee charged to the strategy's batch, read from the receipt
+    pub upfront_fee: Option<Nat>,
+}
+
+impl Storable for TxRecord {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        Cow::Owned(Encode!(self).expect("Failed to encode a transaction record."))
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).expect("Failed to decode a transaction record.")
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+impl TxRecord {
+    /// Returns `true` if the transaction is still waiting for a receipt.
+    pub fn is_pending(&self) -> bool {
+        self.status == TxStatus::Pending
+    }
+
+    /// Returns the fee in wei paid for the transaction, zero unless it was included in a block.
+    ///
+    /// Reverted transactions are included, as they consume gas as well.
+    pub fn fee_paid(&self) -> u128 {
+        match self.status {
+            TxStatus::Confirmed {
+                gas_used,
+                effective_gas_price,
+                ..
+            } => u128::from(gas_used).saturating_mul(effective_gas_price),
+            _ => 0,
+        }
+    }
+
+    /// Returns the upfront fee charged to the batch by the transaction, zero if none was read
+    /// from its receipt.
+    pub fn upfront_fee_paid(&self) -> U256 {
+        self.upfront_fee
+            .as_ref()
+            .and_then(|fee| nat_to_u256(fee).ok())
+            .unwrap_or_default()
+    }
+}
+
+/// Records a newly submitted transaction as `Pending` and returns its pool ID.
+///
+/// Any pending transaction of the same strategy with the same nonce is marked as `Replaced`.
+pub fn record_transaction(
+    strategy: u32,
+    run_id: Option<u64>,
+    nonce: u64,
+    hash: String,
+    raw: String,
+    rpc_canister: Principal,
+    submitted_block: Option<u64>,
+) -> u64 {
+    let now = time() / 1_000_000_000;
+    TX_POOL.with(|pool| {
+        let mut pool = pool.borrow_mut();
+        let id = pool.last_key_value().map_or(0, |(id, _)| id + 1);
+
+        let replaced: Vec<TxRecord> = pool
+            .iter()
+            .map(|(_, record)| record)
+            .filter(|record| record.strategy == strategy && record.nonce == nonce)
+            .filter(TxRecord::is_pending)
+            .collect();
+
+        for mut record in replaced {
+            record.status = TxStatus::Replaced { by: id };
+            record.updated_at = now;
+            pool.insert(record.id, record);
+        }
+
+        pool.insert(
+            id,
+            TxRecord {
+                id,
+                strategy,
+                run_id,
+                nonce,
+                hash,
+                raw,
+                status: TxStatus::Pending,
+                submitted_at: now,
+                updated_at: now,
+                rpc_canister: Some(rpc_canister),
+                submitted_block,
+                upfront_fee: None,
+            },
+        );
+        id
+    })
+}
+
+/// Updates the status of a transaction record.
+pub fn set_status(id: u64, status: TxStatus) -> ManagerResult<()> {
+    TX_POOL.with(|pool| {
+        let mut pool = pool.borrow_mut();
+        let mut record = pool.get(&id).ok_or(ManagerError::NonExistentValue)?;
+        record.status = status;
+        record.updated_at = time() / 1_000_000_000;
+        pool.insert(id, record);
+        Ok(())
+    })
+}
+
+/// Stores the receipt of a transaction: its `Confirmed` status and the upfront fee it charged.
+fn record_receipt(id: u64, status: TxStatus, upfront_fee: U256) -> ManagerResult<()> {
+    TX_POOL.with(|pool| {
+        let mut pool = pool.borrow_mut();
+        let mut record = pool.get(&id).ok_or(ManagerError::NonExistentValue)?;
+        record.status = status;
+        record.upfront_fee = Some(u256_to_nat(&upfront_fee)?);
+        record.updated_at = time() / 1_000_000_000;
+        pool.insert(id, record);
+        Ok(())
+    })
+}
+
+/// Returns the transaction record with the given pool ID.
+pub fn get_transaction(id: u64) -> Option<TxRecord> {
+    TX_POOL.with(|pool| pool.borrow().get(&id))
+}
+
+/// Returns all pending transactions of a strategy, ordered by nonce.
+pub fn pending_transactions(strategy: u32) -> Vec<TxRecord> {
+    let mut records: Vec<TxRecord> = TX_POOL.with(|pool| {
+        pool.borrow()
+            .iter()
+            .map(|(_, record)| record)
+            .filter(|record| record.strategy == strategy && record.is_pending())
+            .collect()
+    });
+    records.sort_by_key(|record| record.nonce);
+    records
+}
+
+/// Returns the pending transactions of a strategy submitted at least `min_blocks` blocks
+/// before `current_block`, ordered by nonce.
+///
+/// Transactions recorded without a submission block are never considered stuck.
+pub fn stuck_transactions(strategy: u32, current_block: u64, min_blocks: u64) -> Vec<TxRecord> {
+    pending_transactions(strategy)
+        .into_iter()
+        .filter(|record| {
+            record
+                .submitted_block
+                .is_some_and(|block| current_block.saturating_sub(block) >= min_blocks)
+        })
+        .collect()
+}
+
+/// Returns all transactions of a strategy that were sent with the given nonce.
+pub fn transactions_for_nonce(strategy: u32, nonce: u64) -> Vec<TxRecord> {
+    TX_POOL.with(|pool| {
+        pool.borrow()
+            .iter()
+            .map(|(_, record)| record)
+            .filter(|record| record.strategy == strategy && record.nonce == nonce)
+            .collect()
+    })
+}
+
+/// Returns all transactions submitted during the given strategy run.
+pub fn transactions_for_run(run_id: u64) -> Vec<TxRecord> {
+    TX_POOL.with(|pool| {
+        pool.borrow()
+            .iter()
+            .map(|(_, record)| record)
+            .filter(|record| record.run_id == Some(run_id))
+            .collect()
+    })
+}
+
+/// Returns up to `depth` of the most recent transactions of a strategy.
+pub fn latest_transactions(strategy: u32, depth: u64) -> Vec<TxRecord> {
+    let records: Vec<TxRecord> = TX_POOL.with(|pool| {
+        pool.borrow()
+            .iter()
+            .map(|(_, record)| record)
+            .filter(|record| record.strategy == strategy)
+            .collect()
+    });
+    records[records.len().saturating_sub(depth as usize)..].to_vec()
+}
+
+/// Returns the fees in wei paid by a strategy for transactions confirmed at or after `since` (in seconds).
+pub fn gas_spent_since(strategy: u32, since: u64) -> u128 {
+    TX_POOL.with(|pool| {
+        pool.borrow()
+            .iter()
+            .map(|(_, record)| record)
+            .filter(|record| record.strategy == strategy && record.updated_at >= since)
+            .fold(0, |spent, record| spent.saturating_add(record.fee_paid()))
+    })
+}
+
+/// Removes the settled transactions last updated more than `SETTLED_TX_RETENTION` seconds
+/// before `now`, then the oldest ones above `MAX_SETTLED_TRANSACTIONS`, and returns the
+/// number of removed records.
+///
+/// Pending transactions are kept, as is the latest record, which the next pool ID follows.
+pub fn tx_pool_cleanup(now: u64) -> u64 {
+    let cutoff = now.saturating_sub(SETTLED_TX_RETENTION);
+    TX_POOL.with(|pool| {
+        let mut pool = pool.borrow_mut();
+        let last_id = pool.last_key_value().map(|(id, _)| id);
+        let settled: Vec<(u64, u64)> = pool
+            .iter()
+            .map(|(_, record)| record)
+            .filter(|record| !record.is_pending() && Some(record.id) != last_id)
+            .map(|record| (record.id, record.updated_at))
+            .collect();
+        let excess = settled
+            .len()
+            .saturating_sub(MAX_SETTLED_TRANSACTIONS as usize);
+
+        let removed: Vec<u64> = settled
+            .into_iter()
+            .enumerate()
+            .filter(|(position, (_, updated_at))| *position < excess || *updated_at < cutoff)
+            .map(|(_, (id, _))| id)
+            .collect();
+        for id in &removed {
+            pool.remove(id);
+        }
+        removed.len() as u64
+    })
+}
+
+/// The subset of an `eth_getTransactionReceipt` response that is needed by the pool.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawReceipt {
+    block_number: String,
+    gas_used: String,
+    effective_gas_price: String,
+    status: String,
+    #[serde(default)]
+    logs: Vec<RawLog>,
+}
+
+/// The subset of a receipt log that is needed to decode its event.
+#[derive(Deserialize)]
+struct RawLog {
+    topics: Vec<String>,
+    data: String,
+}
+
+/// Sums the upfront fees charged to the batch of `batch_manager` by the `BatchUpdated` events
+/// among `logs`.
+///
+/// Logs that are not `BatchUpdated` events, or that cannot be decoded, are skipped.
+fn upfront_fee_from_logs(logs: &[RawLog], batch_manager: Address) -> U256 {
+    logs.iter()
+        .filter_map(|log| {
+            let topics = log
+                .topics
+                .iter()
+                .map(|topic| topic.parse::<B256>().ok())
+                .collect::<Option<Vec<B256>>>()?;
+            let data = hex::decode(log.data.strip_prefix("0x").unwrap_or(&log.data)).ok()?;
+            BatchUpdated::decode_raw_log(topics, &data, true).ok()
+        })
+        .filter(|event| event._interestBatchManager == batch_manager)
+        .fold(U256::ZERO, |total, event| {
+            total.saturating_add(event._debtIncreaseFromUpfrontFee)
+        })
+}
+
+/// The HTTPS response format of `eth_getTransactionReceipt`.
+/// The result is `null` as long as the transaction is not included in a block.
+#[derive(Deserialize)]
+struct ReceiptResponse {
+    result: Option<RawReceipt>,
+}
+
+/// Fetches the receipt of a transaction and converts it into a `Confirmed` status, along with
+/// the upfront fee it charged to the batch of `batch_manager`.
+/// Returns `None` if the transaction is not included in a block yet.
+async fn fetch_receipt_status(
+    rpc_canister: &Service,
+    hash: &str,
+    batch_manager: Address,
+) -> ManagerResult<Option<(TxStatus, U256)>> {
+    let json_args = json!({
+        "id": 1,
+        "jsonrpc": "2.0",
+        "params": [hash],
+        "method": "eth_getTransactionReceipt"
+    })
+    .to_string();
+
+    let rpc_canister_response = request_with_dynamic_retries(rpc_canister, json_args).await?;
+
+    let decoded_response: ReceiptResponse =
+        serde_json::from_str(&rpc_canister_response).map_err(|err| {
+            ManagerError::DecodingError(format!(
+                "Could not decode eth_getTransactionReceipt response: {} error: {}",
+                &rpc_canister_response, err
+            ))
+        })?;
+
+    match decoded_response.result {
+        Some(receipt) => Ok(Some((
+            TxStatus::Confirmed {
+                block_number: parse_quantity(&receipt.block_number)? as u64,
+                gas_used: parse_quantity(&receipt.gas_used)? as u64,
+                effective_gas_price: parse_quantity(&receipt.effective_gas_price)?,
+                success: parse_quantity(&receipt.status)? == 1,
+            },
+            upfront_fee_from_logs(&receipt.logs, batch_manager),
+        ))),
+        None => Ok(None),
+    }
+}
+
+/// Polls the receipts of all pending transactions of a strategy and updates their status.
+///
+/// Transactions without a receipt whose nonce has already been consumed on-chain are marked as `Dropped`.
+/// The upfront fees are read for the batch of `batch_manager`.
+/// Returns the records whose status changed.
+pub async fn poll_receipts(
+    strategy: u32,
+    rpc_canister: &Service,
+    eoa: Address,
+    batch_manager: Address,
+) -> ManagerResult<Vec<TxRecord>> {
+    let pending = pending_transactions(strategy);
+    if pending.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let mut updated = vec![];
+    let mut unresolved = vec![];
+
+    for record in pending {
+        match fetch_receipt_status(rpc_canister, &record.hash, batch_manager).await? {
+            Some((status, upfront_fee)) => {
+                record_receipt(record.id, status, upfront_fee)?;
+                if let Some(confirmed) = get_transaction(record.id) {
+                    index_adjustment(&confirmed);
+                    record_rate_adjustment(&confirmed);
+                    updated.push(confirmed);
+                }
+            }
+            None => unresolved.push(record),
+        }
+    }
+
+    if unresolved.is_empty() {
+        return Ok(updated);
+    }
+
+    let chain_nonce = get_nonce(rpc_canister, eoa).await?.to::<u64>();
+    for record in unresolved {
+        if record.nonce < chain_nonce {
+            set_status(record.id, TxStatus::Dropped)?;
+            updated.extend(get_transaction(record.id));
+        }
+    }
+
+    Ok(updated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(status: TxStatus) -> TxRecord {
+        TxRecord {
+            id: 7,
+            strategy: 1,
+            run_id: Some(2),
+            nonce: 3,
+            hash: "0xabc".to_string(),
+            raw: "0x02f8".to_string(),
+            status,
+            submitted_at: 100,
+            updated_at: 100,
+            rpc_canister: Some(Principal::anonymous()),
+            submitted_block: Some(21_000_000),
+            upfront_fee: None,
+        }
+    }
+
+    #[test]
+    fn test_storable_roundtrip() {
+        let original = record(TxStatus::Confirmed {
+            block_number: 21_000_000,
+            gas_used: 52_000,
+            effective_gas_price: 3_000_000_000,
+            success: true,
+        });
+
+        let decoded = TxRecord::from_bytes(original.to_bytes());
+
+        assert_eq!(decoded.id, original.id);
+        assert_eq!(decoded.run_id, original.run_id);
+        assert_eq!(decoded.hash, original.hash);
+        assert_eq!(decoded.raw, original.raw);
+        assert_eq!(decoded.status, original.status);
+    }
+
+    #[test]
+    fn test_is_pending() {
+        assert!(record(TxStatus::Pending).is_pending());
+        assert!(!record(TxStatus::Dropped).is_pending());
+        assert!(!record(TxStatus::Replaced { by: 8 }).is_pending());
+    }
+
+    #[test]
+    fn test_fee_paid() {
+        let reverted = record(TxStatus::Confirmed {
+            block_number: 21_000_000,
+            gas_used: 52_000,
+            effective_gas_price: 3_000_000_000,
+            success: false,
+        });
+
+        assert_eq!(reverted.fee_paid(), 156_000_000_000_000);
+        assert_eq!(record(TxStatus::Pending).fee_paid(), 0);
+        assert_eq!(record(TxStatus::Dropped).fee_paid(), 0);
+    }
+
+    #[test]
+    fn test_upfront_fee_from_logs() {
+        let batch_manager = Address::repeat_byte(1);
+        let batch_updated = |manager: Address, fee: u64| {
+            let log = BatchUpdated {
+                _interestBatchManager: manager,
+                _operation: 2,
+                _debt: U256::from(1_000),
+                _coll: U256::from(1),
+                _annualInterestRate: U256::from(5),
+                _annualManagementFee: U256::ZERO,
+                _totalDebtShares: U256::from(1_000),
+                _debtIncreaseFromUpfrontFee: U256::from(fee),
+            }
+            .encode_log_data();
+            RawLog {
+                topics: log
+                    .topics()
+                    .iter()
+                    .map(|topic| format!("0x{}", hex::encode(topic)))
+                    .collect(),
+                data: format!("0x{}", hex::encode(&log.data)),
+            }
+        };
+        let transfer = RawLog {
+            topics: vec![format!("0x{}", "dd".repeat(32))],
+            data: "0x".to_string(),
+        };
+
+        let logs = vec![
+            batch_updated(batch_manager, 40),
+            transfer,
+            batch_updated(Address::repeat_byte(2), 7),
+            batch_updated(batch_manager, 2),
+        ];
+        assert_eq!(upfront_fee_from_logs(&logs, batch_manager), U256::from(42));
+        assert_eq!(upfront_fee_from_logs(&[], batch_manager), U256::ZERO);
+
+        let mut confirmed = record(TxStatus::Pending);
+        assert_eq!(confirmed.upfront_fee_paid(), U256::ZERO);
+        confirmed.upfront_fee = Some(Nat::from(42_u8));
+        assert_eq!(confirmed.upfront_fee_paid(), U256::from(42));
+    }
+
+    #[test]
+    fn test_stuck_transactions() {
+        TX_POOL.with(|pool| {
+            let mut pool = pool.borrow_mut();
+            let keys: Vec<u64> = pool.iter().map(|(id, _)| id).collect();
+            for key in keys {
+                pool.remove(&key);
+            }
+        });
+        let first = record_transaction(
+            9,
+            None,
+            1,
+            "0x01".to_string(),
+            "0x02".to_string(),
+            Principal::anonymous(),
+            Some(100),
+        );
+        record_transaction(
+            9,
+            None,
+            2,
+            "0x03".to_string(),
+            "0x04".to_string(),
+            Principal::anonymous(),
+            Some(104),
+        );
+        record_transaction(
+            9,
+            None,
+            3,
+            "0x05".to_string(),
+            "0x06".to_string(),
+            Principal::anonymous(),
+            None,
+        );
+
+        let stuck = stuck_transactions(9, 105, 5);
+        assert_eq!(stuck.len(), 1);
+        assert_eq!(stuck[0].id, first);
+        assert_eq!(stuck_transactions(9, 109, 5).len(), 2);
+        // A block number behind the submission does not underflow
+        assert!(stuck_transactions(9, 50, 5).is_empty());
+
+        // A replacement resets the submission block of the nonce
+        let replacement = record_transaction(
+            9,
+            None,
+            1,
+            "0x07".to_string(),
+            "0x08".to_string(),
+            Principal::anonymous(),
+            Some(105),
+        );
+        assert_eq!(
+            get_transaction(first).unwrap().status,
+            TxStatus::Replaced { by: replacement }
+        );
+        assert!(stuck_transactions(9, 105, 5).is_empty());
+    }
+
+    #[test]
+    fn test_tx_pool_cleanup() {
+        let now = SETTLED_TX_RETENTION + 1_000;
+        let insert = |id: u64, status: TxStatus, updated_at: u64| {
+            let record = TxRecord {
+                id,
+                updated_at,
+                ..record(status)
+            };
+            TX_POOL.with(|pool| pool.borrow_mut().insert(id, record));
+        };
+        insert(0, TxStatus::Dropped, 0);
+        insert(1, TxStatus::Pending, 0);
+        insert(2, TxStatus::Replaced { by: 3 }, now);
+        insert(3, TxStatus::Dropped, 0);
+
+        // The pending record and the latest record are kept, whatever their age
+        assert_eq!(tx_pool_cleanup(now), 1);
+        assert!(get_transaction(0).is_none());
+        assert!(get_transaction(1).is_some());
+        assert!(get_transaction(2).is_some());
+        assert!(get_transaction(3).is_some());
+
+        // The oldest settled records above the cap are removed, along with the expired one
+        for id in 4..(MAX_SETTLED_TRANSACTIONS + 6) {
+            insert(id, TxStatus::Dropped, now);
+        }
+        assert_eq!(tx_pool_cleanup(now), 3);
+        assert!(get_transaction(2).is_none());
+        assert!(get_transaction(3).is_none());
+        assert!(get_transaction(4).is_none());
+        assert!(get_transaction(5).is_some());
+        assert!(get_transaction(1).is_some());
+    }
+
+    #[test]
+    fn test_parse_quantity() {
+        assert_eq!(parse_quantity("0x1").unwrap(), 1);
+        assert_eq!(parse_quantity("0x1406f40").unwrap(), 21_000_000);
+        assert!(parse_quantity("0xzz").is_err());
+    }
+
+    #[test]
+    fn test_decode_pending_receipt() {
+        let response: ReceiptResponse =
+            serde_json::from_str(r#"{"id":1,"jsonrpc":"2.0","result":null}"#).unwrap();
+        assert!(response.result.is_none());
+    }
+}
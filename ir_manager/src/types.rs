@@ -3,14 +3,19 @@
 // This is allowed in this module because the sol! macro doesn't provide its own docs.
 #![allow(missing_docs)]
 
+use std::borrow::Cow;
+
 use alloy_sol_types::sol;
-use candid::{CandidType, Nat, Principal};
+use candid::{CandidType, Decode, Encode, Nat, Principal};
 #[cfg(feature = "mainnet")]
 use evm_rpc_types::EthMainnetService;
 #[cfg(feature = "sepolia")]
 use evm_rpc_types::EthSepoliaService;
+use ic_stable_structures::{storable::Bound, Storable};
 use serde::{Deserialize, Serialize};
 
+use crate::chain::ChainConfig;
+
 /// Derivation path for the tECDSA signatures
 pub type DerivationPath = Vec<Vec<u8>>;
 
@@ -23,9 +28,9 @@ pub type ProviderService = EthSepoliaService;
 pub type ProviderService = EthMainnetService;
 
 /// Strategy input provided by the caller during the initialization phase
-#[derive(CandidType, Deserialize)]
+#[derive(CandidType, Deserialize, Debug)]
 pub struct StrategyInput {
-    /// Key in the Hashmap<u32, StrategyData> that is `STRATEGY_DATA`
+    /// Key in the HashMap<u32, StableStrategy> that is `STRATEGY_STATE`
     pub key: u32,
     /// Minimum target for this strategy
     pub target_min: Nat,
@@ -45,6 +50,10 @@ pub struct StrategyInput {
     pub collateral_registry: String,
     /// Hint helper contract address.
     pub hint_helper: String,
+    /// Mints the strategy even if its configuration conflicts with another strategy
+    pub force: Option<bool>,
+    /// Chain of the strategy's branch, the home chain if `None`
+    pub chain: Option<ChainConfig>,
 }
 
 /// Response for the ckETH<>Cycles swaps
@@ -63,6 +72,57 @@ pub struct SwapResponse {
     pub discounted_rate: u64,
 }
 
+/// Response for the ckETH<>Cycles swaps with the breakdown of the rate, kept in the swap history
+#[derive(CandidType, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SwapResponseV2 {
+    /// Unique, monotonically increasing ID of the swap
+    pub swap_id: u64,
+    /// Principal that received the ckETH
+    pub receiver: Principal,
+    /// Timestamp of the swap in seconds
+    pub timestamp: u64,
+    /// The amount of accepted cycles
+    pub accepted_cycles: Nat,
+    /// The amount of ckETH that is returned to the caller
+    pub returning_ether: Nat,
+    /// Cycles that were not accepted and are being returned
+    pub returning_cycles: Nat,
+    /// The ETH<>CXDR rate reported by the exchange rate canister
+    pub xrc_rate: u64,
+    /// Percentage of the XRC rate the swap was priced at
+    pub discount_percentage: u64,
+    /// The discounted ETH<>CXDR rate
+    pub discounted_rate: u64,
+    /// Cycles paid per whole ckETH (1e18 units)
+    pub effective_price: Nat,
+    /// Index of the ckETH ledger block that holds the transfer
+    pub ledger_block_index: Nat,
+}
+
+impl Storable for SwapResponseV2 {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        Cow::Owned(Encode!(self).expect("Failed to encode a swap."))
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).expect("Failed to decode a swap.")
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+impl From<SwapResponseV2> for SwapResponse {
+    fn from(value: SwapResponseV2) -> Self {
+        Self {
+            accepted_cycles: value.accepted_cycles,
+            returning_ether: value.returning_ether,
+            returning_cycles: value.returning_cycles,
+            real_rate: value.xrc_rate,
+            discounted_rate: value.discounted_rate,
+        }
+    }
+}
+
 /// ICRC-1 subaccount type
 pub type Subaccount = [u8; 32];
 
@@ -92,6 +152,26 @@ sol!(
         uint256 debt;
     }
 
+    struct LatestBatchData {
+        uint256 entireDebtWithoutRedistribution;
+        uint256 entireCollWithoutRedistribution;
+        uint256 accruedInterest;
+        uint256 recordedDebt;
+        uint256 annualInterestRate;
+        uint256 weightedRecordedDebt;
+        uint256 annualManagementFee;
+        uint256 accruedManagementFee;
+        uint256 weightedRecordedBatchManagementFee;
+        uint256 lastDebtUpdateTime;
+        uint256 lastInterestRateAdjTime;
+    }
+
+    struct InterestBatchManager {
+        uint128 minInterestRate;
+        uint128 maxInterestRate;
+        uint256 minInterestRateChangePeriod;
+    }
+
     // Liquity getters
     function getRedemptionRateWithDecay() public view override returns (uint256);
     function getEntireBranchDebt() public view returns (uint256 entireSystemDebt);
@@ -103,6 +183,9 @@ sol!(
         returns (DebtPerInterestRate[] memory, uint256 currId);
 
     function getTroveAnnualInterestRate(uint256 _troveId) external view returns (uint256);
+    function getLatestBatchData(address _batchAddress) external view returns (LatestBatchData memory);
+    function borrowerOperations() external view returns (address);
+    function getInterestBatchManager(address _account) external view returns (InterestBatchManager memory);
     function predictAdjustBatchInterestRateUpfrontFee(
         uint256 _collIndex,
         address _batchAddress,
@@ -128,6 +211,44 @@ sol!(
         uint256 _maxUpfrontFee
     );
 
+    // Liquity events (`_operation` is the `BatchOperation` enum, encoded as `uint8`)
+    event BatchUpdated(
+        address indexed _interestBatchManager,
+        uint8 _operation,
+        uint256 _debt,
+        uint256 _coll,
+        uint256 _annualInterestRate,
+        uint256 _annualManagementFee,
+        uint256 _totalDebtShares,
+        uint256 _debtIncreaseFromUpfrontFee
+    );
+
     // ckETH Helper
     function depositEth(bytes32 principal, bytes32 subaccount) public payable;
+
+    // IRM Registry
+    function recordAdjustment(
+        address _batchManager,
+        uint256 _newAnnualInterestRate,
+        uint256 _blockNumber,
+        bytes32 _txHash
+    ) external;
+
+    // Multicall3
+    struct Call3 {
+        address target;
+        bool allowFailure;
+        bytes callData;
+    }
+
+    struct Call3Result {
+        bool success;
+        bytes returnData;
+    }
+
+    function aggregate3(Call3[] calldata calls) external payable returns (Call3Result[] memory returnData);
+
+    // ERC-4337 smart account and entry point
+    function execute(address dest, uint256 value, bytes calldata func) external;
+    function getNonce(address sender, uint192 key) external view returns (uint256 nonce);
 );
@@ -0,0 +1,592 @@
+//! Upgrade Gating
+//!
+//! Upgrading while a strategy run is awaiting an inter-canister call drops the run's
+//! continuation, which can leave a signed transaction unrecorded or a journal collection
+//! unwritten. Upgrades are therefore gated by a pause that is kept in stable memory:
+//!
+//! ```plain
+//! Running ──prepare_for_upgrade()──► Paused ──(no locks, no pending retries)──► Ready
+//!    ▲                                 │                                          │
+//!    │                          not ready yet:                             snapshot taken,
+//!    │                          call again later                            upgrade now
+//!    │                                                                            │
+//!    └──────────────────────── resume_after_upgrade() ◄───────────────────────────┘
+//! ```
+//!
+//! While paused, no new strategy runs, recharges, cleanups, digests, or swaps are started.
+//! In-flight runs and pending resubmissions are left to finish, and their journal collections
+//! are written when they do. The pause survives the upgrade, so the new binary stays idle
+//! until a controller resumes it.
+//!
+//! The strategies, managers, halt status, EOA nonces, and trove layouts live on the heap, as do
+//! the settings of the digest, journal retention, rate source, scheduling, halt callback, and
+//! provider pools. They are written to stable memory by `pre_upgrade` and read back by `post_upgrade`, and the
+//! resume is refused if the restored state does not match the snapshot. The timer of a halt
+//! in progress does not survive the upgrade either, so `post_upgrade` re-arms it:
+//!
+//! ```plain
+//! pre_upgrade ──► save_heap_state ──► HEAP_STATE ──► restore_heap_state ◄── post_upgrade
+//!
+//! resume_after_upgrade ──► snapshot before == snapshot now? ──no──► Err, stays paused
+//! ```
+
+use std::borrow::Cow;
+
+use candid::{CandidType, Decode, Encode, Nat, Principal};
+use ic_stable_structures::{storable::Bound, Storable};
+use serde::Deserialize;
+
+use crate::{
+    build_info::build_info,
+    clock::Seconds,
+    constants::STRATEGY_LOCK_TIMEOUT,
+    digest::DigestState,
+    halt::{Halt, HaltCallback},
+    providers::{set_pool_members, ProviderPool},
+    rate_source::RateSource,
+    retention::RetentionPolicy,
+    scheduler::{ExecutionPermit, SchedulingMode},
+    state::{
+        DIGEST, EXECUTION_PERMITS, HALT_CALLBACK, HALT_STATE, HEAP_STATE, JOURNAL,
+        JOURNAL_RETENTION, MANAGERS, NONCES, RATE_SOURCE, READ_POOL_PROVIDERS, RUN_COUNTER,
+        SCHEDULING_MODE, STRATEGY_STATE, TROVE_LAYOUTS, TX_POOL, UPGRADE_STATE,
+        WRITE_POOL_PROVIDERS,
+    },
+    strategy::{persisted::PersistedStrategy, stable::StableStrategy, troves::TroveLayout},
+    types::ProviderService,
+    utils::{
+        address::parse_address,
+        common::{nat_to_u256, u256_to_nat},
+        error::{ManagerError, ManagerResult},
+        nonce::NonceState,
+    },
+};
+
+/// Counters describing the persisted state at the time of the snapshot
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq)]
+pub struct StateSnapshot {
+    /// Version of the binary that took the snapshot
+    pub version: String,
+    /// Git commit of the binary that took the snapshot
+    pub git_commit: String,
+    /// Timestamp in seconds of the snapshot
+    pub taken_at: u64,
+    /// Number of strategies
+    pub strategies: u64,
+    /// Last assigned run ID
+    pub last_run_id: u64,
+    /// Pool ID of the last recorded transaction
+    pub last_tx_id: Option<u64>,
+    /// Number of journal collections
+    pub journal_collections: u64,
+}
+
+/// Upgrade gating state kept in stable memory
+#[derive(CandidType, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct UpgradeState {
+    /// Timestamp in seconds at which the canister was paused, `None` while running
+    pub paused_since: Option<u64>,
+    /// Snapshot taken once the canister was ready to be upgraded
+    pub snapshot: Option<StateSnapshot>,
+}
+
+impl Storable for UpgradeState {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        Cow::Owned(Encode!(self).expect("Failed to encode the upgrade state."))
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).expect("Failed to decode the upgrade state.")
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+/// Heap state written to stable memory by `pre_upgrade`
+#[derive(CandidType, Deserialize, Clone, Debug, Default)]
+pub struct HeapState {
+    /// Timestamp in seconds at which the state was saved, `None` if there is nothing to restore
+    pub saved_at: Option<u64>,
+    /// Strategies, ordered by key
+    pub strategies: Vec<PersistedStrategy>,
+    /// Trove managers of all branches
+    pub managers: Vec<String>,
+    /// Halt status of the canister
+    pub halt: Halt,
+    /// Next nonce of each strategy EOA
+    pub nonces: Vec<(String, u64)>,
+    /// Trove list segmentations, keyed by strategy key, with the number of troves fetched
+    pub trove_layouts: Vec<(u32, Vec<Nat>, u64)>,
+    /// Webhooks and bookkeeping of the daily digest
+    pub digest: DigestState,
+    /// Limits of the journal
+    pub journal_retention: RetentionPolicy,
+    /// Source of the ETH/CXDR rate
+    pub rate_source: RateSource,
+    /// Who triggers strategy executions
+    pub scheduling_mode: SchedulingMode,
+    /// Execution permits of external keepers, ordered by keeper
+    pub execution_permits: Vec<(Principal, ExecutionPermit)>,
+    /// Canister method notified of the halt status transitions
+    pub halt_callback: Option<HaltCallback>,
+    /// Members of the read provider pool
+    pub read_pool_providers: Vec<ProviderService>,
+    /// Members of the write provider pool
+    pub write_pool_providers: Vec<ProviderService>,
+}
+
+impl Storable for HeapState {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        Cow::Owned(Encode!(self).expect("Failed to encode the heap state."))
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).expect("Failed to decode the heap state.")
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+/// Whether the canister can be upgraded safely
+#[derive(CandidType, Clone, Debug, PartialEq)]
+pub struct UpgradeReadiness {
+    /// `true` if nothing is in flight anymore
+    pub ready: bool,
+    /// Timestamp in seconds at which the canister was paused
+    pub paused_since: u64,
+    /// Strategies that are still executing
+    pub locked_strategies: Vec<u32>,
+    /// Strategies with a rate adjustment resubmission still scheduled
+    pub pending_retries: Vec<u32>,
+    /// Snapshot of the persisted state, once ready
+    pub snapshot: Option<StateSnapshot>,
+}
+
+/// Returns `Err(ManagerError::PausedForUpgrade)` if the canister is paused for an upgrade.
+pub fn ensure_not_paused() -> ManagerResult<()> {
+    match UPGRADE_STATE.with(|state| state.borrow().get().paused_since) {
+        Some(since) => Err(ManagerError::PausedForUpgrade { since }),
+        None => Ok(()),
+    }
+}
+
+/// Returns `true` if the strategy is still executing at `now` (in seconds).
+///
+/// Locks older than `STRATEGY_LOCK_TIMEOUT` are considered abandoned, like in `Lock::try_lock`.
+pub fn is_in_flight(strategy: &StableStrategy, now: u64) -> bool {
+    strategy.lock.is_locked
+        && strategy.lock.last_locked_at.map_or(true, |locked_at| {
+            !Seconds(locked_at).is_older_than(STRATEGY_LOCK_TIMEOUT, Seconds(now))
+        })
+}
+
+/// Fails with `ManagerError::Locked` if the strategy is executing at `now` (in seconds).
+///
+/// Used by the admin endpoints that write strategy data a run in flight would overwrite.
+pub fn ensure_not_in_flight(key: u32, now: u64) -> ManagerResult<()> {
+    let in_flight = STRATEGY_STATE.with(|strategies| {
+        strategies
+            .borrow()
+            .get(&key)
+            .is_some_and(|strategy| is_in_flight(strategy, now))
+    });
+    if in_flight {
+        return Err(ManagerError::Locked);
+    }
+    Ok(())
+}
+
+/// Takes a snapshot of the persisted state at `now` (in seconds).
+pub fn snapshot(now: u64) -> StateSnapshot {
+    let info = build_info();
+    StateSnapshot {
+        version: info.version,
+        git_commit: info.git_commit,
+        taken_at: now,
+        strategies: STRATEGY_STATE.with(|state| state.borrow().len() as u64),
+        last_run_id: RUN_COUNTER.with(|counter| *counter.borrow().get()),
+        last_tx_id: TX_POOL.with(|pool| pool.borrow().last_key_value().map(|(id, _)| id)),
+        journal_collections: JOURNAL.with(|journal| journal.borrow().len()),
+    }
+}
+
+/// Pauses the canister, and reports whether the in-flight work has drained at `now` (in seconds).
+///
+/// Can be called repeatedly until the canister is ready. The snapshot is taken on the first
+/// call that finds the canister ready.
+pub fn prepare_for_upgrade(now: u64) -> UpgradeReadiness {
+    let (locked_strategies, pending_retries) = STRATEGY_STATE.with(|state| {
+        let state = state.borrow();
+        let mut locked = vec![];
+        let mut retries = vec![];
+        for (key, strategy) in state.iter() {
+            if is_in_flight(strategy, now) {
+                locked.push(*key);
+            }
+            if strategy.data.pending_retry.is_some() {
+                retries.push(*key);
+            }
+        }
+        locked.sort_unstable();
+        retries.sort_unstable();
+        (locked, retries)
+    });
+    let ready = locked_strategies.is_empty() && pending_retries.is_empty();
+
+    UPGRADE_STATE.with(|state| {
+        let mut cell = state.borrow_mut();
+        let mut upgrade = cell.get().clone();
+        let paused_since = *upgrade.paused_since.get_or_insert(now);
+        if ready && upgrade.snapshot.is_none() {
+            upgrade.snapshot = Some(snapshot(now));
+        }
+        let readiness = UpgradeReadiness {
+            ready,
+            paused_since,
+            locked_strategies,
+            pending_retries,
+            snapshot: upgrade.snapshot.clone(),
+        };
+        cell.set(upgrade)
+            .expect("Failed to persist the upgrade state.");
+        readiness
+    })
+}
+
+/// Writes the heap state to stable memory at `now` (in seconds), for `post_upgrade` to restore.
+pub fn save_heap_state(now: u64) -> ManagerResult<()> {
+    let mut strategies = STRATEGY_STATE.with(|state| {
+        state
+            .borrow()
+            .values()
+            .map(PersistedStrategy::try_from)
+            .collect::<ManagerResult<Vec<_>>>()
+    })?;
+    strategies.sort_unstable_by_key(|strategy| strategy.settings.key);
+
+    let trove_layouts = TROVE_LAYOUTS.with(|layouts| {
+        layouts
+            .borrow()
+            .iter()
+            .map(|(key, layout)| {
+                let boundaries = layout
+                    .boundaries
+                    .iter()
+                    .map(u256_to_nat)
+                    .collect::<ManagerResult<Vec<_>>>()?;
+                Ok((*key, boundaries, layout.troves_count as u64))
+            })
+            .collect::<ManagerResult<Vec<_>>>()
+    })?;
+
+    let mut execution_permits = EXECUTION_PERMITS.with(|permits| {
+        permits
+            .borrow()
+            .iter()
+            .map(|(keeper, permit)| (*keeper, permit.clone()))
+            .collect::<Vec<_>>()
+    });
+    execution_permits.sort_unstable_by_key(|(keeper, _)| *keeper);
+
+    let heap = HeapState {
+        saved_at: Some(now),
+        strategies,
+        managers: MANAGERS.with(|managers| {
+            managers
+                .borrow()
+                .iter()
+                .map(|manager| manager.to_string())
+                .collect()
+        }),
+        halt: HALT_STATE.with(|halt| halt.borrow().clone()),
+        nonces: NONCES.with(|nonces| {
+            nonces
+                .borrow()
+                .iter()
+                .map(|(eoa, state)| (eoa.to_string(), state.next))
+                .collect()
+        }),
+        trove_layouts,
+        digest: DIGEST.with(|digest| digest.borrow().clone()),
+        journal_retention: JOURNAL_RETENTION.with(|retention| *retention.borrow()),
+        rate_source: RATE_SOURCE.with(|source| *source.borrow()),
+        scheduling_mode: SCHEDULING_MODE.with(|mode| *mode.borrow()),
+        execution_permits,
+        halt_callback: HALT_CALLBACK.with(|callback| callback.borrow().clone()),
+        read_pool_providers: READ_POOL_PROVIDERS.with(|members| members.borrow().clone()),
+        write_pool_providers: WRITE_POOL_PROVIDERS.with(|members| members.borrow().clone()),
+    };
+    HEAP_STATE.with(|cell| {
+        cell.borrow_mut()
+            .set(heap)
+            .map(|_| ())
+            .map_err(|err| ManagerError::Custom(format!("{:?}", err)))
+    })
+}
+
+/// Restores the heap state saved by the previous binary, and returns the number of restored
+/// strategies, or `None` if nothing was saved.
+///
+/// The saved state is cleared once restored, so that it is never restored twice.
+pub fn restore_heap_state() -> ManagerResult<Option<usize>> {
+    let heap = HEAP_STATE.with(|cell| cell.borrow().get().clone());
+    if heap.saved_at.is_none() {
+        return Ok(None);
+    }
+
+    let strategies = heap
+        .strategies
+        .into_iter()
+        .map(|strategy| {
+            let strategy = StableStrategy::try_from(strategy)?;
+            Ok((strategy.settings.key, strategy))
+        })
+        .collect::<ManagerResult<Vec<_>>>()?;
+    let managers = heap
+        .managers
+        .iter()
+        .map(|manager| parse_address("manager", manager, false))
+        .collect::<ManagerResult<Vec<_>>>()?;
+    let nonces = heap
+        .nonces
+        .iter()
+        .map(|(eoa, next)| {
+            let state = NonceState {
+                next: *next,
+                ..Default::default()
+            };
+            Ok((parse_address("eoa", eoa, false)?, state))
+        })
+        .collect::<ManagerResult<Vec<_>>>()?;
+    let trove_layouts = heap
+        .trove_layouts
+        .iter()
+        .map(|(key, boundaries, troves_count)| {
+            let layout = TroveLayout {
+                boundaries: boundaries
+                    .iter()
+                    .map(nat_to_u256)
+                    .collect::<ManagerResult<Vec<_>>>()?,
+                troves_count: *troves_count as usize,
+            };
+            Ok((*key, layout))
+        })
+        .collect::<ManagerResult<Vec<_>>>()?;
+
+    let restored = strategies.len();
+    STRATEGY_STATE.with(|state| state.borrow_mut().extend(strategies));
+    MANAGERS.with(|state| *state.borrow_mut() = managers);
+    HALT_STATE.with(|state| *state.borrow_mut() = heap.halt);
+    NONCES.with(|state| state.borrow_mut().extend(nonces));
+    TROVE_LAYOUTS.with(|state| state.borrow_mut().extend(trove_layouts));
+    DIGEST.with(|state| *state.borrow_mut() = heap.digest);
+    JOURNAL_RETENTION.with(|state| *state.borrow_mut() = heap.journal_retention);
+    RATE_SOURCE.with(|state| *state.borrow_mut() = heap.rate_source);
+    SCHEDULING_MODE.with(|state| *state.borrow_mut() = heap.scheduling_mode);
+    EXECUTION_PERMITS.with(|state| state.borrow_mut().extend(heap.execution_permits));
+    HALT_CALLBACK.with(|state| *state.borrow_mut() = heap.halt_callback);
+    set_pool_members(ProviderPool::Read, heap.read_pool_providers)?;
+    set_pool_members(ProviderPool::Write, heap.write_pool_providers)?;
+    HEAP_STATE.with(|cell| {
+        cell.borrow_mut()
+            .set(HeapState::default())
+            .expect("Failed to clear the heap state.")
+    });
+    Ok(Some(restored))
+}
+
+/// Compares the state after an upgrade with the snapshot taken before it.
+///
+/// The journal is left out, as the upgrade itself journals.
+fn check_restored(before: &StateSnapshot, now: &StateSnapshot) -> ManagerResult<()> {
+    if before.strategies == now.strategies
+        && before.last_run_id == now.last_run_id
+        && before.last_tx_id == now.last_tx_id
+    {
+        return Ok(());
+    }
+    Err(ManagerError::Permanent(format!(
+        "The state after the upgrade does not match the snapshot. Strategies: {} before, {} now. Last run ID: {} before, {} now. Last transaction ID: {:?} before, {:?} now.",
+        before.strategies,
+        now.strategies,
+        before.last_run_id,
+        now.last_run_id,
+        before.last_tx_id,
+        now.last_tx_id
+    )))
+}
+
+/// Lifts the pause at `now` (in seconds) and returns the snapshot taken before the upgrade,
+/// if any.
+///
+/// Fails, and stays paused, if the state does not match the snapshot.
+pub fn resume_after_upgrade(now: u64) -> ManagerResult<Option<StateSnapshot>> {
+    UPGRADE_STATE.with(|state| {
+        let mut cell = state.borrow_mut();
+        let upgrade = cell.get().clone();
+        if upgrade.paused_since.is_none() {
+            return Err(ManagerError::Custom(
+                "The canister is not paused for an upgrade.".to_string(),
+            ));
+        }
+        if let Some(before) = &upgrade.snapshot {
+            check_restored(before, &snapshot(now))?;
+        }
+        cell.set(UpgradeState::default())
+            .expect("Failed to persist the upgrade state.");
+        Ok(upgrade.snapshot)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        constants::HALT_DELAY,
+        halt::{halt_status, resume_halt_countdown, HaltStatus},
+    };
+
+    fn reset() {
+        STRATEGY_STATE.with(|state| state.borrow_mut().clear());
+        UPGRADE_STATE.with(|state| {
+            state
+                .borrow_mut()
+                .set(UpgradeState::default())
+                .expect("Failed to reset the upgrade state.")
+        });
+    }
+
+    #[test]
+    fn test_prepare_waits_for_locked_strategies() {
+        reset();
+        let mut strategy = StableStrategy::default();
+        strategy.lock.is_locked = true;
+        strategy.lock.last_locked_at = Some(90);
+        STRATEGY_STATE.with(|state| state.borrow_mut().insert(1, strategy));
+
+        let readiness = prepare_for_upgrade(100);
+        assert!(!readiness.ready);
+        assert_eq!(readiness.locked_strategies, vec![1]);
+        assert_eq!(readiness.snapshot, None);
+        assert_eq!(
+            ensure_not_paused(),
+            Err(ManagerError::PausedForUpgrade { since: 100 })
+        );
+
+        STRATEGY_STATE.with(|state| {
+            state
+                .borrow_mut()
+                .get_mut(&1)
+                .expect("The strategy was inserted above.")
+                .lock
+                .is_locked = false
+        });
+        let readiness = prepare_for_upgrade(200);
+        assert!(readiness.ready);
+        assert_eq!(readiness.paused_since, 100);
+        assert_eq!(
+            readiness.snapshot.map(|snapshot| snapshot.strategies),
+            Some(1)
+        );
+    }
+
+    #[test]
+    fn test_abandoned_locks_do_not_block() {
+        let mut strategy = StableStrategy::default();
+        strategy.lock.is_locked = true;
+        strategy.lock.last_locked_at = Some(0);
+        assert!(!is_in_flight(&strategy, STRATEGY_LOCK_TIMEOUT.get() + 1));
+        assert!(is_in_flight(&strategy, STRATEGY_LOCK_TIMEOUT.get()));
+    }
+
+    #[test]
+    fn test_admin_writes_wait_for_runs() {
+        reset();
+        let mut strategy = StableStrategy::default();
+        strategy.lock.is_locked = true;
+        strategy.lock.last_locked_at = Some(90);
+        STRATEGY_STATE.with(|state| state.borrow_mut().insert(2, strategy));
+
+        assert_eq!(ensure_not_in_flight(2, 100), Err(ManagerError::Locked));
+        assert_eq!(ensure_not_in_flight(3, 100), Ok(()));
+    }
+
+    #[test]
+    fn test_resume_requires_pause() {
+        reset();
+        assert!(resume_after_upgrade(100).is_err());
+        prepare_for_upgrade(100);
+        assert!(resume_after_upgrade(100).is_ok());
+        assert_eq!(ensure_not_paused(), Ok(()));
+    }
+
+    #[test]
+    fn test_heap_state_survives_upgrade() {
+        reset();
+        let mut strategy = StableStrategy::default();
+        strategy.settings.key = 4;
+        strategy.settings.enabled = true;
+        STRATEGY_STATE.with(|state| state.borrow_mut().insert(4, strategy));
+        let manager = alloy_primitives::Address::repeat_byte(9);
+        MANAGERS.with(|managers| *managers.borrow_mut() = vec![manager]);
+        SCHEDULING_MODE.with(|mode| *mode.borrow_mut() = SchedulingMode::External);
+        DIGEST.with(|digest| {
+            digest.borrow_mut().webhook_url = Some("https://hooks.example.org".to_string())
+        });
+        let readiness = prepare_for_upgrade(100);
+        assert!(readiness.ready);
+
+        save_heap_state(100).unwrap();
+        // The upgrade wipes the heap
+        STRATEGY_STATE.with(|state| state.borrow_mut().clear());
+        MANAGERS.with(|managers| managers.borrow_mut().clear());
+        SCHEDULING_MODE.with(|mode| *mode.borrow_mut() = SchedulingMode::default());
+        DIGEST.with(|digest| *digest.borrow_mut() = DigestState::default());
+        assert!(resume_after_upgrade(200).is_err());
+        assert!(ensure_not_paused().is_err());
+
+        assert_eq!(restore_heap_state(), Ok(Some(1)));
+        assert_eq!(restore_heap_state(), Ok(None));
+        assert!(STRATEGY_STATE.with(|state| state.borrow()[&4].settings.enabled));
+        assert_eq!(
+            MANAGERS.with(|managers| managers.borrow().clone()),
+            vec![manager]
+        );
+        assert_eq!(
+            SCHEDULING_MODE.with(|mode| *mode.borrow()),
+            SchedulingMode::External
+        );
+        assert!(DIGEST.with(|digest| digest.borrow().webhook_url.is_some()));
+        assert!(resume_after_upgrade(200).is_ok());
+    }
+
+    #[test]
+    fn test_halt_in_progress_completes_after_upgrade() {
+        reset();
+        let halts_at = HALT_DELAY + 100;
+        HALT_STATE.with(|halt| {
+            *halt.borrow_mut() = Halt {
+                status: HaltStatus::HaltingInProgress { halts_at },
+                ..Halt::default()
+            }
+        });
+
+        save_heap_state(100).unwrap();
+        // The upgrade wipes the heap and the timers
+        HALT_STATE.with(|halt| *halt.borrow_mut() = Halt::default());
+        assert_eq!(restore_heap_state(), Ok(Some(0)));
+        assert_eq!(
+            halt_status(halts_at).status,
+            HaltStatus::HaltingInProgress { halts_at }
+        );
+
+        // The halt time passed during the upgrade
+        resume_halt_countdown(halts_at + 10);
+        assert_eq!(
+            halt_status(halts_at + 10).status,
+            HaltStatus::Halted {
+                halted_at: halts_at + 10
+            }
+        );
+    }
+}
@@ -0,0 +1,169 @@
+//! Ethereum Address Validation
+//!
+//! Every address received from a caller goes through this module before any state is
+//! touched, so that malformed inputs fail early with an error naming the offending field.
+//!
+//! ```plain
+//! input ──► trim ──► 0x + 40 hex digits? ──► EIP-55 checksum (if mixed case)? ──► non-zero?
+//!                                                                                    │
+//!                           confirm_address_role (eth_getCode, when reachable) ◄─────┘
+//! ```
+//!
+//! Addresses are normalized to their checksummed form. The role check is best-effort: it
+//! only fails when the chain confirms that the address has the wrong role, and is skipped
+//! when the EVM RPC canister cannot be reached.
+
+use alloy_primitives::Address;
+use ic_exports::ic_cdk::print;
+use serde::Deserialize;
+use serde_json::json;
+
+use super::{
+    common::request_with_dynamic_retries,
+    error::{ManagerError, ManagerResult},
+    evm_rpc::Service,
+};
+
+/// Expected kind of account behind an address
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AddressRole {
+    /// A deployed contract
+    Contract,
+    /// An externally owned account, without code
+    Eoa,
+}
+
+/// The HTTPS response format of `eth_getCode`.
+#[derive(Deserialize)]
+struct GetCodeResponse {
+    result: String,
+}
+
+/// Returns an `InvalidAddress` error for the given field.
+fn invalid(field: &str, reason: &str) -> ManagerError {
+    ManagerError::InvalidAddress {
+        field: field.to_string(),
+        reason: reason.to_string(),
+    }
+}
+
+/// Parses and validates an address input.
+///
+/// # Arguments
+/// * `field` - Name of the input, reported in errors
+/// * `input` - `0x`-prefixed address, either in a single case or EIP-55 checksummed
+/// * `allow_zero` - Whether the zero address is a meaningful value for this input
+pub fn parse_address(field: &str, input: &str, allow_zero: bool) -> ManagerResult<Address> {
+    let input = input.trim();
+    let digits = input
+        .strip_prefix("0x")
+        .ok_or_else(|| invalid(field, "The address must start with 0x."))?;
+    if digits.len() != 40 || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(invalid(
+            field,
+            "The address must have exactly 40 hexadecimal digits.",
+        ));
+    }
+
+    let address: Address = input
+        .parse()
+        .map_err(|_| invalid(field, "The address could not be parsed."))?;
+
+    let mixed_case = digits.chars().any(|c| c.is_ascii_lowercase())
+        && digits.chars().any(|c| c.is_ascii_uppercase());
+    if mixed_case && address.to_checksum(None) != input {
+        return Err(invalid(
+            field,
+            "The address has an invalid EIP-55 checksum.",
+        ));
+    }
+
+    if !allow_zero && address == Address::ZERO {
+        return Err(invalid(field, "The zero address is not allowed."));
+    }
+
+    Ok(address)
+}
+
+/// Confirms with `eth_getCode` that an address has the expected role.
+///
+/// Only fails when the chain confirms a mismatch; the check is skipped if the code cannot be fetched.
+pub async fn confirm_address_role(
+    rpc_canister: &Service,
+    field: &str,
+    address: Address,
+    role: AddressRole,
+) -> ManagerResult<()> {
+    let json_data = json!({
+        "id": 1,
+        "jsonrpc": "2.0",
+        "params": [address.to_string(), "latest"],
+        "method": "eth_getCode"
+    })
+    .to_string();
+
+    let code = match request_with_dynamic_retries(rpc_canister, json_data).await {
+        Ok(response) => match serde_json::from_str::<GetCodeResponse>(&response) {
+            Ok(decoded) => decoded.result,
+            Err(err) => {
+                print(format!(
+                    "Skipped the role check of {}: could not decode eth_getCode response: {}",
+                    field, err
+                ));
+                return Ok(());
+            }
+        },
+        Err(err) => {
+            print(format!(
+                "Skipped the role check of {}: eth_getCode failed: {:?}",
+                field, err
+            ));
+            return Ok(());
+        }
+    };
+
+    let has_code = !matches!(code.as_str(), "" | "0x" | "0x0");
+    match (role, has_code) {
+        (AddressRole::Contract, false) => {
+            Err(invalid(field, "No contract is deployed at the address."))
+        }
+        (AddressRole::Eoa, true) => Err(invalid(field, "The address is a contract, not an EOA.")),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_address() {
+        let checksummed = "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed";
+        let address = parse_address("manager", checksummed, false).unwrap();
+        assert_eq!(address.to_checksum(None), checksummed);
+        assert_eq!(
+            parse_address(
+                "manager",
+                &format!(" {} ", checksummed.to_lowercase()),
+                false
+            ),
+            Ok(address)
+        );
+
+        let invalid_reason = |input: &str| match parse_address("manager", input, false) {
+            Err(ManagerError::InvalidAddress { field, reason }) => {
+                assert_eq!(field, "manager");
+                reason
+            }
+            other => panic!("Unexpected result: {:?}", other),
+        };
+        assert!(invalid_reason("5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed").contains("0x"));
+        assert!(invalid_reason("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeA").contains("40"));
+        assert!(invalid_reason("0x5AAeb6053F3E94C9b9A09f33669435E7Ef1BeAed").contains("checksum"));
+        assert!(invalid_reason(&Address::ZERO.to_string()).contains("zero"));
+        assert_eq!(
+            parse_address("manager", &Address::ZERO.to_string(), true),
+            Ok(Address::ZERO)
+        );
+    }
+}
@@ -0,0 +1,45 @@
+//! Types for Internet Computer's cycles minting canister
+
+use candid::{CandidType, Principal};
+use serde::Deserialize;
+
+/// Arguments of the `notify_top_up` method
+#[derive(CandidType, Clone, Debug, Deserialize)]
+pub struct NotifyTopUpArg {
+    /// Index of the ICP ledger block of the transfer to the CMC
+    pub block_index: u64,
+    /// Canister the minted cycles are sent to
+    pub canister_id: Principal,
+}
+
+/// Errors returned by the `notify_top_up` method
+#[derive(CandidType, Clone, Debug, Deserialize, PartialEq)]
+pub enum NotifyError {
+    /// The transfer was refunded, minus the fee.
+    Refunded {
+        /// Reason of the refund
+        reason: String,
+        /// Index of the ledger block of the refund, if it was completed
+        block_index: Option<u64>,
+    },
+    /// The transfer is already being processed.
+    Processing,
+    /// The transfer is too old to be processed. Holds the oldest processable block index.
+    TransactionTooOld(u64),
+    /// The transfer is not a valid top-up.
+    InvalidTransaction(String),
+    /// Any other error.
+    Other {
+        /// Error code
+        error_code: u64,
+        /// Error message
+        error_message: String,
+    },
+}
+
+impl NotifyError {
+    /// Returns `true` if the same notification can succeed when it is sent again.
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, NotifyError::Processing | NotifyError::Other { .. })
+    }
+}
@@ -6,25 +6,40 @@ use alloy_primitives::{Address, U256};
 use alloy_sol_types::SolCall;
 use candid::{Nat, Principal};
 use evm_rpc_types::{
-    HttpOutcallError, MultiRpcResult, RpcConfig, RpcError, RpcService, RpcServices,
+    ConsensusStrategy, HttpOutcallError, MultiRpcResult, RpcConfig, RpcError, RpcService,
+    RpcServices,
 };
 use ic_exports::ic_cdk::{
     self,
     api::{call::CallResult, is_controller},
     call, id, print,
 };
-use num_bigint::BigUint;
+use serde::Deserialize;
+use serde_json::json;
 
+pub use super::units::{nat_to_u128, nat_to_u256, u256_to_nat};
 use super::{error::*, evm_rpc::*, exchange::*};
 
 use crate::{
+    chain::{chain_service, chain_services, is_home_chain},
+    clock::time,
     constants::{
-        cketh_ledger, exchange_rate_canister, DEFAULT_MAX_RESPONSE_BYTES, MAX_RETRY_ATTEMPTS,
+        cketh_ledger, exchange_rate_canister, DEFAULT_MAX_BLOCK_LAG, DEFAULT_MAX_RESPONSE_BYTES,
+        DEFAULT_NO_CONSENSUS_THRESHOLD, FRESHNESS_RANKING_INTERVAL, MAX_RETRY_ATTEMPTS,
         PROVIDER_COUNT, PROVIDER_THRESHOLD,
     },
-    providers::{extract_multi_rpc_result, get_ranked_rpc_provider, get_ranked_rpc_providers},
-    state::{LAST_SAFE_BLOCK, RPC_SERVICE},
-    types::Account,
+    custom_providers::sync_rotation,
+    digest::raise_alert,
+    flags::{flag_int, MAX_BLOCK_LAG, NO_CONSENSUS_THRESHOLD},
+    journal::{JournalCollection, LogType},
+    metrics::{consensus_fallback_active, record_consensus_outcome, CallClass},
+    payload_sizes::{eth_call_class, record_payload_size, request_class, starting_response_bytes},
+    providers::{
+        decrement_provider_score, extract_multi_rpc_result, get_ranked_rpc_provider,
+        get_ranked_rpc_providers, is_provider_disabled, lagging_providers, ProviderPool,
+    },
+    state::{LAST_FRESHNESS_RANKING, LAST_SAFE_BLOCK, RPC_SERVICE},
+    types::{Account, ProviderService},
 };
 
 /// Returns the estimated cycles cost of performing the RPC call if successful
@@ -33,7 +48,7 @@ pub async fn estimate_cycles(
     json_data: String,
     max_response_bytes: u64,
 ) -> ManagerResult<u128> {
-    let rpc = get_rpc_service();
+    let rpc = request_service(rpc_canister, 0)?;
     let call_result = rpc_canister
         .request_cost(rpc, json_data, max_response_bytes)
         .await;
@@ -49,13 +64,6 @@ pub async fn estimate_cycles(
     }
 }
 
-/// Converts a Nat to u128
-pub fn nat_to_u128(num: Nat) -> ManagerResult<u128> {
-    u128::try_from(num.0).map_err(|err| {
-        ManagerError::DecodingError(format!("Error converting Nat to u128: {:#?}", err))
-    })
-}
-
 /// Returns Err if the `caller` is not a controller of the canister
 pub fn only_controller(caller: Principal) -> ManagerResult<()> {
     if !is_controller(&caller) {
@@ -70,27 +78,6 @@ pub fn string_to_address(input: String) -> ManagerResult<Address> {
     Address::from_str(&input).map_err(|err| ManagerError::DecodingError(format!("{:#?}", err)))
 }
 
-/// Converts values of type `Nat` to `U256`
-pub fn nat_to_u256(n: &Nat) -> ManagerResult<U256> {
-    let be_bytes = n.0.to_bytes_be();
-    if be_bytes.len() > 32 {
-        return Err(ManagerError::DecodingError("The `Nat` input length exceedes 32 bytes when converted to big-endian bytes representation.".to_string()));
-    }
-    // Ensure the byte array is exactly 32 bytes long
-    let mut padded_bytes = [0u8; 32];
-    let start_pos = 32 - be_bytes.len();
-    padded_bytes[start_pos..].copy_from_slice(&be_bytes);
-
-    Ok(U256::from_be_bytes(padded_bytes))
-}
-
-/// Converts values of type `U256` to `Nat`
-pub fn u256_to_nat(n: &U256) -> ManagerResult<Nat> {
-    let be_bytes = n.to_be_bytes::<32>();
-    let biguint = BigUint::from_bytes_be(&be_bytes);
-    Ok(Nat::from(biguint))
-}
-
 /// Returns the ckETH balance of the canister
 pub async fn fetch_cketh_balance() -> ManagerResult<Nat> {
     let ledger_principal = cketh_ledger();
@@ -161,17 +148,136 @@ pub fn decode_abi_response<T, F: SolCall<Return = T>>(hex_data: String) -> Manag
 }
 
 pub async fn get_block_tag(rpc_canister: &Service, latest: bool) -> ManagerResult<BlockTag> {
+    let block = get_block(rpc_canister, latest, ProviderPool::Read).await?;
+    // The pools only serve the home chain
+    if is_home_chain(rpc_canister.1) && freshness_ranking_due(time() / 1_000_000_000) {
+        rank_by_block_freshness(rpc_canister).await;
+    }
+    Ok(BlockTag::Number(block.number))
+}
+
+/// The HTTPS response format of `eth_blockNumber`.
+#[derive(Deserialize)]
+struct BlockNumberResponse {
+    result: String,
+}
+
+/// Parses a `0x`-prefixed hex quantity.
+pub fn parse_quantity(value: &str) -> ManagerResult<u128> {
+    let stripped = value.strip_prefix("0x").unwrap_or(value);
+    u128::from_str_radix(stripped, 16).map_err(|err| {
+        ManagerError::DecodingError(format!("Could not decode quantity {}: {}", value, err))
+    })
+}
+
+/// Returns `true` if the read providers were last ranked by block freshness at least
+/// `FRESHNESS_RANKING_INTERVAL` seconds before `now` (in seconds), and claims the ranking.
+///
+/// The ranking calls every provider, so it is shared by the runs within the interval instead
+/// of being repeated by each of them.
+fn freshness_ranking_due(now: u64) -> bool {
+    LAST_FRESHNESS_RANKING.with(|last| {
+        if now.saturating_sub(last.get()) < FRESHNESS_RANKING_INTERVAL {
+            return false;
+        }
+        last.set(now);
+        true
+    })
+}
+
+/// Penalizes the read providers whose block height lags more than `MAX_BLOCK_LAG` blocks
+/// behind the highest height reported by the pool.
+///
+/// Providers that fail to report their height are skipped, as failed calls are already
+/// penalized where they happen.
+async fn rank_by_block_freshness(rpc_canister: &Service) {
+    let max_lag = flag_int!(MAX_BLOCK_LAG, DEFAULT_MAX_BLOCK_LAG);
+    if max_lag <= 0 {
+        return;
+    }
+
+    let mut heights = vec![];
+    for provider in ProviderPool::Read.members() {
+        if is_provider_disabled(&provider) {
+            continue;
+        }
+        if let Ok(height) = fetch_block_number(rpc_canister, provider).await {
+            heights.push((provider, height));
+        }
+    }
+
+    let max_height = heights
+        .iter()
+        .map(|(_, height)| *height)
+        .max()
+        .unwrap_or_default();
+    for provider in lagging_providers(&heights, max_lag as u64) {
+        decrement_provider_score(ProviderPool::Read, &provider);
+        JournalCollection::open(None).append_note(
+            Ok(()),
+            LogType::ProviderReputationChange,
+            format!(
+                "Provider {:?} lags more than {} blocks behind the highest block height {} (read pool): -1",
+                provider, max_lag, max_height
+            ),
+        );
+    }
+}
+
+/// Fetches the block height of a single provider with an `eth_blockNumber` call.
+async fn fetch_block_number(
+    rpc_canister: &Service,
+    provider: ProviderService,
+) -> ManagerResult<u64> {
+    #[cfg(feature = "sepolia")]
+    let rpc = RpcService::EthSepolia(provider);
+    #[cfg(feature = "mainnet")]
+    let rpc = RpcService::EthMainnet(provider);
+
+    let json_data = json!({
+        "id": 1,
+        "jsonrpc": "2.0",
+        "params": [],
+        "method": "eth_blockNumber"
+    })
+    .to_string();
+
+    let cost_result = rpc_canister
+        .request_cost(rpc.clone(), json_data.clone(), DEFAULT_MAX_RESPONSE_BYTES)
+        .await;
+    let cycles =
+        nat_to_u128(extract_call_result(cost_result)?.map_err(ManagerError::RpcResponseError)?)?;
+
+    let call_result = rpc_canister
+        .request(rpc, json_data, DEFAULT_MAX_RESPONSE_BYTES, cycles)
+        .await;
+    let response = extract_call_result(call_result)?.map_err(ManagerError::RpcResponseError)?;
+
+    let decoded: BlockNumberResponse = serde_json::from_str(&response).map_err(|err| {
+        ManagerError::DecodingError(format!(
+            "Could not decode eth_blockNumber response: {} error: {}",
+            response, err
+        ))
+    })?;
+    Ok(parse_quantity(&decoded.result)? as u64)
+}
+
+/// Fetches the latest (or safe) block from the top-ranked provider of the given pool.
+///
+/// On other chains than the home chain, the block is fetched from the chain's providers.
+pub async fn get_block(
+    rpc_canister: &Service,
+    latest: bool,
+    pool: ProviderPool,
+) -> ManagerResult<Block> {
     let mut result = None;
     let mut last_error = None;
 
     for _ in 1..=MAX_RETRY_ATTEMPTS {
-        let rpc = get_ranked_rpc_provider();
+        let (rpc, consensus) = single_read_providers(rpc_canister, pool)?;
         let rpc_config = RpcConfig {
             response_size_estimate: Some(3000),
-            response_consensus: Some(evm_rpc_types::ConsensusStrategy::Threshold {
-                total: Some(1),
-                min: 1,
-            }),
+            response_consensus: Some(consensus),
         };
 
         let tag = if latest {
@@ -185,7 +291,7 @@ pub async fn get_block_tag(rpc_canister: &Service, latest: bool) -> ManagerResul
             .await;
 
         let rpc_result = extract_call_result(call_result)?;
-        let current_result = extract_multi_rpc_result(rpc, rpc_result);
+        let current_result = extract_multi_rpc_result(pool, rpc, rpc_result);
 
         match current_result {
             Ok(r) => {
@@ -204,7 +310,7 @@ pub async fn get_block_tag(rpc_canister: &Service, latest: bool) -> ManagerResul
         }))
     })?;
 
-    if !latest {
+    if !latest && is_home_chain(rpc_canister.1) {
         // As a sanity check, we expect that the new block height > last queried height
         let last_height = LAST_SAFE_BLOCK.with(|height| height.get());
         if nat_to_u128(result.number.clone())? < last_height {
@@ -214,7 +320,7 @@ pub async fn get_block_tag(rpc_canister: &Service, latest: bool) -> ManagerResul
         }
     }
 
-    Ok(BlockTag::Number(result.number))
+    Ok(result)
 }
 
 fn is_response_size_error(err: &RpcError) -> bool {
@@ -236,6 +342,68 @@ pub fn get_rpc_config(max_response_bytes: Option<u64>) -> RpcConfig {
     }
 }
 
+/// Returns the providers and the consensus strategy to use for a read.
+///
+/// While the call class is in consensus fallback, the read is served by the single
+/// highest-ranked provider of the pool instead of requiring a quorum. On other chains than
+/// the home chain, all providers of the chain must agree.
+fn read_providers(
+    rpc_canister: &Service,
+    class: CallClass,
+    pool: ProviderPool,
+) -> ManagerResult<(RpcServices, ConsensusStrategy)> {
+    if let Some(services) = chain_services(rpc_canister.1)? {
+        return Ok((services, ConsensusStrategy::Equality));
+    }
+    if consensus_fallback_active(class, time() / 1_000_000_000) {
+        single_read_providers(rpc_canister, pool)
+    } else {
+        Ok((
+            get_ranked_rpc_providers(pool),
+            ConsensusStrategy::Threshold {
+                total: Some(PROVIDER_COUNT),
+                min: PROVIDER_THRESHOLD,
+            },
+        ))
+    }
+}
+
+/// Returns the highest-ranked provider of the pool, or the providers of the chain on other
+/// chains than the home chain, for reads that do not require a quorum.
+pub fn single_read_providers(
+    rpc_canister: &Service,
+    pool: ProviderPool,
+) -> ManagerResult<(RpcServices, ConsensusStrategy)> {
+    if let Some(services) = chain_services(rpc_canister.1)? {
+        return Ok((services, ConsensusStrategy::Equality));
+    }
+    Ok((
+        get_ranked_rpc_provider(pool),
+        ConsensusStrategy::Threshold {
+            total: Some(1),
+            min: 1,
+        },
+    ))
+}
+
+/// Records the consensus outcome of a call and raises an alert once the
+/// `no_consensus_threshold` flag is crossed.
+pub fn track_consensus<T>(class: CallClass, result: &ManagerResult<T>) {
+    let threshold = flag_int!(NO_CONSENSUS_THRESHOLD, DEFAULT_NO_CONSENSUS_THRESHOLD).max(1) as u64;
+    if let Some(streak) = record_consensus_outcome(class, result, threshold, time() / 1_000_000_000)
+    {
+        let fallback = if class.is_read() {
+            " Reads of this class now use a single provider until consensus is restored."
+        } else {
+            ""
+        };
+        raise_alert(format!(
+            "{} consecutive {:?} calls ended without consensus.{}",
+            streak, class, fallback
+        ));
+    }
+}
+
 /// Performs `eth_call` calls to the EVM RPC canister and doubles the max response bytes argument, if insufficient
 /// Exits the loop if either of the following are satisfied:
 /// A) The EVM RPC canister responds with Ok() or an error that is not related to the response size
@@ -247,8 +415,11 @@ pub async fn call_with_dynamic_retries(
     to: Address,
     data: Vec<u8>,
 ) -> ManagerResult<String> {
-    let mut max_response_bytes = DEFAULT_MAX_RESPONSE_BYTES;
-    let provider_set: RpcServices = get_ranked_rpc_providers();
+    let class = eth_call_class(&data);
+    let mut max_response_bytes = starting_response_bytes(&class);
+    let mut doublings = 0;
+    let (provider_set, consensus) =
+        read_providers(rpc_canister, CallClass::EthCall, ProviderPool::Read)?;
     let data_string = format!("0x{}", hex::encode(data));
 
     // There is a 2 MB limit on the response size, an ICP limitation.
@@ -263,23 +434,31 @@ pub async fn call_with_dynamic_retries(
             transaction,
             block: Some(block.clone()),
         };
-        let config = get_rpc_config(Some(max_response_bytes));
+        let config = RpcConfig {
+            response_size_estimate: Some(max_response_bytes),
+            response_consensus: Some(consensus.clone()),
+        };
         let response = rpc_canister
             .eth_call(provider_set.clone(), Some(config), args)
             .await;
 
         let extracted_response = extract_call_result(response)?;
         let extracted_rpc_result =
-            extract_multi_rpc_result(provider_set.clone(), extracted_response);
+            extract_multi_rpc_result(ProviderPool::Read, provider_set.clone(), extracted_response);
 
         if let Err(ManagerError::RpcResponseError(err)) = extracted_rpc_result.clone() {
             if is_response_size_error(&err) {
                 max_response_bytes *= 2;
+                doublings += 1;
                 continue;
             }
         }
 
         // note: if the code has reached this line, it means that a response unrelated to the size was received.
+        track_consensus(CallClass::EthCall, &extracted_rpc_result);
+        if let Ok(response) = &extracted_rpc_result {
+            record_payload_size(&class, response.len() as u64, max_response_bytes, doublings);
+        }
         return extracted_rpc_result;
     }
 
@@ -288,9 +467,24 @@ pub async fn call_with_dynamic_retries(
     ))
 }
 
+/// Returns the provider of a single JSON-RPC request.
+///
+/// On the home chain, the providers are rotated with every call. On other chains, the
+/// `rotation`-th provider of the chain is returned.
+fn request_service(rpc_canister: &Service, rotation: usize) -> ManagerResult<RpcService> {
+    Ok(match chain_service(rpc_canister.1, rotation)? {
+        Some(service) => service,
+        None => get_rpc_service(),
+    })
+}
+
+/// Returns the next provider in the rotation of single requests on the home chain.
+///
+/// The custom providers of the read pool take turns with the built-in providers.
 pub fn get_rpc_service() -> RpcService {
     RPC_SERVICE.with(|rpc| {
         let mut state = rpc.borrow_mut();
+        sync_rotation(&mut state);
         let rpc = match state.front() {
             Some(inner) => inner.clone(),
             None => unreachable!(),
@@ -309,8 +503,10 @@ pub async fn request_with_dynamic_retries(
     rpc_canister: &Service,
     json_data: String,
 ) -> ManagerResult<String> {
-    let mut max_response_bytes = DEFAULT_MAX_RESPONSE_BYTES;
-    let mut rpc = get_rpc_service();
+    let class = request_class(&json_data);
+    let mut max_response_bytes = starting_response_bytes(&class);
+    let mut doublings = 0;
+    let mut rpc = request_service(rpc_canister, 0)?;
     let mut rpc_changes = 0;
 
     // There is a 2 MB limit on the response size, an ICP limitation.
@@ -333,12 +529,16 @@ pub async fn request_with_dynamic_retries(
             ));
             if is_response_size_error(&err) {
                 max_response_bytes *= 2;
+                doublings += 1;
                 continue;
             }
-            rpc = get_rpc_service();
             rpc_changes += 1;
+            rpc = request_service(rpc_canister, rpc_changes)?;
             continue;
         }
+        if let Ok(response) = &extracted_response {
+            record_payload_size(&class, response.len() as u64, max_response_bytes, doublings);
+        }
         return extracted_response;
     }
 
@@ -357,7 +557,11 @@ pub async fn request_with_dynamic_retries(
 /// On success, returns the nonce associated with the given address
 pub async fn get_nonce(rpc_canister: &Service, address: Address) -> ManagerResult<U256> {
     let account = address.to_string();
-    let rpc: RpcServices = get_ranked_rpc_providers();
+    let (rpc, consensus) = read_providers(
+        rpc_canister,
+        CallClass::TransactionCount,
+        ProviderPool::Write,
+    )?;
     let args = GetTransactionCountArgs {
         address: account,
         block: BlockTag::Latest,
@@ -365,10 +569,7 @@ pub async fn get_nonce(rpc_canister: &Service, address: Address) -> ManagerResul
 
     let config = RpcConfig {
         response_size_estimate: Some(10_000),
-        response_consensus: Some(evm_rpc_types::ConsensusStrategy::Threshold {
-            total: Some(PROVIDER_COUNT),
-            min: PROVIDER_THRESHOLD,
-        }),
+        response_consensus: Some(consensus),
     };
 
     let result = rpc_canister
@@ -376,8 +577,9 @@ pub async fn get_nonce(rpc_canister: &Service, address: Address) -> ManagerResul
         .await;
 
     let wrapped_number = extract_call_result::<MultiRpcResult<Nat>>(result)?;
-    let number = extract_multi_rpc_result(rpc, wrapped_number)?;
-    nat_to_u256(&number)
+    let number = extract_multi_rpc_result(ProviderPool::Write, rpc, wrapped_number);
+    track_consensus(CallClass::TransactionCount, &number);
+    nat_to_u256(&number?)
 }
 
 /// Extracts the Ok or Err values of a canister call and returns them.
@@ -502,4 +704,14 @@ mod tests {
         assert!(result.is_ok());
         assert_eq!(result.unwrap(), value);
     }
+
+    #[test]
+    fn test_freshness_ranking_is_shared() {
+        let now = 1_700_000_000;
+        assert!(freshness_ranking_due(now));
+        // The runs within the interval reuse the ranking
+        assert!(!freshness_ranking_due(now + 1));
+        assert!(!freshness_ranking_due(now + FRESHNESS_RANKING_INTERVAL - 1));
+        assert!(freshness_ranking_due(now + FRESHNESS_RANKING_INTERVAL));
+    }
 }
@@ -1,10 +1,23 @@
 //! Result and error types
+//!
+//! Every `ManagerError` falls into an `ErrorCategory`, which decides how a failed strategy run
+//! is handled:
+//!
+//! ```plain
+//! Category        Example                          Run retried   Counts towards a halt
+//! Transient       wrong nonce, locked, stale data  yes           yes
+//! External        RPC failure, no consensus        yes           yes
+//! Permanent       zero target, no troves           no            yes
+//! Configuration   missing EOA, invalid address     no            no (alert instead)
+//! ```
 
 use candid::CandidType;
 use evm_rpc_types::RpcError;
 use ic_exports::ic_kit::RejectionCode;
 use serde::Deserialize;
 
+use crate::{halt::HaltStatus, strategy::conflicts::ConfigConflict};
+
 /// IR Manager Canister Result
 pub type ManagerResult<T> = Result<T, ManagerError>;
 
@@ -23,8 +36,12 @@ pub enum ManagerError {
     DecodingError(String),
     /// Strategy is locked
     Locked,
-    /// Unknown/Custom error
+    /// Unknown/Custom error, handled as transient
     Custom(String),
+    /// A condition that retrying the same operation cannot fix until the state changes
+    Permanent(String),
+    /// The canister or strategy configuration must be changed by a controller
+    Configuration(String),
     /// The cycle balance is above the threshold.
     /// No arbitrage opportunity is available.
     CyclesBalanceAboveRechargingThreshold,
@@ -32,8 +49,170 @@ pub enum ManagerError {
     NoConsensus(String),
     /// Arithmetic error
     Arithmetic(String),
+    /// The canister is halted and no longer accepts this operation
+    Halted {
+        /// The halt status at the time of the call
+        status: HaltStatus,
+    },
+    /// The canister is paused for an upgrade and does not start new work
+    PausedForUpgrade {
+        /// Timestamp in seconds at which the canister was paused
+        since: u64,
+    },
+    /// An address input is malformed or has the wrong role
+    InvalidAddress {
+        /// Name of the input
+        field: String,
+        /// Why the address was rejected
+        reason: String,
+    },
+    /// The caller exceeded the rate limit of the method
+    RateLimited {
+        /// Timestamp in seconds from which the call is accepted again
+        retry_after: u64,
+    },
+    /// The same request was already accepted recently
+    DuplicateRequest {
+        /// Timestamp in seconds at which the request was first accepted
+        first_seen_at: u64,
+    },
+}
+
+/// How an error is expected to evolve, and how a failed run reacts to it
+#[derive(Clone, Copy, CandidType, Debug, Deserialize, PartialEq, Eq)]
+pub enum ErrorCategory {
+    /// May succeed if the operation is retried shortly
+    Transient,
+    /// Fails again until the state it depends on changes
+    Permanent,
+    /// Needs a controller to change the configuration
+    Configuration,
+    /// A dependency, such as the RPC providers or another canister, failed
+    External,
+}
+
+impl ErrorCategory {
+    /// Returns `true` if a failed run is attempted again.
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, ErrorCategory::Transient | ErrorCategory::External)
+    }
+
+    /// Returns `true` if a failure streak of this category may halt the canister.
+    ///
+    /// A misconfigured strategy raises alerts instead, as a halt would not fix it.
+    pub fn halts(&self) -> bool {
+        !matches!(self, ErrorCategory::Configuration)
+    }
+}
+
+impl ManagerError {
+    /// Returns the category of the error.
+    pub fn category(&self) -> ErrorCategory {
+        match self {
+            ManagerError::CallResult(RejectionCode::SysTransient, _) => ErrorCategory::Transient,
+            ManagerError::CallResult(_, _)
+            | ManagerError::RpcResponseError(_)
+            | ManagerError::DecodingError(_)
+            | ManagerError::NoConsensus(_) => ErrorCategory::External,
+            ManagerError::Locked
+            | ManagerError::Custom(_)
+            | ManagerError::RateLimited { .. }
+            | ManagerError::PausedForUpgrade { .. } => ErrorCategory::Transient,
+            ManagerError::Permanent(_)
+            | ManagerError::Arithmetic(_)
+            | ManagerError::CyclesBalanceAboveRechargingThreshold
+            | ManagerError::Halted { .. }
+            | ManagerError::DuplicateRequest { .. } => ErrorCategory::Permanent,
+            ManagerError::Configuration(_)
+            | ManagerError::Unauthorized
+            | ManagerError::NonExistentValue
+            | ManagerError::InvalidAddress { .. } => ErrorCategory::Configuration,
+        }
+    }
+}
+
+/// Validation and setup failures of `mint_strategy`
+///
+/// A failed mint leaves no strategy, manager or chain registered, so it can be retried safely.
+#[derive(Clone, CandidType, Debug, PartialEq)]
+pub enum MintError {
+    /// The caller may not mint strategies, or the canister does not accept changes
+    Rejected(ManagerError),
+    /// The key is already used by another strategy
+    KeyInUse,
+    /// An address field is malformed or has the wrong role
+    InvalidAddress {
+        /// Name of the field
+        field: String,
+        /// Why the address was rejected
+        reason: String,
+    },
+    /// A numeric field does not fit in 256 bits
+    InvalidNumber {
+        /// Name of the field
+        field: String,
+    },
+    /// The minimum target is zero or above 100%
+    TargetOutOfBounds,
+    /// The configuration conflicts with other strategies, and `force` was not set
+    ConflictingConfiguration {
+        /// Conflicts the strategy would create
+        conflicts: Vec<ConfigConflict>,
+    },
+    /// The EOA's nonce could not be fetched through the EVM RPC canister
+    RpcPrincipalUnreachable,
+    /// The threshold ECDSA public key could not be derived
+    KeyDerivationFailed {
+        /// Rejection code of the management canister call
+        code: RejectionCode,
+        /// Rejection message of the management canister call
+        message: String,
+    },
+}
+
+impl From<ManagerError> for MintError {
+    fn from(value: ManagerError) -> Self {
+        MintError::Rejected(value)
+    }
+}
+
+impl MintError {
+    /// Converts the errors of the address validation, keeping the field that was rejected.
+    pub fn from_address_error(err: ManagerError) -> Self {
+        match err {
+            ManagerError::InvalidAddress { field, reason } => {
+                MintError::InvalidAddress { field, reason }
+            }
+            err => MintError::Rejected(err),
+        }
+    }
 }
 
 pub fn arithmetic_err<S: AsRef<str>>(s: S) -> ManagerError {
     ManagerError::Arithmetic(format!("{:#?}", s.as_ref()))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_categories() {
+        let transient = ManagerError::CallResult(RejectionCode::SysTransient, String::new());
+        assert_eq!(transient.category(), ErrorCategory::Transient);
+        let rejected = ManagerError::CallResult(RejectionCode::CanisterReject, String::new());
+        assert_eq!(rejected.category(), ErrorCategory::External);
+        assert_eq!(
+            ManagerError::Custom(String::new()).category(),
+            ErrorCategory::Transient
+        );
+
+        let permanent = ManagerError::Permanent(String::new()).category();
+        assert!(!permanent.is_retryable() && permanent.halts());
+        let configuration = ManagerError::NonExistentValue.category();
+        assert!(!configuration.is_retryable() && !configuration.halts());
+        assert!(ManagerError::NoConsensus(String::new())
+            .category()
+            .is_retryable());
+    }
+}
@@ -8,6 +8,8 @@ use ic_exports::ic_cdk::{
 };
 use serde::Serialize;
 
+use crate::constants::CHAIN_ID;
+
 #[derive(Clone, Debug, PartialEq, Eq, CandidType, Deserialize)]
 pub struct GetTransactionCountArgs {
     pub address: String,
@@ -238,12 +240,13 @@ pub struct Block {
     pub uncles: Vec<String>,
 }
 
+/// EVM RPC canister, and the chain ID of the network its requests are routed to
 #[derive(Copy, Clone, Debug)]
-pub struct Service(pub Principal);
+pub struct Service(pub Principal, pub u64);
 
 impl Default for Service {
     fn default() -> Self {
-        Self(Principal::anonymous())
+        Self(Principal::anonymous(), CHAIN_ID)
     }
 }
 
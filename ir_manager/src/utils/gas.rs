@@ -1,26 +1,106 @@
 //! Makes gas estimations and is used to submit a transaction through the TransactionBuilder
+//!
+//! The EIP-1559 fees of a transaction follow the fee policy of its strategy:
+//!
+//! ```plain
+//! eth_feeHistory(9 blocks, percentile) ──► median tip ──max──► priority fee floor
+//!                                                                    │
+//!                                                                    ▼
+//!                     latest base fee + tip, both × multiplier ──► max fee ──min──► cap
+//! ```
 
 use alloy_primitives::U256;
-use candid::Nat;
+use candid::{CandidType, Nat};
 use evm_rpc_types::RpcConfig;
+use serde::Deserialize;
 use serde_json::json;
 
-use crate::constants::MAX_RETRY_ATTEMPTS;
-use crate::providers::{extract_multi_rpc_result, get_ranked_rpc_provider};
+use crate::constants::{
+    DEFAULT_FEE_PERCENTILE, DEFAULT_MIN_PRIORITY_FEE_PER_GAS, MAX_FEE_MULTIPLIER_PERCENT,
+    MAX_RETRY_ATTEMPTS,
+};
+use crate::providers::{extract_multi_rpc_result, ProviderPool};
 use crate::types::*;
 
-use super::common::{extract_call_result, request_with_dynamic_retries};
+use super::common::{extract_call_result, request_with_dynamic_retries, single_read_providers};
 use super::error::{ManagerError, ManagerResult};
 use super::evm_rpc::{BlockTag, FeeHistory, FeeHistoryArgs, Service};
 
-/// The minimum suggested maximum priority fee per gas.
-const MIN_SUGGEST_MAX_PRIORITY_FEE_PER_GAS: u64 = 1_500_000_000;
-
+#[derive(Clone, Debug, PartialEq)]
 pub struct FeeEstimates {
     pub max_fee_per_gas: u128,
     pub max_priority_fee_per_gas: u128,
 }
 
+/// EIP-1559 fee policy of a strategy
+#[derive(CandidType, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct FeePolicy {
+    /// Percentile of the tips paid in the recent blocks to sample, from 1 to 99
+    pub percentile: u8,
+    /// Lowest priority fee per gas in wei
+    pub min_priority_fee: u64,
+    /// Upper bound of the max fee per gas in wei, none if `None`
+    ///
+    /// Transactions are not included while the base fee is above the cap.
+    pub max_fee_cap: Option<u64>,
+    /// Multiplier in percent applied to the base fee and the tip, at least 100
+    pub multiplier_percent: u64,
+}
+
+impl Default for FeePolicy {
+    fn default() -> Self {
+        Self {
+            percentile: DEFAULT_FEE_PERCENTILE,
+            min_priority_fee: DEFAULT_MIN_PRIORITY_FEE_PER_GAS,
+            max_fee_cap: None,
+            multiplier_percent: 100,
+        }
+    }
+}
+
+impl FeePolicy {
+    /// Checks that the policy can price transactions.
+    pub fn validate(&self) -> ManagerResult<()> {
+        if !(1..=99).contains(&self.percentile) {
+            return Err(ManagerError::Custom(
+                "The percentile must be between 1 and 99.".to_string(),
+            ));
+        }
+        if !(100..=MAX_FEE_MULTIPLIER_PERCENT).contains(&self.multiplier_percent) {
+            return Err(ManagerError::Custom(format!(
+                "The multiplier must be between 100% and {}%.",
+                MAX_FEE_MULTIPLIER_PERCENT
+            )));
+        }
+        if let Some(cap) = self.max_fee_cap {
+            if cap < self.min_priority_fee {
+                return Err(ManagerError::Custom(
+                    "The max fee cap must not be below the priority fee floor.".to_string(),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the fees to bid, given the latest base fee and the sampled tip.
+    pub fn fees(&self, base_fee_per_gas: u128, tip: u128) -> FeeEstimates {
+        let scale = |fee: u128| {
+            fee.saturating_mul(u128::from(self.multiplier_percent))
+                .div_ceil(100)
+        };
+        let mut max_priority_fee_per_gas = scale(tip.max(u128::from(self.min_priority_fee)));
+        let mut max_fee_per_gas = scale(base_fee_per_gas).saturating_add(max_priority_fee_per_gas);
+        if let Some(cap) = self.max_fee_cap {
+            max_fee_per_gas = max_fee_per_gas.min(u128::from(cap));
+            max_priority_fee_per_gas = max_priority_fee_per_gas.min(max_fee_per_gas);
+        }
+        FeeEstimates {
+            max_fee_per_gas,
+            max_priority_fee_per_gas,
+        }
+    }
+}
+
 pub async fn fee_history(
     block_count: Nat,
     newest_block: BlockTag,
@@ -39,13 +119,10 @@ pub async fn fee_history(
     ));
 
     for _ in 1..=MAX_RETRY_ATTEMPTS {
-        let rpc = get_ranked_rpc_provider();
+        let (rpc, consensus) = single_read_providers(evm_rpc, ProviderPool::Read)?;
         let rpc_config = RpcConfig {
             response_size_estimate: Some(3000),
-            response_consensus: Some(evm_rpc_types::ConsensusStrategy::Threshold {
-                total: Some(1),
-                min: 1,
-            }),
+            response_consensus: Some(consensus),
         };
 
         let call_result = evm_rpc
@@ -59,7 +136,7 @@ pub async fn fee_history(
 
         let canister_response = extract_call_result(call_result)?;
 
-        result = extract_multi_rpc_result(rpc, canister_response);
+        result = extract_multi_rpc_result(ProviderPool::Read, rpc, canister_response);
         if result.is_ok() {
             break;
         }
@@ -75,13 +152,20 @@ fn median_index(length: usize) -> usize {
     (length - 1) / 2
 }
 
+/// Estimates the fees of a transaction over the last `block_count` blocks, following `policy`.
 pub async fn estimate_transaction_fees(
     block_count: u8,
     evm_rpc: &Service,
     block_tag: BlockTag,
+    policy: &FeePolicy,
 ) -> ManagerResult<FeeEstimates> {
-    let fee_history =
-        fee_history(Nat::from(block_count), block_tag, Some(vec![95]), evm_rpc).await?;
+    let fee_history = fee_history(
+        Nat::from(block_count),
+        block_tag,
+        Some(vec![policy.percentile]),
+        evm_rpc,
+    )
+    .await?;
 
     let median_index = median_index(block_count.into());
 
@@ -93,7 +177,7 @@ pub async fn estimate_transaction_fees(
     let base_fee_per_gas_u128 = u128::try_from(base_fee_per_gas.0.clone())
         .map_err(|err| ManagerError::DecodingError(format!("{:#?}", err)))?;
 
-    // obtain the 95th percentile of the tips for the past blocks
+    // obtain the sampled percentile of the tips for the past blocks
     let mut percentiles: Vec<Nat> = fee_history
         .reward
         .into_iter()
@@ -107,14 +191,7 @@ pub async fn estimate_transaction_fees(
     let median_reward_u128 = u128::try_from(median_reward.0.clone())
         .map_err(|err| ManagerError::DecodingError(format!("{:#?}", err)))?;
 
-    let max_priority_fee_per_gas = median_reward_u128
-        .saturating_add(base_fee_per_gas_u128)
-        .max(MIN_SUGGEST_MAX_PRIORITY_FEE_PER_GAS as u128);
-
-    Ok(FeeEstimates {
-        max_fee_per_gas: max_priority_fee_per_gas,
-        max_priority_fee_per_gas: median_reward_u128,
-    })
+    Ok(policy.fees(base_fee_per_gas_u128, median_reward_u128))
 }
 
 pub async fn get_estimate_gas(
@@ -166,3 +243,72 @@ pub async fn get_estimate_gas(
 
     Ok(exaggerated_estimation)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fee_policy_fees() {
+        let policy = FeePolicy::default();
+        assert_eq!(
+            policy.fees(10_000_000_000, 2_000_000_000),
+            FeeEstimates {
+                max_fee_per_gas: 12_000_000_000,
+                max_priority_fee_per_gas: 2_000_000_000,
+            }
+        );
+        // Tips below the floor are raised to it
+        assert_eq!(
+            policy.fees(10_000_000_000, 0).max_priority_fee_per_gas,
+            1_500_000_000
+        );
+
+        let policy = FeePolicy {
+            multiplier_percent: 150,
+            max_fee_cap: Some(20_000_000_000),
+            ..FeePolicy::default()
+        };
+        assert_eq!(
+            policy.fees(10_000_000_000, 2_000_000_000),
+            FeeEstimates {
+                max_fee_per_gas: 18_000_000_000,
+                max_priority_fee_per_gas: 3_000_000_000,
+            }
+        );
+        // The cap bounds both fees
+        assert_eq!(
+            policy.fees(30_000_000_000, 30_000_000_000),
+            FeeEstimates {
+                max_fee_per_gas: 20_000_000_000,
+                max_priority_fee_per_gas: 20_000_000_000,
+            }
+        );
+    }
+
+    #[test]
+    fn test_fee_policy_validate() {
+        assert!(FeePolicy::default().validate().is_ok());
+        let invalid = [
+            FeePolicy {
+                percentile: 0,
+                ..FeePolicy::default()
+            },
+            FeePolicy {
+                multiplier_percent: 99,
+                ..FeePolicy::default()
+            },
+            FeePolicy {
+                multiplier_percent: MAX_FEE_MULTIPLIER_PERCENT + 1,
+                ..FeePolicy::default()
+            },
+            FeePolicy {
+                max_fee_cap: Some(1),
+                ..FeePolicy::default()
+            },
+        ];
+        for policy in invalid {
+            assert!(policy.validate().is_err());
+        }
+    }
+}
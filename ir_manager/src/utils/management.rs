@@ -0,0 +1,92 @@
+//! IC Management Canister Calls
+//!
+//! Wraps the calls to the management canister (tECDSA and `raw_rand`) with bounded retries,
+//! and records their latency, cycle cost, and outcome in the metrics. A transient rejection
+//! used to abort a whole run after all the expensive RPC work was done.
+//!
+//! ```plain
+//! management_call ──► attempt ──┬── Ok ───────────────────────────────► record ──► Ok
+//!                       ▲       ├── SysTransient (attempts left) ──┐
+//!                       └───────┼──────────────────────────────────┘
+//!                               └── other rejection / no attempts ──► record ──► Err
+//! ```
+//!
+//! Cycles are estimated from the canister balance before and after the call, so cycles
+//! received concurrently (e.g. by a swap) can hide part of the cost.
+
+use std::future::Future;
+
+use ic_exports::ic_cdk::api::{
+    call::{CallResult, RejectionCode},
+    canister_balance128,
+};
+
+use crate::{
+    clock::time,
+    constants::MANAGEMENT_CALL_ATTEMPTS,
+    metrics::{record_management_call, ManagementCall},
+};
+
+use super::{
+    common::extract_call_result,
+    error::{ManagerError, ManagerResult},
+};
+
+/// Returns `true` if the error is a rejection that may succeed when the call is retried.
+pub fn is_transient(err: &ManagerError) -> bool {
+    matches!(
+        err,
+        ManagerError::CallResult(RejectionCode::SysTransient, _)
+    )
+}
+
+/// Calls the management canister with retries on transient rejections.
+///
+/// # Arguments
+/// * `call` - Class of the call, under which the metrics are recorded
+/// * `request` - Issues the call, invoked once per attempt
+pub async fn management_call<T, F, Fut>(call: ManagementCall, request: F) -> ManagerResult<T>
+where
+    F: Fn() -> Fut,
+    Fut: Future<Output = CallResult<(T,)>>,
+{
+    let started_at = time();
+    let balance_before = canister_balance128();
+
+    let mut attempts = 0;
+    let result = loop {
+        attempts += 1;
+        let result = extract_call_result(request().await);
+        match &result {
+            Err(err) if is_transient(err) && attempts < MANAGEMENT_CALL_ATTEMPTS => continue,
+            _ => break result,
+        }
+    };
+
+    record_management_call(
+        call,
+        &result,
+        attempts - 1,
+        time().saturating_sub(started_at) / 1_000_000,
+        balance_before.saturating_sub(canister_balance128()),
+    );
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_transient() {
+        assert!(is_transient(&ManagerError::CallResult(
+            RejectionCode::SysTransient,
+            String::new()
+        )));
+        assert!(!is_transient(&ManagerError::CallResult(
+            RejectionCode::CanisterReject,
+            String::new()
+        )));
+        assert!(!is_transient(&ManagerError::Locked));
+    }
+}
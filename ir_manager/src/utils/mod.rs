@@ -1,13 +1,27 @@
 //! Utility and helper functions needed for:
 //! - Transaction signing, gas estimation, and submission
-//! - Interacting with the EVM RPC and the exchange rate canisters
+//! - Nonce allocation across the transactions of an EOA
+//! - Interacting with the EVM RPC, the exchange rate, and the cycles minting canisters
+//! - Calling the IC management canister with retries
+//! - Validating and normalizing address inputs
+//! - Aggregating contract reads with Multicall3
+//! - Submitting ERC-4337 UserOperations to a bundler
+//! - Bounded sequences in stable memory
 //! - Error handling
-//! - Type casting
+//! - Type casting and typed units
 
+pub(crate) mod address;
+pub(crate) mod cmc;
 pub(crate) mod common;
 pub(crate) mod error;
 pub(crate) mod evm_rpc;
 pub(crate) mod exchange;
 pub(crate) mod gas;
+pub(crate) mod management;
+pub(crate) mod multicall;
+pub(crate) mod nonce;
+pub(crate) mod ring;
 pub(crate) mod signer;
 pub(crate) mod transaction_builder;
+pub(crate) mod units;
+pub(crate) mod user_operation;
@@ -0,0 +1,180 @@
+//! Multicall3 Reads
+//!
+//! Aggregates independent contract reads into a single `aggregate3` call of the Multicall3
+//! contract, so that they cost one consensus round through the EVM RPC canister instead of one
+//! per read:
+//!
+//! ```plain
+//! add(manager_a, getEntireBranchDebt)        ──► 0 ─┐
+//! add(manager_a, getUnbackedPortionPrice...) ──► 1 ─┤                   ┌──► decode::<C>(0)
+//! add(registry,  getRedemptionRateWithDecay) ──► 2 ─┼──► aggregate3 ──► ├──► decode::<C>(1)
+//! add(getter,    getDebtPerInterestRate...)  ──► 3 ─┘    (one eth_call) └──► ...
+//! ```
+//!
+//! Every call is sent with `allowFailure = false`, so a single reverting read reverts the
+//! whole batch, like the individual reads would fail the execution.
+
+use std::str::FromStr;
+
+use alloy_primitives::{Address, Bytes};
+use alloy_sol_types::SolCall;
+use evm_rpc_types::BlockTag;
+
+use crate::{
+    constants::MULTICALL3,
+    types::{aggregate3Call, Call3},
+    utils::{
+        common::{call_with_dynamic_retries, decode_abi_response},
+        error::{ManagerError, ManagerResult},
+        evm_rpc::Service,
+    },
+};
+
+/// Returns the address of the Multicall3 contract.
+fn multicall3() -> Address {
+    Address::from_str(MULTICALL3).expect("Failed to parse the Multicall3 address.")
+}
+
+/// Reads to aggregate into a single Multicall3 call
+#[derive(Default)]
+pub struct MulticallBatch {
+    calls: Vec<Call3>,
+}
+
+impl MulticallBatch {
+    /// Adds a read of `target` and returns its index in the results.
+    pub fn add<C: SolCall>(&mut self, target: Address, call: &C) -> usize {
+        self.calls.push(Call3 {
+            target,
+            allowFailure: false,
+            callData: Bytes::from(call.abi_encode()),
+        });
+        self.calls.len() - 1
+    }
+
+    /// Returns the calldata of the `aggregate3` call.
+    fn calldata(self) -> Vec<u8> {
+        aggregate3Call { calls: self.calls }.abi_encode()
+    }
+
+    /// Sends the batch at `block_tag` and returns the raw results, in the order of the reads.
+    pub async fn execute(
+        self,
+        rpc_canister: &Service,
+        block_tag: BlockTag,
+    ) -> ManagerResult<MulticallResults> {
+        let expected = self.calls.len();
+        let response =
+            call_with_dynamic_retries(rpc_canister, block_tag, multicall3(), self.calldata())
+                .await?;
+        MulticallResults::decode_response(response, expected)
+    }
+}
+
+/// Raw results of a Multicall3 batch
+#[derive(Debug)]
+pub struct MulticallResults(Vec<Bytes>);
+
+impl MulticallResults {
+    /// Decodes the hex-encoded response of an `aggregate3` call of `expected` reads.
+    fn decode_response(response: String, expected: usize) -> ManagerResult<Self> {
+        let results = decode_abi_response::<_, aggregate3Call>(response)?.returnData;
+        if results.len() != expected {
+            return Err(ManagerError::DecodingError(format!(
+                "Multicall3 returned {} results for {} calls.",
+                results.len(),
+                expected
+            )));
+        }
+        results
+            .into_iter()
+            .enumerate()
+            .map(|(index, result)| {
+                if result.success {
+                    Ok(result.returnData)
+                } else {
+                    Err(ManagerError::Custom(format!(
+                        "Call {} of the Multicall3 batch failed.",
+                        index
+                    )))
+                }
+            })
+            .collect::<ManagerResult<Vec<Bytes>>>()
+            .map(Self)
+    }
+
+    /// Decodes the result of the read at `index` as the return value of `C`.
+    pub fn decode<C: SolCall>(&self, index: usize) -> ManagerResult<C::Return> {
+        let data = self.0.get(index).ok_or(ManagerError::NonExistentValue)?;
+        C::abi_decode_returns(data, false)
+            .map_err(|err| ManagerError::DecodingError(err.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::U256;
+    use alloy_sol_types::SolValue;
+
+    use super::*;
+    use crate::types::{getEntireBranchDebtCall, getRedemptionRateWithDecayCall, Call3Result};
+
+    fn response(results: Vec<Call3Result>) -> String {
+        format!("0x{}", alloy::hex::encode((results,).abi_encode_params()))
+    }
+
+    fn success(value: U256) -> Call3Result {
+        Call3Result {
+            success: true,
+            returnData: Bytes::from(value.abi_encode()),
+        }
+    }
+
+    #[test]
+    fn test_calldata() {
+        let mut batch = MulticallBatch::default();
+        let manager = Address::repeat_byte(1);
+        assert_eq!(batch.add(manager, &getEntireBranchDebtCall {}), 0);
+        assert_eq!(batch.add(manager, &getRedemptionRateWithDecayCall {}), 1);
+
+        let decoded = aggregate3Call::abi_decode(&batch.calldata(), true).unwrap();
+        assert_eq!(decoded.calls.len(), 2);
+        assert_eq!(decoded.calls[1].target, manager);
+        assert!(!decoded.calls[1].allowFailure);
+        assert_eq!(
+            decoded.calls[1].callData.to_vec(),
+            getRedemptionRateWithDecayCall::SELECTOR.to_vec()
+        );
+    }
+
+    #[test]
+    fn test_decode_results() {
+        let results = MulticallResults::decode_response(
+            response(vec![success(U256::from(7)), success(U256::from(9))]),
+            2,
+        )
+        .unwrap();
+        assert_eq!(
+            results
+                .decode::<getEntireBranchDebtCall>(0)
+                .unwrap()
+                .entireSystemDebt,
+            U256::from(7)
+        );
+        assert_eq!(
+            results
+                .decode::<getRedemptionRateWithDecayCall>(1)
+                .unwrap()
+                ._0,
+            U256::from(9)
+        );
+        assert!(results.decode::<getRedemptionRateWithDecayCall>(2).is_err());
+
+        let failed = Call3Result {
+            success: false,
+            returnData: Bytes::new(),
+        };
+        assert!(MulticallResults::decode_response(response(vec![failed]), 1).is_err());
+        assert!(MulticallResults::decode_response(response(vec![success(U256::ZERO)]), 2).is_err());
+    }
+}
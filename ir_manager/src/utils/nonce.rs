@@ -0,0 +1,186 @@
+//! Per-EOA Nonce Manager
+//!
+//! A strategy EOA signs rate adjustments, registry records, and ckETH deposits, which are sent
+//! by different tasks that interleave at every `await`. Each task leases its nonce from the
+//! manager before signing, so that two transactions are never signed with the same nonce:
+//!
+//! ```plain
+//!                         NONCES[eoa] { next, in_flight }
+//!                                   │
+//! NonceLease::acquire(eoa, stored) ─┤ nonce = max(next, stored), next = nonce + 1
+//!                                   │
+//!        ┌── accepted ──► commit ───┤ nonce consumed, strategy stores nonce + 1
+//!        │                          │
+//! send ──┤                          │
+//!        │                          │
+//!        └── rejected/failed ──► drop ──► released, next rolls back if it was the last one
+//!
+//! nonce error ──► get_nonce ──► reconcile_nonce(eoa, chain nonce) ──► next = chain nonce
+//!                                                                     (or above in-flight ones)
+//! ```
+//!
+//! The manager lives on the heap. After an upgrade it starts from the nonces stored in the
+//! strategies, and the first nonce error reconciles it against the chain.
+
+use std::collections::BTreeSet;
+
+use alloy_primitives::Address;
+
+use crate::state::NONCES;
+
+/// Nonce bookkeeping of a single EOA
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct NonceState {
+    /// Next nonce to hand out
+    pub next: u64,
+    /// Nonces leased to transactions that are being signed or submitted
+    pub in_flight: BTreeSet<u64>,
+}
+
+/// A nonce reserved for a single transaction
+///
+/// The nonce is released when the lease is dropped without being committed, so that a
+/// transaction that was never accepted does not leave a gap.
+#[derive(Debug)]
+pub struct NonceLease {
+    eoa: Address,
+    nonce: u64,
+    settled: bool,
+}
+
+impl NonceLease {
+    /// Leases the next nonce of an EOA.
+    ///
+    /// # Arguments
+    /// * `eoa` - Address of the signing EOA
+    /// * `stored` - Nonce stored in the strategy, used if it is ahead of the manager
+    pub fn acquire(eoa: Address, stored: u64) -> Self {
+        let nonce = NONCES.with(|nonces| {
+            let mut nonces = nonces.borrow_mut();
+            let state = nonces.entry(eoa).or_default();
+            let nonce = state.next.max(stored);
+            state.next = nonce + 1;
+            state.in_flight.insert(nonce);
+            nonce
+        });
+        Self {
+            eoa,
+            nonce,
+            settled: false,
+        }
+    }
+
+    /// Returns the leased nonce.
+    pub fn nonce(&self) -> u64 {
+        self.nonce
+    }
+
+    /// Marks the nonce as consumed by an accepted transaction and returns the nonce that
+    /// follows it, to be stored in its strategy.
+    ///
+    /// The manager may already count later leases that are still in flight. They are not
+    /// included, as a later lease that is dropped rolls the manager back below them.
+    pub fn commit(mut self) -> u64 {
+        self.settled = true;
+        NONCES.with(|nonces| {
+            let mut nonces = nonces.borrow_mut();
+            let state = nonces.entry(self.eoa).or_default();
+            state.in_flight.remove(&self.nonce);
+            state.next = state.next.max(self.nonce + 1);
+        });
+        self.nonce + 1
+    }
+}
+
+impl Drop for NonceLease {
+    fn drop(&mut self) {
+        if self.settled {
+            return;
+        }
+        NONCES.with(|nonces| {
+            if let Some(state) = nonces.borrow_mut().get_mut(&self.eoa) {
+                state.in_flight.remove(&self.nonce);
+                if state.next == self.nonce + 1 {
+                    state.next = self.nonce;
+                }
+            }
+        });
+    }
+}
+
+/// Aligns the manager with the on-chain nonce of an EOA and returns the next nonce to use.
+///
+/// Nonces that are still leased are never handed out again.
+pub fn reconcile_nonce(eoa: Address, chain_nonce: u64) -> u64 {
+    NONCES.with(|nonces| {
+        let mut nonces = nonces.borrow_mut();
+        let state = nonces.entry(eoa).or_default();
+        state.next = state
+            .in_flight
+            .last()
+            .map_or(chain_nonce, |last| chain_nonce.max(last + 1));
+        state.next
+    })
+}
+
+/// Returns the nonce bookkeeping of an EOA.
+pub fn nonce_state(eoa: Address) -> NonceState {
+    NONCES.with(|nonces| nonces.borrow().get(&eoa).cloned().unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_leases_never_share_a_nonce() {
+        let eoa = Address::repeat_byte(1);
+
+        let adjustment = NonceLease::acquire(eoa, 5);
+        // The deposit reads a stale nonce from its strategy snapshot
+        let deposit = NonceLease::acquire(eoa, 5);
+        assert_eq!(adjustment.nonce(), 5);
+        assert_eq!(deposit.nonce(), 6);
+        assert_eq!(nonce_state(eoa).in_flight, BTreeSet::from([5, 6]));
+
+        assert_eq!(adjustment.commit(), 6);
+        drop(deposit);
+        let state = nonce_state(eoa);
+        assert_eq!(state.next, 6);
+        assert!(state.in_flight.is_empty());
+
+        // A stored nonce ahead of the manager wins
+        assert_eq!(NonceLease::acquire(eoa, 9).commit(), 10);
+    }
+
+    #[test]
+    fn test_dropped_lease_leaves_no_gap() {
+        let eoa = Address::repeat_byte(3);
+
+        let adjustment = NonceLease::acquire(eoa, 5);
+        let deposit = NonceLease::acquire(eoa, 5);
+        let stored = adjustment.commit();
+        assert_eq!(stored, 6);
+
+        // The later lease is dropped after the earlier one was committed
+        drop(deposit);
+        let next = NonceLease::acquire(eoa, stored);
+        assert_eq!(next.nonce(), 6);
+        assert_eq!(next.commit(), 7);
+    }
+
+    #[test]
+    fn test_reconcile_nonce() {
+        let eoa = Address::repeat_byte(2);
+        let committed = NonceLease::acquire(eoa, 10);
+        assert_eq!(committed.commit(), 11);
+
+        // The transaction was dropped: the chain is behind the manager
+        assert_eq!(reconcile_nonce(eoa, 10), 10);
+
+        let in_flight = NonceLease::acquire(eoa, 0);
+        assert_eq!(in_flight.nonce(), 10);
+        assert_eq!(reconcile_nonce(eoa, 8), 11);
+        assert_eq!(reconcile_nonce(eoa, 12), 12);
+    }
+}
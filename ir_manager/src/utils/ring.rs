@@ -0,0 +1,268 @@
+//! Stable Ring Buffer
+//!
+//! A bounded, ordered sequence in stable memory. The elements live in a `StableVec` that
+//! grows up to the capacity and then wraps around, while the position of the oldest element
+//! and the number of elements are kept in a `StableCell`:
+//!
+//! ```plain
+//!                  head          head + len (mod capacity)
+//!                   │                  │
+//! slots:  [ e3 ][ e4 ][ e0 ][ e1 ][ e2 ]     e0 oldest ── e4 newest
+//!           ▲                 ▲
+//!           └─ wrapped        └─ pop_front(n): head += n, len -= n
+//!
+//! push at capacity ──► the oldest element is overwritten
+//! ```
+//!
+//! Appending and pruning the oldest elements are O(1). Removing elements in the middle
+//! (`retain`) compacts the sequence and is O(n).
+//!
+//! A vector written before the ring existed is adopted on first use: its elements become
+//! the content of the ring, keeping the newest ones if there are more than the capacity.
+
+use std::borrow::Cow;
+
+use candid::{CandidType, Decode, Encode};
+use ic_stable_structures::{
+    storable::Bound, Cell as StableCell, GrowFailed, Memory, Storable, Vec as StableVec,
+};
+use serde::Deserialize;
+
+/// Position and length of the ring within its vector
+#[derive(CandidType, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+pub struct RingState {
+    /// Slot of the oldest element
+    pub head: u64,
+    /// Number of elements
+    pub len: u64,
+    /// `false` until the vector has been adopted by the ring
+    pub initialized: bool,
+}
+
+impl Storable for RingState {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        Cow::Owned(Encode!(self).expect("Failed to encode the ring state."))
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).expect("Failed to decode the ring state.")
+    }
+
+    const BOUND: Bound = Bound::Bounded {
+        max_size: 64,
+        is_fixed_size: false,
+    };
+}
+
+/// A ring buffer of at most `capacity` elements in stable memory
+pub struct StableRing<T: Storable, M: Memory> {
+    slots: StableVec<T, M>,
+    state: StableCell<RingState, M>,
+    capacity: u64,
+}
+
+impl<T: Storable, M: Memory> StableRing<T, M> {
+    /// Opens the ring stored in `slots` and `state`, adopting a vector written without a ring.
+    pub fn init(slots: StableVec<T, M>, state: StableCell<RingState, M>, capacity: u64) -> Self {
+        let mut ring = Self {
+            slots,
+            state,
+            capacity: capacity.max(1),
+        };
+        if !ring.state.get().initialized {
+            ring.adopt_slots();
+        }
+        ring
+    }
+
+    /// Takes over the elements of the vector, keeping the newest ones up to the capacity.
+    fn adopt_slots(&mut self) {
+        let stored = self.slots.len();
+        let excess = stored.saturating_sub(self.capacity);
+        if excess > 0 {
+            for index in 0..self.capacity {
+                if let Some(element) = self.slots.get(excess + index) {
+                    self.slots.set(index, &element);
+                }
+            }
+            for _ in self.capacity..stored {
+                self.slots.pop();
+            }
+        }
+        self.set_state(RingState {
+            head: 0,
+            len: stored.min(self.capacity),
+            initialized: true,
+        });
+    }
+
+    fn set_state(&mut self, state: RingState) {
+        self.state
+            .set(state)
+            .expect("Failed to persist the ring state.");
+    }
+
+    /// Returns the slot of the element at a logical position.
+    fn slot(&self, index: u64) -> u64 {
+        (self.state.get().head + index) % self.capacity
+    }
+
+    /// Returns the maximum number of elements.
+    pub fn capacity(&self) -> u64 {
+        self.capacity
+    }
+
+    /// Returns the number of elements.
+    pub fn len(&self) -> u64 {
+        self.state.get().len
+    }
+
+    /// Returns `true` if the ring has no elements.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the element at a position counted from the oldest one.
+    pub fn get(&self, index: u64) -> Option<T> {
+        if index >= self.len() {
+            return None;
+        }
+        self.slots.get(self.slot(index))
+    }
+
+    /// Replaces the element at a position counted from the oldest one.
+    ///
+    /// Positions out of range are ignored.
+    pub fn set(&self, index: u64, element: &T) {
+        if index < self.len() {
+            self.slots.set(self.slot(index), element);
+        }
+    }
+
+    /// Returns the newest element.
+    pub fn last(&self) -> Option<T> {
+        self.len().checked_sub(1).and_then(|index| self.get(index))
+    }
+
+    /// Iterates over the elements from the oldest to the newest.
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = T> + '_ {
+        (0..self.len()).filter_map(|index| self.get(index))
+    }
+
+    /// Appends an element, overwriting the oldest one if the ring is full.
+    pub fn push(&mut self, element: &T) -> Result<(), GrowFailed> {
+        if self.len() == self.capacity {
+            self.pop_front(1);
+        }
+        let slot = self.slot(self.len());
+        if slot < self.slots.len() {
+            self.slots.set(slot, element);
+        } else {
+            self.slots.push(element)?;
+        }
+        let state = *self.state.get();
+        self.set_state(RingState {
+            len: state.len + 1,
+            ..state
+        });
+        Ok(())
+    }
+
+    /// Removes up to `count` of the oldest elements and returns the number removed.
+    pub fn pop_front(&mut self, count: u64) -> u64 {
+        let state = *self.state.get();
+        let removed = count.min(state.len);
+        if removed > 0 {
+            self.set_state(RingState {
+                head: (state.head + removed) % self.capacity,
+                len: state.len - removed,
+                ..state
+            });
+        }
+        removed
+    }
+
+    /// Keeps only the elements for which `keep` returns `true`, in order, and returns the
+    /// number removed.
+    pub fn retain(&mut self, mut keep: impl FnMut(&T) -> bool) -> u64 {
+        let len = self.len();
+        let mut kept = 0;
+        for index in 0..len {
+            if let Some(element) = self.get(index) {
+                if keep(&element) {
+                    if index != kept {
+                        self.set(kept, &element);
+                    }
+                    kept += 1;
+                }
+            }
+        }
+        let state = *self.state.get();
+        self.set_state(RingState { len: kept, ..state });
+        len - kept
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ic_stable_structures::{
+        memory_manager::{MemoryId, MemoryManager, VirtualMemory},
+        DefaultMemoryImpl,
+    };
+
+    use super::*;
+
+    type TestRing = StableRing<u64, VirtualMemory<DefaultMemoryImpl>>;
+
+    fn ring(preloaded: &[u64], capacity: u64) -> TestRing {
+        let manager = MemoryManager::init(DefaultMemoryImpl::default());
+        let slots = StableVec::init(manager.get(MemoryId::new(0))).unwrap();
+        for element in preloaded {
+            slots.push(element).unwrap();
+        }
+        let state = StableCell::init(manager.get(MemoryId::new(1)), RingState::default()).unwrap();
+        StableRing::init(slots, state, capacity)
+    }
+
+    fn contents(ring: &TestRing) -> Vec<u64> {
+        ring.iter().collect()
+    }
+
+    #[test]
+    fn test_push_wraps_around() {
+        let mut ring = ring(&[], 3);
+        for element in 1..=5 {
+            ring.push(&element).unwrap();
+        }
+        assert_eq!(contents(&ring), vec![3, 4, 5]);
+        assert_eq!(ring.last(), Some(5));
+        assert_eq!(ring.get(3), None);
+
+        assert_eq!(ring.pop_front(2), 2);
+        assert_eq!(contents(&ring), vec![5]);
+        ring.push(&6).unwrap();
+        ring.push(&7).unwrap();
+        ring.push(&8).unwrap();
+        assert_eq!(contents(&ring), vec![6, 7, 8]);
+        assert_eq!(ring.pop_front(10), 3);
+        assert!(ring.is_empty());
+    }
+
+    #[test]
+    fn test_retain_compacts() {
+        let mut ring = ring(&[], 4);
+        for element in 1..=6 {
+            ring.push(&element).unwrap();
+        }
+        assert_eq!(ring.retain(|element| element % 2 == 0), 2);
+        assert_eq!(contents(&ring), vec![4, 6]);
+        ring.push(&7).unwrap();
+        assert_eq!(contents(&ring), vec![4, 6, 7]);
+    }
+
+    #[test]
+    fn test_existing_vector_is_adopted() {
+        assert_eq!(contents(&ring(&[1, 2, 3, 4, 5], 3)), vec![3, 4, 5]);
+        assert_eq!(contents(&ring(&[1, 2], 3)), vec![1, 2]);
+    }
+}
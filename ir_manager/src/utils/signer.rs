@@ -1,4 +1,11 @@
 //! Generates public keys, signs transactions, and computes signatures
+//!
+//! Every EOA is derived from the threshold ECDSA key named in `ECDSA_KEY_NAME`, which is
+//! `key_1` on mainnet and set by a controller for other environments, e.g. `dfx_test_key`
+//! on a local replica. The name is fixed once an EOA is derived from it, as the EOAs of
+//! another key are different addresses.
+
+use std::str::FromStr;
 
 use alloy::consensus::{SignableTransaction, TxEip1559, TxEnvelope};
 use alloy::eips::eip2718::Encodable2718;
@@ -8,27 +15,77 @@ use alloy::signers::Signature;
 use candid::Principal;
 
 use ic_exports::ic_cdk::api::management_canister::ecdsa::{
-    ecdsa_public_key, sign_with_ecdsa, EcdsaKeyId, EcdsaPublicKeyArgument, SignWithEcdsaArgument,
+    ecdsa_public_key, sign_with_ecdsa, EcdsaCurve, EcdsaKeyId, EcdsaPublicKeyArgument,
+    SignWithEcdsaArgument,
 };
 
+use crate::constants::ECDSA_KEY_NAMES;
+use crate::funding::funding_eoa;
+use crate::metrics::ManagementCall;
+use crate::state::{ECDSA_KEY_NAME, STRATEGY_STATE};
 use crate::types::DerivationPath;
 use crate::utils::error::ManagerError;
 
-use super::common::extract_call_result;
 use super::error::ManagerResult;
+use super::management::management_call;
+
+/// Returns the name of the threshold ECDSA key the EOAs are derived from.
+pub fn ecdsa_key_name() -> String {
+    ECDSA_KEY_NAME.with(|name| name.borrow().get().clone())
+}
+
+/// Returns the threshold ECDSA key the EOAs are derived from.
+pub fn ecdsa_key_id() -> EcdsaKeyId {
+    EcdsaKeyId {
+        curve: EcdsaCurve::Secp256k1,
+        name: ecdsa_key_name(),
+    }
+}
+
+/// Replaces the name of the threshold ECDSA key and returns the previous one.
+///
+/// # Errors
+/// Returns `ManagerError::Custom` if the key is unknown, or if an EOA was already derived.
+pub fn set_ecdsa_key_name(name: String) -> ManagerResult<String> {
+    if !ECDSA_KEY_NAMES.contains(&name.as_str()) {
+        return Err(ManagerError::Custom(format!(
+            "Unknown threshold ECDSA key {}. Expected one of {:?}.",
+            name, ECDSA_KEY_NAMES
+        )));
+    }
+    let derived = STRATEGY_STATE.with(|state| {
+        state
+            .borrow()
+            .values()
+            .any(|strategy| strategy.settings.eoa_pk.is_some())
+    }) || funding_eoa().is_some();
+    if derived && name != ecdsa_key_name() {
+        return Err(ManagerError::Custom(
+            "The key name cannot change once an EOA is derived from it.".to_string(),
+        ));
+    }
+    Ok(ECDSA_KEY_NAME.with(|current| {
+        current
+            .borrow_mut()
+            .set(name)
+            .expect("Failed to persist the ECDSA key name.")
+    }))
+}
 
 pub async fn get_canister_public_key(
     key_id: EcdsaKeyId,
     canister_id: Option<Principal>,
     derivation_path: DerivationPath,
 ) -> ManagerResult<Vec<u8>> {
-    let call_result = ecdsa_public_key(EcdsaPublicKeyArgument {
-        canister_id,
-        derivation_path,
-        key_id,
+    management_call(ManagementCall::EcdsaPublicKey, || {
+        ecdsa_public_key(EcdsaPublicKeyArgument {
+            canister_id,
+            derivation_path: derivation_path.clone(),
+            key_id: key_id.clone(),
+        })
     })
-    .await;
-    extract_call_result(call_result).map(|v| v.public_key)
+    .await
+    .map(|v| v.public_key)
 }
 
 pub async fn sign_eip1559_transaction(
@@ -38,14 +95,15 @@ pub async fn sign_eip1559_transaction(
 ) -> ManagerResult<String> {
     let tx_hash = tx.signature_hash();
 
-    let call_result = sign_with_ecdsa(SignWithEcdsaArgument {
-        message_hash: tx_hash.to_vec(),
-        derivation_path: derivation_path.clone(),
-        key_id: key_id.clone(),
+    let r_and_s = management_call(ManagementCall::SignWithEcdsa, || {
+        sign_with_ecdsa(SignWithEcdsaArgument {
+            message_hash: tx_hash.to_vec(),
+            derivation_path: derivation_path.clone(),
+            key_id: key_id.clone(),
+        })
     })
-    .await;
-
-    let r_and_s = extract_call_result(call_result)?.signature;
+    .await?
+    .signature;
 
     let ecdsa_pub_key = get_canister_public_key(key_id, None, derivation_path).await?;
     let parity = y_parity(&tx_hash, &r_and_s, &ecdsa_pub_key)?;
@@ -61,6 +119,57 @@ pub async fn sign_eip1559_transaction(
     Ok(format!("0x{}", hex::encode(&signed_tx_bytes)))
 }
 
+/// Signs a 32-byte hash and returns the 65-byte `r || s || v` signature, with `v` being 27
+/// or 28, as smart accounts recover it with `ecrecover`.
+pub async fn sign_prehash(
+    prehash: FixedBytes<32>,
+    key_id: EcdsaKeyId,
+    derivation_path: DerivationPath,
+) -> ManagerResult<Vec<u8>> {
+    let r_and_s = management_call(ManagementCall::SignWithEcdsa, || {
+        sign_with_ecdsa(SignWithEcdsaArgument {
+            message_hash: prehash.to_vec(),
+            derivation_path: derivation_path.clone(),
+            key_id: key_id.clone(),
+        })
+    })
+    .await?
+    .signature;
+
+    let ecdsa_pub_key = get_canister_public_key(key_id, None, derivation_path).await?;
+    let parity = recovery_parity(&prehash, &r_and_s, &ecdsa_pub_key)?;
+
+    let mut signature = r_and_s;
+    signature.push(27 + parity);
+    Ok(signature)
+}
+
+/// Recovers the address that produced the 65-byte `r || s || v` signature of `prehash`.
+pub fn recover_address(prehash: &FixedBytes<32>, signature: &[u8]) -> ManagerResult<Address> {
+    use alloy::signers::k256::ecdsa::{RecoveryId, Signature, VerifyingKey};
+
+    let (r_and_s, v) = match signature {
+        [r_and_s @ .., v] if r_and_s.len() == 64 => (r_and_s, *v),
+        _ => {
+            return Err(ManagerError::DecodingError(format!(
+                "expected a 65-byte signature, got {} bytes",
+                signature.len()
+            )))
+        }
+    };
+    let recid = RecoveryId::try_from(v.wrapping_sub(27))
+        .map_err(|err| ManagerError::DecodingError(format!("invalid recovery ID: {:#?}", err)))?;
+    let signature = Signature::try_from(r_and_s).map_err(|err| {
+        ManagerError::DecodingError(format!("failed to parse the signature: {:#?}", err))
+    })?;
+    let key = VerifyingKey::recover_from_prehash(prehash.as_slice(), &signature, recid).map_err(
+        |err| ManagerError::DecodingError(format!("failed to recover from prehash: {:#?}", err)),
+    )?;
+    let address = pubkey_bytes_to_address(&key.to_sec1_bytes())?;
+    Address::from_str(&address)
+        .map_err(|err| ManagerError::DecodingError(format!("invalid address: {:#?}", err)))
+}
+
 /// Converts the public key bytes to an Ethereum address with a checksum.
 pub fn pubkey_bytes_to_address(pubkey_bytes: &[u8]) -> ManagerResult<String> {
     use alloy::signers::k256::elliptic_curve::sec1::ToEncodedPoint;
@@ -84,6 +193,11 @@ pub fn pubkey_bytes_to_address(pubkey_bytes: &[u8]) -> ManagerResult<String> {
 
 /// Computes the parity bit allowing to recover the public key from the signature.
 fn y_parity(prehash: &FixedBytes<32>, sig: &[u8], pubkey: &[u8]) -> ManagerResult<Parity> {
+    recovery_parity(prehash, sig, pubkey).map(|parity| Parity::Eip155(parity as u64))
+}
+
+/// Returns the recovery ID (0 or 1) of the signature.
+fn recovery_parity(prehash: &FixedBytes<32>, sig: &[u8], pubkey: &[u8]) -> ManagerResult<u8> {
     use alloy::signers::k256::ecdsa::{RecoveryId, Signature, VerifyingKey};
 
     let orig_key = VerifyingKey::from_sec1_bytes(pubkey).map_err(|err| {
@@ -102,7 +216,7 @@ fn y_parity(prehash: &FixedBytes<32>, sig: &[u8], pubkey: &[u8]) -> ManagerResul
         })?;
 
         if recovered_key == orig_key {
-            return Ok(Parity::Eip155(parity as u64));
+            return Ok(parity);
         }
     }
 
@@ -112,3 +226,50 @@ fn y_parity(prehash: &FixedBytes<32>, sig: &[u8], pubkey: &[u8]) -> ManagerResul
         hex::encode(pubkey)
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::DEFAULT_ECDSA_KEY_NAME;
+
+    #[test]
+    fn test_set_ecdsa_key_name() {
+        assert_eq!(ecdsa_key_name(), DEFAULT_ECDSA_KEY_NAME);
+        assert!(set_ecdsa_key_name("key_2".to_string()).is_err());
+        assert_eq!(
+            set_ecdsa_key_name("dfx_test_key".to_string()),
+            Ok(DEFAULT_ECDSA_KEY_NAME.to_string())
+        );
+        assert_eq!(ecdsa_key_id().name, "dfx_test_key");
+    }
+
+    #[test]
+    fn test_recover_address() {
+        use alloy::signers::k256::ecdsa::SigningKey;
+
+        let key = SigningKey::from_slice(&[7; 32]).unwrap();
+        let public_key = key.verifying_key().to_sec1_bytes();
+        let prehash = keccak256(b"payload");
+        let (signature, recid) = key.sign_prehash_recoverable(prehash.as_slice()).unwrap();
+        let r_and_s = signature.to_bytes().to_vec();
+        assert_eq!(
+            recovery_parity(&prehash, &r_and_s, &public_key),
+            Ok(recid.to_byte())
+        );
+
+        let mut signature = r_and_s;
+        signature.push(27 + recid.to_byte());
+        let address = recover_address(&prehash, &signature).unwrap();
+        assert_eq!(
+            address.to_string(),
+            pubkey_bytes_to_address(&public_key).unwrap()
+        );
+
+        // Another payload recovers to another address
+        assert_ne!(
+            recover_address(&keccak256(b"other"), &signature).ok(),
+            Some(address)
+        );
+        assert!(recover_address(&prehash, &signature[..64]).is_err());
+    }
+}
@@ -1,29 +1,74 @@
 //! Transaction builder (and sender) that interacts with the EVM RPC canister
+//!
+//! Also replaces pending transactions that do not confirm, e.g. because they are underpriced:
+//!
+//! ```plain
+//! pending for stuck_tx_blocks blocks ──► SpeedUp: same call, same nonce ──┐
+//!                                                                         ├──► bumped fees ──► sign ──► send
+//! cancel_pending_tx(key) ──────────────► Cancel: self-transfer, same nonce┘    (max of +bump%
+//!                                                                               and estimate)
+//! ```
+//!
+//! Replacements are recorded in the transaction pool, which marks the replaced transaction as
+//! `Replaced`. The nonce of the strategy is unchanged, as the replacement consumes the same one.
 
 use std::str::FromStr;
 
-use alloy::consensus::TxEip1559;
-use alloy_primitives::{Address, Bytes, TxKind, U256};
+use alloy::consensus::{TxEip1559, TxEnvelope};
+use alloy::eips::eip2718::Decodable2718;
+use alloy_primitives::{keccak256, Address, Bytes, TxKind, U256};
 use evm_rpc_types::RpcServices;
-use ic_exports::ic_cdk::api::management_canister::ecdsa::{EcdsaCurve, EcdsaKeyId};
 
 use crate::{
-    constants::CHAIN_ID,
-    providers::{extract_multi_rpc_send_raw_transaction_status, get_ranked_rpc_providers},
+    chain::chain_services,
+    constants::{
+        CANCEL_TX_GAS_LIMIT, DEFAULT_STUCK_TX_BLOCKS, DEFAULT_TX_FEE_BUMP_PERCENT,
+        MAX_TX_REPLACEMENTS, MIN_TX_FEE_BUMP_PERCENT,
+    },
+    flags::{flag_int, STUCK_TX_BLOCKS, TX_FEE_BUMP_PERCENT},
+    journal::{JournalCollection, LogType},
+    metrics::CallClass,
+    providers::{
+        extract_multi_rpc_send_raw_transaction_status, get_ranked_rpc_providers, ProviderPool,
+    },
+    tx_pool::{record_transaction, stuck_transactions, transactions_for_nonce, TxRecord},
     types::DerivationPath,
 };
 
 use super::{
-    common::get_block_tag,
+    common::{get_block_tag, track_consensus},
     error::{ManagerError, ManagerResult},
-    evm_rpc::{SendRawTransactionStatus, Service},
-    gas::{estimate_transaction_fees, FeeEstimates},
-    signer::sign_eip1559_transaction,
+    evm_rpc::{BlockTag, SendRawTransactionStatus, Service},
+    gas::{estimate_transaction_fees, FeeEstimates, FeePolicy},
+    signer::{ecdsa_key_id, sign_eip1559_transaction},
 };
 
+/// Kind of replacement sent for a pending transaction
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Replacement {
+    /// Resends the same call with bumped fees
+    SpeedUp,
+    /// Sends a zero-value self-transfer with bumped fees, burning the nonce
+    Cancel,
+}
+
+/// Signing and pricing parameters of a replacement
+pub struct ReplacementParams<'a> {
+    /// Kind of replacement
+    pub replacement: Replacement,
+    /// Address of the EOA that signed the pending transaction
+    pub eoa: Address,
+    /// Derivation path of the EOA
+    pub derivation_path: DerivationPath,
+    /// Cycles attached to the submission
+    pub cycles: u128,
+    /// Fee policy of the strategy, used to estimate the current fees
+    pub fee_policy: &'a FeePolicy,
+}
+
 /// Transaction builder struct
 #[derive(Default)]
-pub struct TransactionBuilder {
+pub struct TransactionBuilder<'a> {
     to: String,
     from: String,
     data: Vec<u8>,
@@ -31,9 +76,13 @@ pub struct TransactionBuilder {
     nonce: u64,
     derivation_path: DerivationPath,
     cycles: u128,
+    strategy_key: Option<u32>,
+    run_id: Option<u64>,
+    fee_policy: FeePolicy,
+    journal: Option<&'a mut JournalCollection>,
 }
 
-impl TransactionBuilder {
+impl<'a> TransactionBuilder<'a> {
     /// Sets the `to` field
     pub fn to(mut self, to: String) -> Self {
         self.to = to;
@@ -76,28 +125,55 @@ impl TransactionBuilder {
         self
     }
 
+    /// Sets the `strategy_key` field.
+    /// Submitted transactions are recorded in the transaction pool under this strategy.
+    pub fn strategy_key(mut self, strategy_key: u32) -> Self {
+        self.strategy_key = Some(strategy_key);
+        self
+    }
+
+    /// Sets the `run_id` field.
+    /// Submitted transactions are linked to this strategy run in the transaction pool.
+    pub fn run_id(mut self, run_id: Option<u64>) -> Self {
+        self.run_id = run_id;
+        self
+    }
+
+    /// Sets the `fee_policy` field.
+    /// The fees of the transaction are estimated following this policy.
+    pub fn fee_policy(mut self, fee_policy: FeePolicy) -> Self {
+        self.fee_policy = fee_policy;
+        self
+    }
+
+    /// Sets the `journal` field.
+    /// The fees chosen for the transaction are logged to this journal.
+    pub fn journal(mut self, journal: &'a mut JournalCollection) -> Self {
+        self.journal = Some(journal);
+        self
+    }
+
     /// Builds the TransactionBuilder into a Transaction and sends it.
     /// Makes async calls to estimate the gas limit, priority fee per gas unit, and fee per gas.
     /// Handles the signing internally.
     pub async fn send(self, rpc_canister: &Service) -> ManagerResult<SendRawTransactionStatus> {
-        let chain_id = CHAIN_ID;
+        let chain_id = rpc_canister.1;
         let input = Bytes::from(self.data.clone());
-        let rpc: RpcServices = get_ranked_rpc_providers();
         let block_tag = get_block_tag(rpc_canister, true).await?;
+        let fees =
+            estimate_transaction_fees(9, rpc_canister, block_tag.clone(), &self.fee_policy).await?;
+        if let Some(journal) = self.journal {
+            log_fees(journal, &self.fee_policy, &fees, "the transaction");
+        }
         let FeeEstimates {
             max_fee_per_gas,
             max_priority_fee_per_gas,
-        } = estimate_transaction_fees(9, rpc_canister, block_tag.clone()).await?;
+        } = fees;
 
         let estimated_gas =
             super::gas::get_estimate_gas(rpc_canister, self.data, self.to.clone(), self.from)
                 .await?;
 
-        let key_id = EcdsaKeyId {
-            curve: EcdsaCurve::Secp256k1,
-            name: String::from("key_1"),
-        };
-
         let request = TxEip1559 {
             chain_id,
             to: TxKind::Call(
@@ -114,26 +190,280 @@ impl TransactionBuilder {
         };
 
         let signed_transaction =
-            sign_eip1559_transaction(request, key_id, self.derivation_path).await?;
+            sign_eip1559_transaction(request, ecdsa_key_id(), self.derivation_path).await?;
 
-        match rpc_canister
-            .eth_send_raw_transaction(rpc.clone(), None, signed_transaction, self.cycles)
-            .await
-        {
-            Ok((response,)) => {
-                let extracted_response =
-                    extract_multi_rpc_send_raw_transaction_status(rpc, response)?;
-                Ok(extracted_response)
+        submit_transaction(
+            rpc_canister,
+            signed_transaction,
+            self.cycles,
+            self.strategy_key,
+            self.run_id,
+            self.nonce,
+            block_number(&block_tag),
+        )
+        .await
+    }
+}
+
+/// Journals the fees chosen for a submission.
+fn log_fees(journal: &mut JournalCollection, policy: &FeePolicy, fees: &FeeEstimates, what: &str) {
+    journal.append_note(
+        Ok(()),
+        LogType::Info,
+        format!(
+            "Fees of {}: max fee per gas {} wei, max priority fee per gas {} wei (percentile {}, multiplier {}%, priority fee floor {} wei, max fee cap {}).",
+            what,
+            fees.max_fee_per_gas,
+            fees.max_priority_fee_per_gas,
+            policy.percentile,
+            policy.multiplier_percent,
+            policy.min_priority_fee,
+            policy
+                .max_fee_cap
+                .map_or("none".to_string(), |cap| format!("{} wei", cap))
+        ),
+    );
+}
+
+/// Returns the number of a block tag, if it designates a block by number.
+fn block_number(block_tag: &BlockTag) -> Option<u64> {
+    match block_tag {
+        BlockTag::Number(number) => u64::try_from(number.0.clone()).ok(),
+        _ => None,
+    }
+}
+
+/// Sends a signed transaction through the write providers, or the providers of the chain on
+/// other chains than the home chain.
+///
+/// Accepted transactions of a strategy are recorded in the transaction pool.
+async fn submit_transaction(
+    rpc_canister: &Service,
+    signed_transaction: String,
+    cycles: u128,
+    strategy_key: Option<u32>,
+    run_id: Option<u64>,
+    nonce: u64,
+    submitted_block: Option<u64>,
+) -> ManagerResult<SendRawTransactionStatus> {
+    let rpc: RpcServices = match chain_services(rpc_canister.1)? {
+        Some(services) => services,
+        None => get_ranked_rpc_providers(ProviderPool::Write),
+    };
+    match rpc_canister
+        .eth_send_raw_transaction(rpc.clone(), None, signed_transaction.clone(), cycles)
+        .await
+    {
+        Ok((response,)) => {
+            let extracted_response = extract_multi_rpc_send_raw_transaction_status(rpc, response);
+            track_consensus(CallClass::SendRawTransaction, &extracted_response);
+            let extracted_response = extracted_response?;
+            if let (SendRawTransactionStatus::Ok(_), Some(strategy_key)) =
+                (&extracted_response, strategy_key)
+            {
+                let hash = transaction_hash(&signed_transaction)?;
+                record_transaction(
+                    strategy_key,
+                    run_id,
+                    nonce,
+                    hash,
+                    signed_transaction,
+                    rpc_canister.0,
+                    submitted_block,
+                );
             }
-            Err(e) => Err(ManagerError::Custom(e.1)),
+            Ok(extracted_response)
+        }
+        Err(e) => Err(ManagerError::Custom(e.1)),
+    }
+}
+
+/// Returns `fee` raised by `percent` percent, rounded up and by at least one wei.
+pub fn bump_fee(fee: u128, percent: u64) -> u128 {
+    let bump = fee.saturating_mul(u128::from(percent)).div_ceil(100);
+    fee.saturating_add(bump.max(1))
+}
+
+/// Builds the replacement of a pending transaction.
+///
+/// Each fee is the larger of the bumped fee of `pending` and the `current` estimate, so that
+/// the mempools accept the replacement and it is priced for the current market.
+pub fn replacement_transaction(
+    pending: &TxEip1559,
+    replacement: Replacement,
+    eoa: Address,
+    current: &FeeEstimates,
+    bump_percent: u64,
+) -> TxEip1559 {
+    let max_priority_fee_per_gas = bump_fee(pending.max_priority_fee_per_gas, bump_percent)
+        .max(current.max_priority_fee_per_gas);
+    let max_fee_per_gas = bump_fee(pending.max_fee_per_gas, bump_percent)
+        .max(current.max_fee_per_gas)
+        .max(max_priority_fee_per_gas);
+
+    let mut request = TxEip1559 {
+        max_fee_per_gas,
+        max_priority_fee_per_gas,
+        ..pending.clone()
+    };
+    if replacement == Replacement::Cancel {
+        request.to = TxKind::Call(eoa);
+        request.value = U256::ZERO;
+        request.input = Bytes::new();
+        request.gas_limit = CANCEL_TX_GAS_LIMIT;
+        request.access_list = Default::default();
+    }
+    request
+}
+
+/// Decodes the EIP-1559 transaction of a hex-encoded signed transaction.
+pub fn decode_eip1559_transaction(raw: &str) -> ManagerResult<TxEip1559> {
+    let stripped = raw.strip_prefix("0x").unwrap_or(raw);
+    let bytes =
+        hex::decode(stripped).map_err(|err| ManagerError::DecodingError(format!("{:#?}", err)))?;
+    match TxEnvelope::decode_2718(&mut bytes.as_slice()) {
+        Ok(TxEnvelope::Eip1559(signed)) => Ok(signed.tx().clone()),
+        Ok(_) => Err(ManagerError::DecodingError(
+            "The transaction is not an EIP-1559 transaction.".to_string(),
+        )),
+        Err(err) => Err(ManagerError::DecodingError(format!("{:#?}", err))),
+    }
+}
+
+/// Sends a replacement of a pending transaction with the same nonce and bumped fees.
+///
+/// Cancellations are recorded without the run ID of the replaced transaction, as they do
+/// not adjust any rate.
+///
+/// # Arguments
+/// * `rpc_canister` - EVM RPC canister to send the replacement through
+/// * `record` - Pending transaction to replace
+/// * `params` - Kind of replacement, and the account and fee policy it is sent with
+/// * `journal` - Journal the fees of the replacement are logged to
+pub async fn replace_transaction(
+    rpc_canister: &Service,
+    record: &TxRecord,
+    params: ReplacementParams<'_>,
+    journal: &mut JournalCollection,
+) -> ManagerResult<SendRawTransactionStatus> {
+    let ReplacementParams {
+        replacement,
+        eoa,
+        derivation_path,
+        cycles,
+        fee_policy,
+    } = params;
+    let pending = decode_eip1559_transaction(&record.raw)?;
+    let block_tag = get_block_tag(rpc_canister, true).await?;
+    let current = estimate_transaction_fees(9, rpc_canister, block_tag.clone(), fee_policy).await?;
+    let bump_percent = (flag_int!(TX_FEE_BUMP_PERCENT, DEFAULT_TX_FEE_BUMP_PERCENT).max(0) as u64)
+        .max(MIN_TX_FEE_BUMP_PERCENT);
+
+    let request = replacement_transaction(&pending, replacement, eoa, &current, bump_percent);
+    if let Some(cap) = fee_policy.max_fee_cap {
+        if request.max_fee_per_gas > u128::from(cap) {
+            return Err(ManagerError::Custom(format!(
+                "The replacement of nonce {} would bid {} wei per gas, above the max fee cap of {} wei.",
+                record.nonce, request.max_fee_per_gas, cap
+            )));
         }
     }
+    log_fees(
+        journal,
+        fee_policy,
+        &FeeEstimates {
+            max_fee_per_gas: request.max_fee_per_gas,
+            max_priority_fee_per_gas: request.max_priority_fee_per_gas,
+        },
+        &format!("the replacement of nonce {}", record.nonce),
+    );
+    let signed_transaction =
+        sign_eip1559_transaction(request, ecdsa_key_id(), derivation_path).await?;
+
+    let run_id = match replacement {
+        Replacement::SpeedUp => record.run_id,
+        Replacement::Cancel => None,
+    };
+    submit_transaction(
+        rpc_canister,
+        signed_transaction,
+        cycles,
+        Some(record.strategy),
+        run_id,
+        record.nonce,
+        block_number(&block_tag),
+    )
+    .await
+}
+
+/// Speeds up the transactions of a strategy that are still pending `stuck_tx_blocks` blocks
+/// after their submission.
+///
+/// Returns each stuck transaction with the outcome of its replacement. Nonces that were
+/// already replaced `MAX_TX_REPLACEMENTS` times are left to `cancel_pending_tx`.
+pub async fn replace_stuck_transactions(
+    strategy: u32,
+    rpc_canister: &Service,
+    eoa: Address,
+    derivation_path: DerivationPath,
+    cycles: u128,
+    fee_policy: &FeePolicy,
+    journal: &mut JournalCollection,
+) -> ManagerResult<Vec<(TxRecord, ManagerResult<SendRawTransactionStatus>)>> {
+    let min_blocks = flag_int!(STUCK_TX_BLOCKS, DEFAULT_STUCK_TX_BLOCKS);
+    if min_blocks <= 0 {
+        return Ok(vec![]);
+    }
+
+    let block_tag = get_block_tag(rpc_canister, true).await?;
+    let Some(current_block) = block_number(&block_tag) else {
+        return Ok(vec![]);
+    };
+
+    let mut outcomes = vec![];
+    for record in stuck_transactions(strategy, current_block, min_blocks as u64) {
+        let replacements = transactions_for_nonce(strategy, record.nonce)
+            .len()
+            .saturating_sub(1);
+        let outcome = if replacements >= MAX_TX_REPLACEMENTS {
+            Err(ManagerError::Custom(format!(
+                "The nonce was already replaced {} times. Cancel the transaction with cancel_pending_tx.",
+                replacements
+            )))
+        } else {
+            replace_transaction(
+                rpc_canister,
+                &record,
+                ReplacementParams {
+                    replacement: Replacement::SpeedUp,
+                    eoa,
+                    derivation_path: derivation_path.clone(),
+                    cycles,
+                    fee_policy,
+                },
+                journal,
+            )
+            .await
+        };
+        outcomes.push((record, outcome));
+    }
+    Ok(outcomes)
+}
+
+/// Computes the hash of a hex-encoded signed transaction.
+fn transaction_hash(signed_transaction: &str) -> ManagerResult<String> {
+    let stripped = signed_transaction
+        .strip_prefix("0x")
+        .unwrap_or(signed_transaction);
+    let bytes =
+        hex::decode(stripped).map_err(|err| ManagerError::DecodingError(format!("{:#?}", err)))?;
+    Ok(keccak256(bytes).to_string())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::types::DerivationPath;
+    use crate::{constants::CHAIN_ID, types::DerivationPath};
     use alloy_primitives::U256;
 
     #[test]
@@ -146,6 +476,8 @@ mod tests {
         assert_eq!(builder.nonce, 0);
         assert_eq!(builder.derivation_path, DerivationPath::default());
         assert_eq!(builder.cycles, 0);
+        assert_eq!(builder.strategy_key, None);
+        assert_eq!(builder.run_id, None);
     }
 
     #[test]
@@ -197,6 +529,89 @@ mod tests {
         assert_eq!(builder.cycles, cycles);
     }
 
+    #[test]
+    fn test_set_strategy_key() {
+        let builder = TransactionBuilder::default().strategy_key(7);
+        assert_eq!(builder.strategy_key, Some(7));
+    }
+
+    #[test]
+    fn test_set_run_id() {
+        let builder = TransactionBuilder::default().run_id(Some(3));
+        assert_eq!(builder.run_id, Some(3));
+    }
+
+    #[test]
+    fn test_set_fee_policy() {
+        let policy = FeePolicy {
+            percentile: 50,
+            ..FeePolicy::default()
+        };
+        let builder = TransactionBuilder::default().fee_policy(policy);
+        assert_eq!(builder.fee_policy, policy);
+        assert!(builder.journal.is_none());
+    }
+
+    #[test]
+    fn test_transaction_hash() {
+        // keccak256 of an empty byte string
+        assert_eq!(
+            transaction_hash("0x").unwrap(),
+            "0xc5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470"
+        );
+    }
+
+    #[test]
+    fn test_bump_fee() {
+        assert_eq!(bump_fee(1_000_000_000, 20), 1_200_000_000);
+        // Rounded up, and by at least one wei
+        assert_eq!(bump_fee(15, 10), 17);
+        assert_eq!(bump_fee(0, 10), 1);
+        assert_eq!(bump_fee(u128::MAX, 10), u128::MAX);
+    }
+
+    #[test]
+    fn test_replacement_transaction() {
+        let eoa = Address::repeat_byte(1);
+        let pending = TxEip1559 {
+            chain_id: CHAIN_ID,
+            nonce: 4,
+            gas_limit: 300_000,
+            max_fee_per_gas: 10_000_000_000,
+            max_priority_fee_per_gas: 1_000_000_000,
+            to: TxKind::Call(Address::repeat_byte(2)),
+            value: U256::ZERO,
+            access_list: Default::default(),
+            input: Bytes::from(vec![0xde, 0xad]),
+        };
+        let current = FeeEstimates {
+            max_fee_per_gas: 13_000_000_000,
+            max_priority_fee_per_gas: 1_000_000_000,
+        };
+
+        let speed_up = replacement_transaction(&pending, Replacement::SpeedUp, eoa, &current, 20);
+        assert_eq!(speed_up.nonce, 4);
+        assert_eq!(speed_up.to, pending.to);
+        assert_eq!(speed_up.input, pending.input);
+        assert_eq!(speed_up.gas_limit, 300_000);
+        // The estimate wins over the bump when the market moved further
+        assert_eq!(speed_up.max_fee_per_gas, 13_000_000_000);
+        assert_eq!(speed_up.max_priority_fee_per_gas, 1_200_000_000);
+
+        let cancel = replacement_transaction(&pending, Replacement::Cancel, eoa, &current, 20);
+        assert_eq!(cancel.nonce, 4);
+        assert_eq!(cancel.to, TxKind::Call(eoa));
+        assert!(cancel.input.is_empty());
+        assert_eq!(cancel.gas_limit, CANCEL_TX_GAS_LIMIT);
+        assert_eq!(cancel.max_priority_fee_per_gas, 1_200_000_000);
+    }
+
+    #[test]
+    fn test_decode_invalid_transaction() {
+        assert!(decode_eip1559_transaction("0xzz").is_err());
+        assert!(decode_eip1559_transaction("0x").is_err());
+    }
+
     #[test]
     fn test_chained_setters() {
         let to_address = "0x0123456789abcdef0123456789abcdef01234567".to_string();
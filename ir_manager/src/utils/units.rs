@@ -0,0 +1,233 @@
+//! Typed Units
+//!
+//! Amounts, rates, and cycles all travel as `U256`, `Nat`, or plain integers, which makes it
+//! easy to mix up their scales. The wrappers below name the unit of a value, and convert and
+//! format it in one place:
+//!
+//! ```plain
+//!  Bps(25) ──to_rate──► Rate(0.0025e18) ──Display──► "0.25%"
+//!
+//!  Nat ──Wei::from_nat (checked, <= 256 bits)──► Wei ──to_nat──► Nat
+//!  Nat ──Cycles::from_nat (checked, <= 128 bits)──► Cycles
+//! ```
+//!
+//! Rates and percentages use the fixed-point scale of the protocol: `Rate(1e18)` is 100%.
+
+use std::fmt;
+
+use alloy_primitives::U256;
+use candid::Nat;
+use num_bigint::BigUint;
+
+use super::error::{ManagerError, ManagerResult};
+
+/// Fixed-point scale of amounts and rates (1e18)
+pub const WAD: u128 = 1_000_000_000_000_000_000;
+
+/// One basis point in the fixed-point scale (1e14)
+const BPS_SCALE: u128 = WAD / 10_000;
+
+/// Converts values of type `Nat` to `U256`
+pub fn nat_to_u256(n: &Nat) -> ManagerResult<U256> {
+    let be_bytes = n.0.to_bytes_be();
+    if be_bytes.len() > 32 {
+        return Err(ManagerError::DecodingError("The `Nat` input length exceedes 32 bytes when converted to big-endian bytes representation.".to_string()));
+    }
+    // Ensure the byte array is exactly 32 bytes long
+    let mut padded_bytes = [0u8; 32];
+    let start_pos = 32 - be_bytes.len();
+    padded_bytes[start_pos..].copy_from_slice(&be_bytes);
+
+    Ok(U256::from_be_bytes(padded_bytes))
+}
+
+/// Converts values of type `U256` to `Nat`
+pub fn u256_to_nat(n: &U256) -> ManagerResult<Nat> {
+    let be_bytes = n.to_be_bytes::<32>();
+    let biguint = BigUint::from_bytes_be(&be_bytes);
+    Ok(Nat::from(biguint))
+}
+
+/// Converts a Nat to u128
+pub fn nat_to_u128(num: Nat) -> ManagerResult<u128> {
+    u128::try_from(num.0).map_err(|err| {
+        ManagerError::DecodingError(format!("Error converting Nat to u128: {:#?}", err))
+    })
+}
+
+/// Formats `value / 10^decimals` with at most `precision` fractional digits, without trailing zeros.
+fn format_fixed(value: U256, decimals: u32, precision: u32) -> String {
+    let divisor = U256::from(10_u8).pow(U256::from(decimals));
+    let integer = value / divisor;
+    let fraction = (value % divisor) / U256::from(10_u8).pow(U256::from(decimals - precision));
+    let fraction = format!(
+        "{:0>width$}",
+        fraction.to_string(),
+        width = precision as usize
+    );
+    let fraction = fraction.trim_end_matches('0');
+    if fraction.is_empty() {
+        integer.to_string()
+    } else {
+        format!("{}.{}", integer, fraction)
+    }
+}
+
+/// An amount of ETH or BOLD in its smallest unit (1e-18)
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Wei(pub U256);
+
+impl Wei {
+    /// Converts a Candid amount, failing above 256 bits.
+    pub fn from_nat(value: &Nat) -> ManagerResult<Self> {
+        nat_to_u256(value).map(Self)
+    }
+
+    /// Converts the amount to a Candid amount.
+    pub fn to_nat(self) -> Nat {
+        Nat::from(BigUint::from_bytes_be(&self.0.to_be_bytes::<32>()))
+    }
+}
+
+impl fmt::Display for Wei {
+    /// Formats the amount in whole units with up to 6 decimals, e.g. `1.5`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", format_fixed(self.0, 18, 6))
+    }
+}
+
+/// A rate or a fraction in the 1e18 fixed-point scale of the protocol (a wad), where
+/// `Rate(1e18)` is 100%
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Rate(pub U256);
+
+impl Rate {
+    /// Converts a Candid rate, failing above 256 bits.
+    pub fn from_nat(value: &Nat) -> ManagerResult<Self> {
+        nat_to_u256(value).map(Self)
+    }
+
+    /// Converts the rate to a Candid rate.
+    pub fn to_nat(self) -> Nat {
+        Wei(self.0).to_nat()
+    }
+
+    /// Returns the rate in whole basis points, rounded down.
+    pub fn to_bps(self) -> ManagerResult<Bps> {
+        u64::try_from(self.0 / U256::from(BPS_SCALE))
+            .map(Bps)
+            .map_err(|_| {
+                ManagerError::Arithmetic("The rate does not fit in basis points.".to_string())
+            })
+    }
+
+    /// Returns the rate as a fraction, e.g. `0.05` for 5%.
+    ///
+    /// Only meant for display and statistics, as the conversion is lossy.
+    pub fn to_f64(self) -> f64 {
+        u128::try_from(self.0).map_or(f64::INFINITY, |value| value as f64) / WAD as f64
+    }
+
+    /// Converts a fraction, e.g. `0.05` for 5%, failing on negative or non-finite values.
+    pub fn from_f64(fraction: f64) -> ManagerResult<Self> {
+        if !fraction.is_finite() || fraction < 0.0 {
+            return Err(ManagerError::Arithmetic(format!(
+                "{} is not a valid rate.",
+                fraction
+            )));
+        }
+        let scaled = (fraction * WAD as f64).round();
+        if scaled >= u128::MAX as f64 {
+            return Err(ManagerError::Arithmetic(format!(
+                "{} is not a valid rate.",
+                fraction
+            )));
+        }
+        Ok(Self(U256::from(scaled as u128)))
+    }
+}
+
+impl fmt::Display for Rate {
+    /// Formats the rate as a percentage with up to 4 decimals, e.g. `5.25%`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}%", format_fixed(self.0, 16, 4))
+    }
+}
+
+/// A rate in basis points (0.01%)
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Bps(pub u64);
+
+impl Bps {
+    /// Returns the rate in the fixed-point scale.
+    pub const fn to_wad(self) -> u128 {
+        self.0 as u128 * BPS_SCALE
+    }
+
+    /// Returns the rate as a typed `Rate`.
+    pub fn to_rate(self) -> Rate {
+        Rate(U256::from(self.to_wad()))
+    }
+}
+
+impl fmt::Display for Bps {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} bps", self.0)
+    }
+}
+
+/// An amount of cycles
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Cycles(pub u128);
+
+impl Cycles {
+    /// Converts a Candid amount, failing above 128 bits.
+    pub fn from_nat(value: Nat) -> ManagerResult<Self> {
+        nat_to_u128(value).map(Self)
+    }
+
+    /// Converts the amount to a Candid amount.
+    pub fn to_nat(self) -> Nat {
+        Nat::from(self.0)
+    }
+}
+
+impl fmt::Display for Cycles {
+    /// Formats the amount in trillions of cycles with up to 3 decimals, e.g. `1.5T`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}T", format_fixed(U256::from(self.0), 12, 3))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_conversions() {
+        assert_eq!(
+            Bps(25).to_rate(),
+            Rate(U256::from(2_500_000_000_000_000_u128))
+        );
+        assert_eq!(Bps(25).to_rate().to_bps(), Ok(Bps(25)));
+        assert_eq!(Rate::from_f64(0.5), Ok(Bps(5_000).to_rate()));
+        assert!(Rate::from_f64(-0.01).is_err());
+        assert!(Rate::from_f64(f64::NAN).is_err());
+        assert_eq!(Bps(500).to_rate().to_f64(), 0.05);
+
+        let amount = Wei(U256::from(WAD) * U256::from(3_u8));
+        assert_eq!(Wei::from_nat(&amount.to_nat()), Ok(amount));
+        assert!(Wei::from_nat(&Nat(BigUint::from(2_u8).pow(256))).is_err());
+        assert!(Cycles::from_nat(Nat(BigUint::from(2_u8).pow(128))).is_err());
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(Bps(525).to_rate().to_string(), "5.25%");
+        assert_eq!(Rate(U256::from(WAD)).to_string(), "100%");
+        assert_eq!(Bps(525).to_string(), "525 bps");
+        assert_eq!(Wei(U256::from(WAD + WAD / 2)).to_string(), "1.5");
+        assert_eq!(Cycles(30_000_000_000_000).to_string(), "30T");
+        assert_eq!(Cycles(1_234_567_000_000).to_string(), "1.234T");
+    }
+}
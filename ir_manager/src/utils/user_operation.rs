@@ -0,0 +1,368 @@
+//! ERC-4337 UserOperations
+//!
+//! Alternative execution backend of a strategy: instead of a raw transaction paid by the
+//! strategy EOA, the rate adjustment is wrapped into a UserOperation (EntryPoint v0.7) of a
+//! smart account owned by the EOA, sponsored by a paymaster and submitted to a bundler:
+//!
+//! ```plain
+//! setNewRate ──► execute(batch_manager, 0, setNewRate) ──► PackedUserOperation
+//!                      (smart account call)                     │ getNonce (EntryPoint)
+//!                                                               ▼
+//!                               sign(toEthSignedMessageHash(userOpHash)) with the EOA key
+//!                                                               │
+//!                                                               ▼
+//!                                   bundler: eth_sendUserOperation ──► userOpHash
+//! ```
+//!
+//! The EOA only signs, so it does not need to hold ETH. The gas limits are taken from the
+//! configuration rather than estimated, so that every replica builds the same operation. The
+//! bundler responses are reduced by `transform_bundler_response`, which also maps the
+//! duplicate submissions of the other replicas to the hash of the operation.
+
+use alloy::hex;
+use alloy_primitives::{keccak256, Address, Bytes, FixedBytes, U256};
+use alloy_sol_types::{SolCall, SolValue};
+use candid::{CandidType, Nat};
+use evm_rpc_types::BlockTag;
+use ic_exports::ic_cdk::api::management_canister::http_request::{
+    http_request, CanisterHttpRequestArgument, HttpHeader, HttpMethod, HttpResponse, TransformArgs,
+    TransformContext,
+};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::{
+    constants::{BUNDLER_CYCLES, DEFAULT_MAX_RESPONSE_BYTES},
+    types::{executeCall, getNonceCall, DerivationPath},
+    utils::{
+        address::parse_address,
+        common::{call_with_dynamic_retries, decode_abi_response, extract_call_result},
+        error::{ManagerError, ManagerResult},
+        evm_rpc::Service,
+        gas::FeeEstimates,
+        signer::{ecdsa_key_id, sign_prehash},
+    },
+};
+
+/// Bundler and paymaster of a strategy submitting its rate adjustments as UserOperations
+#[derive(Clone, Debug, PartialEq, CandidType, Deserialize)]
+pub struct UserOperationConfig {
+    /// HTTPS JSON-RPC endpoint of the bundler
+    pub bundler_url: String,
+    /// EntryPoint v0.7 contract
+    pub entry_point: String,
+    /// Smart account owned by the strategy EOA, which must be allowed to set the batch rate
+    pub smart_account: String,
+    /// Paymaster sponsoring the operations
+    pub paymaster: String,
+    /// Data passed to the paymaster
+    pub paymaster_data: Vec<u8>,
+    /// Gas limit of the `execute` call
+    pub call_gas_limit: u64,
+    /// Gas limit of the account validation
+    pub verification_gas_limit: u64,
+    /// Gas paid to the bundler for the calldata and the bundle overhead
+    pub pre_verification_gas: u64,
+    /// Gas limit of the paymaster validation
+    pub paymaster_verification_gas_limit: u64,
+    /// Gas limit of the paymaster post-operation call
+    pub paymaster_post_op_gas_limit: u64,
+}
+
+impl UserOperationConfig {
+    /// Checks that the endpoint uses HTTPS and that the addresses are valid.
+    pub fn validate(&self) -> ManagerResult<()> {
+        if !self.bundler_url.starts_with("https://") {
+            return Err(ManagerError::Configuration(
+                "The bundler URL must use HTTPS.".to_string(),
+            ));
+        }
+        parse_address("entry_point", &self.entry_point, false)?;
+        parse_address("smart_account", &self.smart_account, false)?;
+        parse_address("paymaster", &self.paymaster, false)?;
+        Ok(())
+    }
+}
+
+/// Packs two 128-bit values into a 32-byte word, `high` first.
+fn pack_u128s(high: u128, low: u128) -> U256 {
+    (U256::from(high) << 128) | U256::from(low)
+}
+
+/// A UserOperation of EntryPoint v0.7, without a factory
+#[derive(Clone, Debug, PartialEq)]
+struct UserOperation {
+    sender: Address,
+    nonce: U256,
+    call_data: Bytes,
+    call_gas_limit: u64,
+    verification_gas_limit: u64,
+    pre_verification_gas: u64,
+    max_fee_per_gas: u128,
+    max_priority_fee_per_gas: u128,
+    paymaster: Address,
+    paymaster_verification_gas_limit: u64,
+    paymaster_post_op_gas_limit: u64,
+    paymaster_data: Bytes,
+    signature: Bytes,
+}
+
+impl UserOperation {
+    /// Returns the `paymasterAndData` field of the packed operation.
+    fn paymaster_and_data(&self) -> Vec<u8> {
+        let mut packed = self.paymaster.to_vec();
+        packed.extend_from_slice(&u128::from(self.paymaster_verification_gas_limit).to_be_bytes());
+        packed.extend_from_slice(&u128::from(self.paymaster_post_op_gas_limit).to_be_bytes());
+        packed.extend_from_slice(&self.paymaster_data);
+        packed
+    }
+
+    /// Returns the hash the smart account verifies the signature against, as computed by
+    /// `EntryPoint.getUserOpHash`.
+    fn hash(&self, entry_point: Address, chain_id: u64) -> FixedBytes<32> {
+        let packed = (
+            self.sender,
+            self.nonce,
+            keccak256(Bytes::new()),
+            keccak256(&self.call_data),
+            pack_u128s(
+                u128::from(self.verification_gas_limit),
+                u128::from(self.call_gas_limit),
+            ),
+            U256::from(self.pre_verification_gas),
+            pack_u128s(self.max_priority_fee_per_gas, self.max_fee_per_gas),
+            keccak256(self.paymaster_and_data()),
+        )
+            .abi_encode_params();
+        keccak256((keccak256(packed), entry_point, U256::from(chain_id)).abi_encode_params())
+    }
+
+    /// Returns the operation in the JSON-RPC format of the bundlers.
+    fn to_json(&self) -> Value {
+        let quantity = |value: u128| format!("0x{:x}", value);
+        json!({
+            "sender": self.sender.to_string(),
+            "nonce": format!("0x{:x}", self.nonce),
+            "callData": format!("0x{}", hex::encode(&self.call_data)),
+            "callGasLimit": quantity(self.call_gas_limit.into()),
+            "verificationGasLimit": quantity(self.verification_gas_limit.into()),
+            "preVerificationGas": quantity(self.pre_verification_gas.into()),
+            "maxFeePerGas": quantity(self.max_fee_per_gas),
+            "maxPriorityFeePerGas": quantity(self.max_priority_fee_per_gas),
+            "paymaster": self.paymaster.to_string(),
+            "paymasterVerificationGasLimit": quantity(self.paymaster_verification_gas_limit.into()),
+            "paymasterPostOpGasLimit": quantity(self.paymaster_post_op_gas_limit.into()),
+            "paymasterData": format!("0x{}", hex::encode(&self.paymaster_data)),
+            "signature": format!("0x{}", hex::encode(&self.signature)),
+        })
+    }
+}
+
+/// Returns the EIP-191 hash of a 32-byte message, as `toEthSignedMessageHash` computes it.
+fn eth_signed_message_hash(message: FixedBytes<32>) -> FixedBytes<32> {
+    let mut prefixed = b"\x19Ethereum Signed Message:\n32".to_vec();
+    prefixed.extend_from_slice(message.as_slice());
+    keccak256(prefixed)
+}
+
+/// Wraps `calldata` to `target` into a UserOperation of the configured smart account, signs
+/// it with the key at `derivation_path`, and submits it to the bundler.
+///
+/// Returns the hash of the operation.
+pub async fn send_user_operation(
+    config: &UserOperationConfig,
+    rpc_canister: &Service,
+    block_tag: BlockTag,
+    target: Address,
+    calldata: Vec<u8>,
+    fees: FeeEstimates,
+    derivation_path: DerivationPath,
+) -> ManagerResult<String> {
+    let entry_point = parse_address("entry_point", &config.entry_point, false)?;
+    let sender = parse_address("smart_account", &config.smart_account, false)?;
+
+    let nonce_call = getNonceCall {
+        sender,
+        key: Default::default(),
+    };
+    let response = call_with_dynamic_retries(
+        rpc_canister,
+        block_tag,
+        entry_point,
+        nonce_call.abi_encode(),
+    )
+    .await?;
+    let nonce = decode_abi_response::<_, getNonceCall>(response)?.nonce;
+
+    let call_data = executeCall {
+        dest: target,
+        value: U256::ZERO,
+        func: Bytes::from(calldata),
+    }
+    .abi_encode();
+
+    let mut operation = UserOperation {
+        sender,
+        nonce,
+        call_data: Bytes::from(call_data),
+        call_gas_limit: config.call_gas_limit,
+        verification_gas_limit: config.verification_gas_limit,
+        pre_verification_gas: config.pre_verification_gas,
+        max_fee_per_gas: fees.max_fee_per_gas,
+        max_priority_fee_per_gas: fees.max_priority_fee_per_gas,
+        paymaster: parse_address("paymaster", &config.paymaster, false)?,
+        paymaster_verification_gas_limit: config.paymaster_verification_gas_limit,
+        paymaster_post_op_gas_limit: config.paymaster_post_op_gas_limit,
+        paymaster_data: Bytes::from(config.paymaster_data.clone()),
+        signature: Bytes::new(),
+    };
+    let hash = operation.hash(entry_point, rpc_canister.1);
+    operation.signature = Bytes::from(
+        sign_prehash(
+            eth_signed_message_hash(hash),
+            ecdsa_key_id(),
+            derivation_path,
+        )
+        .await?,
+    );
+
+    let body = json!({
+        "id": 1,
+        "jsonrpc": "2.0",
+        "method": "eth_sendUserOperation",
+        "params": [operation.to_json(), entry_point.to_string()],
+    });
+    let request = CanisterHttpRequestArgument {
+        url: config.bundler_url.clone(),
+        max_response_bytes: Some(DEFAULT_MAX_RESPONSE_BYTES),
+        method: HttpMethod::POST,
+        headers: vec![HttpHeader {
+            name: "Content-Type".to_string(),
+            value: "application/json".to_string(),
+        }],
+        body: Some(body.to_string().into_bytes()),
+        transform: Some(TransformContext::from_name(
+            "transform_bundler_response".to_string(),
+            hash.to_vec(),
+        )),
+    };
+
+    let response = extract_call_result(http_request(request, BUNDLER_CYCLES).await)?;
+    let body = String::from_utf8_lossy(&response.body).to_string();
+    if response.status >= Nat::from(300_u16) {
+        return Err(ManagerError::Custom(format!(
+            "The bundler responded with status {}: {}",
+            response.status, body
+        )));
+    }
+    body.strip_prefix("ok:").map(str::to_string).ok_or_else(|| {
+        ManagerError::Custom(format!("The bundler rejected the UserOperation: {}", body))
+    })
+}
+
+/// Returns `true` if a bundler error reports that the operation was already submitted.
+fn is_duplicate(message: &str) -> bool {
+    let message = message.to_lowercase();
+    message.contains("already known") || message.contains("duplicate")
+}
+
+/// Reduces a bundler response to `ok:<userOpHash>` or `err:<message>`, so that all replicas
+/// agree on it. The hash of the operation is passed as the transform context.
+///
+/// Only the first replica's submission is new to the bundler, so a duplicate error is
+/// reported as a success.
+pub fn transform_bundler_response(args: TransformArgs) -> HttpResponse {
+    let hash = format!("0x{}", hex::encode(&args.context));
+    let response: Value = serde_json::from_slice(&args.response.body).unwrap_or(Value::Null);
+    let body = match (
+        response.get("result").and_then(Value::as_str),
+        response.pointer("/error/message").and_then(Value::as_str),
+    ) {
+        (Some(_), _) => format!("ok:{}", hash),
+        (None, Some(message)) if is_duplicate(message) => format!("ok:{}", hash),
+        (None, Some(message)) => format!("err:{}", message),
+        (None, None) => "err:malformed response".to_string(),
+    };
+    HttpResponse {
+        status: args.response.status,
+        headers: vec![],
+        body: body.into_bytes(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn operation() -> UserOperation {
+        UserOperation {
+            sender: Address::repeat_byte(1),
+            nonce: U256::from(3),
+            call_data: Bytes::from(vec![0xab; 4]),
+            call_gas_limit: 200_000,
+            verification_gas_limit: 100_000,
+            pre_verification_gas: 50_000,
+            max_fee_per_gas: 30_000_000_000,
+            max_priority_fee_per_gas: 1_000_000_000,
+            paymaster: Address::repeat_byte(2),
+            paymaster_verification_gas_limit: 60_000,
+            paymaster_post_op_gas_limit: 10_000,
+            paymaster_data: Bytes::from(vec![0xcd]),
+            signature: Bytes::new(),
+        }
+    }
+
+    #[test]
+    fn test_user_operation_hash() {
+        let operation = operation();
+        let packed = operation.paymaster_and_data();
+        assert_eq!(packed.len(), 20 + 16 + 16 + 1);
+        assert_eq!(&packed[..20], Address::repeat_byte(2).as_slice());
+        assert_eq!(&packed[20..36], 60_000_u128.to_be_bytes().as_slice());
+
+        let entry_point = Address::repeat_byte(3);
+        let hash = operation.hash(entry_point, 1);
+        // The signature is not part of the hash, the chain is
+        let signed = UserOperation {
+            signature: Bytes::from(vec![1; 65]),
+            ..operation.clone()
+        };
+        assert_eq!(signed.hash(entry_point, 1), hash);
+        assert_ne!(operation.hash(entry_point, 10), hash);
+
+        let json = operation.to_json();
+        assert_eq!(json["nonce"], "0x3");
+        assert_eq!(json["callGasLimit"], "0x30d40");
+        assert_eq!(json["paymasterData"], "0xcd");
+    }
+
+    #[test]
+    fn test_transform_bundler_response() {
+        let transform = |body: &str| {
+            let response = transform_bundler_response(TransformArgs {
+                response: HttpResponse {
+                    status: Nat::from(200_u16),
+                    headers: vec![HttpHeader {
+                        name: "date".to_string(),
+                        value: "now".to_string(),
+                    }],
+                    body: body.as_bytes().to_vec(),
+                },
+                context: vec![0x12, 0x34],
+            });
+            assert!(response.headers.is_empty());
+            String::from_utf8(response.body).unwrap()
+        };
+
+        assert_eq!(transform(r#"{"result":"0x1234"}"#), "ok:0x1234");
+        assert_eq!(
+            transform(r#"{"error":{"code":-32602,"message":"Already known"}}"#),
+            "ok:0x1234"
+        );
+        assert_eq!(
+            transform(r#"{"error":{"code":-32500,"message":"AA21 didn't pay prefund"}}"#),
+            "err:AA21 didn't pay prefund"
+        );
+        assert_eq!(transform("<html>"), "err:malformed response");
+    }
+}